@@ -0,0 +1,50 @@
+use super::Abbreviation;
+use std::collections::HashMap;
+
+/// Expands a fixed set of abbreviations wherever they are typed
+///
+/// # Example
+/// ```rust
+/// use reedline::{Abbreviation, DefaultAbbreviations};
+///
+/// let abbreviations = DefaultAbbreviations::new()
+///     .with_abbreviation("gco", "git checkout")
+///     .with_abbreviation("gst", "git status");
+///
+/// assert_eq!(abbreviations.expand("gco", "gco", 0), Some("git checkout".into()));
+/// ```
+#[derive(Default)]
+pub struct DefaultAbbreviations {
+    abbreviations: HashMap<String, String>,
+}
+
+impl DefaultAbbreviations {
+    /// Create an empty set of abbreviations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A builder that adds an abbreviation expanding to `expansion`
+    #[must_use]
+    pub fn with_abbreviation(mut self, abbreviation: &str, expansion: &str) -> Self {
+        self.insert(abbreviation, expansion);
+        self
+    }
+
+    /// Add an abbreviation, replacing the previous expansion of it
+    pub fn insert(&mut self, abbreviation: &str, expansion: &str) {
+        self.abbreviations
+            .insert(abbreviation.to_string(), expansion.to_string());
+    }
+
+    /// Remove an abbreviation
+    pub fn remove(&mut self, abbreviation: &str) {
+        self.abbreviations.remove(abbreviation);
+    }
+}
+
+impl Abbreviation for DefaultAbbreviations {
+    fn expand(&self, word: &str, _line: &str, _pos: usize) -> Option<String> {
+        self.abbreviations.get(word).cloned()
+    }
+}
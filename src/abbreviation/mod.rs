@@ -0,0 +1,18 @@
+mod default;
+pub use default::DefaultAbbreviations;
+
+/// A trait that's responsible for expanding abbreviations, e.g. `gco` to `git checkout`
+///
+/// The word left of the insertion point is expanded when a space is typed, when
+/// the line is submitted or when [`EditCommand::ExpandAbbreviation`](crate::EditCommand::ExpandAbbreviation)
+/// is run.
+pub trait Abbreviation: Send {
+    /// Return the expansion of `word`, which starts at byte offset `pos` of `line`,
+    /// or `None` if it is not an abbreviation
+    fn expand(&self, word: &str, line: &str, pos: usize) -> Option<String>;
+}
+
+/// Hook called with the abbreviation and its expansion before it is expanded
+///
+/// Returns the text replacing the abbreviation, `None` vetoes the expansion.
+pub type AbbreviationHook = Box<dyn FnMut(&str, String) -> Option<String> + Send>;
@@ -0,0 +1,315 @@
+use crate::{Completer, Suggestion};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+/// The line completions are computed for by an [`AsyncCompleter`]
+pub struct CompletionRequest {
+    line: String,
+    pos: usize,
+    suggestions: Arc<Mutex<Vec<Suggestion>>>,
+    cancelled: Arc<AtomicBool>,
+    done: Arc<AtomicBool>,
+}
+
+impl CompletionRequest {
+    /// The line to complete
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// The position of the cursor in the line
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Adds suggestions to the ones shown by the menu, which shows them right
+    /// away instead of waiting for all of them
+    pub fn push(&self, suggestions: impl IntoIterator<Item = Suggestion>) {
+        if let Ok(mut pushed) = self.suggestions.lock() {
+            pushed.extend(suggestions);
+        }
+    }
+
+    /// Whether the line changed since, so the suggestions won't be shown
+    /// anymore and computing them can stop early
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// The completions of a line being computed
+struct PendingCompletions {
+    line: String,
+    pos: usize,
+    suggestions: Arc<Mutex<Vec<Suggestion>>>,
+    cancelled: Arc<AtomicBool>,
+    done: Arc<AtomicBool>,
+}
+
+/// A completer computing its suggestions on a background thread, for
+/// completions that are slow to compute like the ones of a language server
+///
+/// Keystrokes don't wait for the suggestions: the menu shows that they are
+/// loading and adds them as they arrive. Changing the line cancels the request.
+/// The requests are computed one after the other by a single worker thread.
+///
+/// # Example
+/// ```rust
+/// use reedline::{AsyncCompleter, Reedline, Span, Suggestion};
+///
+/// let completer = AsyncCompleter::new(|request| {
+///     for value in ["status", "stash"] {
+///         if request.is_cancelled() {
+///             return;
+///         }
+///         request.push([Suggestion {
+///             value: value.to_string(),
+///             description: None,
+///             extra: None,
+///             span: Span::new(0, request.pos()),
+///             append_whitespace: true,
+///             kind: None,
+///             icon: None,
+///             priority: 0,
+///         }]);
+///     }
+/// });
+/// let mut line_editor = Reedline::create().with_completer(Box::new(completer));
+/// ```
+pub struct AsyncCompleter {
+    compute: Arc<dyn Fn(&CompletionRequest) + Send + Sync>,
+    worker: Option<Sender<CompletionRequest>>,
+    pending: Option<PendingCompletions>,
+}
+
+impl AsyncCompleter {
+    /// A completer computing the suggestions of a line with `compute`, which
+    /// pushes them to the request
+    pub fn new<F>(compute: F) -> Self
+    where
+        F: Fn(&CompletionRequest) + Send + Sync + 'static,
+    {
+        Self {
+            compute: Arc::new(compute),
+            worker: None,
+            pending: None,
+        }
+    }
+
+    /// Starts computing the suggestions of `line`, cancelling the ones computed
+    /// before
+    fn request(&mut self, line: &str, pos: usize) {
+        self.cancel();
+        let request = CompletionRequest {
+            line: line.to_string(),
+            pos,
+            suggestions: Arc::new(Mutex::new(vec![])),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            done: Arc::new(AtomicBool::new(false)),
+        };
+        let pending = PendingCompletions {
+            line: request.line.clone(),
+            pos,
+            suggestions: Arc::clone(&request.suggestions),
+            cancelled: Arc::clone(&request.cancelled),
+            done: Arc::clone(&request.done),
+        };
+        if let Err(mpsc::SendError(request)) = self.worker().send(request) {
+            request.done.store(true, Ordering::Release);
+        }
+        self.pending = Some(pending);
+    }
+
+    /// The channel to the worker thread computing the requests, started with
+    /// the first one
+    fn worker(&mut self) -> &Sender<CompletionRequest> {
+        let compute = &self.compute;
+        self.worker.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel::<CompletionRequest>();
+            let compute = Arc::clone(compute);
+            thread::spawn(move || {
+                for request in receiver {
+                    // The requests replaced while queued are skipped. A panic
+                    // only ends the request, so that it is not pending forever
+                    if !request.is_cancelled() {
+                        let _ = panic::catch_unwind(AssertUnwindSafe(|| compute(&request)));
+                    }
+                    request.done.store(true, Ordering::Release);
+                }
+            });
+            sender
+        })
+    }
+
+    fn cancel(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            pending.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Reads the suggestions of `line` arrived so far, requesting them if the
+    /// line changed
+    fn arrived<T: Default>(
+        &mut self,
+        line: &str,
+        pos: usize,
+        read: impl FnOnce(&[Suggestion]) -> T,
+    ) -> T {
+        let requested =
+            matches!(&self.pending, Some(pending) if pending.line == line && pending.pos == pos);
+        if !requested {
+            self.request(line, pos);
+        }
+        self.pending
+            .as_ref()
+            .and_then(|pending| pending.suggestions.lock().ok())
+            .map(|suggestions| read(&suggestions))
+            .unwrap_or_default()
+    }
+}
+
+impl Completer for AsyncCompleter {
+    /// The suggestions arrived so far
+    fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+        self.arrived(line, pos, <[Suggestion]>::to_vec)
+    }
+
+    fn partial_complete(
+        &mut self,
+        line: &str,
+        pos: usize,
+        start: usize,
+        offset: usize,
+    ) -> Vec<Suggestion> {
+        self.arrived(line, pos, |suggestions| {
+            suggestions
+                .iter()
+                .skip(start)
+                .take(offset)
+                .cloned()
+                .collect()
+        })
+    }
+
+    fn total_completions(&mut self, line: &str, pos: usize) -> usize {
+        self.arrived(line, pos, <[Suggestion]>::len)
+    }
+
+    fn has_pending_completions(&self) -> bool {
+        matches!(&self.pending, Some(pending) if !pending.done.load(Ordering::Acquire))
+    }
+}
+
+impl Drop for AsyncCompleter {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::mpsc;
+
+    fn suggestion(value: &str) -> Suggestion {
+        Suggestion {
+            value: value.to_string(),
+            ..Suggestion::default()
+        }
+    }
+
+    fn values(suggestions: Vec<Suggestion>) -> Vec<String> {
+        suggestions
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect()
+    }
+
+    #[test]
+    fn suggestions_stream_in() {
+        let (sender, receiver) = mpsc::channel::<&'static str>();
+        let receiver = Mutex::new(receiver);
+        let mut completer = AsyncCompleter::new(move |request| {
+            for value in receiver.lock().unwrap().iter() {
+                request.push([suggestion(value)]);
+            }
+        });
+
+        assert_eq!(
+            values(completer.complete("git st", 6)),
+            Vec::<String>::new()
+        );
+        assert!(completer.has_pending_completions());
+
+        sender.send("stash").unwrap();
+        while completer.complete("git st", 6).is_empty() {
+            thread::yield_now();
+        }
+        assert_eq!(values(completer.complete("git st", 6)), vec!["stash"]);
+        assert!(completer.has_pending_completions());
+
+        sender.send("status").unwrap();
+        drop(sender);
+        while completer.has_pending_completions() {
+            thread::yield_now();
+        }
+        assert_eq!(
+            values(completer.complete("git st", 6)),
+            vec!["stash", "status"]
+        );
+    }
+
+    #[test]
+    fn changing_the_line_cancels_the_request() {
+        let (sender, receiver) = mpsc::channel::<String>();
+        let sender = Mutex::new(sender);
+        let mut completer = AsyncCompleter::new(move |request| {
+            sender
+                .lock()
+                .unwrap()
+                .send(request.line().to_string())
+                .unwrap();
+            while request.line() == "git st" && !request.is_cancelled() {
+                thread::yield_now();
+            }
+            request.push([suggestion("stash")]);
+        });
+
+        completer.complete("git st", 6);
+        assert_eq!(receiver.recv().unwrap(), "git st");
+        completer.complete("git sta", 7);
+        assert_eq!(receiver.recv().unwrap(), "git sta");
+        while completer.has_pending_completions() {
+            thread::yield_now();
+        }
+        assert_eq!(values(completer.complete("git sta", 7)), vec!["stash"]);
+    }
+
+    #[test]
+    fn panicking_request_is_not_pending() {
+        let mut completer = AsyncCompleter::new(|request| {
+            if request.line() == "panic" {
+                panic!("completion failed");
+            }
+            request.push([suggestion("stash")]);
+        });
+
+        completer.complete("panic", 5);
+        while completer.has_pending_completions() {
+            thread::yield_now();
+        }
+        completer.complete("git st", 6);
+        while completer.has_pending_completions() {
+            thread::yield_now();
+        }
+        assert_eq!(values(completer.complete("git st", 6)), vec!["stash"]);
+    }
+}
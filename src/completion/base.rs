@@ -35,6 +35,11 @@ pub trait Completer: Send {
     /// action that will return a partial section of available completions
     /// this command comes handy when trying to avoid to pull all the data at once
     /// from the completer
+    ///
+    /// The list menu pages through the completions with it, and the columnar
+    /// menu loads them in chunks with [`crate::ColumnarMenu::with_chunk_size`].
+    /// Completers suggesting huge numbers of values should implement it
+    /// without computing all of them.
     fn partial_complete(
         &mut self,
         line: &str,
@@ -53,6 +58,13 @@ pub trait Completer: Send {
     fn total_completions(&mut self, line: &str, pos: usize) -> usize {
         self.complete(line, pos).len()
     }
+
+    /// Whether the suggestions of the last line are still computed in the
+    /// background, like by an [`crate::AsyncCompleter`]. While they are, the
+    /// menu shows that they are loading and is updated as they arrive
+    fn has_pending_completions(&self) -> bool {
+        false
+    }
 }
 
 /// Suggestion returned by the Completer
@@ -70,4 +82,107 @@ pub struct Suggestion {
     /// Whether to append a space after selecting this suggestion.
     /// This helps to avoid that a completer repeats the complete suggestion.
     pub append_whitespace: bool,
+    /// Optional kind of the replacement, marked in the menus unless the
+    /// suggestion has an icon
+    pub kind: Option<SuggestionKind>,
+    /// Optional short marker painted in front of the value by the menus
+    pub icon: Option<String>,
+    /// Weight the columnar menu sorts the suggestions by, higher first.
+    /// Suggestions of equal weight keep the order of the completer, like in the
+    /// list menu, which pages through the suggestions as completed
+    pub priority: i32,
+}
+
+impl Suggestion {
+    /// Creates a suggestion replacing `span` with `value`, with the other fields
+    /// set to their defaults
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::{Span, Suggestion, SuggestionKind};
+    ///
+    /// let suggestion = Suggestion::new("status", Span::new(4, 7))
+    ///     .with_kind(SuggestionKind::Command)
+    ///     .with_priority(1);
+    /// ```
+    pub fn new(value: impl Into<String>, span: Span) -> Self {
+        Self {
+            value: value.into(),
+            span,
+            ..Self::default()
+        }
+    }
+
+    /// A builder that sets the kind of the suggestion
+    #[must_use]
+    pub fn with_kind(mut self, kind: SuggestionKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// A builder that sets the marker painted in front of the value
+    #[must_use]
+    pub fn with_icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    /// A builder that sets the weight the columnar menu sorts by
+    #[must_use]
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// The marker painted in front of the value: the icon, or else the marker
+    /// of the kind
+    pub fn marker(&self) -> Option<&str> {
+        self.icon
+            .as_deref()
+            .or_else(|| self.kind.map(SuggestionKind::marker))
+    }
+}
+
+/// The kind of a [`Suggestion`], like the category of an IDE completion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SuggestionKind {
+    /// A command or an alias
+    Command,
+    /// A file path
+    File,
+    /// A directory path
+    Directory,
+    /// A flag or option of a command
+    Flag,
+    /// A variable or an environment variable
+    Variable,
+}
+
+impl SuggestionKind {
+    /// The marker of the kind painted by the menus
+    pub fn marker(self) -> &'static str {
+        match self {
+            SuggestionKind::Command => ">",
+            SuggestionKind::File => "f",
+            SuggestionKind::Directory => "d",
+            SuggestionKind::Flag => "-",
+            SuggestionKind::Variable => "$",
+        }
+    }
+
+    /// The title of the section header of the kind painted by the menus
+    pub fn title(self) -> &'static str {
+        match self {
+            SuggestionKind::Command => "Commands",
+            SuggestionKind::File => "Files",
+            SuggestionKind::Directory => "Directories",
+            SuggestionKind::Flag => "Flags",
+            SuggestionKind::Variable => "Variables",
+        }
+    }
+}
+
+/// Sorts suggestions by their priority, keeping the order of equal ones
+pub(crate) fn sort_by_priority(suggestions: &mut [Suggestion]) {
+    suggestions.sort_by_key(|suggestion| std::cmp::Reverse(suggestion.priority));
 }
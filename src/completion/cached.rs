@@ -0,0 +1,263 @@
+use crate::{Completer, Span, Suggestion};
+
+/// The suggestions of the word last completed
+struct CachedCompletions {
+    /// The line before the word
+    head: String,
+    /// The word the suggestions replace, up to the cursor
+    word: String,
+    /// The line after the cursor
+    tail: String,
+    suggestions: Vec<Suggestion>,
+}
+
+/// A completer remembering the suggestions of another completer, for completers
+/// that are expensive to ask like the ones running an external command
+///
+/// While more characters are typed into the word, the remembered suggestions
+/// starting with the word are shown without asking the completer again. It is
+/// asked again once the word shrinks, a word boundary is typed or the rest of
+/// the line changes.
+///
+/// # Example
+/// ```rust
+/// use reedline::{CachedCompleter, DefaultCompleter, Reedline};
+///
+/// let commands = DefaultCompleter::new(vec!["status".into(), "stash".into()]);
+/// let completer = CachedCompleter::new(Box::new(commands));
+/// let mut line_editor = Reedline::create().with_completer(Box::new(completer));
+/// ```
+pub struct CachedCompleter {
+    completer: Box<dyn Completer>,
+    cache: Option<CachedCompletions>,
+}
+
+impl CachedCompleter {
+    /// A completer remembering the suggestions of `completer`
+    pub fn new(completer: Box<dyn Completer>) -> Self {
+        Self {
+            completer,
+            cache: None,
+        }
+    }
+
+    /// Forgets the remembered suggestions, e.g. after the candidates changed
+    pub fn clear(&mut self) {
+        self.cache = None;
+    }
+
+    /// The remembered suggestions still matching the word before the cursor,
+    /// if the line only grew by characters of the word
+    fn narrowed(&self, line: &str, pos: usize) -> Option<Vec<Suggestion>> {
+        let cache = self.cache.as_ref()?;
+        let start = cache.head.len();
+        if line.get(..start)? != cache.head || line.get(pos..)? != cache.tail {
+            return None;
+        }
+        let typed = line.get(start..pos)?;
+        let added = typed.strip_prefix(cache.word.as_str())?;
+        if added.contains(char::is_whitespace) {
+            return None;
+        }
+
+        Some(
+            cache
+                .suggestions
+                .iter()
+                .filter(|suggestion| suggestion.value.starts_with(typed))
+                .map(|suggestion| Suggestion {
+                    span: Span::new(start, pos),
+                    ..suggestion.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Remembers the suggestions if they all replace the same word ending at
+    /// the cursor, which the narrowing relies on
+    fn remember(&mut self, line: &str, pos: usize, suggestions: &[Suggestion]) {
+        self.cache = None;
+        // The suggestions still arriving would be missing from the cache
+        if self.completer.has_pending_completions() {
+            return;
+        }
+        let span = match suggestions.first() {
+            Some(suggestion) => suggestion.span,
+            None => return,
+        };
+        let same_word = suggestions.iter().all(|suggestion| suggestion.span == span);
+        if !same_word || span.end != pos {
+            return;
+        }
+        if let (Some(head), Some(word), Some(tail)) = (
+            line.get(..span.start),
+            line.get(span.start..pos),
+            line.get(pos..),
+        ) {
+            self.cache = Some(CachedCompletions {
+                head: head.to_string(),
+                word: word.to_string(),
+                tail: tail.to_string(),
+                suggestions: suggestions.to_vec(),
+            });
+        }
+    }
+}
+
+impl Completer for CachedCompleter {
+    fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+        if let Some(suggestions) = self.narrowed(line, pos) {
+            return suggestions;
+        }
+        let suggestions = self.completer.complete(line, pos);
+        self.remember(line, pos, &suggestions);
+        suggestions
+    }
+
+    /// A chunk of the remembered suggestions, or else of the completer, which
+    /// is not remembered as it misses the other suggestions
+    fn partial_complete(
+        &mut self,
+        line: &str,
+        pos: usize,
+        start: usize,
+        offset: usize,
+    ) -> Vec<Suggestion> {
+        match self.narrowed(line, pos) {
+            Some(suggestions) => suggestions.into_iter().skip(start).take(offset).collect(),
+            None => self.completer.partial_complete(line, pos, start, offset),
+        }
+    }
+
+    fn total_completions(&mut self, line: &str, pos: usize) -> usize {
+        match self.narrowed(line, pos) {
+            Some(suggestions) => suggestions.len(),
+            None => self.completer.total_completions(line, pos),
+        }
+    }
+
+    fn has_pending_completions(&self) -> bool {
+        self.completer.has_pending_completions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultCompleter;
+    use pretty_assertions::assert_eq;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    /// The start and size of the chunks asked for
+    type Chunks = Arc<Mutex<Vec<(usize, usize)>>>;
+
+    /// Counts how often the completer is asked for all of its suggestions,
+    /// and records the chunks asked for
+    struct CountingCompleter {
+        completer: DefaultCompleter,
+        asked: Arc<AtomicUsize>,
+        chunks: Chunks,
+    }
+
+    impl Completer for CountingCompleter {
+        fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+            self.asked.fetch_add(1, Ordering::Relaxed);
+            self.completer.complete(line, pos)
+        }
+
+        fn partial_complete(
+            &mut self,
+            line: &str,
+            pos: usize,
+            start: usize,
+            offset: usize,
+        ) -> Vec<Suggestion> {
+            self.chunks.lock().unwrap().push((start, offset));
+            self.completer.partial_complete(line, pos, start, offset)
+        }
+
+        fn total_completions(&mut self, line: &str, pos: usize) -> usize {
+            self.completer.total_completions(line, pos)
+        }
+    }
+
+    fn completer() -> (CachedCompleter, Arc<AtomicUsize>, Chunks) {
+        let asked = Arc::new(AtomicUsize::new(0));
+        let chunks = Arc::new(Mutex::new(vec![]));
+        let completer = CountingCompleter {
+            completer: DefaultCompleter::new_with_wordlen(
+                vec!["status".into(), "stash".into(), "switch".into()],
+                1,
+            ),
+            asked: Arc::clone(&asked),
+            chunks: Arc::clone(&chunks),
+        };
+        (CachedCompleter::new(Box::new(completer)), asked, chunks)
+    }
+
+    fn values(completer: &mut CachedCompleter, line: &str) -> Vec<(String, Span)> {
+        completer
+            .complete(line, line.len())
+            .into_iter()
+            .map(|suggestion| (suggestion.value, suggestion.span))
+            .collect()
+    }
+
+    #[test]
+    fn typing_into_the_word_narrows_the_cache() {
+        let (mut completer, asked, _) = completer();
+
+        assert_eq!(values(&mut completer, "git s").len(), 3);
+        assert_eq!(
+            values(&mut completer, "git sta"),
+            vec![
+                ("stash".to_string(), Span::new(4, 7)),
+                ("status".to_string(), Span::new(4, 7))
+            ]
+        );
+        assert_eq!(
+            values(&mut completer, "git stat"),
+            vec![("status".to_string(), Span::new(4, 8))]
+        );
+        assert_eq!(asked.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn shrinking_the_word_asks_again() {
+        let (mut completer, asked, _) = completer();
+
+        values(&mut completer, "git sta");
+        assert_eq!(values(&mut completer, "git s").len(), 3);
+        assert_eq!(asked.load(Ordering::Relaxed), 2);
+
+        values(&mut completer, "git s ");
+        assert_eq!(asked.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn chunks_are_asked_from_the_completer() {
+        let (mut completer, asked, chunks) = completer();
+
+        assert_eq!(completer.total_completions("git s", 5), 3);
+        assert_eq!(completer.partial_complete("git s", 5, 1, 2).len(), 2);
+        assert_eq!(*chunks.lock().unwrap(), vec![(1, 2)]);
+        assert_eq!(asked.load(Ordering::Relaxed), 0);
+
+        // the remembered suggestions are chunked without asking
+        values(&mut completer, "git s");
+        assert_eq!(completer.total_completions("git st", 6), 2);
+        assert_eq!(
+            completer
+                .partial_complete("git st", 6, 1, 2)
+                .into_iter()
+                .map(|suggestion| suggestion.value)
+                .collect::<Vec<_>>(),
+            vec!["status"]
+        );
+        assert_eq!(*chunks.lock().unwrap(), vec![(1, 2)]);
+        assert_eq!(asked.load(Ordering::Relaxed), 1);
+    }
+}
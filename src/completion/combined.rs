@@ -0,0 +1,264 @@
+use crate::{Completer, Suggestion};
+
+/// How a [`CombinedCompleter`] puts together the suggestions of its completers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CombineStrategy {
+    /// The suggestions of every completer, grouped in the order the completers
+    /// were added (default)
+    #[default]
+    Grouped,
+    /// Only the suggestions of the first completer having any, asking the
+    /// others as fallbacks
+    FirstWithSuggestions,
+    /// The suggestions of every completer sorted by their value, dropping
+    /// those suggested by an earlier completer already
+    Alphabetical,
+}
+
+/// A completer asking several completers, like one for paths and one for
+/// commands, for their suggestions
+///
+/// # Example
+/// ```rust
+/// use reedline::{CombineStrategy, CombinedCompleter, DefaultCompleter, Reedline};
+///
+/// let commands = DefaultCompleter::new(vec!["git".into(), "cargo".into()]);
+/// let branches = DefaultCompleter::new(vec!["main".into(), "feature".into()]);
+/// let completer = CombinedCompleter::default()
+///     .with_completer("command", Box::new(commands))
+///     .with_completer("branch", Box::new(branches))
+///     .with_strategy(CombineStrategy::Grouped)
+///     .with_group_labels(true);
+/// let mut line_editor = Reedline::create().with_completer(Box::new(completer));
+/// ```
+#[derive(Default)]
+pub struct CombinedCompleter {
+    /// The completers with the name of their group of suggestions
+    completers: Vec<(String, Box<dyn Completer>)>,
+    strategy: CombineStrategy,
+    group_labels: bool,
+}
+
+impl CombinedCompleter {
+    /// A builder that adds a completer after the ones added before, its
+    /// suggestions grouped as `group`
+    #[must_use]
+    pub fn with_completer(mut self, group: &str, completer: Box<dyn Completer>) -> Self {
+        self.completers.push((group.to_string(), completer));
+        self
+    }
+
+    /// A builder that sets how the suggestions of the completers are put together
+    #[must_use]
+    pub fn with_strategy(mut self, strategy: CombineStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// A builder that sets whether the menus show the group of each suggestion
+    /// in front of its description
+    #[must_use]
+    pub fn with_group_labels(mut self, group_labels: bool) -> Self {
+        self.group_labels = group_labels;
+        self
+    }
+
+    fn labeled(&self, group: &str, suggestion: Suggestion) -> Suggestion {
+        if !self.group_labels {
+            return suggestion;
+        }
+        let description = match suggestion.description {
+            Some(description) => format!("{}: {}", group, description),
+            None => group.to_string(),
+        };
+        Suggestion {
+            description: Some(description),
+            ..suggestion
+        }
+    }
+}
+
+impl Completer for CombinedCompleter {
+    fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+        let mut suggestions = vec![];
+        for (group, completer) in &mut self.completers {
+            let completions = completer.complete(line, pos);
+            let found = !completions.is_empty();
+            suggestions.extend(
+                completions
+                    .into_iter()
+                    .map(|suggestion| (group.clone(), suggestion)),
+            );
+            if found && self.strategy == CombineStrategy::FirstWithSuggestions {
+                break;
+            }
+        }
+
+        if self.strategy == CombineStrategy::Alphabetical {
+            // Stable, so that of equal suggestions the one of the earlier completer is kept
+            suggestions.sort_by(|(_, a), (_, b)| a.value.cmp(&b.value));
+            suggestions.dedup_by(|(_, later), (_, earlier)| {
+                later.value == earlier.value && later.span == earlier.span
+            });
+        }
+
+        suggestions
+            .into_iter()
+            .map(|(group, suggestion)| self.labeled(&group, suggestion))
+            .collect()
+    }
+
+    /// A chunk of the suggestions, asked from the completers it spans. The
+    /// alphabetical order needs all of them
+    fn partial_complete(
+        &mut self,
+        line: &str,
+        pos: usize,
+        start: usize,
+        offset: usize,
+    ) -> Vec<Suggestion> {
+        if self.strategy == CombineStrategy::Alphabetical {
+            return self
+                .complete(line, pos)
+                .into_iter()
+                .skip(start)
+                .take(offset)
+                .collect();
+        }
+
+        let mut suggestions = vec![];
+        let mut start = start;
+        for (group, completer) in &mut self.completers {
+            if suggestions.len() == offset {
+                break;
+            }
+            let total = completer.total_completions(line, pos);
+            if start < total {
+                let chunk =
+                    completer.partial_complete(line, pos, start, offset - suggestions.len());
+                suggestions.extend(
+                    chunk
+                        .into_iter()
+                        .map(|suggestion| (group.clone(), suggestion)),
+                );
+                start = 0;
+            } else {
+                start -= total;
+            }
+            if total > 0 && self.strategy == CombineStrategy::FirstWithSuggestions {
+                break;
+            }
+        }
+
+        suggestions
+            .into_iter()
+            .map(|(group, suggestion)| self.labeled(&group, suggestion))
+            .collect()
+    }
+
+    fn total_completions(&mut self, line: &str, pos: usize) -> usize {
+        match self.strategy {
+            CombineStrategy::Grouped => self
+                .completers
+                .iter_mut()
+                .map(|(_, completer)| completer.total_completions(line, pos))
+                .sum(),
+            CombineStrategy::FirstWithSuggestions => self
+                .completers
+                .iter_mut()
+                .map(|(_, completer)| completer.total_completions(line, pos))
+                .find(|total| *total > 0)
+                .unwrap_or(0),
+            CombineStrategy::Alphabetical => self.complete(line, pos).len(),
+        }
+    }
+
+    fn has_pending_completions(&self) -> bool {
+        self.completers
+            .iter()
+            .any(|(_, completer)| completer.has_pending_completions())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultCompleter;
+    use pretty_assertions::assert_eq;
+
+    fn completer(strategy: CombineStrategy) -> CombinedCompleter {
+        CombinedCompleter::default()
+            .with_completer(
+                "command",
+                Box::new(DefaultCompleter::new(vec!["stat".into(), "strace".into()])),
+            )
+            .with_completer(
+                "branch",
+                Box::new(DefaultCompleter::new(vec!["stash".into(), "strace".into()])),
+            )
+            .with_strategy(strategy)
+    }
+
+    fn values(completer: &mut CombinedCompleter, line: &str) -> Vec<String> {
+        completer
+            .complete(line, line.len())
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect()
+    }
+
+    #[test]
+    fn grouped_keeps_the_order_of_the_completers() {
+        let mut completer = completer(CombineStrategy::Grouped);
+
+        assert_eq!(
+            values(&mut completer, "st"),
+            vec!["stat", "strace", "stash", "strace"]
+        );
+    }
+
+    #[test]
+    fn first_with_suggestions_falls_back() {
+        let mut completer = completer(CombineStrategy::FirstWithSuggestions);
+
+        assert_eq!(values(&mut completer, "st"), vec!["stat", "strace"]);
+        assert_eq!(values(&mut completer, "stas"), vec!["stash"]);
+    }
+
+    #[test]
+    fn chunks_span_the_completers() {
+        let mut completer = completer(CombineStrategy::Grouped);
+
+        assert_eq!(completer.total_completions("st", 2), 4);
+        assert_eq!(
+            completer
+                .partial_complete("st", 2, 1, 2)
+                .into_iter()
+                .map(|suggestion| suggestion.value)
+                .collect::<Vec<_>>(),
+            vec!["strace", "stash"]
+        );
+
+        let mut completer = completer.with_strategy(CombineStrategy::FirstWithSuggestions);
+        assert_eq!(completer.total_completions("st", 2), 2);
+        assert_eq!(completer.partial_complete("st", 2, 1, 2).len(), 1);
+    }
+
+    #[test]
+    fn alphabetical_drops_duplicates() {
+        let mut completer = completer(CombineStrategy::Alphabetical).with_group_labels(true);
+
+        let suggestions = completer.complete("st", 2);
+        assert_eq!(
+            suggestions
+                .iter()
+                .map(|suggestion| (suggestion.value.as_str(), suggestion.description.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("stash", Some("branch")),
+                ("stat", Some("command")),
+                ("strace", Some("command"))
+            ]
+        );
+    }
+}
@@ -1,15 +1,25 @@
-use std::ops::Deref;
+use std::time::Duration;
+
+use chrono::Local;
 
 use crate::{
-    history::SearchQuery, menu_functions::parse_selection_char, Completer, History, Span,
-    Suggestion,
+    history::{substring_search, SearchFilter, SearchQuery},
+    menu_functions::parse_selection_char,
+    Completer, History, HistoryItem, HistoryRanking, HistoryScope, Span, Suggestion,
 };
 
 const SELECTION_CHAR: char = '!';
 
 // The HistoryCompleter is created just before updating the menu
 // It pulls data from the object that contains access to the History
-pub(crate) struct HistoryCompleter<'menu>(&'menu dyn History);
+// The extra of its suggestions holds the columns shown by a history menu, in
+// the order of `HistoryColumn`
+pub(crate) struct HistoryCompleter<'menu> {
+    history: &'menu dyn History,
+    ranking: HistoryRanking,
+    scope: HistoryScope,
+    regex: bool,
+}
 
 // Safe to implement Send since the Historycompleter should only be used when
 // updating the menu and that must happen in the same thread
@@ -18,16 +28,11 @@ unsafe impl<'menu> Send for HistoryCompleter<'menu> {}
 impl<'menu> Completer for HistoryCompleter<'menu> {
     fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
         let parsed = parse_selection_char(line, SELECTION_CHAR);
-        let values = self
-            .0
-            .search(SearchQuery::all_that_contain_rev(
-                parsed.remainder.to_string(),
-            ))
-            .expect("todo: error handling");
+        let values = self.search(parsed.remainder).expect("todo: error handling");
 
         values
             .into_iter()
-            .map(|value| self.create_suggestion(line, pos, value.command_line.deref()))
+            .map(|value| self.create_suggestion(line, pos, &value))
             .collect()
     }
 
@@ -35,33 +40,128 @@ impl<'menu> Completer for HistoryCompleter<'menu> {
 
     fn total_completions(&mut self, line: &str, _pos: usize) -> usize {
         let parsed = parse_selection_char(line, SELECTION_CHAR);
-        let count = self
-            .0
-            .count(SearchQuery::all_that_contain_rev(
-                parsed.remainder.to_string(),
-            ))
-            .expect("todo: error handling");
-        count as usize
+        match (self.ranking, self.scope, self.regex) {
+            (HistoryRanking::Recency, HistoryScope::Global, false) => {
+                self.history
+                    .count(SearchQuery::all_that_contain_rev(
+                        parsed.remainder.to_string(),
+                    ))
+                    .expect("todo: error handling") as usize
+            }
+            _ => self
+                .search(parsed.remainder)
+                .expect("todo: error handling")
+                .len(),
+        }
     }
 }
 
 impl<'menu> HistoryCompleter<'menu> {
-    pub fn new(history: &'menu dyn History) -> Self {
-        Self(history)
+    pub fn new(history: &'menu dyn History, ranking: HistoryRanking, scope: HistoryScope) -> Self {
+        Self {
+            history,
+            ranking,
+            scope,
+            regex: false,
+        }
+    }
+
+    /// Sets whether the text searched for is a regular expression
+    pub fn with_regex(mut self, regex: bool) -> Self {
+        self.regex = regex;
+        self
     }
 
-    fn create_suggestion(&self, line: &str, pos: usize, value: &str) -> Suggestion {
+    /// The entries of the scope containing `substring`, in the order of the ranking
+    fn search(&self, substring: &str) -> crate::Result<Vec<HistoryItem>> {
+        match self.search_in_scope(substring, self.scope) {
+            // the history doesn't know where its entries ran, offer all of them
+            Err(err) if err.is_unsupported_feature() && self.scope != HistoryScope::Global => {
+                self.search_in_scope(substring, HistoryScope::Global)
+            }
+            result => result,
+        }
+    }
+
+    fn search_in_scope(
+        &self,
+        substring: &str,
+        scope: HistoryScope,
+    ) -> crate::Result<Vec<HistoryItem>> {
+        let search = match substring_search(substring.to_string(), self.regex) {
+            Some(search) => search,
+            None => return Ok(vec![]),
+        };
+        let filter = scope.restrict(SearchFilter::from_text_search(search));
+        match self.ranking {
+            HistoryRanking::Recency => self.history.search(SearchQuery {
+                filter,
+                ..SearchQuery::all_that_contain_rev(String::new())
+            }),
+            HistoryRanking::Frecency { .. } => {
+                self.history
+                    .search_frecent(filter, self.ranking.boosted_cwd().as_deref(), None)
+            }
+        }
+    }
+
+    fn create_suggestion(&self, line: &str, pos: usize, item: &HistoryItem) -> Suggestion {
         let span = Span {
             start: pos,
             end: pos + line.len(),
         };
 
         Suggestion {
-            value: value.to_string(),
+            value: item.command_line.clone(),
             description: None,
-            extra: None,
+            extra: history_columns(item),
             span,
             append_whitespace: false,
+            kind: None,
+            icon: None,
+            priority: 0,
         }
     }
 }
+
+/// The columns of a history menu for an entry, in the order of
+/// [`crate::HistoryColumn`], empty where the history doesn't know them.
+/// `None` if the history knows none of them.
+fn history_columns(item: &HistoryItem) -> Option<Vec<String>> {
+    if item.start_timestamp.is_none()
+        && item.cwd.is_none()
+        && item.duration.is_none()
+        && item.exit_status.is_none()
+    {
+        return None;
+    }
+    Some(vec![
+        item.start_timestamp
+            .map(|timestamp| {
+                timestamp
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default(),
+        item.cwd.clone().unwrap_or_default(),
+        item.duration.map(format_duration).unwrap_or_default(),
+        item.exit_status
+            .map(|status| status.to_string())
+            .unwrap_or_default(),
+    ])
+}
+
+/// A duration in its largest units, like `350ms`, `4.2s` or `3m 20s`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs == 0 {
+        format!("{}ms", duration.as_millis())
+    } else if secs < 60 {
+        format!("{:.1}s", duration.as_secs_f64())
+    } else if secs < 3600 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {}m", secs / 3600, secs % 3600 / 60)
+    }
+}
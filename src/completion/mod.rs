@@ -1,6 +1,13 @@
+mod background;
 mod base;
+mod cached;
+mod combined;
 mod default;
 pub(crate) mod history;
 
-pub use base::{Completer, Span, Suggestion};
+pub use background::{AsyncCompleter, CompletionRequest};
+pub(crate) use base::sort_by_priority;
+pub use base::{Completer, Span, Suggestion, SuggestionKind};
+pub use cached::CachedCompleter;
+pub use combined::{CombineStrategy, CombinedCompleter};
 pub use default::DefaultCompleter;
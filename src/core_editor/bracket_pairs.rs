@@ -0,0 +1,88 @@
+/// Pairs of delimiters completed automatically while typing
+///
+/// - Typing an opening delimiter also inserts the closing one after the cursor.
+/// - Typing a closing delimiter in front of the same character moves over it.
+/// - A backspace between an opening and its closing delimiter removes both.
+///
+/// Pairs with the same opening and closing character, like quotes, are not
+/// completed directly after a word character, so apostrophes can still be typed.
+/// The pairs can be chosen to match the language edited in the line editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BracketPairs {
+    pairs: Vec<(char, char)>,
+}
+
+impl Default for BracketPairs {
+    /// Brackets and the double, single and back quotes
+    fn default() -> Self {
+        BracketPairs::brackets()
+            .with_pair('"', '"')
+            .with_pair('\'', '\'')
+            .with_pair('`', '`')
+    }
+}
+
+impl BracketPairs {
+    /// No pairs, disabling the automatic completion
+    pub fn new() -> Self {
+        BracketPairs { pairs: vec![] }
+    }
+
+    /// Parentheses, square and curly brackets
+    pub fn brackets() -> Self {
+        BracketPairs::new()
+            .with_pair('(', ')')
+            .with_pair('[', ']')
+            .with_pair('{', '}')
+    }
+
+    /// Add a pair, replacing the pair with the same opening delimiter
+    #[must_use]
+    pub fn with_pair(mut self, open: char, close: char) -> Self {
+        self.pairs.retain(|(existing, _)| *existing != open);
+        self.pairs.push((open, close));
+        self
+    }
+
+    /// Check if no pair is configured
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// The closing delimiter of the pair opened by `open`
+    pub fn closing(&self, open: char) -> Option<char> {
+        self.pairs
+            .iter()
+            .find(|(existing, _)| *existing == open)
+            .map(|(_, close)| *close)
+    }
+
+    /// Check if `c` closes one of the pairs
+    pub fn is_closing(&self, c: char) -> bool {
+        self.pairs.iter().any(|(_, close)| *close == c)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn with_pair_replaces_existing_pair() {
+        let pairs = BracketPairs::new().with_pair('<', ']').with_pair('<', '>');
+
+        assert_eq!(pairs.closing('<'), Some('>'));
+        assert!(pairs.is_closing('>'));
+        assert!(!pairs.is_closing(']'));
+    }
+
+    #[test]
+    fn default_contains_quotes() {
+        let pairs = BracketPairs::default();
+
+        assert_eq!(pairs.closing('"'), Some('"'));
+        assert_eq!(pairs.closing('('), Some(')'));
+        assert!(BracketPairs::new().is_empty());
+    }
+}
@@ -1,18 +1,31 @@
 /// Defines an interface to interact with a Clipboard for cut and paste.
 ///
+/// Implement it to connect the editor to the clipboard of the host application
+/// and set it with [`crate::Reedline::with_clipboard`].
+///
 /// Mutable reference requirements are stricter than always necessary, but the currently used system clipboard API demands them for exclusive access.
 pub trait Clipboard: Send {
+    /// Replace the content of the clipboard
     fn set(&mut self, content: &str, mode: ClipboardMode);
 
+    /// Get the content of the clipboard and how it should be inserted
     fn get(&mut self) -> (String, ClipboardMode);
 
+    /// Empty the clipboard
     fn clear(&mut self) {
         self.set("", ClipboardMode::Normal);
     }
 
+    /// Length of the content of the clipboard in bytes
     fn len(&mut self) -> usize {
         self.get().0.len()
     }
+
+    /// Escape sequence telling the terminal about the content last set, which
+    /// the line editor writes together with its next repaint
+    fn take_terminal_sequence(&mut self) -> Option<String> {
+        None
+    }
 }
 
 /// Determines how the content in the clipboard should be inserted
@@ -38,7 +51,7 @@ pub struct LocalClipboard {
 }
 
 impl LocalClipboard {
-    #[allow(dead_code)]
+    /// Create an empty clipboard
     pub fn new() -> Self {
         Self::default()
     }
@@ -85,33 +98,47 @@ mod system_clipboard {
 
     /// Wrapper around [`clipboard`](https://docs.rs/clipboard) crate
     ///
-    /// Requires that the feature `system_clipboard` is enabled
+    /// Requires that the feature `system_clipboard` is enabled.
+    /// If the system clipboard is not available, e.g. without a display server,
+    /// the content is only kept inside the application like with [`LocalClipboard`]
     pub struct SystemClipboard {
-        cb: ClipboardContext,
+        cb: Option<ClipboardContext>,
         local_copy: String,
         mode: ClipboardMode,
     }
 
     impl SystemClipboard {
+        /// Connect to the system clipboard
         pub fn new() -> Self {
-            let cb = ClipboardProvider::new().unwrap();
             SystemClipboard {
-                cb,
+                cb: ClipboardProvider::new().ok(),
                 local_copy: String::new(),
                 mode: ClipboardMode::Normal,
             }
         }
     }
 
+    impl Default for SystemClipboard {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     impl Clipboard for SystemClipboard {
         fn set(&mut self, content: &str, mode: ClipboardMode) {
             self.local_copy = content.to_owned();
-            let _ = self.cb.set_contents(content.to_owned());
+            if let Some(cb) = self.cb.as_mut() {
+                let _ = cb.set_contents(content.to_owned());
+            }
             self.mode = mode;
         }
 
         fn get(&mut self) -> (String, ClipboardMode) {
-            let system_content = self.cb.get_contents().unwrap_or_default();
+            let system_content = match self.cb.as_mut().map(|cb| cb.get_contents()) {
+                Some(Ok(content)) => content,
+                // Fall back to the content cut or copied inside the line editor
+                _ => self.local_copy.clone(),
+            };
             if system_content == self.local_copy {
                 // We assume the content was yanked inside the line editor and the last yank determined the mode.
                 (system_content, self.mode)
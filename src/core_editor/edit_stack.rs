@@ -1,7 +1,66 @@
+/// Bounds for the amount of undo history kept by the [`crate::Editor`]
+///
+/// When a limit is exceeded the oldest entries are evicted. The entry
+/// currently being pointed to is always kept.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UndoLimit {
+    /// Maximum number of entries kept
+    pub max_entries: Option<usize>,
+    /// Maximum number of bytes of buffer content kept across all entries
+    pub max_bytes: Option<usize>,
+}
+
+impl UndoLimit {
+    /// Limit the undo history to `max_entries` entries
+    pub fn entries(max_entries: usize) -> Self {
+        UndoLimit {
+            max_entries: Some(max_entries),
+            max_bytes: None,
+        }
+    }
+
+    /// Limit the undo history to `max_bytes` bytes of buffer content
+    pub fn bytes(max_bytes: usize) -> Self {
+        UndoLimit {
+            max_entries: None,
+            max_bytes: Some(max_bytes),
+        }
+    }
+
+    /// Check if the given entries are more than this limit allows
+    pub(crate) fn is_exceeded_by<'a, T, I>(&self, entries: I) -> bool
+    where
+        T: UndoSize + 'a,
+        I: ExactSizeIterator<Item = &'a T>,
+    {
+        if matches!(self.max_entries, Some(max_entries) if entries.len() > max_entries) {
+            return true;
+        }
+        match self.max_bytes {
+            Some(max_bytes) => entries.map(UndoSize::undo_size).sum::<usize>() > max_bytes,
+            None => false,
+        }
+    }
+}
+
+/// Approximate size of an entry kept in the undo history
+pub(crate) trait UndoSize {
+    /// Size in bytes
+    fn undo_size(&self) -> usize;
+}
+
+#[cfg(test)]
+impl UndoSize for isize {
+    fn undo_size(&self) -> usize {
+        std::mem::size_of::<isize>()
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct EditStack<T> {
     internal_list: Vec<T>,
     index: usize,
+    limit: UndoLimit,
 }
 
 impl<T> EditStack<T> {
@@ -12,22 +71,81 @@ impl<T> EditStack<T> {
         EditStack {
             internal_list: vec![T::default()],
             index: 0,
+            limit: UndoLimit::default(),
         }
     }
 }
 
+/// Storage for the undo/redo states of the [`crate::Editor`]
+pub(crate) trait UndoHistory<T>: Send {
+    /// Go back one point in the undo history
+    fn undo(&mut self) -> &T;
+
+    /// Go forward one point in the undo history
+    fn redo(&mut self) -> &T;
+
+    /// Insert a new entry after the current point of the undo history
+    fn insert(&mut self, value: T);
+
+    /// Replace the current entry, used to group several edits into one undo point
+    fn amend(&mut self, value: T);
+
+    /// Reset the history to the initial state
+    fn reset(&mut self);
+
+    /// Return the entry currently being pointed to
+    fn current(&mut self) -> &T;
+
+    /// Set the limit after which the oldest entries are evicted
+    fn set_limit(&mut self, limit: UndoLimit);
+
+    /// All the entries of the history in the order they were inserted
+    fn entries(&self) -> Vec<&T>;
+
+    /// Position of the current entry in [`UndoHistory::entries`]
+    fn position(&self) -> usize;
+
+    /// Make the entry at `position` of [`UndoHistory::entries`] the current one
+    fn jump_to(&mut self, position: usize) -> &T;
+
+    /// Switch to the next alternative branch of the current entry, if the
+    /// history keeps track of them
+    fn next_branch(&mut self) -> &T {
+        self.current()
+    }
+
+    /// Switch to the previous alternative branch of the current entry, if the
+    /// history keeps track of them
+    fn previous_branch(&mut self) -> &T {
+        self.current()
+    }
+}
+
 impl<T> EditStack<T>
 where
-    T: Default + Clone + Send,
+    T: UndoSize,
+{
+    /// Drop the oldest entries until the limit is satisfied
+    fn evict(&mut self) {
+        while self.index > 0 && self.limit.is_exceeded_by(self.internal_list.iter()) {
+            self.internal_list.remove(0);
+            self.index -= 1;
+        }
+    }
+}
+
+impl<T> UndoHistory<T> for EditStack<T>
+where
+    T: Default + Clone + Send + UndoSize,
 {
     /// Go back one point in the undo stack. If present on first edit do nothing
-    pub(super) fn undo(&mut self) -> &T {
+    fn undo(&mut self) -> &T {
         self.index = if self.index == 0 { 0 } else { self.index - 1 };
         &self.internal_list[self.index]
     }
 
     /// Go forward one point in the undo stack. If present on the last edit do nothing
-    pub(super) fn redo(&mut self) -> &T {
+    fn redo(&mut self) -> &T {
         self.index = if self.index == self.internal_list.len() - 1 {
             self.index
         } else {
@@ -39,7 +157,7 @@ where
     /// Insert a new entry to the undo stack.
     /// NOTE: (IMP): If we have hit undo a few times then discard all the other values that come
     /// after the current point
-    pub(super) fn insert(&mut self, value: T) {
+    fn insert(&mut self, value: T) {
         if self.index < self.internal_list.len() - 1 {
             self.internal_list.resize_with(self.index + 1, || {
                 panic!("Impossible state reached: Bug in UndoStack logic")
@@ -47,16 +165,41 @@ where
         }
         self.internal_list.push(value);
         self.index += 1;
+        self.evict();
+    }
+
+    /// Replace the last entry of the undo stack. The initial entry is never replaced.
+    fn amend(&mut self, value: T) {
+        self.undo();
+        self.insert(value);
     }
 
     /// Reset the stack to the initial state
-    pub(super) fn reset(&mut self) {
+    fn reset(&mut self) {
         self.index = 0;
         self.internal_list = vec![T::default()];
     }
 
     /// Return the entry currently being pointed to
-    pub(super) fn current(&mut self) -> &T {
+    fn current(&mut self) -> &T {
+        &self.internal_list[self.index]
+    }
+
+    fn set_limit(&mut self, limit: UndoLimit) {
+        self.limit = limit;
+        self.evict();
+    }
+
+    fn entries(&self) -> Vec<&T> {
+        self.internal_list.iter().collect()
+    }
+
+    fn position(&self) -> usize {
+        self.index
+    }
+
+    fn jump_to(&mut self, position: usize) -> &T {
+        self.index = position.min(self.internal_list.len() - 1);
         &self.internal_list[self.index]
     }
 }
@@ -74,6 +217,7 @@ mod test {
         EditStack {
             internal_list: values.to_vec(),
             index,
+            limit: UndoLimit::default(),
         }
     }
 
@@ -110,4 +254,33 @@ mod test {
         stack.insert(value_to_insert);
         assert_eq!(stack, expected_stack);
     }
+
+    #[rstest]
+    #[case(UndoLimit::entries(2), &[2, 3][..], 1)]
+    #[case(UndoLimit::bytes(3 * std::mem::size_of::<isize>()), &[1, 2, 3][..], 2)]
+    #[case(UndoLimit::default(), &[0, 1, 2, 3][..], 3)]
+    fn insert_evicts_oldest(
+        #[case] limit: UndoLimit,
+        #[case] expected_values: &[isize],
+        #[case] expected_index: usize,
+    ) {
+        let mut stack = EditStack::new();
+        stack.set_limit(limit);
+        for value in 1..=3 {
+            stack.insert(value);
+        }
+
+        let mut expected_stack = edit_stack(expected_values, expected_index);
+        expected_stack.limit = limit;
+        assert_eq!(stack, expected_stack);
+    }
+
+    #[test]
+    fn eviction_keeps_current_entry() {
+        let mut stack = edit_stack(&[1, 2, 3][..], 2);
+        stack.set_limit(UndoLimit::bytes(1));
+
+        assert_eq!(stack.internal_list, vec![3]);
+        assert_eq!(*stack.undo(), 3);
+    }
 }
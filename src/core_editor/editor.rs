@@ -1,6 +1,58 @@
-use super::{edit_stack::EditStack, Clipboard, ClipboardMode, LineBuffer};
-use crate::enums::{EditType, UndoBehavior};
-use crate::{core_editor::get_default_clipboard, EditCommand};
+use super::{
+    capitalize, delimiter_pair,
+    edit_stack::{EditStack, UndoHistory, UndoLimit, UndoSize},
+    kill_ring::KillDirection,
+    matching_bracket,
+    registers::RegisterStore,
+    surrounding_object, toggle_case,
+    undo_tree::UndoTree,
+    BracketPairs, Clipboard, ClipboardMode, LineBuffer, WordBoundary,
+};
+use crate::enums::{EditType, UndoBehavior, UndoCoalescing};
+use crate::{Abbreviation, AbbreviationHook, EditCommand};
+use std::{
+    collections::HashMap,
+    ops::Range,
+    time::{Duration, Instant},
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// State stored for every undo point
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UndoEntry {
+    /// Snapshot of the buffer after the edit
+    line_buffer: LineBuffer,
+    /// Insertion point before the edit was made, restored when the edit is undone
+    edit_position: usize,
+    /// Time of the last edit recorded in this entry
+    timestamp: Instant,
+}
+
+impl Default for UndoEntry {
+    fn default() -> Self {
+        UndoEntry {
+            line_buffer: LineBuffer::default(),
+            edit_position: 0,
+            timestamp: Instant::now(),
+        }
+    }
+}
+
+/// Effect of the previous edit command on the kill ring
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum KillState {
+    None,
+    /// Text was cut, a following cut joins it in the kill ring
+    Killed,
+    /// Text was pasted in the given range, a following yank-pop may replace it
+    Yanked(Range<usize>),
+}
+
+impl UndoSize for UndoEntry {
+    fn undo_size(&self) -> usize {
+        self.line_buffer.get_buffer().len()
+    }
+}
 
 /// Stateful editor executing changes to the underlying [`LineBuffer`]
 ///
@@ -8,19 +60,43 @@ use crate::{core_editor::get_default_clipboard, EditCommand};
 /// the undo/redo history and has facilities for cut/copy/yank/paste
 pub struct Editor {
     line_buffer: LineBuffer,
-    cut_buffer: Box<dyn Clipboard>,
-
-    edit_stack: EditStack<LineBuffer>,
+    registers: RegisterStore,
+    join_kills: bool,
+    kill_state: KillState,
+    selection_anchor: Option<usize>,
+    block_selection: bool,
+    secondary_cursors: Vec<usize>,
+    marks: HashMap<char, usize>,
+    bracket_pairs: BracketPairs,
+    abbreviations: Option<Box<dyn Abbreviation>>,
+    abbreviation_hook: Option<AbbreviationHook>,
+
+    edit_stack: Box<dyn UndoHistory<UndoEntry>>,
     last_undo_behavior: UndoBehavior,
+    last_edit_position: usize,
+    undo_coalescing: UndoCoalescing,
+    undo_limit: UndoLimit,
 }
 
 impl Default for Editor {
     fn default() -> Self {
         Editor {
             line_buffer: LineBuffer::new(),
-            cut_buffer: Box::new(get_default_clipboard()),
-            edit_stack: EditStack::new(),
+            registers: RegisterStore::default(),
+            join_kills: true,
+            kill_state: KillState::None,
+            selection_anchor: None,
+            block_selection: false,
+            secondary_cursors: vec![],
+            marks: HashMap::new(),
+            bracket_pairs: BracketPairs::new(),
+            abbreviations: None,
+            abbreviation_hook: None,
+            edit_stack: Box::new(EditStack::new()),
             last_undo_behavior: UndoBehavior::CreateUndoPoint,
+            last_edit_position: 0,
+            undo_coalescing: UndoCoalescing::default(),
+            undo_limit: UndoLimit::default(),
         }
     }
 }
@@ -34,11 +110,155 @@ impl Editor {
     /// Set the current LineBuffer.
     /// Undo behavior specifies how this change should be reflected on the undo stack.
     pub(crate) fn set_line_buffer(&mut self, line_buffer: LineBuffer, undo_behavior: UndoBehavior) {
+        let edit_position = self.line_buffer.insertion_point();
+        self.restore_line_buffer(line_buffer);
+        self.update_undo_state(undo_behavior, edit_position);
+    }
+
+    /// Replace the line buffer, keeping the word boundary of the current one
+    fn restore_line_buffer(&mut self, mut line_buffer: LineBuffer) {
+        line_buffer.set_word_boundary(self.line_buffer.word_boundary().clone());
         self.line_buffer = line_buffer;
-        self.update_undo_state(undo_behavior);
+    }
+
+    /// Set the policy deciding what counts as a word for the word motions and edits
+    pub(crate) fn set_word_boundary(&mut self, word_boundary: WordBoundary) {
+        self.line_buffer.set_word_boundary(word_boundary);
+    }
+
+    /// Set the clipboard backing the default register used for cut, copy and paste
+    pub(crate) fn set_clipboard(&mut self, clipboard: Box<dyn Clipboard>) {
+        self.registers.set_clipboard(clipboard);
+    }
+
+    /// The escape sequence the clipboard wants written to the terminal
+    pub(crate) fn take_clipboard_sequence(&mut self) -> Option<String> {
+        self.registers.take_terminal_sequence()
+    }
+
+    /// Set the delimiters that are completed automatically while typing
+    pub(crate) fn set_bracket_pairs(&mut self, bracket_pairs: BracketPairs) {
+        self.bracket_pairs = bracket_pairs;
+    }
+
+    /// Set the abbreviations expanded when typing a space
+    pub(crate) fn set_abbreviations(&mut self, abbreviations: Box<dyn Abbreviation>) {
+        self.abbreviations = Some(abbreviations);
+    }
+
+    pub(crate) fn has_abbreviations(&self) -> bool {
+        self.abbreviations.is_some()
+    }
+
+    /// Set the hook that can veto or modify the expansion of an abbreviation
+    pub(crate) fn set_abbreviation_hook(&mut self, hook: AbbreviationHook) {
+        self.abbreviation_hook = Some(hook);
+    }
+
+    /// Set if consecutive cuts are joined into a single kill ring entry like in Emacs
+    pub(crate) fn set_join_kills(&mut self, join_kills: bool) {
+        self.join_kills = join_kills;
+    }
+
+    /// Set the policy used to group consecutive edits into a single undo point
+    pub(crate) fn set_undo_coalescing(&mut self, undo_coalescing: UndoCoalescing) {
+        self.undo_coalescing = undo_coalescing;
+    }
+
+    /// Set the bounds after which the oldest undo points are evicted
+    pub(crate) fn set_undo_limit(&mut self, undo_limit: UndoLimit) {
+        self.undo_limit = undo_limit;
+        self.edit_stack.set_limit(undo_limit);
+    }
+
+    /// Switch between a linear undo stack and an undo tree that keeps the
+    /// alternative branches created by editing after an undo.
+    /// The current undo history is discarded.
+    pub(crate) fn set_undo_tree(&mut self, undo_tree: bool) {
+        self.edit_stack = if undo_tree {
+            Box::new(UndoTree::new())
+        } else {
+            Box::new(EditStack::new())
+        };
+        self.edit_stack.set_limit(self.undo_limit);
+        self.update_undo_state(UndoBehavior::CreateUndoPoint, 0);
     }
 
     pub(crate) fn run_edit_command(&mut self, command: &EditCommand) {
+        let edit_position = self.line_buffer.insertion_point();
+        let deleted_char = match command {
+            EditCommand::Delete => self.line_buffer.grapheme_right().chars().next(),
+            EditCommand::Backspace => self.line_buffer.grapheme_left().chars().next(),
+            _ => None,
+        };
+        let previous_len = match command.edit_type() {
+            EditType::EditText | EditType::UndoRedo if !self.marks.is_empty() => {
+                Some(self.line_buffer.len())
+            }
+            _ => None,
+        };
+        let kill_direction = kill_direction(command);
+        let join_kill = match (self.join_kills, &self.kill_state) {
+            (true, KillState::Killed) => kill_direction,
+            _ => None,
+        };
+        self.registers.join_next_cut(join_kill);
+        let yanked = match command {
+            EditCommand::AddCursorAtNextOccurrence => self.execute_command(command),
+            _ if self.secondary_cursors.is_empty() => self.execute_command(command),
+            _ if applies_to_all_cursors(command) => {
+                self.run_at_all_cursors(command);
+                None
+            }
+            _ => {
+                self.secondary_cursors.clear();
+                self.execute_command(command)
+            }
+        };
+
+        // Commands that don't touch the buffer must not break up the coalesced edits
+        if command.edit_type() != EditType::NoOp {
+            let new_undo_behavior = match (command, command.edit_type()) {
+                (_, EditType::MoveCursor) => UndoBehavior::MoveCursor,
+                (EditCommand::InsertChar(c), EditType::EditText) => {
+                    UndoBehavior::InsertCharacter(*c)
+                }
+                (EditCommand::Delete, EditType::EditText) => UndoBehavior::Delete(deleted_char),
+                (EditCommand::Backspace, EditType::EditText) => {
+                    UndoBehavior::Backspace(deleted_char)
+                }
+                (_, EditType::UndoRedo) => UndoBehavior::UndoRedo,
+                (_, _) => UndoBehavior::CreateUndoPoint,
+            };
+            // Moved lines stay selected
+            let moved_selection = match command {
+                EditCommand::MoveLineUp | EditCommand::MoveLineDown => self.selection_anchor,
+                _ => None,
+            };
+            let secondary_cursors = std::mem::take(&mut self.secondary_cursors);
+            self.update_undo_state(new_undo_behavior, edit_position);
+            self.secondary_cursors = secondary_cursors;
+            if moved_selection.is_some() {
+                self.selection_anchor = moved_selection;
+            }
+        }
+
+        self.kill_state = match (kill_direction, yanked) {
+            (Some(_), _) => KillState::Killed,
+            (None, Some(range)) => KillState::Yanked(range),
+            (None, None) => KillState::None,
+        };
+
+        if let Some(previous_len) = previous_len {
+            self.update_marks(previous_len, edit_position);
+        }
+    }
+
+    /// Run the edit command at the insertion point, returns the range of the pasted text
+    fn execute_command(&mut self, command: &EditCommand) -> Option<Range<usize>> {
+        let edit_position = self.line_buffer.insertion_point();
+        let mut yanked = None;
+
         match command {
             EditCommand::MoveToStart => self.line_buffer.move_to_start(),
             EditCommand::MoveToLineStart => self.line_buffer.move_to_line_start(),
@@ -47,19 +267,23 @@ impl Editor {
             EditCommand::MoveToPosition(pos) => self.line_buffer.set_insertion_point(*pos),
             EditCommand::MoveLeft => self.line_buffer.move_left(),
             EditCommand::MoveRight => self.line_buffer.move_right(),
+            EditCommand::MoveUp => self.line_buffer.move_line_up(),
+            EditCommand::MoveDown => self.line_buffer.move_line_down(),
             EditCommand::MoveWordLeft => self.line_buffer.move_word_left(),
+            EditCommand::MoveSubwordLeft => self.line_buffer.move_subword_left(),
+            EditCommand::MoveSubwordRight => self.line_buffer.move_subword_right(),
             EditCommand::MoveBigWordLeft => self.line_buffer.move_big_word_left(),
             EditCommand::MoveWordRight => self.line_buffer.move_word_right(),
             EditCommand::MoveWordRightStart => self.line_buffer.move_word_right_start(),
             EditCommand::MoveBigWordRightStart => self.line_buffer.move_big_word_right_start(),
             EditCommand::MoveWordRightEnd => self.line_buffer.move_word_right_end(),
             EditCommand::MoveBigWordRightEnd => self.line_buffer.move_big_word_right_end(),
-            EditCommand::InsertChar(c) => self.line_buffer.insert_char(*c),
+            EditCommand::InsertChar(c) => self.insert_char(*c),
             EditCommand::InsertString(str) => self.line_buffer.insert_str(str),
             EditCommand::InsertNewline => self.line_buffer.insert_newline(),
             EditCommand::ReplaceChar(chr) => self.replace_char(*chr),
             EditCommand::ReplaceChars(n_chars, str) => self.replace_chars(*n_chars, str),
-            EditCommand::Backspace => self.line_buffer.delete_left_grapheme(),
+            EditCommand::Backspace => self.backspace(),
             EditCommand::Delete => self.line_buffer.delete_right_grapheme(),
             EditCommand::CutChar => self.cut_char(),
             EditCommand::BackspaceWord => self.line_buffer.delete_word_left(),
@@ -67,26 +291,62 @@ impl Editor {
             EditCommand::Clear => self.line_buffer.clear(),
             EditCommand::ClearToLineEnd => self.line_buffer.clear_to_line_end(),
             EditCommand::CutCurrentLine => self.cut_current_line(),
+            EditCommand::MoveLineUp => self.move_lines_up(),
+            EditCommand::MoveLineDown => self.move_lines_down(),
+            EditCommand::DuplicateLine => self.duplicate_lines(),
+            EditCommand::JoinLines => self.join_lines(),
+            EditCommand::DeleteLine => self.delete_lines(),
             EditCommand::CutFromStart => self.cut_from_start(),
             EditCommand::CutFromLineStart => self.cut_from_line_start(),
             EditCommand::CutToEnd => self.cut_from_end(),
             EditCommand::CutToLineEnd => self.cut_to_line_end(),
             EditCommand::CutWordLeft => self.cut_word_left(),
+            EditCommand::CutSubwordLeft => {
+                let left_index = self.line_buffer.subword_left_index();
+                self.cut_range(left_index..edit_position);
+            }
+            EditCommand::CutSubwordRight => {
+                let right_index = self.line_buffer.subword_right_index();
+                self.cut_range(edit_position..right_index);
+            }
             EditCommand::CutBigWordLeft => self.cut_big_word_left(),
             EditCommand::CutWordRight => self.cut_word_right(),
             EditCommand::CutBigWordRight => self.cut_big_word_right(),
             EditCommand::CutWordRightToNext => self.cut_word_right_to_next(),
             EditCommand::CutBigWordRightToNext => self.cut_big_word_right_to_next(),
-            EditCommand::PasteCutBufferBefore => self.insert_cut_buffer_before(),
-            EditCommand::PasteCutBufferAfter => self.insert_cut_buffer_after(),
-            EditCommand::UppercaseWord => self.line_buffer.uppercase_word(),
-            EditCommand::LowercaseWord => self.line_buffer.lowercase_word(),
+            EditCommand::PasteCutBufferBefore => yanked = Some(self.insert_cut_buffer_before()),
+            EditCommand::PasteCutBufferAfter => yanked = Some(self.insert_cut_buffer_after()),
+            EditCommand::YankPop => yanked = self.yank_pop(),
+            EditCommand::UppercaseWord => {
+                if !self.transform_selection(str::to_uppercase) {
+                    self.line_buffer.uppercase_word();
+                }
+            }
+            EditCommand::LowercaseWord => {
+                if !self.transform_selection(str::to_lowercase) {
+                    self.line_buffer.lowercase_word();
+                }
+            }
+            EditCommand::CapitalizeWord => {
+                if !self.transform_selection(capitalize) {
+                    self.line_buffer.capitalize_word();
+                }
+            }
+            EditCommand::ToggleCase => {
+                if !self.transform_selection(toggle_case) {
+                    self.line_buffer.switchcase_char();
+                }
+            }
             EditCommand::SwitchcaseChar => self.line_buffer.switchcase_char(),
             EditCommand::CapitalizeChar => self.line_buffer.capitalize_char(),
             EditCommand::SwapWords => self.line_buffer.swap_words(),
+            EditCommand::TransposeWords => self.line_buffer.transpose_words(),
+            EditCommand::TransposeLines => self.line_buffer.transpose_lines(),
             EditCommand::SwapGraphemes => self.line_buffer.swap_graphemes(),
             EditCommand::Undo => self.undo(),
             EditCommand::Redo => self.redo(),
+            EditCommand::UndoEarlier(duration) => self.undo_earlier(*duration),
+            EditCommand::RedoLater(duration) => self.redo_later(*duration),
             EditCommand::CutRightUntil(c) => self.cut_right_until_char(*c, false, true),
             EditCommand::CutRightBefore(c) => self.cut_right_until_char(*c, true, true),
             EditCommand::MoveRightUntil(c) => self.move_right_until_char(*c, false, true),
@@ -95,33 +355,271 @@ impl Editor {
             EditCommand::CutLeftBefore(c) => self.cut_left_until_char(*c, true, true),
             EditCommand::MoveLeftUntil(c) => self.move_left_until_char(*c, false, true),
             EditCommand::MoveLeftBefore(c) => self.move_left_until_char(*c, true, true),
+            EditCommand::CopyFromStart => self.copy_range(0..edit_position, ClipboardMode::Normal),
+            EditCommand::CopyFromLineStart => {
+                let line_start = self.line_buffer.current_line_range().start;
+                self.copy_range(line_start..edit_position, ClipboardMode::Normal);
+            }
+            EditCommand::CopyToEnd => {
+                self.copy_range(edit_position..self.line_buffer.len(), ClipboardMode::Normal);
+            }
+            EditCommand::CopyToLineEnd => {
+                let line_end = self.line_buffer.find_current_line_end();
+                self.copy_range(edit_position..line_end, ClipboardMode::Normal);
+            }
+            EditCommand::CopyCurrentLine => {
+                self.copy_range(self.line_buffer.current_line_range(), ClipboardMode::Lines);
+            }
+            EditCommand::CopyWordLeft => {
+                let left_index = self.line_buffer.word_left_index();
+                self.copy_range(left_index..edit_position, ClipboardMode::Normal);
+            }
+            EditCommand::CopyBigWordLeft => {
+                let left_index = self.line_buffer.big_word_left_index();
+                self.copy_range(left_index..edit_position, ClipboardMode::Normal);
+            }
+            EditCommand::CopyWordRight => {
+                let right_index = self.line_buffer.word_right_index();
+                self.copy_range(edit_position..right_index, ClipboardMode::Normal);
+            }
+            EditCommand::CopyBigWordRight => {
+                let right_index = self.line_buffer.next_whitespace();
+                self.copy_range(edit_position..right_index, ClipboardMode::Normal);
+            }
+            EditCommand::CopyWordRightToNext => {
+                let right_index = self.line_buffer.word_right_start_index();
+                self.copy_range(edit_position..right_index, ClipboardMode::Normal);
+            }
+            EditCommand::CopyBigWordRightToNext => {
+                let right_index = self.line_buffer.big_word_right_start_index();
+                self.copy_range(edit_position..right_index, ClipboardMode::Normal);
+            }
+            EditCommand::CopyRightUntil(c) => self.copy_right_until_char(*c, false, true),
+            EditCommand::CopyRightBefore(c) => self.copy_right_until_char(*c, true, true),
+            EditCommand::CopyLeftUntil(c) => self.copy_left_until_char(*c, false, true),
+            EditCommand::CopyLeftBefore(c) => self.copy_left_until_char(*c, true, true),
+            EditCommand::SelectRegister(register) => self.registers.select(*register),
+            EditCommand::CutTextObject(text_object) => {
+                if let Some(range) = text_object.range(self.get_buffer(), edit_position) {
+                    self.cut_range(range);
+                }
+            }
+            EditCommand::CopyTextObject(text_object) => {
+                if let Some(range) = text_object.range(self.get_buffer(), edit_position) {
+                    self.copy_range(range, ClipboardMode::Normal);
+                }
+            }
+            EditCommand::SelectTextObject(text_object) => {
+                let range = match self.get_selection() {
+                    Some(selection) => text_object.expand(self.get_buffer(), selection),
+                    None => text_object.range(self.get_buffer(), edit_position),
+                };
+                if let Some(range) = range {
+                    self.start_selection(range.start, false);
+                    self.line_buffer.set_insertion_point(range.end);
+                }
+            }
+            EditCommand::SurroundSelection(delimiter) => self.surround_selection(*delimiter),
+            EditCommand::ChangeSurrounding(from, to) => self.change_surrounding(*from, Some(*to)),
+            EditCommand::DeleteSurrounding(delimiter) => self.change_surrounding(*delimiter, None),
+            EditCommand::SetMark(name) => {
+                self.marks.insert(*name, edit_position);
+            }
+            EditCommand::JumpToMark(name) => self.jump_to_mark(*name),
+            EditCommand::JumpToMarkLine(name) => {
+                self.jump_to_mark(*name);
+                self.line_buffer.move_to_line_start();
+            }
+            EditCommand::MoveToMatchingBracket => {
+                if let Some((_, matching)) = self.matching_bracket() {
+                    self.line_buffer.set_insertion_point(matching);
+                }
+            }
+            EditCommand::ExpandAbbreviation => self.expand_abbreviation(),
+            EditCommand::StartSelection => self.start_selection(edit_position, false),
+            EditCommand::StartBlockSelection => self.start_selection(edit_position, true),
+            EditCommand::AddCursorsAtBlockStart => self.add_cursors_at_block_start(),
+            EditCommand::AddCursorAtNextOccurrence => self.add_cursor_at_next_occurrence(),
+            EditCommand::SelectAll => {
+                self.start_selection(0, false);
+                self.line_buffer.move_to_end();
+            }
+            EditCommand::CutSelection => self.cut_selection(),
+            EditCommand::CopySelection => self.copy_selection(),
+            EditCommand::CopySelectionAndMoveToStart => {
+                let start = self.get_selection().map(|range| range.start);
+                self.copy_selection();
+                if let Some(start) = start {
+                    self.line_buffer.set_insertion_point(start);
+                }
+            }
+            EditCommand::Paste => self.paste(),
+        }
+        yanked
+    }
+
+    /// Run the command at every cursor. The cursors are processed from the end of
+    /// the buffer, so an edit only moves the cursors that were already processed.
+    fn run_at_all_cursors(&mut self, command: &EditCommand) {
+        let primary = self.line_buffer.insertion_point();
+        let mut cursors = self.secondary_cursors.clone();
+        cursors.push(primary);
+        cursors.sort_unstable();
+        cursors.dedup();
+
+        let mut moved_cursors: Vec<(usize, bool)> = Vec::with_capacity(cursors.len());
+        for cursor in cursors.into_iter().rev() {
+            let previous_len = self.line_buffer.len();
+            self.line_buffer.set_insertion_point(cursor);
+            self.execute_command(command);
+            let len = self.line_buffer.len();
+            for (position, _) in &mut moved_cursors {
+                *position = (*position + len).saturating_sub(previous_len);
+            }
+            moved_cursors.push((self.line_buffer.insertion_point(), cursor == primary));
         }
 
-        let new_undo_behavior = match (command, command.edit_type()) {
-            (_, EditType::MoveCursor) => UndoBehavior::MoveCursor,
-            (EditCommand::InsertChar(c), EditType::EditText) => UndoBehavior::InsertCharacter(*c),
-            (EditCommand::Delete, EditType::EditText) => {
-                let deleted_char = self.edit_stack.current().grapheme_right().chars().next();
-                UndoBehavior::Delete(deleted_char)
+        let mut secondary_cursors = vec![];
+        for (position, is_primary) in moved_cursors.into_iter().rev() {
+            if is_primary {
+                self.line_buffer.set_insertion_point(position);
+            } else {
+                secondary_cursors.push(position);
             }
-            (EditCommand::Backspace, EditType::EditText) => {
-                let deleted_char = self.edit_stack.current().grapheme_left().chars().next();
-                UndoBehavior::Backspace(deleted_char)
+        }
+        let primary = self.line_buffer.insertion_point();
+        secondary_cursors.retain(|position| *position != primary);
+        secondary_cursors.dedup();
+        self.secondary_cursors = secondary_cursors;
+    }
+
+    /// Place the insertion point at the start of the block selection and a
+    /// secondary cursor at the left column of every other selected line
+    fn add_cursors_at_block_start(&mut self) {
+        let block = self.get_block_selection();
+        if let Some((first, rest)) = block.split_first() {
+            self.line_buffer.set_insertion_point(first.start);
+            self.secondary_cursors = rest.iter().map(|range| range.start).collect();
+        }
+        self.selection_anchor = None;
+    }
+
+    /// Add a cursor at the same offset in the next occurrence of the word at
+    /// the insertion point, searching from the most recently added cursor
+    fn add_cursor_at_next_occurrence(&mut self) {
+        let primary = self.line_buffer.insertion_point();
+        let word_range = self.line_buffer.word_around_insertion_point();
+        if word_range.is_empty() {
+            return;
+        }
+        let offset_in_word = primary - word_range.start;
+        let word = self.line_buffer.get_buffer()[word_range.clone()].to_string();
+
+        let last_cursor = self.secondary_cursors.last().copied().unwrap_or(primary);
+        let search_start = last_cursor.saturating_sub(offset_in_word) + word.len();
+        if let Some(start) = self.line_buffer.find_word_occurrence(&word, search_start) {
+            let cursor = start + offset_in_word;
+            if cursor != primary && !self.secondary_cursors.contains(&cursor) {
+                self.secondary_cursors.push(cursor);
             }
-            (_, EditType::UndoRedo) => UndoBehavior::UndoRedo,
-            (_, _) => UndoBehavior::CreateUndoPoint,
-        };
-        self.update_undo_state(new_undo_behavior);
+        }
+    }
+
+    /// Positions of the additional cursors edits are applied at, in the order they were added
+    pub(crate) fn secondary_cursors(&self) -> &[usize] {
+        &self.secondary_cursors
+    }
+
+    /// Remove all cursors but the insertion point
+    pub(crate) fn clear_secondary_cursors(&mut self) {
+        self.secondary_cursors.clear();
     }
 
     pub(crate) fn move_line_up(&mut self) {
         self.line_buffer.move_line_up();
-        self.update_undo_state(UndoBehavior::MoveCursor);
+        self.update_undo_state(UndoBehavior::MoveCursor, 0);
     }
 
     pub(crate) fn move_line_down(&mut self) {
         self.line_buffer.move_line_down();
-        self.update_undo_state(UndoBehavior::MoveCursor);
+        self.update_undo_state(UndoBehavior::MoveCursor, 0);
+    }
+
+    /// Positions of the bracket under the insertion point, or if there is none the
+    /// one just before it, and of its match
+    pub(crate) fn matching_bracket(&self) -> Option<(usize, usize)> {
+        let buffer = self.line_buffer.get_buffer();
+        let insertion_point = self.line_buffer.insertion_point();
+        let before = buffer[..insertion_point]
+            .chars()
+            .next_back()
+            .map(|c| insertion_point - c.len_utf8());
+        std::iter::once(insertion_point)
+            .chain(before)
+            .find_map(|bracket| Some((bracket, matching_bracket(buffer, bracket)?)))
+    }
+
+    /// Range of the buffer between the selection anchor and the insertion point,
+    /// `None` if nothing is selected
+    pub(crate) fn get_selection(&self) -> Option<Range<usize>> {
+        let anchor = self.selection_anchor.filter(|_| !self.block_selection)?;
+        let insertion_point = self.line_buffer.insertion_point();
+        let range = anchor.min(insertion_point)..anchor.max(insertion_point);
+        let valid = !range.is_empty() && self.line_buffer.get_buffer().get(range.clone()).is_some();
+        valid.then_some(range)
+    }
+
+    /// Ranges of the block selection spanned by the selection anchor and the
+    /// insertion point, one for every line that reaches into the selected columns.
+    /// Both the column of the anchor and of the insertion point are included.
+    pub(crate) fn get_block_selection(&self) -> Vec<Range<usize>> {
+        let anchor = match self.selection_anchor {
+            Some(anchor) if self.block_selection => anchor,
+            _ => return vec![],
+        };
+        let buffer = self.line_buffer.get_buffer();
+        let insertion_point = self.line_buffer.insertion_point();
+        if buffer.get(anchor..).is_none() {
+            return vec![];
+        }
+
+        let line_start = |offset: usize| buffer[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let column = |offset: usize| buffer[line_start(offset)..offset].graphemes(true).count();
+        let (left, right) = (
+            column(anchor).min(column(insertion_point)),
+            column(anchor).max(column(insertion_point)),
+        );
+        let start = line_start(anchor.min(insertion_point));
+        let end = anchor.max(insertion_point);
+        let end = buffer[end..].find('\n').map_or(buffer.len(), |i| end + i);
+
+        let mut ranges = vec![];
+        let mut line_offset = start;
+        for line in buffer[start..end].split('\n') {
+            let content = line.strip_suffix('\r').unwrap_or(line);
+            let column_offset = |column: usize| {
+                content
+                    .grapheme_indices(true)
+                    .nth(column)
+                    .map_or(content.len(), |(i, _)| i)
+            };
+            let range = line_offset + column_offset(left)..line_offset + column_offset(right + 1);
+            if !range.is_empty() {
+                ranges.push(range);
+            }
+            line_offset += line.len() + 1;
+        }
+        ranges
+    }
+
+    /// The marks set in the buffer by their name
+    pub fn marks(&self) -> &HashMap<char, usize> {
+        &self.marks
+    }
+
+    /// End the selection without changing the buffer
+    pub(crate) fn clear_selection(&mut self) {
+        self.selection_anchor = None;
     }
 
     /// Get the text of the current LineBuffer
@@ -134,15 +632,16 @@ impl Editor {
     where
         F: FnOnce(&mut LineBuffer),
     {
-        self.update_undo_state(undo_behavior);
+        self.update_undo_state(undo_behavior, self.line_buffer.insertion_point());
         func(&mut self.line_buffer);
     }
 
     /// Set the text of the current LineBuffer given the specified UndoBehavior
     /// Insertion point update to the end of the buffer.
     pub(crate) fn set_buffer(&mut self, buffer: String, undo_behavior: UndoBehavior) {
+        let edit_position = self.line_buffer.insertion_point();
         self.line_buffer.set_buffer(buffer);
-        self.update_undo_state(undo_behavior);
+        self.update_undo_state(undo_behavior, edit_position);
     }
 
     pub(crate) fn insertion_point(&self) -> usize {
@@ -170,45 +669,146 @@ impl Editor {
     }
 
     pub(crate) fn move_to_start(&mut self, undo_behavior: UndoBehavior) {
+        let edit_position = self.line_buffer.insertion_point();
         self.line_buffer.move_to_start();
-        self.update_undo_state(undo_behavior);
+        self.update_undo_state(undo_behavior, edit_position);
     }
 
     pub(crate) fn move_to_end(&mut self, undo_behavior: UndoBehavior) {
+        let edit_position = self.line_buffer.insertion_point();
         self.line_buffer.move_to_end();
-        self.update_undo_state(undo_behavior);
+        self.update_undo_state(undo_behavior, edit_position);
+    }
+
+    pub(crate) fn move_to_position(&mut self, position: usize, undo_behavior: UndoBehavior) {
+        let edit_position = self.line_buffer.insertion_point();
+        self.line_buffer.set_insertion_point(position);
+        self.update_undo_state(undo_behavior, edit_position);
     }
 
     #[allow(dead_code)]
     pub(crate) fn move_to_line_start(&mut self, undo_behavior: UndoBehavior) {
+        let edit_position = self.line_buffer.insertion_point();
         self.line_buffer.move_to_line_start();
-        self.update_undo_state(undo_behavior);
+        self.update_undo_state(undo_behavior, edit_position);
     }
 
     pub(crate) fn move_to_line_end(&mut self, undo_behavior: UndoBehavior) {
+        let edit_position = self.line_buffer.insertion_point();
         self.line_buffer.move_to_line_end();
-        self.update_undo_state(undo_behavior);
+        self.update_undo_state(undo_behavior, edit_position);
     }
 
     fn undo(&mut self) {
-        let val = self.edit_stack.undo();
-        self.line_buffer = val.clone();
+        let edit_position = self.edit_stack.current().edit_position;
+        let line_buffer = self.edit_stack.undo().line_buffer.clone();
+        self.restore_line_buffer(line_buffer);
+        self.restore_edit_position(edit_position);
+    }
+
+    /// Put the cursor back to where the undone edit was made
+    fn restore_edit_position(&mut self, edit_position: usize) {
+        if self
+            .line_buffer
+            .get_buffer()
+            .is_char_boundary(edit_position)
+        {
+            self.line_buffer.set_insertion_point(edit_position);
+        }
     }
 
     fn redo(&mut self) {
-        let val = self.edit_stack.redo();
-        self.line_buffer = val.clone();
+        let line_buffer = self.edit_stack.redo().line_buffer.clone();
+        self.restore_line_buffer(line_buffer);
+    }
+
+    fn undo_earlier(&mut self, duration: Duration) {
+        let entries = self.edit_stack.entries();
+        let position = self.edit_stack.position();
+        let target = entries[position].timestamp.checked_sub(duration);
+
+        // The most recent entry before the current one that is old enough
+        let new_position = entries[..position]
+            .iter()
+            .rposition(|entry| matches!(target, Some(target) if entry.timestamp <= target))
+            .unwrap_or(0);
+        // The first of the undone edits, where the cursor goes back to
+        let edit_position =
+            (new_position < position).then(|| entries[new_position + 1].edit_position);
+
+        let line_buffer = self.edit_stack.jump_to(new_position).line_buffer.clone();
+        self.restore_line_buffer(line_buffer);
+        if let Some(edit_position) = edit_position {
+            self.restore_edit_position(edit_position);
+        }
+    }
+
+    fn redo_later(&mut self, duration: Duration) {
+        let entries = self.edit_stack.entries();
+        let position = self.edit_stack.position();
+        let target = entries[position].timestamp.checked_add(duration);
+
+        // The oldest entry after the current one that is recent enough
+        let new_position = entries[position + 1..]
+            .iter()
+            .position(|entry| matches!(target, Some(target) if entry.timestamp >= target))
+            .map_or(entries.len() - 1, |offset| position + 1 + offset);
+
+        let line_buffer = self.edit_stack.jump_to(new_position).line_buffer.clone();
+        self.restore_line_buffer(line_buffer);
+    }
+
+    /// Switch to the next alternative branch of the undo tree
+    pub(crate) fn undo_branch_next(&mut self) {
+        let line_buffer = self.edit_stack.next_branch().line_buffer.clone();
+        self.restore_line_buffer(line_buffer);
+        self.last_undo_behavior = UndoBehavior::UndoRedo;
+    }
+
+    /// Switch to the previous alternative branch of the undo tree
+    pub(crate) fn undo_branch_previous(&mut self) {
+        let line_buffer = self.edit_stack.previous_branch().line_buffer.clone();
+        self.restore_line_buffer(line_buffer);
+        self.last_undo_behavior = UndoBehavior::UndoRedo;
     }
 
-    fn update_undo_state(&mut self, undo_behavior: UndoBehavior) {
+    /// Record the current state on the undo stack. `edit_position` is the
+    /// insertion point before the change was made.
+    fn update_undo_state(&mut self, undo_behavior: UndoBehavior, edit_position: usize) {
+        // Any change in between breaks a chain of kills or the yank-pop after a paste
+        // and removes the additional cursors, `run_edit_command` restores the state
+        // caused by the command afterwards
+        self.kill_state = KillState::None;
+        self.secondary_cursors.clear();
+        // Only cursor movement extends the selection, any edit ends it
+        if !matches!(undo_behavior, UndoBehavior::MoveCursor) {
+            self.selection_anchor = None;
+        }
         if matches!(undo_behavior, UndoBehavior::UndoRedo) {
             self.last_undo_behavior = UndoBehavior::UndoRedo;
             return;
         }
-        if !undo_behavior.create_undo_point_after(&self.last_undo_behavior) {
-            self.edit_stack.undo();
+        let create_undo_point =
+            undo_behavior.create_undo_point_with(&self.last_undo_behavior, self.undo_coalescing);
+        if create_undo_point {
+            self.last_edit_position = edit_position;
+        }
+        // Cursor movement amends the current entry but keeps the time of its edit
+        let timestamp = if matches!(undo_behavior, UndoBehavior::MoveCursor) {
+            self.edit_stack.current().timestamp
+        } else {
+            Instant::now()
+        };
+        let entry = UndoEntry {
+            line_buffer: self.line_buffer.clone(),
+            edit_position: self.last_edit_position,
+            timestamp,
+        };
+        if create_undo_point {
+            self.edit_stack.insert(entry);
+        } else {
+            self.edit_stack.amend(entry);
         }
-        self.edit_stack.insert(self.line_buffer.clone());
         self.last_undo_behavior = undo_behavior;
     }
 
@@ -217,16 +817,131 @@ impl Editor {
 
         let cut_slice = &self.line_buffer.get_buffer()[deletion_range.clone()];
         if !cut_slice.is_empty() {
-            self.cut_buffer.set(cut_slice, ClipboardMode::Lines);
+            self.registers.cut(cut_slice, ClipboardMode::Lines);
             self.line_buffer.set_insertion_point(deletion_range.start);
             self.line_buffer.clear_range(deletion_range);
         }
     }
 
+    /// Range of the lines spanned by the selection, or of the current line if
+    /// nothing is selected, without the newline terminating the last one
+    fn selected_lines(&self) -> Range<usize> {
+        let buffer = self.line_buffer.get_buffer();
+        let (first, last) = match self.get_selection() {
+            // A selection ending at the start of a line doesn't include that line
+            Some(range) => (range.start, (range.end - 1).max(range.start)),
+            None => {
+                let insertion_point = self.line_buffer.insertion_point();
+                (insertion_point, insertion_point)
+            }
+        };
+        let start = buffer[..first].rfind('\n').map_or(0, |index| index + 1);
+        let end = buffer[last..]
+            .find('\n')
+            .map_or(buffer.len(), |index| last + index);
+        start..end
+    }
+
+    fn move_lines_up(&mut self) {
+        let lines = self.selected_lines();
+        if lines.start == 0 {
+            return;
+        }
+        let buffer = self.line_buffer.get_buffer();
+        let previous_start = buffer[..lines.start - 1]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let swapped = format!(
+            "{}\n{}",
+            &buffer[lines.clone()],
+            &buffer[previous_start..lines.start - 1]
+        );
+        let offset = lines.start - previous_start;
+        let insertion_point = self.line_buffer.insertion_point() - offset;
+        self.line_buffer
+            .replace_range(previous_start..lines.end, &swapped);
+        self.line_buffer.set_insertion_point(insertion_point);
+        self.selection_anchor = self.selection_anchor.map(|anchor| anchor - offset);
+    }
+
+    fn move_lines_down(&mut self) {
+        let lines = self.selected_lines();
+        let buffer = self.line_buffer.get_buffer();
+        if lines.end == buffer.len() {
+            return;
+        }
+        let next_end = buffer[lines.end + 1..]
+            .find('\n')
+            .map_or(buffer.len(), |index| lines.end + 1 + index);
+        let next_line = &buffer[lines.end + 1..next_end];
+        let swapped = format!("{}\n{}", next_line, &buffer[lines.clone()]);
+        let offset = next_line.len() + 1;
+        let insertion_point = self.line_buffer.insertion_point() + offset;
+        self.line_buffer
+            .replace_range(lines.start..next_end, &swapped);
+        self.line_buffer.set_insertion_point(insertion_point);
+        self.selection_anchor = self.selection_anchor.map(|anchor| anchor + offset);
+    }
+
+    fn duplicate_lines(&mut self) {
+        let lines = self.selected_lines();
+        let copy = format!("\n{}", &self.line_buffer.get_buffer()[lines.clone()]);
+        let insertion_point = self.line_buffer.insertion_point() + copy.len();
+        self.line_buffer.replace_range(lines.end..lines.end, &copy);
+        self.line_buffer.set_insertion_point(insertion_point);
+    }
+
+    /// Replace the newlines and the indentation following them with a single
+    /// space. Without a selection the current line is joined with the next one.
+    /// The insertion point is placed at the last join.
+    fn join_lines(&mut self) {
+        let mut lines = self.selected_lines();
+        let buffer = self.line_buffer.get_buffer();
+        if !buffer[lines.clone()].contains('\n') {
+            lines.end = buffer[(lines.end + 1).min(buffer.len())..]
+                .find('\n')
+                .map_or(buffer.len(), |index| lines.end + 1 + index);
+        }
+        let mut joined = String::new();
+        let mut join_point = None;
+        for (index, line) in buffer[lines.clone()].split('\n').enumerate() {
+            if index == 0 {
+                joined.push_str(line);
+                continue;
+            }
+            let line = line.trim_start();
+            join_point = Some(lines.start + joined.len());
+            if !line.is_empty() && !joined.is_empty() && !joined.ends_with(char::is_whitespace) {
+                joined.push(' ');
+            }
+            joined.push_str(line);
+        }
+        if let Some(join_point) = join_point {
+            self.line_buffer.replace_range(lines, &joined);
+            self.line_buffer.set_insertion_point(join_point);
+        }
+    }
+
+    fn delete_lines(&mut self) {
+        let lines = self.selected_lines();
+        let buffer_len = self.line_buffer.len();
+        if lines.end < buffer_len {
+            self.line_buffer.clear_range(lines.start..lines.end + 1);
+            self.line_buffer.set_insertion_point(lines.start);
+        } else if lines.start > 0 {
+            // Remove the newline in front of the last line and move to the line before it
+            self.line_buffer.clear_range(lines.start - 1..lines.end);
+            self.line_buffer.set_insertion_point(lines.start - 1);
+            self.line_buffer.move_to_line_start();
+        } else {
+            self.line_buffer.clear();
+        }
+    }
+
     fn cut_from_start(&mut self) {
         let insertion_offset = self.line_buffer.insertion_point();
         if insertion_offset > 0 {
-            self.cut_buffer.set(
+            self.registers.cut(
                 &self.line_buffer.get_buffer()[..insertion_offset],
                 ClipboardMode::Normal,
             );
@@ -240,7 +955,7 @@ impl Editor {
         let deletion_range = self.line_buffer.insertion_point()..previous_offset;
         let cut_slice = &self.line_buffer.get_buffer()[deletion_range.clone()];
         if !cut_slice.is_empty() {
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            self.registers.cut(cut_slice, ClipboardMode::Normal);
             self.line_buffer.clear_range(deletion_range);
         }
     }
@@ -248,7 +963,7 @@ impl Editor {
     fn cut_from_end(&mut self) {
         let cut_slice = &self.line_buffer.get_buffer()[self.line_buffer.insertion_point()..];
         if !cut_slice.is_empty() {
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            self.registers.cut(cut_slice, ClipboardMode::Normal);
             self.line_buffer.clear_to_end();
         }
     }
@@ -257,7 +972,7 @@ impl Editor {
         let cut_slice = &self.line_buffer.get_buffer()
             [self.line_buffer.insertion_point()..self.line_buffer.find_current_line_end()];
         if !cut_slice.is_empty() {
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            self.registers.cut(cut_slice, ClipboardMode::Normal);
             self.line_buffer.clear_to_line_end();
         }
     }
@@ -267,7 +982,7 @@ impl Editor {
         let left_index = self.line_buffer.word_left_index();
         if left_index < insertion_offset {
             let cut_range = left_index..insertion_offset;
-            self.cut_buffer.set(
+            self.registers.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -276,12 +991,27 @@ impl Editor {
         }
     }
 
+    /// Replace the selected text with the result of `transform` and move the
+    /// insertion point to the start of the selection. Returns `false` if nothing
+    /// is selected.
+    fn transform_selection(&mut self, transform: impl Fn(&str) -> String) -> bool {
+        match self.get_selection() {
+            Some(range) => {
+                let transformed = transform(&self.line_buffer.get_buffer()[range.clone()]);
+                self.line_buffer.replace_range(range.clone(), &transformed);
+                self.line_buffer.set_insertion_point(range.start);
+                true
+            }
+            None => false,
+        }
+    }
+
     fn cut_big_word_left(&mut self) {
         let insertion_offset = self.line_buffer.insertion_point();
         let left_index = self.line_buffer.big_word_left_index();
         if left_index < insertion_offset {
             let cut_range = left_index..insertion_offset;
-            self.cut_buffer.set(
+            self.registers.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -295,7 +1025,7 @@ impl Editor {
         let right_index = self.line_buffer.word_right_index();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
+            self.registers.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -308,7 +1038,7 @@ impl Editor {
         let right_index = self.line_buffer.next_whitespace();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
+            self.registers.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -321,7 +1051,7 @@ impl Editor {
         let right_index = self.line_buffer.word_right_start_index();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
+            self.registers.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -334,7 +1064,7 @@ impl Editor {
         let right_index = self.line_buffer.big_word_right_start_index();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
+            self.registers.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -347,7 +1077,7 @@ impl Editor {
         let right_index = self.line_buffer.grapheme_right_index();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
+            self.registers.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -355,11 +1085,9 @@ impl Editor {
         }
     }
 
-    fn insert_cut_buffer_before(&mut self) {
-        match self.cut_buffer.get() {
-            (content, ClipboardMode::Normal) => {
-                self.line_buffer.insert_str(&content);
-            }
+    fn insert_cut_buffer_before(&mut self) -> Range<usize> {
+        let content = match self.registers.get() {
+            (content, ClipboardMode::Normal) => content,
             (mut content, ClipboardMode::Lines) => {
                 // TODO: Simplify that?
                 self.line_buffer.move_to_line_start();
@@ -368,16 +1096,17 @@ impl Editor {
                     // TODO: Make sure platform requirements are met
                     content.push('\n');
                 }
-                self.line_buffer.insert_str(&content);
+                content
             }
-        }
+        };
+        self.insert_yanked(&content)
     }
 
-    fn insert_cut_buffer_after(&mut self) {
-        match self.cut_buffer.get() {
+    fn insert_cut_buffer_after(&mut self) -> Range<usize> {
+        let content = match self.registers.get() {
             (content, ClipboardMode::Normal) => {
                 self.line_buffer.move_right();
-                self.line_buffer.insert_str(&content);
+                content
             }
             (mut content, ClipboardMode::Lines) => {
                 // TODO: Simplify that?
@@ -387,9 +1116,32 @@ impl Editor {
                     // TODO: Make sure platform requirements are met
                     content.push('\n');
                 }
-                self.line_buffer.insert_str(&content);
+                content
             }
-        }
+        };
+        self.insert_yanked(&content)
+    }
+
+    /// Insert the pasted content and return the range it now occupies
+    fn insert_yanked(&mut self, content: &str) -> Range<usize> {
+        let start = self.line_buffer.insertion_point();
+        self.line_buffer.insert_str(content);
+        start..self.line_buffer.insertion_point()
+    }
+
+    /// Replace the text inserted by the previous paste or yank-pop with the
+    /// kill before it in the kill ring
+    fn yank_pop(&mut self) -> Option<Range<usize>> {
+        let range = match &self.kill_state {
+            KillState::Yanked(range) => range.clone(),
+            _ => return None,
+        };
+        self.line_buffer.get_buffer().get(range.clone())?;
+
+        let (content, _) = self.registers.yank_pop()?;
+        self.line_buffer.clear_range(range.clone());
+        self.line_buffer.set_insertion_point(range.start);
+        Some(self.insert_yanked(&content))
     }
 
     fn move_right_until_char(&mut self, c: char, before_char: bool, current_line: bool) {
@@ -417,7 +1169,7 @@ impl Editor {
                 &self.line_buffer.get_buffer()[self.line_buffer.insertion_point()..index + extra];
 
             if !cut_slice.is_empty() {
-                self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+                self.registers.cut(cut_slice, ClipboardMode::Normal);
 
                 if before_char {
                     self.line_buffer.delete_right_before_char(c, current_line);
@@ -437,7 +1189,7 @@ impl Editor {
                 &self.line_buffer.get_buffer()[index + extra..self.line_buffer.insertion_point()];
 
             if !cut_slice.is_empty() {
-                self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+                self.registers.cut(cut_slice, ClipboardMode::Normal);
 
                 if before_char {
                     self.line_buffer.delete_left_before_char(c, current_line);
@@ -448,6 +1200,236 @@ impl Editor {
         }
     }
 
+    /// Insert the character, completing or moving over the delimiters of the
+    /// bracket pairs
+    fn insert_char(&mut self, c: char) {
+        if c == ' ' {
+            self.expand_abbreviation();
+        }
+        let buffer = self.line_buffer.get_buffer();
+        let insertion_point = self.line_buffer.insertion_point();
+        let next = buffer[insertion_point..].chars().next();
+        let previous = buffer[..insertion_point].chars().next_back();
+
+        if self.bracket_pairs.is_closing(c) && next == Some(c) {
+            self.line_buffer.move_right();
+            return;
+        }
+        self.line_buffer.insert_char(c);
+        match self.bracket_pairs.closing(c) {
+            Some(close)
+                if close != c || !matches!(previous, Some(previous) if is_word_char(previous)) =>
+            {
+                self.line_buffer.insert_char(close);
+                self.line_buffer.move_left();
+            }
+            _ => {}
+        }
+    }
+
+    /// Replace the word left of the insertion point with its expansion if it is
+    /// an abbreviation. The word has to end at the insertion point.
+    fn expand_abbreviation(&mut self) {
+        let abbreviations = match &self.abbreviations {
+            Some(abbreviations) => abbreviations,
+            None => return,
+        };
+        let buffer = self.line_buffer.get_buffer();
+        let end = self.line_buffer.insertion_point();
+        if matches!(buffer[end..].chars().next(), Some(c) if !c.is_whitespace()) {
+            return;
+        }
+        let start = buffer[..end]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(index, c)| index + c.len_utf8());
+        if start == end {
+            return;
+        }
+        let word = &buffer[start..end];
+        let expansion = abbreviations
+            .expand(word, buffer, start)
+            .and_then(|expansion| match &mut self.abbreviation_hook {
+                Some(hook) => hook(word, expansion),
+                None => Some(expansion),
+            });
+        if let Some(expansion) = expansion {
+            self.line_buffer.replace_range(start..end, &expansion);
+            self.line_buffer
+                .set_insertion_point(start + expansion.len());
+        }
+    }
+
+    /// Delete the grapheme left of the insertion point and the closing
+    /// delimiter right of it if both form an empty bracket pair
+    fn backspace(&mut self) {
+        let buffer = self.line_buffer.get_buffer();
+        let insertion_point = self.line_buffer.insertion_point();
+        let next = buffer[insertion_point..].chars().next();
+        let previous = buffer[..insertion_point].chars().next_back();
+
+        if let (Some(open), Some(next)) = (previous, next) {
+            if self.bracket_pairs.closing(open) == Some(next) {
+                self.line_buffer.delete_right_grapheme();
+            }
+        }
+        self.line_buffer.delete_left_grapheme();
+    }
+
+    fn surround_selection(&mut self, delimiter: char) {
+        if let Some(range) = self.get_selection() {
+            let (open, close) = delimiter_pair(delimiter);
+            self.line_buffer
+                .replace_range(range.end..range.end, &close.to_string());
+            self.line_buffer
+                .replace_range(range.start..range.start, &open.to_string());
+            self.line_buffer.set_insertion_point(range.start);
+        }
+    }
+
+    /// Replace the delimiters of the innermost pair of `from` with those of `to`,
+    /// remove them if `to` is `None`
+    fn change_surrounding(&mut self, from: char, to: Option<char>) {
+        let insertion_point = self.line_buffer.insertion_point();
+        if let Some(range) = surrounding_object(from).range(self.get_buffer(), insertion_point) {
+            let (open, close) = delimiter_pair(from);
+            let (new_open, new_close) = match to.map(delimiter_pair) {
+                Some((open, close)) => (open.to_string(), close.to_string()),
+                None => (String::new(), String::new()),
+            };
+            self.line_buffer
+                .replace_range(range.end - close.len_utf8()..range.end, &new_close);
+            self.line_buffer
+                .replace_range(range.start..range.start + open.len_utf8(), &new_open);
+            self.line_buffer.set_insertion_point(range.start);
+        }
+    }
+
+    fn jump_to_mark(&mut self, name: char) {
+        if let Some(&mark) = self.marks.get(&name) {
+            // The buffer may have been replaced without updating the marks
+            let buffer = self.line_buffer.get_buffer();
+            let mark = if buffer.is_char_boundary(mark) {
+                mark
+            } else {
+                buffer.len()
+            };
+            self.line_buffer.set_insertion_point(mark);
+        }
+    }
+
+    /// Move the marks so they keep pointing at the same text after the buffer
+    /// changed from `previous_len` bytes. Marks in deleted text move to its start.
+    ///
+    /// The change is assumed to start at the insertion point before or after the
+    /// edit, whichever is first, and to insert or delete the difference in length.
+    fn update_marks(&mut self, previous_len: usize, edit_position: usize) {
+        let len = self.line_buffer.len();
+        let change_start = edit_position.min(self.line_buffer.insertion_point());
+        let deleted_end = change_start + previous_len.saturating_sub(len);
+        for mark in self.marks.values_mut() {
+            if *mark >= deleted_end {
+                *mark = *mark + len - previous_len;
+            } else if *mark > change_start {
+                *mark = change_start;
+            }
+        }
+    }
+
+    fn start_selection(&mut self, anchor: usize, block_selection: bool) {
+        self.selection_anchor = Some(anchor);
+        self.block_selection = block_selection;
+    }
+
+    /// The lines of a block selection are joined by newlines
+    fn block_selection_text(&self, ranges: &[Range<usize>]) -> String {
+        ranges
+            .iter()
+            .map(|range| &self.line_buffer.get_buffer()[range.clone()])
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn cut_selection(&mut self) {
+        let block = self.get_block_selection();
+        if let Some(first) = block.first() {
+            let start = first.start;
+            self.registers
+                .cut(&self.block_selection_text(&block), ClipboardMode::Normal);
+            for range in block.into_iter().rev() {
+                self.line_buffer.clear_range(range);
+            }
+            self.line_buffer.set_insertion_point(start);
+        } else if let Some(range) = self.get_selection() {
+            self.registers.cut(
+                &self.line_buffer.get_buffer()[range.clone()],
+                ClipboardMode::Normal,
+            );
+            self.line_buffer.clear_range(range.clone());
+            self.line_buffer.set_insertion_point(range.start);
+        }
+    }
+
+    fn copy_selection(&mut self) {
+        let block = self.get_block_selection();
+        if let Some(first) = block.first() {
+            let start = first.start;
+            self.registers
+                .copy(&self.block_selection_text(&block), ClipboardMode::Normal);
+            self.line_buffer.set_insertion_point(start);
+        } else if let Some(range) = self.get_selection() {
+            self.copy_range(range, ClipboardMode::Normal);
+        }
+        self.selection_anchor = None;
+    }
+
+    /// Insert the clipboard content at the insertion point, replacing the selection
+    fn paste(&mut self) {
+        if let Some(range) = self.get_selection() {
+            self.line_buffer.clear_range(range.clone());
+            self.line_buffer.set_insertion_point(range.start);
+        }
+        let (content, _) = self.registers.get();
+        self.line_buffer.insert_str(&content);
+    }
+
+    fn cut_range(&mut self, range: Range<usize>) {
+        let cut_slice = &self.line_buffer.get_buffer()[range.clone()];
+        if !cut_slice.is_empty() {
+            self.registers.cut(cut_slice, ClipboardMode::Normal);
+            self.line_buffer.clear_range(range.clone());
+            self.line_buffer.set_insertion_point(range.start);
+        }
+    }
+
+    fn copy_range(&mut self, range: Range<usize>, mode: ClipboardMode) {
+        let copy_slice = &self.line_buffer.get_buffer()[range];
+        if !copy_slice.is_empty() {
+            self.registers.copy(copy_slice, mode);
+        }
+    }
+
+    fn copy_right_until_char(&mut self, c: char, before_char: bool, current_line: bool) {
+        if let Some(index) = self.line_buffer.find_char_right(c, current_line) {
+            let extra = if before_char { 0 } else { c.len_utf8() };
+            self.copy_range(
+                self.line_buffer.insertion_point()..index + extra,
+                ClipboardMode::Normal,
+            );
+        }
+    }
+
+    fn copy_left_until_char(&mut self, c: char, before_char: bool, current_line: bool) {
+        if let Some(index) = self.line_buffer.find_char_left(c, current_line) {
+            let extra = if before_char { c.len_utf8() } else { 0 };
+            self.copy_range(
+                index + extra..self.line_buffer.insertion_point(),
+                ClipboardMode::Normal,
+            );
+        }
+    }
+
     fn replace_char(&mut self, character: char) {
         self.line_buffer.delete_right_grapheme();
 
@@ -463,9 +1445,61 @@ impl Editor {
     }
 }
 
+/// Commands that are run at every cursor if there are multiple cursors, all
+/// other commands remove the additional cursors
+fn applies_to_all_cursors(command: &EditCommand) -> bool {
+    matches!(
+        command,
+        EditCommand::InsertChar(_)
+            | EditCommand::InsertString(_)
+            | EditCommand::Backspace
+            | EditCommand::Delete
+            | EditCommand::BackspaceWord
+            | EditCommand::DeleteWord
+            | EditCommand::MoveLeft
+            | EditCommand::MoveRight
+            | EditCommand::MoveWordLeft
+            | EditCommand::MoveWordRight
+            | EditCommand::MoveSubwordLeft
+            | EditCommand::MoveSubwordRight
+            | EditCommand::MoveToLineStart
+            | EditCommand::MoveToLineEnd
+    )
+}
+
+/// Side of the cursor a cutting command removes its text from
+fn kill_direction(command: &EditCommand) -> Option<KillDirection> {
+    match command {
+        EditCommand::CutFromStart
+        | EditCommand::CutFromLineStart
+        | EditCommand::CutWordLeft
+        | EditCommand::CutBigWordLeft
+        | EditCommand::CutSubwordLeft
+        | EditCommand::CutLeftUntil(_)
+        | EditCommand::CutLeftBefore(_) => Some(KillDirection::Backward),
+        EditCommand::CutChar
+        | EditCommand::CutCurrentLine
+        | EditCommand::CutToEnd
+        | EditCommand::CutToLineEnd
+        | EditCommand::CutWordRight
+        | EditCommand::CutBigWordRight
+        | EditCommand::CutSubwordRight
+        | EditCommand::CutWordRightToNext
+        | EditCommand::CutBigWordRightToNext
+        | EditCommand::CutRightUntil(_)
+        | EditCommand::CutRightBefore(_) => Some(KillDirection::Forward),
+        _ => None,
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{TextObject, TextObjectScope, TextObjectType};
     use pretty_assertions::assert_eq;
     use rstest::rstest;
 
@@ -644,4 +1678,683 @@ mod test {
         editor.run_edit_command(&EditCommand::Undo);
         assert_eq!(editor.get_buffer(), "This \r\n is a test");
     }
+
+    #[rstest]
+    #[case(UndoCoalescing::Never, &["ab c", "ab ", "ab", "a", ""])]
+    #[case(UndoCoalescing::Word, &["ab", ""])]
+    #[case(UndoCoalescing::Run, &[""])]
+    fn test_undo_coalescing_policy(#[case] policy: UndoCoalescing, #[case] expected: &[&str]) {
+        let mut editor = editor_with("");
+        editor.set_undo_coalescing(policy);
+        for cmd in str_to_edit_commands("ab cd") {
+            editor.run_edit_command(&cmd);
+        }
+        assert_eq!(editor.get_buffer(), "ab cd");
+        for expected in expected {
+            editor.run_edit_command(&EditCommand::Undo);
+            assert_eq!(editor.get_buffer(), *expected);
+        }
+    }
+
+    #[test]
+    fn test_undo_coalescing_run_stops_at_cursor_movement() {
+        let mut editor = editor_with("");
+        editor.set_undo_coalescing(UndoCoalescing::Run);
+        for cmd in str_to_edit_commands("ab cd") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::MoveLeft);
+        editor.run_edit_command(&EditCommand::MoveRight);
+        for cmd in str_to_edit_commands(" ef") {
+            editor.run_edit_command(&cmd);
+        }
+        assert_eq!(editor.get_buffer(), "ab cd ef");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "ab cd");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "");
+    }
+
+    #[test]
+    fn test_undo_tree_keeps_branches() {
+        let mut editor = editor_with("");
+        editor.set_undo_tree(true);
+        for cmd in str_to_edit_commands("abc") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::Undo);
+        for cmd in str_to_edit_commands("xyz") {
+            editor.run_edit_command(&cmd);
+        }
+        assert_eq!(editor.get_buffer(), "xyz");
+        editor.undo_branch_previous();
+        assert_eq!(editor.get_buffer(), "abc");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "");
+        editor.run_edit_command(&EditCommand::Redo);
+        assert_eq!(editor.get_buffer(), "abc");
+        editor.undo_branch_next();
+        assert_eq!(editor.get_buffer(), "xyz");
+    }
+
+    #[test]
+    fn test_undo_restores_cursor_of_edit() {
+        let mut editor = editor_with("abc def");
+        editor.line_buffer.set_insertion_point(3);
+        for cmd in str_to_edit_commands("XY") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::MoveToEnd);
+        assert_eq!(editor.get_buffer(), "abcXY def");
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "abc def");
+        assert_eq!(editor.insertion_point(), 3);
+
+        // The cursor move is recorded with the edit
+        editor.run_edit_command(&EditCommand::Redo);
+        assert_eq!(editor.get_buffer(), "abcXY def");
+        assert_eq!(editor.insertion_point(), 9);
+    }
+
+    #[test]
+    fn test_undo_backspace_restores_cursor() {
+        let mut editor = editor_with("abc def");
+        editor.line_buffer.set_insertion_point(3);
+        for _ in 0..2 {
+            editor.run_edit_command(&EditCommand::Backspace);
+        }
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        assert_eq!(editor.get_buffer(), "a def");
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "abc def");
+        assert_eq!(editor.insertion_point(), 3);
+    }
+
+    #[test]
+    fn test_undo_limit_evicts_oldest_edits() {
+        let mut editor = editor_with("");
+        editor.set_undo_limit(UndoLimit::entries(2));
+        for cmd in str_to_edit_commands("ab cd ef") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "ab cd");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "ab cd");
+    }
+
+    #[test]
+    fn test_undo_earlier_restores_the_cursor() {
+        let mut editor = editor_with("ab");
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        editor.run_edit_command(&EditCommand::InsertChar('x'));
+        editor.run_edit_command(&EditCommand::MoveToEnd);
+
+        editor.run_edit_command(&EditCommand::UndoEarlier(Duration::ZERO));
+        assert_eq!(editor.get_buffer(), "ab");
+        assert_eq!(editor.insertion_point(), 0);
+    }
+
+    #[test]
+    fn test_undo_earlier_follows_time_across_branches() {
+        let mut editor = editor_with("");
+        editor.set_undo_tree(true);
+        for cmd in str_to_edit_commands("abc") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::Undo);
+        for cmd in str_to_edit_commands("xyz") {
+            editor.run_edit_command(&cmd);
+        }
+
+        // The state before "xyz" in time is the undone "abc" branch
+        editor.run_edit_command(&EditCommand::UndoEarlier(Duration::ZERO));
+        assert_eq!(editor.get_buffer(), "abc");
+        editor.run_edit_command(&EditCommand::RedoLater(Duration::ZERO));
+        assert_eq!(editor.get_buffer(), "xyz");
+
+        editor.run_edit_command(&EditCommand::UndoEarlier(Duration::from_secs(3600)));
+        assert_eq!(editor.get_buffer(), "");
+        editor.run_edit_command(&EditCommand::RedoLater(Duration::from_secs(3600)));
+        assert_eq!(editor.get_buffer(), "xyz");
+    }
+
+    #[test]
+    fn test_named_register_keeps_copy_across_cuts() {
+        let mut editor = editor_with("one two");
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        for cmd in [
+            EditCommand::SelectRegister('a'),
+            EditCommand::CopyWordRightToNext,
+            EditCommand::SelectRegister('"'),
+            EditCommand::MoveToEnd,
+            EditCommand::CutWordLeft,
+        ] {
+            editor.run_edit_command(&cmd);
+        }
+        assert_eq!(editor.get_buffer(), "one ");
+
+        editor.run_edit_command(&EditCommand::SelectRegister('a'));
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "one one ");
+
+        editor.run_edit_command(&EditCommand::SelectRegister('"'));
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "one one two");
+    }
+
+    #[test]
+    fn test_copy_does_not_create_undo_point() {
+        let mut editor = editor_with("");
+        for cmd in str_to_edit_commands("abc") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::CopyWordLeft);
+        editor.run_edit_command(&EditCommand::Undo);
+
+        assert_eq!(editor.get_buffer(), "");
+    }
+
+    #[test]
+    fn test_consecutive_kills_are_joined() {
+        let mut editor = editor_with("one two three");
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        editor.run_edit_command(&EditCommand::CutWordRight);
+        assert_eq!(editor.get_buffer(), " ");
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "one ");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "two three ");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "one ");
+    }
+
+    #[test]
+    fn test_kills_are_not_joined_when_disabled() {
+        let mut editor = editor_with("one two");
+        editor.set_join_kills(false);
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+
+        assert_eq!(editor.get_buffer(), "one ");
+    }
+
+    #[test]
+    fn test_yank_pop_needs_previous_paste() {
+        let mut editor = editor_with("one two");
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        editor.run_edit_command(&EditCommand::CutWordRight);
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        editor.run_edit_command(&EditCommand::MoveToEnd);
+        editor.run_edit_command(&EditCommand::YankPop);
+
+        assert_eq!(editor.get_buffer(), "one ");
+    }
+
+    #[test]
+    fn test_cut_and_paste_selection() {
+        let mut editor = editor_with("one two");
+        for cmd in [
+            EditCommand::MoveToStart,
+            EditCommand::StartSelection,
+            EditCommand::MoveWordRightStart,
+        ] {
+            editor.run_edit_command(&cmd);
+        }
+        assert_eq!(editor.get_selection(), Some(0..4));
+
+        editor.run_edit_command(&EditCommand::CutSelection);
+        assert_eq!(editor.get_buffer(), "two");
+        assert_eq!(editor.get_selection(), None);
+
+        editor.run_edit_command(&EditCommand::SelectAll);
+        editor.run_edit_command(&EditCommand::Paste);
+        assert_eq!(editor.get_buffer(), "one ");
+    }
+
+    #[test]
+    fn test_copy_selection_ends_selection() {
+        let mut editor = editor_with("one two");
+        for cmd in [
+            EditCommand::StartSelection,
+            EditCommand::MoveWordLeft,
+            EditCommand::CopySelection,
+            EditCommand::MoveToStart,
+            EditCommand::Paste,
+        ] {
+            editor.run_edit_command(&cmd);
+        }
+
+        assert_eq!(editor.get_buffer(), "twoone two");
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[test]
+    fn test_edit_ends_selection() {
+        let mut editor = editor_with("one");
+        editor.run_edit_command(&EditCommand::StartSelection);
+        editor.run_edit_command(&EditCommand::MoveLeft);
+        editor.run_edit_command(&EditCommand::InsertChar('x'));
+
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[test]
+    fn test_cut_text_object() {
+        let mut editor = editor_with("echo (foo bar)");
+        editor.run_edit_command(&EditCommand::MoveToPosition(8));
+        editor.run_edit_command(&EditCommand::CutTextObject(TextObject::new(
+            TextObjectScope::Inner,
+            TextObjectType::Bracket('('),
+        )));
+
+        assert_eq!(editor.get_buffer(), "echo ()");
+        assert_eq!(editor.insertion_point(), 6);
+        assert_eq!(editor.registers.get().0, "foo bar");
+    }
+
+    #[test]
+    fn test_select_text_object_again_expands() {
+        let mut editor = editor_with("f(a, (b), c)");
+        editor.run_edit_command(&EditCommand::MoveToPosition(6));
+        let inner_bracket = EditCommand::SelectTextObject(TextObject::new(
+            TextObjectScope::Inner,
+            TextObjectType::Bracket('('),
+        ));
+        editor.run_edit_command(&inner_bracket);
+        assert_eq!(editor.get_selection(), Some(6..7));
+        editor.run_edit_command(&inner_bracket);
+        assert_eq!(editor.get_selection(), Some(2..11));
+
+        editor.run_edit_command(&EditCommand::CopySelectionAndMoveToStart);
+        assert_eq!(editor.registers.get().0, "a, (b), c");
+        assert_eq!(editor.insertion_point(), 2);
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[rstest]
+    #[case(EditCommand::SurroundSelection('('), "echo (foo) bar")]
+    #[case(EditCommand::SurroundSelection('"'), "echo \"foo\" bar")]
+    fn test_surround_selection(#[case] command: EditCommand, #[case] expected: &str) {
+        let mut editor = editor_with("echo foo bar");
+        editor.run_edit_command(&EditCommand::MoveToPosition(6));
+        editor.run_edit_command(&EditCommand::SelectTextObject(TextObject::new(
+            TextObjectScope::Inner,
+            TextObjectType::Word,
+        )));
+        assert_eq!(editor.get_selection(), Some(5..8));
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), 5);
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[rstest]
+    #[case(
+        "echo 'a b'",
+        7,
+        EditCommand::ChangeSurrounding('\'', '"'),
+        "echo \"a b\""
+    )]
+    #[case("f((a), b)", 7, EditCommand::ChangeSurrounding(')', ']'), "f[(a), b]")]
+    #[case("f((a), b)", 3, EditCommand::DeleteSurrounding('b'), "f(a, b)")]
+    #[case("echo a", 5, EditCommand::DeleteSurrounding('"'), "echo a")]
+    fn test_change_surrounding(
+        #[case] buffer: &str,
+        #[case] position: usize,
+        #[case] command: EditCommand,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(buffer);
+        editor.run_edit_command(&EditCommand::MoveToPosition(position));
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case("(", "()", 1)]
+    #[case("(a)", "(a)", 3)]
+    #[case("f(\"a", "f(\"a\")", 4)]
+    #[case("it's", "it's", 4)]
+    #[case("''", "''", 2)]
+    #[case("(\u{8}", "", 0)]
+    #[case("a)", "a)", 2)]
+    fn test_bracket_pairs(
+        #[case] input: &str,
+        #[case] expected: &str,
+        #[case] expected_insertion_point: usize,
+    ) {
+        let mut editor = Editor::default();
+        editor.set_bracket_pairs(BracketPairs::default());
+        for c in input.chars() {
+            match c {
+                '\u{8}' => editor.run_edit_command(&EditCommand::Backspace),
+                c => editor.run_edit_command(&EditCommand::InsertChar(c)),
+            }
+        }
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_insertion_point);
+    }
+
+    #[test]
+    fn test_marks_follow_edits() {
+        let mut editor = editor_with("one two three");
+        editor.run_edit_command(&EditCommand::MoveToPosition(4));
+        editor.run_edit_command(&EditCommand::SetMark('a'));
+        editor.run_edit_command(&EditCommand::MoveToPosition(8));
+        editor.run_edit_command(&EditCommand::SetMark('b'));
+
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        editor.run_edit_command(&EditCommand::InsertString("zero ".into()));
+        assert_eq!(editor.marks().get(&'a'), Some(&9));
+        assert_eq!(editor.marks().get(&'b'), Some(&13));
+
+        // A mark in deleted text moves to the start of the deletion
+        editor.run_edit_command(&EditCommand::MoveToPosition(9));
+        editor.run_edit_command(&EditCommand::CutWordRightToNext);
+        editor.run_edit_command(&EditCommand::JumpToMark('b'));
+        assert_eq!(editor.get_buffer(), "zero one three");
+        assert_eq!(editor.insertion_point(), 9);
+        assert_eq!(editor.marks().get(&'a'), Some(&9));
+    }
+
+    #[test]
+    fn test_jump_to_mark_line() {
+        let mut editor = editor_with("one\ntwo");
+        editor.run_edit_command(&EditCommand::MoveToPosition(6));
+        editor.run_edit_command(&EditCommand::SetMark('a'));
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        editor.run_edit_command(&EditCommand::JumpToMarkLine('a'));
+
+        assert_eq!(editor.insertion_point(), 4);
+    }
+
+    #[rstest]
+    #[case("f(a, (b))", 1, Some(8))]
+    #[case("f(a, (b))", 8, Some(1))]
+    #[case("f(a, (b))", 9, Some(1))]
+    #[case("f(a, (b))", 7, Some(5))]
+    #[case("f(a, (b))", 3, None)]
+    #[case("f(a, (b)", 1, None)]
+    fn test_move_to_matching_bracket(
+        #[case] buffer: &str,
+        #[case] position: usize,
+        #[case] expected: Option<usize>,
+    ) {
+        let mut editor = editor_with(buffer);
+        editor.run_edit_command(&EditCommand::MoveToPosition(position));
+        editor.run_edit_command(&EditCommand::MoveToMatchingBracket);
+
+        assert_eq!(editor.insertion_point(), expected.unwrap_or(position));
+    }
+
+    fn editor_with_abbreviations(buffer: &str) -> Editor {
+        let mut editor = editor_with(buffer);
+        editor.set_abbreviations(Box::new(
+            crate::DefaultAbbreviations::new().with_abbreviation("gco", "git checkout"),
+        ));
+        editor
+    }
+
+    #[rstest]
+    #[case("gco", "git checkout ")]
+    #[case("echo gco", "echo git checkout ")]
+    #[case("xgco", "xgco ")]
+    #[case("gco ", "gco  ")]
+    fn test_abbreviation_expands_on_space(#[case] buffer: &str, #[case] expected: &str) {
+        let mut editor = editor_with_abbreviations(buffer);
+        editor.run_edit_command(&EditCommand::InsertChar(' '));
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected.len());
+    }
+
+    #[test]
+    fn test_abbreviation_expansion_is_undone_separately() {
+        let mut editor = editor_with_abbreviations("");
+        for c in "gco".chars() {
+            editor.run_edit_command(&EditCommand::InsertChar(c));
+        }
+        editor.run_edit_command(&EditCommand::InsertChar(' '));
+        editor.run_edit_command(&EditCommand::Undo);
+
+        assert_eq!(editor.get_buffer(), "gco");
+    }
+
+    #[test]
+    fn test_abbreviation_hook() {
+        let mut editor = editor_with_abbreviations("gco");
+        editor.set_abbreviation_hook(Box::new(|abbreviation, expansion| {
+            (abbreviation == "gco").then(|| format!("{} -q", expansion))
+        }));
+        editor.run_edit_command(&EditCommand::ExpandAbbreviation);
+        assert_eq!(editor.get_buffer(), "git checkout -q");
+
+        editor.set_abbreviation_hook(Box::new(|_, _| None));
+        editor.set_buffer("gco".into(), UndoBehavior::CreateUndoPoint);
+        editor.run_edit_command(&EditCommand::ExpandAbbreviation);
+        assert_eq!(editor.get_buffer(), "gco");
+    }
+
+    #[rstest]
+    #[case(WordBoundary::Unicode, "cd /usr/local/", "cd /usr/local")]
+    #[case(WordBoundary::PathComponent, "cd /usr/local/", "cd /usr/")]
+    #[case(WordBoundary::ShellToken, "ls foo.txt|", "ls foo.txt")]
+    #[case(WordBoundary::ShellToken, "ls foo.txt", "ls ")]
+    fn test_cut_word_left_with_word_boundary(
+        #[case] word_boundary: WordBoundary,
+        #[case] buffer: &str,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(buffer);
+        editor.set_word_boundary(word_boundary);
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case(EditCommand::UppercaseWord, "FOO BAR baz")]
+    #[case(EditCommand::LowercaseWord, "foo bar baz")]
+    #[case(EditCommand::CapitalizeWord, "Foo Bar baz")]
+    #[case(EditCommand::ToggleCase, "fOO BAR baz")]
+    fn test_case_transform_of_selection(#[case] command: EditCommand, #[case] expected: &str) {
+        let mut editor = editor_with("Foo bar baz");
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        editor.run_edit_command(&EditCommand::StartSelection);
+        editor.run_edit_command(&EditCommand::MoveToPosition(7));
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), 0);
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[rstest]
+    #[case(EditCommand::MoveLineUp, 6, "two\none\nthree", 2)]
+    #[case(EditCommand::MoveLineUp, 1, "one\ntwo\nthree", 1)]
+    #[case(EditCommand::MoveLineDown, 6, "one\nthree\ntwo", 12)]
+    #[case(EditCommand::MoveLineDown, 9, "one\ntwo\nthree", 9)]
+    #[case(EditCommand::DuplicateLine, 5, "one\ntwo\ntwo\nthree", 9)]
+    #[case(EditCommand::JoinLines, 1, "one two\nthree", 3)]
+    #[case(EditCommand::JoinLines, 9, "one\ntwo\nthree", 9)]
+    #[case(EditCommand::DeleteLine, 5, "one\nthree", 4)]
+    #[case(EditCommand::DeleteLine, 10, "one\ntwo", 4)]
+    fn test_line_commands(
+        #[case] command: EditCommand,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with("one\ntwo\nthree");
+        editor.run_edit_command(&EditCommand::MoveToPosition(position));
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "one\ntwo\nthree");
+    }
+
+    #[rstest]
+    #[case(EditCommand::MoveUp, 6, 2)]
+    #[case(EditCommand::MoveUp, 1, 1)]
+    #[case(EditCommand::MoveDown, 1, 5)]
+    #[case(EditCommand::MoveDown, 9, 9)]
+    fn test_move_up_and_down_stay_in_buffer(
+        #[case] command: EditCommand,
+        #[case] position: usize,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with("one\ntwo\nthree");
+        editor.run_edit_command(&EditCommand::MoveToPosition(position));
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.insertion_point(), expected_position);
+    }
+
+    #[rstest]
+    #[case(EditCommand::MoveLineDown, "three\none\n  two")]
+    #[case(EditCommand::JoinLines, "one two\nthree")]
+    #[case(EditCommand::DeleteLine, "three")]
+    fn test_line_commands_on_selection(#[case] command: EditCommand, #[case] expected: &str) {
+        let mut editor = editor_with("one\n  two\nthree");
+        editor.run_edit_command(&EditCommand::MoveToPosition(1));
+        editor.run_edit_command(&EditCommand::StartSelection);
+        editor.run_edit_command(&EditCommand::MoveToPosition(8));
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case(EditCommand::MoveLineUp, 5, 10, "two\nthree\none", 1..6)]
+    #[case(EditCommand::MoveLineDown, 1, 6, "three\none\ntwo", 7..12)]
+    fn test_moved_lines_stay_selected(
+        #[case] command: EditCommand,
+        #[case] anchor: usize,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_selection: Range<usize>,
+    ) {
+        let mut editor = editor_with("one\ntwo\nthree");
+        editor.run_edit_command(&EditCommand::MoveToPosition(anchor));
+        editor.run_edit_command(&EditCommand::StartSelection);
+        editor.run_edit_command(&EditCommand::MoveToPosition(position));
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.get_selection(), Some(expected_selection));
+    }
+
+    #[test]
+    fn test_cut_subwords() {
+        let mut editor = editor_with("git log --maxCount");
+        editor.run_edit_command(&EditCommand::CutSubwordLeft);
+        assert_eq!(editor.get_buffer(), "git log --max");
+
+        editor.run_edit_command(&EditCommand::MoveToPosition(2));
+        editor.run_edit_command(&EditCommand::CutSubwordRight);
+        assert_eq!(editor.get_buffer(), "gi log --max");
+        assert_eq!(editor.insertion_point(), 2);
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "git log --max");
+    }
+
+    #[test]
+    fn test_word_boundary_survives_undo() {
+        let mut editor = Editor::default();
+        editor.set_word_boundary(WordBoundary::PathComponent);
+        editor.run_edit_command(&EditCommand::InsertString("a/b".into()));
+        editor.run_edit_command(&EditCommand::Undo);
+        editor.run_edit_command(&EditCommand::InsertString("c/d".into()));
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+
+        assert_eq!(editor.get_buffer(), "c/");
+    }
+
+    #[test]
+    fn test_block_selection() {
+        let mut editor = editor_with("abcd\nef\nghij");
+        editor.run_edit_command(&EditCommand::MoveToPosition(1));
+        editor.run_edit_command(&EditCommand::StartBlockSelection);
+        editor.run_edit_command(&EditCommand::MoveToPosition(10));
+        assert_eq!(editor.get_block_selection(), vec![1..3, 6..7, 9..11]);
+        assert_eq!(editor.get_selection(), None);
+
+        editor.run_edit_command(&EditCommand::CopySelection);
+        assert_eq!(editor.insertion_point(), 1);
+        assert!(editor.get_block_selection().is_empty());
+        assert_eq!(editor.registers.get().0, "bc\nf\nhi");
+
+        editor.run_edit_command(&EditCommand::StartBlockSelection);
+        editor.run_edit_command(&EditCommand::MoveToPosition(10));
+        editor.run_edit_command(&EditCommand::CutSelection);
+        assert_eq!(editor.get_buffer(), "ad\ne\ngj");
+        assert_eq!(editor.insertion_point(), 1);
+    }
+
+    #[test]
+    fn test_insert_at_block_start() {
+        let mut editor = editor_with("abc\nd\nefg");
+        editor.run_edit_command(&EditCommand::MoveToPosition(9));
+        editor.run_edit_command(&EditCommand::StartBlockSelection);
+        editor.run_edit_command(&EditCommand::MoveToPosition(1));
+        editor.run_edit_command(&EditCommand::AddCursorsAtBlockStart);
+        // The short line in between is skipped
+        assert_eq!(editor.insertion_point(), 1);
+        assert_eq!(editor.secondary_cursors(), &[7]);
+
+        for cmd in str_to_edit_commands("--") {
+            editor.run_edit_command(&cmd);
+        }
+        assert_eq!(editor.get_buffer(), "a--bc\nd\ne--fg");
+    }
+
+    #[test]
+    fn test_edit_at_multiple_cursors() {
+        let mut editor = editor_with("let a = a + a");
+        editor.run_edit_command(&EditCommand::MoveToPosition(5));
+        for _ in 0..3 {
+            editor.run_edit_command(&EditCommand::AddCursorAtNextOccurrence);
+        }
+        assert_eq!(editor.secondary_cursors(), &[9, 13]);
+
+        editor.run_edit_command(&EditCommand::Backspace);
+        for cmd in str_to_edit_commands("bc") {
+            editor.run_edit_command(&cmd);
+        }
+        assert_eq!(editor.get_buffer(), "let bc = bc + bc");
+        assert_eq!(editor.insertion_point(), 6);
+        assert_eq!(editor.secondary_cursors(), &[11, 16]);
+
+        // The edits at all cursors are undone together
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "let  =  + ");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "let a = a + a");
+        assert!(editor.secondary_cursors().is_empty());
+    }
+
+    #[test]
+    fn test_other_commands_remove_secondary_cursors() {
+        let mut editor = editor_with("ab ab");
+        editor.run_edit_command(&EditCommand::AddCursorAtNextOccurrence);
+        assert_eq!(editor.secondary_cursors(), &[2]);
+
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        assert!(editor.secondary_cursors().is_empty());
+    }
 }
@@ -0,0 +1,135 @@
+use super::ClipboardMode;
+use std::collections::VecDeque;
+
+/// Number of kills remembered by the [`KillRing`]
+pub const KILL_RING_SIZE: usize = 60;
+
+/// Side of the buffer the killed text was taken from, relative to the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KillDirection {
+    /// The text left of the cursor was killed, joined kills are prepended
+    Backward,
+    /// The text right of the cursor was killed, joined kills are appended
+    Forward,
+}
+
+/// Emacs style ring of the most recent kills
+///
+/// The most recent kill is at the front. Yanking always starts at the front,
+/// every yank-pop moves one entry further back, wrapping around at the end.
+pub struct KillRing {
+    entries: VecDeque<(String, ClipboardMode)>,
+    capacity: usize,
+    yank_index: usize,
+}
+
+impl Default for KillRing {
+    fn default() -> Self {
+        KillRing::new(KILL_RING_SIZE)
+    }
+}
+
+impl KillRing {
+    pub fn new(capacity: usize) -> Self {
+        KillRing {
+            entries: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            yank_index: 0,
+        }
+    }
+
+    /// Add a new kill to the front of the ring, dropping the oldest kill if full
+    pub fn push(&mut self, content: &str, mode: ClipboardMode) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_back();
+        }
+        self.entries.push_front((content.to_string(), mode));
+        self.yank_index = 0;
+    }
+
+    /// Join a kill with the most recent one, as done for consecutive kills
+    pub fn join(&mut self, content: &str, mode: ClipboardMode, direction: KillDirection) {
+        match self.entries.front_mut() {
+            Some((previous, _)) => match direction {
+                KillDirection::Forward => previous.push_str(content),
+                KillDirection::Backward => previous.insert_str(0, content),
+            },
+            None => self.push(content, mode),
+        }
+        self.yank_index = 0;
+    }
+
+    /// The most recent kill
+    pub fn head(&self) -> Option<&(String, ClipboardMode)> {
+        self.entries.front()
+    }
+
+    /// Restart yank-pop at the most recent kill
+    pub fn reset_yank(&mut self) {
+        self.yank_index = 0;
+    }
+
+    /// Move to the kill before the one yanked last and return it
+    pub fn yank_pop(&mut self) -> Option<&(String, ClipboardMode)> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        self.yank_index = (self.yank_index + 1) % self.entries.len();
+        self.entries.get(self.yank_index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn content(entry: Option<&(String, ClipboardMode)>) -> Option<&str> {
+        entry.map(|(content, _)| content.as_str())
+    }
+
+    #[test]
+    fn yank_pop_cycles_through_kills() {
+        let mut ring = KillRing::default();
+        for kill in ["one", "two", "three"] {
+            ring.push(kill, ClipboardMode::Normal);
+        }
+
+        assert_eq!(content(ring.head()), Some("three"));
+        assert_eq!(content(ring.yank_pop()), Some("two"));
+        assert_eq!(content(ring.yank_pop()), Some("one"));
+        assert_eq!(content(ring.yank_pop()), Some("three"));
+        ring.yank_pop();
+        ring.reset_yank();
+        assert_eq!(content(ring.yank_pop()), Some("two"));
+    }
+
+    #[test]
+    fn join_appends_or_prepends() {
+        let mut ring = KillRing::default();
+        ring.join("two", ClipboardMode::Normal, KillDirection::Forward);
+        ring.join(" three", ClipboardMode::Normal, KillDirection::Forward);
+        ring.join("one ", ClipboardMode::Normal, KillDirection::Backward);
+
+        assert_eq!(content(ring.head()), Some("one two three"));
+        assert_eq!(content(ring.yank_pop()), Some("one two three"));
+    }
+
+    #[test]
+    fn oldest_kill_is_dropped() {
+        let mut ring = KillRing::new(2);
+        for kill in ["one", "two", "three"] {
+            ring.push(kill, ClipboardMode::Normal);
+        }
+
+        assert_eq!(content(ring.yank_pop()), Some("two"));
+        assert_eq!(content(ring.yank_pop()), Some("three"));
+    }
+
+    #[test]
+    fn yank_pop_on_empty_ring() {
+        let mut ring = KillRing::default();
+
+        assert!(ring.yank_pop().is_none());
+    }
+}
@@ -1,16 +1,28 @@
 use {
+    super::WordBoundary,
     itertools::Itertools,
     std::{convert::From, ops::Range},
     unicode_segmentation::UnicodeSegmentation,
 };
 
 /// In memory representation of the entered line(s) including a cursor position to facilitate cursor based editing.
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct LineBuffer {
     lines: String,
     insertion_point: usize,
+    word_boundary: WordBoundary,
 }
 
+/// Buffers compare by their content and insertion point, the word boundary is
+/// a setting of the editor and doesn't take part in the comparison
+impl PartialEq for LineBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        self.lines == other.lines && self.insertion_point == other.insertion_point
+    }
+}
+
+impl Eq for LineBuffer {}
+
 impl From<&str> for LineBuffer {
     fn from(input: &str) -> Self {
         let mut line_buffer = LineBuffer::new();
@@ -25,6 +37,16 @@ impl LineBuffer {
         Self::default()
     }
 
+    /// Get the policy deciding what counts as a word
+    pub fn word_boundary(&self) -> &WordBoundary {
+        &self.word_boundary
+    }
+
+    /// Set the policy deciding what counts as a word for the word motions and edits
+    pub fn set_word_boundary(&mut self, word_boundary: WordBoundary) {
+        self.word_boundary = word_boundary;
+    }
+
     /// Check to see if the line buffer is empty
     pub fn is_empty(&self) -> bool {
         self.lines.is_empty()
@@ -170,9 +192,10 @@ impl LineBuffer {
 
     /// Cursor position *behind* the next word to the right
     pub fn word_right_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .split_word_bound_indices()
-            .find(|(_, word)| !is_whitespace_str(word))
+        self.word_boundary
+            .split(&self.lines[self.insertion_point..])
+            .into_iter()
+            .find(|(_, word)| !self.word_boundary.is_separator(word))
             .map(|(i, word)| self.insertion_point + i + word.len())
             .unwrap_or_else(|| self.lines.len())
     }
@@ -193,13 +216,16 @@ impl LineBuffer {
 
     /// Cursor position *at end of* the next word to the right
     pub fn word_right_end_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .split_word_bound_indices()
+        self.word_boundary
+            .split(&self.lines[self.insertion_point..])
+            .into_iter()
             .find_map(|(i, word)| {
                 word.grapheme_indices(true)
                     .next_back()
                     .map(|x| self.insertion_point + x.0 + i)
-                    .filter(|x| !is_whitespace_str(word) && *x != self.insertion_point)
+                    .filter(|x| {
+                        !self.word_boundary.is_separator(word) && *x != self.insertion_point
+                    })
             })
             .unwrap_or_else(|| {
                 self.lines
@@ -237,9 +263,10 @@ impl LineBuffer {
 
     /// Cursor position *in front of* the next word to the right
     pub fn word_right_start_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .split_word_bound_indices()
-            .find(|(i, word)| *i != 0 && !is_whitespace_str(word))
+        self.word_boundary
+            .split(&self.lines[self.insertion_point..])
+            .into_iter()
+            .find(|(i, word)| *i != 0 && !self.word_boundary.is_separator(word))
             .map(|(i, _)| self.insertion_point + i)
             .unwrap_or_else(|| self.lines.len())
     }
@@ -260,9 +287,10 @@ impl LineBuffer {
 
     /// Cursor position *in front of* the next word to the left
     pub fn word_left_index(&self) -> usize {
-        self.lines[..self.insertion_point]
-            .split_word_bound_indices()
-            .filter(|(_, word)| !is_whitespace_str(word))
+        self.word_boundary
+            .split(&self.lines[..self.insertion_point])
+            .into_iter()
+            .filter(|(_, word)| !self.word_boundary.is_separator(word))
             .last()
             .map(|(i, _)| i)
             .unwrap_or(0)
@@ -283,6 +311,57 @@ impl LineBuffer {
             .unwrap_or(0)
     }
 
+    /// Ranges of the subwords in the buffer. Subwords are runs of alphanumeric
+    /// characters, split at camelCase humps and before the last capital of an
+    /// acronym followed by lowercase letters, e.g. `HTTPServer` into `HTTP` and `Server`
+    fn subword_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges = vec![];
+        let mut start = None;
+        let mut chars = self.lines.char_indices().peekable();
+        let mut previous: Option<char> = None;
+        while let Some((index, c)) = chars.next() {
+            let next = chars.peek().map(|(_, next)| *next);
+            if !c.is_alphanumeric() {
+                if let Some(start) = start.take() {
+                    ranges.push(start..index);
+                }
+            } else if let (Some(subword_start), Some(previous)) = (start, previous) {
+                let hump = !previous.is_uppercase() && c.is_uppercase();
+                let acronym_end = previous.is_uppercase()
+                    && c.is_uppercase()
+                    && matches!(next, Some(next) if next.is_lowercase());
+                if hump || acronym_end {
+                    ranges.push(subword_start..index);
+                    start = Some(index);
+                }
+            } else {
+                start = Some(index);
+            }
+            previous = Some(c);
+        }
+        if let Some(start) = start {
+            ranges.push(start..self.lines.len());
+        }
+        ranges
+    }
+
+    /// Cursor position *behind* the next subword to the right
+    pub fn subword_right_index(&self) -> usize {
+        self.subword_ranges()
+            .into_iter()
+            .find(|range| range.end > self.insertion_point)
+            .map_or(self.lines.len(), |range| range.end)
+    }
+
+    /// Cursor position *in front of* the next subword to the left
+    pub fn subword_left_index(&self) -> usize {
+        self.subword_ranges()
+            .into_iter()
+            .rev()
+            .find(|range| range.start < self.insertion_point)
+            .map_or(0, |range| range.start)
+    }
+
     /// Cursor position on the next whitespace
     pub fn next_whitespace(&self) -> usize {
         self.lines[self.insertion_point..]
@@ -292,6 +371,16 @@ impl LineBuffer {
             .unwrap_or_else(|| self.lines.len())
     }
 
+    /// Move cursor position *behind* the next subword to the right
+    pub fn move_subword_right(&mut self) {
+        self.insertion_point = self.subword_right_index();
+    }
+
+    /// Move cursor position *in front of* the next subword to the left
+    pub fn move_subword_left(&mut self) {
+        self.insertion_point = self.subword_left_index();
+    }
+
     /// Move cursor position *behind* the next unicode grapheme to the right
     pub fn move_right(&mut self) {
         self.insertion_point = self.grapheme_right_index();
@@ -432,9 +521,11 @@ impl LineBuffer {
     /// Gets the range of the word the current edit position is pointing to
     pub fn current_word_range(&self) -> Range<usize> {
         let right_index = self.word_right_index();
-        let left_index = self.lines[..right_index]
-            .split_word_bound_indices()
-            .filter(|(_, word)| !is_whitespace_str(word))
+        let left_index = self
+            .word_boundary
+            .split(&self.lines[..right_index])
+            .into_iter()
+            .filter(|(_, word)| !self.word_boundary.is_separator(word))
             .last()
             .map(|(i, _)| i)
             .unwrap_or(0);
@@ -442,6 +533,48 @@ impl LineBuffer {
         left_index..right_index
     }
 
+    /// Range of the word (alphanumeric characters and `_`) the insertion point is
+    /// in or directly behind. Empty if there is no such word
+    pub fn word_around_insertion_point(&self) -> Range<usize> {
+        let start = self.lines[..self.insertion_point]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_word_char(*c))
+            .last()
+            .map_or(self.insertion_point, |(index, _)| index);
+        let end = self.lines[self.insertion_point..]
+            .char_indices()
+            .find(|(_, c)| !is_word_char(*c))
+            .map_or(self.lines.len(), |(index, _)| self.insertion_point + index);
+
+        start..end
+    }
+
+    /// Start of the next occurrence of `word` that is not part of a longer word,
+    /// searching from `offset` and wrapping around at the end of the buffer
+    pub fn find_word_occurrence(&self, word: &str, offset: usize) -> Option<usize> {
+        if word.is_empty() {
+            return None;
+        }
+        let occurrences: Vec<usize> = self
+            .lines
+            .match_indices(word)
+            .map(|(index, _)| index)
+            .filter(|index| {
+                let before = self.lines[..*index].chars().next_back();
+                let after = self.lines[index + word.len()..].chars().next();
+                !matches!(before, Some(c) if is_word_char(c))
+                    && !matches!(after, Some(c) if is_word_char(c))
+            })
+            .collect();
+
+        occurrences
+            .iter()
+            .find(|index| **index >= offset)
+            .or_else(|| occurrences.first())
+            .copied()
+    }
+
     /// Range over the current line
     ///
     /// Starts on the first non-newline character and is an exclusive range
@@ -474,6 +607,15 @@ impl LineBuffer {
         self.move_word_right();
     }
 
+    /// Capitalizes the current word from the insertion point on and moves the
+    /// insertion point behind it
+    pub fn capitalize_word(&mut self) {
+        let change_range = self.insertion_point..self.word_right_index();
+        let capitalized = capitalize(&self.get_buffer()[change_range.clone()]);
+        self.replace_range(change_range.clone(), &capitalized);
+        self.insertion_point = change_range.start + capitalized.len();
+    }
+
     /// Switches the ASCII case of the current char
     pub fn switchcase_char(&mut self) {
         let insertion_offset = self.insertion_point();
@@ -563,6 +705,79 @@ impl LineBuffer {
         }
     }
 
+    /// Swaps the word before the insertion point with the word after it, like
+    /// `transpose-words` in Emacs. Inside a word that word is swapped with the
+    /// next one, at the end of the buffer the last two words are swapped. The
+    /// insertion point moves behind the second word. Punctuation is skipped.
+    pub fn transpose_words(&mut self) {
+        let words: Vec<Range<usize>> = self
+            .word_boundary
+            .split(&self.lines)
+            .into_iter()
+            .filter(|(_, word)| word.chars().any(char::is_alphanumeric))
+            .map(|(index, word)| index..index + word.len())
+            .collect();
+        if words.len() < 2 {
+            return;
+        }
+        let next = words
+            .iter()
+            .position(|word| word.end > self.insertion_point)
+            .unwrap_or(words.len());
+        let inside_word =
+            matches!(words.get(next), Some(word) if word.start < self.insertion_point);
+        let first = if inside_word {
+            next
+        } else {
+            next.saturating_sub(1)
+        };
+        let first = first.min(words.len() - 2);
+        let (first, second) = (words[first].clone(), words[first + 1].clone());
+
+        let swapped = format!(
+            "{}{}{}",
+            &self.lines[second.clone()],
+            &self.lines[first.end..second.start],
+            &self.lines[first.clone()]
+        );
+        self.replace_range(first.start..second.end, &swapped);
+        self.insertion_point = second.end;
+    }
+
+    /// Swaps the current line with the line above, or with the line below on the
+    /// first line, like `transpose-lines` in Emacs. The insertion point moves to
+    /// the start of the line following both.
+    pub fn transpose_lines(&mut self) {
+        let current = self.current_line_range();
+        let current =
+            current.start..current.start + self.lines[current].trim_end_matches('\n').len();
+        let (first, second) = if current.start > 0 {
+            let previous_start = self.lines[..current.start - 1]
+                .rfind('\n')
+                .map_or(0, |offset| offset + 1);
+            (previous_start..current.start - 1, current)
+        } else if current.end < self.lines.len() {
+            let next_end = self.lines[current.end + 1..]
+                .find('\n')
+                .map_or(self.lines.len(), |offset| current.end + 1 + offset);
+            (current.clone(), current.end + 1..next_end)
+        } else {
+            return;
+        };
+
+        let swapped = format!(
+            "{}\n{}",
+            &self.lines[second.clone()],
+            &self.lines[first.clone()]
+        );
+        self.replace_range(first.start..second.end, &swapped);
+        self.insertion_point = if second.end < self.lines.len() {
+            second.end + 1
+        } else {
+            second.end
+        };
+    }
+
     /// Swaps current grapheme with grapheme on right
     pub fn swap_graphemes(&mut self) {
         let initial_offset = self.insertion_point();
@@ -747,6 +962,41 @@ fn is_whitespace_str(s: &str) -> bool {
     s.chars().all(char::is_whitespace)
 }
 
+/// Upper case the first letter of every word and lower case all others
+pub(crate) fn capitalize(text: &str) -> String {
+    let mut in_word = false;
+    let mut capitalized = String::with_capacity(text.len());
+    for c in text.chars() {
+        if !c.is_alphanumeric() {
+            capitalized.push(c);
+            in_word = false;
+        } else if in_word {
+            capitalized.extend(c.to_lowercase());
+        } else {
+            capitalized.extend(c.to_uppercase());
+            in_word = true;
+        }
+    }
+    capitalized
+}
+
+/// Switch the case of every character
+pub(crate) fn toggle_case(text: &str) -> String {
+    let mut toggled = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_uppercase() {
+            toggled.extend(c.to_lowercase());
+        } else {
+            toggled.extend(c.to_uppercase());
+        }
+    }
+    toggled
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -777,6 +1027,14 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    #[test]
+    fn test_word_boundary_is_ignored_in_comparison() {
+        let mut line_buffer = buffer_with("cd /usr");
+        line_buffer.set_word_boundary(WordBoundary::PathComponent);
+
+        assert_eq!(line_buffer, buffer_with("cd /usr"));
+    }
+
     #[test]
     fn insert_str_updates_insertion_point_point_correctly() {
         let mut line_buffer = LineBuffer::new();
@@ -1569,4 +1827,146 @@ mod test {
 
         assert_eq!(index, expected);
     }
+
+    #[rstest]
+    #[case("foo bar foo", "foo", 1, Some(8))]
+    #[case("foo bar foo", "foo", 9, Some(0))]
+    #[case("foo foobar foo", "foo", 1, Some(11))]
+    #[case("foo_bar foo", "foo", 1, Some(8))]
+    #[case("foo", "bar", 0, None)]
+    #[case("foo", "", 0, None)]
+    fn test_find_word_occurrence(
+        #[case] input: &str,
+        #[case] word: &str,
+        #[case] offset: usize,
+        #[case] expected: Option<usize>,
+    ) {
+        let line_buffer = buffer_with(input);
+
+        assert_eq!(line_buffer.find_word_occurrence(word, offset), expected);
+    }
+
+    #[rstest]
+    #[case("foo bar", 0, 0..3)]
+    #[case("foo bar", 3, 0..3)]
+    #[case("foo_1 bar", 2, 0..5)]
+    #[case("foo bar", 5, 4..7)]
+    #[case("foo  bar", 4, 4..4)]
+    fn test_word_around_insertion_point(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: Range<usize>,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.word_around_insertion_point(), expected);
+    }
+
+    #[rstest]
+    #[case("fooBarBaz", 0, 3)]
+    #[case("fooBarBaz", 4, 6)]
+    #[case("foo_bar", 3, 7)]
+    #[case("HTTPServer", 0, 4)]
+    #[case("--max-count", 0, 5)]
+    #[case("foo2Bar", 0, 4)]
+    #[case("foo ", 3, 4)]
+    fn test_subword_right_index(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.subword_right_index(), expected);
+    }
+
+    #[rstest]
+    #[case("fooBarBaz", 9, 6)]
+    #[case("fooBarBaz", 6, 3)]
+    #[case("foo_bar", 4, 0)]
+    #[case("HTTPServer", 10, 4)]
+    #[case("--max-count", 6, 2)]
+    #[case("foo", 0, 0)]
+    fn test_subword_left_index(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.subword_left_index(), expected);
+    }
+
+    #[rstest]
+    #[case("hello WORLD", 0, "Hello WORLD", 5)]
+    #[case("hello WORLD", 5, "hello World", 11)]
+    #[case("heLLO", 2, "heLlo", 5)]
+    fn capitalize_word_works(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+        line_buffer.capitalize_word();
+
+        assert_eq!(line_buffer.get_buffer(), expected);
+        assert_eq!(line_buffer.insertion_point(), expected_position);
+    }
+
+    #[rstest]
+    #[case(capitalize, "foo-BAR baz2x", "Foo-Bar Baz2x")]
+    #[case(toggle_case, "Foo bär", "fOO BÄR")]
+    fn case_transforms(
+        #[case] transform: fn(&str) -> String,
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(transform(input), expected);
+    }
+
+    #[rstest]
+    #[case("one two three", 5, "one three two", 13)]
+    #[case("one two three", 4, "two one three", 7)]
+    #[case("one two three", 3, "two one three", 7)]
+    #[case("one two three", 13, "one three two", 13)]
+    #[case("one two three", 0, "two one three", 7)]
+    #[case("one (two)", 4, "two (one)", 8)]
+    #[case("one", 1, "one", 1)]
+    fn transpose_words_works(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+        line_buffer.transpose_words();
+
+        assert_eq!(line_buffer.get_buffer(), expected);
+        assert_eq!(line_buffer.insertion_point(), expected_position);
+    }
+
+    #[rstest]
+    #[case("one\ntwo\nthree", 5, "two\none\nthree", 8)]
+    #[case("one\ntwo\nthree", 10, "one\nthree\ntwo", 13)]
+    #[case("one\ntwo\nthree", 1, "two\none\nthree", 8)]
+    #[case("one", 1, "one", 1)]
+    fn transpose_lines_works(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+        line_buffer.transpose_lines();
+
+        assert_eq!(line_buffer.get_buffer(), expected);
+        assert_eq!(line_buffer.insertion_point(), expected_position);
+    }
 }
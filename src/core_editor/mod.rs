@@ -1,8 +1,24 @@
+mod bracket_pairs;
 mod clip_buffer;
 mod edit_stack;
 mod editor;
+mod kill_ring;
 mod line_buffer;
+mod registers;
+mod text_object;
+mod undo_tree;
+mod word_boundary;
 
-pub(crate) use clip_buffer::{get_default_clipboard, Clipboard, ClipboardMode};
+pub use bracket_pairs::BracketPairs;
+pub(crate) use clip_buffer::get_default_clipboard;
+#[cfg(feature = "system_clipboard")]
+pub use clip_buffer::SystemClipboard;
+pub use clip_buffer::{Clipboard, ClipboardMode, LocalClipboard};
+pub use edit_stack::UndoLimit;
 pub use editor::Editor;
 pub use line_buffer::LineBuffer;
+pub(crate) use line_buffer::{capitalize, toggle_case};
+pub(crate) use registers::RegisterStore;
+pub(crate) use text_object::{delimiter_pair, matching_bracket, surrounding_object};
+pub use text_object::{TextObject, TextObjectScope, TextObjectType};
+pub use word_boundary::WordBoundary;
@@ -0,0 +1,270 @@
+use super::{
+    get_default_clipboard,
+    kill_ring::{KillDirection, KillRing},
+    Clipboard, ClipboardMode,
+};
+use std::collections::HashMap;
+
+/// Content of a single register together with the way it should be pasted
+type Register = (String, ClipboardMode);
+
+/// Vi style registers backing cut, copy and paste
+///
+/// - The unnamed register `"` always holds the most recently cut or copied
+///   text. It is backed by the [`Clipboard`] so it is shared with the system
+///   clipboard if the `system_clipboard` feature is enabled.
+/// - The named registers `a` to `z` are only written when selected. Selecting
+///   their uppercase variant appends to them instead of replacing the content.
+/// - The numbered register `0` holds the most recently copied text, `1` to `9`
+///   hold the last nine cuts, with the most recent in `1`.
+/// - Cuts and copies that don't go to a named register are also added to the
+///   [`KillRing`], which can be cycled through after a paste.
+///
+/// A register is selected with [`RegisterStore::select`] and stays selected for
+/// all following cuts, copies and pastes until another register is selected.
+/// Consecutive writes to the same selected register are appended, so repeated
+/// cuts of a single command end up together in the register.
+pub(crate) struct RegisterStore {
+    unnamed: Box<dyn Clipboard>,
+    named: HashMap<char, Register>,
+    numbered: [Register; 10],
+    kill_ring: KillRing,
+    join_kill: Option<KillDirection>,
+    selected: Option<char>,
+    selected_written: bool,
+}
+
+impl Default for RegisterStore {
+    fn default() -> Self {
+        RegisterStore::new(Box::new(get_default_clipboard()))
+    }
+}
+
+impl RegisterStore {
+    pub fn new(unnamed: Box<dyn Clipboard>) -> Self {
+        RegisterStore {
+            unnamed,
+            named: HashMap::new(),
+            numbered: Default::default(),
+            kill_ring: KillRing::default(),
+            join_kill: None,
+            selected: None,
+            selected_written: false,
+        }
+    }
+
+    /// Replace the clipboard backing the unnamed register
+    pub fn set_clipboard(&mut self, clipboard: Box<dyn Clipboard>) {
+        self.unnamed = clipboard;
+    }
+
+    /// The escape sequence the clipboard wants written to the terminal
+    pub fn take_terminal_sequence(&mut self) -> Option<String> {
+        self.unnamed.take_terminal_sequence()
+    }
+
+    /// Check if `register` is the name of a register that can be selected
+    pub fn is_register(register: char) -> bool {
+        register == '"' || register.is_ascii_alphanumeric()
+    }
+
+    /// Select the register used by the following cuts, copies and pastes.
+    ///
+    /// Selecting `"` returns to the default behavior. Unknown names are ignored.
+    pub fn select(&mut self, register: char) {
+        if Self::is_register(register) {
+            self.selected = (register != '"').then_some(register);
+            self.selected_written = false;
+        }
+    }
+
+    /// Join the next cut with the previous one instead of adding a new entry,
+    /// as done by consecutive kills in Emacs
+    pub fn join_next_cut(&mut self, direction: Option<KillDirection>) {
+        self.join_kill = direction;
+    }
+
+    /// Store text removed from the buffer
+    pub fn cut(&mut self, content: &str, mode: ClipboardMode) {
+        self.store(content, mode, true);
+    }
+
+    /// Store text copied from the buffer without removing it
+    pub fn copy(&mut self, content: &str, mode: ClipboardMode) {
+        self.store(content, mode, false);
+    }
+
+    /// Get the content of the selected register
+    pub fn get(&mut self) -> Register {
+        match self.selected {
+            Some(register @ '0'..='9') => {
+                let index = register.to_digit(10).unwrap_or_default() as usize;
+                self.numbered[index].clone()
+            }
+            Some(register) => self
+                .named
+                .get(&register.to_ascii_lowercase())
+                .cloned()
+                .unwrap_or_default(),
+            None => {
+                self.kill_ring.reset_yank();
+                self.unnamed.get()
+            }
+        }
+    }
+
+    /// Get the kill before the one pasted last from the kill ring
+    pub fn yank_pop(&mut self) -> Option<Register> {
+        self.kill_ring.yank_pop().cloned()
+    }
+
+    fn store(&mut self, content: &str, mode: ClipboardMode, is_cut: bool) {
+        let register = match self.selected {
+            Some(register) if register.is_ascii_alphabetic() => {
+                let append = self.selected_written || register.is_ascii_uppercase();
+                let entry = self.named.entry(register.to_ascii_lowercase()).or_default();
+                if append {
+                    entry.0.push_str(content);
+                } else {
+                    *entry = (content.to_string(), mode);
+                }
+                entry.clone()
+            }
+            _ => {
+                let join = self.join_kill.filter(|_| is_cut);
+                let joins_previous = join.is_some() || self.selected_written;
+                match join {
+                    Some(direction) => self.kill_ring.join(content, mode, direction),
+                    None if self.selected_written => {
+                        self.kill_ring.join(content, mode, KillDirection::Forward);
+                    }
+                    None => self.kill_ring.push(content, mode),
+                }
+                let register = self.kill_ring.head().cloned().unwrap_or_default();
+
+                if !is_cut {
+                    self.numbered[0] = register.clone();
+                } else if joins_previous {
+                    self.numbered[1] = register.clone();
+                } else {
+                    self.numbered[1..].rotate_right(1);
+                    self.numbered[1] = register.clone();
+                }
+                register
+            }
+        };
+        // Only track the writes while a register is explicitly selected, the
+        // unnamed register is otherwise replaced by every cut or copy
+        self.selected_written = self.selected.is_some();
+        self.join_kill = None;
+        self.unnamed.set(&register.0, register.1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core_editor::clip_buffer::LocalClipboard;
+    use pretty_assertions::assert_eq;
+
+    fn register_store() -> RegisterStore {
+        RegisterStore::new(Box::new(LocalClipboard::new()))
+    }
+
+    fn content(store: &mut RegisterStore, register: char) -> String {
+        store.select(register);
+        let (content, _) = store.get();
+        store.select('"');
+        content
+    }
+
+    #[test]
+    fn unnamed_register_holds_last_write() {
+        let mut store = register_store();
+        store.cut("one", ClipboardMode::Normal);
+        store.copy("two", ClipboardMode::Normal);
+
+        assert_eq!(content(&mut store, '"'), "two");
+        assert_eq!(content(&mut store, '0'), "two");
+        assert_eq!(content(&mut store, '1'), "one");
+    }
+
+    #[test]
+    fn cuts_shift_numbered_registers() {
+        let mut store = register_store();
+        for cut in ["one", "two", "three"] {
+            store.cut(cut, ClipboardMode::Normal);
+        }
+
+        assert_eq!(content(&mut store, '1'), "three");
+        assert_eq!(content(&mut store, '2'), "two");
+        assert_eq!(content(&mut store, '3'), "one");
+        assert_eq!(content(&mut store, '0'), "");
+    }
+
+    #[test]
+    fn named_register_only_written_when_selected() {
+        let mut store = register_store();
+        store.select('a');
+        store.copy("one", ClipboardMode::Normal);
+        store.select('"');
+        store.copy("two", ClipboardMode::Normal);
+
+        assert_eq!(content(&mut store, 'a'), "one");
+        assert_eq!(content(&mut store, '"'), "two");
+        // Writing a named register does not touch the numbered ones
+        assert_eq!(content(&mut store, '0'), "two");
+    }
+
+    #[test]
+    fn uppercase_register_appends() {
+        let mut store = register_store();
+        store.select('a');
+        store.cut("one", ClipboardMode::Normal);
+        store.select('A');
+        store.cut(" two", ClipboardMode::Normal);
+        store.select('"');
+
+        assert_eq!(content(&mut store, 'a'), "one two");
+        assert_eq!(content(&mut store, '"'), "one two");
+    }
+
+    #[test]
+    fn repeated_writes_to_selected_register_append() {
+        let mut store = register_store();
+        store.select('b');
+        store.cut("one ", ClipboardMode::Normal);
+        store.cut("two", ClipboardMode::Normal);
+        store.select('b');
+        store.cut("three", ClipboardMode::Normal);
+
+        assert_eq!(content(&mut store, 'b'), "three");
+    }
+
+    #[test]
+    fn joined_cuts_replace_last_cut() {
+        let mut store = register_store();
+        store.cut("one", ClipboardMode::Normal);
+        store.cut("three", ClipboardMode::Normal);
+        store.join_next_cut(Some(KillDirection::Backward));
+        store.cut("two ", ClipboardMode::Normal);
+
+        assert_eq!(content(&mut store, '"'), "two three");
+        assert_eq!(content(&mut store, '1'), "two three");
+        assert_eq!(content(&mut store, '2'), "one");
+        assert_eq!(
+            store.yank_pop().map(|(content, _)| content),
+            Some("one".into())
+        );
+    }
+
+    #[test]
+    fn unknown_register_is_ignored() {
+        let mut store = register_store();
+        store.select('a');
+        store.select('!');
+        store.copy("one", ClipboardMode::Normal);
+
+        assert_eq!(content(&mut store, 'a'), "one");
+    }
+}
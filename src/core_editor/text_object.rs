@@ -0,0 +1,466 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Extent of a [`TextObject`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextObjectScope {
+    /// Only the object itself, without the delimiters (vi `i`)
+    #[default]
+    Inner,
+    /// The object including its delimiters or the whitespace around it (vi `a`)
+    Around,
+}
+
+/// Kind of text a [`TextObject`] selects
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextObjectType {
+    /// A run of word characters, of other non-blank characters or of whitespace
+    #[default]
+    Word,
+    /// A run of non-blank characters or of whitespace
+    BigWord,
+    /// Text between two of the given quote characters on the current line
+    Quote(char),
+    /// Text between the given opening bracket and its matching closing bracket
+    Bracket(char),
+    /// Consecutive non-blank lines
+    Paragraph,
+}
+
+/// Region of text around the insertion point like a word, a quoted string or
+/// the content of a pair of brackets, as selected by the vi text objects
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextObject {
+    /// Whether the delimiters or surrounding whitespace are included
+    pub scope: TextObjectScope,
+    /// The kind of text selected
+    pub object_type: TextObjectType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Newline,
+    Whitespace,
+    Word,
+    Punctuation,
+}
+
+impl TextObject {
+    /// Create a text object
+    pub fn new(scope: TextObjectScope, object_type: TextObjectType) -> Self {
+        TextObject { scope, object_type }
+    }
+
+    /// Range of the object in `buffer` around `offset`, `None` if there is
+    /// no such object at the offset
+    pub fn range(&self, buffer: &str, offset: usize) -> Option<Range<usize>> {
+        buffer.get(offset..)?;
+        let around = self.scope == TextObjectScope::Around;
+        match self.object_type {
+            TextObjectType::Word => word_range(buffer, offset, false, around),
+            TextObjectType::BigWord => word_range(buffer, offset, true, around),
+            TextObjectType::Quote(quote) => quote_range(buffer, offset, quote, around),
+            TextObjectType::Bracket(open) => bracket_range(buffer, offset, open, around),
+            TextObjectType::Paragraph => paragraph_range(buffer, offset, around),
+        }
+    }
+
+    /// Range of the next bigger object containing `selection`, like selecting
+    /// the object again in vi. Words and paragraphs extend to the following
+    /// object, brackets to the enclosing pair. `None` if there is no bigger
+    /// object, quotes don't nest.
+    pub(crate) fn expand(&self, buffer: &str, selection: Range<usize>) -> Option<Range<usize>> {
+        let bigger = |range: &Range<usize>| {
+            range.start <= selection.start && range.end >= selection.end && *range != selection
+        };
+        match self.object_type {
+            TextObjectType::Word | TextObjectType::BigWord | TextObjectType::Paragraph => {
+                if selection.end >= buffer.len() {
+                    return None;
+                }
+                let next = self.range(buffer, selection.end)?;
+                Some(selection.start..next.end).filter(bigger)
+            }
+            TextObjectType::Bracket(_) => buffer
+                .get(..selection.start)?
+                .char_indices()
+                .rev()
+                .find_map(|(offset, _)| self.range(buffer, offset).filter(bigger)),
+            TextObjectType::Quote(_) => None,
+        }
+    }
+}
+
+/// Closing bracket belonging to `open`
+pub(crate) fn closing_bracket(open: char) -> Option<char> {
+    match open {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        '<' => Some('>'),
+        _ => None,
+    }
+}
+
+/// Opening and closing delimiter for `delimiter`, which may be either side of
+/// a bracket pair or one of the vi aliases `b` and `B`. Other characters delimit
+/// both sides themselves
+pub(crate) fn delimiter_pair(delimiter: char) -> (char, char) {
+    match delimiter {
+        '(' | ')' | 'b' => ('(', ')'),
+        '[' | ']' => ('[', ']'),
+        '{' | '}' | 'B' => ('{', '}'),
+        '<' | '>' => ('<', '>'),
+        c => (c, c),
+    }
+}
+
+/// Position of the bracket matching the one starting at `offset`
+///
+/// Only `()`, `[]` and `{}` are matched, like the `%` motion of vi. Returns
+/// `None` if there is no bracket at `offset` or it is unbalanced.
+pub(crate) fn matching_bracket(buffer: &str, offset: usize) -> Option<usize> {
+    let bracket = buffer.get(offset..)?.chars().next()?;
+    let (open, close) = match bracket {
+        '(' | ')' | '[' | ']' | '{' | '}' => delimiter_pair(bracket),
+        _ => return None,
+    };
+    let mut depth = 0;
+    let mut is_match = |c: char, towards: char, away: char| {
+        if c == away {
+            depth += 1;
+        } else if c == towards {
+            depth -= 1;
+            return depth == 0;
+        }
+        false
+    };
+    if bracket == open {
+        buffer[offset..]
+            .char_indices()
+            .find(|(_, c)| is_match(*c, close, open))
+            .map(|(index, _)| offset + index)
+    } else {
+        buffer[..offset + close.len_utf8()]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| is_match(*c, open, close))
+            .map(|(index, _)| index)
+    }
+}
+
+/// Text object of the innermost pair of `delimiter` including the delimiters
+pub(crate) fn surrounding_object(delimiter: char) -> TextObject {
+    let (open, close) = delimiter_pair(delimiter);
+    let object_type = if open == close {
+        TextObjectType::Quote(open)
+    } else {
+        TextObjectType::Bracket(open)
+    };
+    TextObject::new(TextObjectScope::Around, object_type)
+}
+
+fn char_class(c: char, big_word: bool) -> CharClass {
+    if c == '\n' {
+        CharClass::Newline
+    } else if c.is_whitespace() {
+        CharClass::Whitespace
+    } else if big_word || c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+fn word_range(buffer: &str, offset: usize, big_word: bool, around: bool) -> Option<Range<usize>> {
+    // At the end of the buffer the word to the left is used
+    let offset = if offset == buffer.len() {
+        buffer.char_indices().last()?.0
+    } else {
+        offset
+    };
+    let class = |c: char| char_class(c, big_word);
+    let run_start = |end: usize, run_class: CharClass| {
+        buffer[..end]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| class(*c) == run_class)
+            .last()
+            .map_or(end, |(index, _)| index)
+    };
+    let run_end = |start: usize, run_class: CharClass| {
+        buffer[start..]
+            .char_indices()
+            .find(|(_, c)| class(*c) != run_class)
+            .map_or(buffer.len(), |(index, _)| start + index)
+    };
+
+    let current = class(buffer[offset..].chars().next()?);
+    let mut start = run_start(offset, current);
+    let mut end = run_end(offset, current);
+    if around {
+        match current {
+            CharClass::Newline => {}
+            CharClass::Whitespace => {
+                // Blanks are selected together with the following word
+                match buffer[end..].chars().next().map(class) {
+                    Some(next) if next != CharClass::Newline => end = run_end(end, next),
+                    _ => {}
+                }
+            }
+            CharClass::Word | CharClass::Punctuation => {
+                // Trailing blanks are preferred, otherwise the leading ones are taken
+                let trailing = run_end(end, CharClass::Whitespace);
+                if trailing > end {
+                    end = trailing;
+                } else {
+                    start = run_start(start, CharClass::Whitespace);
+                }
+            }
+        }
+    }
+    Some(start..end)
+}
+
+fn quote_range(buffer: &str, offset: usize, quote: char, around: bool) -> Option<Range<usize>> {
+    let line_start = buffer[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = buffer[offset..]
+        .find('\n')
+        .map_or(buffer.len(), |index| offset + index);
+
+    // Quotes escaped with a backslash don't delimit the string
+    let mut escaped = false;
+    let quotes = buffer[line_start..line_end]
+        .char_indices()
+        .filter_map(|(index, c)| {
+            let is_quote = c == quote && !escaped;
+            escaped = c == '\\' && !escaped;
+            is_quote.then_some(line_start + index)
+        })
+        .collect::<Vec<_>>();
+
+    // The quotes are paired from the start of the line, if the insertion point is
+    // not inside a pair the next one on the line is used
+    let (open, close) = quotes
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .find(|(_, close)| offset <= *close)?;
+
+    if around {
+        Some(open..close + quote.len_utf8())
+    } else {
+        Some(open + quote.len_utf8()..close)
+    }
+}
+
+fn bracket_range(buffer: &str, offset: usize, open: char, around: bool) -> Option<Range<usize>> {
+    let close = closing_bracket(open)?;
+
+    // An opening bracket at the insertion point belongs to the object
+    let search_end = match buffer[offset..].chars().next() {
+        Some(c) if c == open => offset + c.len_utf8(),
+        _ => offset,
+    };
+    let mut depth = 0;
+    let open_index = buffer[..search_end]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| {
+            if *c == close {
+                depth += 1;
+            } else if *c == open {
+                if depth == 0 {
+                    return true;
+                }
+                depth -= 1;
+            }
+            false
+        })?
+        .0;
+
+    let content_start = open_index + open.len_utf8();
+    let mut depth = 0;
+    let close_index = buffer[content_start..]
+        .char_indices()
+        .find(|(_, c)| {
+            if *c == open {
+                depth += 1;
+            } else if *c == close {
+                if depth == 0 {
+                    return true;
+                }
+                depth -= 1;
+            }
+            false
+        })
+        .map(|(index, _)| content_start + index)?;
+
+    if around {
+        Some(open_index..close_index + close.len_utf8())
+    } else {
+        Some(content_start..close_index)
+    }
+}
+
+fn paragraph_range(buffer: &str, offset: usize, around: bool) -> Option<Range<usize>> {
+    // Every line including its newline
+    let mut lines = vec![];
+    let mut line_start = 0;
+    for (index, _) in buffer.match_indices('\n') {
+        lines.push(line_start..index + 1);
+        line_start = index + 1;
+    }
+    lines.push(line_start..buffer.len());
+
+    let is_blank = |line: &Range<usize>| buffer[line.clone()].trim().is_empty();
+    let current = lines
+        .iter()
+        .position(|line| line.contains(&offset))
+        .unwrap_or(lines.len() - 1);
+
+    // First and last line of the run of lines of the same kind as `line`
+    let run = |line: usize| {
+        let blank = is_blank(&lines[line]);
+        let first = lines[..line]
+            .iter()
+            .rposition(|l| is_blank(l) != blank)
+            .map_or(0, |index| index + 1);
+        let last = lines[line..]
+            .iter()
+            .position(|l| is_blank(l) != blank)
+            .map_or(lines.len() - 1, |index| line + index - 1);
+        (first, last)
+    };
+
+    let (mut first, mut last) = run(current);
+    if around {
+        // The following run is preferred, otherwise the preceding one is taken
+        if last + 1 < lines.len() && !lines[last + 1].is_empty() {
+            last = run(last + 1).1;
+        } else if first > 0 {
+            first = run(first - 1).0;
+        }
+    }
+    Some(lines[first].start..lines[last].end)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn text_object(scope: TextObjectScope, object_type: TextObjectType) -> TextObject {
+        TextObject::new(scope, object_type)
+    }
+
+    #[rstest]
+    #[case("foo bar baz", 5, TextObjectScope::Inner, TextObjectType::Word, Some(4..7))]
+    #[case("foo bar baz", 5, TextObjectScope::Around, TextObjectType::Word, Some(4..8))]
+    #[case("foo bar", 5, TextObjectScope::Around, TextObjectType::Word, Some(3..7))]
+    #[case("foo   bar", 4, TextObjectScope::Inner, TextObjectType::Word, Some(3..6))]
+    #[case("foo   bar", 4, TextObjectScope::Around, TextObjectType::Word, Some(3..9))]
+    #[case("foo.bar baz", 1, TextObjectScope::Inner, TextObjectType::Word, Some(0..3))]
+    #[case("foo.bar baz", 1, TextObjectScope::Inner, TextObjectType::BigWord, Some(0..7))]
+    #[case("foo", 3, TextObjectScope::Inner, TextObjectType::Word, Some(0..3))]
+    #[case("", 0, TextObjectScope::Inner, TextObjectType::Word, None)]
+    fn test_word_objects(
+        #[case] buffer: &str,
+        #[case] offset: usize,
+        #[case] scope: TextObjectScope,
+        #[case] object_type: TextObjectType,
+        #[case] expected: Option<Range<usize>>,
+    ) {
+        assert_eq!(
+            text_object(scope, object_type).range(buffer, offset),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(r#"echo "foo bar""#, 7, TextObjectScope::Inner, Some(6..13))]
+    #[case(r#"echo "foo bar""#, 7, TextObjectScope::Around, Some(5..14))]
+    #[case(r#"echo "foo bar""#, 0, TextObjectScope::Inner, Some(6..13))]
+    #[case(r#"a "b" c "d""#, 6, TextObjectScope::Inner, Some(9..10))]
+    #[case(r#""a \" b""#, 1, TextObjectScope::Inner, Some(1..7))]
+    #[case(r#"echo "foo"#, 7, TextObjectScope::Inner, None)]
+    fn test_quote_objects(
+        #[case] buffer: &str,
+        #[case] offset: usize,
+        #[case] scope: TextObjectScope,
+        #[case] expected: Option<Range<usize>>,
+    ) {
+        let object = text_object(scope, TextObjectType::Quote('"'));
+        assert_eq!(object.range(buffer, offset), expected);
+    }
+
+    #[rstest]
+    #[case("f(a, (b), c)", 3, TextObjectScope::Inner, Some(2..11))]
+    #[case("f(a, (b), c)", 3, TextObjectScope::Around, Some(1..12))]
+    #[case("f(a, (b), c)", 6, TextObjectScope::Inner, Some(6..7))]
+    #[case("f(a, (b), c)", 5, TextObjectScope::Inner, Some(6..7))]
+    #[case("f(a, (b), c)", 7, TextObjectScope::Inner, Some(6..7))]
+    #[case("f(a, (b), c)", 9, TextObjectScope::Inner, Some(2..11))]
+    #[case("f(a", 2, TextObjectScope::Inner, None)]
+    #[case("f a", 2, TextObjectScope::Inner, None)]
+    fn test_bracket_objects(
+        #[case] buffer: &str,
+        #[case] offset: usize,
+        #[case] scope: TextObjectScope,
+        #[case] expected: Option<Range<usize>>,
+    ) {
+        let object = text_object(scope, TextObjectType::Bracket('('));
+        assert_eq!(object.range(buffer, offset), expected);
+    }
+
+    #[rstest]
+    #[case("foo bar baz", 0..3, TextObjectScope::Inner, TextObjectType::Word, Some(0..4))]
+    #[case("foo bar baz", 0..4, TextObjectScope::Around, TextObjectType::Word, Some(0..8))]
+    #[case("foo bar", 4..7, TextObjectScope::Inner, TextObjectType::Word, None)]
+    #[case("f(a, (b), c)", 6..7, TextObjectScope::Inner, TextObjectType::Bracket('('), Some(2..11))]
+    #[case("f(a, (b), c)", 5..8, TextObjectScope::Around, TextObjectType::Bracket('('), Some(1..12))]
+    #[case("f(a, (b), c)", 2..11, TextObjectScope::Inner, TextObjectType::Bracket('('), None)]
+    #[case("\"a\" \"b\"", 1..2, TextObjectScope::Inner, TextObjectType::Quote('"'), None)]
+    fn test_expand(
+        #[case] buffer: &str,
+        #[case] selection: Range<usize>,
+        #[case] scope: TextObjectScope,
+        #[case] object_type: TextObjectType,
+        #[case] expected: Option<Range<usize>>,
+    ) {
+        let object = text_object(scope, object_type);
+        assert_eq!(object.expand(buffer, selection), expected);
+    }
+
+    #[rstest]
+    #[case("a\nb\n\nc", 2, TextObjectScope::Inner, Some(0..4))]
+    #[case("a\nb\n\nc", 2, TextObjectScope::Around, Some(0..5))]
+    #[case("a\n\nb\nc", 4, TextObjectScope::Inner, Some(3..6))]
+    #[case("a\n\nb\nc", 4, TextObjectScope::Around, Some(2..6))]
+    #[case("a\n\n\nb", 2, TextObjectScope::Inner, Some(2..4))]
+    fn test_paragraph_objects(
+        #[case] buffer: &str,
+        #[case] offset: usize,
+        #[case] scope: TextObjectScope,
+        #[case] expected: Option<Range<usize>>,
+    ) {
+        let object = text_object(scope, TextObjectType::Paragraph);
+        assert_eq!(object.range(buffer, offset), expected);
+    }
+
+    #[rstest]
+    #[case("(a [b] {c})", 0, Some(10))]
+    #[case("(a [b] {c})", 10, Some(0))]
+    #[case("(a [b] {c})", 3, Some(5))]
+    #[case("(a [b] {c})", 9, Some(7))]
+    #[case("(a [b] {c})", 1, None)]
+    #[case("((a)", 0, None)]
+    #[case("<a>", 0, None)]
+    fn test_matching_bracket(
+        #[case] buffer: &str,
+        #[case] offset: usize,
+        #[case] expected: Option<usize>,
+    ) {
+        assert_eq!(matching_bracket(buffer, offset), expected);
+    }
+}
@@ -0,0 +1,304 @@
+use super::edit_stack::{UndoHistory, UndoLimit, UndoSize};
+
+#[derive(Debug, PartialEq, Eq)]
+struct UndoNode<T> {
+    value: T,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // Index into `children` of the branch that is followed when redoing
+    active_child: usize,
+}
+
+impl<T> UndoNode<T> {
+    fn new(value: T, parent: Option<usize>) -> Self {
+        UndoNode {
+            value,
+            parent,
+            children: vec![],
+            active_child: 0,
+        }
+    }
+}
+
+/// Tree structured undo history
+///
+/// In contrast to the [`super::edit_stack::EditStack`] inserting a new entry
+/// after hitting undo does not discard the entries that could have been redone.
+/// Instead they are kept as an alternative branch that can be visited with
+/// [`UndoHistory::next_branch`] and [`UndoHistory::previous_branch`]
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct UndoTree<T> {
+    nodes: Vec<UndoNode<T>>,
+    current: usize,
+    limit: UndoLimit,
+}
+
+impl<T> UndoTree<T> {
+    pub fn new() -> Self
+    where
+        T: Default,
+    {
+        UndoTree {
+            nodes: vec![UndoNode::new(T::default(), None)],
+            current: 0,
+            limit: UndoLimit::default(),
+        }
+    }
+
+    /// Drop the oldest entries until the limit is satisfied.
+    ///
+    /// The root is replaced by its child leading to the current node, all the
+    /// other branches starting at the root are dropped with it.
+    fn evict(&mut self)
+    where
+        T: UndoSize,
+    {
+        while self.current != 0
+            && self
+                .limit
+                .is_exceeded_by(self.nodes.iter().map(|n| &n.value))
+        {
+            let mut new_root = self.current;
+            while let Some(parent) = self.nodes[new_root].parent {
+                if parent == 0 {
+                    break;
+                }
+                new_root = parent;
+            }
+            self.reroot(new_root);
+        }
+    }
+
+    /// Make `new_root` the root of the tree, dropping all nodes that are not below it
+    fn reroot(&mut self, new_root: usize) {
+        let mut keep = vec![false; self.nodes.len()];
+        let mut pending = vec![new_root];
+        while let Some(index) = pending.pop() {
+            keep[index] = true;
+            pending.extend(&self.nodes[index].children);
+        }
+
+        let mut new_indices = vec![0; self.nodes.len()];
+        let mut kept = 0;
+        for (index, keep) in keep.iter().enumerate() {
+            if *keep {
+                new_indices[index] = kept;
+                kept += 1;
+            }
+        }
+
+        let nodes = std::mem::take(&mut self.nodes);
+        self.nodes = nodes
+            .into_iter()
+            .zip(keep)
+            .filter_map(|(node, keep)| keep.then_some(node))
+            .map(|mut node| {
+                node.parent = node.parent.map(|parent| new_indices[parent]);
+                node.children = node.children.iter().map(|c| new_indices[*c]).collect();
+                node
+            })
+            .collect();
+        self.nodes[new_indices[new_root]].parent = None;
+        self.current = new_indices[self.current];
+
+        // Keeping the relative order of the nodes ensures the root stays at index 0
+        debug_assert_eq!(new_indices[new_root], 0);
+    }
+
+    /// Move to the sibling of the current node, `offset` positions away
+    fn switch_branch(&mut self, offset: isize) -> &T {
+        if let Some(parent) = self.nodes[self.current].parent {
+            let siblings = self.nodes[parent].children.len() as isize;
+            let position = self.nodes[parent].active_child as isize;
+            let new_position = (position + offset).rem_euclid(siblings) as usize;
+
+            self.nodes[parent].active_child = new_position;
+            self.current = self.nodes[parent].children[new_position];
+        }
+        &self.nodes[self.current].value
+    }
+}
+
+impl<T> UndoHistory<T> for UndoTree<T>
+where
+    T: Default + Clone + Send + UndoSize,
+{
+    /// Go to the parent of the current node. If present on the root do nothing
+    fn undo(&mut self) -> &T {
+        if let Some(parent) = self.nodes[self.current].parent {
+            self.current = parent;
+        }
+        &self.nodes[self.current].value
+    }
+
+    /// Go to the most recently visited child of the current node. If present on a
+    /// leaf do nothing
+    fn redo(&mut self) -> &T {
+        let node = &self.nodes[self.current];
+        if let Some(child) = node.children.get(node.active_child) {
+            self.current = *child;
+        }
+        &self.nodes[self.current].value
+    }
+
+    /// Insert a new child below the current node. Existing children are kept as
+    /// alternative branches
+    fn insert(&mut self, value: T) {
+        let new_index = self.nodes.len();
+        self.nodes.push(UndoNode::new(value, Some(self.current)));
+
+        let node = &mut self.nodes[self.current];
+        node.children.push(new_index);
+        node.active_child = node.children.len() - 1;
+        self.current = new_index;
+        self.evict();
+    }
+
+    /// Replace the value of the current node. The root is never replaced.
+    fn amend(&mut self, value: T) {
+        if self.current == 0 {
+            self.insert(value);
+        } else {
+            self.nodes[self.current].value = value;
+        }
+    }
+
+    /// Reset the tree to the initial state
+    fn reset(&mut self) {
+        let limit = self.limit;
+        *self = UndoTree::new();
+        self.limit = limit;
+    }
+
+    /// Return the entry currently being pointed to
+    fn current(&mut self) -> &T {
+        &self.nodes[self.current].value
+    }
+
+    fn set_limit(&mut self, limit: UndoLimit) {
+        self.limit = limit;
+        self.evict();
+    }
+
+    /// The nodes of the tree are stored in the order they were inserted
+    fn entries(&self) -> Vec<&T> {
+        self.nodes.iter().map(|node| &node.value).collect()
+    }
+
+    fn position(&self) -> usize {
+        self.current
+    }
+
+    /// Jump to any node of the tree, following redos take the path back to it
+    fn jump_to(&mut self, position: usize) -> &T {
+        self.current = position.min(self.nodes.len() - 1);
+
+        let mut child = self.current;
+        while let Some(parent) = self.nodes[child].parent {
+            let parent_node = &mut self.nodes[parent];
+            if let Some(index) = parent_node.children.iter().position(|c| *c == child) {
+                parent_node.active_child = index;
+            }
+            child = parent;
+        }
+        &self.nodes[self.current].value
+    }
+
+    fn next_branch(&mut self) -> &T {
+        self.switch_branch(1)
+    }
+
+    fn previous_branch(&mut self) -> &T {
+        self.switch_branch(-1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn undo_tree(values: &[isize]) -> UndoTree<isize> {
+        let mut tree = UndoTree::new();
+        for value in values {
+            tree.insert(*value);
+        }
+        tree
+    }
+
+    #[rstest]
+    #[case(undo_tree(&[1, 2, 3]), 2)]
+    #[case(undo_tree(&[]), 0)]
+    fn undo_works(#[case] tree: UndoTree<isize>, #[case] value_after_undo: isize) {
+        let mut tree = tree;
+
+        let value = tree.undo();
+        assert_eq!(*value, value_after_undo);
+    }
+
+    #[test]
+    fn insert_after_undo_keeps_branch() {
+        let mut tree = undo_tree(&[1, 2]);
+        tree.undo();
+        tree.insert(3);
+
+        assert_eq!(*tree.current(), 3);
+        assert_eq!(*tree.undo(), 1);
+        // Redo follows the most recent branch
+        assert_eq!(*tree.redo(), 3);
+        assert_eq!(*tree.previous_branch(), 2);
+        assert_eq!(*tree.next_branch(), 3);
+        assert_eq!(*tree.next_branch(), 2);
+        // After switching the redo follows the selected branch
+        assert_eq!(*tree.undo(), 1);
+        assert_eq!(*tree.redo(), 2);
+    }
+
+    #[test]
+    fn switch_branch_without_siblings_does_nothing() {
+        let mut tree = undo_tree(&[1, 2]);
+
+        assert_eq!(*tree.next_branch(), 2);
+        assert_eq!(*tree.previous_branch(), 2);
+    }
+
+    #[test]
+    fn amend_replaces_current_but_not_root() {
+        let mut tree = undo_tree(&[]);
+        tree.amend(1);
+        tree.amend(2);
+
+        assert_eq!(tree.nodes.len(), 2);
+        assert_eq!(*tree.current(), 2);
+        assert_eq!(*tree.undo(), 0);
+    }
+
+    #[test]
+    fn eviction_drops_root_and_other_branches() {
+        let mut tree = undo_tree(&[1, 2]);
+        tree.undo();
+        tree.undo();
+        tree.insert(3);
+        tree.insert(4);
+        tree.set_limit(UndoLimit::entries(2));
+
+        assert_eq!(tree.nodes.len(), 2);
+        assert_eq!(*tree.current(), 4);
+        assert_eq!(*tree.undo(), 3);
+        assert_eq!(*tree.undo(), 3);
+        assert_eq!(*tree.redo(), 4);
+    }
+
+    #[test]
+    fn jump_to_other_branch_updates_redo_path() {
+        let mut tree = undo_tree(&[1, 2]);
+        tree.undo();
+        tree.insert(3);
+
+        assert_eq!(*tree.jump_to(2), 2);
+        assert_eq!(*tree.undo(), 1);
+        assert_eq!(*tree.redo(), 2);
+        assert_eq!(tree.entries(), vec![&0, &1, &2, &3]);
+    }
+}
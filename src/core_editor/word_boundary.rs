@@ -0,0 +1,121 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Policy deciding what counts as a word for the word motions and edits
+///
+/// Applies to the commands moving, cutting or deleting by word, like
+/// `MoveWordLeft`, `CutWordLeft` or the vi motions `w`, `b` and `e`. The
+/// commands working on WORDs always split at whitespace.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum WordBoundary {
+    /// Word boundaries as defined by the Unicode standard
+    #[default]
+    Unicode,
+
+    /// Words are runs of alphanumeric characters and the given characters,
+    /// like `WORDCHARS` in zsh. Runs of the other characters that are not
+    /// whitespace form words of their own.
+    WordChars(String),
+
+    /// Path components, `/` separates words like whitespace does. Deleting a
+    /// word left of `cd /usr/local/` leaves `cd /usr/`
+    PathComponent,
+
+    /// Shell tokens, words are only separated by whitespace and the shell
+    /// operators `|`, `&`, `;`, `<`, `>`, `(` and `)` form words of their own
+    ShellToken,
+}
+
+/// Class of a grapheme, consecutive graphemes of the same class form a segment
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum CharClass {
+    Separator,
+    Word,
+    Other,
+}
+
+impl WordBoundary {
+    /// Split `text` into the words and the separators between them, together
+    /// with their byte offsets
+    pub(crate) fn split<'a>(&self, text: &'a str) -> Vec<(usize, &'a str)> {
+        match self {
+            WordBoundary::Unicode => text.split_word_bound_indices().collect(),
+            _ => {
+                let mut segments: Vec<(usize, &str)> = vec![];
+                let mut previous_class = None;
+                for (index, grapheme) in text.grapheme_indices(true) {
+                    let class = self.class(grapheme);
+                    match segments.last_mut() {
+                        Some((start, segment)) if previous_class == Some(class) => {
+                            *segment = &text[*start..index + grapheme.len()];
+                        }
+                        _ => segments.push((index, grapheme)),
+                    }
+                    previous_class = Some(class);
+                }
+                segments
+            }
+        }
+    }
+
+    /// Check if a segment returned by [`WordBoundary::split`] separates words
+    pub(crate) fn is_separator(&self, segment: &str) -> bool {
+        segment.chars().all(|c| self.is_separator_char(c))
+    }
+
+    fn is_separator_char(&self, c: char) -> bool {
+        c.is_whitespace() || (*self == WordBoundary::PathComponent && c == '/')
+    }
+
+    fn class(&self, grapheme: &str) -> CharClass {
+        let c = grapheme.chars().next().unwrap_or(' ');
+        if self.is_separator_char(c) {
+            return CharClass::Separator;
+        }
+        match self {
+            WordBoundary::WordChars(word_chars)
+                if !c.is_alphanumeric() && !word_chars.contains(c) =>
+            {
+                CharClass::Other
+            }
+            WordBoundary::ShellToken if "|&;<>()".contains(c) => CharClass::Other,
+            _ => CharClass::Word,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn words(word_boundary: &WordBoundary, text: &str) -> Vec<String> {
+        word_boundary
+            .split(text)
+            .into_iter()
+            .filter(|(_, segment)| !word_boundary.is_separator(segment))
+            .map(|(_, segment)| segment.to_string())
+            .collect()
+    }
+
+    #[rstest]
+    #[case(WordBoundary::Unicode, "cd foo-bar/baz", vec!["cd", "foo", "-", "bar", "/", "baz"])]
+    #[case(WordBoundary::WordChars("-".into()), "cd foo-bar//baz", vec!["cd", "foo-bar", "//", "baz"])]
+    #[case(WordBoundary::PathComponent, "cd /usr/local-1/", vec!["cd", "usr", "local-1"])]
+    #[case(WordBoundary::ShellToken, "ls -l|grep a.b;", vec!["ls", "-l", "|", "grep", "a.b", ";"])]
+    fn test_split(
+        #[case] word_boundary: WordBoundary,
+        #[case] text: &str,
+        #[case] expected: Vec<&str>,
+    ) {
+        assert_eq!(words(&word_boundary, text), expected);
+    }
+
+    #[test]
+    fn test_split_offsets() {
+        assert_eq!(
+            WordBoundary::PathComponent.split("a //b"),
+            vec![(0, "a"), (1, " //"), (4, "b")]
+        );
+    }
+}
@@ -1,5 +1,6 @@
-use crate::{enums::ReedlineEvent, PromptEditMode};
+use crate::{enums::ReedlineEvent, KeybindingConflict, Keybindings, PromptEditMode};
 use crossterm::event::Event;
+use std::time::Duration;
 
 /// Define the style of parsing for the edit events
 /// Available default options:
@@ -11,4 +12,42 @@ pub trait EditMode: Send {
 
     /// What to display in the prompt indicator
     fn edit_mode(&self) -> PromptEditMode;
+
+    /// Time left to wait for the next key of a pending key sequence, `None`
+    /// if no sequence was started
+    fn sequence_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Handle the keys of the pending key sequence once its timeout is up
+    fn handle_sequence_timeout(&mut self) -> ReedlineEvent {
+        ReedlineEvent::None
+    }
+
+    /// The keys continuing the pending key sequence or partial command, each
+    /// with a description of what it does. Shown below the prompt if
+    /// [`Reedline::with_keybinding_hints`](crate::Reedline::with_keybinding_hints) is enabled.
+    fn pending_continuations(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+
+    /// The keybindings of the current mode, `None` if the edit mode doesn't
+    /// use [`Keybindings`]
+    fn keybindings(&self) -> Option<&Keybindings> {
+        None
+    }
+
+    /// Change the keybindings of the current mode after construction, see
+    /// [`Reedline::bind_key`](crate::Reedline::bind_key)
+    fn keybindings_mut(&mut self) -> Option<&mut Keybindings> {
+        None
+    }
+
+    /// The conflicts of the keybindings of the current mode, including the
+    /// bindings shadowed by the keys the edit mode handles itself
+    fn keybinding_conflicts(&self) -> Vec<KeybindingConflict> {
+        self.keybindings()
+            .map(Keybindings::find_conflicts)
+            .unwrap_or_default()
+    }
 }
@@ -1,15 +1,17 @@
 use crate::{
     edit_mode::{
+        combine_events,
         keybindings::{
             add_common_control_bindings, add_common_edit_bindings, add_common_navigation_bindings,
-            edit_bind, Keybindings,
+            edit_bind, sort_conflicts, text_input_conflict, KeybindingConflict, Keybindings,
         },
-        EditMode,
+        EditMode, MacroRecorder, SequenceInput, SequenceMatcher,
     },
     enums::{EditCommand, ReedlineEvent},
     PromptEditMode,
 };
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use std::time::Duration;
 
 /// Returns the current default emacs keybindings
 pub fn default_emacs_keybindings() -> Keybindings {
@@ -50,6 +52,16 @@ pub fn default_emacs_keybindings() -> Keybindings {
     kb.add_binding(KM::CONTROL, KC::Char('w'), edit_bind(EC::CutWordLeft));
     kb.add_binding(KM::CONTROL, KC::Char('k'), edit_bind(EC::CutToEnd));
     kb.add_binding(KM::CONTROL, KC::Char('u'), edit_bind(EC::CutFromStart));
+    // Selection
+    kb.add_binding(KM::CONTROL, KC::Char(' '), edit_bind(EC::StartSelection));
+    kb.add_binding(KM::ALT, KC::Char('w'), edit_bind(EC::CopySelection));
+    for delimiter in ['(', '[', '{', '"', '\''] {
+        kb.add_binding(
+            KM::ALT,
+            KC::Char(delimiter),
+            edit_bind(EC::SurroundSelection(delimiter)),
+        );
+    }
     // Edits
     kb.add_binding(KM::CONTROL, KC::Char('t'), edit_bind(EC::SwapGraphemes));
 
@@ -74,6 +86,8 @@ pub fn default_emacs_keybindings() -> Keybindings {
         ]),
     );
     // Edits
+    kb.add_binding(KM::ALT, KC::Up, edit_bind(EC::MoveLineUp));
+    kb.add_binding(KM::ALT, KC::Down, edit_bind(EC::MoveLineDown));
     kb.add_binding(KM::ALT, KC::Delete, edit_bind(EC::DeleteWord));
     kb.add_binding(KM::ALT, KC::Backspace, edit_bind(EC::BackspaceWord));
     kb.add_binding(
@@ -82,30 +96,276 @@ pub fn default_emacs_keybindings() -> Keybindings {
         ReedlineEvent::Edit(vec![EditCommand::BackspaceWord]),
     );
     // Cutting
+    kb.add_binding(KM::ALT, KC::Char('t'), edit_bind(EC::TransposeWords));
     kb.add_binding(KM::ALT, KC::Char('d'), edit_bind(EC::CutWordRight));
+    kb.add_binding(KM::ALT, KC::Char('y'), edit_bind(EC::YankPop));
     // Case changes
     kb.add_binding(KM::ALT, KC::Char('u'), edit_bind(EC::UppercaseWord));
     kb.add_binding(KM::ALT, KC::Char('l'), edit_bind(EC::LowercaseWord));
-    kb.add_binding(KM::ALT, KC::Char('c'), edit_bind(EC::CapitalizeChar));
+    kb.add_binding(KM::ALT, KC::Char('c'), edit_bind(EC::CapitalizeWord));
 
     kb
 }
 
+/// Register of the [`MacroRecorder`] holding the keyboard macro
+const KEYBOARD_MACRO_REGISTER: char = 'e';
+
+/// The keys completing the built-in `C-x` chords
+const CHORDS: [(&str, &str); 4] = [
+    ("(", "start recording a macro"),
+    (")", "stop recording the macro"),
+    ("e", "replay the macro"),
+    ("C-t", "transpose the lines"),
+];
+
+/// Largest count of a numeric argument, as the command is repeated that often
+const MAX_ARGUMENT: usize = 9999;
+
+/// Numeric argument typed in front of a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NumericArgument {
+    count: usize,
+    // Digits replace the default count of `C-u`
+    has_digits: bool,
+}
+
 /// This parses the incoming Events like a emacs style-editor
+///
+/// Keyboard macros are recorded with `C-x (` and `C-x )` and replayed with `C-x e`.
+///
+/// Numeric arguments repeat the following command: `M-5 C-d` deletes five
+/// characters, unless the `M-` digit is bound. With
+/// [`Emacs::with_universal_argument`] `C-u` is the universal argument instead of
+/// cutting to the start of the line, repeating the command four times, or as
+/// often as the digits typed after it.
 pub struct Emacs {
     keybindings: Keybindings,
+    macros: MacroRecorder,
+    // `C-x` was typed and the next key completes the chord
+    chord_prefix: Option<Event>,
+    argument: Option<NumericArgument>,
+    universal_argument: bool,
+    sequences: SequenceMatcher,
 }
 
 impl Default for Emacs {
     fn default() -> Self {
-        Emacs {
-            keybindings: default_emacs_keybindings(),
-        }
+        Emacs::new(default_emacs_keybindings())
     }
 }
 
 impl EditMode for Emacs {
     fn parse_event(&mut self, event: Event) -> ReedlineEvent {
+        // The prefix of a chord is only recorded together with the key completing
+        // it, so the chords starting and stopping the recording are not part of the macro
+        let recording = self.macros.is_recording();
+        let prefix = self.chord_prefix;
+        let result = self.parse_emacs_event(event);
+        if recording && self.macros.is_recording() && self.chord_prefix.is_none() {
+            if let Some(prefix) = prefix {
+                self.macros.record(prefix);
+            }
+            self.macros.record(event);
+        }
+        result
+    }
+
+    fn edit_mode(&self) -> PromptEditMode {
+        PromptEditMode::Emacs
+    }
+
+    fn sequence_timeout(&self) -> Option<Duration> {
+        self.sequences
+            .remaining_time(self.keybindings.sequence_timeout())
+    }
+
+    fn handle_sequence_timeout(&mut self) -> ReedlineEvent {
+        let input = self.sequences.flush(&self.keybindings);
+        self.parse_sequence_input(input)
+    }
+
+    fn pending_continuations(&self) -> Vec<(String, String)> {
+        if self.chord_prefix.is_some() {
+            return CHORDS
+                .iter()
+                .map(|(key, description)| (key.to_string(), description.to_string()))
+                .collect();
+        }
+        self.sequences.continuations(&self.keybindings)
+    }
+
+    fn keybindings(&self) -> Option<&Keybindings> {
+        Some(&self.keybindings)
+    }
+
+    fn keybindings_mut(&mut self) -> Option<&mut Keybindings> {
+        Some(&mut self.keybindings)
+    }
+
+    fn keybinding_conflicts(&self) -> Vec<KeybindingConflict> {
+        let mut conflicts = self.keybindings.find_conflicts();
+        conflicts.extend(self.keybindings.get_keybindings().keys().filter_map(|key| {
+            match (key.modifier, key.key_code) {
+                (KeyModifiers::CONTROL, KeyCode::Char('u')) if self.universal_argument => {
+                    Some(KeybindingConflict::Shadowed {
+                        keys: vec![key.clone()],
+                        reason: "it is the universal argument".to_string(),
+                    })
+                }
+                _ => text_input_conflict(key),
+            }
+        }));
+        sort_conflicts(&mut conflicts);
+        conflicts
+    }
+}
+
+impl Emacs {
+    /// Emacs style input parsing constructor if you want to use custom keybindings
+    pub fn new(keybindings: Keybindings) -> Self {
+        Emacs {
+            keybindings,
+            macros: MacroRecorder::default(),
+            chord_prefix: None,
+            argument: None,
+            universal_argument: false,
+            sequences: SequenceMatcher::default(),
+        }
+    }
+
+    /// A builder to use `C-u` as the universal argument, taking precedence over
+    /// its binding
+    #[must_use]
+    pub fn with_universal_argument(mut self, universal_argument: bool) -> Self {
+        self.universal_argument = universal_argument;
+        self
+    }
+
+    /// Handle the `C-x (`, `C-x )`, `C-x e` and `C-x C-t` chords, unless `C-x` is bound
+    fn parse_chord(&mut self, event: Event) -> Option<ReedlineEvent> {
+        let (modifiers, code) = match event {
+            Event::Key(KeyEvent { code, modifiers }) => (modifiers, code),
+            _ => return None,
+        };
+
+        if self.chord_prefix.take().is_none() {
+            let is_prefix = modifiers == KeyModifiers::CONTROL
+                && code == KeyCode::Char('x')
+                && self.keybindings.find_binding(modifiers, code).is_none();
+            self.chord_prefix = is_prefix.then_some(event);
+            return is_prefix.then_some(ReedlineEvent::None);
+        }
+
+        let event = match code {
+            KeyCode::Char('(') => {
+                self.macros.start(KEYBOARD_MACRO_REGISTER);
+                ReedlineEvent::None
+            }
+            KeyCode::Char(')') => {
+                self.macros.stop();
+                ReedlineEvent::None
+            }
+            KeyCode::Char('t') if modifiers == KeyModifiers::CONTROL => {
+                ReedlineEvent::Edit(vec![EditCommand::TransposeLines])
+            }
+            KeyCode::Char('e') => match self.macros.replay(KEYBOARD_MACRO_REGISTER) {
+                Some(events) => {
+                    let events = events
+                        .into_iter()
+                        .map(|event| self.parse_emacs_event(event))
+                        .collect();
+                    self.macros.finish_replay();
+                    ReedlineEvent::Multiple(events)
+                }
+                None => ReedlineEvent::None,
+            },
+            _ => ReedlineEvent::None,
+        };
+        Some(event)
+    }
+
+    /// Accumulate the numeric argument, returns `false` if the event is not part of it
+    fn parse_argument(&mut self, event: Event) -> bool {
+        let (modifiers, code) = match event {
+            Event::Key(KeyEvent { code, modifiers }) => (modifiers, code),
+            _ => return false,
+        };
+        let argument = match (modifiers, code, self.argument) {
+            (KeyModifiers::CONTROL, KeyCode::Char('u'), argument) if self.universal_argument => {
+                match argument {
+                    Some(argument) if argument.has_digits => argument,
+                    Some(argument) => NumericArgument {
+                        count: argument.count.saturating_mul(4).min(MAX_ARGUMENT),
+                        has_digits: false,
+                    },
+                    None => NumericArgument {
+                        count: 4,
+                        has_digits: false,
+                    },
+                }
+            }
+            (KeyModifiers::ALT | KeyModifiers::NONE, KeyCode::Char(c @ '0'..='9'), argument)
+                if argument.is_some()
+                    || (modifiers == KeyModifiers::ALT
+                        && self.keybindings.find_binding(modifiers, code).is_none()) =>
+            {
+                let digit = c.to_digit(10).unwrap_or_default() as usize;
+                let count = match argument {
+                    Some(argument) if argument.has_digits => argument
+                        .count
+                        .saturating_mul(10)
+                        .saturating_add(digit)
+                        .min(MAX_ARGUMENT),
+                    _ => digit,
+                };
+                NumericArgument {
+                    count,
+                    has_digits: true,
+                }
+            }
+            _ => return false,
+        };
+        self.argument = Some(argument);
+        true
+    }
+
+    fn parse_emacs_event(&mut self, event: Event) -> ReedlineEvent {
+        let input = self.sequences.process(&self.keybindings, event);
+        self.parse_sequence_input(input)
+    }
+
+    /// Handle the input resolved by the key sequence matcher, a numeric argument
+    /// repeats the event of a sequence like any other
+    fn parse_sequence_input(&mut self, input: Vec<SequenceInput>) -> ReedlineEvent {
+        let mut events = Vec::with_capacity(input.len());
+        for input in input {
+            events.push(match input {
+                SequenceInput::Event(event) => self.parse_single_event(event),
+                SequenceInput::Sequence(event) => match self.argument.take() {
+                    Some(argument) => repeat_event(event, argument.count),
+                    None => event,
+                },
+            });
+        }
+        combine_events(events)
+    }
+
+    fn parse_single_event(&mut self, event: Event) -> ReedlineEvent {
+        if let Some(event) = self.parse_chord(event) {
+            return event;
+        }
+        if self.parse_argument(event) {
+            return ReedlineEvent::None;
+        }
+
+        let result = self.parse_key_event(event);
+        match self.argument.take() {
+            Some(argument) => repeat_event(result, argument.count),
+            None => result,
+        }
+    }
+
+    fn parse_key_event(&mut self, event: Event) -> ReedlineEvent {
         match event {
             Event::Key(KeyEvent { code, modifiers }) => match (modifiers, code) {
                 (modifier, KeyCode::Char(c)) => {
@@ -152,16 +412,28 @@ impl EditMode for Emacs {
             Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
         }
     }
-
-    fn edit_mode(&self) -> PromptEditMode {
-        PromptEditMode::Emacs
-    }
 }
 
-impl Emacs {
-    /// Emacs style input parsing constructor if you want to use custom keybindings
-    pub fn new(keybindings: Keybindings) -> Self {
-        Emacs { keybindings }
+/// Repeat the event `count` times for a numeric argument. The kills to the end
+/// of the line or buffer kill `count` lines instead and `C-d` only deletes, so
+/// it doesn't exit once the buffer is empty
+fn repeat_event(event: ReedlineEvent, count: usize) -> ReedlineEvent {
+    match event {
+        ReedlineEvent::CtrlD => ReedlineEvent::Edit(vec![EditCommand::Delete; count]),
+        ReedlineEvent::Edit(commands) => {
+            let commands: Vec<EditCommand> = commands
+                .into_iter()
+                .flat_map(|command| match command {
+                    EditCommand::CutToEnd | EditCommand::CutToLineEnd => {
+                        vec![EditCommand::CutToLineEnd, EditCommand::CutChar]
+                    }
+                    command => vec![command],
+                })
+                .collect();
+            ReedlineEvent::Edit((0..count).flat_map(|_| commands.clone()).collect())
+        }
+        ReedlineEvent::None => ReedlineEvent::None,
+        event => ReedlineEvent::Multiple(vec![event; count]),
     }
 }
 
@@ -201,6 +473,54 @@ mod test {
         assert_eq!(result, ReedlineEvent::HistoryHintComplete);
     }
 
+    #[test]
+    fn keybindings_can_be_changed_after_construction() {
+        let mut emacs = Emacs::default();
+        let ctrl_l = Event::Key(KeyEvent {
+            modifiers: KeyModifiers::CONTROL,
+            code: KeyCode::Char('l'),
+        });
+
+        emacs.keybindings_mut().unwrap().add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('l'),
+            ReedlineEvent::HistoryHintComplete,
+        );
+        assert_eq!(
+            emacs.parse_event(ctrl_l),
+            ReedlineEvent::HistoryHintComplete
+        );
+
+        emacs
+            .keybindings_mut()
+            .unwrap()
+            .remove_binding(KeyModifiers::CONTROL, KeyCode::Char('l'));
+        assert_eq!(emacs.parse_event(ctrl_l), ReedlineEvent::None);
+    }
+
+    #[test]
+    fn bindings_shadowed_by_emacs_are_conflicts() {
+        let mut keybindings = Keybindings::new();
+        keybindings.add_binding(KeyModifiers::NONE, KeyCode::Char('a'), ReedlineEvent::Up);
+        keybindings.add_binding(KeyModifiers::ALT, KeyCode::Char('1'), ReedlineEvent::Up);
+        keybindings.add_binding(KeyModifiers::CONTROL, KeyCode::Char('R'), ReedlineEvent::Up);
+        keybindings.add_binding(KeyModifiers::CONTROL, KeyCode::Char('r'), ReedlineEvent::Up);
+        let emacs = Emacs::new(keybindings);
+
+        let conflicts = emacs
+            .keybinding_conflicts()
+            .iter()
+            .map(|conflict| conflict.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            conflicts,
+            vec![
+                "C-R is unreachable, letters are looked up in lowercase",
+                "a is shadowed, the character is inserted",
+            ]
+        );
+    }
+
     #[test]
     fn inserting_character_works() {
         let mut emacs = Emacs::default();
@@ -261,4 +581,179 @@ mod test {
             ReedlineEvent::Edit(vec![EditCommand::InsertChar('😀')])
         );
     }
+
+    #[test]
+    fn keyboard_macro_is_replayed() {
+        let mut emacs = Emacs::default();
+        let mut press = |modifiers, c| {
+            emacs.parse_event(Event::Key(KeyEvent {
+                modifiers,
+                code: KeyCode::Char(c),
+            }))
+        };
+
+        press(KeyModifiers::CONTROL, 'x');
+        press(KeyModifiers::SHIFT, '(');
+        assert_eq!(
+            press(KeyModifiers::NONE, 'a'),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('a')])
+        );
+        press(KeyModifiers::CONTROL, 'x');
+        press(KeyModifiers::SHIFT, ')');
+
+        press(KeyModifiers::CONTROL, 'x');
+        assert_eq!(
+            press(KeyModifiers::NONE, 'e'),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::InsertChar(
+                'a'
+            )])])
+        );
+    }
+
+    #[test]
+    fn transpose_lines_chord() {
+        let mut emacs = Emacs::default();
+        let mut press = |c| {
+            emacs.parse_event(Event::Key(KeyEvent {
+                modifiers: KeyModifiers::CONTROL,
+                code: KeyCode::Char(c),
+            }))
+        };
+
+        assert_eq!(press('x'), ReedlineEvent::None);
+        assert_eq!(
+            press('t'),
+            ReedlineEvent::Edit(vec![EditCommand::TransposeLines])
+        );
+        assert_eq!(
+            press('t'),
+            ReedlineEvent::Edit(vec![EditCommand::SwapGraphemes])
+        );
+    }
+
+    fn press(emacs: &mut Emacs, modifiers: KeyModifiers, code: KeyCode) -> ReedlineEvent {
+        emacs.parse_event(Event::Key(KeyEvent { modifiers, code }))
+    }
+
+    #[test]
+    fn numeric_argument_repeats_command() {
+        let mut emacs = Emacs::default();
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::ALT, KeyCode::Char('1')),
+            ReedlineEvent::None
+        );
+        press(&mut emacs, KeyModifiers::NONE, KeyCode::Char('2'));
+        let result = press(&mut emacs, KeyModifiers::CONTROL, KeyCode::Char('d'));
+
+        assert_eq!(result, ReedlineEvent::Edit(vec![EditCommand::Delete; 12]));
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::NONE, KeyCode::Char('2')),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('2')])
+        );
+    }
+
+    #[test]
+    fn numeric_argument_is_capped() {
+        let mut emacs = Emacs::default();
+        press(&mut emacs, KeyModifiers::ALT, KeyCode::Char('9'));
+        for _ in 0..30 {
+            press(&mut emacs, KeyModifiers::NONE, KeyCode::Char('9'));
+        }
+        let result = press(&mut emacs, KeyModifiers::CONTROL, KeyCode::Char('d'));
+
+        assert_eq!(
+            result,
+            ReedlineEvent::Edit(vec![EditCommand::Delete; MAX_ARGUMENT])
+        );
+    }
+
+    #[test]
+    fn bound_alt_digit_is_no_numeric_argument() {
+        let mut keybindings = default_emacs_keybindings();
+        keybindings.add_binding(KeyModifiers::ALT, KeyCode::Char('1'), ReedlineEvent::Up);
+        let mut emacs = Emacs::new(keybindings);
+
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::ALT, KeyCode::Char('1')),
+            ReedlineEvent::Up
+        );
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::NONE, KeyCode::Char('2')),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('2')])
+        );
+    }
+
+    #[test]
+    fn ctrl_u_cuts_without_universal_argument() {
+        let mut emacs = Emacs::default();
+
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::CONTROL, KeyCode::Char('u')),
+            ReedlineEvent::Edit(vec![EditCommand::CutFromStart])
+        );
+    }
+
+    #[test]
+    fn universal_argument_kills_lines() {
+        let mut emacs = Emacs::default().with_universal_argument(true);
+        press(&mut emacs, KeyModifiers::CONTROL, KeyCode::Char('u'));
+        press(&mut emacs, KeyModifiers::CONTROL, KeyCode::Char('u'));
+        let result = press(&mut emacs, KeyModifiers::CONTROL, KeyCode::Char('k'));
+
+        let kill_line = [EditCommand::CutToLineEnd, EditCommand::CutChar];
+        assert_eq!(
+            result,
+            ReedlineEvent::Edit(kill_line.iter().cycle().take(32).cloned().collect())
+        );
+    }
+
+    #[test]
+    fn universal_argument_with_digits() {
+        let mut emacs = Emacs::default().with_universal_argument(true);
+        press(&mut emacs, KeyModifiers::CONTROL, KeyCode::Char('u'));
+        press(&mut emacs, KeyModifiers::NONE, KeyCode::Char('3'));
+        let result = press(&mut emacs, KeyModifiers::NONE, KeyCode::Char('a'));
+
+        assert_eq!(
+            result,
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('a'); 3])
+        );
+    }
+
+    #[test]
+    fn key_sequence_with_chord_prefix() {
+        let mut keybindings = default_emacs_keybindings();
+        keybindings.add_sequence_binding(
+            &[
+                (KeyModifiers::CONTROL, KeyCode::Char('x')),
+                (KeyModifiers::CONTROL, KeyCode::Char('e')),
+            ],
+            ReedlineEvent::OpenEditor,
+        );
+        let mut emacs = Emacs::new(keybindings);
+
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::CONTROL, KeyCode::Char('x')),
+            ReedlineEvent::None
+        );
+        assert!(emacs.sequence_timeout().is_some());
+        assert_eq!(
+            emacs.pending_continuations(),
+            vec![("C-e".to_string(), "OpenEditor".to_string())]
+        );
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::CONTROL, KeyCode::Char('e')),
+            ReedlineEvent::OpenEditor
+        );
+
+        // The built-in chords still work when the sequence is broken
+        press(&mut emacs, KeyModifiers::CONTROL, KeyCode::Char('x'));
+        emacs.handle_sequence_timeout();
+        assert_eq!(emacs.pending_continuations().len(), CHORDS.len());
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::CONTROL, KeyCode::Char('t')),
+            ReedlineEvent::Edit(vec![EditCommand::TransposeLines])
+        );
+        assert_eq!(emacs.sequence_timeout(), None);
+    }
 }
@@ -2,20 +2,184 @@ use {
     crate::{enums::ReedlineEvent, EditCommand},
     crossterm::event::{KeyCode, KeyModifiers},
     serde::{Deserialize, Serialize},
-    std::collections::HashMap,
+    std::{collections::HashMap, time::Duration},
 };
 
+#[cfg(feature = "toml")]
+use crate::result::{ReedlineError, ReedlineErrorVariants, Result};
+
+/// Default time to wait for the next key of a key sequence
+const DEFAULT_SEQUENCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+fn default_sequence_timeout_ms() -> u64 {
+    DEFAULT_SEQUENCE_TIMEOUT.as_millis() as u64
+}
+
+/// A key together with the modifiers held while pressing it
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct KeyCombination {
+    /// The held modifiers
     pub modifier: KeyModifiers,
+    /// The pressed key
     pub key_code: KeyCode,
 }
 
+/// A problem with a binding that keeps it from working as expected, see
+/// [`Keybindings::find_conflicts`] and [`EditMode::keybinding_conflicts`](crate::EditMode::keybinding_conflicts)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeybindingConflict {
+    /// The key is bound on its own and as a sequence of just this key, the
+    /// sequence is used
+    Duplicate {
+        /// The key bound twice
+        keys: Vec<KeyCombination>,
+    },
+
+    /// The keys start longer sequences, so their binding only runs after the
+    /// sequence timeout
+    Ambiguous {
+        /// The keys of the delayed binding
+        keys: Vec<KeyCombination>,
+        /// The sequences starting with `keys`
+        longer: Vec<Vec<KeyCombination>>,
+    },
+
+    /// The edit mode handles the key itself and never looks up the binding
+    Shadowed {
+        /// The shadowed key
+        keys: Vec<KeyCombination>,
+        /// What the edit mode does with the key instead
+        reason: String,
+    },
+
+    /// The binding can never be triggered
+    Unreachable {
+        /// The unreachable key
+        keys: Vec<KeyCombination>,
+        /// Why the binding is never looked up
+        reason: String,
+    },
+}
+
+impl KeybindingConflict {
+    /// The keys of the binding with the conflict
+    pub fn keys(&self) -> &[KeyCombination] {
+        match self {
+            KeybindingConflict::Duplicate { keys }
+            | KeybindingConflict::Ambiguous { keys, .. }
+            | KeybindingConflict::Shadowed { keys, .. }
+            | KeybindingConflict::Unreachable { keys, .. } => keys,
+        }
+    }
+}
+
+impl std::fmt::Display for KeybindingConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys = sequence_label(self.keys());
+        match self {
+            KeybindingConflict::Duplicate { .. } => write!(
+                f,
+                "{} is bound on its own and as a sequence, the sequence is used",
+                keys
+            ),
+            KeybindingConflict::Ambiguous { longer, .. } => write!(
+                f,
+                "{} starts {}, its binding only runs after the sequence timeout",
+                keys,
+                longer
+                    .iter()
+                    .map(|sequence| sequence_label(sequence))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            KeybindingConflict::Shadowed { reason, .. } => {
+                write!(f, "{} is shadowed, {}", keys, reason)
+            }
+            KeybindingConflict::Unreachable { reason, .. } => {
+                write!(f, "{} is unreachable, {}", keys, reason)
+            }
+        }
+    }
+}
+
 /// Main definition of editor keybindings
+///
+/// Serializes to the format read by [`Keybindings::from_toml`], a list of
+/// bindings with string keys that also works with formats like JSON
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(into = "KeybindingsConfig", try_from = "KeybindingsConfig")]
 pub struct Keybindings {
     /// Defines a keybinding for a reedline event
     pub bindings: HashMap<KeyCombination, ReedlineEvent>,
+    sequences: HashMap<Vec<KeyCombination>, ReedlineEvent>,
+    sequence_timeout: Duration,
+}
+
+/// Representation of [`Keybindings`] in configuration files
+#[derive(Serialize, Deserialize)]
+struct KeybindingsConfig {
+    /// Milliseconds to wait for the next key of a sequence
+    #[serde(default = "default_sequence_timeout_ms")]
+    sequence_timeout: u64,
+    #[serde(default)]
+    bindings: Vec<BindingConfig>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BindingConfig {
+    /// Key labels separated by spaces, like `C-r` or `C-x C-e`
+    keys: String,
+    event: ReedlineEvent,
+}
+
+impl From<Keybindings> for KeybindingsConfig {
+    fn from(keybindings: Keybindings) -> Self {
+        let mut bindings = keybindings
+            .bindings
+            .into_iter()
+            .map(|(key, event)| (vec![key], event))
+            .chain(keybindings.sequences)
+            .map(|(keys, event)| BindingConfig {
+                keys: sequence_label(&keys),
+                event,
+            })
+            .collect::<Vec<_>>();
+        bindings.sort_by(|a, b| a.keys.cmp(&b.keys));
+
+        Self {
+            sequence_timeout: u64::try_from(keybindings.sequence_timeout.as_millis())
+                .unwrap_or(u64::MAX),
+            bindings,
+        }
+    }
+}
+
+impl TryFrom<KeybindingsConfig> for Keybindings {
+    type Error = String;
+
+    fn try_from(config: KeybindingsConfig) -> std::result::Result<Self, String> {
+        let mut keybindings = Keybindings::new();
+        keybindings.set_sequence_timeout(Duration::from_millis(config.sequence_timeout));
+        for BindingConfig { keys, event } in config.bindings {
+            if matches!(&event, ReedlineEvent::UntilFound(events) if events.is_empty()) {
+                return Err(format!("the binding of `{}` has an empty UntilFound", keys));
+            }
+            let mut sequence = keys
+                .split_whitespace()
+                .map(parse_key_label)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            match sequence.len() {
+                0 => return Err("a binding needs at least one key".to_string()),
+                1 => {
+                    keybindings.bindings.insert(sequence.remove(0), event);
+                }
+                _ => {
+                    keybindings.sequences.insert(sequence, event);
+                }
+            }
+        }
+        Ok(keybindings)
+    }
 }
 
 impl Default for Keybindings {
@@ -29,6 +193,8 @@ impl Keybindings {
     pub fn new() -> Self {
         Self {
             bindings: HashMap::new(),
+            sequences: HashMap::new(),
+            sequence_timeout: DEFAULT_SEQUENCE_TIMEOUT,
         }
     }
 
@@ -81,6 +247,351 @@ impl Keybindings {
     pub fn get_keybindings(&self) -> &HashMap<KeyCombination, ReedlineEvent> {
         &self.bindings
     }
+
+    /// Adds a keybinding for a sequence of keys, like `jk` to leave the vi
+    /// insert mode or `C-x C-e` in emacs mode
+    ///
+    /// The keys of a sequence are only handled on their own if the next key
+    /// doesn't continue the sequence or none follows within the
+    /// [sequence timeout](Keybindings::set_sequence_timeout). In vi mode a
+    /// sequence bound to [`ReedlineEvent::Esc`] acts like the escape key.
+    ///
+    /// # Panics
+    ///
+    /// If `keys` is empty or `command` is an empty [`ReedlineEvent::UntilFound`]
+    ///
+    /// # Example
+    /// ```rust
+    /// use crossterm::event::{KeyCode, KeyModifiers};
+    /// use reedline::{default_vi_insert_keybindings, ReedlineEvent};
+    ///
+    /// let mut keybindings = default_vi_insert_keybindings();
+    /// keybindings.add_sequence_binding(
+    ///     &[
+    ///         (KeyModifiers::NONE, KeyCode::Char('j')),
+    ///         (KeyModifiers::NONE, KeyCode::Char('k')),
+    ///     ],
+    ///     ReedlineEvent::Esc,
+    /// );
+    /// ```
+    pub fn add_sequence_binding(
+        &mut self,
+        keys: &[(KeyModifiers, KeyCode)],
+        command: ReedlineEvent,
+    ) {
+        assert!(!keys.is_empty(), "A key sequence needs at least one key");
+        if let ReedlineEvent::UntilFound(subcommands) = &command {
+            assert!(
+                !subcommands.is_empty(),
+                "UntilFound should contain a series of potential events to handle"
+            );
+        }
+
+        self.sequences.insert(key_sequence(keys), command);
+    }
+
+    /// Remove the keybinding of a sequence of keys
+    ///
+    /// Returns `Some(ReedlineEvent)` if the sequence was previously bound to a particular [`ReedlineEvent`]
+    pub fn remove_sequence_binding(
+        &mut self,
+        keys: &[(KeyModifiers, KeyCode)],
+    ) -> Option<ReedlineEvent> {
+        self.sequences.remove(&key_sequence(keys))
+    }
+
+    /// Find the keybinding of a sequence of keys
+    pub fn find_sequence_binding(&self, keys: &[(KeyModifiers, KeyCode)]) -> Option<ReedlineEvent> {
+        self.sequence_binding(&key_sequence(keys))
+    }
+
+    pub(crate) fn sequence_binding(&self, keys: &[KeyCombination]) -> Option<ReedlineEvent> {
+        self.sequences.get(keys).cloned()
+    }
+
+    /// Check if `keys` are the start of a longer bound sequence
+    pub(crate) fn is_sequence_prefix(&self, keys: &[KeyCombination]) -> bool {
+        self.sequences
+            .keys()
+            .any(|sequence| sequence.len() > keys.len() && sequence.starts_with(keys))
+    }
+
+    pub(crate) fn has_sequences(&self) -> bool {
+        !self.sequences.is_empty()
+    }
+
+    /// Get the keybindings of key sequences
+    pub fn get_sequence_bindings(&self) -> &HashMap<Vec<KeyCombination>, ReedlineEvent> {
+        &self.sequences
+    }
+
+    /// How long to wait for the next key of a sequence before handling the keys
+    /// typed so far on their own, 500ms by default
+    pub fn sequence_timeout(&self) -> Duration {
+        self.sequence_timeout
+    }
+
+    /// Set how long to wait for the next key of a sequence
+    pub fn set_sequence_timeout(&mut self, timeout: Duration) {
+        self.sequence_timeout = timeout;
+    }
+
+    /// Report the bindings that don't work as they may be expected to by
+    /// themselves: keys bound twice and bindings delayed by longer sequences
+    ///
+    /// The conflicts with the keys an edit mode handles itself are reported by
+    /// [`EditMode::keybinding_conflicts`](crate::EditMode::keybinding_conflicts).
+    pub fn find_conflicts(&self) -> Vec<KeybindingConflict> {
+        let mut conflicts = self
+            .bindings
+            .keys()
+            .filter(|key| self.sequences.contains_key(std::slice::from_ref(*key)))
+            .map(|key| KeybindingConflict::Duplicate {
+                keys: vec![key.clone()],
+            })
+            .collect::<Vec<_>>();
+
+        let bound_keys = self
+            .bindings
+            .keys()
+            .map(|key| vec![key.clone()])
+            .chain(self.sequences.keys().cloned());
+        for keys in bound_keys {
+            let mut longer = self
+                .sequences
+                .keys()
+                .filter(|sequence| sequence.len() > keys.len() && sequence.starts_with(&keys))
+                .cloned()
+                .collect::<Vec<_>>();
+            if !longer.is_empty() {
+                longer.sort_by_key(|sequence| sequence_label(sequence));
+                conflicts.push(KeybindingConflict::Ambiguous { keys, longer });
+            }
+        }
+        sort_conflicts(&mut conflicts);
+        conflicts
+    }
+
+    /// Describe what a key combination is bound to, including the sequences
+    /// it starts, one binding per line like `C-x C-e: OpenEditor`
+    ///
+    /// Returns `None` if the key is neither bound nor starts a sequence
+    pub fn describe_binding(&self, modifier: KeyModifiers, key_code: KeyCode) -> Option<String> {
+        let key = KeyCombination { modifier, key_code };
+        let mut sequences = self
+            .sequences
+            .iter()
+            .filter(|(sequence, _)| sequence.first() == Some(&key))
+            .map(|(sequence, event)| (sequence_label(sequence), describe_event(event)))
+            .collect::<Vec<_>>();
+        sequences.sort();
+
+        let lines = self
+            .bindings
+            .get(&key)
+            .map(|event| (key_label(&key), describe_event(event)))
+            .into_iter()
+            .chain(sequences)
+            .map(|(keys, description)| format!("{}: {}", keys, description))
+            .collect::<Vec<_>>();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    /// Read keybindings from a TOML configuration (requires the `toml` feature)
+    ///
+    /// Every `[[bindings]]` entry binds `keys`, a key combination like `C-r`
+    /// or a sequence of them separated by spaces like `C-x C-e`, to a
+    /// serialized [`ReedlineEvent`]. Events without data are written as their
+    /// name, the others as a table with the name as the only key. The optional
+    /// `sequence_timeout` is given in milliseconds.
+    ///
+    /// The keys are labeled as in the keybinding hints: `C-`, `M-` and `S-`
+    /// add the control, alt and shift modifier, special keys use their
+    /// [`KeyCode`] name like `Enter`, `F1` or `Space`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use crossterm::event::{KeyCode, KeyModifiers};
+    /// use reedline::{Keybindings, ReedlineEvent};
+    ///
+    /// let keybindings = Keybindings::from_toml(
+    ///     r#"
+    /// sequence_timeout = 300
+    ///
+    /// [[bindings]]
+    /// keys = "C-r"
+    /// event = "SearchHistory"
+    ///
+    /// [[bindings]]
+    /// keys = "C-x C-e"
+    /// event = { Edit = [{ InsertString = "ls" }, "MoveToEnd"] }
+    /// "#,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     keybindings.find_binding(KeyModifiers::CONTROL, KeyCode::Char('r')),
+    ///     Some(ReedlineEvent::SearchHistory)
+    /// );
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn from_toml(config: &str) -> Result<Self> {
+        toml::from_str(config).map_err(|e| {
+            ReedlineError(ReedlineErrorVariants::KeybindingsConfigError(e.to_string()))
+        })
+    }
+
+    /// Write the keybindings as a TOML configuration read by
+    /// [`Keybindings::from_toml`], sorted by their keys
+    #[cfg(feature = "toml")]
+    pub fn to_toml(&self) -> Result<String> {
+        crate::utils::to_toml_string(self)
+            .map_err(|e| ReedlineError(ReedlineErrorVariants::KeybindingsConfigError(e)))
+    }
+}
+
+fn key_sequence(keys: &[(KeyModifiers, KeyCode)]) -> Vec<KeyCombination> {
+    keys.iter()
+        .map(|(modifier, key_code)| KeyCombination {
+            modifier: *modifier,
+            key_code: *key_code,
+        })
+        .collect()
+}
+
+/// Short label of a key combination like `C-x`, `M-f` or `Enter`
+pub(crate) fn key_label(key: &KeyCombination) -> String {
+    let key_code = match key.key_code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        key_code => format!("{:?}", key_code),
+    };
+    [
+        (KeyModifiers::CONTROL, "C-"),
+        (KeyModifiers::ALT, "M-"),
+        (KeyModifiers::SHIFT, "S-"),
+    ]
+    .iter()
+    .filter(|(modifier, _)| key.modifier.contains(*modifier))
+    .map(|(_, prefix)| *prefix)
+    .chain(std::iter::once(key_code.as_str()))
+    .collect()
+}
+
+/// Labels of a key sequence separated by spaces, like `C-x C-e`
+pub(crate) fn sequence_label(keys: &[KeyCombination]) -> String {
+    keys.iter().map(key_label).collect::<Vec<_>>().join(" ")
+}
+
+/// Sort the conflicts by the labels of their keys
+pub(crate) fn sort_conflicts(conflicts: &mut [KeybindingConflict]) {
+    conflicts.sort_by_key(|conflict| sequence_label(conflict.keys()));
+}
+
+/// Conflict of a single key binding with the handling of the keys typed as
+/// text, shared by the edit modes inserting characters like emacs or the vi
+/// insert mode
+pub(crate) fn text_input_conflict(key: &KeyCombination) -> Option<KeybindingConflict> {
+    let keys = vec![key.clone()];
+    match (key.modifier, key.key_code) {
+        (modifier, KeyCode::Char(_))
+            if modifier == KeyModifiers::NONE
+                || modifier == KeyModifiers::SHIFT
+                || modifier == KeyModifiers::CONTROL | KeyModifiers::ALT
+                || modifier == KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT =>
+        {
+            Some(KeybindingConflict::Shadowed {
+                keys,
+                reason: "the character is inserted".to_string(),
+            })
+        }
+        (_, KeyCode::Char(c)) if c != c.to_ascii_lowercase() => Some(uppercase_conflict(keys)),
+        (KeyModifiers::NONE, KeyCode::Enter) => Some(KeybindingConflict::Shadowed {
+            keys,
+            reason: "it submits the line".to_string(),
+        }),
+        _ => None,
+    }
+}
+
+/// Bindings of uppercase letters are unreachable where the edit mode looks up
+/// the keys in lowercase
+pub(crate) fn uppercase_conflict(keys: Vec<KeyCombination>) -> KeybindingConflict {
+    KeybindingConflict::Unreachable {
+        keys,
+        reason: "letters are looked up in lowercase".to_string(),
+    }
+}
+
+/// Parse a key combination from its [label](key_label)
+pub(crate) fn parse_key_label(label: &str) -> std::result::Result<KeyCombination, String> {
+    let mut modifier = KeyModifiers::NONE;
+    let mut key = label;
+    while key.chars().nth(1).is_some() {
+        modifier |= match key.get(..2) {
+            Some("C-") => KeyModifiers::CONTROL,
+            Some("M-") => KeyModifiers::ALT,
+            Some("S-") => KeyModifiers::SHIFT,
+            _ => break,
+        };
+        key = &key[2..];
+    }
+
+    let mut chars = key.chars();
+    let key_code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match key {
+            "Space" => KeyCode::Char(' '),
+            "Backspace" => KeyCode::Backspace,
+            "Enter" => KeyCode::Enter,
+            "Left" => KeyCode::Left,
+            "Right" => KeyCode::Right,
+            "Up" => KeyCode::Up,
+            "Down" => KeyCode::Down,
+            "Home" => KeyCode::Home,
+            "End" => KeyCode::End,
+            "PageUp" => KeyCode::PageUp,
+            "PageDown" => KeyCode::PageDown,
+            "Tab" => KeyCode::Tab,
+            "BackTab" => KeyCode::BackTab,
+            "Delete" => KeyCode::Delete,
+            "Insert" => KeyCode::Insert,
+            "Null" => KeyCode::Null,
+            "Esc" => KeyCode::Esc,
+            _ => match key.strip_prefix('F').map(str::parse) {
+                Some(Ok(n)) => KeyCode::F(n),
+                _ => return Err(format!("unknown key `{}`", label)),
+            },
+        },
+    };
+    Ok(KeyCombination { modifier, key_code })
+}
+
+/// Short description of what a bound event does
+pub(crate) fn describe_event(event: &ReedlineEvent) -> String {
+    match event {
+        ReedlineEvent::Edit(commands) => commands
+            .iter()
+            .map(|command| command.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        ReedlineEvent::Multiple(events) => events
+            .iter()
+            .map(describe_event)
+            .collect::<Vec<_>>()
+            .join(", "),
+        ReedlineEvent::UntilFound(events) => events
+            .iter()
+            .map(describe_event)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        ReedlineEvent::Menu(name) => format!("Menu {}", name),
+        ReedlineEvent::ExecuteHostCommand(command) | ReedlineEvent::Custom(command) => {
+            command.clone()
+        }
+        event => event.to_string(),
+    }
 }
 
 pub fn edit_bind(command: EditCommand) -> ReedlineEvent {
@@ -92,6 +603,7 @@ pub fn edit_bind(command: EditCommand) -> ReedlineEvent {
 /// `Ctrl-C`, `Ctrl-D`, `Ctrl-O`, `Ctrl-R`
 /// + `Esc`
 /// + `Ctrl-O` to open the external editor
+/// + `Shift-Delete` to remove the selected entry of the history menu or search
 pub fn add_common_control_bindings(kb: &mut Keybindings) {
     use KeyCode as KC;
     use KeyModifiers as KM;
@@ -102,6 +614,7 @@ pub fn add_common_control_bindings(kb: &mut Keybindings) {
     kb.add_binding(KM::CONTROL, KC::Char('l'), ReedlineEvent::ClearScreen);
     kb.add_binding(KM::CONTROL, KC::Char('r'), ReedlineEvent::SearchHistory);
     kb.add_binding(KM::CONTROL, KC::Char('o'), ReedlineEvent::OpenEditor);
+    kb.add_binding(KM::SHIFT, KC::Delete, ReedlineEvent::DeleteHistoryEntry);
 }
 /// Add the arrow navigation and its `Ctrl` variants
 pub fn add_common_navigation_bindings(kb: &mut Keybindings) {
@@ -195,3 +708,174 @@ pub fn add_common_edit_bindings(kb: &mut Keybindings) {
     kb.add_binding(KM::CONTROL, KC::Char('h'), edit_bind(EC::Backspace));
     kb.add_binding(KM::CONTROL, KC::Char('w'), edit_bind(EC::BackspaceWord));
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "toml")]
+    use crate::{default_emacs_keybindings, default_vi_normal_keybindings};
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(KeyModifiers::CONTROL, KeyCode::Char('x'), "C-x")]
+    #[case(KeyModifiers::CONTROL | KeyModifiers::ALT, KeyCode::Char('-'), "C-M--")]
+    #[case(KeyModifiers::SHIFT, KeyCode::Char('S'), "S-S")]
+    #[case(KeyModifiers::NONE, KeyCode::Char(' '), "Space")]
+    #[case(KeyModifiers::ALT, KeyCode::F(12), "M-F12")]
+    #[case(KeyModifiers::NONE, KeyCode::BackTab, "BackTab")]
+    fn test_key_label_round_trip(
+        #[case] modifier: KeyModifiers,
+        #[case] key_code: KeyCode,
+        #[case] label: &str,
+    ) {
+        let key = KeyCombination { modifier, key_code };
+        assert_eq!(key_label(&key), label);
+        assert_eq!(parse_key_label(label), Ok(key));
+    }
+
+    #[test]
+    fn unknown_key_label_is_an_error() {
+        assert!(parse_key_label("C-Return").is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[rstest]
+    #[case(default_emacs_keybindings())]
+    #[case(default_vi_normal_keybindings())]
+    fn test_toml_round_trip(#[case] mut keybindings: Keybindings) {
+        keybindings.add_sequence_binding(
+            &[
+                (KeyModifiers::CONTROL, KeyCode::Char('x')),
+                (KeyModifiers::NONE, KeyCode::Char(' ')),
+            ],
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertString("\"quoted\"".into())]),
+                ReedlineEvent::Menu("completion_menu".into()),
+            ]),
+        );
+        let config = keybindings.to_toml().unwrap();
+        let parsed = Keybindings::from_toml(&config).unwrap();
+
+        assert_eq!(parsed.bindings, keybindings.bindings);
+        assert_eq!(parsed.sequences, keybindings.sequences);
+        assert_eq!(parsed.sequence_timeout, keybindings.sequence_timeout);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_config_format() {
+        let mut keybindings = Keybindings::new();
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('r'),
+            ReedlineEvent::SearchHistory,
+        );
+        keybindings.add_sequence_binding(
+            &[
+                (KeyModifiers::NONE, KeyCode::Char('j')),
+                (KeyModifiers::NONE, KeyCode::Char('k')),
+            ],
+            edit_bind(EditCommand::InsertChar('x')),
+        );
+
+        assert_eq!(
+            keybindings.to_toml().unwrap(),
+            r#"sequence_timeout = 500
+
+[[bindings]]
+event = "SearchHistory"
+keys = "C-r"
+
+[[bindings]]
+event = { Edit = [{ InsertChar = "x" }] }
+keys = "j k"
+"#
+        );
+    }
+
+    fn key(c: char) -> KeyCombination {
+        KeyCombination {
+            modifier: KeyModifiers::CONTROL,
+            key_code: KeyCode::Char(c),
+        }
+    }
+
+    #[test]
+    fn duplicate_and_ambiguous_bindings_are_conflicts() {
+        let mut keybindings = Keybindings::new();
+        keybindings.add_binding(KeyModifiers::CONTROL, KeyCode::Char('a'), ReedlineEvent::Up);
+        keybindings.add_binding(KeyModifiers::CONTROL, KeyCode::Char('x'), ReedlineEvent::Up);
+        keybindings.add_sequence_binding(
+            &[(KeyModifiers::CONTROL, KeyCode::Char('a'))],
+            ReedlineEvent::Down,
+        );
+        keybindings.add_sequence_binding(
+            &[
+                (KeyModifiers::CONTROL, KeyCode::Char('x')),
+                (KeyModifiers::CONTROL, KeyCode::Char('e')),
+            ],
+            ReedlineEvent::OpenEditor,
+        );
+
+        let conflicts = keybindings.find_conflicts();
+        assert_eq!(
+            conflicts,
+            vec![
+                KeybindingConflict::Duplicate {
+                    keys: vec![key('a')]
+                },
+                KeybindingConflict::Ambiguous {
+                    keys: vec![key('x')],
+                    longer: vec![vec![key('x'), key('e')]]
+                },
+            ]
+        );
+        assert_eq!(
+            conflicts[1].to_string(),
+            "C-x starts C-x C-e, its binding only runs after the sequence timeout"
+        );
+    }
+
+    #[test]
+    fn describe_binding_lists_the_sequences() {
+        let mut keybindings = Keybindings::new();
+        keybindings.add_binding(KeyModifiers::CONTROL, KeyCode::Char('x'), ReedlineEvent::Up);
+        keybindings.add_sequence_binding(
+            &[
+                (KeyModifiers::CONTROL, KeyCode::Char('x')),
+                (KeyModifiers::CONTROL, KeyCode::Char('e')),
+            ],
+            ReedlineEvent::OpenEditor,
+        );
+
+        assert_eq!(
+            keybindings.describe_binding(KeyModifiers::CONTROL, KeyCode::Char('x')),
+            Some("C-x: Up\nC-x C-e: OpenEditor".to_string())
+        );
+        assert_eq!(
+            keybindings.describe_binding(KeyModifiers::CONTROL, KeyCode::Char('e')),
+            None
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[rstest]
+    #[case(
+        "[[bindings]]\nkeys = \"C-Return\"\nevent = \"Enter\"",
+        "unknown key `C-Return`"
+    )]
+    #[case(
+        "[[bindings]]\nkeys = \"\"\nevent = \"Enter\"",
+        "a binding needs at least one key"
+    )]
+    #[case(
+        "[[bindings]]\nkeys = \"a\"\nevent = \"Unknown\"",
+        "unknown variant `Unknown`"
+    )]
+    #[case("[[bindings]\n", "expected a right bracket")]
+    fn invalid_toml_config(#[case] config: &str, #[case] expected: &str) {
+        let error = Keybindings::from_toml(config).unwrap_err().to_string();
+        assert!(error.contains(expected), "{}", error);
+    }
+}
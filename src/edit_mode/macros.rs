@@ -0,0 +1,138 @@
+use crossterm::event::Event;
+use std::collections::HashMap;
+
+/// Records keyboard macros and keeps them for replay
+///
+/// Macros are stored under a register name. Recording into the uppercase
+/// variant of a letter appends to the macro stored under the lowercase letter.
+/// The edit mode is responsible for passing every incoming event to
+/// [`MacroRecorder::record`] and for parsing the events of a replayed macro.
+#[derive(Default)]
+pub(crate) struct MacroRecorder {
+    macros: HashMap<char, Vec<Event>>,
+    recording: Option<(char, Vec<Event>)>,
+    last_replayed: Option<char>,
+    replaying: bool,
+}
+
+impl MacroRecorder {
+    /// Start recording a macro into `register`, replacing a recording in progress
+    pub fn start(&mut self, register: char) {
+        let events = if register.is_uppercase() {
+            self.macros
+                .get(&register.to_ascii_lowercase())
+                .cloned()
+                .unwrap_or_default()
+        } else {
+            vec![]
+        };
+        self.recording = Some((register.to_ascii_lowercase(), events));
+    }
+
+    /// Stop the recording and store the macro
+    pub fn stop(&mut self) {
+        if let Some((register, events)) = self.recording.take() {
+            self.macros.insert(register, events);
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Add the event to the macro being recorded, if any
+    pub fn record(&mut self, event: Event) {
+        if let Some((_, events)) = &mut self.recording {
+            events.push(event);
+        }
+    }
+
+    /// Get the events of the macro in `register` for replay, `@` refers to the
+    /// macro replayed last.
+    ///
+    /// Returns `None` while another macro is replayed, so a macro replaying
+    /// itself doesn't recurse forever. [`MacroRecorder::finish_replay`] has to
+    /// be called once the events are parsed.
+    pub fn replay(&mut self, register: char) -> Option<Vec<Event>> {
+        if self.replaying {
+            return None;
+        }
+        let register = match register {
+            '@' => self.last_replayed?,
+            register => register.to_ascii_lowercase(),
+        };
+        let events = self.macros.get(&register)?.clone();
+        self.last_replayed = Some(register);
+        self.replaying = true;
+        Some(events)
+    }
+
+    /// Mark the replay started with [`MacroRecorder::replay`] as done
+    pub fn finish_replay(&mut self) {
+        self.replaying = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use pretty_assertions::assert_eq;
+
+    fn key(c: char) -> Event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::NONE,
+        })
+    }
+
+    fn record(recorder: &mut MacroRecorder, register: char, keys: &str) {
+        recorder.start(register);
+        for c in keys.chars() {
+            recorder.record(key(c));
+        }
+        recorder.stop();
+    }
+
+    #[test]
+    fn recorded_macro_is_replayed() {
+        let mut recorder = MacroRecorder::default();
+        recorder.record(key('x'));
+        record(&mut recorder, 'a', "ab");
+
+        assert!(!recorder.is_recording());
+        assert_eq!(recorder.replay('a'), Some(vec![key('a'), key('b')]));
+        recorder.finish_replay();
+        assert_eq!(recorder.replay('@'), Some(vec![key('a'), key('b')]));
+        recorder.finish_replay();
+        assert_eq!(recorder.replay('b'), None);
+    }
+
+    #[test]
+    fn uppercase_register_appends() {
+        let mut recorder = MacroRecorder::default();
+        record(&mut recorder, 'a', "a");
+        record(&mut recorder, 'A', "b");
+
+        assert_eq!(recorder.replay('a'), Some(vec![key('a'), key('b')]));
+    }
+
+    #[test]
+    fn no_replay_while_replaying() {
+        let mut recorder = MacroRecorder::default();
+        record(&mut recorder, 'a', "a");
+
+        assert!(recorder.replay('a').is_some());
+        assert_eq!(recorder.replay('a'), None);
+        recorder.finish_replay();
+        assert!(recorder.replay('a').is_some());
+    }
+
+    #[test]
+    fn nothing_to_replay_before_first_replay() {
+        let mut recorder = MacroRecorder::default();
+        record(&mut recorder, 'a', "a");
+
+        assert_eq!(recorder.replay('@'), None);
+    }
+}
@@ -1,9 +1,13 @@
 mod base;
 mod emacs;
 mod keybindings;
+mod macros;
+mod sequences;
 mod vi;
 
 pub use base::EditMode;
 pub use emacs::{default_emacs_keybindings, Emacs};
-pub use keybindings::Keybindings;
+pub use keybindings::{KeyCombination, KeybindingConflict, Keybindings};
+pub(crate) use macros::MacroRecorder;
+pub(crate) use sequences::{combine_events, SequenceInput, SequenceMatcher};
 pub use vi::{default_vi_insert_keybindings, default_vi_normal_keybindings, Vi};
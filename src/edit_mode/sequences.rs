@@ -0,0 +1,234 @@
+use super::keybindings::{describe_event, key_label, KeyCombination, Keybindings};
+use crate::enums::ReedlineEvent;
+use crossterm::event::{Event, KeyEvent};
+use std::time::{Duration, Instant};
+
+/// Input resolved by the [`SequenceMatcher`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SequenceInput {
+    /// The event of a completed key sequence
+    Sequence(ReedlineEvent),
+    /// An event that is not part of a sequence, handled by the edit mode as usual
+    Event(Event),
+}
+
+/// Collects the keys of the key sequences bound in [`Keybindings`]
+///
+/// The edit mode passes every event to [`SequenceMatcher::process`] and handles
+/// the resolved input. Keys that may continue a sequence are held back until
+/// the sequence is complete, broken by another key or timed out. The edit mode
+/// is responsible for calling [`SequenceMatcher::flush`] once
+/// [`SequenceMatcher::remaining_time`] is up.
+#[derive(Default)]
+pub(crate) struct SequenceMatcher {
+    pending: Vec<Event>,
+    last_key: Option<Instant>,
+}
+
+impl SequenceMatcher {
+    /// Add the event to the pending keys and resolve the keys that can't be
+    /// part of a longer sequence
+    pub fn process(&mut self, keybindings: &Keybindings, event: Event) -> Vec<SequenceInput> {
+        if self.pending.is_empty() && !keybindings.has_sequences() {
+            return vec![SequenceInput::Event(event)];
+        }
+        self.pending.push(event);
+        self.last_key = Some(Instant::now());
+        self.resolve(keybindings, false)
+    }
+
+    /// Resolve all pending keys after the timeout, completing the longest
+    /// sequence they start with
+    pub fn flush(&mut self, keybindings: &Keybindings) -> Vec<SequenceInput> {
+        self.resolve(keybindings, true)
+    }
+
+    /// Time left to wait for the next key of a sequence, `None` without any
+    /// pending keys
+    pub fn remaining_time(&self, timeout: Duration) -> Option<Duration> {
+        self.last_key
+            .filter(|_| !self.pending.is_empty())
+            .map(|last_key| timeout.saturating_sub(last_key.elapsed()))
+    }
+
+    /// The keys continuing the pending sequence together with the description
+    /// of the event they complete
+    pub fn continuations(&self, keybindings: &Keybindings) -> Vec<(String, String)> {
+        let keys = self.pending_keys();
+        if keys.is_empty() {
+            return vec![];
+        }
+        let mut continuations = keybindings
+            .get_sequence_bindings()
+            .iter()
+            .filter(|(sequence, _)| sequence.len() > keys.len() && sequence.starts_with(&keys))
+            .map(|(sequence, event)| {
+                let keys = sequence[keys.len()..]
+                    .iter()
+                    .map(key_label)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                (keys, describe_event(event))
+            })
+            .collect::<Vec<_>>();
+        continuations.sort();
+        continuations
+    }
+
+    fn resolve(&mut self, keybindings: &Keybindings, timed_out: bool) -> Vec<SequenceInput> {
+        let mut resolved = vec![];
+        while !self.pending.is_empty() {
+            let keys = self.pending_keys();
+            if !timed_out
+                && keys.len() == self.pending.len()
+                && keybindings.is_sequence_prefix(&keys)
+            {
+                break;
+            }
+
+            let sequence = (1..=keys.len())
+                .rev()
+                .find_map(|len| Some(len).zip(keybindings.sequence_binding(&keys[..len])));
+            match sequence {
+                Some((len, event)) => {
+                    self.pending.drain(..len);
+                    resolved.push(SequenceInput::Sequence(event));
+                }
+                None => resolved.push(SequenceInput::Event(self.pending.remove(0))),
+            }
+        }
+        if self.pending.is_empty() {
+            self.last_key = None;
+        }
+        resolved
+    }
+
+    /// The key combinations of the pending events up to the first other event
+    fn pending_keys(&self) -> Vec<KeyCombination> {
+        self.pending
+            .iter()
+            .map_while(|event| match event {
+                Event::Key(KeyEvent { code, modifiers }) => Some(KeyCombination {
+                    modifier: *modifiers,
+                    key_code: *code,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Combine the events of the resolved input into a single event, leaving out
+/// the keys without an event like the prefix of a chord
+pub(crate) fn combine_events(mut events: Vec<ReedlineEvent>) -> ReedlineEvent {
+    events.retain(|event| *event != ReedlineEvent::None);
+    match events.len() {
+        0 => ReedlineEvent::None,
+        1 => events.remove(0),
+        _ => ReedlineEvent::Multiple(events),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use pretty_assertions::assert_eq;
+
+    fn key(c: char) -> Event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::NONE,
+        })
+    }
+
+    fn keybindings() -> Keybindings {
+        let mut keybindings = Keybindings::new();
+        let keys = |keys: &str| {
+            keys.chars()
+                .map(|c| (KeyModifiers::NONE, KeyCode::Char(c)))
+                .collect::<Vec<_>>()
+        };
+        keybindings.add_sequence_binding(&keys("jk"), ReedlineEvent::Esc);
+        keybindings.add_sequence_binding(&keys("ab"), ReedlineEvent::Enter);
+        keybindings.add_sequence_binding(&keys("abc"), ReedlineEvent::ClearScreen);
+        keybindings
+    }
+
+    #[test]
+    fn completed_sequence_is_matched() {
+        let keybindings = keybindings();
+        let mut matcher = SequenceMatcher::default();
+
+        assert_eq!(matcher.process(&keybindings, key('j')), vec![]);
+        assert!(matcher.remaining_time(Duration::from_secs(1)).is_some());
+        assert_eq!(
+            matcher.process(&keybindings, key('k')),
+            vec![SequenceInput::Sequence(ReedlineEvent::Esc)]
+        );
+        assert_eq!(matcher.remaining_time(Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn broken_sequence_passes_the_keys_on() {
+        let keybindings = keybindings();
+        let mut matcher = SequenceMatcher::default();
+
+        assert_eq!(matcher.process(&keybindings, key('j')), vec![]);
+        assert_eq!(
+            matcher.process(&keybindings, key('j')),
+            vec![SequenceInput::Event(key('j'))]
+        );
+        assert_eq!(
+            matcher.process(&keybindings, key('x')),
+            vec![
+                SequenceInput::Event(key('j')),
+                SequenceInput::Event(key('x'))
+            ]
+        );
+    }
+
+    #[test]
+    fn ambiguous_sequence_waits_for_timeout() {
+        let keybindings = keybindings();
+        let mut matcher = SequenceMatcher::default();
+
+        assert_eq!(matcher.process(&keybindings, key('a')), vec![]);
+        assert_eq!(matcher.process(&keybindings, key('b')), vec![]);
+        assert_eq!(
+            matcher.flush(&keybindings),
+            vec![SequenceInput::Sequence(ReedlineEvent::Enter)]
+        );
+
+        assert_eq!(matcher.process(&keybindings, key('a')), vec![]);
+        assert_eq!(
+            matcher.flush(&keybindings),
+            vec![SequenceInput::Event(key('a'))]
+        );
+    }
+
+    #[test]
+    fn continuations_of_pending_keys() {
+        let keybindings = keybindings();
+        let mut matcher = SequenceMatcher::default();
+        assert_eq!(matcher.continuations(&keybindings), vec![]);
+
+        matcher.process(&keybindings, key('a'));
+        assert_eq!(
+            matcher.continuations(&keybindings),
+            vec![
+                ("b".to_string(), "Enter".to_string()),
+                ("b c".to_string(), "ClearScreen".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn no_sequences_pass_through() {
+        let mut matcher = SequenceMatcher::default();
+        assert_eq!(
+            matcher.process(&Keybindings::new(), key('j')),
+            vec![SequenceInput::Event(key('j'))]
+        );
+    }
+}
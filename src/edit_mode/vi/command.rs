@@ -1,6 +1,10 @@
-use super::{motion::Motion, parser::ReedlineOption, ViToTill};
+use super::{
+    motion::{parse_motion, Motion},
+    parser::{parse_number, ReedlineOption, MAX_COUNT},
+    ViToTill,
+};
 use crate::{EditCommand, ReedlineEvent, Vi};
-use std::iter::Peekable;
+use std::{iter::Peekable, time::Duration};
 
 pub fn parse_command<'iter, I>(vi: &Vi, input: &mut Peekable<I>) -> Option<Command>
 where
@@ -9,7 +13,26 @@ where
     match input.peek() {
         Some('d') => {
             let _ = input.next();
-            Some(Command::Delete)
+            match input.peek() {
+                Some('s') => {
+                    let _ = input.next();
+                    match input.next() {
+                        Some(c) => Some(Command::DeleteSurrounding(*c)),
+                        None => Some(Command::Incomplete),
+                    }
+                }
+                _ => Some(Command::Delete),
+            }
+        }
+        Some('y') => {
+            let _ = input.next();
+            match input.peek() {
+                Some('s') => {
+                    let _ = input.next();
+                    parse_surround_with(input)
+                }
+                _ => Some(Command::Yank),
+            }
         }
         Some('p') => {
             let _ = input.next();
@@ -81,7 +104,16 @@ where
         }
         Some('c') => {
             let _ = input.next();
-            Some(Command::Change)
+            match input.peek() {
+                Some('s') => {
+                    let _ = input.next();
+                    match (input.next(), input.next()) {
+                        (Some(from), Some(to)) => Some(Command::ChangeSurrounding(*from, *to)),
+                        _ => Some(Command::Incomplete),
+                    }
+                }
+                _ => Some(Command::Change),
+            }
         }
         Some('x') => {
             let _ = input.next();
@@ -98,10 +130,42 @@ where
             let _ = input.next();
             Some(Command::SubstituteCharWithInsert)
         }
+        Some('m') => {
+            let _ = input.next();
+            match input.peek() {
+                Some(c) if c.is_ascii_lowercase() => Some(Command::SetMark(**c)),
+                Some(_) => None,
+                None => Some(Command::Incomplete),
+            }
+        }
+        Some('`') => {
+            let _ = input.next();
+            match input.peek() {
+                Some(c) if c.is_ascii_lowercase() => Some(Command::JumpToMark(**c)),
+                Some(_) => None,
+                None => Some(Command::Incomplete),
+            }
+        }
+        Some('\'') => {
+            let _ = input.next();
+            match input.peek() {
+                Some(c) if c.is_ascii_lowercase() => Some(Command::JumpToMarkLine(**c)),
+                Some(_) => None,
+                None => Some(Command::Incomplete),
+            }
+        }
         Some('?') => {
             let _ = input.next();
             Some(Command::HistorySearch)
         }
+        Some('J') => {
+            let _ = input.next();
+            Some(Command::JoinLines)
+        }
+        Some('%') => {
+            let _ = input.next();
+            Some(Command::MoveToMatchingBracket)
+        }
         Some('C') => {
             let _ = input.next();
             Some(Command::ChangeToLineEnd)
@@ -166,14 +230,89 @@ where
             let _ = input.next();
             Some(Command::Switchcase)
         }
+        Some('g') => {
+            let _ = input.next();
+            match input.peek() {
+                Some('-') => {
+                    let _ = input.next();
+                    Some(Command::UndoEarlier)
+                }
+                Some('+') => {
+                    let _ = input.next();
+                    Some(Command::RedoLater)
+                }
+                Some('j') => {
+                    let _ = input.next();
+                    Some(Command::MoveDisplayLineDown)
+                }
+                Some('k') => {
+                    let _ = input.next();
+                    Some(Command::MoveDisplayLineUp)
+                }
+                Some(c @ ('U' | 'u' | '~')) => {
+                    let transform = match c {
+                        'U' => Command::UppercaseWith,
+                        'u' => Command::LowercaseWith,
+                        _ => Command::ToggleCaseWith,
+                    };
+                    let c = **c;
+                    let _ = input.next();
+                    parse_case_transform(input, c, transform)
+                }
+                Some(_) => None,
+                None => Some(Command::Incomplete),
+            }
+        }
         _ => None,
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Parse the motion and the delimiter of `ys{motion}{char}`, `yss{char}` surrounds the line
+fn parse_surround_with<'iter, I>(input: &mut Peekable<I>) -> Option<Command>
+where
+    I: Iterator<Item = &'iter char>,
+{
+    let count = parse_number(input).unwrap_or(1);
+    let motion = match input.peek() {
+        Some('s') => {
+            let _ = input.next();
+            Some(Motion::Line)
+        }
+        _ => parse_motion(input, None),
+    };
+    match (motion, input.next()) {
+        (Some(motion), Some(c)) => Some(Command::SurroundWith(motion, count, *c)),
+        (_, None) => Some(Command::Incomplete),
+        (None, Some(_)) => None,
+    }
+}
+
+/// Parse the motion of `gU{motion}`, `gu{motion}` and `g~{motion}`, repeating
+/// the last character of the command like `gUU` changes the line
+fn parse_case_transform<'iter, I>(
+    input: &mut Peekable<I>,
+    command_char: char,
+    transform: fn(Motion, usize) -> Command,
+) -> Option<Command>
+where
+    I: Iterator<Item = &'iter char>,
+{
+    let count = parse_number(input).unwrap_or(1);
+    match input.peek() {
+        Some(c) if **c == command_char => {
+            let _ = input.next();
+            Some(transform(Motion::Line, count))
+        }
+        Some(_) => parse_motion(input, None).map(|motion| transform(motion, count)),
+        None => Some(Command::Incomplete),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Command {
     Incomplete,
     Delete,
+    Yank,
     DeleteChar,
     ReplaceChar(char),
     SubstituteCharWithInsert,
@@ -183,6 +322,8 @@ pub enum Command {
     MoveRight,
     MoveUp,
     MoveDown,
+    MoveDisplayLineUp,
+    MoveDisplayLineDown,
     MoveWordRightStart,
     MoveBigWordRightStart,
     MoveWordRightEnd,
@@ -208,6 +349,19 @@ pub enum Command {
     ReverseToTill(ViToTill),
     HistorySearch,
     Switchcase,
+    UndoEarlier,
+    RedoLater,
+    SetMark(char),
+    JumpToMark(char),
+    JumpToMarkLine(char),
+    MoveToMatchingBracket,
+    JoinLines,
+    SurroundWith(Motion, usize, char),
+    UppercaseWith(Motion, usize),
+    LowercaseWith(Motion, usize),
+    ToggleCaseWith(Motion, usize),
+    ChangeSurrounding(char, char),
+    DeleteSurrounding(char),
 }
 
 impl Command {
@@ -215,6 +369,10 @@ impl Command {
         match self {
             Self::MoveUp => vec![ReedlineOption::Event(ReedlineEvent::Up)],
             Self::MoveDown => vec![ReedlineOption::Event(ReedlineEvent::Down)],
+            Self::MoveDisplayLineUp => vec![ReedlineOption::Event(ReedlineEvent::DisplayLineUp)],
+            Self::MoveDisplayLineDown => {
+                vec![ReedlineOption::Event(ReedlineEvent::DisplayLineDown)]
+            }
             Self::MoveLeft => vec![ReedlineOption::Event(ReedlineEvent::Left)],
             Self::MoveRight => vec![ReedlineOption::Event(ReedlineEvent::Right)],
             Self::MoveToLineStart => vec![ReedlineOption::Edit(EditCommand::MoveToLineStart)],
@@ -266,16 +424,90 @@ impl Command {
             Self::SubstituteCharWithInsert => vec![ReedlineOption::Edit(EditCommand::CutChar)],
             Self::HistorySearch => vec![ReedlineOption::Event(ReedlineEvent::SearchHistory)],
             Self::Switchcase => vec![ReedlineOption::Edit(EditCommand::SwitchcaseChar)],
+            Self::UndoEarlier => vec![ReedlineOption::Edit(EditCommand::UndoEarlier(
+                Duration::ZERO,
+            ))],
+            Self::RedoLater => vec![ReedlineOption::Edit(EditCommand::RedoLater(Duration::ZERO))],
+            Self::SurroundWith(..)
+            | Self::UppercaseWith(..)
+            | Self::LowercaseWith(..)
+            | Self::ToggleCaseWith(..) => self.to_reedline_with_count(1),
+            Self::ChangeSurrounding(from, to) => {
+                vec![ReedlineOption::Edit(EditCommand::ChangeSurrounding(
+                    *from, *to,
+                ))]
+            }
+            Self::DeleteSurrounding(c) => {
+                vec![ReedlineOption::Edit(EditCommand::DeleteSurrounding(*c))]
+            }
+            Self::SetMark(c) => vec![ReedlineOption::Edit(EditCommand::SetMark(*c))],
+            Self::JumpToMark(c) => vec![ReedlineOption::Edit(EditCommand::JumpToMark(*c))],
+            Self::JumpToMarkLine(c) => {
+                vec![ReedlineOption::Edit(EditCommand::JumpToMarkLine(*c))]
+            }
+            Self::MoveToMatchingBracket => {
+                vec![ReedlineOption::Edit(EditCommand::MoveToMatchingBracket)]
+            }
+            Self::JoinLines => vec![ReedlineOption::Edit(EditCommand::JoinLines)],
             // Mark a command as incomplete whenever a motion is required to finish the command
-            Self::Delete | Self::Change | Self::Incomplete => vec![ReedlineOption::Incomplete],
+            Self::Delete | Self::Yank | Self::Change | Self::Incomplete => {
+                vec![ReedlineOption::Incomplete]
+            }
         }
     }
 
+    /// Events of the command given `count` times, the commands applied to a
+    /// motion apply to `count` times the motion instead of being repeated
+    pub fn to_reedline_with_count(&self, count: usize) -> Vec<ReedlineOption> {
+        let count_with = |motion_count: &usize| motion_count.saturating_mul(count).min(MAX_COUNT);
+        match self {
+            Self::SurroundWith(motion, motion_count, c) => apply_to_motion(
+                motion,
+                count_with(motion_count),
+                EditCommand::SurroundSelection(*c),
+            ),
+            Self::UppercaseWith(motion, motion_count) => {
+                apply_to_motion(motion, count_with(motion_count), EditCommand::UppercaseWord)
+            }
+            Self::LowercaseWith(motion, motion_count) => {
+                apply_to_motion(motion, count_with(motion_count), EditCommand::LowercaseWord)
+            }
+            Self::ToggleCaseWith(motion, motion_count) => {
+                apply_to_motion(motion, count_with(motion_count), EditCommand::ToggleCase)
+            }
+            _ => (0..count).flat_map(|_| self.to_reedline()).collect(),
+        }
+    }
+
+    /// Events of the operator applied to `count` times the motion
     pub fn to_reedline_with_motion(
         &self,
         motion: &Motion,
-        count: &Option<usize>,
+        count: usize,
     ) -> Option<Vec<ReedlineOption>> {
+        // Repeating the cut of a text object would cut the object left behind,
+        // the bigger object is selected instead. The copying commands don't move
+        // the cursor, so repeating them copies the same text again.
+        if count > 1 {
+            match (self, motion) {
+                (Self::Delete | Self::Change, Motion::TextObject(_)) => {
+                    let mut edits = apply_to_motion(motion, count, EditCommand::CutSelection);
+                    if *self == Self::Change {
+                        edits.push(ReedlineOption::Event(ReedlineEvent::Repaint));
+                    }
+                    return Some(edits);
+                }
+                (Self::Yank, _) => {
+                    return Some(apply_to_motion(
+                        motion,
+                        count,
+                        EditCommand::CopySelectionAndMoveToStart,
+                    ))
+                }
+                _ => {}
+            }
+        }
+
         let edits = match self {
             Self::Delete => match motion {
                 Motion::End => Some(vec![ReedlineOption::Edit(EditCommand::CutToLineEnd)]),
@@ -307,6 +539,43 @@ impl Command {
                     Some(vec![ReedlineOption::Edit(EditCommand::CutLeftBefore(*c))])
                 }
                 Motion::Start => Some(vec![ReedlineOption::Edit(EditCommand::CutFromLineStart)]),
+                Motion::TextObject(text_object) => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CutTextObject(*text_object),
+                )]),
+            },
+            Self::Yank => match motion {
+                Motion::End => Some(vec![ReedlineOption::Edit(EditCommand::CopyToLineEnd)]),
+                Motion::Line => Some(vec![ReedlineOption::Edit(EditCommand::CopyCurrentLine)]),
+                Motion::NextWord => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyWordRightToNext)])
+                }
+                Motion::NextBigWord => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CopyBigWordRightToNext,
+                )]),
+                Motion::NextWordEnd => Some(vec![ReedlineOption::Edit(EditCommand::CopyWordRight)]),
+                Motion::NextBigWordEnd => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyBigWordRight)])
+                }
+                Motion::PreviousWord => Some(vec![ReedlineOption::Edit(EditCommand::CopyWordLeft)]),
+                Motion::PreviousBigWord => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyBigWordLeft)])
+                }
+                Motion::RightUntil(c) => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyRightUntil(*c))])
+                }
+                Motion::RightBefore(c) => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyRightBefore(*c))])
+                }
+                Motion::LeftUntil(c) => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyLeftUntil(*c))])
+                }
+                Motion::LeftBefore(c) => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyLeftBefore(*c))])
+                }
+                Motion::Start => Some(vec![ReedlineOption::Edit(EditCommand::CopyFromLineStart)]),
+                Motion::TextObject(text_object) => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CopyTextObject(*text_object),
+                )]),
             },
             Self::Change => match motion {
                 Motion::End => Some(vec![
@@ -362,22 +631,93 @@ impl Command {
                     ReedlineOption::Edit(EditCommand::CutFromLineStart),
                     ReedlineOption::Event(ReedlineEvent::Repaint),
                 ]),
+                Motion::TextObject(text_object) => Some(vec![
+                    ReedlineOption::Edit(EditCommand::CutTextObject(*text_object)),
+                    ReedlineOption::Event(ReedlineEvent::Repaint),
+                ]),
             },
             _ => None,
         };
 
-        match count {
-            Some(count) => edits.map(|edits| {
-                std::iter::repeat(edits)
-                    .take(*count)
-                    .flatten()
-                    .collect::<Vec<ReedlineOption>>()
-            }),
-            None => edits,
+        edits.map(|edits| {
+            (0..count)
+                .flat_map(|_| edits.iter().cloned())
+                .collect::<Vec<ReedlineOption>>()
+        })
+    }
+}
+
+/// Edits selecting the text covered by `count` times the motion, like the
+/// operators do, f and t include the character they move to
+fn selection_for_motion(motion: &Motion, count: usize) -> Vec<EditCommand> {
+    let repeated = |edit: EditCommand| (0..count).map(move |_| edit.clone());
+    let select_to = |moves: Vec<EditCommand>| {
+        std::iter::once(EditCommand::StartSelection)
+            .chain(moves)
+            .collect()
+    };
+    match motion {
+        Motion::NextWord => select_to(repeated(EditCommand::MoveWordRightStart).collect()),
+        Motion::NextBigWord => select_to(repeated(EditCommand::MoveBigWordRightStart).collect()),
+        Motion::NextWordEnd => select_to(
+            repeated(EditCommand::MoveWordRightEnd)
+                .chain([EditCommand::MoveRight])
+                .collect(),
+        ),
+        Motion::NextBigWordEnd => select_to(
+            repeated(EditCommand::MoveBigWordRightEnd)
+                .chain([EditCommand::MoveRight])
+                .collect(),
+        ),
+        Motion::PreviousWord => select_to(repeated(EditCommand::MoveWordLeft).collect()),
+        Motion::PreviousBigWord => select_to(repeated(EditCommand::MoveBigWordLeft).collect()),
+        // Every further line is reached by stepping over the newline
+        Motion::Line => [
+            EditCommand::MoveToLineStart,
+            EditCommand::StartSelection,
+            EditCommand::MoveToLineEnd,
+        ]
+        .into_iter()
+        .chain((1..count).flat_map(|_| [EditCommand::MoveRight, EditCommand::MoveToLineEnd]))
+        .collect(),
+        Motion::Start => select_to(vec![EditCommand::MoveToLineStart]),
+        Motion::End => select_to(vec![EditCommand::MoveToLineEnd]),
+        Motion::RightUntil(c) => select_to(
+            repeated(EditCommand::MoveRightUntil(*c))
+                .chain([EditCommand::MoveRight])
+                .collect(),
+        ),
+        // Moving before the character again wouldn't pass it, the earlier
+        // occurrences are moved to instead
+        Motion::RightBefore(c) => select_to(
+            repeated(EditCommand::MoveRightUntil(*c))
+                .skip(1)
+                .chain([EditCommand::MoveRightBefore(*c), EditCommand::MoveRight])
+                .collect(),
+        ),
+        Motion::LeftUntil(c) => select_to(repeated(EditCommand::MoveLeftUntil(*c)).collect()),
+        Motion::LeftBefore(c) => select_to(
+            repeated(EditCommand::MoveLeftUntil(*c))
+                .skip(1)
+                .chain([EditCommand::MoveLeftBefore(*c)])
+                .collect(),
+        ),
+        Motion::TextObject(text_object) => {
+            repeated(EditCommand::SelectTextObject(*text_object)).collect()
         }
     }
 }
 
+/// Select the text covered by `count` times the motion and apply the edit to
+/// the selection
+fn apply_to_motion(motion: &Motion, count: usize, edit: EditCommand) -> Vec<ReedlineOption> {
+    selection_for_motion(motion, count)
+        .into_iter()
+        .chain(std::iter::once(edit))
+        .map(ReedlineOption::Edit)
+        .collect()
+}
+
 impl From<ViToTill> for EditCommand {
     fn from(val: ViToTill) -> Self {
         EditCommand::from(&val)
@@ -0,0 +1,127 @@
+/// Motions following an operator
+const MOTIONS: [(&str, &str); 15] = [
+    ("w", "next word"),
+    ("W", "next WORD"),
+    ("e", "end of word"),
+    ("E", "end of WORD"),
+    ("b", "previous word"),
+    ("B", "previous WORD"),
+    ("0 ^", "line start"),
+    ("$", "line end"),
+    ("f{char}", "to char"),
+    ("t{char}", "till char"),
+    ("F{char}", "back to char"),
+    ("T{char}", "back till char"),
+    ("i{object}", "inside object"),
+    ("a{object}", "around object"),
+    ("{count}", "repeat the motion"),
+];
+
+/// Text objects following `i` or `a`
+const TEXT_OBJECTS: [(&str, &str); 8] = [
+    ("w", "word"),
+    ("W", "WORD"),
+    ("p", "paragraph"),
+    ("\" ' `", "quotes"),
+    ("( ) b", "parentheses"),
+    ("[ ]", "brackets"),
+    ("{ } B", "braces"),
+    ("< >", "angle brackets"),
+];
+
+const G_COMMANDS: [(&str, &str); 7] = [
+    ("-", "undo earlier"),
+    ("+", "redo later"),
+    ("j", "down a screen row"),
+    ("k", "up a screen row"),
+    ("U{motion}", "uppercase"),
+    ("u{motion}", "lowercase"),
+    ("~{motion}", "toggle case"),
+];
+
+/// The keys continuing the partial command in the cache of the normal mode
+pub fn continuations(cache: &[char]) -> Vec<(String, String)> {
+    let command = match cache {
+        ['"', _, command @ ..] | command => skip_count(command),
+    };
+
+    let extra: &[(&str, &str)] = match command {
+        ['"'] => &[("{register}", "use the register")],
+        ['d'] => &[("d", "line"), ("s{char}", "delete surrounding")],
+        ['c'] => &[("s{from}{to}", "change surrounding")],
+        ['y'] => &[("y", "line"), ("s{motion}{char}", "surround")],
+        ['g'] => &G_COMMANDS,
+        ['g', c @ ('U' | 'u' | '~')] => match c {
+            'U' => &[("U", "uppercase line")],
+            'u' => &[("u", "lowercase line")],
+            _ => &[("~", "toggle case of line")],
+        },
+        ['m'] => &[("{a-z}", "set mark")],
+        ['`'] => &[("{a-z}", "jump to mark")],
+        ['\''] => &[("{a-z}", "jump to line of mark")],
+        ['r'] => &[("{char}", "replace char")],
+        ['f' | 't' | 'F' | 'T'] => &[("{char}", "target char")],
+        ['q'] => &[("{register}", "record macro")],
+        ['@'] => &[("{register}", "replay macro")],
+        _ => &[],
+    };
+
+    let motion = match command {
+        ['y', 's', motion @ ..]
+        | ['g', 'U' | 'u' | '~', motion @ ..]
+        | ['d' | 'c' | 'y', motion @ ..] => Some(skip_count(motion)),
+        _ => None,
+    };
+    let follow_up: &[(&str, &str)] = match motion {
+        Some([]) => &MOTIONS,
+        Some(['i' | 'a']) => &TEXT_OBJECTS,
+        _ => &[],
+    };
+
+    extra
+        .iter()
+        .chain(follow_up)
+        .map(|(keys, description)| (keys.to_string(), description.to_string()))
+        .collect()
+}
+
+fn skip_count(keys: &[char]) -> &[char] {
+    match keys {
+        [first, ..] if first.is_ascii_digit() && *first != '0' => {
+            let digits = keys.iter().take_while(|c| c.is_ascii_digit()).count();
+            &keys[digits..]
+        }
+        _ => keys,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn keys(continuations: Vec<(String, String)>) -> Vec<String> {
+        continuations.into_iter().map(|(keys, _)| keys).collect()
+    }
+
+    #[rstest]
+    #[case("", vec![])]
+    #[case("3", vec![])]
+    #[case("w", vec![])]
+    #[case("g", vec!["-", "+", "j", "k", "U{motion}", "u{motion}", "~{motion}"])]
+    #[case("gU", [vec!["U"], keys_of(&MOTIONS)].concat())]
+    #[case("\"a2d", [vec!["d", "s{char}"], keys_of(&MOTIONS)].concat())]
+    #[case("d10", keys_of(&MOTIONS))]
+    #[case("c2i", keys_of(&TEXT_OBJECTS))]
+    #[case("m", vec!["{a-z}"])]
+    #[case("ys", keys_of(&MOTIONS))]
+    fn test_continuations(#[case] cache: &str, #[case] expected: Vec<&str>) {
+        let cache = cache.chars().collect::<Vec<char>>();
+        assert_eq!(keys(continuations(&cache)), expected);
+    }
+
+    fn keys_of(table: &[(&'static str, &str)]) -> Vec<&'static str> {
+        table.iter().map(|(keys, _)| *keys).collect()
+    }
+}
@@ -1,22 +1,32 @@
 mod command;
+mod continuations;
 mod motion;
 mod parser;
+mod text_object;
 mod vi_keybindings;
 
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 pub use vi_keybindings::{default_vi_insert_keybindings, default_vi_normal_keybindings};
 
-use super::EditMode;
+use super::{combine_events, EditMode, MacroRecorder, SequenceInput, SequenceMatcher};
 use crate::{
-    edit_mode::{keybindings::Keybindings, vi::parser::parse},
+    edit_mode::{
+        keybindings::{
+            sort_conflicts, text_input_conflict, uppercase_conflict, KeybindingConflict,
+            Keybindings,
+        },
+        vi::parser::{parse, ParseResult},
+    },
     enums::{EditCommand, ReedlineEvent},
     PromptEditMode, PromptViMode,
 };
+use std::time::Duration;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum ViMode {
     Normal,
     Insert,
+    VisualBlock,
 }
 
 /// Vi left-right motions to or till a character.
@@ -56,15 +66,27 @@ impl From<EditCommand> for Option<ViToTill> {
     }
 }
 
+/// The last change for `.`, together with the edits typed in insert mode if
+/// the change entered it
+#[derive(Debug, Clone)]
+struct LastChange {
+    input: ParseResult,
+    inserted: Vec<EditCommand>,
+    // the edits in insert mode are still added
+    inserting: bool,
+}
+
 /// This parses incoming input `Event`s like a Vi-Style editor
 pub struct Vi {
     cache: Vec<char>,
     insert_keybindings: Keybindings,
     normal_keybindings: Keybindings,
     mode: ViMode,
-    previous: Option<ReedlineEvent>,
+    last_change: Option<LastChange>,
     // last f, F, t, T motion for ; and ,
     last_to_till: Option<ViToTill>,
+    macros: MacroRecorder,
+    sequences: SequenceMatcher,
 }
 
 impl Default for Vi {
@@ -74,8 +96,10 @@ impl Default for Vi {
             normal_keybindings: default_vi_normal_keybindings(),
             cache: Vec::new(),
             mode: ViMode::Insert,
-            previous: None,
+            last_change: None,
             last_to_till: None,
+            macros: MacroRecorder::default(),
+            sequences: SequenceMatcher::default(),
         }
     }
 }
@@ -91,17 +115,181 @@ impl Vi {
     }
 }
 
-impl EditMode for Vi {
-    fn parse_event(&mut self, event: Event) -> ReedlineEvent {
+impl Vi {
+    /// Start (`q{register}`) or stop (`q`) the recording of a macro or replay
+    /// one (`@{register}`, `@@` for the macro replayed last)
+    fn parse_macro_command(&mut self, c: char) -> Option<ReedlineEvent> {
+        match (self.cache.as_slice(), c) {
+            ([], 'q') if self.macros.is_recording() => {
+                self.macros.stop();
+                Some(ReedlineEvent::None)
+            }
+            ([], 'q' | '@') => {
+                self.cache.push(c);
+                Some(ReedlineEvent::None)
+            }
+            (['q'], register) => {
+                self.cache.clear();
+                if register.is_ascii_alphanumeric() {
+                    self.macros.start(register);
+                }
+                Some(ReedlineEvent::None)
+            }
+            (['@'], register) => {
+                self.cache.clear();
+                Some(self.replay_macro(register))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse the events of a recorded macro as if they were typed again
+    fn replay_macro(&mut self, register: char) -> ReedlineEvent {
+        match self.macros.replay(register) {
+            Some(events) => {
+                let events = events
+                    .into_iter()
+                    .map(|event| self.parse_vi_event(event))
+                    .collect();
+                self.macros.finish_replay();
+                ReedlineEvent::Multiple(events)
+            }
+            None => ReedlineEvent::None,
+        }
+    }
+
+    /// Handle a character typed in visual block mode. Apart from the block
+    /// operations only motions are accepted, they extend the block selection.
+    fn parse_visual_block(&mut self, c: char) -> ReedlineEvent {
+        let (edit, mode) = match c {
+            'y' if self.cache.is_empty() => (EditCommand::CopySelection, ViMode::Normal),
+            'd' | 'x' if self.cache.is_empty() => (EditCommand::CutSelection, ViMode::Normal),
+            'I' if self.cache.is_empty() => (EditCommand::AddCursorsAtBlockStart, ViMode::Insert),
+            _ => {
+                self.cache.push(c);
+                let res = parse(self, &mut self.cache.iter().peekable());
+                let event = res.to_reedline_event();
+                if event == ReedlineEvent::None {
+                    if !res.is_valid() {
+                        self.cache.clear();
+                    }
+                    return ReedlineEvent::None;
+                }
+
+                self.cache.clear();
+                return if res.is_motion() {
+                    within_buffer(event)
+                } else {
+                    ReedlineEvent::None
+                };
+            }
+        };
+
+        self.cache.clear();
+        self.mode = mode;
+        ReedlineEvent::Multiple(vec![
+            ReedlineEvent::Edit(vec![edit]),
+            ReedlineEvent::Repaint,
+        ])
+    }
+}
+
+/// The motion with its moves up and down staying in the buffer, where
+/// browsing the history would drop the block selection
+fn within_buffer(event: ReedlineEvent) -> ReedlineEvent {
+    match event {
+        ReedlineEvent::Up => ReedlineEvent::Edit(vec![EditCommand::MoveUp]),
+        ReedlineEvent::Down => ReedlineEvent::Edit(vec![EditCommand::MoveDown]),
+        ReedlineEvent::Multiple(events) => {
+            ReedlineEvent::Multiple(events.into_iter().map(within_buffer).collect())
+        }
+        event => event,
+    }
+}
+
+impl Vi {
+    /// Replay the last change, with `count` replacing the count it was typed with
+    fn repeat_last_change(&self, count: Option<usize>) -> ReedlineEvent {
+        match &self.last_change {
+            Some(change) => {
+                let input = match count {
+                    Some(count) => change.input.with_count(count),
+                    None => change.input.clone(),
+                };
+                let mut events = vec![input.to_reedline_event()];
+                if !change.inserted.is_empty() {
+                    events.push(ReedlineEvent::Edit(change.inserted.clone()));
+                }
+                ReedlineEvent::Multiple(events)
+            }
+            None => ReedlineEvent::None,
+        }
+    }
+
+    /// Keybindings of the current mode
+    fn mode_keybindings(&self) -> &Keybindings {
+        match self.mode {
+            ViMode::Insert => &self.insert_keybindings,
+            ViMode::Normal | ViMode::VisualBlock => &self.normal_keybindings,
+        }
+    }
+
+    fn parse_vi_event(&mut self, event: Event) -> ReedlineEvent {
+        let keybindings = match self.mode {
+            ViMode::Insert => &self.insert_keybindings,
+            ViMode::Normal | ViMode::VisualBlock => &self.normal_keybindings,
+        };
+        let input = self.sequences.process(keybindings, event);
+        self.parse_sequence_input(input)
+    }
+
+    /// Handle the input resolved by the key sequence matcher, a sequence bound
+    /// to [`ReedlineEvent::Esc`] is handled like the escape key
+    fn parse_sequence_input(&mut self, input: Vec<SequenceInput>) -> ReedlineEvent {
+        let mut events = Vec::with_capacity(input.len());
+        for input in input {
+            events.push(match input {
+                SequenceInput::Event(event) => self.parse_single_event(event),
+                SequenceInput::Sequence(ReedlineEvent::Esc) => {
+                    self.parse_single_event(Event::Key(KeyEvent {
+                        code: KeyCode::Esc,
+                        modifiers: KeyModifiers::NONE,
+                    }))
+                }
+                SequenceInput::Sequence(event) => event,
+            });
+        }
+        combine_events(events)
+    }
+
+    fn parse_single_event(&mut self, event: Event) -> ReedlineEvent {
+        let inserting = self.mode == ViMode::Insert;
+        let result = self.parse_vi_input(event);
+
+        // The edits typed after entering insert mode belong to the change, any
+        // other event like leaving insert mode ends it
+        if let Some(change) = self.last_change.as_mut().filter(|change| change.inserting) {
+            match &result {
+                ReedlineEvent::Edit(edits) if inserting && self.mode == ViMode::Insert => {
+                    change.inserted.extend(edits.iter().cloned());
+                }
+                _ if !inserting => {}
+                _ => change.inserting = false,
+            }
+        }
+        result
+    }
+
+    fn parse_vi_input(&mut self, event: Event) -> ReedlineEvent {
         match event {
             Event::Key(KeyEvent { code, modifiers }) => match (self.mode, modifiers, code) {
                 (ViMode::Normal, modifier, KeyCode::Char(c)) => {
                     // The repeat character is the only character that is not managed
-                    // by the parser since the last event is stored in the editor
-                    if c == '.' {
-                        if let Some(event) = &self.previous {
-                            return event.clone();
-                        }
+                    // by the parser since the last change is stored in the edit mode
+                    if c == '.' && self.cache.iter().all(char::is_ascii_digit) {
+                        let count = self.cache.iter().collect::<String>().parse().ok();
+                        self.cache.clear();
+                        return self.repeat_last_change(count);
                     }
 
                     let c = c.to_ascii_lowercase();
@@ -111,12 +299,23 @@ impl EditMode for Vi {
                         .find_binding(modifiers, KeyCode::Char(c))
                     {
                         event
+                    } else if modifier == KeyModifiers::CONTROL && c == 'v' {
+                        self.cache.clear();
+                        self.mode = ViMode::VisualBlock;
+                        ReedlineEvent::Multiple(vec![
+                            ReedlineEvent::Edit(vec![EditCommand::StartBlockSelection]),
+                            ReedlineEvent::Repaint,
+                        ])
                     } else if modifier == KeyModifiers::NONE || modifier == KeyModifiers::SHIFT {
-                        self.cache.push(if modifier == KeyModifiers::SHIFT {
+                        let c = if modifier == KeyModifiers::SHIFT {
                             c.to_ascii_uppercase()
                         } else {
                             c
-                        });
+                        };
+                        if let Some(event) = self.parse_macro_command(c) {
+                            return event;
+                        }
+                        self.cache.push(c);
 
                         let res = parse(self, &mut self.cache.iter().peekable());
 
@@ -154,13 +353,35 @@ impl EditMode for Vi {
                             }
                         }
 
-                        self.previous = Some(event.clone());
+                        if event != ReedlineEvent::None && res.is_change() {
+                            self.last_change = Some(LastChange {
+                                inserting: self.mode == ViMode::Insert,
+                                input: res,
+                                inserted: vec![],
+                            });
+                        }
 
                         event
                     } else {
                         ReedlineEvent::None
                     }
                 }
+                (ViMode::VisualBlock, KeyModifiers::CONTROL, KeyCode::Char('v'))
+                | (_, KeyModifiers::NONE, KeyCode::Esc) => {
+                    self.cache.clear();
+                    self.mode = ViMode::Normal;
+                    ReedlineEvent::Multiple(vec![ReedlineEvent::Esc, ReedlineEvent::Repaint])
+                }
+                (ViMode::VisualBlock, modifier, KeyCode::Char(c))
+                    if modifier == KeyModifiers::NONE || modifier == KeyModifiers::SHIFT =>
+                {
+                    let c = if modifier == KeyModifiers::SHIFT {
+                        c.to_ascii_uppercase()
+                    } else {
+                        c.to_ascii_lowercase()
+                    };
+                    self.parse_visual_block(c)
+                }
                 (ViMode::Insert, modifier, KeyCode::Char(c)) => {
                     // Note. The modifier can also be a combination of modifiers, for
                     // example:
@@ -194,16 +415,11 @@ impl EditMode for Vi {
                             .unwrap_or(ReedlineEvent::None)
                     }
                 }
-                (_, KeyModifiers::NONE, KeyCode::Esc) => {
-                    self.cache.clear();
-                    self.mode = ViMode::Normal;
-                    ReedlineEvent::Multiple(vec![ReedlineEvent::Esc, ReedlineEvent::Repaint])
-                }
                 (_, KeyModifiers::NONE, KeyCode::Enter) => {
                     self.mode = ViMode::Insert;
                     ReedlineEvent::Enter
                 }
-                (ViMode::Normal, _, _) => self
+                (ViMode::Normal | ViMode::VisualBlock, _, _) => self
                     .normal_keybindings
                     .find_binding(modifiers, code)
                     .unwrap_or(ReedlineEvent::None),
@@ -217,12 +433,83 @@ impl EditMode for Vi {
             Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
         }
     }
+}
+
+impl EditMode for Vi {
+    fn parse_event(&mut self, event: Event) -> ReedlineEvent {
+        // The keys starting and stopping the recording are not part of the macro
+        let recording = self.macros.is_recording();
+        let result = self.parse_vi_event(event);
+        if recording && self.macros.is_recording() {
+            self.macros.record(event);
+        }
+        result
+    }
 
     fn edit_mode(&self) -> PromptEditMode {
         match self.mode {
             ViMode::Normal => PromptEditMode::Vi(PromptViMode::Normal),
             ViMode::Insert => PromptEditMode::Vi(PromptViMode::Insert),
+            ViMode::VisualBlock => PromptEditMode::Vi(PromptViMode::VisualBlock),
+        }
+    }
+
+    fn sequence_timeout(&self) -> Option<Duration> {
+        self.sequences
+            .remaining_time(self.mode_keybindings().sequence_timeout())
+    }
+
+    fn handle_sequence_timeout(&mut self) -> ReedlineEvent {
+        let keybindings = match self.mode {
+            ViMode::Insert => &self.insert_keybindings,
+            ViMode::Normal | ViMode::VisualBlock => &self.normal_keybindings,
+        };
+        let input = self.sequences.flush(keybindings);
+        self.parse_sequence_input(input)
+    }
+
+    fn pending_continuations(&self) -> Vec<(String, String)> {
+        let mut continuations = self.sequences.continuations(self.mode_keybindings());
+        if self.mode != ViMode::Insert {
+            continuations.extend(continuations::continuations(&self.cache));
         }
+        continuations
+    }
+
+    fn keybindings(&self) -> Option<&Keybindings> {
+        Some(self.mode_keybindings())
+    }
+
+    fn keybinding_conflicts(&self) -> Vec<KeybindingConflict> {
+        let keybindings = self.mode_keybindings();
+        let mut conflicts = keybindings.find_conflicts();
+        conflicts.extend(keybindings.get_keybindings().keys().filter_map(|key| {
+            let keys = vec![key.clone()];
+            match (self.mode, key.modifier, key.key_code) {
+                (_, KeyModifiers::NONE, KeyCode::Esc) => Some(KeybindingConflict::Shadowed {
+                    keys,
+                    reason: "it switches to normal mode".to_string(),
+                }),
+                (ViMode::Insert, _, _) => text_input_conflict(key),
+                (_, KeyModifiers::NONE, KeyCode::Enter) => Some(KeybindingConflict::Shadowed {
+                    keys,
+                    reason: "it submits the line".to_string(),
+                }),
+                (_, _, KeyCode::Char(c)) if c != c.to_ascii_lowercase() => {
+                    Some(uppercase_conflict(keys))
+                }
+                _ => None,
+            }
+        }));
+        sort_conflicts(&mut conflicts);
+        conflicts
+    }
+
+    fn keybindings_mut(&mut self) -> Option<&mut Keybindings> {
+        Some(match self.mode {
+            ViMode::Insert => &mut self.insert_keybindings,
+            ViMode::Normal | ViMode::VisualBlock => &mut self.normal_keybindings,
+        })
     }
 }
 
@@ -232,6 +519,56 @@ mod test {
     use pretty_assertions::assert_eq;
     use rstest::rstest;
 
+    #[test]
+    fn keybindings_of_the_current_mode_are_changed() {
+        let mut vi = Vi::default();
+        vi.keybindings_mut().unwrap().add_binding(
+            KeyModifiers::ALT,
+            KeyCode::Char('h'),
+            ReedlineEvent::ClearScreen,
+        );
+        assert!(vi
+            .insert_keybindings
+            .find_binding(KeyModifiers::ALT, KeyCode::Char('h'))
+            .is_some());
+        assert!(vi
+            .normal_keybindings
+            .find_binding(KeyModifiers::ALT, KeyCode::Char('h'))
+            .is_none());
+
+        vi.mode = ViMode::Normal;
+        assert!(vi
+            .keybindings()
+            .unwrap()
+            .find_binding(KeyModifiers::ALT, KeyCode::Char('h'))
+            .is_none());
+    }
+
+    #[test]
+    fn bindings_shadowed_in_normal_mode_are_conflicts() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Vi::default()
+        };
+        let keybindings = vi.keybindings_mut().unwrap();
+        keybindings.add_binding(KeyModifiers::NONE, KeyCode::Esc, ReedlineEvent::Up);
+        keybindings.add_binding(KeyModifiers::SHIFT, KeyCode::Char('A'), ReedlineEvent::Up);
+        keybindings.add_binding(KeyModifiers::NONE, KeyCode::Char('a'), ReedlineEvent::Up);
+
+        let conflicts = vi
+            .keybinding_conflicts()
+            .iter()
+            .map(|conflict| conflict.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            conflicts,
+            vec![
+                "Esc is shadowed, it switches to normal mode",
+                "S-A is unreachable, letters are looked up in lowercase",
+            ]
+        );
+    }
+
     #[test]
     fn esc_leads_to_normal_mode_test() {
         let mut vi = Vi::default();
@@ -346,4 +683,175 @@ mod test {
 
         assert_eq!(vi.last_to_till, Some(expected));
     }
+
+    #[test]
+    fn visual_block_mode_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Vi::default()
+        };
+        let key = |code, modifiers| Event::Key(KeyEvent { code, modifiers });
+
+        let result = vi.parse_event(key(KeyCode::Char('v'), KeyModifiers::CONTROL));
+        assert_eq!(
+            result,
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::StartBlockSelection]),
+                ReedlineEvent::Repaint
+            ])
+        );
+        assert_eq!(vi.mode, ViMode::VisualBlock);
+
+        // Motions extend the selection, other commands are ignored
+        let result = vi.parse_event(key(KeyCode::Char('j'), KeyModifiers::NONE));
+        assert_eq!(
+            result,
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveDown])])
+        );
+        let result = vi.parse_event(key(KeyCode::Char('p'), KeyModifiers::NONE));
+        assert_eq!(result, ReedlineEvent::None);
+
+        let result = vi.parse_event(key(KeyCode::Char('i'), KeyModifiers::SHIFT));
+        assert_eq!(
+            result,
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::AddCursorsAtBlockStart]),
+                ReedlineEvent::Repaint
+            ])
+        );
+        assert_eq!(vi.mode, ViMode::Insert);
+    }
+
+    #[test]
+    fn macro_recording_and_replay_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Vi::default()
+        };
+        let mut type_keys = |keys: &str| {
+            keys.chars()
+                .map(|c| {
+                    vi.parse_event(Event::Key(KeyEvent {
+                        code: KeyCode::Char(c),
+                        modifiers: KeyModifiers::NONE,
+                    }))
+                })
+                .last()
+                .unwrap_or(ReedlineEvent::None)
+        };
+
+        assert_eq!(type_keys("qaxq"), ReedlineEvent::None);
+        let cut_char =
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutChar])]);
+        assert_eq!(
+            type_keys("@a"),
+            ReedlineEvent::Multiple(vec![cut_char.clone()])
+        );
+        assert_eq!(type_keys("@@"), ReedlineEvent::Multiple(vec![cut_char]));
+        assert_eq!(type_keys("@b"), ReedlineEvent::None);
+    }
+
+    fn key_press(vi: &mut Vi, c: char) -> ReedlineEvent {
+        vi.parse_event(Event::Key(KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::NONE,
+        }))
+    }
+
+    fn type_keys(vi: &mut Vi, keys: &str) -> ReedlineEvent {
+        keys.chars()
+            .map(|c| key_press(vi, c))
+            .last()
+            .unwrap_or(ReedlineEvent::None)
+    }
+
+    #[test]
+    fn repeat_last_change_with_count_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Vi::default()
+        };
+        let cut_words = |count| {
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Multiple(
+                (0..count)
+                    .map(|_| ReedlineEvent::Edit(vec![EditCommand::CutWordRightToNext]))
+                    .collect(),
+            )])
+        };
+
+        assert_eq!(type_keys(&mut vi, "."), ReedlineEvent::None);
+        type_keys(&mut vi, "2dw");
+        // Motions and yanks are not changes
+        type_keys(&mut vi, "wyw");
+        assert_eq!(type_keys(&mut vi, "."), cut_words(2));
+        assert_eq!(type_keys(&mut vi, "3."), cut_words(3));
+    }
+
+    #[test]
+    fn repeat_change_with_inserted_text_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Vi::default()
+        };
+        let esc = Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+        });
+
+        type_keys(&mut vi, "cwab");
+        vi.parse_event(esc);
+        type_keys(&mut vi, "l");
+        assert_eq!(
+            type_keys(&mut vi, "."),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Multiple(vec![
+                    ReedlineEvent::Edit(vec![EditCommand::CutWordRightToNext]),
+                    ReedlineEvent::Repaint,
+                ]),
+                ReedlineEvent::Edit(vec![
+                    EditCommand::InsertChar('a'),
+                    EditCommand::InsertChar('b')
+                ]),
+            ])
+        );
+        assert_eq!(vi.mode, ViMode::Normal);
+
+        type_keys(&mut vi, "ic");
+        vi.parse_event(esc);
+        assert_eq!(
+            type_keys(&mut vi, "."),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Multiple(vec![ReedlineEvent::Repaint]),
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar('c')]),
+            ])
+        );
+    }
+
+    #[test]
+    fn key_sequence_leaves_insert_mode_test() {
+        let mut insert_keybindings = default_vi_insert_keybindings();
+        insert_keybindings.add_sequence_binding(
+            &[
+                (KeyModifiers::NONE, KeyCode::Char('j')),
+                (KeyModifiers::NONE, KeyCode::Char('k')),
+            ],
+            ReedlineEvent::Esc,
+        );
+        let mut vi = Vi::new(insert_keybindings, default_vi_normal_keybindings());
+
+        assert_eq!(key_press(&mut vi, 'j'), ReedlineEvent::None);
+        assert_eq!(
+            key_press(&mut vi, 'k'),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Esc, ReedlineEvent::Repaint])
+        );
+        assert_eq!(vi.mode, ViMode::Normal);
+
+        type_keys(&mut vi, "i");
+        assert_eq!(key_press(&mut vi, 'j'), ReedlineEvent::None);
+        assert_eq!(
+            vi.handle_sequence_timeout(),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('j')])
+        );
+        assert_eq!(vi.mode, ViMode::Insert);
+    }
 }
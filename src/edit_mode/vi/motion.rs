@@ -1,6 +1,10 @@
+use super::text_object::parse_text_object;
+use crate::TextObject;
 use std::iter::Peekable;
 
-pub fn parse_motion<'iter, I>(input: &mut Peekable<I>) -> Option<Motion>
+/// Parse the motion of an operator, `line_char` is the character of the
+/// operator that selects the whole line when repeated, like `dd` and `yy`
+pub fn parse_motion<'iter, I>(input: &mut Peekable<I>, line_char: Option<char>) -> Option<Motion>
 where
     I: Iterator<Item = &'iter char>,
 {
@@ -29,7 +33,7 @@ where
             let _ = input.next();
             Some(Motion::NextBigWordEnd)
         }
-        Some('d') => {
+        Some(c) if Some(**c) == line_char => {
             let _ = input.next();
             Some(Motion::Line)
         }
@@ -41,6 +45,7 @@ where
             let _ = input.next();
             Some(Motion::End)
         }
+        Some('i' | 'a') => parse_text_object(input).map(Motion::TextObject),
         Some('f') => {
             let _ = input.next();
             input.peek().map(|c| Motion::RightUntil(**c))
@@ -61,7 +66,7 @@ where
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Motion {
     NextWord,
     NextBigWord,
@@ -76,4 +81,5 @@ pub enum Motion {
     RightBefore(char),
     LeftUntil(char),
     LeftBefore(char),
+    TextObject(TextObject),
 }
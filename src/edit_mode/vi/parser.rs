@@ -1,8 +1,11 @@
 use super::command::{parse_command, Command};
 use super::motion::{parse_motion, Motion};
-use crate::{EditCommand, ReedlineEvent, Vi};
+use crate::{core_editor::RegisterStore, EditCommand, ReedlineEvent, Vi};
 use std::iter::Peekable;
 
+/// Largest count of a command or motion, as the command is repeated that often
+pub const MAX_COUNT: usize = 9999;
+
 #[derive(Debug, Clone)]
 pub enum ReedlineOption {
     Event(ReedlineEvent),
@@ -10,8 +13,9 @@ pub enum ReedlineOption {
     Incomplete,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParseResult {
+    register: Option<char>,
     multiplier: Option<usize>,
     command: Option<Command>,
     count: Option<usize>,
@@ -39,39 +43,104 @@ impl ParseResult {
         )
     }
 
+    /// Check if the input only moves the cursor, the only input accepted in
+    /// visual block mode apart from the block operations
+    pub fn is_motion(&self) -> bool {
+        matches!(
+            (&self.command, &self.motion),
+            (
+                Some(
+                    Command::MoveLeft
+                        | Command::MoveRight
+                        | Command::MoveUp
+                        | Command::MoveDown
+                        | Command::MoveDisplayLineUp
+                        | Command::MoveDisplayLineDown
+                        | Command::MoveWordRightStart
+                        | Command::MoveBigWordRightStart
+                        | Command::MoveWordRightEnd
+                        | Command::MoveBigWordRightEnd
+                        | Command::MoveWordLeft
+                        | Command::MoveBigWordLeft
+                        | Command::MoveToLineStart
+                        | Command::MoveToLineEnd
+                        | Command::MoveRightUntil(_)
+                        | Command::MoveRightBefore(_)
+                        | Command::MoveLeftUntil(_)
+                        | Command::MoveLeftBefore(_)
+                        | Command::ReplayToTill(_)
+                        | Command::ReverseToTill(_)
+                        | Command::JumpToMark(_)
+                        | Command::JumpToMarkLine(_)
+                        | Command::MoveToMatchingBracket
+                ),
+                None
+            )
+        )
+    }
+
+    /// Check if the input changes the buffer and is repeated by `.`
+    pub fn is_change(&self) -> bool {
+        !self.is_motion()
+            && !matches!(
+                (&self.command, &self.motion),
+                (
+                    None | Some(
+                        Command::Incomplete
+                            | Command::Yank
+                            | Command::Undo
+                            | Command::UndoEarlier
+                            | Command::RedoLater
+                            | Command::HistorySearch
+                            | Command::SetMark(_)
+                    ),
+                    _
+                )
+            )
+    }
+
+    /// The same input with `count` replacing the typed counts, like `3.`
+    /// repeats `2dw` as `3dw`
+    pub fn with_count(&self, count: usize) -> Self {
+        ParseResult {
+            multiplier: Some(count),
+            count: None,
+            ..self.clone()
+        }
+    }
+
     pub fn to_reedline_event(&self) -> ReedlineEvent {
         match (&self.multiplier, &self.command, &self.count, &self.motion) {
             // Movements with h,j,k,l are always single char or a number followed
             // by a single command (char)
             (multiplier, Some(command), None, None) => {
-                let events = command.to_reedline().into_iter().map(|event| match event {
-                    ReedlineOption::Edit(e) => ReedlineEvent::Edit(vec![e]),
-                    ReedlineOption::Event(e) => e,
-                    ReedlineOption::Incomplete => ReedlineEvent::None,
-                });
-
-                let multiplier = multiplier.unwrap_or(1);
-                let events = std::iter::repeat(events)
-                    .take(multiplier)
-                    .flatten()
+                let events = command
+                    .to_reedline_with_count(multiplier.unwrap_or(1))
+                    .into_iter()
+                    .map(|event| match event {
+                        ReedlineOption::Edit(e) => ReedlineEvent::Edit(vec![e]),
+                        ReedlineOption::Event(e) => e,
+                        ReedlineOption::Incomplete => ReedlineEvent::None,
+                    })
                     .collect::<Vec<ReedlineEvent>>();
 
                 if events.contains(&ReedlineEvent::None) {
                     ReedlineEvent::None
                 } else {
-                    ReedlineEvent::Multiple(events)
+                    ReedlineEvent::Multiple(self.with_register(events))
                 }
             }
             // This case handles all combinations of commands and motions that could exist
-            // The option count is used to multiply the actions that should be done with the motion
-            // and the multiplier repeats the whole chain x number of time
+            // The multiplier and the count multiply, `2d3w` deletes six words like `6dw`
             (multiplier, Some(command), count, Some(motion)) => {
+                let count = multiplier
+                    .unwrap_or(1)
+                    .saturating_mul(count.unwrap_or(1))
+                    .min(MAX_COUNT);
                 match command.to_reedline_with_motion(motion, count) {
                     Some(events) => {
-                        let multiplier = multiplier.unwrap_or(1);
-                        let events = std::iter::repeat(events)
-                            .take(multiplier)
-                            .flatten()
+                        let events = events
+                            .into_iter()
                             .map(|option| match option {
                                 ReedlineOption::Edit(edit) => ReedlineEvent::Edit(vec![edit]),
                                 ReedlineOption::Event(event) => event,
@@ -79,7 +148,7 @@ impl ParseResult {
                             })
                             .collect::<Vec<ReedlineEvent>>();
 
-                        ReedlineEvent::Multiple(events)
+                        ReedlineEvent::Multiple(self.with_register(events))
                     }
                     None => ReedlineEvent::None,
                 }
@@ -87,9 +156,48 @@ impl ParseResult {
             _ => ReedlineEvent::None,
         }
     }
+
+    /// Select the register for the duration of the events and
+    /// return to the default register afterwards
+    fn with_register(&self, events: Vec<ReedlineEvent>) -> Vec<ReedlineEvent> {
+        match self.register {
+            Some(register) if register != '"' => {
+                let mut with_register = Vec::with_capacity(events.len() + 2);
+                with_register.push(ReedlineEvent::Edit(vec![EditCommand::SelectRegister(
+                    register,
+                )]));
+                with_register.extend(events);
+                with_register.push(ReedlineEvent::Edit(vec![EditCommand::SelectRegister('"')]));
+                with_register
+            }
+            _ => events,
+        }
+    }
+}
+
+fn parse_register<'iter, I>(input: &mut Peekable<I>) -> Option<char>
+where
+    I: Iterator<Item = &'iter char>,
+{
+    match input.peek() {
+        Some('"') => {
+            let _ = input.next();
+            match input.peek() {
+                Some(&&c) if RegisterStore::is_register(c) => {
+                    let _ = input.next();
+                    Some(c)
+                }
+                // An unknown register is left in the input and marks it as invalid
+                Some(_) => None,
+                // Waiting for the name of the register
+                None => Some('"'),
+            }
+        }
+        _ => None,
+    }
 }
 
-fn parse_number<'iter, I>(input: &mut Peekable<I>) -> Option<usize>
+pub fn parse_number<'iter, I>(input: &mut Peekable<I>) -> Option<usize>
 where
     I: Iterator<Item = &'iter char>,
 {
@@ -101,8 +209,10 @@ where
                 if c.is_ascii_digit() {
                     let c = c.to_digit(10).expect("already checked if is a digit");
                     let _ = input.next();
-                    count *= 10;
-                    count += c as usize;
+                    count = count
+                        .saturating_mul(10)
+                        .saturating_add(c as usize)
+                        .min(MAX_COUNT);
                 } else {
                     return Some(count);
                 }
@@ -117,13 +227,30 @@ pub fn parse<'iter, I>(vi: &Vi, input: &mut Peekable<I>) -> ParseResult
 where
     I: Iterator<Item = &'iter char>,
 {
+    let register = parse_register(input);
     let multiplier = parse_number(input);
     let command = parse_command(vi, input);
-    let count = parse_number(input);
-    let motion = parse_motion(input);
+    // Without a command, like after `g` and an unknown key, the rest is garbage
+    let (count, motion) = match command {
+        Some(ref command) => {
+            let count = parse_number(input);
+            let line_char = match command {
+                Command::Delete => Some('d'),
+                Command::Yank => Some('y'),
+                _ => None,
+            };
+            (count, parse_motion(input, line_char))
+        }
+        None => (None, None),
+    };
 
-    let valid =
-        { multiplier.is_some() || command.is_some() || count.is_some() || motion.is_some() };
+    let valid = {
+        register.is_some()
+            || multiplier.is_some()
+            || command.is_some()
+            || count.is_some()
+            || motion.is_some()
+    };
 
     // If after parsing all the input characters there is a remainder,
     // then there is garbage in the input. Having unrecognized characters will get
@@ -133,6 +260,7 @@ where
     let has_garbage = input.next().is_some();
 
     ParseResult {
+        register,
         multiplier,
         command,
         count,
@@ -144,8 +272,10 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{TextObject, TextObjectScope, TextObjectType};
     use pretty_assertions::assert_eq;
     use rstest::rstest;
+    use std::time::Duration;
 
     fn vi_parse(input: &[char]) -> ParseResult {
         let vi = Vi::default();
@@ -160,6 +290,7 @@ mod tests {
         assert_eq!(
             output,
             ParseResult {
+                register: None,
                 multiplier: None,
                 command: Some(Command::Delete),
                 count: None,
@@ -177,6 +308,7 @@ mod tests {
         assert_eq!(
             output,
             ParseResult {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: None,
@@ -194,6 +326,7 @@ mod tests {
         assert_eq!(
             output,
             ParseResult {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: Some(2),
@@ -211,6 +344,7 @@ mod tests {
         assert_eq!(
             output,
             ParseResult {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: Some(20),
@@ -228,6 +362,7 @@ mod tests {
         assert_eq!(
             output,
             ParseResult {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: None,
@@ -237,6 +372,32 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[case(&['d', 'y'], Command::Delete)]
+    #[case(&['y', 'd'], Command::Yank)]
+    fn test_other_operator_is_no_line_motion(#[case] input: &[char], #[case] command: Command) {
+        let output = vi_parse(input);
+
+        assert_eq!(
+            output,
+            ParseResult {
+                register: None,
+                multiplier: None,
+                command: Some(command),
+                count: None,
+                motion: None,
+                valid: false
+            }
+        );
+    }
+
+    #[rstest]
+    #[case(&['g', 'w'])]
+    #[case(&['2', 'g', '5'])]
+    fn test_unknown_g_command_is_invalid(#[case] input: &[char]) {
+        assert!(!vi_parse(input).is_valid());
+    }
+
     #[test]
     fn test_has_garbage() {
         let input = ['2', 'd', 'm'];
@@ -245,6 +406,7 @@ mod tests {
         assert_eq!(
             output,
             ParseResult {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: None,
@@ -254,6 +416,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unknown_register_is_garbage() {
+        let input = ['"', '!', 'p'];
+        let output = vi_parse(&input);
+
+        assert!(!output.is_valid());
+    }
+
+    #[test]
+    fn test_incomplete_text_object_is_valid() {
+        assert!(vi_parse(&['d', 'i']).is_valid());
+        assert!(!vi_parse(&['d', 'i', 'x']).is_valid());
+    }
+
     #[test]
     fn test_two_up() {
         let input = ['2', 'k'];
@@ -262,6 +438,7 @@ mod tests {
         assert_eq!(
             output,
             ParseResult {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::MoveUp),
                 count: None,
@@ -274,6 +451,23 @@ mod tests {
     #[rstest]
     #[case(&['2', 'k'], ReedlineEvent::Multiple(vec![ReedlineEvent::Up, ReedlineEvent::Up]))]
     #[case(&['k'], ReedlineEvent::Multiple(vec![ReedlineEvent::Up]))]
+    #[case(&['y', 'y'],
+        ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyCurrentLine])]))]
+    #[case(&['"', 'a', 'y', 'w'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('a')]),
+        ReedlineEvent::Edit(vec![EditCommand::CopyWordRightToNext]),
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('"')]),
+        ]))]
+    #[case(&['"', 'A', '2', 'p'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('A')]),
+        ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter]),
+        ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter]),
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('"')]),
+        ]))]
+    #[case(&['"', '"', 'p'],
+        ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter])]))]
+    #[case(&['"'], ReedlineEvent::None)]
+    #[case(&['"', 'a'], ReedlineEvent::None)]
     #[case(&['w'],
         ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveWordRightStart])]))]
     #[case(&['W'],
@@ -310,10 +504,182 @@ mod tests {
     #[case(&['d', 'e'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutWordRight])]))]
     #[case(&['d', 'b'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutWordLeft])]))]
     #[case(&['d', 'B'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutBigWordLeft])]))]
+    #[case(&['g', '-'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::UndoEarlier(Duration::ZERO)])]))]
+    #[case(&['2', 'g', '+'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::RedoLater(Duration::ZERO)]),
+        ReedlineEvent::Edit(vec![EditCommand::RedoLater(Duration::ZERO)])
+        ]))]
+    #[case(&['g'], ReedlineEvent::None)]
+    #[case(&['g', 'j'], ReedlineEvent::Multiple(vec![ReedlineEvent::DisplayLineDown]))]
+    #[case(&['2', 'g', 'k'], ReedlineEvent::Multiple(vec![ReedlineEvent::DisplayLineUp, ReedlineEvent::DisplayLineUp]))]
+    #[case(&['d', 'i', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::CutTextObject(TextObject::new(TextObjectScope::Inner, TextObjectType::Word))
+        ])]))]
+    #[case(&['y', 'a', '('], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::CopyTextObject(TextObject::new(TextObjectScope::Around, TextObjectType::Bracket('(')))
+        ])]))]
+    #[case(&['c', 'i', '"'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CutTextObject(TextObject::new(
+            TextObjectScope::Inner,
+            TextObjectType::Quote('"')
+        ))]),
+        ReedlineEvent::Repaint,
+        ]))]
+    #[case(&['d', 'i'], ReedlineEvent::None)]
+    #[case(&['m', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::SetMark('a')])]))]
+    #[case(&['`', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::JumpToMark('a')])]))]
+    #[case(&['\'', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::JumpToMarkLine('a')])]))]
+    #[case(&['%'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToMatchingBracket])]))]
+    #[case(&['m'], ReedlineEvent::None)]
+    #[case(&['J'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::JoinLines])]))]
+    #[case(&['y', 's', 'i', 'w', '"'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectTextObject(TextObject::new(TextObjectScope::Inner, TextObjectType::Word))]),
+        ReedlineEvent::Edit(vec![EditCommand::SurroundSelection('"')]),
+        ]))]
+    #[case(&['y', 's', 's', ')'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineStart]),
+        ReedlineEvent::Edit(vec![EditCommand::StartSelection]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineEnd]),
+        ReedlineEvent::Edit(vec![EditCommand::SurroundSelection(')')]),
+        ]))]
+    #[case(&['y', 's', 'i', 'w'], ReedlineEvent::None)]
+    #[case(&['c', 's', '\'', '"'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::ChangeSurrounding('\'', '"')])]))]
+    #[case(&['c', 's', '\''], ReedlineEvent::None)]
+    #[case(&['d', 's', '('], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::DeleteSurrounding('(')])]))]
+    #[case(&['g', 'U', 'w'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::StartSelection]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveWordRightStart]),
+        ReedlineEvent::Edit(vec![EditCommand::UppercaseWord]),
+        ]))]
+    #[case(&['g', 'u', 'u'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineStart]),
+        ReedlineEvent::Edit(vec![EditCommand::StartSelection]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineEnd]),
+        ReedlineEvent::Edit(vec![EditCommand::LowercaseWord]),
+        ]))]
+    #[case(&['g', '~', 'i', 'w'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectTextObject(TextObject::new(TextObjectScope::Inner, TextObjectType::Word))]),
+        ReedlineEvent::Edit(vec![EditCommand::ToggleCase]),
+        ]))]
+    #[case(&['g', 'U'], ReedlineEvent::None)]
     fn test_reedline_move(#[case] input: &[char], #[case] expected: ReedlineEvent) {
         let res = vi_parse(input);
         let output = res.to_reedline_event();
 
         assert_eq!(output, expected);
     }
+
+    fn edits(edits: Vec<EditCommand>) -> Vec<ReedlineEvent> {
+        edits
+            .into_iter()
+            .map(|edit| ReedlineEvent::Edit(vec![edit]))
+            .collect()
+    }
+
+    fn inner_bracket() -> EditCommand {
+        EditCommand::SelectTextObject(TextObject::new(
+            TextObjectScope::Inner,
+            TextObjectType::Bracket('('),
+        ))
+    }
+
+    #[rstest]
+    #[case("3dw", edits(vec![EditCommand::CutWordRightToNext; 3]))]
+    #[case("d3w", edits(vec![EditCommand::CutWordRightToNext; 3]))]
+    #[case("2d3w", edits(vec![EditCommand::CutWordRightToNext; 6]))]
+    #[case("2dd", edits(vec![EditCommand::CutCurrentLine; 2]))]
+    #[case("d2fx", edits(vec![EditCommand::CutRightUntil('x'); 2]))]
+    #[case("2cw", [
+        ReedlineEvent::Edit(vec![EditCommand::CutWordRightToNext]),
+        ReedlineEvent::Repaint,
+        ReedlineEvent::Edit(vec![EditCommand::CutWordRightToNext]),
+        ReedlineEvent::Repaint,
+    ].to_vec())]
+    #[case("2di(", edits(vec![inner_bracket(), inner_bracket(), EditCommand::CutSelection]))]
+    #[case("c2i(", [
+        edits(vec![inner_bracket(), inner_bracket(), EditCommand::CutSelection]),
+        vec![ReedlineEvent::Repaint],
+    ].concat())]
+    #[case("3yw", edits(vec![
+        EditCommand::StartSelection,
+        EditCommand::MoveWordRightStart,
+        EditCommand::MoveWordRightStart,
+        EditCommand::MoveWordRightStart,
+        EditCommand::CopySelectionAndMoveToStart,
+    ]))]
+    #[case("y2e", edits(vec![
+        EditCommand::StartSelection,
+        EditCommand::MoveWordRightEnd,
+        EditCommand::MoveWordRightEnd,
+        EditCommand::MoveRight,
+        EditCommand::CopySelectionAndMoveToStart,
+    ]))]
+    #[case("2yy", edits(vec![
+        EditCommand::MoveToLineStart,
+        EditCommand::StartSelection,
+        EditCommand::MoveToLineEnd,
+        EditCommand::MoveRight,
+        EditCommand::MoveToLineEnd,
+        EditCommand::CopySelectionAndMoveToStart,
+    ]))]
+    #[case("y3tx", edits(vec![
+        EditCommand::StartSelection,
+        EditCommand::MoveRightUntil('x'),
+        EditCommand::MoveRightUntil('x'),
+        EditCommand::MoveRightBefore('x'),
+        EditCommand::MoveRight,
+        EditCommand::CopySelectionAndMoveToStart,
+    ]))]
+    #[case("2gUw", edits(vec![
+        EditCommand::StartSelection,
+        EditCommand::MoveWordRightStart,
+        EditCommand::MoveWordRightStart,
+        EditCommand::UppercaseWord,
+    ]))]
+    #[case("gU2w", edits(vec![
+        EditCommand::StartSelection,
+        EditCommand::MoveWordRightStart,
+        EditCommand::MoveWordRightStart,
+        EditCommand::UppercaseWord,
+    ]))]
+    #[case("2gu2b", edits(vec![
+        EditCommand::StartSelection,
+        EditCommand::MoveWordLeft,
+        EditCommand::MoveWordLeft,
+        EditCommand::MoveWordLeft,
+        EditCommand::MoveWordLeft,
+        EditCommand::LowercaseWord,
+    ]))]
+    #[case("ys2i()", edits(vec![inner_bracket(), inner_bracket(), EditCommand::SurroundSelection(')')]))]
+    #[case("3x", edits(vec![EditCommand::CutChar; 3]))]
+    fn test_count_composition(#[case] input: &str, #[case] expected: Vec<ReedlineEvent>) {
+        let input = input.chars().collect::<Vec<char>>();
+        let output = vi_parse(&input).to_reedline_event();
+
+        assert_eq!(output, ReedlineEvent::Multiple(expected));
+    }
+
+    #[test]
+    fn test_counts_are_capped() {
+        let input = "99999999999d99999999w".chars().collect::<Vec<char>>();
+        let output = vi_parse(&input);
+        assert_eq!(output.multiplier, Some(MAX_COUNT));
+        assert_eq!(output.count, Some(MAX_COUNT));
+        assert!(matches!(
+            output.to_reedline_event(),
+            ReedlineEvent::Multiple(events) if events.len() <= MAX_COUNT
+        ));
+
+        let input = "99999999999gU99999999w".chars().collect::<Vec<char>>();
+        assert!(matches!(
+            vi_parse(&input).to_reedline_event(),
+            ReedlineEvent::Multiple(_)
+        ));
+
+        let input = "99999999999x".chars().collect::<Vec<char>>();
+        assert_eq!(
+            vi_parse(&input).to_reedline_event(),
+            ReedlineEvent::Multiple(edits(vec![EditCommand::CutChar; MAX_COUNT]))
+        );
+    }
 }
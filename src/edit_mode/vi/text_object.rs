@@ -0,0 +1,51 @@
+use crate::{TextObject, TextObjectScope, TextObjectType};
+use std::iter::Peekable;
+
+/// Parse a text object following an operator, like the `iw` of `diw`
+pub fn parse_text_object<'iter, I>(input: &mut Peekable<I>) -> Option<TextObject>
+where
+    I: Iterator<Item = &'iter char>,
+{
+    let scope = match input.peek() {
+        Some('i') => TextObjectScope::Inner,
+        Some('a') => TextObjectScope::Around,
+        _ => return None,
+    };
+    let _ = input.next();
+
+    let object_type = match input.peek() {
+        Some('w') => TextObjectType::Word,
+        Some('W') => TextObjectType::BigWord,
+        Some('p') => TextObjectType::Paragraph,
+        Some(&&quote @ ('"' | '\'' | '`')) => TextObjectType::Quote(quote),
+        Some('(' | ')' | 'b') => TextObjectType::Bracket('('),
+        Some('[' | ']') => TextObjectType::Bracket('['),
+        Some('{' | '}' | 'B') => TextObjectType::Bracket('{'),
+        Some('<' | '>') => TextObjectType::Bracket('<'),
+        _ => return None,
+    };
+    let _ = input.next();
+
+    Some(TextObject::new(scope, object_type))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(&['i', 'w'], Some(TextObject::new(TextObjectScope::Inner, TextObjectType::Word)))]
+    #[case(&['a', 'W'], Some(TextObject::new(TextObjectScope::Around, TextObjectType::BigWord)))]
+    #[case(&['i', '"'], Some(TextObject::new(TextObjectScope::Inner, TextObjectType::Quote('"'))))]
+    #[case(&['a', ')'], Some(TextObject::new(TextObjectScope::Around, TextObjectType::Bracket('('))))]
+    #[case(&['i', 'B'], Some(TextObject::new(TextObjectScope::Inner, TextObjectType::Bracket('{'))))]
+    #[case(&['i', 'p'], Some(TextObject::new(TextObjectScope::Inner, TextObjectType::Paragraph)))]
+    #[case(&['i'], None)]
+    #[case(&['i', 'x'], None)]
+    #[case(&['w'], None)]
+    fn test_parse_text_object(#[case] input: &[char], #[case] expected: Option<TextObject>) {
+        assert_eq!(parse_text_object(&mut input.iter().peekable()), expected);
+    }
+}
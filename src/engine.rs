@@ -1,34 +1,50 @@
 #[cfg(feature = "bashisms")]
-use crate::{
-    history::SearchFilter,
-    menu_functions::{parse_selection_char, ParseAction},
-};
+use crate::menu_functions::{parse_selection_char, ParseAction};
 
 use crate::result::{ReedlineError, ReedlineErrorVariants};
 use {
     crate::{
-        completion::{Completer, DefaultCompleter},
+        abbreviation::{Abbreviation, AbbreviationHook},
+        completion::{history::HistoryCompleter, Completer, DefaultCompleter},
         core_editor::Editor,
         edit_mode::{EditMode, Emacs},
         enums::{EventStatus, ReedlineEvent},
         highlighter::SimpleMatchHighlighter,
-        hinter::Hinter,
+        hinter::{HintPolicy, Hinter, HinterList},
         history::{
-            FileBackedHistory, History, HistoryCursor, HistoryItem, HistoryItemId,
-            HistoryNavigationQuery, HistorySessionId, SearchDirection, SearchQuery,
+            glob_matches, match_ranges, substring_search, CommandLineSearch, FileBackedHistory,
+            History, HistoryCursor, HistoryExclusionFilter, HistoryItem, HistoryItemId,
+            HistoryNavigationMode, HistoryNavigationQuery, HistoryRanking, HistoryScope,
+            HistorySessionId, SearchDirection, SearchFilter, SearchQuery,
+        },
+        menu::HistorySearchPanel,
+        painting::{
+            ghost_lines, gutter_width, insert_line_numbers, keybinding_hints_string, overlay,
+            LineNumberMode, Painter, PromptLines,
         },
-        painting::{Painter, PromptLines},
-        prompt::{PromptEditMode, PromptHistorySearchStatus},
+        prompt::{PromptEditMode, PromptHistorySearchStatus, TransientPrompt},
+        terminal_extensions::osc8,
         utils::text_manipulation,
-        EditCommand, ExampleHighlighter, Highlighter, LineBuffer, Menu, MenuEvent, Prompt,
-        PromptHistorySearch, ReedlineMenu, Signal, UndoBehavior, ValidationResult, Validator,
+        BracketPairs, Clipboard, EditCommand, ExampleHighlighter, Highlighter, KeybindingConflict,
+        Keybindings, LineBuffer, Menu, MenuEvent, Prompt, PromptHistorySearch, ReedlineMenu,
+        Signal, Suggestion, Theme, UndoBehavior, UndoCoalescing, UndoLimit, ValidationResult,
+        Validator, WordBoundary,
     },
     crossterm::{
         event,
-        event::{Event, KeyCode, KeyEvent, KeyModifiers},
+        event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
         terminal, Result,
     },
-    std::{borrow::Borrow, fs::File, io, io::Write, process::Command, time::Duration},
+    nu_ansi_term::Style,
+    std::{
+        borrow::Borrow,
+        collections::{HashMap, VecDeque},
+        fs::File,
+        io,
+        io::Write,
+        process::Command,
+        time::{Duration, Instant},
+    },
 };
 
 // The POLL_WAIT is used to specify for how long the POLL should wait for
@@ -42,6 +58,12 @@ const POLL_WAIT: u64 = 10;
 // a paste. 10 events in 10 milliseconds is conservative enough (unlikely somebody
 // will type more than 10 characters in 10 milliseconds)
 const EVENTS_THRESHOLD: usize = 10;
+// A batch of keys read this soon after pasted lines is taken for the rest of
+// the paste, which a slow terminal may deliver in several batches
+const PASTE_CONTINUATION: Duration = Duration::from_millis(50);
+// How often a prompt with segments computed in the background is checked for
+// changes while waiting for input
+const PROMPT_REFRESH_INTERVAL: Duration = Duration::from_millis(50);
 
 /// Determines if inputs should be used to extend the regular line buffer,
 /// traverse the history in the standard prompt or edit the search string in the
@@ -88,8 +110,22 @@ pub struct Reedline {
     history_cursor: HistoryCursor,
     history_session_id: Option<HistorySessionId>, // none if history doesn't support this
     history_last_run_id: Option<HistoryItemId>,
+    history_ranking: HistoryRanking,
+    history_navigation_mode: HistoryNavigationMode,
+    history_scope: HistoryScope,
+    // The scope `ToggleHistoryScope` restricts the history to
+    history_toggled_scope: HistoryScope,
+    // Whether the history search and the history menu take regular expressions
+    history_regex_search: bool,
+    // Rows of matches listed below the reverse history search, 0 for none
+    history_search_panel_rows: u16,
     input_mode: InputMode,
 
+    // Command lines kept out of the history
+    history_ignore_space: bool,
+    history_exclusion_patterns: Vec<String>,
+    history_exclusion_filter: Option<HistoryExclusionFilter>,
+
     // Validator
     validator: Option<Box<dyn Validator>>,
 
@@ -107,15 +143,63 @@ pub struct Reedline {
     // Highlight the edit buffer
     highlighter: Box<dyn Highlighter>,
 
+    // Colors of the editor, passed on to the hinter and the menus once set
+    // with `with_theme`
+    theme: Theme,
+    custom_theme: bool,
+
     // Showcase hints based on various strategies (history, language-completion, spellcheck, etc)
-    hinter: Option<Box<dyn Hinter>>,
+    // The hinters in the order they are asked for a hint
+    hinters: HinterList,
+    // A hint was still computed when the buffer was painted
+    pending_hint: bool,
+    // The active menu was still waiting for suggestions when it was painted
+    pending_completions: bool,
     hide_hints: bool,
 
+    // Show the continuations of a pending key sequence below the prompt
+    keybinding_hints: bool,
+    showing_keybinding_hints: bool,
+
+    // Number the lines of a multiline buffer in a gutter
+    line_numbers: LineNumberMode,
+
+    // Move `Up` and `Down` by rows of the screen instead of lines of the buffer
+    display_line_navigation: bool,
+
     // Use ansi coloring or not
     use_ansi_coloring: bool,
 
+    // Paint the hyperlinks of the highlighted buffer
+    use_hyperlinks: bool,
+
+    // Insert the newlines of pasted text instead of submitting it, and mark the
+    // buffer holding the paste until the next input
+    paste_protection: bool,
+    showing_paste: bool,
+    last_paste: Option<Instant>,
+
+    // Mark the prompt, command and output for the terminal, with the exit code
+    // of the command still running since the last submission
+    shell_integration: bool,
+    command_running: bool,
+    last_exit_code: Option<i32>,
+
+    // Capture the mouse, with the events waiting for their `ReedlineEvent::Mouse`
+    mouse_support: bool,
+    mouse_events: VecDeque<MouseEvent>,
+
+    // The prompt as last painted, while parts of it are computed in the background
+    pending_prompt: Option<String>,
+
+    // Repaint the prompt while idle, e.g. for a clock
+    prompt_refresh_interval: Option<Duration>,
+    last_repaint: Instant,
+
     // Engine Menus
     menus: Vec<ReedlineMenu>,
+    // The filter typed into the active menu, while filtering it
+    menu_filter: Option<String>,
 
     // Text editor used to open the line buffer for editing
     buffer_editor: Option<BufferEditor>,
@@ -131,6 +215,9 @@ impl Drop for Reedline {
         // Ensures that the terminal is in a good state if we panic semigracefully
         // Calling `disable_raw_mode()` twice is fine with Linux
         let _ignore = terminal::disable_raw_mode();
+        if self.mouse_support {
+            let _ignore = self.painter.disable_mouse_capture();
+        }
     }
 }
 
@@ -142,7 +229,6 @@ impl Reedline {
         let painter = Painter::new(std::io::BufWriter::new(std::io::stderr()));
         let buffer_highlighter = Box::new(ExampleHighlighter::default());
         let completer = Box::new(DefaultCompleter::default());
-        let hinter = None;
         let validator = None;
         let edit_mode = Box::new(Emacs::default());
 
@@ -155,22 +241,53 @@ impl Reedline {
             history_session_id: None,
             history_last_run_id: None,
             input_mode: InputMode::Regular,
+            history_ignore_space: false,
+            history_exclusion_patterns: Vec::new(),
+            history_exclusion_filter: None,
+            history_ranking: HistoryRanking::Recency,
+            history_navigation_mode: HistoryNavigationMode::Adaptive,
+            history_scope: HistoryScope::Global,
+            history_toggled_scope: HistoryScope::Directory,
+            history_regex_search: false,
+            history_search_panel_rows: 0,
             painter,
             edit_mode,
             completer,
             quick_completions: false,
             partial_completions: false,
             highlighter: buffer_highlighter,
-            hinter,
+            theme: Theme::default(),
+            custom_theme: false,
+            hinters: HinterList::default(),
+            pending_hint: false,
+            pending_completions: false,
             hide_hints: false,
+            keybinding_hints: false,
+            showing_keybinding_hints: false,
+            line_numbers: LineNumberMode::Off,
+            display_line_navigation: false,
             validator,
             use_ansi_coloring: true,
+            use_hyperlinks: osc8::supports_hyperlinks(),
+            paste_protection: false,
+            showing_paste: false,
+            last_paste: None,
+            shell_integration: false,
+            command_running: false,
+            last_exit_code: None,
+            mouse_support: false,
+            mouse_events: VecDeque::new(),
+            pending_prompt: None,
+            prompt_refresh_interval: None,
+            last_repaint: Instant::now(),
             menus: Vec::new(),
+            menu_filter: None,
             buffer_editor: None,
         }
     }
 
-    /// A builder to include a [`Hinter`] in your instance of the Reedline engine
+    /// A builder to include a [`Hinter`] in your instance of the Reedline engine,
+    /// replacing the hinters added before
     /// # Example
     /// ```rust
     /// //Cargo.toml
@@ -188,14 +305,50 @@ impl Reedline {
     /// ```
     #[must_use]
     pub fn with_hinter(mut self, hinter: Box<dyn Hinter>) -> Self {
-        self.hinter = Some(hinter);
+        self.hinters.clear();
+        self.with_fallback_hinter(hinter, None)
+    }
+
+    /// A builder that adds a [`Hinter`] asked for a hint after the ones added
+    /// before, see [`Reedline::with_hint_policy`]
+    ///
+    /// With a `style` its hints are painted in it instead of the hint style of
+    /// the theme, setting them apart from the hints of the other hinters.
+    ///
+    /// # Example
+    /// ```rust
+    /// use {
+    ///     nu_ansi_term::{Color, Style},
+    ///     reedline::{DefaultHinter, HintStrategy, Reedline},
+    /// };
+    ///
+    /// let mut line_editor = Reedline::create()
+    ///     .with_hinter(Box::new(
+    ///         DefaultHinter::default().with_strategy(HintStrategy::DirectoryOnly),
+    ///     ))
+    ///     .with_fallback_hinter(
+    ///         Box::new(DefaultHinter::default()),
+    ///         Some(Style::new().fg(Color::DarkGray)),
+    ///     );
+    /// ```
+    #[must_use]
+    pub fn with_fallback_hinter(mut self, hinter: Box<dyn Hinter>, style: Option<Style>) -> Self {
+        let theme = self.custom_theme.then_some(&self.theme);
+        self.hinters.push(hinter, style, theme);
         self
     }
 
-    /// Remove current [`Hinter`]
+    /// A builder that sets which hint is shown when several hinters have one
+    #[must_use]
+    pub fn with_hint_policy(mut self, policy: HintPolicy) -> Self {
+        self.hinters.set_policy(policy);
+        self
+    }
+
+    /// Remove the current [`Hinter`]s
     #[must_use]
     pub fn disable_hints(mut self) -> Self {
-        self.hinter = None;
+        self.hinters.clear();
         self
     }
 
@@ -238,6 +391,93 @@ impl Reedline {
         self
     }
 
+    /// A builder that configures how consecutive edits are grouped into undo points
+    /// # Example
+    /// ```rust
+    /// use reedline::{Reedline, UndoCoalescing};
+    ///
+    /// let mut line_editor = Reedline::create().with_undo_coalescing(UndoCoalescing::Run);
+    /// ```
+    #[must_use]
+    pub fn with_undo_coalescing(mut self, undo_coalescing: UndoCoalescing) -> Self {
+        self.editor.set_undo_coalescing(undo_coalescing);
+        self
+    }
+
+    /// A builder that bounds the undo history by number of entries and/or total bytes.
+    /// The oldest entries are evicted once the limit is exceeded.
+    /// # Example
+    /// ```rust
+    /// use reedline::{Reedline, UndoLimit};
+    ///
+    /// let mut line_editor = Reedline::create().with_undo_limit(UndoLimit {
+    ///     max_entries: Some(100),
+    ///     max_bytes: Some(1 << 20),
+    /// });
+    /// ```
+    #[must_use]
+    pub fn with_undo_limit(mut self, undo_limit: UndoLimit) -> Self {
+        self.editor.set_undo_limit(undo_limit);
+        self
+    }
+
+    /// A builder which replaces the linear undo stack with an undo tree.
+    /// Editing after an undo then creates a new branch instead of discarding
+    /// the undone changes, the branches can be visited with
+    /// [`ReedlineEvent::UndoBranchNext`] and [`ReedlineEvent::UndoBranchPrevious`]
+    #[must_use]
+    pub fn with_undo_tree(mut self, undo_tree: bool) -> Self {
+        self.editor.set_undo_tree(undo_tree);
+        self
+    }
+
+    /// A builder that enables the automatic completion of the given [`BracketPairs`]
+    /// while typing
+    /// # Example
+    /// ```rust
+    /// use reedline::{BracketPairs, Reedline};
+    ///
+    /// let mut line_editor =
+    ///     Reedline::create().with_bracket_pairs(BracketPairs::brackets().with_pair('"', '"'));
+    /// ```
+    #[must_use]
+    pub fn with_bracket_pairs(mut self, bracket_pairs: BracketPairs) -> Self {
+        self.editor.set_bracket_pairs(bracket_pairs);
+        self
+    }
+
+    /// A builder that sets the [`WordBoundary`] deciding what counts as a word for
+    /// the word motions and edits
+    /// # Example
+    /// ```rust
+    /// use reedline::{Reedline, WordBoundary};
+    ///
+    /// // Delete up to the last `/` of a path with a single `CutWordLeft`
+    /// let mut line_editor = Reedline::create().with_word_boundary(WordBoundary::PathComponent);
+    /// ```
+    #[must_use]
+    pub fn with_word_boundary(mut self, word_boundary: WordBoundary) -> Self {
+        self.editor.set_word_boundary(word_boundary);
+        self
+    }
+
+    /// A builder that sets the [`Clipboard`] used for cut, copy and paste.
+    /// Implement the trait to use the clipboard of the host application.
+    ///
+    /// Defaults to the system clipboard if the `system_clipboard` feature is
+    /// enabled and to a clipboard local to the instance otherwise.
+    /// # Example
+    /// ```rust
+    /// use reedline::{LocalClipboard, Reedline};
+    ///
+    /// let mut line_editor = Reedline::create().with_clipboard(Box::new(LocalClipboard::new()));
+    /// ```
+    #[must_use]
+    pub fn with_clipboard(mut self, clipboard: Box<dyn Clipboard>) -> Self {
+        self.editor.set_clipboard(clipboard);
+        self
+    }
+
     /// A builder which enables or disables the use of ansi coloring in the prompt
     /// and in the command line syntax highlighting.
     #[must_use]
@@ -246,6 +486,94 @@ impl Reedline {
         self
     }
 
+    /// A builder which enables or disables painting the hyperlinks of the
+    /// [`StyledText`](crate::StyledText) returned by the [`Highlighter`]
+    ///
+    /// Defaults to a guess from the environment if the terminal supports
+    /// hyperlinks (OSC 8), as others may print the escape sequences.
+    #[must_use]
+    pub fn with_hyperlinks(mut self, use_hyperlinks: bool) -> Self {
+        self.use_hyperlinks = use_hyperlinks;
+        self
+    }
+
+    /// A builder which enables or disables synchronized output (DEC mode 2026),
+    /// asking the terminal to draw every repaint at once to avoid flicker
+    ///
+    /// Enabled by default, terminals not supporting the mode ignore it.
+    #[must_use]
+    pub fn with_synchronized_output(mut self, synchronized_output: bool) -> Self {
+        self.painter.set_synchronized_output(synchronized_output);
+        self
+    }
+
+    /// A builder that hides the right prompt on terminals narrower than
+    /// `min_width` columns, leaving the space to the input. Left prompts wider
+    /// than the terminal are always shortened from the start
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    ///
+    /// let mut line_editor = Reedline::create().with_right_prompt_min_width(60);
+    /// ```
+    #[must_use]
+    pub fn with_right_prompt_min_width(mut self, min_width: u16) -> Self {
+        self.painter.set_right_prompt_min_width(min_width);
+        self
+    }
+
+    /// A builder that protects against running pasted lines, off by default
+    ///
+    /// The newlines of text pasted in one go are inserted instead of submitting
+    /// the buffer, which is painted with the pasted text style until the next
+    /// input. Only an explicit Enter runs the pasted lines.
+    ///
+    /// Without bracketed paste support in the terminal backend, pastes are
+    /// recognized by several keys arriving in one batch with an Enter, or
+    /// right after such a batch, so tools typing into the terminal can't
+    /// submit lines either. Lines typed ahead over a slow connection or typed
+    /// very fast are taken for a paste as well and need another Enter.
+    #[must_use]
+    pub fn with_paste_protection(mut self, paste_protection: bool) -> Self {
+        self.paste_protection = paste_protection;
+        self
+    }
+
+    /// A builder that sets the style of the buffer holding a multi-line paste,
+    /// see [`Reedline::with_paste_protection`]
+    ///
+    /// Overridden by a later [`Reedline::with_theme`].
+    #[must_use]
+    pub fn with_pasted_text_style(mut self, style: Style) -> Self {
+        self.theme.pasted_text = style;
+        self
+    }
+
+    /// A builder that marks the prompt, the typed command and its output with
+    /// the OSC 133 escape sequences
+    ///
+    /// Terminals like WezTerm, Kitty or iTerm2 use them to jump between prompts
+    /// or to show the exit status of each command, as reported through
+    /// [`Reedline::set_last_exit_code`].
+    #[must_use]
+    pub fn with_shell_integration(mut self, shell_integration: bool) -> Self {
+        self.shell_integration = shell_integration;
+        self.painter.set_shell_integration(shell_integration);
+        self
+    }
+
+    /// A builder that captures the mouse while reading a line
+    ///
+    /// A left click moves the insertion point to the clicked character or
+    /// selects the clicked menu entry, the wheel scrolls through an active menu.
+    /// While the mouse is captured, most terminals only select text for copying
+    /// with Shift held.
+    #[must_use]
+    pub fn with_mouse_support(mut self, mouse_support: bool) -> Self {
+        self.mouse_support = mouse_support;
+        self
+    }
+
     /// A builder that configures the highlighter for your instance of the Reedline engine
     /// # Example
     /// ```rust
@@ -268,6 +596,141 @@ impl Reedline {
         self
     }
 
+    /// A builder that configures the [`Abbreviation`]s expanded when typing a space
+    /// or submitting the line
+    /// # Example
+    /// ```rust
+    /// use reedline::{DefaultAbbreviations, Reedline};
+    ///
+    /// let abbreviations = DefaultAbbreviations::new().with_abbreviation("gco", "git checkout");
+    /// let mut line_editor = Reedline::create().with_abbreviations(Box::new(abbreviations));
+    /// ```
+    #[must_use]
+    pub fn with_abbreviations(mut self, abbreviations: Box<dyn Abbreviation>) -> Self {
+        self.editor.set_abbreviations(abbreviations);
+        self
+    }
+
+    /// A builder that sets an [`AbbreviationHook`] called before every expansion of an
+    /// abbreviation, it can return a different expansion or `None` to keep the abbreviation
+    /// # Example
+    /// ```rust
+    /// use reedline::{DefaultAbbreviations, Reedline};
+    ///
+    /// let abbreviations = DefaultAbbreviations::new().with_abbreviation("gco", "git checkout");
+    /// let mut line_editor = Reedline::create()
+    ///     .with_abbreviations(Box::new(abbreviations))
+    ///     .with_abbreviation_hook(Box::new(|_abbreviation, expansion| {
+    ///         Some(format!("{} --quiet", expansion))
+    ///     }));
+    /// ```
+    #[must_use]
+    pub fn with_abbreviation_hook(mut self, hook: AbbreviationHook) -> Self {
+        self.editor.set_abbreviation_hook(hook);
+        self
+    }
+
+    /// A builder that sets the style of the bracket matching the one at the cursor,
+    /// `None` turns the highlighting of matching brackets off again (default)
+    ///
+    /// Overridden by a later [`Reedline::with_theme`].
+    /// # Example
+    /// ```rust
+    /// use {
+    ///     nu_ansi_term::{Color, Style},
+    ///     reedline::Reedline,
+    /// };
+    ///
+    /// let mut line_editor =
+    ///     Reedline::create().with_matching_bracket_style(Some(Style::new().fg(Color::Cyan)));
+    /// ```
+    #[must_use]
+    pub fn with_matching_bracket_style(mut self, style: Option<Style>) -> Self {
+        self.theme.matching_bracket = style;
+        self
+    }
+
+    /// A builder that sets the colors of the editor, the hint and the menus,
+    /// also for the hinter and menus added later on
+    ///
+    /// The styles of the theme take precedence over those set on the hinter
+    /// and the menus themselves. It replaces the styles set before with
+    /// [`Reedline::with_matching_bracket_style`] and
+    /// [`Reedline::with_pasted_text_style`], which change the theme when
+    /// called after this.
+    #[must_use]
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.hinters.apply_theme(&theme);
+        for menu in &mut self.menus {
+            menu.apply_theme(&theme);
+        }
+        self.theme = theme;
+        self.custom_theme = true;
+        self
+    }
+
+    /// A builder that enables the popup below the prompt listing the keys that
+    /// can follow a pending key sequence, chord or partial vi command, together
+    /// with what they do
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    ///
+    /// let mut line_editor = Reedline::create().with_keybinding_hints(true);
+    /// ```
+    #[must_use]
+    pub fn with_keybinding_hints(mut self, keybinding_hints: bool) -> Self {
+        self.keybinding_hints = keybinding_hints;
+        self
+    }
+
+    /// A builder that paints a gutter numbering the lines while the buffer
+    /// spans multiple lines, with absolute numbers or, for counted vi motions,
+    /// with the distance to the line of the cursor
+    /// # Example
+    /// ```rust
+    /// use reedline::{LineNumberMode, Reedline};
+    ///
+    /// let mut line_editor = Reedline::create().with_line_numbers(LineNumberMode::Relative);
+    /// ```
+    #[must_use]
+    pub fn with_line_numbers(mut self, mode: LineNumberMode) -> Self {
+        self.line_numbers = mode;
+        self
+    }
+
+    /// A builder that repaints the prompt every `interval` while waiting for
+    /// input, for prompts showing a clock or the time since the last command
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    /// use std::time::Duration;
+    ///
+    /// let mut line_editor =
+    ///     Reedline::create().with_prompt_refresh_interval(Duration::from_secs(1));
+    /// ```
+    #[must_use]
+    pub fn with_prompt_refresh_interval(mut self, interval: Duration) -> Self {
+        self.prompt_refresh_interval = Some(interval);
+        self
+    }
+
+    /// A builder that makes [`ReedlineEvent::Up`] and [`ReedlineEvent::Down`]
+    /// move by the rows of the screen, like [`ReedlineEvent::DisplayLineUp`]
+    /// and [`ReedlineEvent::DisplayLineDown`], so lines wrapping the screen can
+    /// be walked through row by row
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    ///
+    /// let mut line_editor = Reedline::create().with_display_line_navigation(true);
+    /// ```
+    #[must_use]
+    pub fn with_display_line_navigation(mut self, display_line_navigation: bool) -> Self {
+        self.display_line_navigation = display_line_navigation;
+        self
+    }
+
     /// A builder which configures the history for your instance of the Reedline engine
     /// # Example
     /// ```rust,no_run
@@ -288,6 +751,142 @@ impl Reedline {
         self
     }
 
+    /// A builder that keeps command lines starting with a space out of the
+    /// history, like `ignorespace` of bash
+    #[must_use]
+    pub fn with_history_ignore_space(mut self, ignore_space: bool) -> Self {
+        self.history_ignore_space = ignore_space;
+        self
+    }
+
+    /// A builder that keeps the command lines matching one of the `patterns`
+    /// out of the history, like `HISTIGNORE` of bash
+    ///
+    /// A pattern has to match the whole command line. `*` matches any text,
+    /// `?` any character and `[...]` one of the characters in the brackets.
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    ///
+    /// let mut line_editor = Reedline::create().with_history_exclusion_patterns(vec![
+    ///     "ls".into(),
+    ///     "[bf]g".into(),
+    ///     "* --password *".into(),
+    /// ]);
+    /// ```
+    #[must_use]
+    pub fn with_history_exclusion_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.history_exclusion_patterns = patterns;
+        self
+    }
+
+    /// A builder that sets a [`HistoryExclusionFilter`] keeping the command
+    /// lines it returns `true` for out of the history
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    ///
+    /// let mut line_editor = Reedline::create()
+    ///     .with_history_exclusion_filter(Box::new(|line| line.contains("SECRET=")));
+    /// ```
+    #[must_use]
+    pub fn with_history_exclusion_filter(mut self, filter: HistoryExclusionFilter) -> Self {
+        self.history_exclusion_filter = Some(filter);
+        self
+    }
+
+    /// A builder that sets the order of the entries offered by the history
+    /// navigation and the history menu, by default the most recent first
+    /// # Example
+    /// ```rust
+    /// use reedline::{HistoryRanking, Reedline};
+    ///
+    /// let mut line_editor = Reedline::create()
+    ///     .with_history_ranking(HistoryRanking::Frecency { boost_cwd: true });
+    /// ```
+    #[must_use]
+    pub fn with_history_ranking(mut self, ranking: HistoryRanking) -> Self {
+        self.history_ranking = ranking;
+        self
+    }
+
+    /// A builder that sets which entries Up and Down walk through from the
+    /// line buffer, see [`HistoryNavigationMode`]
+    /// # Example
+    /// ```rust
+    /// use reedline::{HistoryNavigationMode, Reedline};
+    ///
+    /// // Type a prefix, then cycle through the entries starting with it
+    /// let mut line_editor =
+    ///     Reedline::create().with_history_navigation_mode(HistoryNavigationMode::Prefix);
+    /// ```
+    #[must_use]
+    pub fn with_history_navigation_mode(mut self, mode: HistoryNavigationMode) -> Self {
+        self.history_navigation_mode = mode;
+        self
+    }
+
+    /// A builder that restricts the history navigation and the history menu
+    /// to the entries run in the current working directory, or below it
+    ///
+    /// [`ReedlineEvent::ToggleHistoryScope`] switches between this scope and
+    /// all entries, restricting them to [`HistoryScope::Directory`] when
+    /// starting from [`HistoryScope::Global`].
+    /// # Example
+    /// ```rust
+    /// use reedline::{HistoryScope, Reedline};
+    ///
+    /// let mut line_editor = Reedline::create().with_history_scope(HistoryScope::Subtree);
+    /// ```
+    #[must_use]
+    pub fn with_history_scope(mut self, scope: HistoryScope) -> Self {
+        self.history_scope = scope;
+        if scope != HistoryScope::Global {
+            self.history_toggled_scope = scope;
+        }
+        self
+    }
+
+    /// A builder that makes the reverse history search (Ctrl-R) and the
+    /// history menu take the text typed as a regular expression
+    ///
+    /// While the text is no valid regular expression yet nothing matches it.
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    ///
+    /// let mut line_editor = Reedline::create().with_history_regex_search(true);
+    /// ```
+    #[cfg(feature = "regex")]
+    #[must_use]
+    pub fn with_history_regex_search(mut self, regex: bool) -> Self {
+        self.history_regex_search = regex;
+        self
+    }
+
+    /// A builder that lists up to `rows` matches of the reverse history search
+    /// (Ctrl-R) below the prompt, with the parts matching highlighted and the
+    /// match at the cursor selected
+    ///
+    /// Up and Down move the selection in the list, the most recent match being
+    /// on top. By default (0) only the match at the cursor is shown.
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    ///
+    /// let mut line_editor = Reedline::create().with_history_search_panel(10);
+    /// ```
+    #[must_use]
+    pub fn with_history_search_panel(mut self, rows: u16) -> Self {
+        self.history_search_panel_rows = rows;
+        self
+    }
+
+    /// Which entries the history navigation and the history menu offer right now
+    pub fn history_scope(&self) -> HistoryScope {
+        self.history_scope
+    }
+
     /// A builder that configures the validator for your instance of the Reedline engine
     /// # Example
     /// ```rust
@@ -330,17 +929,52 @@ impl Reedline {
     /// A builder which configures the edit mode for your instance of the Reedline engine
     #[must_use]
     pub fn with_edit_mode(mut self, edit_mode: Box<dyn EditMode>) -> Self {
+        // Consecutive kills are only joined in the kill ring in Emacs style editing
+        self.editor
+            .set_join_kills(matches!(edit_mode.edit_mode(), PromptEditMode::Emacs));
         self.edit_mode = edit_mode;
         self
     }
 
     /// A builder that appends a menu to the engine
     #[must_use]
-    pub fn with_menu(mut self, menu: ReedlineMenu) -> Self {
+    pub fn with_menu(mut self, mut menu: ReedlineMenu) -> Self {
+        if self.custom_theme {
+            menu.apply_theme(&self.theme);
+        }
         self.menus.push(menu);
         self
     }
 
+    /// Open the [`ReedlineMenu::Picker`] named `name` with the `items` to pick
+    /// from, closing the open menu
+    ///
+    /// The picker shows on the next call of [`Reedline::read_line`], which
+    /// returns the picked item in [`Signal::Picked`]. Call it from the host
+    /// between two calls, e.g. after a [`ReedlineEvent::Custom`] event bound to
+    /// a key. Returns `false` if there is no picker with that name.
+    pub fn open_picker(&mut self, name: &str, items: Vec<Suggestion>) -> bool {
+        let index = match self
+            .menus
+            .iter()
+            .position(|menu| matches!(menu, ReedlineMenu::Picker { .. }) && menu.name() == name)
+        {
+            Some(index) => index,
+            None => return false,
+        };
+
+        self.deactivate_menus();
+        if let ReedlineMenu::Picker {
+            menu,
+            items: picker_items,
+        } = &mut self.menus[index]
+        {
+            *picker_items = items;
+            menu.menu_event(MenuEvent::Activate(false));
+        }
+        true
+    }
+
     /// A builder that clears the list of menus added to the engine
     #[must_use]
     pub fn clear_menus(mut self) -> Self {
@@ -353,6 +987,71 @@ impl Reedline {
         self.edit_mode.edit_mode()
     }
 
+    /// Bind a key combination to an event in the keybindings of the current
+    /// edit mode, in vi the ones of the insert or normal mode
+    ///
+    /// Takes effect for the next key, so hosts can offer a `bindkey`-style
+    /// command run through [`ReedlineEvent::ExecuteHostCommand`] between two
+    /// calls to [`Reedline::read_line`]. Returns `false` if the edit mode
+    /// doesn't use [`Keybindings`].
+    ///
+    /// # Panics
+    ///
+    /// If `event` is an empty [`ReedlineEvent::UntilFound`]
+    ///
+    /// # Example
+    /// ```rust
+    /// use crossterm::event::{KeyCode, KeyModifiers};
+    /// use reedline::{Reedline, ReedlineEvent};
+    ///
+    /// let mut line_editor = Reedline::create();
+    /// line_editor.bind_key(
+    ///     KeyModifiers::ALT,
+    ///     KeyCode::Char('h'),
+    ///     ReedlineEvent::ExecuteHostCommand("help".into()),
+    /// );
+    /// ```
+    pub fn bind_key(
+        &mut self,
+        modifier: KeyModifiers,
+        key_code: KeyCode,
+        event: ReedlineEvent,
+    ) -> bool {
+        match self.edit_mode.keybindings_mut() {
+            Some(keybindings) => {
+                keybindings.add_binding(modifier, key_code, event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a key combination from the keybindings of the current edit mode
+    ///
+    /// Returns the event the key combination was bound to
+    pub fn unbind_key(
+        &mut self,
+        modifier: KeyModifiers,
+        key_code: KeyCode,
+    ) -> Option<ReedlineEvent> {
+        self.edit_mode
+            .keybindings_mut()?
+            .remove_binding(modifier, key_code)
+    }
+
+    /// The keybindings of the current edit mode, `None` if the edit mode
+    /// doesn't use [`Keybindings`]
+    pub fn get_bindings(&self) -> Option<&Keybindings> {
+        self.edit_mode.keybindings()
+    }
+
+    /// The keybindings of the current edit mode that don't work as they may
+    /// be expected to, like bindings shadowed by the keys the edit mode
+    /// handles itself, for a `bindkey -l`-style diagnostic
+    pub fn keybinding_conflicts(&self) -> Vec<KeybindingConflict> {
+        self.edit_mode.keybinding_conflicts()
+    }
+
     /// Output the complete [`History`] chronologically with numbering to the terminal
     pub fn print_history(&mut self) -> Result<()> {
         let history: Vec<_> = self
@@ -366,11 +1065,34 @@ impl Reedline {
         Ok(())
     }
 
+    /// The contents of the buffer, kept after [`Reedline::read_line`] returned
+    /// for a [`ReedlineEvent::Custom`] event
+    pub fn current_buffer_contents(&self) -> &str {
+        self.editor.get_buffer()
+    }
+
+    /// The position of the cursor in the buffer as a byte offset
+    pub fn current_insertion_point(&self) -> usize {
+        self.editor.insertion_point()
+    }
+
     /// Read-only view of the history
     pub fn history(&self) -> &dyn History {
         &*self.history
     }
 
+    /// Read-only view of the marks set in the buffer (vi `m`), as offsets into the
+    /// buffer by their name
+    pub fn marks(&self) -> &HashMap<char, usize> {
+        self.editor.marks()
+    }
+
+    /// Report the exit code of the command last submitted, so the terminal
+    /// can show it when the shell integration is enabled
+    pub fn set_last_exit_code(&mut self, exit_code: i32) {
+        self.last_exit_code = Some(exit_code);
+    }
+
     /// Update the underlying [`History`] to/from disk
     pub fn sync_history(&mut self) -> std::io::Result<()> {
         // TODO: check for interactions in the non-submitting events
@@ -392,6 +1114,23 @@ impl Reedline {
         Ok(())
     }
 
+    /// Attach a custom key/value pair to the [`HistoryItem::metadata`] of the
+    /// last history item, replacing the value already set for the key
+    ///
+    /// The metadata is stored and can be searched for by the
+    /// [`crate::SqliteBackedHistory`], see [`crate::SearchFilter::metadata`]
+    pub fn set_last_command_metadata(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> crate::Result<()> {
+        let (key, value) = (key.into(), value.into());
+        self.update_last_command_context(&|mut c| {
+            c.metadata.insert(key.clone(), value.clone());
+            c
+        })
+    }
+
     /// Wait for input and provide the user with a specified [`Prompt`].
     ///
     /// Returns a [`crossterm::Result`] in which the `Err` type is [`crossterm::ErrorKind`]
@@ -399,17 +1138,38 @@ impl Reedline {
     /// handles user inputs.
     pub fn read_line(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
         terminal::enable_raw_mode()?;
+        if self.mouse_support {
+            self.painter.enable_mouse_capture()?;
+        }
 
         let result = self.read_line_helper(prompt);
 
+        if self.mouse_support {
+            self.painter.disable_mouse_capture()?;
+        }
         terminal::disable_raw_mode()?;
 
         result
     }
 
-    /// Writes `msg` to the terminal with a following carriage return and newline
-    fn print_line(&mut self, msg: &str) -> Result<()> {
-        self.painter.paint_line(msg)
+    /// Writes `msg` to the terminal with a following carriage return and newline
+    fn print_line(&mut self, msg: &str) -> Result<()> {
+        self.painter.paint_line(msg)
+    }
+
+    /// Shows `message` in a status line below the input, e.g. to report an
+    /// error or a mode of the host, until the next keystroke or
+    /// [`Reedline::clear_status()`]
+    ///
+    /// Set before [`Reedline::read_line()`], the message appears with the prompt
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        self.painter.set_status(Some(message.into()));
+    }
+
+    /// Removes the status line set with [`Reedline::set_status()`] or by the
+    /// validator
+    pub fn clear_status(&mut self) {
+        self.painter.set_status(None);
     }
 
     /// Clear the screen by printing enough whitespace to start the prompt or
@@ -430,8 +1190,17 @@ impl Reedline {
     /// Helper implementing the logic for [`Reedline::read_line()`] to be wrapped
     /// in a `raw_mode` context.
     fn read_line_helper(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
+        if self.command_running {
+            self.painter.mark_command_finished(self.last_exit_code)?;
+            self.command_running = false;
+        }
+        self.last_exit_code = None;
+
         self.painter.initialize_prompt_position()?;
         self.hide_hints = false;
+        self.mouse_events.clear();
+        self.showing_paste = false;
+        self.last_paste = None;
 
         self.repaint(prompt)?;
 
@@ -441,7 +1210,22 @@ impl Reedline {
         loop {
             let mut paste_enter_state = false;
 
-            if event::poll(Duration::from_millis(1000))? {
+            // A pending key sequence is resolved once no further key arrives in time
+            let sequence_timeout = self.edit_mode.sequence_timeout();
+            let refresh_timeout =
+                (self.pending_prompt.is_some() || self.pending_hint || self.pending_completions)
+                    .then_some(PROMPT_REFRESH_INTERVAL)
+                    .into_iter()
+                    .chain(
+                        self.prompt_refresh_interval
+                            .map(|interval| interval.saturating_sub(self.last_repaint.elapsed())),
+                    )
+                    .min();
+            if event::poll(
+                sequence_timeout
+                    .or(refresh_timeout)
+                    .unwrap_or(Duration::from_millis(1000)),
+            )? {
                 let mut latest_resize = None;
 
                 // There could be multiple events queued up!
@@ -462,8 +1246,10 @@ impl Reedline {
                             // multiple complete entries are submitted, events
                             // are still in the crossterm queue for us to
                             // process.
-                            paste_enter_state = crossterm_events.len() > EVENTS_THRESHOLD;
-                            break;
+                            if !self.paste_protection {
+                                paste_enter_state = crossterm_events.len() > EVENTS_THRESHOLD;
+                                break;
+                            }
                         }
                         x => {
                             crossterm_events.push(x);
@@ -475,12 +1261,52 @@ impl Reedline {
                     reedline_events.push(ReedlineEvent::Resize(x, y));
                 }
 
+                // Pasted lines are inserted without running them. Crossterm
+                // doesn't report bracketed pastes, which would tell for sure.
+                let continues_paste = self
+                    .last_paste
+                    .is_some_and(|last_paste| last_paste.elapsed() < PASTE_CONTINUATION);
+                let pasted_lines =
+                    self.paste_protection && holds_pasted_lines(&crossterm_events, continues_paste);
+                if !crossterm_events.is_empty() {
+                    self.showing_paste = pasted_lines;
+                    self.last_paste = pasted_lines.then(Instant::now);
+                }
+
+                // The status line stays until the next keystroke
+                let status_cleared = self.painter.has_status()
+                    && crossterm_events
+                        .iter()
+                        .any(|event| matches!(event, Event::Key(_)));
+                if status_cleared {
+                    self.painter.set_status(None);
+                }
+
                 // Accelerate pasted text by fusing `EditCommand`s
                 //
                 // (Text should only be `EditCommand::InsertChar`s)
                 let mut last_edit_commands = None;
                 for event in crossterm_events.drain(..) {
-                    match (&mut last_edit_commands, self.edit_mode.parse_event(event)) {
+                    if let Event::Mouse(mouse) = event {
+                        // Resolved against the painted layout once its `ReedlineEvent::Mouse` is handled
+                        self.mouse_events.push_back(mouse);
+                    }
+                    let reedline_event = match event {
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Enter,
+                            modifiers: KeyModifiers::NONE,
+                        }) if pasted_lines => ReedlineEvent::Edit(vec![EditCommand::InsertNewline]),
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Char(label),
+                            modifiers,
+                        }) if self.quick_select_modifier() == Some(modifiers)
+                            && self.shows_quick_select_label(label) =>
+                        {
+                            ReedlineEvent::MenuQuickSelect(label)
+                        }
+                        event => self.edit_mode.parse_event(event),
+                    };
+                    match (&mut last_edit_commands, reedline_event) {
                         (None, ReedlineEvent::Edit(ec)) => {
                             last_edit_commands = Some(ec);
                         }
@@ -500,13 +1326,46 @@ impl Reedline {
                 if let Some(ec) = last_edit_commands {
                     reedline_events.push(ReedlineEvent::Edit(ec));
                 }
-            };
+                if status_cleared {
+                    reedline_events.push(ReedlineEvent::Repaint);
+                }
+            } else if sequence_timeout.is_some() {
+                reedline_events.push(self.edit_mode.handle_sequence_timeout());
+            } else {
+                let prompt_resolved = match &self.pending_prompt {
+                    Some(painted_prompt) => {
+                        !prompt.has_pending_segments()
+                            || *painted_prompt != self.rendered_prompt(prompt)
+                    }
+                    None => false,
+                };
+                let refresh_due = matches!(
+                    self.prompt_refresh_interval,
+                    Some(interval) if self.last_repaint.elapsed() >= interval
+                );
+                let hint_resolved = self.pending_hint && !self.hinters.has_pending_hint();
+                if self.pending_completions {
+                    // The suggestions arrived since are added to the menu,
+                    // which drops the filter of the values replaced
+                    if let Some(menu) = self.active_menu() {
+                        menu.menu_event(MenuEvent::Refresh);
+                        self.menu_filter = None;
+                    }
+                }
+                if prompt_resolved || hint_resolved || self.pending_completions || refresh_due {
+                    reedline_events.push(ReedlineEvent::Repaint);
+                }
+            }
 
             for event in reedline_events.drain(..) {
                 match self.handle_event(prompt, event)? {
                     EventStatus::Exits(signal) => {
                         // Move the cursor below the input area, for external commands or new read_line call
                         self.painter.move_cursor_to_end()?;
+                        if self.shell_integration && matches!(signal, Signal::Success(_)) {
+                            self.painter.mark_output_start()?;
+                            self.command_running = true;
+                        }
                         return Ok(signal);
                     }
                     EventStatus::Handled => {
@@ -515,7 +1374,14 @@ impl Reedline {
                         }
                     }
                     EventStatus::Inapplicable => {
-                        // Nothing changed, no need to repaint
+                        // Nothing changed, but the keybinding hints may have to
+                        // appear or disappear for a key continuing a sequence
+                        if self.keybinding_hints
+                            && (self.showing_keybinding_hints
+                                || !self.edit_mode.pending_continuations().is_empty())
+                        {
+                            self.repaint(prompt)?;
+                        }
                     }
                 }
             }
@@ -585,11 +1451,15 @@ impl Reedline {
                 // TODO: Decide if we need to do something special to have a nicer painter state on the next go
                 Ok(EventStatus::Exits(Signal::Success(host_command)))
             }
+            ReedlineEvent::Custom(name) => Ok(EventStatus::Exits(Signal::Custom(name))),
             ReedlineEvent::Edit(commands) => {
                 self.run_history_commands(&commands);
                 Ok(EventStatus::Handled)
             }
-            ReedlineEvent::Mouse => Ok(EventStatus::Handled),
+            ReedlineEvent::Mouse => {
+                self.mouse_events.pop_front();
+                Ok(EventStatus::Handled)
+            }
             ReedlineEvent::Resize(width, height) => {
                 self.painter.handle_resize(width, height);
                 Ok(EventStatus::Inapplicable)
@@ -598,34 +1468,42 @@ impl Reedline {
                 // A handled Event causes a repaint
                 Ok(EventStatus::Handled)
             }
-            ReedlineEvent::PreviousHistory | ReedlineEvent::Up | ReedlineEvent::SearchHistory => {
-                self.history_cursor
-                    .back(self.history.as_ref())
-                    .expect("todo: error handling");
+            // The panel lists the most recent match on top
+            ReedlineEvent::Up | ReedlineEvent::DisplayLineUp
+                if self.history_search_panel_rows > 0 =>
+            {
+                self.history_search_newer();
                 Ok(EventStatus::Handled)
             }
-            ReedlineEvent::NextHistory | ReedlineEvent::Down => {
-                self.history_cursor
-                    .forward(self.history.as_ref())
-                    .expect("todo: error handling");
-                // Hacky way to ensure that we don't fall of into failed search going forward
-                if self.history_cursor.string_at_cursor().is_none() {
-                    self.history_cursor
-                        .back(self.history.as_ref())
-                        .expect("todo: error handling");
-                }
+            ReedlineEvent::Down | ReedlineEvent::DisplayLineDown
+                if self.history_search_panel_rows > 0 =>
+            {
+                self.history_search_older();
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::PreviousHistory
+            | ReedlineEvent::Up
+            | ReedlineEvent::DisplayLineUp
+            | ReedlineEvent::SearchHistory => {
+                self.history_search_older();
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::NextHistory | ReedlineEvent::Down | ReedlineEvent::DisplayLineDown => {
+                self.history_search_newer();
                 Ok(EventStatus::Handled)
             }
             ReedlineEvent::Esc => {
                 self.input_mode = InputMode::Regular;
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::DeleteHistoryEntry => Ok(self.delete_history_search_entry()),
             // TODO: Check if events should be handled
             ReedlineEvent::Right
             | ReedlineEvent::Left
             | ReedlineEvent::Multiple(_)
             | ReedlineEvent::None
             | ReedlineEvent::HistoryHintWordComplete
+            | ReedlineEvent::HistoryHintLineComplete
             | ReedlineEvent::OpenEditor
             | ReedlineEvent::Menu(_)
             | ReedlineEvent::MenuNext
@@ -636,7 +1514,15 @@ impl Reedline {
             | ReedlineEvent::MenuRight
             | ReedlineEvent::MenuPageNext
             | ReedlineEvent::MenuPagePrevious
-            | ReedlineEvent::RecordToTill => Ok(EventStatus::Inapplicable),
+            | ReedlineEvent::MenuDocumentationDown
+            | ReedlineEvent::MenuDocumentationUp
+            | ReedlineEvent::MenuToggleMultiSelect
+            | ReedlineEvent::MenuQuickSelect(_)
+            | ReedlineEvent::MenuFilter
+            | ReedlineEvent::RecordToTill
+            | ReedlineEvent::UndoBranchNext
+            | ReedlineEvent::UndoBranchPrevious
+            | ReedlineEvent::ToggleHistoryScope => Ok(EventStatus::Inapplicable),
         }
     }
 
@@ -645,6 +1531,11 @@ impl Reedline {
         prompt: &dyn Prompt,
         event: ReedlineEvent,
     ) -> io::Result<EventStatus> {
+        let event = match self.filter_active_menu(event) {
+            Some(event) => event,
+            None => return Ok(EventStatus::Handled),
+        };
+
         match event {
             ReedlineEvent::Menu(name) => {
                 if self.active_menu().is_none() {
@@ -655,7 +1546,12 @@ impl Reedline {
                             menu.update_values(
                                 &mut self.editor,
                                 self.completer.as_mut(),
-                                self.history.as_ref(),
+                                &mut HistoryCompleter::new(
+                                    self.history.as_ref(),
+                                    self.history_ranking,
+                                    self.history_scope,
+                                )
+                                .with_regex(self.history_regex_search),
                             );
 
                             if menu.get_values().len() == 1 {
@@ -663,14 +1559,25 @@ impl Reedline {
                             }
                         }
 
-                        if self.partial_completions
+                        let prefix_first = menu.inserts_common_prefix_first();
+                        let buffer = self.editor.get_buffer().to_string();
+                        if (self.partial_completions || prefix_first)
                             && menu.can_partially_complete(
                                 self.quick_completions,
                                 &mut self.editor,
                                 self.completer.as_mut(),
-                                self.history.as_ref(),
+                                &mut HistoryCompleter::new(
+                                    self.history.as_ref(),
+                                    self.history_ranking,
+                                    self.history_scope,
+                                )
+                                .with_regex(self.history_regex_search),
                             )
                         {
+                            // The menu opens on the next press once nothing is left to insert
+                            if prefix_first && self.editor.get_buffer() != buffer {
+                                menu.menu_event(MenuEvent::Deactivate);
+                            }
                             return Ok(EventStatus::Handled);
                         }
 
@@ -735,36 +1642,58 @@ impl Reedline {
                         Ok(EventStatus::Handled)
                     })
             }
-            ReedlineEvent::HistoryHintComplete => {
-                if let Some(hinter) = self.hinter.as_mut() {
-                    let current_hint = hinter.complete_hint();
-                    if self.hints_active()
-                        && self.editor.is_cursor_at_buffer_end()
-                        && !current_hint.is_empty()
-                        && self.active_menu().is_none()
-                    {
-                        self.run_edit_commands(&[EditCommand::InsertString(current_hint)]);
-                        return Ok(EventStatus::Handled);
-                    }
+            ReedlineEvent::MenuDocumentationDown => {
+                self.active_menu()
+                    .map_or(Ok(EventStatus::Inapplicable), |menu| {
+                        menu.menu_event(MenuEvent::DocumentationDown);
+                        Ok(EventStatus::Handled)
+                    })
+            }
+            ReedlineEvent::MenuDocumentationUp => {
+                self.active_menu()
+                    .map_or(Ok(EventStatus::Inapplicable), |menu| {
+                        menu.menu_event(MenuEvent::DocumentationUp);
+                        Ok(EventStatus::Handled)
+                    })
+            }
+            ReedlineEvent::MenuToggleMultiSelect => {
+                self.active_menu()
+                    .map_or(Ok(EventStatus::Inapplicable), |menu| {
+                        menu.menu_event(MenuEvent::ToggleMultiSelect);
+                        Ok(EventStatus::Handled)
+                    })
+            }
+            ReedlineEvent::MenuQuickSelect(label) => {
+                let available_lines = self.painter.menu_available_lines();
+                let selected = self
+                    .active_menu()
+                    .is_some_and(|menu| menu.quick_select(label, available_lines));
+                if selected {
+                    self.handle_editor_event(prompt, ReedlineEvent::Enter)
+                } else {
+                    Ok(EventStatus::Inapplicable)
                 }
-                Ok(EventStatus::Inapplicable)
             }
-            ReedlineEvent::HistoryHintWordComplete => {
-                if let Some(hinter) = self.hinter.as_mut() {
-                    let current_hint_part = hinter.next_hint_token();
-                    if self.hints_active()
-                        && self.editor.is_cursor_at_buffer_end()
-                        && !current_hint_part.is_empty()
-                        && self.active_menu().is_none()
-                    {
-                        self.run_edit_commands(&[EditCommand::InsertString(current_hint_part)]);
-                        return Ok(EventStatus::Handled);
-                    }
+            ReedlineEvent::MenuFilter => {
+                if self.active_menu().is_none() {
+                    return Ok(EventStatus::Inapplicable);
                 }
-                Ok(EventStatus::Inapplicable)
+                self.menu_filter = Some(String::new());
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::HistoryHintComplete => {
+                Ok(self.complete_hint_part(self.hinters.complete_hint()))
+            }
+            ReedlineEvent::HistoryHintWordComplete => {
+                Ok(self.complete_hint_part(self.hinters.next_hint_token()))
+            }
+            ReedlineEvent::HistoryHintLineComplete => {
+                Ok(self.complete_hint_part(self.hinters.next_hint_line()))
             }
             ReedlineEvent::Esc => {
                 self.deactivate_menus();
+                self.editor.clear_secondary_cursors();
+                self.editor.clear_selection();
                 Ok(EventStatus::Handled)
             }
             ReedlineEvent::CtrlD => {
@@ -794,12 +1723,27 @@ impl Reedline {
             }
             ReedlineEvent::Enter => {
                 for menu in self.menus.iter_mut() {
-                    if menu.is_active() {
-                        menu.replace_in_buffer(&mut self.editor);
+                    if !menu.is_active() {
+                        continue;
+                    }
+                    if let ReedlineMenu::Picker { menu, .. } = menu {
+                        let value = menu.get_selected_value();
+                        let name = menu.name().to_string();
                         menu.menu_event(MenuEvent::Deactivate);
+                        self.menu_filter = None;
 
-                        return Ok(EventStatus::Handled);
+                        return match value {
+                            Some(value) => {
+                                self.repaint(prompt)?;
+                                Ok(EventStatus::Exits(Signal::Picked { menu: name, value }))
+                            }
+                            None => Ok(EventStatus::Handled),
+                        };
                     }
+                    menu.replace_in_buffer(&mut self.editor);
+                    menu.menu_event(MenuEvent::Deactivate);
+
+                    return Ok(EventStatus::Handled);
                 }
 
                 #[cfg(feature = "bashisms")]
@@ -807,15 +1751,28 @@ impl Reedline {
                     return self.handle_editor_event(prompt, event);
                 }
 
+                if self.editor.has_abbreviations() {
+                    self.run_edit_commands(&[EditCommand::ExpandAbbreviation]);
+                }
+
                 let buffer = self.editor.get_buffer().to_string();
                 match self.validator.as_mut().map(|v| v.validate(&buffer)) {
                     None | Some(ValidationResult::Complete) => {
                         self.hide_hints = true;
                         // Additional repaint to show the content without hints etc.
-                        self.repaint(prompt)?;
+                        // behind the transient prompt, if there is one
+                        match TransientPrompt::of(prompt) {
+                            Some(transient_prompt) => self.repaint(&transient_prompt)?,
+                            None => self.repaint(prompt)?,
+                        }
                         let buf = self.editor.get_buffer();
-                        if !buf.is_empty() {
+                        // The last run command is not in the history if it was excluded
+                        self.history_last_run_id = None;
+                        if !buf.is_empty() && !self.is_excluded_from_history(buf) {
                             let mut entry = HistoryItem::from_command_line(buf);
+                            entry.cwd = std::env::current_dir()
+                                .ok()
+                                .map(|cwd| cwd.to_string_lossy().to_string());
                             // todo: in theory there's a race condition here because another shell might get the next session id at the same time
                             entry.session_id =
                                 Some(*self.history_session_id.get_or_insert_with(|| {
@@ -833,6 +1790,11 @@ impl Reedline {
                     }
                     Some(ValidationResult::Incomplete) => {
                         self.run_edit_commands(&[EditCommand::InsertNewline]);
+                        if let Some(status) =
+                            self.validator.as_ref().and_then(|v| v.status(&buffer))
+                        {
+                            self.painter.set_status(Some(status));
+                        }
 
                         Ok(EventStatus::Handled)
                     }
@@ -842,6 +1804,7 @@ impl Reedline {
                 // TODO: Decide if we need to do something special to have a nicer painter state on the next go
                 Ok(EventStatus::Exits(Signal::Success(host_command)))
             }
+            ReedlineEvent::Custom(name) => Ok(EventStatus::Exits(Signal::Custom(name))),
             ReedlineEvent::Edit(commands) => {
                 self.run_edit_commands(&commands);
                 if let Some(menu) = self.menus.iter_mut().find(|men| men.is_active()) {
@@ -850,7 +1813,12 @@ impl Reedline {
                         menu.update_values(
                             &mut self.editor,
                             self.completer.as_mut(),
-                            self.history.as_ref(),
+                            &mut HistoryCompleter::new(
+                                self.history.as_ref(),
+                                self.history_ranking,
+                                self.history_scope,
+                            )
+                            .with_regex(self.history_regex_search),
                         );
 
                         if menu.get_values().len() == 1 {
@@ -870,7 +1838,8 @@ impl Reedline {
             ReedlineEvent::OpenEditor => self.open_editor().map(|_| EventStatus::Handled),
             ReedlineEvent::Resize(width, height) => {
                 self.painter.handle_resize(width, height);
-                Ok(EventStatus::Inapplicable)
+                // The prompt is laid out again for the new width
+                Ok(EventStatus::Handled)
             }
             ReedlineEvent::Repaint => {
                 // A handled Event causes a repaint
@@ -884,6 +1853,22 @@ impl Reedline {
                 self.next_history();
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::DisplayLineUp => {
+                self.display_line_command(prompt, true);
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::DisplayLineDown => {
+                self.display_line_command(prompt, false);
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::Up if self.display_line_navigation => {
+                self.display_line_command(prompt, true);
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::Down if self.display_line_navigation => {
+                self.display_line_command(prompt, false);
+                Ok(EventStatus::Handled)
+            }
             ReedlineEvent::Up => {
                 self.up_command();
                 Ok(EventStatus::Handled)
@@ -904,6 +1889,19 @@ impl Reedline {
                 self.enter_history_search();
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::UndoBranchNext => {
+                self.editor.undo_branch_next();
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::UndoBranchPrevious => {
+                self.editor.undo_branch_previous();
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::ToggleHistoryScope => {
+                self.toggle_history_scope();
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::DeleteHistoryEntry => Ok(self.delete_history_menu_entry()),
             ReedlineEvent::Multiple(events) => {
                 let mut latest_signal = EventStatus::Inapplicable;
                 for event in events {
@@ -939,57 +1937,253 @@ impl Reedline {
                 // Exhausting the event handlers is still considered handled
                 Ok(EventStatus::Inapplicable)
             }
-            ReedlineEvent::None | ReedlineEvent::Mouse | ReedlineEvent::RecordToTill => {
-                Ok(EventStatus::Inapplicable)
+            ReedlineEvent::Mouse => self.handle_mouse_event(prompt),
+            ReedlineEvent::None | ReedlineEvent::RecordToTill => Ok(EventStatus::Inapplicable),
+        }
+    }
+
+    /// Resolves the oldest captured mouse event against the layout of the last
+    /// repaint
+    fn handle_mouse_event(&mut self, prompt: &dyn Prompt) -> Result<EventStatus> {
+        let mouse = match self.mouse_events.pop_front() {
+            Some(mouse) => mouse,
+            None => return Ok(EventStatus::Inapplicable),
+        };
+        let menu_active = self.menus.iter().any(|menu| menu.is_active());
+
+        match mouse.kind {
+            MouseEventKind::ScrollUp if menu_active => {
+                self.handle_editor_event(prompt, ReedlineEvent::MenuUp)
+            }
+            MouseEventKind::ScrollDown if menu_active => {
+                self.handle_editor_event(prompt, ReedlineEvent::MenuDown)
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let (Some(row), Some(menu)) =
+                    (self.painter.menu_row_at(mouse.row), self.active_menu())
+                {
+                    menu.menu_event(MenuEvent::Click {
+                        column: mouse.column,
+                        row,
+                    });
+                    return Ok(EventStatus::Handled);
+                }
+
+                let buffer = self.editor.get_buffer();
+                match self.painter.buffer_offset_at(
+                    buffer,
+                    &prompt.render_prompt_multiline_indicator(),
+                    gutter_width(self.line_numbers, buffer) as u16,
+                    mouse.column,
+                    mouse.row,
+                ) {
+                    Some(offset) => self.handle_editor_event(
+                        prompt,
+                        ReedlineEvent::Edit(vec![EditCommand::MoveToPosition(offset)]),
+                    ),
+                    None => Ok(EventStatus::Inapplicable),
+                }
             }
+            _ => Ok(EventStatus::Inapplicable),
         }
     }
 
+    /// The modifier of the keys accepting the labeled entries of the open menu
+    fn quick_select_modifier(&self) -> Option<KeyModifiers> {
+        self.menus
+            .iter()
+            .find(|menu| menu.is_active())
+            .and_then(|menu| menu.quick_select_modifier())
+    }
+
+    /// Whether the active menu paints an entry with `label`, so that the key
+    /// selects it instead of running its binding
+    fn shows_quick_select_label(&self, label: char) -> bool {
+        let available_lines = self.painter.menu_available_lines();
+        self.menus
+            .iter()
+            .find(|menu| menu.is_active())
+            .is_some_and(|menu| menu.shows_quick_select_label(label, available_lines))
+    }
+
     fn active_menu(&mut self) -> Option<&mut ReedlineMenu> {
         self.menus.iter_mut().find(|menu| menu.is_active())
     }
 
+    /// Types the edits into the filter of the active menu while filtering it,
+    /// returning the events the filter doesn't take. Other edits close the
+    /// filter and are returned to be run in the buffer.
+    fn filter_active_menu(&mut self, event: ReedlineEvent) -> Option<ReedlineEvent> {
+        let mut filter = match self.menu_filter.take() {
+            Some(filter) => filter,
+            None => return Some(event),
+        };
+        let menu = match self.menus.iter_mut().find(|menu| menu.is_active()) {
+            Some(menu) => menu,
+            None => return Some(event),
+        };
+
+        match event {
+            ReedlineEvent::Edit(commands) => {
+                let mut commands = commands.into_iter();
+                for command in commands.by_ref() {
+                    match command {
+                        EditCommand::InsertChar(c) => filter.push(c),
+                        EditCommand::InsertString(text) => filter.push_str(&text),
+                        EditCommand::Backspace => {
+                            filter.pop();
+                        }
+                        command => {
+                            menu.menu_event(MenuEvent::Filter(None));
+                            return Some(ReedlineEvent::Edit(
+                                std::iter::once(command).chain(commands).collect(),
+                            ));
+                        }
+                    }
+                }
+                menu.menu_event(MenuEvent::Filter(Some(filter.clone())));
+                self.menu_filter = Some(filter);
+                None
+            }
+            ReedlineEvent::Esc => {
+                menu.menu_event(MenuEvent::Filter(None));
+                None
+            }
+            event => {
+                self.menu_filter = Some(filter);
+                Some(event)
+            }
+        }
+    }
+
     fn deactivate_menus(&mut self) {
+        self.menu_filter = None;
         self.menus
             .iter_mut()
             .for_each(|menu| menu.menu_event(MenuEvent::Deactivate));
     }
 
+    /// Whether the submitted `line` is kept out of the history
+    fn is_excluded_from_history(&self, line: &str) -> bool {
+        (self.history_ignore_space && line.starts_with(' '))
+            || self
+                .history_exclusion_patterns
+                .iter()
+                .any(|pattern| glob_matches(pattern, line))
+            || matches!(&self.history_exclusion_filter, Some(filter) if filter(line))
+    }
+
+    /// Picks up the entries of other sessions sharing the history
+    fn refresh_history(&mut self) {
+        // Navigating works without them if the history can't be read right now
+        let _ignore = self.history.refresh();
+    }
+
     fn previous_history(&mut self) {
         if self.input_mode != InputMode::HistoryTraversal {
             self.input_mode = InputMode::HistoryTraversal;
+            self.refresh_history();
             self.history_cursor =
-                HistoryCursor::new(self.get_history_navigation_based_on_line_buffer());
+                HistoryCursor::new(self.get_history_navigation_based_on_line_buffer())
+                    .with_ranking(self.history_ranking)
+                    .with_scope(self.history_scope);
         }
 
         self.history_cursor
             .back(self.history.as_ref())
             .expect("todo: error handling");
         self.update_buffer_from_history();
-        self.editor.move_to_start(UndoBehavior::HistoryNavigation);
-        self.editor
-            .move_to_line_end(UndoBehavior::HistoryNavigation);
+        if let Some(prefix_len) = self.anchored_history_prefix() {
+            self.editor
+                .move_to_position(prefix_len, UndoBehavior::HistoryNavigation);
+        } else {
+            self.editor.move_to_start(UndoBehavior::HistoryNavigation);
+            self.editor
+                .move_to_line_end(UndoBehavior::HistoryNavigation);
+        }
     }
 
     fn next_history(&mut self) {
         if self.input_mode != InputMode::HistoryTraversal {
             self.input_mode = InputMode::HistoryTraversal;
+            self.refresh_history();
             self.history_cursor =
-                HistoryCursor::new(self.get_history_navigation_based_on_line_buffer());
+                HistoryCursor::new(self.get_history_navigation_based_on_line_buffer())
+                    .with_ranking(self.history_ranking)
+                    .with_scope(self.history_scope);
         }
 
         self.history_cursor
             .forward(self.history.as_ref())
             .expect("todo: error handling");
         self.update_buffer_from_history();
-        self.editor.move_to_end(UndoBehavior::HistoryNavigation);
+        if let Some(prefix_len) = self.anchored_history_prefix() {
+            self.editor
+                .move_to_position(prefix_len, UndoBehavior::HistoryNavigation);
+        } else {
+            self.editor.move_to_end(UndoBehavior::HistoryNavigation);
+        }
+    }
+
+    /// The length of the prefix the entries are cycled through with, while
+    /// navigating with [`HistoryNavigationMode::Prefix`]
+    fn anchored_history_prefix(&self) -> Option<usize> {
+        match self.history_cursor.get_navigation() {
+            HistoryNavigationQuery::PrefixSearch(prefix)
+                if self.history_navigation_mode == HistoryNavigationMode::Prefix
+                    && self.input_mode == InputMode::HistoryTraversal =>
+            {
+                Some(prefix.len())
+            }
+            _ => None,
+        }
+    }
+
+    /// Switches between all entries of the history and the entries of the
+    /// directory scope, updating the entry shown and the open menu
+    fn toggle_history_scope(&mut self) {
+        self.history_scope = match self.history_scope {
+            HistoryScope::Global => self.history_toggled_scope,
+            _ => HistoryScope::Global,
+        };
+
+        if self.input_mode == InputMode::HistoryTraversal {
+            // Start over at the most recent entry of the new scope
+            self.history_cursor = HistoryCursor::new(self.history_cursor.get_navigation())
+                .with_ranking(self.history_ranking)
+                .with_scope(self.history_scope);
+            self.history_cursor
+                .back(self.history.as_ref())
+                .expect("todo: error handling");
+            self.update_buffer_from_history();
+        }
+        for menu in self.menus.iter_mut().filter(|menu| menu.is_active()) {
+            menu.update_values(
+                &mut self.editor,
+                self.completer.as_mut(),
+                &mut HistoryCompleter::new(
+                    self.history.as_ref(),
+                    self.history_ranking,
+                    self.history_scope,
+                )
+                .with_regex(self.history_regex_search),
+            );
+        }
     }
 
     /// Enable the search and navigation through the history from the line buffer prompt
     ///
     /// Enables either prefix search with output in the line buffer or simple traversal
     fn get_history_navigation_based_on_line_buffer(&self) -> HistoryNavigationQuery {
-        if self.editor.is_empty() || !self.editor.is_cursor_at_buffer_end() {
+        let prefix = &self.editor.get_buffer()[..self.editor.insertion_point()];
+        let walk_all = match self.history_navigation_mode {
+            HistoryNavigationMode::Adaptive => {
+                self.editor.is_empty() || !self.editor.is_cursor_at_buffer_end()
+            }
+            HistoryNavigationMode::Prefix => prefix.is_empty(),
+            HistoryNavigationMode::All => true,
+        };
+        if walk_all {
             // Perform bash-style basic up/down entry walking
             HistoryNavigationQuery::Normal(
                 // Hack: Tight coupling point to be able to restore previously typed input
@@ -1001,8 +2195,9 @@ impl Reedline {
             // Current setup (code in other methods)
             // Continuing with typing will leave the search
             // but next invocation of this method will start the next search
-            let buffer = self.editor.get_buffer().to_string();
-            HistoryNavigationQuery::PrefixSearch(buffer)
+            // The text before the cursor, which is the whole buffer unless
+            // navigating with `HistoryNavigationMode::Prefix`
+            HistoryNavigationQuery::PrefixSearch(prefix.to_string())
         }
     }
 
@@ -1010,11 +2205,103 @@ impl Reedline {
     ///
     /// This mode uses a separate prompt and handles keybindings slightly differently!
     fn enter_history_search(&mut self) {
+        self.refresh_history();
         self.history_cursor =
-            HistoryCursor::new(HistoryNavigationQuery::SubstringSearch("".to_string()));
+            HistoryCursor::new(HistoryNavigationQuery::SubstringSearch("".to_string()))
+                .with_regex(self.history_regex_search);
         self.input_mode = InputMode::HistorySearch;
     }
 
+    /// Removes every entry with the command line from the history, `false` if
+    /// the history can't remove entries. Other failures are shown on the status
+    /// line, with the entries removed until then gone
+    fn delete_history_entries(&mut self, command_line: String) -> bool {
+        // The most recent entries first, removing them doesn't move the older ones
+        let deleted = self
+            .history
+            .search(SearchQuery {
+                filter: SearchFilter::from_text_search(CommandLineSearch::Exact(command_line)),
+                ..SearchQuery::everything(SearchDirection::Backward)
+            })
+            .and_then(|items| {
+                let ids = items
+                    .into_iter()
+                    .filter_map(|item| item.id)
+                    .collect::<Vec<_>>();
+                // Restored together by `History::restore_last_deleted`
+                self.history.delete_items(&ids)
+            });
+        match deleted {
+            Err(err) if err.is_unsupported_feature() => false,
+            Err(err) => {
+                self.painter
+                    .set_status(Some(format!("Failed to remove the history entry: {}", err)));
+                true
+            }
+            Ok(()) => true,
+        }
+    }
+
+    /// Removes the match of the history search and searches again
+    fn delete_history_search_entry(&mut self) -> EventStatus {
+        let command_line = match self.history_cursor.string_at_cursor() {
+            Some(command_line) => command_line,
+            None => return EventStatus::Inapplicable,
+        };
+        if !self.delete_history_entries(command_line) {
+            return EventStatus::Inapplicable;
+        }
+        self.history_cursor = HistoryCursor::new(self.history_cursor.get_navigation())
+            .with_regex(self.history_regex_search);
+        self.history_search_older();
+        EventStatus::Handled
+    }
+
+    /// Removes the value selected in the history menu and updates its values
+    fn delete_history_menu_entry(&mut self) -> EventStatus {
+        let command_line = match self.active_menu() {
+            Some(ReedlineMenu::HistoryMenu(menu)) => match menu.get_selected_value() {
+                Some(suggestion) => suggestion.value,
+                None => return EventStatus::Inapplicable,
+            },
+            _ => return EventStatus::Inapplicable,
+        };
+        if !self.delete_history_entries(command_line) {
+            return EventStatus::Inapplicable;
+        }
+        if let Some(menu) = self.menus.iter_mut().find(|menu| menu.is_active()) {
+            menu.update_values(
+                &mut self.editor,
+                self.completer.as_mut(),
+                &mut HistoryCompleter::new(
+                    self.history.as_ref(),
+                    self.history_ranking,
+                    self.history_scope,
+                )
+                .with_regex(self.history_regex_search),
+            );
+        }
+        EventStatus::Handled
+    }
+
+    fn history_search_older(&mut self) {
+        self.history_cursor
+            .back(self.history.as_ref())
+            .expect("todo: error handling");
+    }
+
+    fn history_search_newer(&mut self) {
+        self.history_cursor
+            .forward(self.history.as_ref())
+            .expect("todo: error handling");
+        // Hacky way to ensure that we don't fall of into failed search going forward
+        if self.history_cursor.string_at_cursor().is_none() {
+            self.history_cursor
+                .back(self.history.as_ref())
+                .expect("todo: error handling");
+        }
+    }
+
     /// Dispatches the applicable [`EditCommand`] actions for editing the history search string.
     ///
     /// Only modifies internal state, does not perform regular output!
@@ -1026,11 +2313,13 @@ impl Reedline {
                     if let HistoryNavigationQuery::SubstringSearch(mut substring) = navigation {
                         substring.push(*c);
                         self.history_cursor =
-                            HistoryCursor::new(HistoryNavigationQuery::SubstringSearch(substring));
+                            HistoryCursor::new(HistoryNavigationQuery::SubstringSearch(substring))
+                                .with_regex(self.history_regex_search);
                     } else {
                         self.history_cursor = HistoryCursor::new(
                             HistoryNavigationQuery::SubstringSearch(String::from(*c)),
-                        );
+                        )
+                        .with_regex(self.history_regex_search);
                     }
                     self.history_cursor
                         .back(self.history.as_mut())
@@ -1044,7 +2333,8 @@ impl Reedline {
 
                         self.history_cursor = HistoryCursor::new(
                             HistoryNavigationQuery::SubstringSearch(new_substring.to_string()),
-                        );
+                        )
+                        .with_regex(self.history_regex_search);
                         self.history_cursor
                             .back(self.history.as_mut())
                             .expect("todo: error handling");
@@ -1127,13 +2417,63 @@ impl Reedline {
         }
     }
 
+    fn display_line_command(&mut self, prompt: &dyn Prompt, up: bool) {
+        let buffer = self.editor.get_buffer();
+        let lines = PromptLines::new(prompt, self.prompt_edit_mode(), None, "", "", "");
+        let offset = self.painter.display_line_offset(
+            buffer,
+            self.editor.insertion_point(),
+            &lines,
+            &prompt.render_prompt_multiline_indicator(),
+            gutter_width(self.line_numbers, buffer) as u16,
+            up,
+        );
+
+        match offset {
+            Some(offset) => self.run_edit_commands(&[EditCommand::MoveToPosition(offset)]),
+            // There is no row of the buffer further up or down
+            None if up => self.up_command(),
+            None => self.down_command(),
+        }
+    }
+
+    /// The parts of the prompt painted around the buffer
+    fn rendered_prompt(&self, prompt: &dyn Prompt) -> String {
+        prompt.render_prompt_left().into_owned()
+            + &prompt.render_prompt_right()
+            + &prompt.render_prompt_indicator(self.prompt_edit_mode())
+    }
+
     /// Checks if hints should be displayed and are able to be completed
     fn hints_active(&self) -> bool {
         !self.hide_hints && matches!(self.input_mode, InputMode::Regular)
     }
 
+    /// Inserts the start of the hint shown after the cursor
+    fn complete_hint_part(&mut self, hint_part: String) -> EventStatus {
+        if self.hints_active()
+            && self.editor.is_cursor_at_buffer_end()
+            && !hint_part.is_empty()
+            && self.active_menu().is_none()
+        {
+            self.run_edit_commands(&[EditCommand::InsertString(hint_part)]);
+            EventStatus::Handled
+        } else {
+            EventStatus::Inapplicable
+        }
+    }
+
     /// Repaint of either the buffer or the parts for reverse history search
     fn repaint(&mut self, prompt: &dyn Prompt) -> io::Result<()> {
+        self.pending_prompt = prompt
+            .has_pending_segments()
+            .then(|| self.rendered_prompt(prompt));
+        self.last_repaint = Instant::now();
+
+        if let Some(sequence) = self.editor.take_clipboard_sequence() {
+            self.painter.write_sequence(&sequence)?;
+        }
+
         // Repainting
         if self.input_mode == InputMode::HistorySearch {
             self.history_search_paint(prompt)
@@ -1285,12 +2625,18 @@ impl Reedline {
                 };
 
             let prompt_history_search = PromptHistorySearch::new(status, substring.clone());
+            let panel_rows = self.history_search_panel_rows as usize;
+            let (panel_lines, selected) = self
+                .history_cursor
+                .window(self.history.as_ref(), panel_rows)
+                .expect("todo: error handling");
 
             let res_string = self.history_cursor.string_at_cursor().unwrap_or_default();
 
             // Highlight matches
             let res_string = if self.use_ansi_coloring {
-                let match_highlighter = SimpleMatchHighlighter::new(substring);
+                let match_highlighter = SimpleMatchHighlighter::new(substring.clone())
+                    .with_match_style(self.theme.search_match);
                 let styled = match_highlighter.highlight(&res_string, 0);
                 styled.render_simple()
             } else {
@@ -1306,8 +2652,28 @@ impl Reedline {
                 "",
             );
 
+            let panel = (panel_rows > 0).then(|| {
+                let search = substring_search(substring.clone(), self.history_regex_search);
+                let rows = panel_lines
+                    .into_iter()
+                    .map(|line| {
+                        let ranges = search
+                            .as_ref()
+                            .map_or_else(Vec::new, |search| match_ranges(search, &line));
+                        (line, ranges)
+                    })
+                    .collect();
+                let indicator = lines.prompt_indicator.to_string();
+                ReedlineMenu::EngineCompleter(Box::new(HistorySearchPanel::new(
+                    rows,
+                    selected,
+                    indicator,
+                    &self.theme,
+                )))
+            });
+
             self.painter
-                .repaint_buffer(prompt, &lines, None, self.use_ansi_coloring)?;
+                .repaint_buffer(prompt, &lines, panel.as_ref(), self.use_ansi_coloring)?;
         }
 
         Ok(())
@@ -1320,26 +2686,96 @@ impl Reedline {
         let cursor_position_in_buffer = self.editor.insertion_point();
         let buffer_to_paint = self.editor.get_buffer();
 
-        let (before_cursor, after_cursor) = self
+        let mut styled_buffer = self
             .highlighter
-            .highlight(buffer_to_paint, cursor_position_in_buffer)
-            .render_around_insertion_point(
+            .highlight(buffer_to_paint, cursor_position_in_buffer);
+        if !self.use_hyperlinks {
+            styled_buffer.hyperlinks.clear();
+        }
+        let theme = self.theme;
+        if self.showing_paste {
+            styled_buffer.restyle_range(0..buffer_to_paint.len(), |_| theme.pasted_text);
+        }
+        if let Some(selection) = self.editor.get_selection() {
+            styled_buffer.restyle_range(selection, |style| overlay(style, theme.selection));
+        }
+        for selection in self.editor.get_block_selection() {
+            styled_buffer.restyle_range(selection, |style| overlay(style, theme.selection));
+        }
+        if let (Some(bracket_style), Some((_, matching))) =
+            (theme.matching_bracket, self.editor.matching_bracket())
+        {
+            styled_buffer.restyle_range(matching..matching + 1, |_| bracket_style);
+        }
+        if let Some(prefix_len) = self.anchored_history_prefix() {
+            styled_buffer.restyle_range(0..prefix_len, |style| overlay(style, theme.search_match));
+        }
+        // Highlight the additional cursors starting at the end, so the blocks inserted for
+        // cursors at the end of a line don't move the ones still to be highlighted
+        let mut insertion_point = cursor_position_in_buffer;
+        let mut secondary_cursors = self.editor.secondary_cursors().to_vec();
+        secondary_cursors.sort_unstable_by(|a, b| b.cmp(a));
+        for cursor in secondary_cursors {
+            match buffer_to_paint[cursor..].chars().next() {
+                Some(c) if c != '\n' && c != '\r' => {
+                    styled_buffer.restyle_range(cursor..cursor + c.len_utf8(), |style| {
+                        overlay(style, theme.selection)
+                    });
+                }
+                // Nothing to highlight at the end of a line, show the cursor as a block
+                _ => {
+                    styled_buffer.insert_at(cursor, (theme.selection, " ".into()));
+                    if cursor < insertion_point {
+                        insertion_point += 1;
+                    }
+                }
+            }
+        }
+        let insertion_point = insert_line_numbers(
+            &mut styled_buffer,
+            self.line_numbers,
+            theme.line_numbers,
+            insertion_point,
+        );
+        let (before_cursor, after_cursor) = styled_buffer.render_around_insertion_point(
+            insertion_point,
+            prompt.render_prompt_multiline_indicator().borrow(),
+            self.use_ansi_coloring,
+        );
+
+        let hint: String = if self.hints_active() {
+            let hint = self.hinters.handle_with_completer(
+                buffer_to_paint,
                 cursor_position_in_buffer,
-                prompt.render_prompt_multiline_indicator().borrow(),
+                self.history.as_ref(),
+                self.completer.as_mut(),
                 self.use_ansi_coloring,
             );
+            ghost_lines(
+                &hint,
+                &prompt.render_prompt_multiline_indicator(),
+                self.use_ansi_coloring,
+            )
+        } else {
+            String::new()
+        };
+        self.pending_hint = self.hints_active() && self.hinters.has_pending_hint();
 
-        let hint: String = if self.hints_active() {
-            self.hinter.as_mut().map_or_else(String::new, |hinter| {
-                hinter.handle(
-                    buffer_to_paint,
-                    cursor_position_in_buffer,
-                    self.history.as_ref(),
+        let continuations = if self.keybinding_hints {
+            self.edit_mode.pending_continuations()
+        } else {
+            vec![]
+        };
+        self.showing_keybinding_hints = !continuations.is_empty();
+        let hint = if continuations.is_empty() {
+            hint
+        } else {
+            hint + "\n"
+                + &keybinding_hints_string(
+                    &continuations,
+                    self.painter.screen_width(),
                     self.use_ansi_coloring,
                 )
-            })
-        } else {
-            String::new()
         };
 
         // Needs to add return carriage to newlines because when not in raw mode
@@ -1360,21 +2796,123 @@ impl Reedline {
                 menu.update_working_details(
                     &mut self.editor,
                     self.completer.as_mut(),
-                    self.history.as_ref(),
+                    &mut HistoryCompleter::new(
+                        self.history.as_ref(),
+                        self.history_ranking,
+                        self.history_scope,
+                    )
+                    .with_regex(self.history_regex_search),
                     &self.painter,
                 );
             }
         }
 
         let menu = self.menus.iter().find(|menu| menu.is_active());
+        self.pending_completions =
+            matches!(menu, Some(menu) if menu.has_pending_completions(self.completer.as_ref()));
 
         self.painter
             .repaint_buffer(prompt, &lines, menu, self.use_ansi_coloring)
     }
 }
 
+/// Whether the keys of a batch read in one go hold pasted lines: several keys
+/// with an Enter among them, or any Enter in the batch read right after a paste
+fn holds_pasted_lines(events: &[Event], continues_paste: bool) -> bool {
+    let keys = events
+        .iter()
+        .filter(|event| matches!(event, Event::Key(_)))
+        .count();
+    let has_enter = events.iter().any(|event| {
+        matches!(
+            event,
+            Event::Key(KeyEvent {
+                code: KeyCode::Enter,
+                modifiers: KeyModifiers::NONE,
+            })
+        )
+    });
+    has_enter && (continues_paste || keys > 1)
+}
+
 #[test]
 fn thread_safe() {
     fn f<S: Send>(_: S) {}
     f(Reedline::create());
 }
+
+#[test]
+fn prefix_navigation_searches_the_text_before_the_cursor() {
+    let mut reedline =
+        Reedline::create().with_history_navigation_mode(HistoryNavigationMode::Prefix);
+    reedline
+        .editor
+        .set_buffer("git status".to_string(), UndoBehavior::CreateUndoPoint);
+    reedline
+        .editor
+        .move_to_position(3, UndoBehavior::CreateUndoPoint);
+
+    assert_eq!(
+        reedline.get_history_navigation_based_on_line_buffer(),
+        HistoryNavigationQuery::PrefixSearch("git".to_string())
+    );
+
+    reedline.editor.move_to_start(UndoBehavior::CreateUndoPoint);
+    assert!(matches!(
+        reedline.get_history_navigation_based_on_line_buffer(),
+        HistoryNavigationQuery::Normal(_)
+    ));
+}
+
+#[test]
+fn open_picker_activates_only_pickers() {
+    let mut reedline = Reedline::create()
+        .with_menu(ReedlineMenu::EngineCompleter(Box::new(
+            crate::ColumnarMenu::default().with_name("completion_menu"),
+        )))
+        .with_menu(ReedlineMenu::Picker {
+            menu: Box::new(crate::ListMenu::default().with_name("sessions")),
+            items: Vec::new(),
+        });
+
+    assert!(!reedline.open_picker("completion_menu", Vec::new()));
+    assert!(reedline.active_menu().is_none());
+
+    let items = vec![Suggestion {
+        value: "work".to_string(),
+        ..Suggestion::default()
+    }];
+    assert!(reedline.open_picker("sessions", items));
+    assert!(matches!(
+        reedline.active_menu(),
+        Some(ReedlineMenu::Picker { items, .. }) if items.len() == 1
+    ));
+}
+
+#[test]
+fn batched_keys_with_an_enter_are_pasted_lines() {
+    let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+    let typed = |text: &str| {
+        text.chars()
+            .map(|c| match c {
+                '\n' => key(KeyCode::Enter),
+                c => key(KeyCode::Char(c)),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert!(holds_pasted_lines(&typed("ls\npwd"), false));
+    // a one-line paste, or the first batch of a longer one
+    assert!(holds_pasted_lines(&typed("ls\n"), false));
+    // the rest of the paste in the next batch
+    assert!(holds_pasted_lines(&typed("\n"), true));
+    assert!(holds_pasted_lines(&typed("pwd\n"), true));
+
+    assert!(!holds_pasted_lines(&typed("\n"), false));
+    assert!(!holds_pasted_lines(&typed("ls"), false));
+    assert!(!holds_pasted_lines(&typed("ls"), true));
+    assert!(!holds_pasted_lines(
+        &[Event::Resize(80, 24), key(KeyCode::Enter)],
+        false
+    ));
+}
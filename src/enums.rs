@@ -1,9 +1,16 @@
+use crate::{Suggestion, TextObject};
 use serde::{Deserialize, Serialize};
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
 use strum_macros::EnumIter;
 
 /// Valid ways how `Reedline::read_line()` can return
+///
+/// New ways to return may be added, match them with a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Signal {
     /// Entry succeeded with the provided content
     Success(String),
@@ -11,6 +18,19 @@ pub enum Signal {
     CtrlC, // Interrupt current editing
     /// Abort with `Ctrl+D` signalling `EOF` or abort of a whole interactive session
     CtrlD, // End terminal session
+    /// A [`ReedlineEvent::Custom`] event with the given name was triggered. The
+    /// buffer is not submitted and still there on the next call of
+    /// [`Reedline::read_line()`](crate::Reedline::read_line)
+    Custom(String),
+    /// An item was picked in the [`ReedlineMenu::Picker`](crate::ReedlineMenu::Picker)
+    /// with the given name. The buffer is not submitted and still there on
+    /// the next call of [`Reedline::read_line()`](crate::Reedline::read_line)
+    Picked {
+        /// Name of the picker
+        menu: String,
+        /// The picked item
+        value: Suggestion,
+    },
 }
 
 /// Editing actions which can be mapped to key bindings.
@@ -36,6 +56,12 @@ pub enum EditCommand {
     /// Move one character to the right
     MoveRight,
 
+    /// Move one line up, staying on the first line instead of browsing the history
+    MoveUp,
+
+    /// Move one line down, staying on the last line instead of browsing the history
+    MoveDown,
+
     /// Move one word to the left
     MoveWordLeft,
 
@@ -54,6 +80,13 @@ pub enum EditCommand {
     /// Move one word to the right, stop at end of word
     MoveWordRightEnd,
 
+    /// Move one subword to the left, subwords are split at camelCase humps and
+    /// non-alphanumeric characters like `_`
+    MoveSubwordLeft,
+
+    /// Move one subword to the right, stop at end of subword
+    MoveSubwordRight,
+
     /// Move one WORD to the right, stop at end of WORD
     MoveBigWordRightEnd,
 
@@ -102,6 +135,23 @@ pub enum EditCommand {
     /// Cut the current line
     CutCurrentLine,
 
+    /// Swap the current line, or all lines of the selection, with the line above
+    MoveLineUp,
+
+    /// Swap the current line, or all lines of the selection, with the line below
+    MoveLineDown,
+
+    /// Insert a copy of the current line, or all lines of the selection, below them
+    DuplicateLine,
+
+    /// Join the current line with the next one, or all lines of the selection,
+    /// separated by a single space (vi `J`)
+    JoinLines,
+
+    /// Delete the current line, or all lines of the selection, without storing
+    /// them in the cut buffer
+    DeleteLine,
+
     /// Cut from the start of the buffer to the insertion point
     CutFromStart,
 
@@ -120,6 +170,12 @@ pub enum EditCommand {
     /// Cut the WORD left of the insertion point
     CutBigWordLeft,
 
+    /// Cut the subword left of the insertion point
+    CutSubwordLeft,
+
+    /// Cut the subword right of the insertion point
+    CutSubwordRight,
+
     /// Cut the word right of the insertion point
     CutWordRight,
 
@@ -132,18 +188,139 @@ pub enum EditCommand {
     /// Cut the WORD right of the insertion point and any following space
     CutBigWordRightToNext,
 
+    /// Copy the current line
+    CopyCurrentLine,
+
+    /// Copy from the start of the buffer to the insertion point
+    CopyFromStart,
+
+    /// Copy from the start of the current line to the insertion point
+    CopyFromLineStart,
+
+    /// Copy from the insertion point to the end of the buffer
+    CopyToEnd,
+
+    /// Copy from the insertion point to the end of the current line
+    CopyToLineEnd,
+
+    /// Copy the word left of the insertion point
+    CopyWordLeft,
+
+    /// Copy the WORD left of the insertion point
+    CopyBigWordLeft,
+
+    /// Copy the word right of the insertion point
+    CopyWordRight,
+
+    /// Copy the WORD right of the insertion point
+    CopyBigWordRight,
+
+    /// Copy the word right of the insertion point and any following space
+    CopyWordRightToNext,
+
+    /// Copy the WORD right of the insertion point and any following space
+    CopyBigWordRightToNext,
+
+    /// Select the register used by the following cuts, copies and pastes (vi `"`)
+    ///
+    /// `a` to `z` are the named registers, their uppercase variant appends to
+    /// them. `0` holds the last copy and `1` to `9` the last cuts. `"` selects
+    /// the default register again.
+    SelectRegister(char),
+
     /// Paste the cut buffer in front of the insertion point (Emacs, vi `P`)
     PasteCutBufferBefore,
 
     /// Paste the cut buffer in front of the insertion point (vi `p`)
     PasteCutBufferAfter,
 
-    /// Upper case the current word
+    /// Add a cursor at the next occurrence of the word at the insertion point.
+    /// Insertions, deletions and simple cursor movement are then applied at every
+    /// cursor, any other command goes back to a single cursor
+    AddCursorAtNextOccurrence,
+
+    /// Cut the text object around the insertion point
+    CutTextObject(TextObject),
+
+    /// Copy the text object around the insertion point
+    CopyTextObject(TextObject),
+
+    /// Select the text object around the insertion point
+    SelectTextObject(TextObject),
+
+    /// Surround the selected text with the given delimiter, either side of a
+    /// bracket pair can be given
+    SurroundSelection(char),
+
+    /// Replace the innermost pair of the first delimiter around the insertion
+    /// point with the second one
+    ChangeSurrounding(char, char),
+
+    /// Remove the innermost pair of the given delimiter around the insertion point
+    DeleteSurrounding(char),
+
+    /// Set the mark with the given name at the insertion point. Marks keep
+    /// pointing at the same text when the buffer is edited
+    SetMark(char),
+
+    /// Move the insertion point to the mark with the given name
+    JumpToMark(char),
+
+    /// Move the insertion point to the start of the line of the mark with the given name
+    JumpToMarkLine(char),
+
+    /// Move the insertion point to the bracket matching the one under the insertion point,
+    /// or the one just before it
+    MoveToMatchingBracket,
+
+    /// Expand the abbreviation left of the insertion point
+    ExpandAbbreviation,
+
+    /// Start a selection at the insertion point. Moving the cursor extends it,
+    /// any edit ends it
+    StartSelection,
+
+    /// Start a block selection at the insertion point. It covers the columns
+    /// between the anchor and the insertion point on all lines in between
+    StartBlockSelection,
+
+    /// Select the whole buffer
+    SelectAll,
+
+    /// Cut the selected text
+    CutSelection,
+
+    /// Copy the selected text and end the selection
+    CopySelection,
+
+    /// Copy the selected text, end the selection and move the cursor to the
+    /// start of the copied text like the yank of vi
+    CopySelectionAndMoveToStart,
+
+    /// Paste the clipboard at the insertion point, replacing the selected text
+    Paste,
+
+    /// End a block selection with a cursor at its left column on every selected
+    /// line, to insert the same text on all of them (vi `I` in visual block mode)
+    AddCursorsAtBlockStart,
+
+    /// Replace the text inserted by the previous paste with the kill before it
+    /// in the kill ring (Emacs `M-y`). Does nothing if not preceded by a paste
+    YankPop,
+
+    /// Upper case the current word, or the selection if there is one
     UppercaseWord,
 
-    /// Lower case the current word
+    /// Lower case the current word, or the selection if there is one
     LowercaseWord,
 
+    /// Capitalize the current word from the insertion point on, or every word
+    /// of the selection if there is one
+    CapitalizeWord,
+
+    /// Switch the case of the selection, or the current character if nothing is selected
+    ToggleCase,
+
     /// Capitalize the current character
     CapitalizeChar,
 
@@ -153,6 +330,12 @@ pub enum EditCommand {
     /// Swap the current word with the word to the right
     SwapWords,
 
+    /// Swap the word before the insertion point with the word after it (Emacs `M-t`)
+    TransposeWords,
+
+    /// Swap the current line with the line above it (Emacs `C-x C-t`)
+    TransposeLines,
+
     /// Swap the current grapheme/character with the one to the right
     SwapGraphemes,
 
@@ -162,6 +345,16 @@ pub enum EditCommand {
     /// Redo an edit command from the undo history
     Redo,
 
+    /// Go back to the state of the buffer from the given duration before the
+    /// current one, following the time of the edits instead of the undo order
+    /// (vi `g-`). A zero duration goes to the previous state in time
+    UndoEarlier(Duration),
+
+    /// Go forward to the state of the buffer from the given duration after the
+    /// current one, following the time of the edits instead of the undo order
+    /// (vi `g+`). A zero duration goes to the next state in time
+    RedoLater(Duration),
+
     /// CutUntil right until char
     CutRightUntil(char),
 
@@ -185,6 +378,18 @@ pub enum EditCommand {
 
     /// CutUntil left before char
     MoveLeftBefore(char),
+
+    /// CopyUntil right until char
+    CopyRightUntil(char),
+
+    /// CopyUntil right before char
+    CopyRightBefore(char),
+
+    /// CopyUntil left until char
+    CopyLeftUntil(char),
+
+    /// CopyUntil left before char
+    CopyLeftBefore(char),
 }
 
 impl Display for EditCommand {
@@ -196,7 +401,11 @@ impl Display for EditCommand {
             EditCommand::MoveToLineEnd => write!(f, "MoveToLineEnd"),
             EditCommand::MoveLeft => write!(f, "MoveLeft"),
             EditCommand::MoveRight => write!(f, "MoveRight"),
+            EditCommand::MoveUp => write!(f, "MoveUp"),
+            EditCommand::MoveDown => write!(f, "MoveDown"),
             EditCommand::MoveWordLeft => write!(f, "MoveWordLeft"),
+            EditCommand::MoveSubwordLeft => write!(f, "MoveSubwordLeft"),
+            EditCommand::MoveSubwordRight => write!(f, "MoveSubwordRight"),
             EditCommand::MoveBigWordLeft => write!(f, "MoveBigWordLeft"),
             EditCommand::MoveWordRight => write!(f, "MoveWordRight"),
             EditCommand::MoveWordRightEnd => write!(f, "MoveWordRightEnd"),
@@ -217,26 +426,76 @@ impl Display for EditCommand {
             EditCommand::Clear => write!(f, "Clear"),
             EditCommand::ClearToLineEnd => write!(f, "ClearToLineEnd"),
             EditCommand::CutCurrentLine => write!(f, "CutCurrentLine"),
+            EditCommand::MoveLineUp => write!(f, "MoveLineUp"),
+            EditCommand::MoveLineDown => write!(f, "MoveLineDown"),
+            EditCommand::DuplicateLine => write!(f, "DuplicateLine"),
+            EditCommand::JoinLines => write!(f, "JoinLines"),
+            EditCommand::DeleteLine => write!(f, "DeleteLine"),
             EditCommand::CutFromStart => write!(f, "CutFromStart"),
             EditCommand::CutFromLineStart => write!(f, "CutFromLineStart"),
             EditCommand::CutToEnd => write!(f, "CutToEnd"),
             EditCommand::CutToLineEnd => write!(f, "CutToLineEnd"),
             EditCommand::CutWordLeft => write!(f, "CutWordLeft"),
+            EditCommand::CutSubwordLeft => write!(f, "CutSubwordLeft"),
+            EditCommand::CutSubwordRight => write!(f, "CutSubwordRight"),
             EditCommand::CutBigWordLeft => write!(f, "CutBigWordLeft"),
             EditCommand::CutWordRight => write!(f, "CutWordRight"),
             EditCommand::CutBigWordRight => write!(f, "CutBigWordRight"),
             EditCommand::CutWordRightToNext => write!(f, "CutWordRightToNext"),
             EditCommand::CutBigWordRightToNext => write!(f, "CutBigWordRightToNext"),
+            EditCommand::CopyCurrentLine => write!(f, "CopyCurrentLine"),
+            EditCommand::CopyFromStart => write!(f, "CopyFromStart"),
+            EditCommand::CopyFromLineStart => write!(f, "CopyFromLineStart"),
+            EditCommand::CopyToEnd => write!(f, "CopyToEnd"),
+            EditCommand::CopyToLineEnd => write!(f, "CopyToLineEnd"),
+            EditCommand::CopyWordLeft => write!(f, "CopyWordLeft"),
+            EditCommand::CopyBigWordLeft => write!(f, "CopyBigWordLeft"),
+            EditCommand::CopyWordRight => write!(f, "CopyWordRight"),
+            EditCommand::CopyBigWordRight => write!(f, "CopyBigWordRight"),
+            EditCommand::CopyWordRightToNext => write!(f, "CopyWordRightToNext"),
+            EditCommand::CopyBigWordRightToNext => write!(f, "CopyBigWordRightToNext"),
+            EditCommand::SelectRegister(_) => write!(f, "SelectRegister Value: <char>"),
             EditCommand::PasteCutBufferBefore => write!(f, "PasteCutBufferBefore"),
             EditCommand::PasteCutBufferAfter => write!(f, "PasteCutBufferAfter"),
+            EditCommand::AddCursorAtNextOccurrence => write!(f, "AddCursorAtNextOccurrence"),
+            EditCommand::CutTextObject(_) => write!(f, "CutTextObject Value: <TextObject>"),
+            EditCommand::CopyTextObject(_) => write!(f, "CopyTextObject Value: <TextObject>"),
+            EditCommand::SelectTextObject(_) => {
+                write!(f, "SelectTextObject Value: <TextObject>")
+            }
+            EditCommand::SurroundSelection(_) => write!(f, "SurroundSelection Value: <char>"),
+            EditCommand::ChangeSurrounding(_, _) => {
+                write!(f, "ChangeSurrounding Value: <char> <char>")
+            }
+            EditCommand::DeleteSurrounding(_) => write!(f, "DeleteSurrounding Value: <char>"),
+            EditCommand::SetMark(_) => write!(f, "SetMark Value: <char>"),
+            EditCommand::JumpToMark(_) => write!(f, "JumpToMark Value: <char>"),
+            EditCommand::JumpToMarkLine(_) => write!(f, "JumpToMarkLine Value: <char>"),
+            EditCommand::MoveToMatchingBracket => write!(f, "MoveToMatchingBracket"),
+            EditCommand::ExpandAbbreviation => write!(f, "ExpandAbbreviation"),
+            EditCommand::StartSelection => write!(f, "StartSelection"),
+            EditCommand::StartBlockSelection => write!(f, "StartBlockSelection"),
+            EditCommand::SelectAll => write!(f, "SelectAll"),
+            EditCommand::CutSelection => write!(f, "CutSelection"),
+            EditCommand::CopySelection => write!(f, "CopySelection"),
+            EditCommand::CopySelectionAndMoveToStart => write!(f, "CopySelectionAndMoveToStart"),
+            EditCommand::Paste => write!(f, "Paste"),
+            EditCommand::AddCursorsAtBlockStart => write!(f, "AddCursorsAtBlockStart"),
+            EditCommand::YankPop => write!(f, "YankPop"),
             EditCommand::UppercaseWord => write!(f, "UppercaseWord"),
             EditCommand::LowercaseWord => write!(f, "LowercaseWord"),
+            EditCommand::CapitalizeWord => write!(f, "CapitalizeWord"),
+            EditCommand::ToggleCase => write!(f, "ToggleCase"),
             EditCommand::SwitchcaseChar => write!(f, "SwitchcaseChar"),
             EditCommand::CapitalizeChar => write!(f, "CapitalizeChar"),
             EditCommand::SwapWords => write!(f, "SwapWords"),
+            EditCommand::TransposeWords => write!(f, "TransposeWords"),
+            EditCommand::TransposeLines => write!(f, "TransposeLines"),
             EditCommand::SwapGraphemes => write!(f, "SwapGraphemes"),
             EditCommand::Undo => write!(f, "Undo"),
             EditCommand::Redo => write!(f, "Redo"),
+            EditCommand::UndoEarlier(_) => write!(f, "UndoEarlier Value: <duration>"),
+            EditCommand::RedoLater(_) => write!(f, "RedoLater Value: <duration>"),
             EditCommand::CutRightUntil(_) => write!(f, "CutRightUntil Value: <char>"),
             EditCommand::CutRightBefore(_) => write!(f, "CutRightBefore Value: <char>"),
             EditCommand::MoveRightUntil(_) => write!(f, "MoveRightUntil Value: <char>"),
@@ -245,6 +504,10 @@ impl Display for EditCommand {
             EditCommand::CutLeftBefore(_) => write!(f, "CutLeftBefore Value: <char>"),
             EditCommand::MoveLeftUntil(_) => write!(f, "MoveLeftUntil Value: <char>"),
             EditCommand::MoveLeftBefore(_) => write!(f, "MoveLeftBefore Value: <char>"),
+            EditCommand::CopyRightUntil(_) => write!(f, "CopyRightUntil Value: <char>"),
+            EditCommand::CopyRightBefore(_) => write!(f, "CopyRightBefore Value: <char>"),
+            EditCommand::CopyLeftUntil(_) => write!(f, "CopyLeftUntil Value: <char>"),
+            EditCommand::CopyLeftBefore(_) => write!(f, "CopyLeftBefore Value: <char>"),
         }
     }
 }
@@ -261,8 +524,12 @@ impl EditCommand {
             | EditCommand::MoveToLineEnd
             | EditCommand::MoveToPosition(_)
             | EditCommand::MoveLeft
+            | EditCommand::MoveUp
+            | EditCommand::MoveDown
             | EditCommand::MoveRight
             | EditCommand::MoveWordLeft
+            | EditCommand::MoveSubwordLeft
+            | EditCommand::MoveSubwordRight
             | EditCommand::MoveBigWordLeft
             | EditCommand::MoveWordRight
             | EditCommand::MoveWordRightStart
@@ -272,10 +539,18 @@ impl EditCommand {
             | EditCommand::MoveRightUntil(_)
             | EditCommand::MoveRightBefore(_)
             | EditCommand::MoveLeftUntil(_)
-            | EditCommand::MoveLeftBefore(_) => EditType::MoveCursor,
+            | EditCommand::MoveLeftBefore(_)
+            | EditCommand::SelectAll
+            | EditCommand::AddCursorAtNextOccurrence
+            | EditCommand::AddCursorsAtBlockStart
+            | EditCommand::JumpToMark(_)
+            | EditCommand::JumpToMarkLine(_)
+            | EditCommand::MoveToMatchingBracket
+            | EditCommand::SelectTextObject(_) => EditType::MoveCursor,
 
             // Text edits
             EditCommand::InsertChar(_)
+            | EditCommand::ExpandAbbreviation
             | EditCommand::Backspace
             | EditCommand::Delete
             | EditCommand::CutChar
@@ -288,11 +563,18 @@ impl EditCommand {
             | EditCommand::Clear
             | EditCommand::ClearToLineEnd
             | EditCommand::CutCurrentLine
+            | EditCommand::MoveLineUp
+            | EditCommand::MoveLineDown
+            | EditCommand::DuplicateLine
+            | EditCommand::JoinLines
+            | EditCommand::DeleteLine
             | EditCommand::CutFromStart
             | EditCommand::CutFromLineStart
             | EditCommand::CutToLineEnd
             | EditCommand::CutToEnd
             | EditCommand::CutWordLeft
+            | EditCommand::CutSubwordLeft
+            | EditCommand::CutSubwordRight
             | EditCommand::CutBigWordLeft
             | EditCommand::CutWordRight
             | EditCommand::CutBigWordRight
@@ -300,18 +582,56 @@ impl EditCommand {
             | EditCommand::CutBigWordRightToNext
             | EditCommand::PasteCutBufferBefore
             | EditCommand::PasteCutBufferAfter
+            | EditCommand::YankPop
+            | EditCommand::CutSelection
+            | EditCommand::CutTextObject(_)
+            | EditCommand::SurroundSelection(_)
+            | EditCommand::ChangeSurrounding(_, _)
+            | EditCommand::DeleteSurrounding(_)
+            | EditCommand::Paste
             | EditCommand::UppercaseWord
             | EditCommand::LowercaseWord
+            | EditCommand::CapitalizeWord
+            | EditCommand::ToggleCase
             | EditCommand::SwitchcaseChar
             | EditCommand::CapitalizeChar
             | EditCommand::SwapWords
+            | EditCommand::TransposeWords
+            | EditCommand::TransposeLines
             | EditCommand::SwapGraphemes
             | EditCommand::CutRightUntil(_)
             | EditCommand::CutRightBefore(_)
             | EditCommand::CutLeftUntil(_)
             | EditCommand::CutLeftBefore(_) => EditType::EditText,
 
-            EditCommand::Undo | EditCommand::Redo => EditType::UndoRedo,
+            EditCommand::Undo
+            | EditCommand::Redo
+            | EditCommand::UndoEarlier(_)
+            | EditCommand::RedoLater(_) => EditType::UndoRedo,
+
+            // Neither the buffer nor the cursor change
+            EditCommand::CopyCurrentLine
+            | EditCommand::CopyFromStart
+            | EditCommand::CopyFromLineStart
+            | EditCommand::CopyToEnd
+            | EditCommand::CopyToLineEnd
+            | EditCommand::CopyWordLeft
+            | EditCommand::CopyBigWordLeft
+            | EditCommand::CopyWordRight
+            | EditCommand::CopyBigWordRight
+            | EditCommand::CopyWordRightToNext
+            | EditCommand::CopyBigWordRightToNext
+            | EditCommand::CopyRightUntil(_)
+            | EditCommand::CopyRightBefore(_)
+            | EditCommand::CopyLeftUntil(_)
+            | EditCommand::CopyLeftBefore(_)
+            | EditCommand::SelectRegister(_)
+            | EditCommand::CopyTextObject(_)
+            | EditCommand::SetMark(_)
+            | EditCommand::StartSelection
+            | EditCommand::StartBlockSelection
+            | EditCommand::CopySelection
+            | EditCommand::CopySelectionAndMoveToStart => EditType::NoOp,
         }
     }
 }
@@ -326,6 +646,8 @@ pub enum EditType {
     UndoRedo,
     /// Text editing commands
     EditText,
+    /// Commands that change neither the buffer nor the cursor, e.g. copying
+    NoOp,
 }
 
 /// Every line change should come with an UndoBehavior tag, which can be used to
@@ -379,6 +701,40 @@ impl UndoBehavior {
             (_, _) => true,
         }
     }
+
+    /// Like [`UndoBehavior::create_undo_point_after`] but lets the given
+    /// [`UndoCoalescing`] policy decide how consecutive edits are grouped
+    pub fn create_undo_point_with(&self, previous: &UndoBehavior, policy: UndoCoalescing) -> bool {
+        use UndoBehavior as UB;
+        match (policy, previous, self) {
+            (_, _, UB::MoveCursor) => false,
+            (UndoCoalescing::Word, _, _) => self.create_undo_point_after(previous),
+            (UndoCoalescing::Never, _, _) => true,
+            (UndoCoalescing::Run, UB::InsertCharacter(c_prev), UB::InsertCharacter(_)) => {
+                *c_prev == '\n' || *c_prev == '\r'
+            }
+            (UndoCoalescing::Run, UB::Backspace(_), UB::Backspace(Some(c_new)))
+            | (UndoCoalescing::Run, UB::Delete(_), UB::Delete(Some(c_new))) => {
+                *c_new == '\n' || *c_new == '\r'
+            }
+            (UndoCoalescing::Run, _, _) => self.create_undo_point_after(previous),
+        }
+    }
+}
+
+/// Policy used by the [`crate::Editor`] to decide which consecutive edits are
+/// collapsed into a single undo point
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UndoCoalescing {
+    /// Every single edit forms its own undo point
+    Never,
+    /// Consecutive insertions or deletions are grouped per word, including the
+    /// whitespace that follows it (default)
+    #[default]
+    Word,
+    /// Consecutive insertions or deletions are grouped until the cursor is
+    /// moved, a different kind of edit is performed or a line break is typed
+    Run,
 }
 
 /// Reedline supported actions.
@@ -393,6 +749,10 @@ pub enum ReedlineEvent {
     /// Complete a single token/word of the history hint
     HistoryHintWordComplete,
 
+    /// Complete the history hint up to and including its next line break,
+    /// accepting a multi-line hint one line at a time
+    HistoryHintLineComplete,
+
     /// Handle EndOfLine event
     ///
     /// Expected Behavior:
@@ -425,8 +785,9 @@ pub enum ReedlineEvent {
     /// Esc event
     Esc,
 
-    /// Mouse
-    Mouse, // Fill in details later
+    /// Mouse event, resolved by the engine when
+    /// [`crate::Reedline::with_mouse_support`] captures the mouse
+    Mouse,
 
     /// trigger termimal resize
     Resize(u16, u16),
@@ -446,6 +807,14 @@ pub enum ReedlineEvent {
     /// Move down to the next line, if multiline, or down through the historic buffers
     Down,
 
+    /// Move up to the previous row of the screen, within a wrapped line, or up
+    /// into the historic buffers from the first row
+    DisplayLineUp,
+
+    /// Move down to the next row of the screen, within a wrapped line, or down
+    /// through the historic buffers from the last row
+    DisplayLineDown,
+
     /// Move right to the next column, completion entry, or complete hint
     Right,
 
@@ -458,6 +827,15 @@ pub enum ReedlineEvent {
     /// Search the history for a string
     SearchHistory,
 
+    /// Switch the history navigation and the history menu between all entries
+    /// and the entries run in the current directory, see
+    /// [`crate::Reedline::with_history_scope`]
+    ToggleHistoryScope,
+
+    /// Remove the command line selected in the history menu or found by the
+    /// history search from the history, see [`crate::History::delete`]
+    DeleteHistoryEntry,
+
     /// In vi mode multiple reedline events can be chained while parsing the
     /// command or movement characters
     Multiple(Vec<ReedlineEvent>),
@@ -492,14 +870,70 @@ pub enum ReedlineEvent {
     /// Move to the previous history page
     MenuPagePrevious,
 
+    /// Scrolls down the documentation of the selected menu entry
+    MenuDocumentationDown,
+
+    /// Scrolls up the documentation of the selected menu entry
+    MenuDocumentationUp,
+
+    /// Adds the selected menu entry to the entries inserted together when
+    /// accepting, or removes it again, see [`crate::ColumnarMenu::with_multi_select`]
+    MenuToggleMultiSelect,
+
+    /// Accepts the entry of the open menu painted with the label, see
+    /// [`crate::ColumnarMenu::with_quick_select`]
+    MenuQuickSelect(char),
+
+    /// Starts filtering the entries of the open menu: the characters typed
+    /// afterwards narrow the entries shown instead of editing the buffer, and
+    /// `Esc` shows all of them again
+    MenuFilter,
+
     /// Way to bind the execution of a whole command (directly returning from [`crate::Reedline::read_line()`]) to a keybinding
     ExecuteHostCommand(String),
 
+    /// Return from [`crate::Reedline::read_line()`] with [`Signal::Custom`] to let
+    /// the host run an action of its own, like toggling a pane. Unlike
+    /// [`ReedlineEvent::ExecuteHostCommand`] the buffer is kept for the next
+    /// call of `read_line`, see [`crate::Reedline::current_buffer_contents()`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use crossterm::event::{KeyCode, KeyModifiers};
+    /// use reedline::{
+    ///     default_emacs_keybindings, DefaultPrompt, Emacs, Reedline, ReedlineEvent, Signal,
+    /// };
+    ///
+    /// let mut keybindings = default_emacs_keybindings();
+    /// keybindings.add_binding(
+    ///     KeyModifiers::ALT,
+    ///     KeyCode::Char('p'),
+    ///     ReedlineEvent::Custom("toggle_pane".into()),
+    /// );
+    /// let mut line_editor = Reedline::create().with_edit_mode(Box::new(Emacs::new(keybindings)));
+    ///
+    /// loop {
+    ///     match line_editor.read_line(&DefaultPrompt::default()) {
+    ///         Ok(Signal::Custom(name)) if name == "toggle_pane" => {
+    ///             // Toggle the pane and keep editing the same buffer
+    ///         }
+    ///         _ => break,
+    ///     }
+    /// }
+    /// ```
+    Custom(String),
+
     /// Open text editor
     OpenEditor,
 
     /// Record vi to or till motion
     RecordToTill,
+
+    /// Switch to the next alternative branch of the undo tree
+    UndoBranchNext,
+
+    /// Switch to the previous alternative branch of the undo tree
+    UndoBranchPrevious,
 }
 
 impl Display for ReedlineEvent {
@@ -508,6 +942,7 @@ impl Display for ReedlineEvent {
             ReedlineEvent::None => write!(f, "None"),
             ReedlineEvent::HistoryHintComplete => write!(f, "HistoryHintComplete"),
             ReedlineEvent::HistoryHintWordComplete => write!(f, "HistoryHintWordComplete"),
+            ReedlineEvent::HistoryHintLineComplete => write!(f, "HistoryHintLineComplete"),
             ReedlineEvent::CtrlD => write!(f, "CtrlD"),
             ReedlineEvent::CtrlC => write!(f, "CtrlC"),
             ReedlineEvent::ClearScreen => write!(f, "ClearScreen"),
@@ -524,10 +959,14 @@ impl Display for ReedlineEvent {
             ReedlineEvent::PreviousHistory => write!(f, "PreviousHistory"),
             ReedlineEvent::Up => write!(f, "Up"),
             ReedlineEvent::Down => write!(f, "Down"),
+            ReedlineEvent::DisplayLineUp => write!(f, "DisplayLineUp"),
+            ReedlineEvent::DisplayLineDown => write!(f, "DisplayLineDown"),
             ReedlineEvent::Right => write!(f, "Right"),
             ReedlineEvent::Left => write!(f, "Left"),
             ReedlineEvent::NextHistory => write!(f, "NextHistory"),
             ReedlineEvent::SearchHistory => write!(f, "SearchHistory"),
+            ReedlineEvent::ToggleHistoryScope => write!(f, "ToggleHistoryScope"),
+            ReedlineEvent::DeleteHistoryEntry => write!(f, "DeleteHistoryEntry"),
             ReedlineEvent::Multiple(_) => write!(f, "Multiple[ {{ ReedLineEvents, }} ]"),
             ReedlineEvent::UntilFound(_) => write!(f, "UntilFound [ {{ ReedLineEvents, }} ]"),
             ReedlineEvent::Menu(_) => write!(f, "Menu Name: <string>"),
@@ -539,9 +978,17 @@ impl Display for ReedlineEvent {
             ReedlineEvent::MenuRight => write!(f, "MenuRight"),
             ReedlineEvent::MenuPageNext => write!(f, "MenuPageNext"),
             ReedlineEvent::MenuPagePrevious => write!(f, "MenuPagePrevious"),
+            ReedlineEvent::MenuDocumentationDown => write!(f, "MenuDocumentationDown"),
+            ReedlineEvent::MenuDocumentationUp => write!(f, "MenuDocumentationUp"),
+            ReedlineEvent::MenuToggleMultiSelect => write!(f, "MenuToggleMultiSelect"),
+            ReedlineEvent::MenuQuickSelect(_) => write!(f, "MenuQuickSelect Label: <char>"),
+            ReedlineEvent::MenuFilter => write!(f, "MenuFilter"),
             ReedlineEvent::ExecuteHostCommand(_) => write!(f, "ExecuteHostCommand"),
+            ReedlineEvent::Custom(_) => write!(f, "Custom"),
             ReedlineEvent::OpenEditor => write!(f, "OpenEditor"),
             ReedlineEvent::RecordToTill => write!(f, "RecordToTill"),
+            ReedlineEvent::UndoBranchNext => write!(f, "UndoBranchNext"),
+            ReedlineEvent::UndoBranchPrevious => write!(f, "UndoBranchPrevious"),
         }
     }
 }
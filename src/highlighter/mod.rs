@@ -1,8 +1,12 @@
 mod example;
 mod simple_match;
+#[cfg(feature = "tree-sitter")]
+mod tree_sitter;
 
 use crate::StyledText;
 
+#[cfg(feature = "tree-sitter")]
+pub use self::tree_sitter::TreeSitterHighlighter;
 pub use example::ExampleHighlighter;
 pub use simple_match::SimpleMatchHighlighter;
 /// The syntax highlighting trait. Implementers of this trait will take in the current string and then
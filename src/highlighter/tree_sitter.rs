@@ -0,0 +1,239 @@
+use crate::{
+    highlighter::Highlighter,
+    result::{ReedlineError, ReedlineErrorVariants},
+    Result, StyledText,
+};
+use ::tree_sitter::{InputEdit, Language, Parser, Point, Query, QueryCursor, Tree};
+use nu_ansi_term::{Color, Style};
+use std::{cmp::Reverse, collections::HashMap, sync::Mutex};
+
+/// Highlights the buffer with a [tree-sitter](https://tree-sitter.github.io)
+/// grammar, styling the captures of its highlight query like `@keyword` or
+/// `@string`
+///
+/// The whole buffer is parsed, so multiline input is highlighted across its
+/// lines, and reparsed incrementally on every change by reusing the syntax
+/// tree of the previous buffer. A capture without a style of its own takes the
+/// style of its prefix, e.g. `@function.builtin` the one of `function`.
+///
+/// # Example
+///
+/// With the Rust grammar of the `tree-sitter-rust` crate:
+///
+/// ```rust,ignore
+/// use nu_ansi_term::{Color, Style};
+/// use reedline::{Reedline, TreeSitterHighlighter};
+///
+/// let highlighter = TreeSitterHighlighter::new(
+///     tree_sitter_rust::language(),
+///     tree_sitter_rust::HIGHLIGHT_QUERY,
+/// )?
+/// .with_style("macro", Style::new().fg(Color::Cyan));
+/// let line_editor = Reedline::create().with_highlighter(Box::new(highlighter));
+/// ```
+pub struct TreeSitterHighlighter {
+    query: Query,
+    styles: HashMap<String, Style>,
+    neutral_style: Style,
+    state: Mutex<ParseState>,
+}
+
+/// The parser with the tree of the last highlighted buffer
+struct ParseState {
+    parser: Parser,
+    tree: Option<Tree>,
+    text: String,
+}
+
+impl Highlighter for TreeSitterHighlighter {
+    fn highlight(&self, line: &str, _cursor: usize) -> StyledText {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let mut styles = vec![self.neutral_style; line.len()];
+        if let Some(tree) = state.reparse(line) {
+            let mut captures = QueryCursor::new()
+                .captures(&self.query, tree.root_node(), line.as_bytes())
+                .filter_map(|(query_match, index)| {
+                    let capture = query_match.captures[index];
+                    let style = self.capture_style(capture.index as usize)?;
+                    Some((capture.node.byte_range(), query_match.pattern_index, style))
+                })
+                .collect::<Vec<_>>();
+            // The inner captures override the outer ones, the earlier patterns
+            // of the query the later ones
+            captures.sort_by_key(|(range, pattern, _)| (Reverse(range.len()), Reverse(*pattern)));
+            for (range, _, style) in captures {
+                if let Some(styles) = styles.get_mut(range) {
+                    styles.fill(style);
+                }
+            }
+        }
+
+        let mut styled_text = StyledText::new();
+        let mut start = 0;
+        for (index, _) in line.char_indices().skip(1) {
+            if styles[index] != styles[start] {
+                styled_text.push((styles[start], line[start..index].to_string()));
+                start = index;
+            }
+        }
+        if start < line.len() {
+            styled_text.push((styles[start], line[start..].to_string()));
+        }
+        styled_text
+    }
+}
+
+impl TreeSitterHighlighter {
+    /// Create a highlighter parsing the buffer with the grammar of `language`
+    /// and styling the captures of the `highlights_query`, usually the
+    /// `highlights.scm` of the grammar
+    ///
+    /// Fails if the grammar was generated for another version of tree-sitter
+    /// or the query doesn't match the grammar.
+    pub fn new(language: Language, highlights_query: &str) -> Result<Self> {
+        let config_error =
+            |message: String| ReedlineError(ReedlineErrorVariants::HighlighterConfigError(message));
+
+        let mut parser = Parser::new();
+        parser
+            .set_language(language)
+            .map_err(|err| config_error(err.to_string()))?;
+        let query =
+            Query::new(language, highlights_query).map_err(|err| config_error(err.to_string()))?;
+
+        let styles = [
+            ("keyword", Style::new().fg(Color::Purple)),
+            ("string", Style::new().fg(Color::Green)),
+            ("comment", Style::new().fg(Color::DarkGray)),
+            ("number", Style::new().fg(Color::Yellow)),
+            ("constant", Style::new().fg(Color::Yellow)),
+            ("function", Style::new().fg(Color::Blue)),
+            ("type", Style::new().fg(Color::Cyan)),
+        ]
+        .into_iter()
+        .map(|(capture, style)| (capture.to_string(), style))
+        .collect();
+
+        Ok(Self {
+            query,
+            styles,
+            neutral_style: Style::default(),
+            state: Mutex::new(ParseState {
+                parser,
+                tree: None,
+                text: String::new(),
+            }),
+        })
+    }
+
+    /// Highlighter builder with the style of a capture of the query, like
+    /// `keyword` or `function.builtin`
+    #[must_use]
+    pub fn with_style(mut self, capture: &str, style: Style) -> Self {
+        self.styles.insert(capture.to_string(), style);
+        self
+    }
+
+    /// Highlighter builder with the style of the text outside of any styled
+    /// capture
+    #[must_use]
+    pub fn with_neutral_style(mut self, neutral_style: Style) -> Self {
+        self.neutral_style = neutral_style;
+        self
+    }
+
+    /// The style of the capture with `index` in the query, or of the nearest
+    /// of its prefixes with one
+    fn capture_style(&self, index: usize) -> Option<Style> {
+        let mut name = self.query.capture_names().get(index)?.as_str();
+        loop {
+            if let Some(style) = self.styles.get(name) {
+                return Some(*style);
+            }
+            name = &name[..name.rfind('.')?];
+        }
+    }
+}
+
+impl ParseState {
+    /// Parses `text`, editing the tree of the previous text to reuse its
+    /// unchanged nodes
+    fn reparse(&mut self, text: &str) -> Option<&Tree> {
+        if self.tree.is_some() && self.text == text {
+            return self.tree.as_ref();
+        }
+        if let Some(tree) = &mut self.tree {
+            tree.edit(&input_edit(&self.text, text));
+        }
+        self.tree = self.parser.parse(text, self.tree.as_ref());
+        self.text = text.to_string();
+        self.tree.as_ref()
+    }
+}
+
+/// The edit turning `old` into `new`, replacing what is between their common
+/// prefix and suffix
+fn input_edit(old: &str, new: &str) -> InputEdit {
+    let start = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, old_char), new_char)| old_char != new_char)
+        .map_or(old.len().min(new.len()), |((index, _), _)| index);
+    let suffix: usize = old[start..]
+        .chars()
+        .rev()
+        .zip(new[start..].chars().rev())
+        .take_while(|(old_char, new_char)| old_char == new_char)
+        .map(|(old_char, _)| old_char.len_utf8())
+        .sum();
+    let old_end = old.len() - suffix;
+    let new_end = new.len() - suffix;
+
+    InputEdit {
+        start_byte: start,
+        old_end_byte: old_end,
+        new_end_byte: new_end,
+        start_position: point(old, start),
+        old_end_position: point(old, old_end),
+        new_end_position: point(new, new_end),
+    }
+}
+
+/// The row and byte column of the byte `offset` of `text`
+fn point(text: &str, offset: usize) -> Point {
+    let before = &text[..offset];
+    Point {
+        row: before.matches('\n').count(),
+        column: before.len() - before.rfind('\n').map_or(0, |newline| newline + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn edit_replaces_between_the_common_prefix_and_suffix() {
+        let edit = input_edit("let a = 1;\nlet b = 2;", "let a = 1;\nlet bc = 2;");
+        assert_eq!(
+            (edit.start_byte, edit.old_end_byte, edit.new_end_byte),
+            (16, 16, 17)
+        );
+        assert_eq!(edit.start_position, Point { row: 1, column: 5 });
+        assert_eq!(edit.new_end_position, Point { row: 1, column: 6 });
+    }
+
+    #[test]
+    fn edit_of_appended_text_starts_at_the_old_end() {
+        let edit = input_edit("ab", "abab");
+        assert_eq!(
+            (edit.start_byte, edit.old_end_byte, edit.new_end_byte),
+            (2, 2, 4)
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use super::first_token;
+use crate::{Hinter, History, Theme};
+use nu_ansi_term::{Color, Style};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+/// The line a hint is computed for by an [`AsyncHinter`]
+pub struct HintRequest {
+    line: String,
+    hint: Arc<Mutex<Option<String>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl HintRequest {
+    /// The line the hint continues
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// Whether the line changed since, so the hint won't be shown anymore and
+    /// computing it can stop early
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn resolve(&self, hint: String) {
+        if let Ok(mut resolved) = self.hint.lock() {
+            *resolved = Some(hint);
+        }
+    }
+}
+
+/// The hint of a line being computed
+struct PendingHint {
+    line: String,
+    hint: Arc<Mutex<Option<String>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// A hinter computing its hints on a background thread, for hints that are
+/// slow to compute like the ones of a remote history
+///
+/// Keystrokes don't wait for the hint: it is painted once it arrives, unless
+/// the line changed in the meantime, which cancels the request. The requests
+/// are computed one after the other by a single worker thread.
+///
+/// # Example
+/// ```rust
+/// use reedline::{AsyncHinter, Reedline};
+///
+/// let hinter = AsyncHinter::new(|request| {
+///     match "git status".strip_prefix(request.line()) {
+///         Some(hint) if !request.line().is_empty() => hint.to_string(),
+///         _ => String::new(),
+///     }
+/// });
+/// let mut line_editor = Reedline::create().with_hinter(Box::new(hinter));
+/// ```
+pub struct AsyncHinter {
+    compute: Arc<dyn Fn(&HintRequest) -> String + Send + Sync>,
+    worker: Option<Sender<HintRequest>>,
+    style: Style,
+    current_hint: String,
+    pending: Option<PendingHint>,
+}
+
+impl AsyncHinter {
+    /// A hinter computing the hint of a line with `compute`, returning the text
+    /// following the line or an empty string for no hint
+    pub fn new<F>(compute: F) -> Self
+    where
+        F: Fn(&HintRequest) -> String + Send + Sync + 'static,
+    {
+        Self {
+            compute: Arc::new(compute),
+            worker: None,
+            style: Style::new().fg(Color::LightGray),
+            current_hint: String::new(),
+            pending: None,
+        }
+    }
+
+    /// A builder that sets the style applied to the hint as part of the buffer
+    #[must_use]
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Starts computing the hint of `line`, cancelling the one computed before
+    fn request(&mut self, line: &str) {
+        self.cancel();
+        let request = HintRequest {
+            line: line.to_string(),
+            hint: Arc::new(Mutex::new(None)),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+        let pending = PendingHint {
+            line: request.line.clone(),
+            hint: Arc::clone(&request.hint),
+            cancelled: Arc::clone(&request.cancelled),
+        };
+        if let Err(mpsc::SendError(request)) = self.worker().send(request) {
+            request.resolve(String::new());
+        }
+        self.pending = Some(pending);
+    }
+
+    /// The channel to the worker thread computing the requests, started with
+    /// the first one
+    fn worker(&mut self) -> &Sender<HintRequest> {
+        let compute = &self.compute;
+        self.worker.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel::<HintRequest>();
+            let compute = Arc::clone(compute);
+            thread::spawn(move || {
+                for request in receiver {
+                    // The requests replaced while queued are skipped. A panic
+                    // resolves the request without a hint, so that it is not
+                    // pending forever
+                    let hint = if request.is_cancelled() {
+                        None
+                    } else {
+                        panic::catch_unwind(AssertUnwindSafe(|| compute(&request))).ok()
+                    };
+                    request.resolve(hint.unwrap_or_default());
+                }
+            });
+            sender
+        })
+    }
+
+    fn cancel(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            pending.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Hinter for AsyncHinter {
+    fn handle(
+        &mut self,
+        line: &str,
+        _pos: usize,
+        _history: &dyn History,
+        use_ansi_coloring: bool,
+    ) -> String {
+        let requested = matches!(&self.pending, Some(pending) if pending.line == line);
+        if !requested {
+            self.current_hint.clear();
+            if line.is_empty() {
+                self.cancel();
+            } else {
+                self.request(line);
+            }
+        }
+        if let Some(pending) = &self.pending {
+            if let Ok(hint) = pending.hint.lock() {
+                self.current_hint = hint.clone().unwrap_or_default();
+            }
+        }
+
+        if use_ansi_coloring && !self.current_hint.is_empty() {
+            self.style.paint(&self.current_hint).to_string()
+        } else {
+            self.current_hint.clone()
+        }
+    }
+
+    fn complete_hint(&self) -> String {
+        self.current_hint.clone()
+    }
+
+    fn next_hint_token(&self) -> String {
+        first_token(&self.current_hint)
+    }
+
+    fn has_pending_hint(&self) -> bool {
+        let pending = self.pending.as_ref().map(|pending| pending.hint.lock());
+        matches!(pending, Some(Ok(hint)) if hint.is_none())
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) {
+        self.style = theme.hint;
+    }
+}
+
+impl Drop for AsyncHinter {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileBackedHistory;
+    use pretty_assertions::assert_eq;
+    use std::sync::mpsc;
+
+    fn wait_for_hint(hinter: &AsyncHinter) {
+        while hinter.has_pending_hint() {
+            thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn hint_is_shown_once_computed() {
+        let history = FileBackedHistory::new(1);
+        let (sender, receiver) = mpsc::channel::<String>();
+        let receiver = Mutex::new(receiver);
+        let mut hinter = AsyncHinter::new(move |_| receiver.lock().unwrap().recv().unwrap());
+
+        assert_eq!(hinter.handle("git", 3, &history, false), "");
+        assert!(hinter.has_pending_hint());
+
+        sender.send(" status".to_string()).unwrap();
+        wait_for_hint(&hinter);
+        assert_eq!(hinter.handle("git", 3, &history, false), " status");
+        assert_eq!(hinter.next_hint_token(), " status");
+    }
+
+    #[test]
+    fn changing_the_line_cancels_the_hint() {
+        let history = FileBackedHistory::new(1);
+        let (sender, receiver) = mpsc::channel::<String>();
+        let sender = Mutex::new(sender);
+        let mut hinter = AsyncHinter::new(move |request| {
+            sender
+                .lock()
+                .unwrap()
+                .send(request.line().to_string())
+                .unwrap();
+            if request.line() == "git" {
+                // blocks until the line changed
+                while !request.is_cancelled() {
+                    thread::yield_now();
+                }
+                " status".to_string()
+            } else {
+                " test".to_string()
+            }
+        });
+
+        assert_eq!(hinter.handle("git", 3, &history, false), "");
+        assert_eq!(receiver.recv().unwrap(), "git");
+        hinter.handle("cargo", 5, &history, false);
+        assert_eq!(receiver.recv().unwrap(), "cargo");
+        wait_for_hint(&hinter);
+        assert_eq!(hinter.handle("cargo", 5, &history, false), " test");
+        assert_eq!(hinter.handle("", 0, &history, false), "");
+        assert!(!hinter.has_pending_hint());
+    }
+
+    #[test]
+    fn panicking_request_is_not_pending() {
+        let history = FileBackedHistory::new(1);
+        let mut hinter = AsyncHinter::new(|request| {
+            if request.line() == "panic" {
+                panic!("hint failed");
+            }
+            " status".to_string()
+        });
+
+        hinter.handle("panic", 5, &history, false);
+        wait_for_hint(&hinter);
+        assert_eq!(hinter.handle("panic", 5, &history, false), "");
+        hinter.handle("git", 3, &history, false);
+        wait_for_hint(&hinter);
+        assert_eq!(hinter.handle("git", 3, &history, false), " status");
+    }
+}
@@ -0,0 +1,188 @@
+use super::first_token;
+use crate::{Completer, Hinter, History, Theme};
+use nu_ansi_term::{Color, Style};
+
+/// A hinter showing the top completion of the word before the cursor, taken
+/// from the completer of the engine set with [`crate::Reedline::with_completer`]
+///
+/// Added as a fallback after the history hinter, it still hints while the
+/// history has no matching entry.
+///
+/// # Example
+/// ```rust
+/// use reedline::{CompleterHinter, DefaultCompleter, DefaultHinter, Reedline};
+///
+/// let commands = vec!["status".into(), "stash".into(), "switch".into()];
+/// let mut line_editor = Reedline::create()
+///     .with_completer(Box::new(DefaultCompleter::new(commands)))
+///     .with_hinter(Box::new(DefaultHinter::default()))
+///     .with_fallback_hinter(Box::new(CompleterHinter::default()), None);
+/// ```
+pub struct CompleterHinter {
+    style: Style,
+    current_hint: String,
+    min_chars: usize,
+}
+
+impl Default for CompleterHinter {
+    fn default() -> Self {
+        CompleterHinter {
+            style: Style::new().fg(Color::LightGray),
+            current_hint: String::new(),
+            min_chars: 1,
+        }
+    }
+}
+
+impl CompleterHinter {
+    /// A builder that sets the style applied to the hint as part of the buffer
+    #[must_use]
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// A builder that sets the number of characters of the word that have to be
+    /// present to enable completion hints
+    #[must_use]
+    pub fn with_min_chars(mut self, min_chars: usize) -> Self {
+        self.min_chars = min_chars;
+        self
+    }
+
+    /// The rest of the first completion continuing the word before the cursor
+    fn top_completion(&self, line: &str, pos: usize, completer: &mut dyn Completer) -> String {
+        if pos != line.len() {
+            return String::new();
+        }
+        completer
+            .complete(line, pos)
+            .into_iter()
+            .find_map(|suggestion| {
+                let span = suggestion.span;
+                if span.end != pos {
+                    return None;
+                }
+                let word = line.get(span.start..pos)?;
+                if word.chars().count() < self.min_chars {
+                    return None;
+                }
+                suggestion
+                    .value
+                    .strip_prefix(word)
+                    .filter(|rest| !rest.is_empty())
+                    .map(str::to_string)
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Hinter for CompleterHinter {
+    /// Without the completer of the engine there is no hint
+    fn handle(
+        &mut self,
+        _line: &str,
+        _pos: usize,
+        _history: &dyn History,
+        _use_ansi_coloring: bool,
+    ) -> String {
+        self.current_hint.clear();
+        String::new()
+    }
+
+    fn handle_with_completer(
+        &mut self,
+        line: &str,
+        pos: usize,
+        _history: &dyn History,
+        completer: &mut dyn Completer,
+        use_ansi_coloring: bool,
+    ) -> String {
+        self.current_hint = self.top_completion(line, pos, completer);
+
+        if use_ansi_coloring && !self.current_hint.is_empty() {
+            self.style.paint(&self.current_hint).to_string()
+        } else {
+            self.current_hint.clone()
+        }
+    }
+
+    fn complete_hint(&self) -> String {
+        self.current_hint.clone()
+    }
+
+    fn next_hint_token(&self) -> String {
+        first_token(&self.current_hint)
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) {
+        self.style = theme.hint;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DefaultCompleter, FileBackedHistory, Span, Suggestion};
+    use pretty_assertions::assert_eq;
+
+    fn hint(hinter: &mut CompleterHinter, line: &str) -> String {
+        let mut completer = DefaultCompleter::new_with_wordlen(
+            vec!["status".into(), "stash".into(), "git".into()],
+            1,
+        );
+        hinter.handle_with_completer(
+            line,
+            line.len(),
+            &FileBackedHistory::new(1),
+            &mut completer,
+            false,
+        )
+    }
+
+    #[test]
+    fn hints_the_rest_of_the_top_completion() {
+        let mut hinter = CompleterHinter::default();
+
+        assert_eq!(hint(&mut hinter, "git sta"), "sh");
+        assert_eq!(hinter.complete_hint(), "sh");
+        assert_eq!(hint(&mut hinter, "git stat"), "us");
+        assert_eq!(hint(&mut hinter, "git status"), "");
+        assert_eq!(hint(&mut hinter, "git "), "");
+    }
+
+    #[test]
+    fn min_chars_of_the_word() {
+        let mut hinter = CompleterHinter::default().with_min_chars(3);
+
+        assert_eq!(hint(&mut hinter, "git st"), "");
+        assert_eq!(hint(&mut hinter, "git sta"), "sh");
+    }
+
+    struct SplitCharCompleter;
+
+    impl Completer for SplitCharCompleter {
+        fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+            vec![Suggestion {
+                value: "übel".into(),
+                span: Span::new(line.len() - 1, pos),
+                ..Suggestion::default()
+            }]
+        }
+    }
+
+    #[test]
+    fn span_splitting_a_char_gives_no_hint() {
+        let mut hinter = CompleterHinter::default().with_min_chars(0);
+        let line = "ü";
+        let hint = hinter.handle_with_completer(
+            line,
+            line.len(),
+            &FileBackedHistory::new(1),
+            &mut SplitCharCompleter,
+            false,
+        );
+
+        assert_eq!(hint, "");
+    }
+}
@@ -1,6 +1,27 @@
-use crate::{history::SearchQuery, Hinter, History};
+use super::first_token;
+use crate::{
+    history::{CommandLineSearch, SearchFilter, SearchQuery},
+    Hinter, History, HistoryScope, Theme,
+};
 use nu_ansi_term::{Color, Style};
 
+/// Which entries of the history the [`DefaultHinter`] takes its hint from
+///
+/// Preferring the current working directory needs a history that keeps the
+/// working directory of its entries, like the `SqliteBackedHistory`. With other
+/// histories every strategy hints from all entries.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HintStrategy {
+    /// The most recent entry starting with the line (default)
+    #[default]
+    Global,
+    /// The most recent entry run in the current working directory, or else the
+    /// most recent one of all entries
+    DirectoryFirst,
+    /// Only the entries run in the current working directory
+    DirectoryOnly,
+}
+
 /// A hinter that use the completions or the history to show a hint to the user
 ///
 /// Similar to `fish` autosuggestins
@@ -8,6 +29,7 @@ pub struct DefaultHinter {
     style: Style,
     current_hint: String,
     min_chars: usize,
+    strategy: HintStrategy,
 }
 
 impl Hinter for DefaultHinter {
@@ -19,12 +41,9 @@ impl Hinter for DefaultHinter {
         use_ansi_coloring: bool,
     ) -> String {
         self.current_hint = if line.chars().count() >= self.min_chars {
-            history
-                .search(SearchQuery::last_with_prefix(line.to_string()))
-                .expect("todo: error handling")
-                .get(0)
-                .map_or_else(String::new, |entry| {
-                    entry.command_line[line.len()..].to_string()
+            self.search(line, history)
+                .map_or_else(String::new, |command_line| {
+                    command_line[line.len()..].to_string()
                 })
         } else {
             String::new()
@@ -42,21 +61,11 @@ impl Hinter for DefaultHinter {
     }
 
     fn next_hint_token(&self) -> String {
-        let mut reached_content = false;
-        let result: String = self
-            .current_hint
-            .chars()
-            .take_while(|c| match (c.is_whitespace(), reached_content) {
-                (true, true) => false,
-                (true, false) => true,
-                (false, true) => true,
-                (false, false) => {
-                    reached_content = true;
-                    true
-                }
-            })
-            .collect();
-        result
+        first_token(&self.current_hint)
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) {
+        self.style = theme.hint;
     }
 }
 
@@ -66,6 +75,7 @@ impl Default for DefaultHinter {
             style: Style::new().fg(Color::LightGray),
             current_hint: String::new(),
             min_chars: 1,
+            strategy: HintStrategy::Global,
         }
     }
 }
@@ -84,4 +94,119 @@ impl DefaultHinter {
         self.min_chars = min_chars;
         self
     }
+
+    /// A builder that sets which entries of the history the hint is taken from
+    #[must_use]
+    pub fn with_strategy(mut self, strategy: HintStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// The most recent command line of the strategy starting with `line`
+    fn search(&self, line: &str, history: &dyn History) -> Option<String> {
+        if self.strategy != HintStrategy::Global {
+            match last_with_prefix(line, HistoryScope::Directory, history) {
+                // the history doesn't know where its entries ran, hint from all of them
+                Err(err) if err.is_unsupported_feature() => {}
+                Ok(None) if self.strategy == HintStrategy::DirectoryFirst => {}
+                result => return result.expect("todo: error handling"),
+            }
+        }
+        last_with_prefix(line, HistoryScope::Global, history).expect("todo: error handling")
+    }
+}
+
+fn last_with_prefix(
+    line: &str,
+    scope: HistoryScope,
+    history: &dyn History,
+) -> crate::Result<Option<String>> {
+    let filter = scope.restrict(SearchFilter::from_text_search(CommandLineSearch::Prefix(
+        line.to_string(),
+    )));
+    Ok(history
+        .search(SearchQuery::last_with_search(filter))?
+        .into_iter()
+        .next()
+        .map(|entry| entry.command_line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileBackedHistory, HistoryItem};
+    use pretty_assertions::assert_eq;
+
+    fn hint(strategy: HintStrategy, history: &dyn History) -> String {
+        DefaultHinter::default()
+            .with_strategy(strategy)
+            .handle("git ", 4, history, false)
+    }
+
+    #[test]
+    fn histories_without_directories_hint_from_all_entries() {
+        let mut history = FileBackedHistory::new(10);
+        history
+            .save(HistoryItem::from_command_line("git status"))
+            .unwrap();
+
+        for strategy in [
+            HintStrategy::Global,
+            HintStrategy::DirectoryFirst,
+            HintStrategy::DirectoryOnly,
+        ] {
+            assert_eq!(hint(strategy, &history), "status");
+        }
+    }
+
+    #[test]
+    fn multi_line_hint_completes_line_by_line() {
+        let mut history = FileBackedHistory::new(10);
+        history
+            .save(HistoryItem::from_command_line(
+                "git commit -m 'fix\n\nmore'",
+            ))
+            .unwrap();
+        let mut hinter = DefaultHinter::default();
+
+        assert_eq!(
+            hinter.handle("git ", 4, &history, false),
+            "commit -m 'fix\n\nmore'"
+        );
+        assert_eq!(hinter.next_hint_token(), "commit");
+        assert_eq!(hinter.next_hint_line(), "commit -m 'fix\n");
+        hinter.handle("git commit -m 'fix\n", 19, &history, false);
+        assert_eq!(hinter.next_hint_line(), "\n");
+        hinter.handle("git commit -m 'fix\n\n", 20, &history, false);
+        assert_eq!(hinter.next_hint_line(), "more'");
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn strategy_prefers_the_working_directory() {
+        let cwd = std::env::current_dir()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let mut history = crate::SqliteBackedHistory::in_memory().unwrap();
+        for (command_line, cwd) in [("git status", Some(cwd)), ("git push", None)] {
+            history
+                .save(HistoryItem {
+                    cwd,
+                    ..HistoryItem::from_command_line(command_line)
+                })
+                .unwrap();
+        }
+
+        assert_eq!(hint(HintStrategy::Global, &history), "push");
+        assert_eq!(hint(HintStrategy::DirectoryFirst, &history), "status");
+        assert_eq!(hint(HintStrategy::DirectoryOnly, &history), "status");
+
+        let mut history = crate::SqliteBackedHistory::in_memory().unwrap();
+        history
+            .save(HistoryItem::from_command_line("git push"))
+            .unwrap();
+        assert_eq!(hint(HintStrategy::DirectoryFirst, &history), "push");
+        assert_eq!(hint(HintStrategy::DirectoryOnly, &history), "");
+    }
 }
@@ -0,0 +1,238 @@
+use crate::{Completer, Hinter, History, Theme};
+use nu_ansi_term::Style;
+
+/// Which hint is shown when several hinters were added to
+/// [`crate::Reedline`] with [`crate::Reedline::with_fallback_hinter`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HintPolicy {
+    /// The hint of the first hinter having one, in the order the hinters were
+    /// added (default)
+    #[default]
+    FirstHint,
+    /// The longest hint of all hinters, the first one of equal lengths
+    LongestHint,
+}
+
+/// The hinters of the engine in the order of their priority
+#[derive(Default)]
+pub(crate) struct HinterList {
+    /// The hinters with the style they paint their hints in regardless of the
+    /// theme
+    hinters: Vec<(Box<dyn Hinter>, Option<Style>)>,
+    policy: HintPolicy,
+    /// The hinter of the hint shown
+    active: Option<usize>,
+}
+
+impl HinterList {
+    pub(crate) fn clear(&mut self) {
+        self.hinters.clear();
+        self.active = None;
+    }
+
+    /// Adds a hinter asked after the ones added before, styled with the theme
+    /// if one was set
+    pub(crate) fn push(
+        &mut self,
+        mut hinter: Box<dyn Hinter>,
+        style: Option<Style>,
+        theme: Option<&Theme>,
+    ) {
+        match (style, theme) {
+            (Some(style), theme) => {
+                hinter.apply_theme(&themed_hint(&theme.copied().unwrap_or_default(), style));
+            }
+            (None, Some(theme)) => hinter.apply_theme(theme),
+            (None, None) => {}
+        }
+        self.hinters.push((hinter, style));
+    }
+
+    pub(crate) fn set_policy(&mut self, policy: HintPolicy) {
+        self.policy = policy;
+    }
+
+    /// Asks the hinters with `handle` for the hint to show, following the
+    /// policy
+    fn show_hint(&mut self, mut handle: impl FnMut(&mut dyn Hinter) -> String) -> String {
+        self.active = None;
+        let mut shown = String::new();
+        let mut shown_len = 0;
+        for (index, (hinter, _)) in self.hinters.iter_mut().enumerate() {
+            let hint = handle(hinter.as_mut());
+            let hint_len = hinter.complete_hint().len();
+            if hint_len > shown_len {
+                self.active = Some(index);
+                shown = hint;
+                shown_len = hint_len;
+                if self.policy == HintPolicy::FirstHint {
+                    break;
+                }
+            }
+        }
+        shown
+    }
+
+    fn active_hinter(&self) -> Option<&dyn Hinter> {
+        self.active.map(|index| self.hinters[index].0.as_ref())
+    }
+}
+
+impl Hinter for HinterList {
+    fn handle(
+        &mut self,
+        line: &str,
+        pos: usize,
+        history: &dyn History,
+        use_ansi_coloring: bool,
+    ) -> String {
+        self.show_hint(|hinter| hinter.handle(line, pos, history, use_ansi_coloring))
+    }
+
+    fn handle_with_completer(
+        &mut self,
+        line: &str,
+        pos: usize,
+        history: &dyn History,
+        completer: &mut dyn Completer,
+        use_ansi_coloring: bool,
+    ) -> String {
+        self.show_hint(|hinter| {
+            hinter.handle_with_completer(line, pos, history, completer, use_ansi_coloring)
+        })
+    }
+
+    fn complete_hint(&self) -> String {
+        self.active_hinter()
+            .map_or_else(String::new, Hinter::complete_hint)
+    }
+
+    fn next_hint_token(&self) -> String {
+        self.active_hinter()
+            .map_or_else(String::new, Hinter::next_hint_token)
+    }
+
+    fn next_hint_line(&self) -> String {
+        self.active_hinter()
+            .map_or_else(String::new, Hinter::next_hint_line)
+    }
+
+    fn has_pending_hint(&self) -> bool {
+        self.hinters
+            .iter()
+            .any(|(hinter, _)| hinter.has_pending_hint())
+    }
+
+    /// The hinters added with a style of their own keep painting in it
+    fn apply_theme(&mut self, theme: &Theme) {
+        for (hinter, style) in &mut self.hinters {
+            match style {
+                Some(style) => hinter.apply_theme(&themed_hint(theme, *style)),
+                None => hinter.apply_theme(theme),
+            }
+        }
+    }
+}
+
+fn themed_hint(theme: &Theme, hint: Style) -> Theme {
+    Theme { hint, ..*theme }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileBackedHistory;
+    use nu_ansi_term::Color;
+    use pretty_assertions::assert_eq;
+
+    /// Hints a fixed text after lines it starts with
+    struct FixedHinter {
+        text: &'static str,
+        style: Style,
+        current_hint: String,
+    }
+
+    impl FixedHinter {
+        fn boxed(text: &'static str) -> Box<dyn Hinter> {
+            Box::new(Self {
+                text,
+                style: Style::new(),
+                current_hint: String::new(),
+            })
+        }
+    }
+
+    impl Hinter for FixedHinter {
+        fn handle(&mut self, line: &str, _: usize, _: &dyn History, color: bool) -> String {
+            self.current_hint = self.text.strip_prefix(line).unwrap_or_default().to_string();
+            if color {
+                self.style.paint(&self.current_hint).to_string()
+            } else {
+                self.current_hint.clone()
+            }
+        }
+
+        fn complete_hint(&self) -> String {
+            self.current_hint.clone()
+        }
+
+        fn next_hint_token(&self) -> String {
+            self.current_hint.clone()
+        }
+
+        fn apply_theme(&mut self, theme: &Theme) {
+            self.style = theme.hint;
+        }
+    }
+
+    fn hinters(policy: HintPolicy) -> HinterList {
+        let mut hinters = HinterList::default();
+        hinters.set_policy(policy);
+        hinters.push(FixedHinter::boxed("git status"), None, None);
+        hinters.push(FixedHinter::boxed("git stash list"), None, None);
+        hinters.push(FixedHinter::boxed("cargo test"), None, None);
+        hinters
+    }
+
+    #[test]
+    fn first_hint_falls_back_to_the_next_hinter() {
+        let history = FileBackedHistory::new(1);
+        let mut hinters = hinters(HintPolicy::FirstHint);
+
+        assert_eq!(hinters.handle("git st", 6, &history, false), "atus");
+        assert_eq!(hinters.handle("git stash", 9, &history, false), " list");
+        assert_eq!(hinters.complete_hint(), " list");
+        assert_eq!(hinters.handle("cargo", 5, &history, false), " test");
+        assert_eq!(hinters.handle("ls", 2, &history, false), "");
+        assert_eq!(hinters.complete_hint(), "");
+    }
+
+    #[test]
+    fn longest_hint_asks_every_hinter() {
+        let history = FileBackedHistory::new(1);
+        let mut hinters = hinters(HintPolicy::LongestHint);
+
+        assert_eq!(hinters.handle("git st", 6, &history, false), "ash list");
+        assert_eq!(hinters.next_hint_token(), "ash list");
+    }
+
+    #[test]
+    fn styled_hinters_ignore_the_theme() {
+        let history = FileBackedHistory::new(1);
+        let style = Style::new().fg(Color::Cyan);
+        let mut hinters = HinterList::default();
+        hinters.push(FixedHinter::boxed("git status"), None, None);
+        hinters.push(FixedHinter::boxed("cargo test"), Some(style), None);
+        let theme = Theme::default();
+        hinters.apply_theme(&theme);
+
+        assert_eq!(
+            hinters.handle("git", 3, &history, true),
+            theme.hint.paint(" status").to_string()
+        );
+        assert_eq!(
+            hinters.handle("cargo", 5, &history, true),
+            style.paint(" test").to_string()
+        );
+    }
+}
@@ -1,7 +1,14 @@
+mod background;
+mod completer;
 mod default;
-pub use default::DefaultHinter;
+mod list;
+pub use background::{AsyncHinter, HintRequest};
+pub use completer::CompleterHinter;
+pub use default::{DefaultHinter, HintStrategy};
+pub use list::HintPolicy;
+pub(crate) use list::HinterList;
 
-use crate::History;
+use crate::{Completer, History, Theme};
 /// A trait that's responsible for returning the hint for the current line and position
 /// Hints are often shown in-line as part of the buffer, showing the user text they can accept or ignore
 pub trait Hinter: Send {
@@ -16,10 +23,62 @@ pub trait Hinter: Send {
         use_ansi_coloring: bool,
     ) -> String;
 
+    /// Handle the hinting duty with access to the [`Completer`] of the engine,
+    /// like the [`CompleterHinter`]
+    ///
+    /// The engine calls this method, which defaults to [`Hinter::handle`].
+    fn handle_with_completer(
+        &mut self,
+        line: &str,
+        pos: usize,
+        history: &dyn History,
+        _completer: &mut dyn Completer,
+        use_ansi_coloring: bool,
+    ) -> String {
+        self.handle(line, pos, history, use_ansi_coloring)
+    }
+
     /// Return the current hint unformatted to perform the completion of the full hint
     fn complete_hint(&self) -> String;
 
     /// Return the first semantic token of the hint
     /// for incremental completion
     fn next_hint_token(&self) -> String;
+
+    /// Return the hint up to and including its first line break, to complete
+    /// a multi-line hint line by line
+    fn next_hint_line(&self) -> String {
+        let hint = self.complete_hint();
+        match hint.find('\n') {
+            Some(line_end) => hint[..=line_end].to_string(),
+            None => hint,
+        }
+    }
+
+    /// Whether the hint of the line is still computed in the background, like
+    /// by an [`AsyncHinter`]. While it is, the engine checks for it between
+    /// keystrokes and repaints once it arrived
+    fn has_pending_hint(&self) -> bool {
+        false
+    }
+
+    /// Style the hint as set by [`crate::Reedline::with_theme`]
+    fn apply_theme(&mut self, _theme: &Theme) {}
+}
+
+/// The start of the hint up to the end of its first token, with the whitespace
+/// before it
+fn first_token(hint: &str) -> String {
+    let mut reached_content = false;
+    hint.chars()
+        .take_while(|c| match (c.is_whitespace(), reached_content) {
+            (true, true) => false,
+            (true, false) => true,
+            (false, true) => true,
+            (false, false) => {
+                reached_content = true;
+                true
+            }
+        })
+        .collect()
 }
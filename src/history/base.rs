@@ -1,8 +1,16 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::{collections::BTreeMap, ops::Range, time::Duration};
 
-use crate::{core_editor::LineBuffer, HistoryItem, Result};
+use crate::{
+    core_editor::LineBuffer,
+    result::{ReedlineError, ReedlineErrorVariants},
+    HistoryItem, Result,
+};
 
-use super::{HistoryItemId, HistorySessionId};
+use super::{
+    export::write_items, frecency::rank_by_frecency, fuzzy::fuzzy_match, FuzzyMatch,
+    HistoryExportFormat, HistoryItemId, HistorySessionId, HistoryStats,
+};
 
 /// Browsing modes for a [`History`]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -17,8 +25,25 @@ pub enum HistoryNavigationQuery {
     // Fuzzy Search
 }
 
+/// Which entries Up and Down walk through from the line buffer, see
+/// [`crate::Reedline::with_history_navigation_mode`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryNavigationMode {
+    /// The entries starting with the buffer if the cursor is at its end, all
+    /// entries otherwise (default)
+    #[default]
+    Adaptive,
+    /// The entries starting with the text before the cursor, which stays in
+    /// place and highlighted while cycling, like `up-line-or-beginning-search`
+    /// of zsh
+    Prefix,
+    /// All entries, like bash
+    All,
+}
+
 /// Ways to search for a particular command line in the [`History`]
 // todo: merge with [HistoryNavigationQuery]
+#[derive(Clone)]
 pub enum CommandLineSearch {
     /// Command line starts with the same string
     Prefix(String),
@@ -28,6 +53,71 @@ pub enum CommandLineSearch {
     ///
     /// Useful to gather statistics
     Exact(String),
+    /// Command line matches the full-text query, in the syntax of SQLite FTS5:
+    /// all of the words, `"a phrase"`, words starting with `prefix*`, combined
+    /// with `OR` and `NOT`
+    ///
+    /// Only supported by a [`crate::SqliteBackedHistory::with_full_text_index`]
+    FullText(String),
+    /// Command line contains the characters of the pattern in order, like fzf,
+    /// see [`crate::fuzzy_match`] and [`History::search_fuzzy`]
+    Fuzzy(String),
+    /// Command line contains a match of the regular expression, in the syntax
+    /// of the [`regex`] crate
+    ///
+    /// The backends compile the pattern and report an invalid one as an error.
+    #[cfg(feature = "regex")]
+    Regex(String),
+}
+
+/// The search for the command lines containing `substring`, or a match of it
+/// as a regular expression if `regex` is set
+///
+/// `None` if it is an invalid regular expression, like one still being typed,
+/// which matches nothing.
+pub(crate) fn substring_search(substring: String, regex: bool) -> Option<CommandLineSearch> {
+    #[cfg(feature = "regex")]
+    if regex {
+        return regex::Regex::new(&substring)
+            .is_ok()
+            .then_some(CommandLineSearch::Regex(substring));
+    }
+    #[cfg(not(feature = "regex"))]
+    let _ = regex;
+    Some(CommandLineSearch::Substring(substring))
+}
+
+/// Byte ranges of the parts of `text` matching a [`substring_search`]
+pub(crate) fn match_ranges(search: &CommandLineSearch, text: &str) -> Vec<Range<usize>> {
+    match search {
+        CommandLineSearch::Substring(substring) if !substring.is_empty() => text
+            .match_indices(substring.as_str())
+            .map(|(start, part)| start..start + part.len())
+            .collect(),
+        #[cfg(feature = "regex")]
+        CommandLineSearch::Regex(pattern) => regex::Regex::new(pattern)
+            .map(|regex| {
+                regex
+                    .find_iter(text)
+                    .map(|m| m.range())
+                    .filter(|range| !range.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+/// The compiled pattern of a [`CommandLineSearch::Regex`], for the backends
+/// matching the command lines themselves
+#[cfg(feature = "regex")]
+pub(crate) fn compile_regex(search: Option<&CommandLineSearch>) -> Result<Option<regex::Regex>> {
+    match search {
+        Some(CommandLineSearch::Regex(pattern)) => regex::Regex::new(pattern)
+            .map(Some)
+            .map_err(ReedlineError::history_backend),
+        _ => Ok(None),
+    }
 }
 
 /// Defines how to traverse the history when executing a [`SearchQuery`]
@@ -40,19 +130,26 @@ pub enum SearchDirection {
 }
 
 /// Defines additional filters for querying the [`History`]
+#[derive(Clone)]
 pub struct SearchFilter {
     /// Query for the command line content
     pub command_line: Option<CommandLineSearch>,
-    /// Considered implementation detail for now
-    pub(crate) not_command_line: Option<String>, // to skip the currently shown value in up-arrow navigation
+    /// Skip entries with exactly this command line, like the entry currently
+    /// shown while browsing with the up-arrow
+    pub not_command_line: Option<String>,
     /// Filter based on the executing systems hostname
     pub hostname: Option<String>,
     /// Exact filter for the working directory
     pub cwd_exact: Option<String>,
     /// Prefix filter for the working directory
     pub cwd_prefix: Option<String>,
+    /// Filter for the working directory being this directory or one below it
+    pub cwd_subtree: Option<String>,
     /// Filter whether the command completed
     pub exit_successful: Option<bool>,
+    /// Filter for entries having all of these key/value pairs in their
+    /// [`HistoryItem::metadata`]
+    pub metadata: BTreeMap<String, String>,
 }
 impl SearchFilter {
     /// Create a search filter with a [`CommandLineSearch`]
@@ -69,9 +166,19 @@ impl SearchFilter {
             hostname: None,
             cwd_exact: None,
             cwd_prefix: None,
+            cwd_subtree: None,
             exit_successful: None,
+            metadata: BTreeMap::new(),
         }
     }
+
+    /// A builder that restricts the filter to entries having the `key`/`value`
+    /// pair in their [`HistoryItem::metadata`]
+    #[must_use]
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
 }
 
 /// Query for search in the potentially rich [`History`]
@@ -138,8 +245,86 @@ impl SearchQuery {
     }
 }
 
+/// Which entries a [`History`] keeps when a command line is saved again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryDedupPolicy {
+    /// Every command line is saved
+    KeepAll,
+    /// A command line is not saved again right after itself
+    IgnoreConsecutive,
+    /// Saving a command line again removes its older entries, so it only
+    /// shows up once as the most recent entry, like `HIST_IGNORE_ALL_DUPS` of zsh
+    KeepLatest,
+}
+
+/// Limits on the entries a [`History`] keeps, applied by [`History::prune`]
+///
+/// Pruning removes the oldest entries, starting with the most recent entry
+/// that exceeds one of the limits. Limits left at `None` don't apply.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryRetentionPolicy {
+    /// Number of entries kept
+    pub max_entries: Option<usize>,
+    /// Age of the oldest entry kept, entries without a start time, like those
+    /// of the [`crate::FileBackedHistory`], are kept regardless
+    pub max_age: Option<Duration>,
+    /// Bytes of text of the entries kept, counting the command line, the
+    /// working directory and the host of each
+    ///
+    /// This is not the size of the history file, which adds the other fields
+    /// and the overhead of the storage format.
+    pub max_size: Option<u64>,
+}
+
+impl HistoryRetentionPolicy {
+    /// How many of the entries, given most recent first by their size and
+    /// start time, are kept
+    pub(crate) fn retained(
+        &self,
+        newest_first: impl IntoIterator<Item = (u64, Option<DateTime<Utc>>)>,
+    ) -> usize {
+        let oldest_start = self
+            .max_age
+            .and_then(|age| chrono::Duration::from_std(age).ok())
+            .map(|age| Utc::now() - age);
+        let mut size = 0;
+        let mut kept = 0;
+        for (entry_size, start) in newest_first {
+            size += entry_size;
+            if self.max_entries.is_some_and(|max| kept >= max)
+                || self.max_size.is_some_and(|max| size > max)
+                || matches!((oldest_start, start), (Some(oldest), Some(start)) if start < oldest)
+            {
+                break;
+            }
+            kept += 1;
+        }
+        kept
+    }
+}
+
+/// Bytes an item takes in the sense of [`HistoryRetentionPolicy::max_size`]
+pub(crate) fn stored_size(item: &HistoryItem) -> u64 {
+    [
+        Some(&item.command_line),
+        item.cwd.as_ref(),
+        item.hostname.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|text| text.len() as u64)
+    .sum()
+}
+
 /// Represents a history file or database
 /// Data could be stored e.g. in a plain text file, in a JSONL file, in a SQLite database
+///
+/// Implement it to keep the history in a store of your own, like a remote
+/// service, and check the implementation with
+/// [`crate::check_history_conformance`]. Failures of the store are reported
+/// with [`crate::ReedlineError::history_backend`]. A store that can't honor
+/// part of a request, like filtering by the working directory, returns
+/// [`crate::ReedlineError::history_feature_unsupported`] instead.
 pub trait History: Send {
     /// save a history item to the database
     /// if given id is None, a new id is created and set in the return value
@@ -158,7 +343,61 @@ pub trait History: Send {
         self.count(SearchQuery::everything(SearchDirection::Forward))
     }
     /// return the results of a query
+    ///
+    /// Results are ordered by id, the most recent entry first when searching
+    /// [`SearchDirection::Backward`]. `start_id` excludes the entry it names and
+    /// the entries before it in the search direction.
     fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>>;
+    /// return the distinct command lines matching `filter`, each as its most
+    /// recent item, the ones run the most often and the most recently first
+    ///
+    /// Runs in `cwd` count double. Runs without a start time are weighted by
+    /// their position in the history instead of their age.
+    fn search_frecent(
+        &self,
+        filter: SearchFilter,
+        cwd: Option<&str>,
+        limit: Option<i64>,
+    ) -> Result<Vec<HistoryItem>> {
+        let items = self.search(SearchQuery {
+            filter,
+            ..SearchQuery::everything(SearchDirection::Backward)
+        })?;
+        let mut ranked = rank_by_frecency(items, cwd, Utc::now());
+        if let Some(limit) = limit {
+            ranked.truncate(limit as usize);
+        }
+        Ok(ranked)
+    }
+    /// return the items whose command line matches `pattern` like
+    /// [`CommandLineSearch::Fuzzy`] with how they match, the best match first
+    ///
+    /// The filter of the query is restricted to the pattern, its limit applies
+    /// to the ranked matches. Equally good matches stay in search order.
+    fn search_fuzzy(
+        &self,
+        pattern: &str,
+        query: SearchQuery,
+    ) -> Result<Vec<(HistoryItem, FuzzyMatch)>> {
+        let limit = query.limit;
+        let items = self.search(SearchQuery {
+            filter: SearchFilter {
+                command_line: Some(CommandLineSearch::Fuzzy(pattern.to_string())),
+                ..query.filter
+            },
+            limit: None,
+            ..query
+        })?;
+        let mut matches = items
+            .into_iter()
+            .filter_map(|item| fuzzy_match(pattern, &item.command_line).map(|m| (item, m)))
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|(_, m)| std::cmp::Reverse(m.score));
+        if let Some(limit) = limit {
+            matches.truncate(limit as usize);
+        }
+        Ok(matches)
+    }
 
     /// update an item atomically
     fn update(
@@ -168,8 +407,63 @@ pub trait History: Send {
     ) -> Result<()>;
     /// remove an item from this history
     fn delete(&mut self, h: HistoryItemId) -> Result<()>;
+    /// remove several items at once, like the repetitions of a command line,
+    /// which [`History::restore_last_deleted`] brings back together
+    fn delete_items(&mut self, ids: &[HistoryItemId]) -> Result<()> {
+        ids.iter().try_for_each(|id| self.delete(*id))
+    }
+    /// bring back the items removed last with [`History::delete`] or
+    /// [`History::delete_items`] in their original order, empty if there are
+    /// none left to restore
+    ///
+    /// The removed items are kept in memory until the end of the session or
+    /// until [`History::prune`] purges them.
+    fn restore_last_deleted(&mut self) -> Result<Vec<HistoryItem>> {
+        Err(ReedlineError::history_feature_unsupported(
+            "History",
+            "restoring deleted entries",
+        ))
+    }
     /// ensure that this history is written to disk
     fn sync(&mut self) -> std::io::Result<()>;
+    /// pick up the entries other sessions added to a shared store, called
+    /// before navigating or searching the history
+    fn refresh(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// write the results of a query to `out`, one item per line, returning
+    /// how many were written
+    fn export(
+        &self,
+        query: SearchQuery,
+        format: HistoryExportFormat,
+        out: &mut dyn std::io::Write,
+    ) -> Result<usize> {
+        let items = self.search(query)?;
+        write_items(&items, format, out).map_err(|err| {
+            ReedlineError(ReedlineErrorVariants::HistoryExportError(err.to_string()))
+        })?;
+        Ok(items.len())
+    }
+    /// summarize the entries, e.g. for a `history stats` command of the host
+    fn stats(&self) -> Result<HistoryStats> {
+        let items = self.search(SearchQuery::everything(SearchDirection::Forward))?;
+        Ok(HistoryStats::from_items(&items))
+    }
+    /// remove the oldest entries exceeding the limits of `policy`, returning
+    /// how many were removed, and purge the items kept to be restored
+    fn prune(&mut self, policy: &HistoryRetentionPolicy) -> Result<usize> {
+        let items = self.search(SearchQuery::everything(SearchDirection::Backward))?;
+        let kept = policy.retained(
+            items
+                .iter()
+                .map(|item| (stored_size(item), item.start_timestamp)),
+        );
+        for id in items[kept..].iter().filter_map(|item| item.id) {
+            self.delete(id)?;
+        }
+        Ok(items.len() - kept)
+    }
 }
 
 #[cfg(test)]
@@ -190,6 +484,7 @@ mod test {
             duration: Some(Duration::from_millis(1000)),
             exit_status: Some(exit_status),
             more_info: None,
+            metadata: BTreeMap::new(),
         }
     }
     use std::time::Duration;
@@ -296,6 +591,142 @@ mod test {
         Ok(())
     }
 
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn search_cwd_subtree() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let search_subtree = |cwd: &str| {
+            history.search(SearchQuery {
+                filter: SearchFilter {
+                    cwd_subtree: Some(cwd.to_string()),
+                    ..SearchFilter::anything()
+                },
+                ..SearchQuery::everything(SearchDirection::Forward)
+            })
+        };
+        search_returned(
+            &*history,
+            search_subtree("/home/me/Downloads")?,
+            vec![2, 3, 4, 5, 6, 7],
+        )?;
+        search_returned(&*history, search_subtree("/home/me/Down")?, vec![])?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn search_full_text() -> Result<()> {
+        let mut history = crate::SqliteBackedHistory::in_memory()?;
+        history.save(create_item(1, "/", "git push origin main", 0))?; // 1
+        history.save(create_item(1, "/", "git commit -m 'push it'", 0))?; // 2
+                                                                          // the index covers the entries saved before and after it was created
+        let mut history = history.with_full_text_index()?;
+        history.save(create_item(1, "/", "cargo build --release", 0))?; // 3
+        history.save(create_item(1, "/", "git pushd", 0))?; // 4
+        history.save(create_item(1, "/", "cargo test", 0))?; // 5
+        history.delete(HistoryItemId::new(5))?;
+        let search_full_text = |query: &str| {
+            history.search(SearchQuery {
+                filter: SearchFilter::from_text_search(CommandLineSearch::FullText(
+                    query.to_string(),
+                )),
+                ..SearchQuery::everything(SearchDirection::Forward)
+            })
+        };
+        search_returned(&history, search_full_text("push")?, vec![1, 2])?;
+        search_returned(&history, search_full_text("push*")?, vec![1, 2, 4])?;
+        search_returned(&history, search_full_text("\"git push\"")?, vec![1])?;
+        search_returned(&history, search_full_text("git NOT push")?, vec![4])?;
+        search_returned(&history, search_full_text("cargo")?, vec![3])?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_delete_removes_the_text_from_the_database() -> Result<()> {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("history.sqlite3");
+        {
+            let mut history = crate::SqliteBackedHistory::with_file(path.clone())?;
+            let secret = history.save(HistoryItem::from_command_line("export TOKEN=hunter2"))?;
+            history.save(HistoryItem::from_command_line("ls"))?;
+            history.delete(secret.id.unwrap())?;
+        }
+
+        let contents = std::fs::read(&path).unwrap();
+        assert!(!contents.windows(7).any(|window| window == b"hunter2"));
+        assert!(contents.windows(2).any(|window| window == b"ls"));
+        Ok(())
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn full_text_search_needs_the_index() -> Result<()> {
+        let history = crate::SqliteBackedHistory::in_memory()?;
+        let err = history
+            .search(SearchQuery {
+                filter: SearchFilter::from_text_search(CommandLineSearch::FullText(
+                    "push".to_string(),
+                )),
+                ..SearchQuery::everything(SearchDirection::Forward)
+            })
+            .unwrap_err();
+        assert!(err.is_unsupported_feature());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn search_metadata() -> Result<()> {
+        let mut history = create_filled_example_history()?;
+        for (id, branch) in [(5, "main"), (6, "main"), (7, "fix")] {
+            history.update(HistoryItemId::new(id), &|mut e| {
+                e.metadata
+                    .insert("git_branch".to_string(), branch.to_string());
+                e.metadata.insert("venv".to_string(), "py3".to_string());
+                e
+            })?;
+        }
+        let search_metadata = |pairs: &[(&str, &str)]| {
+            let filter = pairs
+                .iter()
+                .fold(SearchFilter::anything(), |filter, (key, value)| {
+                    filter.with_metadata(*key, *value)
+                });
+            history.search(SearchQuery {
+                filter,
+                ..SearchQuery::everything(SearchDirection::Forward)
+            })
+        };
+        search_returned(
+            &*history,
+            search_metadata(&[("git_branch", "main")])?,
+            vec![5, 6],
+        )?;
+        search_returned(
+            &*history,
+            search_metadata(&[("git_branch", "fix"), ("venv", "py3")])?,
+            vec![7],
+        )?;
+        search_returned(
+            &*history,
+            search_metadata(&[("git_branch", "fix"), ("venv", "py2")])?,
+            vec![],
+        )?;
+        assert_eq!(
+            history
+                .load(HistoryItemId::new(7))?
+                .metadata
+                .get("git_branch"),
+            Some(&"fix".to_string())
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn search_includes() -> Result<()> {
         let history = create_filled_example_history()?;
@@ -309,6 +740,32 @@ mod test {
         Ok(())
     }
 
+    #[cfg(feature = "regex")]
+    #[test]
+    fn search_regex() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let res = history.search(SearchQuery {
+            filter: SearchFilter::from_text_search(CommandLineSearch::Regex(
+                r"^(vim|cat) .*\.conf$".to_string(),
+            )),
+            ..SearchQuery::everything(SearchDirection::Forward)
+        })?;
+        search_returned(&*history, res, vec![10, 12])?;
+        Ok(())
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn search_invalid_regex_is_an_error() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let res = history.search(SearchQuery {
+            filter: SearchFilter::from_text_search(CommandLineSearch::Regex("(vim".to_string())),
+            ..SearchQuery::everything(SearchDirection::Forward)
+        });
+        assert!(res.is_err());
+        Ok(())
+    }
+
     #[test]
     fn search_includes_limit() -> Result<()> {
         let history = create_filled_example_history()?;
@@ -321,4 +778,219 @@ mod test {
 
         Ok(())
     }
+
+    fn create_history_with(dedup_policy: HistoryDedupPolicy) -> Result<Box<dyn History>> {
+        #[cfg(feature = "sqlite")]
+        let history = crate::SqliteBackedHistory::in_memory()?;
+        #[cfg(not(feature = "sqlite"))]
+        let history = crate::FileBackedHistory::default();
+        Ok(Box::new(history.with_dedup_policy(dedup_policy)))
+    }
+
+    fn saved_command_lines(dedup_policy: HistoryDedupPolicy) -> Result<Vec<String>> {
+        let mut history = create_history_with(dedup_policy)?;
+        for command_line in ["ls", "ls", "cd /tmp", "ls"] {
+            history.save(HistoryItem::from_command_line(command_line))?;
+        }
+        let items = history.search(SearchQuery::everything(SearchDirection::Forward))?;
+        Ok(items.into_iter().map(|item| item.command_line).collect())
+    }
+
+    #[test]
+    fn dedup_keep_all() -> Result<()> {
+        assert_eq!(
+            saved_command_lines(HistoryDedupPolicy::KeepAll)?,
+            vec!["ls", "ls", "cd /tmp", "ls"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dedup_ignore_consecutive() -> Result<()> {
+        assert_eq!(
+            saved_command_lines(HistoryDedupPolicy::IgnoreConsecutive)?,
+            vec!["ls", "cd /tmp", "ls"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dedup_keep_latest() -> Result<()> {
+        assert_eq!(
+            saved_command_lines(HistoryDedupPolicy::KeepLatest)?,
+            vec!["cd /tmp", "ls"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn retention_keeps_the_newest_entries_within_the_limits() {
+        let now = Utc::now();
+        let day = chrono::Duration::days(1);
+        let entries = [
+            (10, Some(now)),
+            (10, Some(now - day)),
+            (10, None),
+            (10, Some(now - day * 3)),
+        ];
+        let retained = |policy: HistoryRetentionPolicy| policy.retained(entries);
+
+        assert_eq!(retained(HistoryRetentionPolicy::default()), 4);
+        assert_eq!(
+            retained(HistoryRetentionPolicy {
+                max_entries: Some(2),
+                ..HistoryRetentionPolicy::default()
+            }),
+            2
+        );
+        assert_eq!(
+            retained(HistoryRetentionPolicy {
+                max_size: Some(35),
+                ..HistoryRetentionPolicy::default()
+            }),
+            3
+        );
+        assert_eq!(
+            retained(HistoryRetentionPolicy {
+                max_age: Some(Duration::from_secs(2 * 24 * 60 * 60)),
+                ..HistoryRetentionPolicy::default()
+            }),
+            3
+        );
+    }
+
+    #[test]
+    fn prune_removes_the_oldest_entries() -> Result<()> {
+        let mut history = create_history_with(HistoryDedupPolicy::KeepAll)?;
+        for command_line in ["ls", "cd /tmp", "pwd", "ls"] {
+            history.save(HistoryItem::from_command_line(command_line))?;
+        }
+        let policy = HistoryRetentionPolicy {
+            max_entries: Some(2),
+            ..HistoryRetentionPolicy::default()
+        };
+
+        assert_eq!(history.prune(&policy)?, 2);
+        let items = history.search(SearchQuery::everything(SearchDirection::Forward))?;
+        assert_eq!(
+            items
+                .into_iter()
+                .map(|item| item.command_line)
+                .collect::<Vec<_>>(),
+            vec!["pwd", "ls"]
+        );
+        assert_eq!(history.prune(&policy)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn prune_purges_the_deleted_entries() -> Result<()> {
+        let mut history = create_history_with(HistoryDedupPolicy::KeepAll)?;
+        let secret = history.save(HistoryItem::from_command_line("export TOKEN=secret"))?;
+        history.save(HistoryItem::from_command_line("ls"))?;
+        history.delete(secret.id.unwrap())?;
+
+        assert_eq!(history.prune(&HistoryRetentionPolicy::default())?, 0);
+        assert_eq!(history.restore_last_deleted()?, vec![]);
+        assert_eq!(history.count_all()?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn search_fuzzy_ranks_the_best_matches_first() -> Result<()> {
+        let mut history = create_history_with(HistoryDedupPolicy::KeepAll)?;
+        for command_line in ["cargo build", "git checkout", "cargo bench", "cd build"] {
+            history.save(HistoryItem::from_command_line(command_line))?;
+        }
+        let matches = history.search_fuzzy(
+            "cbu",
+            SearchQuery {
+                limit: Some(2),
+                ..SearchQuery::everything(SearchDirection::Backward)
+            },
+        )?;
+        assert_eq!(
+            matches
+                .iter()
+                .map(|(item, m)| (item.command_line.as_str(), m.positions.clone()))
+                .collect::<Vec<_>>(),
+            vec![("cd build", vec![0, 3, 4]), ("cargo build", vec![0, 6, 7])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn search_frecent_ranks_distinct_command_lines() -> Result<()> {
+        let mut history = create_history_with(HistoryDedupPolicy::KeepAll)?;
+        for command_line in ["make", "ls", "make", "cd /tmp"] {
+            history.save(HistoryItem::from_command_line(command_line))?;
+        }
+
+        let items = history.search_frecent(SearchFilter::anything(), None, None)?;
+        assert_eq!(
+            items
+                .into_iter()
+                .map(|item| item.command_line)
+                .collect::<Vec<_>>(),
+            vec!["make", "cd /tmp", "ls"]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_frecency_matches_the_ranking_of_the_items() -> Result<()> {
+        let mut history = crate::SqliteBackedHistory::in_memory()?;
+        let now = Utc::now();
+        let runs = [
+            ("ls", "/", Some(200)),
+            ("cargo test", "/src", Some(20)),
+            ("ls", "/", None),
+            ("cargo test", "/src", Some(10)),
+            ("git status", "/src", Some(1)),
+            ("ls", "/", Some(0)),
+        ];
+        for (command_line, cwd, days_ago) in runs {
+            history.save(HistoryItem {
+                start_timestamp: days_ago.map(|days| now - chrono::Duration::days(days)),
+                ..create_item(1, cwd, command_line, 0)
+            })?;
+        }
+        let ids =
+            |items: Vec<HistoryItem>| items.into_iter().map(|item| item.id).collect::<Vec<_>>();
+
+        for cwd in [None, Some("/src")] {
+            let items = history.search(SearchQuery::everything(SearchDirection::Backward))?;
+            assert_eq!(
+                ids(history.search_frecent(SearchFilter::anything(), cwd, None)?),
+                ids(rank_by_frecency(items, cwd, now))
+            );
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_stats_match_the_stats_of_the_items() -> Result<()> {
+        use chrono::TimeZone;
+
+        let mut history = crate::SqliteBackedHistory::in_memory()?;
+        let start = Utc.ymd(2022, 3, 14).and_hms(8, 15, 0);
+        for (i, command_line) in ["ls", "cargo test", "ls", "git status", "ls"]
+            .into_iter()
+            .enumerate()
+        {
+            history.save(HistoryItem {
+                start_timestamp: Some(start + chrono::Duration::minutes(50 * i as i64)),
+                ..create_item(1, &format!("/dir{}", i % 2), command_line, 0)
+            })?;
+        }
+        history.save(create_item(1, "/dir1", "cargo build", 0))?;
+        let deleted = history.save(create_item(1, "/dir0", "rm -rf /dir0", 0))?;
+        history.delete(deleted.id.unwrap())?;
+
+        let items = history.search(SearchQuery::everything(SearchDirection::Forward))?;
+        assert_eq!(history.stats()?, HistoryStats::from_items(&items));
+        Ok(())
+    }
 }
@@ -0,0 +1,259 @@
+use super::{
+    base::CommandLineSearch, History, HistoryItem, HistoryItemId, SearchDirection, SearchFilter,
+    SearchQuery,
+};
+use crate::{ReedlineError, Result};
+
+type Check = fn(&mut dyn History) -> Result<()>;
+
+/// Checks that a [`History`] backend behaves as the engine expects, panicking
+/// at the first check that fails
+///
+/// Every check runs against a new, empty history returned by `create`. Checks
+/// of features the history reports as unsupported with
+/// [`crate::ReedlineError::history_feature_unsupported`] are skipped, like
+/// updating entries of the [`crate::FileBackedHistory`].
+///
+/// # Example
+/// ```rust
+/// use reedline::{check_history_conformance, FileBackedHistory};
+///
+/// // In the tests of a custom backend, creating an empty store
+/// check_history_conformance(FileBackedHistory::default);
+/// ```
+pub fn check_history_conformance<H: History>(create: impl Fn() -> H) {
+    let checks: [(&str, Check); 9] = [
+        ("saved items are loaded by their id", saved_items_load_back),
+        ("search follows the direction", search_follows_direction),
+        ("search respects the limit", search_respects_limit),
+        (
+            "search filters the command line",
+            search_filters_command_line,
+        ),
+        (
+            "search starts after the start id",
+            search_starts_after_start_id,
+        ),
+        ("count matches the search", count_matches_search),
+        ("update changes the item", update_changes_item),
+        ("delete removes the item", delete_removes_item),
+        (
+            "restore brings back the deleted items",
+            restore_brings_back_deleted_items,
+        ),
+    ];
+
+    for (name, check) in checks {
+        let mut history = create();
+        if let Err(err) = check(&mut history).and_then(|_| sync(&mut history)) {
+            panic!("history check \"{}\" failed: {}", name, err);
+        }
+    }
+}
+
+fn sync(history: &mut dyn History) -> Result<()> {
+    history.sync().map_err(ReedlineError::history_backend)
+}
+
+/// Saves the command lines in order, returning their ids
+fn fill(history: &mut dyn History, command_lines: &[&str]) -> Result<Vec<HistoryItemId>> {
+    command_lines
+        .iter()
+        .map(|command_line| {
+            let item = history.save(HistoryItem::from_command_line(*command_line))?;
+            Ok(item.id.expect("`save` returns the item with its new id"))
+        })
+        .collect()
+}
+
+fn command_lines(items: Vec<HistoryItem>) -> Vec<String> {
+    items.into_iter().map(|item| item.command_line).collect()
+}
+
+fn search_filter(command_line: CommandLineSearch) -> SearchQuery {
+    SearchQuery {
+        filter: SearchFilter::from_text_search(command_line),
+        ..SearchQuery::everything(SearchDirection::Forward)
+    }
+}
+
+/// Turns an unsupported feature into `None`, to skip its check
+fn supported<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.is_unsupported_feature() => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn saved_items_load_back(history: &mut dyn History) -> Result<()> {
+    let ids = fill(history, &["cd /tmp", "ls -l"])?;
+
+    assert_ne!(ids[0], ids[1], "every saved item gets its own id");
+    assert_eq!(history.load(ids[0])?.command_line, "cd /tmp");
+    assert_eq!(history.load(ids[1])?.command_line, "ls -l");
+    Ok(())
+}
+
+fn search_follows_direction(history: &mut dyn History) -> Result<()> {
+    fill(history, &["cd /tmp", "ls -l", "cat notes.txt"])?;
+
+    assert_eq!(
+        command_lines(history.search(SearchQuery::everything(SearchDirection::Forward))?),
+        vec!["cd /tmp", "ls -l", "cat notes.txt"],
+        "a forward search starts at the oldest entry"
+    );
+    assert_eq!(
+        command_lines(history.search(SearchQuery::everything(SearchDirection::Backward))?),
+        vec!["cat notes.txt", "ls -l", "cd /tmp"],
+        "a backward search starts at the most recent entry"
+    );
+    Ok(())
+}
+
+fn search_respects_limit(history: &mut dyn History) -> Result<()> {
+    fill(history, &["cd /tmp", "ls -l", "cat notes.txt"])?;
+    let query = SearchQuery {
+        limit: Some(2),
+        ..SearchQuery::everything(SearchDirection::Backward)
+    };
+
+    assert_eq!(
+        command_lines(history.search(query)?),
+        vec!["cat notes.txt", "ls -l"]
+    );
+    assert_eq!(
+        command_lines(history.search(SearchQuery::last_with_prefix("cd".to_string()))?),
+        vec!["cd /tmp"]
+    );
+    Ok(())
+}
+
+fn search_filters_command_line(history: &mut dyn History) -> Result<()> {
+    fill(history, &["ls -l", "cat ls", "ls"])?;
+
+    assert_eq!(
+        command_lines(history.search(search_filter(CommandLineSearch::Prefix("ls".to_string())))?),
+        vec!["ls -l", "ls"],
+        "prefix search"
+    );
+    assert_eq!(
+        command_lines(history.search(search_filter(CommandLineSearch::Substring(
+            "ls".to_string()
+        )))?),
+        vec!["ls -l", "cat ls", "ls"],
+        "substring search"
+    );
+    assert_eq!(
+        command_lines(history.search(search_filter(CommandLineSearch::Exact("ls".to_string())))?),
+        vec!["ls"],
+        "exact search"
+    );
+
+    let mut query = SearchQuery::everything(SearchDirection::Forward);
+    query.filter.not_command_line = Some("cat ls".to_string());
+    assert_eq!(
+        command_lines(history.search(query)?),
+        vec!["ls -l", "ls"],
+        "entries equal to `not_command_line` are skipped"
+    );
+    Ok(())
+}
+
+fn search_starts_after_start_id(history: &mut dyn History) -> Result<()> {
+    let ids = fill(history, &["cd /tmp", "ls -l", "cat notes.txt"])?;
+
+    let forward = SearchQuery {
+        start_id: Some(ids[0]),
+        ..SearchQuery::everything(SearchDirection::Forward)
+    };
+    assert_eq!(
+        command_lines(history.search(forward)?),
+        vec!["ls -l", "cat notes.txt"]
+    );
+    let backward = SearchQuery {
+        start_id: Some(ids[2]),
+        ..SearchQuery::everything(SearchDirection::Backward)
+    };
+    assert_eq!(
+        command_lines(history.search(backward)?),
+        vec!["ls -l", "cd /tmp"]
+    );
+    Ok(())
+}
+
+fn count_matches_search(history: &mut dyn History) -> Result<()> {
+    assert_eq!(history.count_all()?, 0, "a new history is empty");
+    fill(history, &["ls -l", "cat ls", "ls"])?;
+
+    assert_eq!(history.count_all()?, 3);
+    assert_eq!(
+        history.count(search_filter(CommandLineSearch::Prefix("ls".to_string())))?,
+        2
+    );
+    Ok(())
+}
+
+fn update_changes_item(history: &mut dyn History) -> Result<()> {
+    let ids = fill(history, &["cd /tmp", "ls -l"])?;
+    let updated = supported(history.update(ids[0], &|mut item| {
+        item.command_line = "cd /var".to_string();
+        item
+    }))?;
+
+    if updated.is_some() {
+        assert_eq!(history.load(ids[0])?.command_line, "cd /var");
+        assert_eq!(history.count_all()?, 2, "updating doesn't add an entry");
+    }
+    Ok(())
+}
+
+fn delete_removes_item(history: &mut dyn History) -> Result<()> {
+    let ids = fill(history, &["cd /tmp", "ls -l", "cat notes.txt"])?;
+
+    if supported(history.delete(ids[1]))?.is_some() {
+        assert_eq!(
+            command_lines(history.search(SearchQuery::everything(SearchDirection::Forward))?),
+            vec!["cd /tmp", "cat notes.txt"]
+        );
+        assert_eq!(history.count_all()?, 2);
+    }
+    Ok(())
+}
+
+fn restore_brings_back_deleted_items(history: &mut dyn History) -> Result<()> {
+    let ids = fill(history, &["cd /tmp", "ls -l", "cat notes.txt", "pwd"])?;
+
+    if supported(history.delete(ids[3]))?.is_none() {
+        return Ok(());
+    }
+    history.delete_items(&[ids[0], ids[2]])?;
+    if let Some(restored) = supported(history.restore_last_deleted())? {
+        assert_eq!(command_lines(restored), vec!["cd /tmp", "cat notes.txt"]);
+        let restored = history.restore_last_deleted()?;
+        assert_eq!(command_lines(restored.clone()), vec!["pwd"]);
+        assert_eq!(history.load(restored[0].id.unwrap())?.command_line, "pwd");
+        assert_eq!(
+            command_lines(history.search(SearchQuery::everything(SearchDirection::Forward))?),
+            vec!["cd /tmp", "ls -l", "cat notes.txt", "pwd"]
+        );
+        assert_eq!(history.restore_last_deleted()?, vec![]);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_backed_history_conforms() {
+        check_history_conformance(crate::FileBackedHistory::default);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_backed_history_conforms() {
+        check_history_conformance(|| crate::SqliteBackedHistory::in_memory().unwrap());
+    }
+}
@@ -1,8 +1,9 @@
-use crate::{History, HistoryNavigationQuery};
+use crate::{History, HistoryNavigationQuery, HistoryRanking, HistoryScope};
 
 use super::base::CommandLineSearch;
 use super::base::SearchDirection;
 use super::base::SearchFilter;
+use super::substring_search;
 use super::HistoryItem;
 use super::SearchQuery;
 use crate::Result;
@@ -13,6 +14,9 @@ pub struct HistoryCursor {
     query: HistoryNavigationQuery,
     current: Option<HistoryItem>,
     skip_dupes: bool,
+    ranking: HistoryRanking,
+    scope: HistoryScope,
+    regex: bool,
 }
 
 impl HistoryCursor {
@@ -21,9 +25,34 @@ impl HistoryCursor {
             query,
             current: None,
             skip_dupes: true,
+            ranking: HistoryRanking::Recency,
+            scope: HistoryScope::Global,
+            regex: false,
         }
     }
 
+    /// A builder that sets the order the entries are navigated in
+    #[must_use]
+    pub fn with_ranking(mut self, ranking: HistoryRanking) -> Self {
+        self.ranking = ranking;
+        self
+    }
+
+    /// A builder that restricts the navigation to the entries of the scope
+    #[must_use]
+    pub fn with_scope(mut self, scope: HistoryScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// A builder that sets whether the text of a
+    /// [`HistoryNavigationQuery::SubstringSearch`] is a regular expression
+    #[must_use]
+    pub fn with_regex(mut self, regex: bool) -> Self {
+        self.regex = regex;
+        self
+    }
+
     /// This moves the cursor backwards respecting the navigation query that is set
     /// - Results in a no-op if the cursor is at the initial point
     pub fn back(&mut self, history: &dyn History) -> Result<()> {
@@ -36,16 +65,21 @@ impl HistoryCursor {
         self.navigate_in_direction(history, SearchDirection::Forward)
     }
 
-    fn get_search_filter(&self) -> SearchFilter {
+    /// `None` if the query matches nothing, see [`substring_search`]
+    fn get_query_filter(&self) -> Option<SearchFilter> {
         let filter = match self.query.clone() {
             HistoryNavigationQuery::Normal(_) => SearchFilter::anything(),
             HistoryNavigationQuery::PrefixSearch(prefix) => {
                 SearchFilter::from_text_search(CommandLineSearch::Prefix(prefix))
             }
             HistoryNavigationQuery::SubstringSearch(substring) => {
-                SearchFilter::from_text_search(CommandLineSearch::Substring(substring))
+                SearchFilter::from_text_search(substring_search(substring, self.regex)?)
             }
         };
+        Some(self.scope.restrict(filter))
+    }
+
+    fn skip_current(&self, filter: SearchFilter) -> SearchFilter {
         if let (true, Some(current)) = (self.skip_dupes, &self.current) {
             SearchFilter {
                 not_command_line: Some(current.command_line.clone()),
@@ -59,11 +93,36 @@ impl HistoryCursor {
         &mut self,
         history: &dyn History,
         direction: SearchDirection,
+    ) -> Result<()> {
+        match self.navigate_in_scope(history, direction) {
+            Err(err) if err.is_unsupported_feature() && self.scope != HistoryScope::Global => {
+                // the history doesn't know where its entries ran, browse all of them
+                self.scope = HistoryScope::Global;
+                self.navigate_in_scope(history, direction)
+            }
+            result => result,
+        }
+    }
+
+    fn navigate_in_scope(
+        &mut self,
+        history: &dyn History,
+        direction: SearchDirection,
     ) -> Result<()> {
         if direction == SearchDirection::Forward && self.current.is_none() {
             // if searching forward but we don't have a starting point, assume we are at the end
             return Ok(());
         }
+        let filter = match self.get_query_filter() {
+            Some(filter) => filter,
+            None => {
+                self.current = None;
+                return Ok(());
+            }
+        };
+        if let HistoryRanking::Frecency { .. } = self.ranking {
+            return self.navigate_ranked(history, direction, filter);
+        }
         let start_id = self.current.as_ref().and_then(|e| e.id);
         let mut next = history.search(SearchQuery {
             start_id,
@@ -72,7 +131,7 @@ impl HistoryCursor {
             end_time: None,
             direction,
             limit: Some(1),
-            filter: self.get_search_filter(),
+            filter: self.skip_current(filter),
         })?;
         if next.len() == 1 {
             self.current = Some(next.swap_remove(0));
@@ -83,6 +142,118 @@ impl HistoryCursor {
         Ok(())
     }
 
+    /// Moves through the distinct command lines ranked by frecency, the
+    /// backward direction going to lower ranks
+    fn navigate_ranked(
+        &mut self,
+        history: &dyn History,
+        direction: SearchDirection,
+        filter: SearchFilter,
+    ) -> Result<()> {
+        let cwd = self.ranking.boosted_cwd();
+        let mut ranked = history.search_frecent(filter, cwd.as_deref(), None)?;
+        let rank = self.current.as_ref().and_then(|current| {
+            ranked
+                .iter()
+                .position(|item| item.command_line == current.command_line)
+        });
+        let next = match (direction, rank) {
+            (SearchDirection::Backward, Some(rank)) => rank + 1,
+            (SearchDirection::Backward, None) => 0,
+            (SearchDirection::Forward, Some(rank)) if rank > 0 => rank - 1,
+            (SearchDirection::Forward, _) => {
+                // going forward from the top rank: we are at the end
+                self.current = None;
+                return Ok(());
+            }
+        };
+        if next < ranked.len() {
+            self.current = Some(ranked.swap_remove(next));
+        }
+        Ok(())
+    }
+
+    /// The command lines around the cursor as the navigation passes them, the
+    /// most recent first, with the index of the one at the cursor
+    ///
+    /// Shows the `rows` most recent ones if the cursor is not at an entry, and
+    /// otherwise scrolls to keep the entry at the cursor in view.
+    pub(crate) fn window(
+        &self,
+        history: &dyn History,
+        rows: usize,
+    ) -> Result<(Vec<String>, Option<usize>)> {
+        let filter = match self.get_query_filter() {
+            Some(filter) if rows > 0 => filter,
+            _ => return Ok((vec![], None)),
+        };
+        let current = match &self.current {
+            Some(current) => current,
+            None => {
+                let newest =
+                    self.distinct_from(history, &filter, None, SearchDirection::Backward, rows)?;
+                return Ok((newest, None));
+            }
+        };
+        let mut newer = self.distinct_from(
+            history,
+            &filter,
+            Some(current),
+            SearchDirection::Forward,
+            rows - 1,
+        )?;
+        let older = self.distinct_from(
+            history,
+            &filter,
+            Some(current),
+            SearchDirection::Backward,
+            rows - 1 - newer.len(),
+        )?;
+        newer.reverse();
+        let selected = newer.len();
+        newer.push(current.command_line.clone());
+        newer.extend(older);
+        Ok((newer, Some(selected)))
+    }
+
+    /// Up to `count` command lines in the direction from the entry, skipping
+    /// the ones equal to the command line before them like the navigation
+    fn distinct_from(
+        &self,
+        history: &dyn History,
+        filter: &SearchFilter,
+        from: Option<&HistoryItem>,
+        direction: SearchDirection,
+        count: usize,
+    ) -> Result<Vec<String>> {
+        let mut command_lines: Vec<String> = vec![];
+        let mut previous = from.map(|item| item.command_line.clone());
+        let mut start_id = from.and_then(|item| item.id);
+        while command_lines.len() < count {
+            let batch = history.search(SearchQuery {
+                start_id,
+                end_id: None,
+                start_time: None,
+                end_time: None,
+                direction,
+                limit: Some(count as i64),
+                filter: filter.clone(),
+            })?;
+            start_id = match batch.last() {
+                Some(last) => last.id,
+                None => break,
+            };
+            for item in batch {
+                if previous.as_ref() != Some(&item.command_line) {
+                    previous = Some(item.command_line.clone());
+                    command_lines.push(item.command_line);
+                }
+            }
+        }
+        command_lines.truncate(count);
+        Ok(command_lines)
+    }
+
     /// Returns the string (if present) at the cursor
     pub fn string_at_cursor(&self) -> Option<String> {
         self.current.as_ref().map(|e| e.command_line.to_string())
@@ -188,6 +359,39 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn frecency_navigates_by_rank() -> Result<()> {
+        let (mut hist, cursor) = create_history();
+        add_text_entries(hist.as_mut(), &["make", "ls", "make", "cd /tmp"]);
+        let mut cursor = cursor.with_ranking(HistoryRanking::Frecency { boost_cwd: false });
+
+        cursor.back(&*hist)?;
+        assert_eq!(cursor.string_at_cursor(), Some("make".to_string()));
+        cursor.back(&*hist)?;
+        assert_eq!(cursor.string_at_cursor(), Some("cd /tmp".to_string()));
+        cursor.back(&*hist)?;
+        cursor.back(&*hist)?;
+        assert_eq!(cursor.string_at_cursor(), Some("ls".to_string()));
+        cursor.forward(&*hist)?;
+        assert_eq!(cursor.string_at_cursor(), Some("cd /tmp".to_string()));
+        cursor.forward(&*hist)?;
+        cursor.forward(&*hist)?;
+        assert_eq!(cursor.string_at_cursor(), None);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    #[test]
+    fn scope_falls_back_to_all_entries_without_working_directories() -> Result<()> {
+        let (mut hist, cursor) = create_history();
+        add_text_entries(hist.as_mut(), &["command1", "command2"]);
+        let mut cursor = cursor.with_scope(HistoryScope::Directory);
+
+        cursor.back(&*hist)?;
+        assert_eq!(cursor.string_at_cursor(), Some("command2".to_string()));
+        Ok(())
+    }
+
     #[cfg(not(feature = "sqlite"))]
     #[test]
     fn appends_only_unique() -> Result<()> {
@@ -346,6 +550,78 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex_search_works() -> Result<()> {
+        let (mut hist, _) = create_history();
+        hist.save(HistoryItem::from_command_line("git push"))?;
+        hist.save(HistoryItem::from_command_line("git pull"))?;
+        hist.save(HistoryItem::from_command_line("git status"))?;
+
+        let mut cursor = HistoryCursor::new(HistoryNavigationQuery::SubstringSearch(
+            "pu(sh|ll)$".to_string(),
+        ))
+        .with_regex(true);
+        cursor.back(&*hist)?;
+        assert_eq!(cursor.string_at_cursor(), Some("git pull".to_string()));
+        cursor.back(&*hist)?;
+        assert_eq!(cursor.string_at_cursor(), Some("git push".to_string()));
+
+        // the pattern is still being typed
+        let mut cursor =
+            HistoryCursor::new(HistoryNavigationQuery::SubstringSearch("pu(sh".to_string()))
+                .with_regex(true);
+        cursor.back(&*hist)?;
+        assert_eq!(cursor.string_at_cursor(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn window_scrolls_to_the_cursor() -> Result<()> {
+        let (mut hist, _) = create_history();
+        let entries = ["make a", "ls", "make b", "make b", "make c", "make d"];
+        add_text_entries(hist.as_mut(), &entries);
+
+        let mut cursor =
+            HistoryCursor::new(HistoryNavigationQuery::SubstringSearch("make".to_string()));
+        assert_eq!(
+            cursor.window(&*hist, 2)?,
+            (vec!["make d".to_string(), "make c".to_string()], None)
+        );
+        cursor.back(&*hist)?;
+        assert_eq!(
+            cursor.window(&*hist, 2)?,
+            (vec!["make d".to_string(), "make c".to_string()], Some(0))
+        );
+        cursor.back(&*hist)?;
+        cursor.back(&*hist)?;
+        // the repeated `make b` is passed once
+        assert_eq!(
+            cursor.window(&*hist, 3)?,
+            (
+                vec![
+                    "make d".to_string(),
+                    "make c".to_string(),
+                    "make b".to_string()
+                ],
+                Some(2)
+            )
+        );
+        cursor.back(&*hist)?;
+        assert_eq!(
+            cursor.window(&*hist, 3)?,
+            (
+                vec![
+                    "make c".to_string(),
+                    "make b".to_string(),
+                    "make a".to_string()
+                ],
+                Some(2)
+            )
+        );
+        Ok(())
+    }
+
     #[test]
     fn substring_search_with_empty_value_returns_none() -> Result<()> {
         let (mut hist, _) = create_history();
@@ -587,4 +863,394 @@ mod tests {
         tmp.close().unwrap();
         Ok(())
     }
+
+    fn create_shared_history_at(cap: usize, path: &Path) -> Box<dyn History> {
+        Box::new(
+            FileBackedHistory::with_file(cap, path.to_owned())
+                .unwrap()
+                .with_sync_policy(HistorySyncPolicy::Shared),
+        )
+    }
+
+    #[test]
+    fn incremental_history_writes_each_entry() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let mut writing_hist = FileBackedHistory::with_file(5, histfile.clone())
+            .unwrap()
+            .with_sync_policy(HistorySyncPolicy::Incremental);
+        add_text_entries(&mut writing_hist, &["A1", "A2"]);
+
+        let (reading_hist, _) = create_history_at(5, &histfile);
+        assert_eq!(get_all_entry_texts(reading_hist.as_ref()), vec!["A1", "A2"]);
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn shared_histories_see_entries_of_each_other() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let mut hist_a = create_shared_history_at(5, &histfile);
+        let mut hist_b = create_shared_history_at(5, &histfile);
+        add_text_entries(hist_a.as_mut(), &["A1"]);
+        add_text_entries(hist_b.as_mut(), &["B1"]);
+        add_text_entries(hist_a.as_mut(), &["A2"]);
+
+        hist_a.refresh()?;
+        hist_b.refresh()?;
+        assert_eq!(get_all_entry_texts(hist_a.as_ref()), vec!["A1", "B1", "A2"]);
+        assert_eq!(get_all_entry_texts(hist_b.as_ref()), vec!["A1", "B1", "A2"]);
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn shared_history_reads_rewritten_file_again() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let (mut writing_hist, _) = create_history_at(3, &histfile);
+            add_text_entries(writing_hist.as_mut(), &["1", "2", "3"]);
+        }
+        let mut hist_a = create_shared_history_at(3, &histfile);
+        let mut hist_b = create_shared_history_at(3, &histfile);
+        // Truncates the file to its capacity, keeping its length
+        add_text_entries(hist_b.as_mut(), &["4"]);
+
+        hist_a.refresh()?;
+        assert_eq!(get_all_entry_texts(hist_a.as_ref()), vec!["2", "3", "4"]);
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn keep_latest_drops_older_duplicates_from_file() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let (mut writing_hist, _) = create_history_at(5, &histfile);
+            add_text_entries(writing_hist.as_mut(), &["ls", "cd /tmp", "ls", "pwd"]);
+        }
+        {
+            let mut hist = FileBackedHistory::with_file(5, histfile.clone())
+                .unwrap()
+                .with_dedup_policy(HistoryDedupPolicy::KeepLatest);
+            assert_eq!(get_all_entry_texts(&hist), vec!["cd /tmp", "ls", "pwd"]);
+
+            add_text_entries(&mut hist, &["cd /tmp"]);
+        }
+
+        let (reading_hist, _) = create_history_at(5, &histfile);
+        assert_eq!(
+            get_all_entry_texts(reading_hist.as_ref()),
+            vec!["ls", "pwd", "cd /tmp"]
+        );
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn prune_rewrites_the_file() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let (mut hist, _) = create_history_at(5, &histfile);
+        add_text_entries(hist.as_mut(), &["A1", "A2", "A3", "A4"]);
+        let policy = HistoryRetentionPolicy {
+            max_entries: Some(3),
+            // "A3\n" and "A4\n"
+            max_size: Some(6),
+            ..HistoryRetentionPolicy::default()
+        };
+
+        assert_eq!(hist.prune(&policy)?, 2);
+        assert_eq!(get_all_entry_texts(hist.as_ref()), vec!["A3", "A4"]);
+        assert_eq!(std::fs::read_to_string(&histfile).unwrap(), "A3\nA4\n");
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn delete_removes_the_entry_from_the_file() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let (mut writing_hist, _) = create_history_at(5, &histfile);
+            add_text_entries(writing_hist.as_mut(), &["ls", "export TOKEN=secret", "pwd"]);
+        }
+        let (mut hist, _) = create_history_at(5, &histfile);
+        add_text_entries(hist.as_mut(), &["cd /tmp"]);
+
+        hist.delete(HistoryItemId::new(1))?;
+        assert_eq!(std::fs::read_to_string(&histfile).unwrap(), "ls\npwd\n");
+        hist.delete(HistoryItemId::new(2))?;
+        assert_eq!(get_all_entry_texts(hist.as_ref()), vec!["ls", "pwd"]);
+        drop(hist);
+
+        let (reading_hist, _) = create_history_at(5, &histfile);
+        assert_eq!(
+            get_all_entry_texts(reading_hist.as_ref()),
+            vec!["ls", "pwd"]
+        );
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn restore_writes_the_entry_back_to_the_file() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let (mut hist, _) = create_history_at(5, &histfile);
+        add_text_entries(hist.as_mut(), &["ls", "rm -rf build", "pwd"]);
+        hist.sync().unwrap();
+        hist.delete(HistoryItemId::new(1))?;
+        assert_eq!(std::fs::read_to_string(&histfile).unwrap(), "ls\npwd\n");
+
+        let restored = hist.restore_last_deleted()?;
+        assert_eq!(restored[0].id, Some(HistoryItemId::new(1)));
+        assert_eq!(
+            std::fs::read_to_string(&histfile).unwrap(),
+            "ls\nrm -rf build\npwd\n"
+        );
+        assert_eq!(
+            get_all_entry_texts(hist.as_ref()),
+            vec!["ls", "rm -rf build", "pwd"]
+        );
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn restore_brings_back_the_removed_repetitions_together() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let (mut hist, _) = create_history_at(5, &histfile);
+        add_text_entries(
+            hist.as_mut(),
+            &["rm -rf build", "ls", "rm -rf build", "pwd"],
+        );
+        hist.sync().unwrap();
+        hist.delete_items(&[HistoryItemId::new(2), HistoryItemId::new(0)])?;
+        assert_eq!(std::fs::read_to_string(&histfile).unwrap(), "ls\npwd\n");
+
+        let restored = hist.restore_last_deleted()?;
+        assert_eq!(
+            restored.iter().map(|item| item.id).collect::<Vec<_>>(),
+            vec![Some(HistoryItemId::new(0)), Some(HistoryItemId::new(2))]
+        );
+        assert_eq!(
+            std::fs::read_to_string(&histfile).unwrap(),
+            "rm -rf build\nls\nrm -rf build\npwd\n"
+        );
+        assert_eq!(
+            get_all_entry_texts(hist.as_ref()),
+            vec!["rm -rf build", "ls", "rm -rf build", "pwd"]
+        );
+        assert_eq!(hist.restore_last_deleted()?, vec![]);
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn encrypted_file_hides_the_entries() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        let open = |key| FileBackedHistory::with_encrypted_file(5, histfile.clone(), key);
+
+        {
+            let mut hist = open([1; 32]).unwrap();
+            add_text_entries(&mut hist, &["ls", "export TOKEN=secret"]);
+        }
+        {
+            let mut hist = open([1; 32]).unwrap();
+            add_text_entries(&mut hist, &["pwd"]);
+        }
+        let content = std::fs::read_to_string(&histfile).unwrap();
+        assert_eq!(content.lines().count(), 3);
+        assert!(!content.contains("secret"));
+
+        let hist = open([1; 32]).unwrap();
+        assert_eq!(
+            get_all_entry_texts(&hist),
+            vec!["ls", "export TOKEN=secret", "pwd"]
+        );
+        drop(hist);
+        assert_eq!(
+            open([2; 32]).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn journal_file_keeps_multiline_entries() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        let open = || {
+            FileBackedHistory::with_file_format(5, histfile.clone(), HistoryFileFormat::Journal)
+                .unwrap()
+        };
+
+        {
+            let mut hist = open();
+            add_text_entries(&mut hist, &["ls", "for x in a b\ndo echo $x\ndone"]);
+        }
+        {
+            let mut hist = open();
+            add_text_entries(&mut hist, &["pwd"]);
+        }
+
+        let hist = open();
+        assert_eq!(
+            get_all_entry_texts(&hist),
+            vec!["ls", "for x in a b\ndo echo $x\ndone", "pwd"]
+        );
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn journal_file_recovers_from_a_cut_off_record() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        let open = || {
+            FileBackedHistory::with_file_format(5, histfile.clone(), HistoryFileFormat::Journal)
+                .unwrap()
+        };
+
+        {
+            let mut hist = open();
+            add_text_entries(&mut hist, &["ls", "cargo build --release"]);
+        }
+        // a crash while writing the last record
+        let len = std::fs::metadata(&histfile).unwrap().len();
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&histfile)
+            .unwrap()
+            .set_len(len - 3)
+            .unwrap();
+
+        {
+            let mut hist = open();
+            assert_eq!(get_all_entry_texts(&hist), vec!["ls"]);
+            add_text_entries(&mut hist, &["pwd"]);
+        }
+        let hist = open();
+        assert_eq!(get_all_entry_texts(&hist), vec!["ls", "pwd"]);
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn journal_file_keeps_the_start_times_when_rewritten() -> Result<()> {
+        use chrono::{TimeZone, Utc};
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        let open = || {
+            FileBackedHistory::with_file_format(5, histfile.clone(), HistoryFileFormat::Journal)
+                .unwrap()
+        };
+        let at = |seconds| Utc.timestamp_opt(seconds, 0).unwrap();
+
+        {
+            let mut hist = open();
+            for (cmd, seconds) in [("ls", 1000), ("rm -rf build", 2000), ("pwd", 3000)] {
+                let mut item = HistoryItem::from_command_line(cmd);
+                item.start_timestamp = Some(at(seconds));
+                hist.save(item)?;
+            }
+            hist.sync().unwrap();
+            hist.delete(HistoryItemId::new(1))?;
+        }
+
+        let hist = open();
+        let start_times = hist
+            .search(SearchQuery::everything(SearchDirection::Forward))?
+            .into_iter()
+            .map(|item| item.start_timestamp)
+            .collect::<Vec<_>>();
+        assert_eq!(start_times, vec![Some(at(1000)), Some(at(3000))]);
+
+        let mut query = SearchQuery::everything(SearchDirection::Forward);
+        query.start_time = Some(at(1500));
+        let found = hist
+            .search(query)?
+            .into_iter()
+            .map(|item| item.command_line)
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec!["pwd"]);
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn retention_policy_prunes_on_drop() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let mut hist = FileBackedHistory::with_file(5, histfile.clone())
+                .unwrap()
+                .with_retention_policy(HistoryRetentionPolicy {
+                    max_entries: Some(2),
+                    ..HistoryRetentionPolicy::default()
+                });
+            add_text_entries(&mut hist, &["A1", "A2", "A3"]);
+        }
+
+        let (reading_hist, _) = create_history_at(5, &histfile);
+        assert_eq!(get_all_entry_texts(reading_hist.as_ref()), vec!["A2", "A3"]);
+
+        tmp.close().unwrap();
+        Ok(())
+    }
 }
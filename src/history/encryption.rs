@@ -0,0 +1,80 @@
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Nonce,
+};
+use std::io;
+
+const NONCE_LEN: usize = 12;
+
+/// Encrypts the lines of a history file one by one, so that other sessions can
+/// still append to it
+pub(crate) struct EntryCipher(ChaCha20Poly1305);
+
+impl std::fmt::Debug for EntryCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EntryCipher")
+    }
+}
+
+impl EntryCipher {
+    pub(crate) fn new(key: &[u8; 32]) -> Self {
+        Self(ChaCha20Poly1305::new(key.into()))
+    }
+
+    /// The line in base64, with the random nonce in front of the ciphertext
+    pub(crate) fn encrypt(&self, line: &str) -> String {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, line.as_bytes())
+            .expect("the entry is short enough to be encrypted");
+        let mut encrypted = nonce.to_vec();
+        encrypted.extend(ciphertext);
+        base64::encode(encrypted)
+    }
+
+    /// Fails if the line wasn't encrypted with the same key
+    pub(crate) fn decrypt(&self, line: &str) -> io::Result<String> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "history entry not encrypted with the given key",
+            )
+        };
+        let encrypted = base64::decode(line).map_err(|_| invalid())?;
+        if encrypted.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = encrypted.split_at(NONCE_LEN);
+        let plaintext = self
+            .0
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid())?;
+        String::from_utf8(plaintext).map_err(|_| invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn decrypts_what_it_encrypts() {
+        let cipher = EntryCipher::new(&[7; 32]);
+        let encrypted = cipher.encrypt("export TOKEN=secret");
+
+        assert!(!encrypted.contains("secret"));
+        assert_ne!(encrypted, cipher.encrypt("export TOKEN=secret"));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "export TOKEN=secret");
+    }
+
+    #[test]
+    fn rejects_other_keys_and_plaintext() {
+        let encrypted = EntryCipher::new(&[7; 32]).encrypt("ls");
+        let cipher = EntryCipher::new(&[8; 32]);
+
+        assert!(cipher.decrypt(&encrypted).is_err());
+        assert!(cipher.decrypt("ls -l").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+/// Called with every submitted command line, returning `true` keeps it out of
+/// the history, see [`crate::Reedline::with_history_exclusion_filter`]
+pub type HistoryExclusionFilter = Box<dyn Fn(&str) -> bool + Send>;
+
+/// Whether the whole `text` matches the shell `pattern`
+///
+/// `*` matches any text, `?` any character and `[...]` one of the characters
+/// or ranges in the brackets, `[!...]` any other character. A backslash
+/// matches the character following it literally.
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+
+    // The position after the last `*` and the text it matches up to, to
+    // backtrack to when the rest of the pattern doesn't match
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(1),
+            Some('[') => match match_class(&pattern[p..], text[t]) {
+                Some((len, matched)) => matched.then_some(len),
+                None => (text[t] == '[').then_some(1),
+            },
+            Some('\\') if p + 1 < pattern.len() => (pattern[p + 1] == text[t]).then_some(2),
+            Some(&c) => (c == text[t]).then_some(1),
+            None => None,
+        };
+        match (step, star) {
+            (Some(step), _) => {
+                p += step;
+                t += 1;
+            }
+            (None, Some((star_p, star_t))) => {
+                p = star_p;
+                t = star_t + 1;
+                star = Some((star_p, star_t + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Length of the class at the start of `pattern` and whether it matches `c`,
+/// `None` if the bracket isn't closed and is matched literally
+fn match_class(pattern: &[char], c: char) -> Option<(usize, bool)> {
+    let negated = matches!(pattern.get(1), Some('!' | '^'));
+    let start = if negated { 2 } else { 1 };
+    // A `]` right after the opening bracket is part of the class
+    let end = start + 1 + pattern.get(start + 1..)?.iter().position(|&c| c == ']')?;
+    let class = &pattern[start..end];
+
+    let mut matched = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            matched |= (class[i]..=class[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            matched |= class[i] == c;
+            i += 1;
+        }
+    }
+
+    Some((end + 1, matched != negated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("ls", "ls", true)]
+    #[case("ls", "ls -l", false)]
+    #[case("ls *", "ls -l", true)]
+    #[case("*", "", true)]
+    #[case("git * --force", "git push origin --force", true)]
+    #[case("git * --force", "git push --force-with-lease", false)]
+    #[case("?g", "bg", true)]
+    #[case("[bf]g", "fg", true)]
+    #[case("[!bf]g", "fg", false)]
+    #[case("cd [a-c]*", "cd build", true)]
+    #[case("cd [a-c]*", "cd src", false)]
+    #[case("echo \\*", "echo *", true)]
+    #[case("echo \\*", "echo a", false)]
+    #[case("[", "[", true)]
+    #[case("a[b", "a[b", true)]
+    fn test_glob_matches(#[case] pattern: &str, #[case] text: &str, #[case] expected: bool) {
+        assert_eq!(glob_matches(pattern, text), expected);
+    }
+}
@@ -0,0 +1,117 @@
+use super::{file_backed::encode_entry, HistoryItem};
+#[cfg(feature = "json")]
+use serde_json::json;
+use std::io::{self, Write};
+
+/// Format of the items written by [`crate::History::export`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryExportFormat {
+    /// One command line per line, as in the file of a
+    /// [`crate::FileBackedHistory`]
+    PlainText,
+    /// One JSON object per line with all the fields of the item, the duration
+    /// in milliseconds and the start as an RFC 3339 timestamp (requires the
+    /// `json` feature)
+    #[cfg(feature = "json")]
+    Json,
+}
+
+/// Writes the `items` in the given `format`
+pub(crate) fn write_items(
+    items: &[HistoryItem],
+    format: HistoryExportFormat,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    for item in items {
+        match format {
+            HistoryExportFormat::PlainText => {
+                writeln!(out, "{}", encode_entry(&item.command_line))?;
+            }
+            #[cfg(feature = "json")]
+            HistoryExportFormat::Json => {
+                let object = json!({
+                    "id": item.id.map(|id| id.get()),
+                    "start_timestamp": item.start_timestamp.map(|start| start.to_rfc3339()),
+                    "command_line": item.command_line,
+                    "session_id": item.session_id.map(|id| id.get()),
+                    "hostname": item.hostname,
+                    "cwd": item.cwd,
+                    "duration_ms": item.duration.map(|duration| duration.as_millis() as u64),
+                    "exit_status": item.exit_status,
+                    "metadata": item.metadata,
+                });
+                writeln!(out, "{}", object)?;
+            }
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{History, SearchQuery};
+    use pretty_assertions::assert_eq;
+
+    fn exported(items: &[HistoryItem], format: HistoryExportFormat) -> String {
+        let mut out = Vec::new();
+        write_items(items, format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn plain_text_escapes_newlines() {
+        let items = [
+            HistoryItem::from_command_line("ls -l"),
+            HistoryItem::from_command_line("echo a\necho b"),
+        ];
+
+        assert_eq!(
+            exported(&items, HistoryExportFormat::PlainText),
+            "ls -l\necho a<\\n>echo b\n"
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_writes_an_object_per_line() {
+        use crate::HistoryItemId;
+        use chrono::{TimeZone, Utc};
+        use std::time::Duration;
+
+        let mut item = HistoryItem::from_command_line("sleep 1");
+        item.id = Some(HistoryItemId::new(3));
+        item.start_timestamp = Utc.timestamp_opt(1609459200, 0).single();
+        item.duration = Some(Duration::from_millis(1005));
+        item.exit_status = Some(0);
+
+        assert_eq!(
+            exported(&[item], HistoryExportFormat::Json),
+            concat!(
+                r#"{"command_line":"sleep 1","cwd":null,"duration_ms":1005,"exit_status":0,"#,
+                r#""hostname":null,"id":3,"metadata":{},"session_id":null,"#,
+                r#""start_timestamp":"2021-01-01T00:00:00+00:00"}"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn export_writes_the_matching_items() -> crate::Result<()> {
+        let mut history = crate::FileBackedHistory::default();
+        for command_line in ["cd /tmp", "ls -l", "ls"] {
+            history.save(HistoryItem::from_command_line(command_line))?;
+        }
+        let mut out = Vec::new();
+
+        let count = history.export(
+            SearchQuery::all_that_contain_rev("ls".to_string()),
+            HistoryExportFormat::PlainText,
+            &mut out,
+        )?;
+
+        assert_eq!(count, 2);
+        assert_eq!(String::from_utf8(out).unwrap(), "ls\nls -l\n");
+        Ok(())
+    }
+}
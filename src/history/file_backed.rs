@@ -1,17 +1,22 @@
 use super::{
-    base::CommandLineSearch, History, HistoryItem, HistoryItemId, HistorySessionId,
-    SearchDirection, SearchQuery,
+    base::CommandLineSearch, fuzzy::fuzzy_match, isolated::in_bounds, History, HistoryDedupPolicy,
+    HistoryItem, HistoryItemId, HistoryRetentionPolicy, HistorySessionId, SearchDirection,
+    SearchQuery,
 };
 use crate::{
     result::{ReedlineError, ReedlineErrorVariants},
     Result,
 };
 
+#[cfg(feature = "encryption")]
+use super::encryption::EntryCipher;
+use super::journal::{encode_record, read_records, JOURNAL_HEADER};
+use chrono::{DateTime, TimeZone, Utc};
 use std::{
-    collections::VecDeque,
-    fs::OpenOptions,
-    io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
-    ops::{Deref, DerefMut},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    ops::DerefMut,
     path::PathBuf,
 };
 
@@ -22,15 +27,82 @@ pub const NEWLINE_ESCAPE: &str = "<\\n>";
 /// Stateful history that allows up/down-arrow browsing with an internal cursor.
 ///
 /// Can optionally be associated with a newline separated history file using the [`FileBackedHistory::with_file()`] constructor.
+/// A journal of checksummed records is more robust, see [`FileBackedHistory::with_file_format()`].
 /// Similar to bash's behavior without HISTTIMEFORMAT.
 /// (See <https://www.gnu.org/software/bash/manual/html_node/Bash-History-Facilities.html>)
 /// If the history is associated to a file all new changes within a given history capacity will be written to disk when History is dropped.
+/// Sessions sharing the file can exchange their entries earlier, see [`HistorySyncPolicy`].
 #[derive(Debug)]
 pub struct FileBackedHistory {
     capacity: usize,
-    entries: VecDeque<String>,
+    entries: VecDeque<Entry>,
     file: Option<PathBuf>,
+    format: HistoryFileFormat,
     len_on_disk: usize, // Keep track what was previously written to disk
+    bytes_on_disk: u64, // Length of the file after the last sync, to only read what was appended since
+    sync_policy: HistorySyncPolicy,
+    dedup_policy: HistoryDedupPolicy,
+    retention_policy: Option<HistoryRetentionPolicy>,
+    deleted: Vec<Vec<DeletedEntry>>, // Removed in this session, for `restore_last_deleted`
+    #[cfg(feature = "encryption")]
+    cipher: Option<EntryCipher>,
+}
+
+/// A command line of the history, with its start time in the journal format
+#[derive(Debug, Clone)]
+struct Entry {
+    command_line: String,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+impl Entry {
+    fn new(command_line: String) -> Self {
+        Entry {
+            command_line,
+            timestamp: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DeletedEntry {
+    index: usize,
+    entry: Entry,
+    // Whether it was removed from the file as well
+    on_disk: bool,
+}
+
+/// How a [`FileBackedHistory`] stores its entries in the history file, see
+/// [`FileBackedHistory::with_file_format`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFileFormat {
+    /// One entry per line, with the newlines of the entries escaped (default)
+    #[default]
+    PlainText,
+    /// A journal of binary records, each with the length of the entry, its
+    /// start time and a checksum
+    ///
+    /// Entries keep their newlines as they are and can be searched by their
+    /// start time. A record cut off by a crash while writing is detected and
+    /// dropped, the file is truncated to the last complete record the next
+    /// time it is read. A corrupted record in between is skipped.
+    Journal,
+}
+
+/// When a [`FileBackedHistory`] exchanges its entries with the other sessions
+/// using the same file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HistorySyncPolicy {
+    /// Write the entries of the session when the history is dropped or synced
+    /// explicitly (default)
+    #[default]
+    OnExit,
+    /// Append each entry to the file as soon as it is saved, so new sessions
+    /// start with it
+    Incremental,
+    /// Append each entry as soon as it is saved and read the entries of the
+    /// other sessions before navigating the history, like `share_history` of zsh
+    Shared,
 }
 
 impl Default for FileBackedHistory {
@@ -42,7 +114,7 @@ impl Default for FileBackedHistory {
     }
 }
 
-fn encode_entry(s: &str) -> String {
+pub(crate) fn encode_entry(s: &str) -> String {
     s.replace('\n', NEWLINE_ESCAPE)
 }
 
@@ -50,24 +122,65 @@ fn decode_entry(s: &str) -> String {
     s.replace(NEWLINE_ESCAPE, "\n")
 }
 
+/// Drops the entries repeated later on or in `newer`, keeping the order of the others
+fn drop_older_duplicates<'a>(
+    entries: VecDeque<Entry>,
+    newer: impl Iterator<Item = &'a Entry>,
+) -> VecDeque<Entry> {
+    let mut seen = newer
+        .map(|entry| entry.command_line.as_str())
+        .collect::<HashSet<_>>();
+    let mut kept = entries
+        .iter()
+        .rev()
+        .filter(|entry| seen.insert(&entry.command_line))
+        .cloned()
+        .collect::<VecDeque<_>>();
+    kept.make_contiguous().reverse();
+    kept
+}
+
 impl History for FileBackedHistory {
-    /// only saves a value if it's different than the last value
+    /// only saves a value if it's not empty and the [`HistoryDedupPolicy`] allows it
     fn save(&mut self, h: HistoryItem) -> Result<HistoryItem> {
-        let entry = h.command_line;
-        // Don't append if the preceding value is identical or the string empty
-        let entry_id = if self
-            .entries
-            .back()
-            .map_or(true, |previous| previous != &entry)
-            && !entry.is_empty()
-        {
+        let entry = Entry {
+            command_line: h.command_line,
+            timestamp: match self.format {
+                HistoryFileFormat::PlainText => None,
+                HistoryFileFormat::Journal => Some(h.start_timestamp.unwrap_or_else(Utc::now)),
+            },
+        };
+        let is_new = !entry.command_line.is_empty()
+            && (self.dedup_policy == HistoryDedupPolicy::KeepAll
+                || self.entries.back().map(|last| &last.command_line) != Some(&entry.command_line));
+        let entry_id = if is_new {
+            if self.dedup_policy == HistoryDedupPolicy::KeepLatest {
+                // The entry moves to the end instead of being repeated
+                let on_disk = self
+                    .entries
+                    .range(..self.len_on_disk)
+                    .filter(|previous| previous.command_line == entry.command_line)
+                    .count();
+                if on_disk > 0 {
+                    self.len_on_disk -= on_disk;
+                    self.bytes_on_disk = 0;
+                }
+                self.entries
+                    .retain(|previous| previous.command_line != entry.command_line);
+            }
             if self.entries.len() == self.capacity {
                 // History is "full", so we delete the oldest entry first,
                 // before adding a new one.
                 self.entries.pop_front();
                 self.len_on_disk = self.len_on_disk.saturating_sub(1);
+                // The entries no longer start like the file, read it again on sync
+                self.bytes_on_disk = 0;
+            }
+            self.entries.push_back(entry.clone());
+            if self.sync_policy != HistorySyncPolicy::OnExit {
+                // If the file can't be written now, the entry is written by the next sync
+                let _ignore = self.sync();
             }
-            self.entries.push_back(entry.to_string());
             Some(HistoryItemId::new((self.entries.len() - 1) as i64))
         } else {
             None
@@ -88,7 +201,9 @@ impl History for FileBackedHistory {
     }
 
     fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
-        if query.start_time.is_some() || query.end_time.is_some() {
+        if (query.start_time.is_some() || query.end_time.is_some())
+            && self.format == HistoryFileFormat::PlainText
+        {
             return Err(ReedlineError(
                 ReedlineErrorVariants::HistoryFeatureUnsupported {
                     history: "FileBackedHistory",
@@ -100,7 +215,9 @@ impl History for FileBackedHistory {
         if query.filter.hostname.is_some()
             || query.filter.cwd_exact.is_some()
             || query.filter.cwd_prefix.is_some()
+            || query.filter.cwd_subtree.is_some()
             || query.filter.exit_successful.is_some()
+            || !query.filter.metadata.is_empty()
         {
             return Err(ReedlineError(
                 ReedlineErrorVariants::HistoryFeatureUnsupported {
@@ -109,6 +226,14 @@ impl History for FileBackedHistory {
                 },
             ));
         }
+        if let Some(CommandLineSearch::FullText(_)) = query.filter.command_line {
+            return Err(ReedlineError::history_feature_unsupported(
+                "FileBackedHistory",
+                "full-text search",
+            ));
+        }
+        #[cfg(feature = "regex")]
+        let regex = super::base::compile_regex(query.filter.command_line.as_ref())?;
         let (min_id, max_id) = {
             let start = query.start_id.map(|e| e.0);
             let end = query.end_id.map(|e| e.0);
@@ -133,11 +258,40 @@ impl History for FileBackedHistory {
         } else {
             intrinsic_limit as usize
         };
-        let filter = |(idx, cmd): (usize, &String)| {
+        // Older duplicates read from the file before they were dropped
+        let latest = (self.dedup_policy == HistoryDedupPolicy::KeepLatest).then(|| {
+            self.entries
+                .iter()
+                .enumerate()
+                .map(|(idx, entry)| (&entry.command_line, idx))
+                .collect::<HashMap<_, _>>()
+        });
+        let filter = |(idx, entry): (usize, &Entry)| {
+            let cmd = &entry.command_line;
+            if let Some(latest) = &latest {
+                if latest[cmd] != idx {
+                    return None;
+                }
+            }
+            if !in_bounds(
+                entry.timestamp,
+                query.start_time,
+                query.end_time,
+                query.direction,
+            ) {
+                return None;
+            }
             if !match &query.filter.command_line {
                 Some(CommandLineSearch::Prefix(p)) => cmd.starts_with(p),
                 Some(CommandLineSearch::Substring(p)) => cmd.contains(p),
                 Some(CommandLineSearch::Exact(p)) => cmd == p,
+                // rejected above
+                Some(CommandLineSearch::FullText(_)) => false,
+                Some(CommandLineSearch::Fuzzy(p)) => fuzzy_match(p, cmd).is_some(),
+                #[cfg(feature = "regex")]
+                Some(CommandLineSearch::Regex(_)) => {
+                    regex.as_ref().is_some_and(|r| r.is_match(cmd))
+                }
                 None => true,
             } {
                 return None;
@@ -149,7 +303,7 @@ impl History for FileBackedHistory {
             }
             Some(FileBackedHistory::construct_entry(
                 Some(HistoryItemId::new(idx as i64)),
-                cmd.to_string(), // todo: this copy might be a perf bottleneck
+                entry.clone(), // todo: this copy might be a perf bottleneck
             ))
         };
 
@@ -179,80 +333,158 @@ impl History for FileBackedHistory {
         ))
     }
 
-    fn delete(&mut self, _h: super::HistoryItemId) -> Result<()> {
-        Err(ReedlineError(
-            ReedlineErrorVariants::HistoryFeatureUnsupported {
-                history: "FileBackedHistory",
-                feature: "removing entries",
-            },
-        ))
+    /// Removes the entry from the history file as well, if it was written to it
+    ///
+    /// The entry is kept in memory for [`History::restore_last_deleted`]
+    /// until the end of the session.
+    fn delete(&mut self, h: super::HistoryItemId) -> Result<()> {
+        self.delete_items(&[h])
     }
 
-    /// Writes unwritten history contents to disk.
-    ///
-    /// If file would exceed `capacity` truncates the oldest entries.
-    fn sync(&mut self) -> std::io::Result<()> {
-        if let Some(fname) = &self.file {
-            // The unwritten entries
-            let own_entries = self.entries.range(self.len_on_disk..);
+    /// Removes the entries from the history file as well in one rewrite,
+    /// nothing if one of them is missing
+    fn delete_items(&mut self, ids: &[HistoryItemId]) -> Result<()> {
+        let mut indices = ids.iter().map(|id| id.0 as usize).collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices.dedup();
+        if indices
+            .last()
+            .is_some_and(|last| *last >= self.entries.len())
+        {
+            return Err(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                "Could not find item",
+            )));
+        }
 
-            if let Some(base_dir) = fname.parent() {
-                std::fs::create_dir_all(base_dir)?;
-            }
+        // The last entries first, removing them doesn't move the earlier ones
+        let mut group = indices
+            .into_iter()
+            .rev()
+            .filter_map(|index| {
+                let entry = self.entries.remove(index)?;
+                let on_disk = index < self.len_on_disk;
+                if on_disk {
+                    self.len_on_disk -= 1;
+                }
+                Some(DeletedEntry {
+                    index,
+                    entry,
+                    on_disk,
+                })
+            })
+            .collect::<Vec<_>>();
+        group.reverse();
+        let removed = group
+            .iter()
+            .filter(|deleted| deleted.on_disk)
+            .map(|deleted| (deleted.index, deleted.entry.command_line.clone()))
+            .collect::<Vec<_>>();
+        if !group.is_empty() {
+            self.deleted.push(group);
+        }
+        if removed.is_empty() {
+            return Ok(());
+        }
 
-            let mut f_lock = fd_lock::RwLock::new(
-                OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .read(true)
-                    .open(fname)?,
-            );
-            let mut writer_guard = f_lock.write()?;
-            let (mut foreign_entries, truncate) = {
-                let reader = BufReader::new(writer_guard.deref());
-                let mut from_file = reader
-                    .lines()
-                    .map(|o| o.map(|i| decode_entry(&i)))
-                    .collect::<std::io::Result<VecDeque<_>>>()?;
-                if from_file.len() + own_entries.len() > self.capacity {
-                    (
-                        from_file.split_off(from_file.len() - (self.capacity - own_entries.len())),
-                        true,
-                    )
+        self.edit_file(|from_file| {
+            for (index, command_line) in removed.into_iter().rev() {
+                // Other sessions may have rewritten the file since it was read
+                let position = if from_file
+                    .get(index)
+                    .is_some_and(|line| line.command_line == command_line)
+                {
+                    Some(index)
                 } else {
-                    (from_file, false)
+                    from_file
+                        .iter()
+                        .rposition(|line| line.command_line == command_line)
+                };
+                if let Some(position) = position {
+                    from_file.remove(position);
                 }
+            }
+        })
+    }
+
+    fn restore_last_deleted(&mut self) -> Result<Vec<HistoryItem>> {
+        let group = match self.deleted.pop() {
+            Some(group) => group,
+            None => return Ok(vec![]),
+        };
+        // The first entries first, so that the later ones return to their place
+        let mut restored = vec![];
+        let mut to_file = vec![];
+        for deleted in group {
+            let index = if deleted.on_disk {
+                to_file.push((deleted.index, deleted.entry.clone()));
+                let index = deleted.index.min(self.len_on_disk);
+                self.len_on_disk += 1;
+                index
+            } else {
+                deleted.index.max(self.len_on_disk).min(self.entries.len())
             };
+            self.entries.insert(index, deleted.entry.clone());
+            restored.push(FileBackedHistory::construct_entry(
+                Some(HistoryItemId::new(index as i64)),
+                deleted.entry,
+            ));
+        }
+        if !to_file.is_empty() {
+            self.edit_file(|from_file| {
+                for (index, entry) in to_file {
+                    from_file.insert(index.min(from_file.len()), entry);
+                }
+            })?;
+        }
+        Ok(restored)
+    }
 
-            {
-                let mut writer = BufWriter::new(writer_guard.deref_mut());
-                if truncate {
-                    writer.seek(SeekFrom::Start(0))?;
+    /// Writes unwritten history contents to disk.
+    ///
+    /// If file would exceed `capacity` truncates the oldest entries.
+    fn sync(&mut self) -> std::io::Result<()> {
+        match self.open_file()? {
+            Some(mut f_lock) => self.sync_file(f_lock.write()?.deref_mut()),
+            None => Ok(()),
+        }
+    }
 
-                    for line in &foreign_entries {
-                        writer.write_all(encode_entry(line).as_bytes())?;
-                        writer.write_all("\n".as_bytes())?;
-                    }
-                } else {
-                    writer.seek(SeekFrom::End(0))?;
-                }
-                for line in own_entries {
-                    writer.write_all(encode_entry(line).as_bytes())?;
-                    writer.write_all("\n".as_bytes())?;
-                }
-                writer.flush()?;
-            }
-            if truncate {
-                let file = writer_guard.deref_mut();
-                let file_len = file.stream_position()?;
-                file.set_len(file_len)?;
-            }
+    /// Merges the entries of the other sessions using the file before removing
+    /// the oldest ones, the `max_age` of the policy only applies to the entries
+    /// of a journal as the plain text entries have no start time
+    fn prune(&mut self, policy: &HistoryRetentionPolicy) -> Result<usize> {
+        self.deleted.clear();
+        let mut f_lock = self.open_file().map_err(ReedlineError::history_backend)?;
+        let mut writer_guard = match &mut f_lock {
+            Some(f_lock) => Some(f_lock.write().map_err(ReedlineError::history_backend)?),
+            None => None,
+        };
+        if let Some(file) = writer_guard.as_deref_mut() {
+            self.sync_file(file)
+                .map_err(ReedlineError::history_backend)?;
+        }
+
+        let kept = policy.retained(
+            self.entries
+                .iter()
+                .rev()
+                .map(|entry| (self.encode_record(entry).len() as u64, entry.timestamp)),
+        );
+        let removed = self.entries.len() - kept;
+        self.entries.drain(..removed);
+        self.len_on_disk = self.len_on_disk.saturating_sub(removed);
 
-            let own_entries = self.entries.drain(self.len_on_disk..);
-            foreign_entries.extend(own_entries);
-            self.entries = foreign_entries;
+        if let (Some(file), true) = (writer_guard.as_deref_mut(), removed > 0) {
+            self.bytes_on_disk = self
+                .rewrite_entries(file, &self.entries)
+                .map_err(ReedlineError::history_backend)?;
+        }
+        Ok(removed)
+    }
 
-            self.len_on_disk = self.entries.len();
+    fn refresh(&mut self) -> Result<()> {
+        if self.sync_policy == HistorySyncPolicy::Shared {
+            self.sync().map_err(ReedlineError::history_backend)?;
         }
         Ok(())
     }
@@ -277,7 +509,15 @@ impl FileBackedHistory {
             capacity,
             entries: VecDeque::new(),
             file: None,
+            format: HistoryFileFormat::PlainText,
             len_on_disk: 0,
+            bytes_on_disk: 0,
+            sync_policy: HistorySyncPolicy::OnExit,
+            dedup_policy: HistoryDedupPolicy::IgnoreConsecutive,
+            retention_policy: None,
+            deleted: vec![],
+            #[cfg(feature = "encryption")]
+            cipher: None,
         }
     }
 
@@ -290,34 +530,315 @@ impl FileBackedHistory {
     /// **Side effects:** creates all nested directories to the file
     ///
     pub fn with_file(capacity: usize, file: PathBuf) -> std::io::Result<Self> {
+        Self::new(capacity).attach_file(file)
+    }
+
+    /// Creates a new history with an associated history file in the given
+    /// format, see [`HistoryFileFormat`]
+    ///
+    /// An existing file has to be in the same format, reading another file as
+    /// a journal fails with [`std::io::ErrorKind::InvalidData`].
+    ///
+    /// **Side effects:** creates all nested directories to the file
+    pub fn with_file_format(
+        capacity: usize,
+        file: PathBuf,
+        format: HistoryFileFormat,
+    ) -> std::io::Result<Self> {
         let mut hist = Self::new(capacity);
+        hist.format = format;
+        hist.attach_file(file)
+    }
+
+    /// Creates a new history with an associated history file whose entries
+    /// are encrypted with `key`, so that they aren't stored in plaintext
+    ///
+    /// Every line of the file holds an entry encrypted with ChaCha20-Poly1305.
+    /// The file has to be empty or encrypted with the same key, otherwise
+    /// reading it fails with [`std::io::ErrorKind::InvalidData`]. The key is
+    /// used as is, derive it from a password with a key derivation function.
+    ///
+    /// **Side effects:** creates all nested directories to the file
+    #[cfg(feature = "encryption")]
+    pub fn with_encrypted_file(
+        capacity: usize,
+        file: PathBuf,
+        key: [u8; 32],
+    ) -> std::io::Result<Self> {
+        let mut hist = Self::new(capacity);
+        hist.cipher = Some(EntryCipher::new(&key));
+        hist.attach_file(file)
+    }
+
+    fn attach_file(mut self, file: PathBuf) -> std::io::Result<Self> {
         if let Some(base_dir) = file.parent() {
             std::fs::create_dir_all(base_dir)?;
         }
-        hist.file = Some(file);
-        hist.sync()?;
-        Ok(hist)
+        self.file = Some(file);
+        self.sync()?;
+        Ok(self)
+    }
+
+    /// A builder that sets when the entries are exchanged with the other
+    /// sessions using the history file
+    #[must_use]
+    pub fn with_sync_policy(mut self, sync_policy: HistorySyncPolicy) -> Self {
+        self.sync_policy = sync_policy;
+        self
+    }
+
+    /// A builder that sets which repeated entries are kept, by default
+    /// [`HistoryDedupPolicy::IgnoreConsecutive`]
+    #[must_use]
+    pub fn with_dedup_policy(mut self, dedup_policy: HistoryDedupPolicy) -> Self {
+        self.dedup_policy = dedup_policy;
+        self
+    }
+
+    /// A builder that sets the limits applied by [`History::prune`] when the
+    /// history is dropped
+    #[must_use]
+    pub fn with_retention_policy(mut self, retention_policy: HistoryRetentionPolicy) -> Self {
+        self.retention_policy = Some(retention_policy);
+        self
+    }
+
+    /// Opens the history file and its lock, creating them if they don't exist
+    fn open_file(&self) -> std::io::Result<Option<fd_lock::RwLock<File>>> {
+        match &self.file {
+            Some(fname) => {
+                if let Some(base_dir) = fname.parent() {
+                    std::fs::create_dir_all(base_dir)?;
+                }
+                let file = OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .read(true)
+                    .open(fname)?;
+                Ok(Some(fd_lock::RwLock::new(file)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The line of the entry in the history file
+    fn encode_line(&self, entry: &str) -> String {
+        let line = encode_entry(entry);
+        #[cfg(feature = "encryption")]
+        if let Some(cipher) = &self.cipher {
+            return cipher.encrypt(&line);
+        }
+        line
     }
 
-    // this history doesn't store any info except command line
-    fn construct_entry(id: Option<HistoryItemId>, command_line: String) -> HistoryItem {
+    fn decode_line(&self, line: &str) -> std::io::Result<String> {
+        #[cfg(feature = "encryption")]
+        if let Some(cipher) = &self.cipher {
+            return cipher.decrypt(line).map(|line| decode_entry(&line));
+        }
+        Ok(decode_entry(line))
+    }
+
+    /// The bytes of the entry in the history file
+    fn encode_record(&self, entry: &Entry) -> Vec<u8> {
+        match self.format {
+            HistoryFileFormat::PlainText => {
+                format!("{}\n", self.encode_line(&entry.command_line)).into_bytes()
+            }
+            HistoryFileFormat::Journal => encode_record(
+                &entry.command_line,
+                entry
+                    .timestamp
+                    .map_or(0, |timestamp| timestamp.timestamp_millis()),
+            ),
+        }
+    }
+
+    /// The start of every history file
+    fn file_header(&self) -> &'static [u8] {
+        match self.format {
+            HistoryFileFormat::PlainText => b"",
+            HistoryFileFormat::Journal => JOURNAL_HEADER,
+        }
+    }
+
+    /// Reads the entries of the locked `file` from `offset` on
+    ///
+    /// A journal is truncated to its last complete record.
+    fn read_entries(&self, file: &mut File, offset: u64) -> std::io::Result<VecDeque<Entry>> {
+        file.seek(SeekFrom::Start(offset))?;
+        match self.format {
+            HistoryFileFormat::PlainText => BufReader::new(file)
+                .lines()
+                .map(|line| self.decode_line(&line?).map(Entry::new))
+                .collect(),
+            HistoryFileFormat::Journal => {
+                let (records, valid_len) = read_records(&mut BufReader::new(&mut *file), offset)?;
+                if file.seek(SeekFrom::End(0))? > valid_len {
+                    file.set_len(valid_len)?;
+                }
+                Ok(records
+                    .into_iter()
+                    .map(|(command_line, timestamp)| Entry {
+                        command_line,
+                        timestamp: Utc.timestamp_millis_opt(timestamp).single(),
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Replaces the content of `file` with the entries, returning its new length
+    fn rewrite_entries(&self, file: &mut File, entries: &VecDeque<Entry>) -> std::io::Result<u64> {
+        {
+            let mut writer = BufWriter::new(&mut *file);
+            writer.seek(SeekFrom::Start(0))?;
+            writer.write_all(self.file_header())?;
+            for line in entries {
+                writer.write_all(&self.encode_record(line))?;
+            }
+            writer.flush()?;
+        }
+        let file_len = file.stream_position()?;
+        file.set_len(file_len)?;
+        Ok(file_len)
+    }
+
+    /// Changes the entries of the history file while it is locked
+    fn edit_file(&mut self, edit: impl FnOnce(&mut VecDeque<Entry>)) -> Result<()> {
+        let mut f_lock = self.open_file().map_err(ReedlineError::history_backend)?;
+        if let Some(f_lock) = &mut f_lock {
+            let mut writer_guard = f_lock.write().map_err(ReedlineError::history_backend)?;
+            let file = writer_guard.deref_mut();
+            let mut from_file = self
+                .read_entries(file, 0)
+                .map_err(ReedlineError::history_backend)?;
+            edit(&mut from_file);
+            self.rewrite_entries(file, &from_file)
+                .map_err(ReedlineError::history_backend)?;
+            // The entries no longer start like the file, read it again on sync
+            self.bytes_on_disk = 0;
+        }
+        Ok(())
+    }
+
+    /// Writes the unwritten entries to the locked `file`, after the entries
+    /// other sessions appended to it
+    fn sync_file(&mut self, file: &mut File) -> std::io::Result<()> {
+        // The unwritten entries
+        let own_entries = self.entries.range(self.len_on_disk..);
+
+        let (mut foreign_entries, truncate) = {
+            let mut from_file = match self.appended_entries(file)? {
+                Some(appended) => {
+                    let mut from_file = self
+                        .entries
+                        .range(..self.len_on_disk)
+                        .cloned()
+                        .collect::<VecDeque<_>>();
+                    from_file.extend(appended);
+                    from_file
+                }
+                None => self.read_entries(file, 0)?,
+            };
+            let mut rewrite = false;
+            if self.dedup_policy == HistoryDedupPolicy::KeepLatest {
+                let len = from_file.len();
+                from_file = drop_older_duplicates(from_file, own_entries.clone());
+                rewrite = from_file.len() != len;
+            }
+            if from_file.len() + own_entries.len() > self.capacity {
+                (
+                    from_file.split_off(from_file.len() - (self.capacity - own_entries.len())),
+                    true,
+                )
+            } else {
+                (from_file, rewrite)
+            }
+        };
+
+        {
+            let mut writer = BufWriter::new(&mut *file);
+            if truncate {
+                writer.seek(SeekFrom::Start(0))?;
+                writer.write_all(self.file_header())?;
+
+                for line in &foreign_entries {
+                    writer.write_all(&self.encode_record(line))?;
+                }
+            } else if writer.seek(SeekFrom::End(0))? == 0 {
+                writer.write_all(self.file_header())?;
+            }
+            for line in own_entries {
+                writer.write_all(&self.encode_record(line))?;
+            }
+            writer.flush()?;
+        }
+        let file_len = file.stream_position()?;
+        if truncate {
+            file.set_len(file_len)?;
+        }
+
+        let own_entries = self.entries.drain(self.len_on_disk..);
+        foreign_entries.extend(own_entries);
+        self.entries = foreign_entries;
+
+        self.len_on_disk = self.entries.len();
+        self.bytes_on_disk = file_len;
+        Ok(())
+    }
+
+    /// The entries other sessions appended to the file since the last sync,
+    /// `None` if the file was rewritten in the meantime and has to be read again
+    ///
+    /// Encrypted entries never encode the same twice, so their file is always
+    /// read again.
+    fn appended_entries(&self, file: &mut File) -> std::io::Result<Option<VecDeque<Entry>>> {
+        let last_synced = match self.len_on_disk.checked_sub(1) {
+            Some(last) if self.bytes_on_disk > 0 => self.encode_record(&self.entries[last]),
+            _ => return Ok(None),
+        };
+        let file_len = file.seek(SeekFrom::End(0))?;
+        let tail_start = match self.bytes_on_disk.checked_sub(last_synced.len() as u64) {
+            Some(tail_start) if file_len >= self.bytes_on_disk => tail_start,
+            _ => return Ok(None),
+        };
+
+        // The file still ends the way it did, with the last entry of the last sync
+        let mut tail = vec![0; last_synced.len()];
+        file.seek(SeekFrom::Start(tail_start))?;
+        file.read_exact(&mut tail)?;
+        if tail != last_synced {
+            return Ok(None);
+        }
+
+        self.read_entries(file, self.bytes_on_disk).map(Some)
+    }
+
+    // this history doesn't store any info except command line and, in a journal, the start time
+    fn construct_entry(id: Option<HistoryItemId>, entry: Entry) -> HistoryItem {
         HistoryItem {
             id,
-            start_timestamp: None,
-            command_line,
+            start_timestamp: entry.timestamp,
+            command_line: entry.command_line,
             session_id: None,
             hostname: None,
             cwd: None,
             duration: None,
             exit_status: None,
             more_info: None,
+            metadata: BTreeMap::new(),
         }
     }
 }
 
 impl Drop for FileBackedHistory {
     /// On drop the content of the [`History`] will be written to the file if specified via [`FileBackedHistory::with_file()`].
+    /// With a [`FileBackedHistory::with_retention_policy()`] the oldest entries are pruned as well.
     fn drop(&mut self) {
-        let _res = self.sync();
+        let _res = match self.retention_policy {
+            Some(policy) => self.prune(&policy).map(|_| ()),
+            None => self.sync().map_err(ReedlineError::history_backend),
+        };
     }
 }
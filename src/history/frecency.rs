@@ -0,0 +1,162 @@
+use super::HistoryItem;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Order of the candidates of the history navigation and the history menu,
+/// see [`crate::Reedline::with_history_ranking`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryRanking {
+    /// The most recent entry first (default)
+    #[default]
+    Recency,
+    /// The command lines run the most often and the most recently first, each
+    /// offered once, see [`crate::History::search_frecent`]
+    Frecency {
+        /// Whether runs in the current working directory count more
+        boost_cwd: bool,
+    },
+}
+
+impl HistoryRanking {
+    /// The directory whose runs count more, the current working directory if
+    /// `boost_cwd` is set
+    pub(crate) fn boosted_cwd(self) -> Option<String> {
+        match self {
+            Self::Frecency { boost_cwd: true } => std::env::current_dir()
+                .ok()
+                .map(|cwd| cwd.to_string_lossy().to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Weight of a run by its age, as the number of days it is at most old and
+/// the weight it gets then
+pub(crate) const AGE_WEIGHTS: [(i64, f64); 4] = [(4, 100.0), (14, 70.0), (31, 50.0), (90, 30.0)];
+/// Weight of a run without a start time, by how many of the runs searched are
+/// more recent at most, as the order of the entries is all that is known
+pub(crate) const POSITION_WEIGHTS: [(usize, f64); 4] =
+    [(10, 100.0), (50, 70.0), (200, 50.0), (1000, 30.0)];
+/// Weight of the runs older than the limits of the weights above
+pub(crate) const OLD_WEIGHT: f64 = 10.0;
+/// Factor of the weight of runs in the current working directory
+pub(crate) const CWD_BOOST: f64 = 2.0;
+
+fn weight_by_age(start: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    let days = (now - start).num_days();
+    AGE_WEIGHTS
+        .iter()
+        .find(|(max_days, _)| days <= *max_days)
+        .map_or(OLD_WEIGHT, |(_, weight)| *weight)
+}
+
+fn weight_by_position(position: usize) -> f64 {
+    POSITION_WEIGHTS
+        .iter()
+        .find(|(max_position, _)| position < *max_position)
+        .map_or(OLD_WEIGHT, |(_, weight)| *weight)
+}
+
+/// Orders the distinct command lines of the items, given most recent first,
+/// by the sum of the weights of their runs, each as its most recent item
+pub(crate) fn rank_by_frecency(
+    newest_first: Vec<HistoryItem>,
+    cwd: Option<&str>,
+    now: DateTime<Utc>,
+) -> Vec<HistoryItem> {
+    let mut ranked: Vec<(f64, HistoryItem)> = vec![];
+    let mut ranks: HashMap<String, usize> = HashMap::new();
+    for (position, item) in newest_first.into_iter().enumerate() {
+        let mut weight = match item.start_timestamp {
+            Some(start) => weight_by_age(start, now),
+            None => weight_by_position(position),
+        };
+        if cwd.is_some() && item.cwd.as_deref() == cwd {
+            weight *= CWD_BOOST;
+        }
+        match ranks.get(&item.command_line) {
+            Some(&rank) => ranked[rank].0 += weight,
+            None => {
+                ranks.insert(item.command_line.clone(), ranked.len());
+                ranked.push((weight, item));
+            }
+        }
+    }
+    // The sort is stable, command lines with the same score stay most recent first
+    ranked.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    ranked.into_iter().map(|(_, item)| item).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use pretty_assertions::assert_eq;
+
+    fn item(command_line: &str, days_ago: Option<i64>, cwd: &str) -> HistoryItem {
+        HistoryItem {
+            start_timestamp: days_ago.map(|days| Utc::now() - Duration::days(days)),
+            cwd: Some(cwd.to_string()),
+            ..HistoryItem::from_command_line(command_line)
+        }
+    }
+
+    fn command_lines(items: Vec<HistoryItem>) -> Vec<String> {
+        items.into_iter().map(|item| item.command_line).collect()
+    }
+
+    #[test]
+    fn frequent_command_lines_come_first() {
+        let items = vec![
+            item("ls", Some(0), "/"),
+            item("make", Some(1), "/"),
+            item("make", Some(2), "/"),
+            item("cd /tmp", Some(3), "/"),
+        ];
+
+        assert_eq!(
+            command_lines(rank_by_frecency(items, None, Utc::now())),
+            vec!["make", "ls", "cd /tmp"]
+        );
+    }
+
+    #[test]
+    fn old_runs_count_less() {
+        let items = vec![
+            item("ls", Some(0), "/"),
+            item("make", Some(100), "/"),
+            item("make", Some(200), "/"),
+        ];
+
+        assert_eq!(
+            command_lines(rank_by_frecency(items, None, Utc::now())),
+            vec!["ls", "make"]
+        );
+    }
+
+    #[test]
+    fn runs_in_the_current_directory_are_boosted() {
+        let items = vec![
+            item("ls", Some(0), "/"),
+            item("cargo test", Some(0), "/src/reedline"),
+        ];
+
+        assert_eq!(
+            command_lines(rank_by_frecency(items, Some("/src/reedline"), Utc::now())),
+            vec!["cargo test", "ls"]
+        );
+    }
+
+    #[test]
+    fn position_stands_in_for_missing_start_times() {
+        let mut items = vec![HistoryItem::from_command_line("ls")];
+        items.extend((0..20).map(|_| HistoryItem::from_command_line("make")));
+        items.extend((0..5).map(|_| HistoryItem::from_command_line("cd /tmp")));
+
+        // The older runs of `cd /tmp` outweigh the single most recent run of `ls`
+        assert_eq!(
+            command_lines(rank_by_frecency(items, None, Utc::now())),
+            vec!["make", "cd /tmp", "ls"]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+/// How a command line matches a [`crate::CommandLineSearch::Fuzzy`] pattern,
+/// see [`crate::History::search_fuzzy`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// How well the command line matches, higher is better
+    pub score: i64,
+    /// Byte offsets of the matched characters in the command line, e.g. to
+    /// style them with [`crate::StyledText::restyle_range`]
+    pub positions: Vec<usize>,
+}
+
+const MATCH_SCORE: i64 = 16;
+/// Extra score of a match continuing the match of the previous character
+const CONSECUTIVE_BONUS: i64 = 4;
+/// Penalty of a gap between two matched characters, growing by
+/// `GAP_EXTENSION_PENALTY` for every further character skipped
+const GAP_PENALTY: i64 = 3;
+const GAP_EXTENSION_PENALTY: i64 = 1;
+
+/// Extra score of a match at the start of a word
+fn boundary_bonus(prev: Option<char>, c: char) -> i64 {
+    match prev {
+        None => 10,
+        Some(prev) if prev.is_whitespace() => 10,
+        Some('/' | '-' | '_' | '.' | ':' | '=' | ',' | ';') => 9,
+        Some(prev) if prev.is_lowercase() && c.is_uppercase() => 7,
+        _ => 0,
+    }
+}
+
+/// Matches the characters of `pattern` in order anywhere in `text`, like fzf
+///
+/// The match is case insensitive unless the pattern contains an uppercase
+/// character. Among the ways the pattern matches the best scored one is
+/// returned, favoring runs of consecutive characters and starts of words.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let pattern = pattern.chars().map(fold).collect::<Vec<_>>();
+    let text = text.char_indices().collect::<Vec<_>>();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: vec![],
+        });
+    }
+    if pattern.len() > text.len() {
+        return None;
+    }
+
+    let bonus = text
+        .iter()
+        .enumerate()
+        .map(|(j, &(_, c))| boundary_bonus(j.checked_sub(1).map(|k| text[k].1), c))
+        .collect::<Vec<_>>();
+    // Best score of the pattern up to `i` with its character `i` matched at
+    // `j`, and where the character before it was matched
+    let mut scores: Vec<Vec<Option<i64>>> = vec![vec![None; text.len()]; pattern.len()];
+    let mut from: Vec<Vec<usize>> = vec![vec![0; text.len()]; pattern.len()];
+    for (i, &p) in pattern.iter().enumerate() {
+        // Best chain of the previous row ending before `j - 1`, with the
+        // penalty of the gap up to `j`
+        let mut gapped: Option<(i64, usize)> = None;
+        for (j, &(_, c)) in text.iter().enumerate() {
+            let prev = if i == 0 {
+                Some((0, 0))
+            } else {
+                let consecutive = j
+                    .checked_sub(1)
+                    .and_then(|k| scores[i - 1][k].map(|score| (score + CONSECUTIVE_BONUS, k)));
+                [consecutive, gapped]
+                    .into_iter()
+                    .flatten()
+                    .max_by_key(|(score, _)| *score)
+            };
+            if fold(c) == p {
+                if let Some((score, k)) = prev {
+                    scores[i][j] = Some(score + MATCH_SCORE + bonus[j]);
+                    from[i][j] = k;
+                }
+            }
+            if i > 0 {
+                let decayed = gapped.map(|(score, k)| (score - GAP_EXTENSION_PENALTY, k));
+                let opened = j
+                    .checked_sub(1)
+                    .and_then(|k| scores[i - 1][k].map(|score| (score - GAP_PENALTY, k)));
+                gapped = [decayed, opened]
+                    .into_iter()
+                    .flatten()
+                    .max_by_key(|(score, _)| *score);
+            }
+        }
+    }
+
+    let last = pattern.len() - 1;
+    let (mut j, score) = scores[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|score| (j, score)))
+        // the first of the best scored ends
+        .max_by_key(|&(j, score)| (score, std::cmp::Reverse(j)))?;
+    let mut positions = vec![0; pattern.len()];
+    for i in (0..pattern.len()).rev() {
+        positions[i] = text[j].0;
+        j = from[i][j];
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("gco", "git checkout", Some(vec![0, 4, 9]))]
+    #[case("gco", "git commit", Some(vec![0, 4, 5]))]
+    #[case("abc", "acb", None)]
+    #[case("", "ls", Some(vec![]))]
+    #[case("ls", "", None)]
+    #[case("build", "cargo build --release", Some(vec![6, 7, 8, 9, 10]))]
+    #[case("CB", "cargo build", None)]
+    #[case("cB", "cargoBuild", Some(vec![0, 5]))]
+    #[case("ä", "Ä ä", Some(vec![0]))]
+    fn test_fuzzy_match_positions(
+        #[case] pattern: &str,
+        #[case] text: &str,
+        #[case] expected: Option<Vec<usize>>,
+    ) {
+        assert_eq!(fuzzy_match(pattern, text).map(|m| m.positions), expected);
+    }
+
+    #[rstest]
+    #[case("cb", "cargo build", "cab")]
+    #[case("gst", "git status", "gist")]
+    #[case("make", "make test", "mark the end")]
+    fn test_fuzzy_match_prefers(#[case] pattern: &str, #[case] better: &str, #[case] worse: &str) {
+        let score = |text| fuzzy_match(pattern, text).unwrap().score;
+        assert!(score(better) > score(worse));
+    }
+}
@@ -0,0 +1,271 @@
+use super::{History, HistoryItem};
+use crate::{
+    result::{ReedlineError, ReedlineErrorVariants},
+    Result,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Reads the history file of another shell, to migrate its entries into a
+/// reedline [`History`]
+///
+/// # Example
+/// ```rust,no_run
+/// use reedline::{FileBackedHistory, HistoryImporter};
+///
+/// let mut history = FileBackedHistory::with_file(1000, "history.txt".into()).unwrap();
+/// if let Some(path) = HistoryImporter::Zsh.default_path() {
+///     let imported = HistoryImporter::Zsh.import(&path, &mut history).unwrap();
+///     println!("imported {} entries", imported);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryImporter {
+    /// `~/.bash_history`, with the `#<seconds>` lines written when
+    /// `HISTTIMEFORMAT` is set
+    Bash,
+    /// `~/.zsh_history`, in the plain or the extended format keeping the start
+    /// and duration of each command
+    Zsh,
+    /// `fish_history`, with the start of each command
+    Fish,
+}
+
+impl HistoryImporter {
+    /// Where the shell keeps its history by default
+    pub fn default_path(self) -> Option<PathBuf> {
+        let home = env::var_os("HOME").map(PathBuf::from);
+        match self {
+            Self::Bash => home.map(|home| home.join(".bash_history")),
+            Self::Zsh => home.map(|home| home.join(".zsh_history")),
+            Self::Fish => env::var_os("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .or_else(|| home.map(|home| home.join(".local").join("share")))
+                .map(|data| data.join("fish").join("fish_history")),
+        }
+    }
+
+    /// Reads the entries of the history file at `path`, oldest first
+    pub fn read(self, path: &Path) -> std::io::Result<Vec<HistoryItem>> {
+        let bytes = std::fs::read(path)?;
+        let bytes = match self {
+            Self::Zsh => unmetafy(&bytes),
+            Self::Bash | Self::Fish => bytes,
+        };
+        Ok(self.parse(&String::from_utf8_lossy(&bytes)))
+    }
+
+    /// Parses the `contents` of a history file, oldest entry first
+    pub fn parse(self, contents: &str) -> Vec<HistoryItem> {
+        match self {
+            Self::Bash => parse_bash(contents),
+            Self::Zsh => parse_zsh(contents),
+            Self::Fish => parse_fish(contents),
+        }
+    }
+
+    /// Saves the entries of the history file at `path` to `history`,
+    /// returning how many were read
+    pub fn import(self, path: &Path, history: &mut dyn History) -> Result<usize> {
+        let items = self.read(path).map_err(|err| {
+            ReedlineError(ReedlineErrorVariants::HistoryImportError(err.to_string()))
+        })?;
+        let count = items.len();
+        for item in items {
+            history.save(item)?;
+        }
+        history.sync().map_err(ReedlineError::history_backend)?;
+        Ok(count)
+    }
+}
+
+fn timestamp(seconds: &str) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(seconds.trim().parse().ok()?, 0).single()
+}
+
+fn parse_bash(contents: &str) -> Vec<HistoryItem> {
+    let mut items = vec![];
+    let mut start_timestamp = None;
+    for line in contents.lines() {
+        match line.strip_prefix('#').and_then(timestamp) {
+            Some(time) => start_timestamp = Some(time),
+            None if line.trim().is_empty() => {}
+            None => {
+                let mut item = HistoryItem::from_command_line(line);
+                item.start_timestamp = start_timestamp.take();
+                items.push(item);
+            }
+        }
+    }
+    items
+}
+
+fn parse_zsh(contents: &str) -> Vec<HistoryItem> {
+    let mut items = vec![];
+    let mut lines = contents.lines();
+    while let Some(line) = lines.next() {
+        // A backslash at the end of the line continues the command on the next one
+        let mut entry = line.to_string();
+        while entry.ends_with('\\') {
+            entry.pop();
+            entry.push('\n');
+            match lines.next() {
+                Some(line) => entry.push_str(line),
+                None => break,
+            }
+        }
+
+        let mut item = match parse_zsh_extended(&entry) {
+            Some((start, elapsed, command_line)) => {
+                let mut item = HistoryItem::from_command_line(command_line);
+                item.start_timestamp = timestamp(start);
+                item.duration = elapsed.trim().parse().ok().map(Duration::from_secs);
+                item
+            }
+            None => HistoryItem::from_command_line(entry.as_str()),
+        };
+        if !item.command_line.trim().is_empty() {
+            item.command_line = item.command_line.trim_end_matches('\n').to_string();
+            items.push(item);
+        }
+    }
+    items
+}
+
+/// Splits an entry of the extended format, `: <start>:<elapsed>;<command>`
+fn parse_zsh_extended(entry: &str) -> Option<(&str, &str, &str)> {
+    let (metadata, command_line) = entry.strip_prefix(": ")?.split_once(';')?;
+    let (start, elapsed) = metadata.split_once(':')?;
+    Some((start, elapsed, command_line))
+}
+
+/// Reverts the escaping of the bytes zsh uses internally in the history file,
+/// which are stored as a meta byte followed by the byte xor 32
+fn unmetafy(bytes: &[u8]) -> Vec<u8> {
+    const META: u8 = 0x83;
+    let mut unmetafied = Vec::with_capacity(bytes.len());
+    let mut bytes = bytes.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            META => unmetafied.extend(bytes.next().map(|byte| byte ^ 32)),
+            byte => unmetafied.push(byte),
+        }
+    }
+    unmetafied
+}
+
+fn parse_fish(contents: &str) -> Vec<HistoryItem> {
+    let mut items: Vec<HistoryItem> = vec![];
+    for line in contents.lines() {
+        if let Some(command_line) = line.strip_prefix("- cmd: ") {
+            items.push(HistoryItem::from_command_line(unescape_fish(command_line)));
+        } else if let (Some(when), Some(item)) = (line.strip_prefix("  when: "), items.last_mut()) {
+            item.start_timestamp = timestamp(when);
+        }
+    }
+    items
+}
+
+/// Reverts the escaping of newlines and backslashes in the commands of fish
+fn unescape_fish(command_line: &str) -> String {
+    let mut unescaped = String::with_capacity(command_line.len());
+    let mut chars = command_line.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                chars.next();
+                unescaped.push('\n');
+            }
+            ('\\', Some('\\')) => {
+                chars.next();
+                unescaped.push('\\');
+            }
+            (c, _) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn command_lines(items: &[HistoryItem]) -> Vec<&str> {
+        items
+            .iter()
+            .map(|item| item.command_line.as_str())
+            .collect()
+    }
+
+    #[rstest]
+    #[case(HistoryImporter::Bash, "ls -l\n\ncd /tmp\n", vec!["ls -l", "cd /tmp"])]
+    #[case(HistoryImporter::Bash, "#1609459200\nls -l\n#2\ncd /tmp\n", vec!["ls -l", "cd /tmp"])]
+    #[case(HistoryImporter::Zsh, "ls -l\ncd /tmp\n", vec!["ls -l", "cd /tmp"])]
+    #[case(
+        HistoryImporter::Zsh,
+        ": 1609459200:3;for f in *; do\\\n  echo $f\\\ndone\n: 1609459210:0;ls -l\n",
+        vec!["for f in *; do\n  echo $f\ndone", "ls -l"]
+    )]
+    #[case(
+        HistoryImporter::Fish,
+        "- cmd: echo 'a\\nb' \\\\\n  when: 1609459200\n  paths:\n    - a\n- cmd: ls\n",
+        vec!["echo 'a\nb' \\", "ls"]
+    )]
+    fn parses_command_lines(
+        #[case] importer: HistoryImporter,
+        #[case] contents: &str,
+        #[case] expected: Vec<&str>,
+    ) {
+        assert_eq!(command_lines(&importer.parse(contents)), expected);
+    }
+
+    #[rstest]
+    #[case(HistoryImporter::Bash, "#1609459200\nls -l\ncd /tmp\n")]
+    #[case(HistoryImporter::Zsh, ": 1609459200:3;ls -l\ncd /tmp\n")]
+    #[case(
+        HistoryImporter::Fish,
+        "- cmd: ls -l\n  when: 1609459200\n- cmd: cd /tmp\n"
+    )]
+    fn parses_start_timestamps(#[case] importer: HistoryImporter, #[case] contents: &str) {
+        let items = importer.parse(contents);
+
+        assert_eq!(
+            items[0].start_timestamp,
+            Utc.timestamp_opt(1609459200, 0).single()
+        );
+        assert_eq!(items[1].start_timestamp, None);
+    }
+
+    #[test]
+    fn zsh_keeps_the_duration() {
+        let items = HistoryImporter::Zsh.parse(": 1609459200:3;sleep 3\n");
+
+        assert_eq!(items[0].duration, Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn zsh_bytes_are_unmetafied() {
+        // "ă" is 0xc4 0x83 in UTF-8, the 0x83 is stored as the meta byte followed by 0x83 ^ 32
+        let bytes = [b'e', b'c', b'h', b'o', b' ', 0xc4, 0x83, 0x83 ^ 32];
+
+        assert_eq!(unmetafy(&bytes), "echo ă".as_bytes());
+    }
+
+    #[test]
+    fn import_saves_into_the_history() -> Result<()> {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(".bash_history");
+        std::fs::write(&path, "ls -l\ncd /tmp\n").unwrap();
+        let mut history = crate::FileBackedHistory::default();
+
+        assert_eq!(HistoryImporter::Bash.import(&path, &mut history)?, 2);
+        assert_eq!(history.count_all()?, 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,357 @@
+use super::{
+    base::CommandLineSearch, fuzzy::fuzzy_match, History, HistoryItem, HistoryItemId,
+    HistorySessionId, SearchDirection, SearchFilter, SearchQuery,
+};
+use crate::{
+    result::{ReedlineError, ReedlineErrorVariants},
+    Result,
+};
+use std::path::MAIN_SEPARATOR;
+
+/// A [`History`] keeping the entries of the session to itself, like zsh
+/// without `share_history`
+///
+/// Navigating and searching only offers the entries saved in this session,
+/// which are kept in memory. They are added to the wrapped history when it is
+/// synced, at the latest when it is dropped at the end of the session.
+///
+/// # Example
+/// ```rust,no_run
+/// use reedline::{FileBackedHistory, IsolatedHistory, Reedline};
+///
+/// let shared = FileBackedHistory::with_file(1000, "history.txt".into()).unwrap();
+/// let mut line_editor =
+///     Reedline::create().with_history(Box::new(IsolatedHistory::new(Box::new(shared))));
+/// ```
+pub struct IsolatedHistory {
+    shared: Box<dyn History>,
+    entries: Vec<SessionEntry>,
+    next_id: i64,
+}
+
+struct SessionEntry {
+    item: HistoryItem,
+    // Whether the entry was added to the shared history, with its id there
+    merged: bool,
+    shared_id: Option<HistoryItemId>,
+}
+
+impl IsolatedHistory {
+    /// Creates a history for the session, merging its entries into `shared`
+    pub fn new(shared: Box<dyn History>) -> Self {
+        Self {
+            shared,
+            entries: vec![],
+            next_id: 0,
+        }
+    }
+
+    fn entry_mut(&mut self, id: HistoryItemId) -> Result<&mut SessionEntry> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.item.id == Some(id))
+            .ok_or(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                "Could not find item",
+            )))
+    }
+
+    /// Adds the entries not merged yet to the shared history
+    fn merge(&mut self) -> Result<()> {
+        for entry in self.entries.iter_mut().filter(|entry| !entry.merged) {
+            let item = HistoryItem {
+                id: None,
+                ..entry.item.clone()
+            };
+            entry.shared_id = self.shared.save(item)?.id;
+            entry.merged = true;
+        }
+        Ok(())
+    }
+}
+
+/// Ignores that the shared history doesn't support a change, like updating the
+/// entries of the [`crate::FileBackedHistory`]
+fn ignore_unsupported(result: Result<()>) -> Result<()> {
+    match result {
+        Err(err) if err.is_unsupported_feature() => Ok(()),
+        result => result,
+    }
+}
+
+fn matches_filter(
+    filter: &SearchFilter,
+    #[cfg(feature = "regex")] regex: Option<&regex::Regex>,
+    item: &HistoryItem,
+) -> bool {
+    let cmd = &item.command_line;
+    let cwd = item.cwd.as_deref();
+    (match &filter.command_line {
+        Some(CommandLineSearch::Prefix(p)) => cmd.starts_with(p),
+        Some(CommandLineSearch::Substring(p)) => cmd.contains(p),
+        Some(CommandLineSearch::Exact(p)) => cmd == p,
+        // rejected by the search
+        Some(CommandLineSearch::FullText(_)) => false,
+        Some(CommandLineSearch::Fuzzy(p)) => fuzzy_match(p, cmd).is_some(),
+        #[cfg(feature = "regex")]
+        Some(CommandLineSearch::Regex(_)) => regex.is_some_and(|r| r.is_match(cmd)),
+        None => true,
+    }) && filter.not_command_line.as_ref() != Some(cmd)
+        && matches_option(&filter.hostname, item.hostname.as_deref())
+        && matches_option(&filter.cwd_exact, cwd)
+        && filter
+            .cwd_prefix
+            .as_ref()
+            .is_none_or(|prefix| cwd.is_some_and(|cwd| cwd.starts_with(prefix.as_str())))
+        && filter.cwd_subtree.as_ref().is_none_or(|dir| {
+            let dir = dir.trim_end_matches(MAIN_SEPARATOR);
+            cwd.is_some_and(|cwd| {
+                cwd == dir
+                    || cwd
+                        .strip_prefix(dir)
+                        .is_some_and(|rest| rest.starts_with(MAIN_SEPARATOR))
+            })
+        })
+        && filter.exit_successful.is_none_or(|successful| {
+            item.exit_status
+                .is_some_and(|status| (status == 0) == successful)
+        })
+        && filter
+            .metadata
+            .iter()
+            .all(|(key, value)| item.metadata.get(key) == Some(value))
+}
+
+fn matches_option(expected: &Option<String>, value: Option<&str>) -> bool {
+    expected
+        .as_deref()
+        .is_none_or(|expected| value == Some(expected))
+}
+
+/// Whether `value` comes after `start` and not after `end` in the direction
+pub(super) fn in_bounds<T: PartialOrd>(
+    value: Option<T>,
+    start: Option<T>,
+    end: Option<T>,
+    direction: SearchDirection,
+) -> bool {
+    let after = |a: &T, b: &T| match direction {
+        SearchDirection::Forward => a > b,
+        SearchDirection::Backward => a < b,
+    };
+    match value {
+        Some(value) => {
+            start.is_none_or(|start| after(&value, &start))
+                && end.is_none_or(|end| !after(&value, &end))
+        }
+        None => start.is_none() && end.is_none(),
+    }
+}
+
+impl History for IsolatedHistory {
+    /// only saves a value if it's not empty and not the same as the previous entry
+    fn save(&mut self, mut h: HistoryItem) -> Result<HistoryItem> {
+        if let Some(id) = h.id {
+            let entry = self.entry_mut(id)?;
+            entry.item = h.clone();
+            return Ok(h);
+        }
+        if h.command_line.is_empty() {
+            return Ok(h);
+        }
+        if let Some(last) = self.entries.last() {
+            if last.item.command_line == h.command_line {
+                return Ok(last.item.clone());
+            }
+        }
+        h.id = Some(HistoryItemId::new(self.next_id));
+        self.next_id += 1;
+        self.entries.push(SessionEntry {
+            item: h.clone(),
+            merged: false,
+            shared_id: None,
+        });
+        Ok(h)
+    }
+
+    fn load(&self, id: HistoryItemId) -> Result<HistoryItem> {
+        self.entries
+            .iter()
+            .find(|entry| entry.item.id == Some(id))
+            .map(|entry| entry.item.clone())
+            .ok_or(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                "Could not find item",
+            )))
+    }
+
+    fn next_session_id(&mut self) -> Result<HistorySessionId> {
+        self.shared.next_session_id()
+    }
+
+    fn count(&self, query: SearchQuery) -> Result<i64> {
+        Ok(self.search(query)?.len() as i64)
+    }
+
+    fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
+        if let Some(CommandLineSearch::FullText(_)) = query.filter.command_line {
+            return Err(ReedlineError::history_feature_unsupported(
+                "IsolatedHistory",
+                "full-text search",
+            ));
+        }
+        #[cfg(feature = "regex")]
+        let regex = super::base::compile_regex(query.filter.command_line.as_ref())?;
+        let matching = |entry: &&SessionEntry| {
+            let item = &entry.item;
+            in_bounds(
+                item.id.map(HistoryItemId::get),
+                query.start_id.map(HistoryItemId::get),
+                query.end_id.map(HistoryItemId::get),
+                query.direction,
+            ) && in_bounds(
+                item.start_timestamp,
+                query.start_time,
+                query.end_time,
+                query.direction,
+            ) && matches_filter(
+                &query.filter,
+                #[cfg(feature = "regex")]
+                regex.as_ref(),
+                item,
+            )
+        };
+        let limit = query.limit.map_or(usize::MAX, |limit| limit as usize);
+        let items = match query.direction {
+            SearchDirection::Forward => self
+                .entries
+                .iter()
+                .filter(matching)
+                .take(limit)
+                .map(|entry| entry.item.clone())
+                .collect(),
+            SearchDirection::Backward => self
+                .entries
+                .iter()
+                .rev()
+                .filter(matching)
+                .take(limit)
+                .map(|entry| entry.item.clone())
+                .collect(),
+        };
+        Ok(items)
+    }
+
+    fn update(
+        &mut self,
+        id: HistoryItemId,
+        updater: &dyn Fn(HistoryItem) -> HistoryItem,
+    ) -> Result<()> {
+        let entry = self.entry_mut(id)?;
+        entry.item = HistoryItem {
+            id: Some(id),
+            ..updater(entry.item.clone())
+        };
+        if let Some(shared_id) = entry.shared_id {
+            ignore_unsupported(self.shared.update(shared_id, updater))?;
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, h: HistoryItemId) -> Result<()> {
+        let shared_id = self.entry_mut(h)?.shared_id;
+        self.entries.retain(|entry| entry.item.id != Some(h));
+        if let Some(shared_id) = shared_id {
+            ignore_unsupported(self.shared.delete(shared_id))?;
+        }
+        Ok(())
+    }
+
+    /// Adds the entries of the session to the shared history and syncs it
+    fn sync(&mut self) -> std::io::Result<()> {
+        self.merge().map_err(std::io::Error::other)?;
+        self.shared.sync()
+    }
+}
+
+impl Drop for IsolatedHistory {
+    /// On drop the entries of the session are merged into the shared history
+    fn drop(&mut self) {
+        let _res = self.sync();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_history_conformance, FileBackedHistory};
+    use pretty_assertions::assert_eq;
+
+    fn command_lines(history: &dyn History) -> Result<Vec<String>> {
+        Ok(history
+            .search(SearchQuery::everything(SearchDirection::Forward))?
+            .into_iter()
+            .map(|item| item.command_line)
+            .collect())
+    }
+
+    #[test]
+    fn isolated_history_conforms() {
+        check_history_conformance(|| IsolatedHistory::new(Box::new(FileBackedHistory::default())));
+    }
+
+    #[test]
+    fn session_only_sees_its_own_entries() -> Result<()> {
+        let tmp = tempfile::tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        let open = || {
+            let shared = FileBackedHistory::with_file(10, histfile.clone()).unwrap();
+            IsolatedHistory::new(Box::new(shared))
+        };
+
+        let mut session_a = open();
+        session_a.save(HistoryItem::from_command_line("A1"))?;
+        {
+            let mut session_b = open();
+            session_b.save(HistoryItem::from_command_line("B1"))?;
+            assert_eq!(command_lines(&session_b)?, vec!["B1"]);
+        }
+        session_a.save(HistoryItem::from_command_line("A2"))?;
+        assert_eq!(command_lines(&session_a)?, vec!["A1", "A2"]);
+        drop(session_a);
+
+        let shared = FileBackedHistory::with_file(10, histfile.clone()).unwrap();
+        assert_eq!(command_lines(&shared)?, vec!["B1", "A1", "A2"]);
+        Ok(())
+    }
+
+    #[test]
+    fn search_metadata_of_session_entries() -> Result<()> {
+        let mut history = IsolatedHistory::new(Box::new(FileBackedHistory::default()));
+        history.save(HistoryItem::from_command_line("git push").with_metadata("branch", "main"))?;
+        history.save(HistoryItem::from_command_line("git pull").with_metadata("branch", "fix"))?;
+
+        let found = history.search(SearchQuery {
+            filter: SearchFilter::anything().with_metadata("branch", "fix"),
+            ..SearchQuery::everything(SearchDirection::Forward)
+        })?;
+        assert_eq!(
+            found
+                .into_iter()
+                .map(|item| item.command_line)
+                .collect::<Vec<_>>(),
+            vec!["git pull"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn entries_are_merged_once() -> Result<()> {
+        let mut history = IsolatedHistory::new(Box::new(FileBackedHistory::default()));
+        history.save(HistoryItem::from_command_line("ls"))?;
+        history.sync().unwrap();
+        history.save(HistoryItem::from_command_line("pwd"))?;
+        history.sync().unwrap();
+
+        assert_eq!(command_lines(history.shared.as_ref())?, vec!["ls", "pwd"]);
+        Ok(())
+    }
+}
@@ -1,14 +1,20 @@
 use chrono::Utc;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::{fmt::Display, time::Duration};
+use std::{collections::BTreeMap, fmt::Display, time::Duration};
 
 /// Unique ID for the [`HistoryItem`]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct HistoryItemId(pub(crate) i64);
 impl HistoryItemId {
-    pub(crate) fn new(i: i64) -> HistoryItemId {
+    /// Wraps the key of an entry in the store of a [`crate::History`]
+    pub fn new(i: i64) -> HistoryItemId {
         HistoryItemId(i)
     }
+
+    /// The key of the entry in the store of the [`crate::History`]
+    pub fn get(self) -> i64 {
+        self.0
+    }
 }
 
 impl Display for HistoryItemId {
@@ -21,9 +27,15 @@ impl Display for HistoryItemId {
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct HistorySessionId(pub(crate) i64);
 impl HistorySessionId {
-    pub(crate) fn new(i: i64) -> HistorySessionId {
+    /// Wraps the key of a session in the store of a [`crate::History`]
+    pub fn new(i: i64) -> HistorySessionId {
         HistorySessionId(i)
     }
+
+    /// The key of the session in the store of the [`crate::History`]
+    pub fn get(self) -> i64 {
+        self.0
+    }
 }
 
 impl Display for HistorySessionId {
@@ -35,7 +47,7 @@ impl Display for HistorySessionId {
 /// This trait represents additional arbitrary context to be added to a history (optional, see [HistoryItem])
 pub trait HistoryItemExtraInfo: Serialize + DeserializeOwned + Default + Send {}
 
-#[derive(Default, Debug, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 /// something that is serialized as null and deserialized by ignoring everything
 pub struct IgnoreAllExtraInfo;
 
@@ -81,6 +93,9 @@ pub struct HistoryItem<ExtraInfo: HistoryItemExtraInfo = IgnoreAllExtraInfo> {
     pub exit_status: Option<i64>,
     /// arbitrary additional information that might be interesting
     pub more_info: Option<ExtraInfo>,
+    /// custom key/value pairs attached by the host, e.g. through
+    /// [`crate::Reedline::set_last_command_metadata`]
+    pub metadata: BTreeMap<String, String>,
 }
 
 impl HistoryItem {
@@ -96,6 +111,15 @@ impl HistoryItem {
             duration: None,
             exit_status: None,
             more_info: None,
+            metadata: BTreeMap::new(),
         }
     }
+
+    /// A builder that attaches the custom `key`/`value` pair to the
+    /// [`HistoryItem::metadata`]
+    #[must_use]
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
 }
@@ -0,0 +1,203 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+};
+
+/// Start of every history file in the [`crate::HistoryFileFormat::Journal`] format
+pub(crate) const JOURNAL_HEADER: &[u8] = b"reedline history journal 1\n";
+/// Records claiming to be longer are taken as corrupted
+const MAX_ENTRY_LEN: u32 = 16 * 1024 * 1024;
+
+/// The record of an entry: the length of the entry, the time it was started in
+/// milliseconds since the epoch, the entry and the CRC-32 of the time and the
+/// entry, the numbers in little endian
+pub(crate) fn encode_record(entry: &str, timestamp: i64) -> Vec<u8> {
+    let mut record = Vec::with_capacity(entry.len() + 16);
+    record.extend((entry.len() as u32).to_le_bytes());
+    record.extend(timestamp.to_le_bytes());
+    record.extend(entry.as_bytes());
+    let checksum = crc32(&record[4..]);
+    record.extend(checksum.to_le_bytes());
+    record
+}
+
+/// Reads the records from `offset` on, returning their entries with their
+/// timestamps and the offset after the last complete record
+///
+/// A record that is corrupted or doesn't match its checksum is skipped, reading
+/// goes on with the next valid record after it. Without one the rest is taken
+/// as a tail cut off by a crash while writing, the offset returned ends before it.
+pub(crate) fn read_records(
+    reader: &mut impl Read,
+    offset: u64,
+) -> io::Result<(VecDeque<(String, i64)>, u64)> {
+    let mut valid_len = offset;
+    if offset == 0 {
+        let mut header = vec![];
+        reader
+            .take(JOURNAL_HEADER.len() as u64)
+            .read_to_end(&mut header)?;
+        if !JOURNAL_HEADER.starts_with(&header) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "history file is not a journal",
+            ));
+        }
+        if header.len() < JOURNAL_HEADER.len() {
+            // empty, or the header itself was cut off
+            return Ok((VecDeque::new(), 0));
+        }
+        valid_len = header.len() as u64;
+    }
+
+    let records_start = valid_len;
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    let mut records = VecDeque::new();
+    let mut position = 0;
+    // A corrupted record is skipped up to the next valid one
+    while let Some((start, (record, len))) =
+        (position..bytes.len()).find_map(|start| Some((start, parse_record(&bytes[start..])?)))
+    {
+        records.push_back(record);
+        position = start + len;
+        valid_len = records_start + position as u64;
+    }
+    Ok((records, valid_len))
+}
+
+/// The entry and the timestamp of the record at the start of `bytes` together
+/// with the length of the record, `None` if it is cut off or corrupted
+fn parse_record(bytes: &[u8]) -> Option<((String, i64), usize)> {
+    let len = u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?);
+    if len > MAX_ENTRY_LEN {
+        return None;
+    }
+    // the timestamp, the entry and the checksum
+    let record_len = 4 + len as usize + 12;
+    let (checked, checksum) = bytes.get(4..record_len)?.split_at(len as usize + 8);
+    if crc32(checked).to_le_bytes() != checksum {
+        return None;
+    }
+    let timestamp = i64::from_le_bytes(checked[..8].try_into().ok()?);
+    let entry = String::from_utf8(checked[8..].to_vec()).ok()?;
+    Some(((entry, timestamp), record_len))
+}
+
+/// CRC-32 as used by zlib and PNG
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const TIMESTAMP: i64 = 1_650_000_000_000;
+
+    fn journal(entries: &[&str]) -> Vec<u8> {
+        let mut journal = JOURNAL_HEADER.to_vec();
+        for entry in entries {
+            journal.extend(encode_record(entry, TIMESTAMP));
+        }
+        journal
+    }
+
+    fn entries(journal: &[u8]) -> (Vec<String>, u64) {
+        let (records, valid_len) = read_records(&mut &journal[..], 0).unwrap();
+        let entries = records.into_iter().map(|(entry, _)| entry).collect();
+        (entries, valid_len)
+    }
+
+    #[test]
+    fn crc32_matches_the_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn reads_back_the_entries() {
+        let journal = journal(&["ls", "echo 'a\nb'", ""]);
+
+        assert_eq!(
+            entries(&journal),
+            (
+                vec!["ls".into(), "echo 'a\nb'".into(), "".into()],
+                journal.len() as u64
+            )
+        );
+    }
+
+    #[test]
+    fn reads_back_the_timestamps() {
+        let mut journal = JOURNAL_HEADER.to_vec();
+        journal.extend(encode_record("ls", TIMESTAMP));
+        journal.extend(encode_record("pwd", TIMESTAMP + 1));
+
+        let (records, _) = read_records(&mut journal.as_slice(), 0).unwrap();
+        assert_eq!(
+            records,
+            vec![("ls".into(), TIMESTAMP), ("pwd".into(), TIMESTAMP + 1)]
+        );
+    }
+
+    #[test]
+    fn drops_a_cut_off_tail() {
+        let complete = journal(&["ls", "pwd"]);
+        for cut in [1, 5, 12, 14] {
+            let journal = &complete[..complete.len() - cut];
+
+            assert_eq!(
+                entries(journal),
+                (vec!["ls".into()], (JOURNAL_HEADER.len() + 18) as u64)
+            );
+        }
+    }
+
+    #[test]
+    fn drops_a_corrupted_tail() {
+        let mut journal = journal(&["ls", "pwd"]);
+        let last = journal.len() - 6;
+        journal[last] ^= 1;
+
+        assert_eq!(entries(&journal).0, vec!["ls"]);
+    }
+
+    #[test]
+    fn skips_a_corrupted_record_in_the_middle() {
+        let mut journal = journal(&["ls", "pwd", "cd"]);
+        let len = journal.len() as u64;
+        // The checksum and the length of the second record
+        let second = JOURNAL_HEADER.len() + 18;
+        journal[second + 14] ^= 1;
+        assert_eq!(entries(&journal), (vec!["ls".into(), "cd".into()], len));
+
+        journal[second] = 0xff;
+        assert_eq!(entries(&journal), (vec!["ls".into(), "cd".into()], len));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert_eq!(
+            read_records(&mut "ls\npwd\n".as_bytes(), 0)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            read_records(&mut &JOURNAL_HEADER[..5], 0).unwrap(),
+            (VecDeque::new(), 0)
+        );
+    }
+}
@@ -1,16 +1,40 @@
 mod base;
+mod conformance;
 mod cursor;
+#[cfg(feature = "encryption")]
+mod encryption;
+mod exclusion;
+mod export;
 mod file_backed;
+mod frecency;
+mod fuzzy;
+mod import;
+mod isolated;
 mod item;
+mod journal;
+mod scope;
 #[cfg(feature = "sqlite")]
 mod sqlite_backed;
+mod stats;
 #[cfg(feature = "sqlite")]
 pub use sqlite_backed::SqliteBackedHistory;
 
+pub(crate) use base::{match_ranges, substring_search};
 pub use base::{
-    CommandLineSearch, History, HistoryNavigationQuery, SearchDirection, SearchFilter, SearchQuery,
+    CommandLineSearch, History, HistoryDedupPolicy, HistoryNavigationMode, HistoryNavigationQuery,
+    HistoryRetentionPolicy, SearchDirection, SearchFilter, SearchQuery,
 };
+pub use conformance::check_history_conformance;
 pub use cursor::HistoryCursor;
+pub(crate) use exclusion::glob_matches;
+pub use exclusion::HistoryExclusionFilter;
+pub use export::HistoryExportFormat;
+pub use frecency::HistoryRanking;
+pub use fuzzy::{fuzzy_match, FuzzyMatch};
+pub use import::HistoryImporter;
+pub use isolated::IsolatedHistory;
 pub use item::{HistoryItem, HistoryItemId, HistorySessionId};
+pub use scope::HistoryScope;
+pub use stats::HistoryStats;
 
-pub use file_backed::{FileBackedHistory, HISTORY_SIZE};
+pub use file_backed::{FileBackedHistory, HistoryFileFormat, HistorySyncPolicy, HISTORY_SIZE};
@@ -0,0 +1,63 @@
+use super::SearchFilter;
+
+/// Which entries the history navigation and the history menu offer, switched
+/// with [`crate::ReedlineEvent::ToggleHistoryScope`]
+///
+/// Restricting the entries to a directory needs a history that keeps the
+/// working directory of its entries, like the `SqliteBackedHistory`. Other
+/// histories keep offering all their entries.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryScope {
+    /// All entries (default)
+    #[default]
+    Global,
+    /// The entries run in the current working directory
+    Directory,
+    /// The entries run in the current working directory or a directory below it
+    Subtree,
+}
+
+impl HistoryScope {
+    /// Restricts `filter` to the entries of the scope
+    pub(crate) fn restrict(self, filter: SearchFilter) -> SearchFilter {
+        let cwd = match (self, std::env::current_dir()) {
+            (Self::Global, _) | (_, Err(_)) => return filter,
+            (_, Ok(cwd)) => cwd.to_string_lossy().to_string(),
+        };
+        match self {
+            Self::Global => filter,
+            Self::Directory => SearchFilter {
+                cwd_exact: Some(cwd),
+                ..filter
+            },
+            Self::Subtree => SearchFilter {
+                cwd_subtree: Some(cwd),
+                ..filter
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn scope_restricts_the_working_directory() {
+        let cwd = std::env::current_dir()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        let global = HistoryScope::Global.restrict(SearchFilter::anything());
+        assert_eq!((global.cwd_exact, global.cwd_subtree), (None, None));
+        let directory = HistoryScope::Directory.restrict(SearchFilter::anything());
+        assert_eq!(
+            (directory.cwd_exact, directory.cwd_subtree),
+            (Some(cwd.clone()), None)
+        );
+        let subtree = HistoryScope::Subtree.restrict(SearchFilter::anything());
+        assert_eq!((subtree.cwd_exact, subtree.cwd_subtree), (None, Some(cwd)));
+    }
+}
@@ -1,9 +1,13 @@
 use chrono::{TimeZone, Utc};
-use rusqlite::{named_params, params, Connection, ToSql};
+use rusqlite::{functions::FunctionFlags, named_params, params, types::FromSql, Connection, ToSql};
 
 use super::{
-    base::{CommandLineSearch, SearchDirection, SearchQuery},
-    History, HistoryItem, HistoryItemId, HistorySessionId,
+    base::{CommandLineSearch, SearchDirection, SearchFilter, SearchQuery},
+    frecency::{AGE_WEIGHTS, CWD_BOOST, OLD_WEIGHT, POSITION_WEIGHTS},
+    fuzzy::fuzzy_match,
+    stats::MOST_FREQUENT_LEN,
+    History, HistoryDedupPolicy, HistoryItem, HistoryItemId, HistoryRetentionPolicy,
+    HistorySessionId, HistoryStats,
 };
 use crate::{
     result::{ReedlineError, ReedlineErrorVariants},
@@ -12,13 +16,25 @@ use crate::{
 
 const SQLITE_APPLICATION_ID: i32 = 1151497937;
 
-use std::{path::PathBuf, time::Duration};
+/// Selects the [`HistoryItem::metadata`] of the entry as a JSON object
+const METADATA_COLUMN: &str = "(select json_group_object(key, value) from history_metadata
+    where history_id = history.id) as metadata";
+
+/// Entries a prune removes at least before the database is compacted, which
+/// rewrites the whole file. The space of fewer entries is reused instead.
+const VACUUM_MIN_REMOVED: usize = 1000;
+
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
 
 /// A history that stores the values to an SQLite database.
 /// In addition to storing the command, the history can store an additional arbitrary HistoryEntryContext,
 /// to add information such as a timestamp, running directory, result...
 pub struct SqliteBackedHistory {
     db: rusqlite::Connection,
+    dedup_policy: HistoryDedupPolicy,
+    retention_policy: Option<HistoryRetentionPolicy>,
+    full_text_index: bool,
+    deleted: Vec<Vec<HistoryItem>>, // Removed in this session, for `restore_last_deleted`
 }
 
 fn deserialize_history_item(row: &rusqlite::Row) -> rusqlite::Result<HistoryItem> {
@@ -50,11 +66,36 @@ fn deserialize_history_item(row: &rusqlite::Row) -> rusqlite::Result<HistoryItem
                 })
             })
             .transpose()?,
+        metadata: row
+            .get::<&str, Option<String>>("metadata")?
+            .map(|x| {
+                serde_json::from_str::<BTreeMap<String, String>>(&x).map_err(|e| {
+                    rusqlite::Error::InvalidColumnType(
+                        0,
+                        format!("could not deserialize metadata: {e}"),
+                        rusqlite::types::Type::Text,
+                    )
+                })
+            })
+            .transpose()?
+            .unwrap_or_default(),
     })
 }
 
 impl History for SqliteBackedHistory {
     fn save(&mut self, mut entry: HistoryItem) -> Result<HistoryItem> {
+        if entry.id.is_none() && self.dedup_policy == HistoryDedupPolicy::IgnoreConsecutive {
+            let mut last = self.search(SearchQuery {
+                limit: Some(1),
+                ..SearchQuery::everything(SearchDirection::Backward)
+            })?;
+            if let Some(last) = last
+                .pop()
+                .filter(|last| last.command_line == entry.command_line)
+            {
+                return Ok(last);
+            }
+        }
         let ret: i64 = self
             .db
             .prepare(
@@ -88,6 +129,28 @@ impl History for SqliteBackedHistory {
                 |row| row.get(0),
             )
             .map_err(map_sqlite_err)?;
+        self.db
+            .execute(
+                "delete from history_metadata where history_id = ?",
+                params![ret],
+            )
+            .map_err(map_sqlite_err)?;
+        for (key, value) in &entry.metadata {
+            self.db
+                .execute(
+                    "insert into history_metadata (history_id, key, value) values (?, ?, ?)",
+                    params![ret, key, value],
+                )
+                .map_err(map_sqlite_err)?;
+        }
+        if entry.id.is_none() && self.dedup_policy == HistoryDedupPolicy::KeepLatest {
+            self.db
+                .execute(
+                    "delete from history where command_line = :command_line and id != :id",
+                    named_params! { ":command_line": entry.command_line, ":id": ret },
+                )
+                .map_err(map_sqlite_err)?;
+        }
         entry.id = Some(HistoryItemId::new(ret));
         Ok(entry)
     }
@@ -95,7 +158,9 @@ impl History for SqliteBackedHistory {
     fn load(&self, id: HistoryItemId) -> Result<HistoryItem> {
         let entry = self
             .db
-            .prepare("select * from history where id = :id")
+            .prepare(&format!(
+                "select *, {METADATA_COLUMN} from history where id = :id"
+            ))
             .map_err(map_sqlite_err)?
             .query_row(named_params! { ":id": id.0 }, deserialize_history_item)
             .map_err(map_sqlite_err)?;
@@ -103,7 +168,7 @@ impl History for SqliteBackedHistory {
     }
 
     fn count(&self, query: SearchQuery) -> Result<i64> {
-        let (query, params) = self.construct_query(&query, "coalesce(count(*), 0)");
+        let (query, params) = self.construct_query(&query, "coalesce(count(*), 0)")?;
         let params_borrow: Vec<(&str, &dyn ToSql)> = params.iter().map(|e| (e.0, &*e.1)).collect();
         let result: i64 = self
             .db
@@ -115,7 +180,7 @@ impl History for SqliteBackedHistory {
     }
 
     fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
-        let (query, params) = self.construct_query(&query, "*");
+        let (query, params) = self.construct_query(&query, &format!("*, {METADATA_COLUMN}"))?;
         let params_borrow: Vec<(&str, &dyn ToSql)> = params.iter().map(|e| (e.0, &*e.1)).collect();
         let results: Vec<HistoryItem> = self
             .db
@@ -128,6 +193,91 @@ impl History for SqliteBackedHistory {
         Ok(results)
     }
 
+    fn search_frecent(
+        &self,
+        filter: SearchFilter,
+        cwd: Option<&str>,
+        limit: Option<i64>,
+    ) -> Result<Vec<HistoryItem>> {
+        let query = SearchQuery {
+            filter,
+            ..SearchQuery::everything(SearchDirection::Backward)
+        };
+        let (matching, mut params) =
+            self.construct_query(&query, "id, command_line, cwd, start_timestamp")?;
+        params.push((":frecency_now", Box::new(Utc::now().timestamp_millis())));
+        params.push((":frecency_cwd", Box::new(cwd.map(str::to_string))));
+        let limit = match limit {
+            Some(l) => {
+                params.push((":limit", Box::new(l)));
+                "limit :limit"
+            }
+            None => "",
+        };
+        let weight = frecency_weight();
+        let query = format!(
+            "select history.*, {METADATA_COLUMN} from history
+        join (
+            select max(id) as latest,
+                sum(weight * (case when cwd = :frecency_cwd then {CWD_BOOST:?} else 1.0 end)) as score
+            from (select *, {weight} as weight from ({matching}))
+            group by command_line
+        ) as ranked on history.id = ranked.latest
+        order by ranked.score desc, ranked.latest desc {limit}"
+        );
+        let params_borrow: Vec<(&str, &dyn ToSql)> = params.iter().map(|e| (e.0, &*e.1)).collect();
+        let results: Vec<HistoryItem> = self
+            .db
+            .prepare(&query)
+            .map_err(map_sqlite_err)?
+            .query_map(&params_borrow[..], deserialize_history_item)
+            .map_err(map_sqlite_err)?
+            .collect::<rusqlite::Result<Vec<HistoryItem>>>()
+            .map_err(map_sqlite_err)?;
+        Ok(results)
+    }
+
+    fn stats(&self) -> Result<HistoryStats> {
+        let query = SearchQuery::everything(SearchDirection::Forward);
+        let (matching, params) =
+            self.construct_query(&query, "command_line, cwd, start_timestamp")?;
+        let params_borrow: Vec<(&str, &dyn ToSql)> = params.iter().map(|e| (e.0, &*e.1)).collect();
+        let (total, distinct) = self
+            .db
+            .query_row(
+                &format!("select count(*), count(distinct command_line) from ({matching})"),
+                &params_borrow[..],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .map_err(map_sqlite_err)?;
+        Ok(HistoryStats {
+            total,
+            distinct,
+            most_frequent: self.counts(
+                &format!(
+                    "select command_line, count(*) as n from ({matching})
+                    group by command_line order by n desc, command_line limit {MOST_FREQUENT_LEN}"
+                ),
+                &params_borrow,
+            )?,
+            busiest_hours: self.counts(
+                &format!(
+                    "select cast(strftime('%H', start_timestamp / 1000, 'unixepoch', 'localtime') as integer)
+                        as hour, count(*) as n from ({matching})
+                    where start_timestamp is not null group by hour order by n desc, hour"
+                ),
+                &params_borrow,
+            )?,
+            per_directory: self.counts(
+                &format!(
+                    "select cwd, count(*) as n from ({matching})
+                    where cwd is not null group by cwd order by n desc, cwd"
+                ),
+                &params_borrow,
+            )?,
+        })
+    }
+
     fn update(
         &mut self,
         id: HistoryItemId,
@@ -139,24 +289,98 @@ impl History for SqliteBackedHistory {
         Ok(())
     }
 
+    /// Removes the entry from the database
+    ///
+    /// The entry is kept in memory for [`History::restore_last_deleted`]
+    /// until the end of the session.
     fn delete(&mut self, h: HistoryItemId) -> Result<()> {
-        let changed = self
-            .db
-            .execute("delete from history where id = ?", params![h.0])
-            .map_err(map_sqlite_err)?;
-        if changed == 0 {
-            return Err(ReedlineError(ReedlineErrorVariants::HistoryDatabaseError(
-                "Could not find item".to_string(),
-            )));
+        self.delete_items(&[h])
+    }
+
+    /// Removes the entries from the database, nothing if one of them is missing
+    fn delete_items(&mut self, ids: &[HistoryItemId]) -> Result<()> {
+        let mut group = ids
+            .iter()
+            .map(|id| {
+                self.load(*id).map_err(|_| {
+                    ReedlineError(ReedlineErrorVariants::HistoryDatabaseError(
+                        "Could not find item".to_string(),
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        group.sort_by_key(|entry| entry.id.map(HistoryItemId::get));
+        group.dedup_by_key(|entry| entry.id);
+        for entry in &group {
+            self.db
+                .execute(
+                    "delete from history where id = ?",
+                    params![entry.id.map(HistoryItemId::get)],
+                )
+                .map_err(map_sqlite_err)?;
+        }
+        if !group.is_empty() {
+            self.deleted.push(group);
         }
         Ok(())
     }
 
+    /// Saves the entries again under their previous ids
+    fn restore_last_deleted(&mut self) -> Result<Vec<HistoryItem>> {
+        self.deleted
+            .pop()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| self.save(entry))
+            .collect()
+    }
+
     fn sync(&mut self) -> std::io::Result<()> {
         // no-op (todo?)
         Ok(())
     }
 
+    /// Compacts the database after removing many entries, so that the file
+    /// shrinks as well
+    fn prune(&mut self, policy: &HistoryRetentionPolicy) -> Result<usize> {
+        self.deleted.clear();
+        let entries = self
+            .db
+            .prepare(
+                "select id, length(cast(command_line as blob)) + coalesce(length(cast(cwd as blob)), 0)
+                    + coalesce(length(cast(hostname as blob)), 0), start_timestamp
+                from history order by id desc",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, Option<i64>>(2)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .map_err(map_sqlite_err)?;
+        let kept = policy.retained(entries.iter().map(|(_, size, start)| {
+            (
+                *size as u64,
+                start.and_then(|e| Utc.timestamp_millis_opt(e).single()),
+            )
+        }));
+        let removed = match entries.get(kept) {
+            Some((first_removed, ..)) => self
+                .db
+                .execute("delete from history where id <= ?", params![first_removed])
+                .map_err(map_sqlite_err)?,
+            None => 0,
+        };
+        if removed >= VACUUM_MIN_REMOVED {
+            self.db.execute_batch("vacuum").map_err(map_sqlite_err)?;
+        }
+        Ok(removed)
+    }
+
     fn next_session_id(&mut self) -> Result<HistorySessionId> {
         Ok(HistorySessionId::new(
             self.db
@@ -177,6 +401,43 @@ fn map_sqlite_err(err: rusqlite::Error) -> ReedlineError {
     )))
 }
 
+/// The weight of a run by its age, or by its position among the runs searched
+/// if it has no start time, like [`super::frecency::rank_by_frecency`]
+fn frecency_weight() -> String {
+    const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+    let by_age = AGE_WEIGHTS
+        .iter()
+        .map(|(days, weight)| {
+            format!(
+                "when :frecency_now - start_timestamp < {} then {weight:?}",
+                (days + 1) * DAY_MS
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let by_position = POSITION_WEIGHTS
+        .iter()
+        .map(|(position, weight)| {
+            format!("when row_number() over (order by id desc) <= {position} then {weight:?}")
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "case when start_timestamp is null
+            then case {by_position} else {OLD_WEIGHT:?} end
+            else case {by_age} else {OLD_WEIGHT:?} end
+        end"
+    )
+}
+
+fn has_full_text_index(db: &Connection) -> rusqlite::Result<bool> {
+    db.query_row(
+        "select count(*) > 0 from sqlite_master where type = 'table' and name = 'history_fts'",
+        params![],
+        |r| r.get(0),
+    )
+}
+
 type BoxedNamedParams<'a> = Vec<(&'static str, Box<dyn ToSql + 'a>)>;
 
 impl SqliteBackedHistory {
@@ -212,6 +473,9 @@ impl SqliteBackedHistory {
             .map_err(map_sqlite_err)?;
         db.pragma_update(None, "foreign_keys", "on")
             .map_err(map_sqlite_err)?;
+        // Deleted entries are overwritten, they may hold secrets typed by accident
+        db.pragma_update(None, "secure_delete", "on")
+            .map_err(map_sqlite_err)?;
         db.pragma_update(None, "application_id", SQLITE_APPLICATION_ID)
             .map_err(map_sqlite_err)?;
         let db_version: i32 = db
@@ -239,6 +503,12 @@ impl SqliteBackedHistory {
             exit_status integer,
             more_info text
         ) strict;
+        create table if not exists history_metadata (
+            history_id integer not null references history(id) on delete cascade,
+            key text not null,
+            value text not null,
+            primary key (history_id, key)
+        ) strict;
         create index if not exists idx_history_time on history(start_timestamp);
         create index if not exists idx_history_cwd on history(cwd); -- suboptimal for many hosts
         create index if not exists idx_history_exit_status on history(exit_status);
@@ -248,13 +518,108 @@ impl SqliteBackedHistory {
         ",
         )
         .map_err(map_sqlite_err)?;
-        Ok(SqliteBackedHistory { db })
+        db.create_scalar_function(
+            "fuzzy_match",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| Ok(fuzzy_match(&ctx.get::<String>(0)?, &ctx.get::<String>(1)?).is_some()),
+        )
+        .map_err(map_sqlite_err)?;
+        // `x regexp y` calls `regexp(y, x)`, the pattern is compiled once per query
+        #[cfg(feature = "regex")]
+        db.create_scalar_function(
+            "regexp",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let regex = ctx.get_or_create_aux(0, |pattern| {
+                    regex::Regex::new(pattern.as_str()?)
+                        .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { e.into() })
+                })?;
+                Ok(regex.is_match(&ctx.get::<String>(1)?))
+            },
+        )
+        .map_err(map_sqlite_err)?;
+        // The triggers keep the index of the database up to date once it was created
+        let full_text_index = has_full_text_index(&db).map_err(map_sqlite_err)?;
+        Ok(SqliteBackedHistory {
+            db,
+            dedup_policy: HistoryDedupPolicy::KeepAll,
+            retention_policy: None,
+            full_text_index,
+            deleted: vec![],
+        })
+    }
+
+    /// Creates an FTS5 index over the command lines if the database doesn't
+    /// have one yet, to search them with [`CommandLineSearch::FullText`]
+    ///
+    /// The index is kept up to date with the history from then on, also when
+    /// the database is opened again without calling this.
+    pub fn with_full_text_index(mut self) -> Result<Self> {
+        if !self.full_text_index {
+            self.db
+                .execute_batch(
+                    "
+            create virtual table history_fts using fts5(
+                command_line, content='history', content_rowid='id'
+            );
+            create trigger history_fts_insert after insert on history begin
+                insert into history_fts(rowid, command_line) values (new.id, new.command_line);
+            end;
+            create trigger history_fts_delete after delete on history begin
+                insert into history_fts(history_fts, rowid, command_line)
+                    values ('delete', old.id, old.command_line);
+            end;
+            create trigger history_fts_update after update of command_line on history begin
+                insert into history_fts(history_fts, rowid, command_line)
+                    values ('delete', old.id, old.command_line);
+                insert into history_fts(rowid, command_line) values (new.id, new.command_line);
+            end;
+            insert into history_fts(history_fts) values ('rebuild');
+            ",
+                )
+                .map_err(map_sqlite_err)?;
+            self.full_text_index = true;
+        }
+        Ok(self)
+    }
+
+    /// A builder that sets which repeated entries are kept, by default
+    /// [`HistoryDedupPolicy::KeepAll`]
+    #[must_use]
+    pub fn with_dedup_policy(mut self, dedup_policy: HistoryDedupPolicy) -> Self {
+        self.dedup_policy = dedup_policy;
+        self
+    }
+
+    /// A builder that sets the limits applied by [`History::prune`] when the
+    /// history is dropped
+    #[must_use]
+    pub fn with_retention_policy(mut self, retention_policy: HistoryRetentionPolicy) -> Self {
+        self.retention_policy = Some(retention_policy);
+        self
+    }
+    /// The rows of a query selecting a value and its count
+    fn counts<T: FromSql>(
+        &self,
+        query: &str,
+        params: &[(&str, &dyn ToSql)],
+    ) -> Result<Vec<(T, i64)>> {
+        self.db
+            .prepare(query)
+            .map_err(map_sqlite_err)?
+            .query_map(params, |r| Ok((r.get(0)?, r.get(1)?)))
+            .map_err(map_sqlite_err)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(map_sqlite_err)
     }
+
     fn construct_query<'a>(
         &self,
         query: &'a SearchQuery,
         select_expression: &str,
-    ) -> (String, BoxedNamedParams<'a>) {
+    ) -> Result<(String, BoxedNamedParams<'a>)> {
         // TODO: this whole function could be done with less allocs
         let (is_asc, asc) = match query.direction {
             SearchDirection::Forward => (true, "asc"),
@@ -301,17 +666,49 @@ impl SqliteBackedHistory {
             }
             None => "",
         };
-        if let Some(command_line) = &query.filter.command_line {
-            // TODO: escape %
-            let command_line_like = match command_line {
-                CommandLineSearch::Exact(e) => e.to_string(),
-                CommandLineSearch::Prefix(prefix) => format!("{prefix}%"),
-                CommandLineSearch::Substring(cont) => format!("%{cont}%"),
-            };
-            wheres.push("command_line like :command_line");
-            params.push((":command_line", Box::new(command_line_like)));
+        match &query.filter.command_line {
+            Some(CommandLineSearch::FullText(_)) if !self.full_text_index => {
+                return Err(ReedlineError::history_feature_unsupported(
+                    "SqliteBackedHistory",
+                    "full-text search without SqliteBackedHistory::with_full_text_index",
+                ));
+            }
+            #[cfg(feature = "regex")]
+            Some(CommandLineSearch::Regex(pattern)) => {
+                wheres.push("command_line regexp :command_line");
+                params.push((":command_line", Box::new(pattern.as_str())));
+            }
+            Some(CommandLineSearch::Fuzzy(pattern)) => {
+                wheres.push("fuzzy_match(:command_line, command_line)");
+                params.push((":command_line", Box::new(pattern)));
+            }
+            Some(CommandLineSearch::FullText(fts_query)) => {
+                wheres.push(
+                    "id in (select rowid from history_fts where history_fts match :command_line)",
+                );
+                params.push((":command_line", Box::new(fts_query)));
+            }
+            Some(command_line) => {
+                // TODO: escape %
+                let command_line_like = match command_line {
+                    CommandLineSearch::Exact(e) => e.to_string(),
+                    CommandLineSearch::Prefix(prefix) => format!("{prefix}%"),
+                    CommandLineSearch::Substring(cont) => format!("%{cont}%"),
+                    // handled above
+                    _ => unreachable!(),
+                };
+                wheres.push("command_line like :command_line");
+                params.push((":command_line", Box::new(command_line_like)));
+            }
+            None => {}
         }
 
+        if self.dedup_policy == HistoryDedupPolicy::KeepLatest {
+            // Older duplicates saved before the policy was set
+            wheres.push(
+                "id = (select max(id) from history as newer where newer.command_line = history.command_line)",
+            );
+        }
         if let Some(str) = &query.filter.not_command_line {
             wheres.push("command_line != :not_cmd");
             params.push((":not_cmd", Box::new(str)));
@@ -329,6 +726,29 @@ impl SqliteBackedHistory {
             let cwd_like = format!("{cwd_prefix}%");
             params.push((":cwd_like", Box::new(cwd_like)));
         }
+        if let Some(cwd_subtree) = &query.filter.cwd_subtree {
+            // Compared without `like`, which would treat `_` and `%` in the path as wildcards
+            wheres.push(
+                "(cwd = :cwd_subtree or substr(cwd, 1, length(:cwd_subtree_dir)) = :cwd_subtree_dir)",
+            );
+            let cwd_subtree_dir = format!(
+                "{}{}",
+                cwd_subtree.trim_end_matches(std::path::MAIN_SEPARATOR),
+                std::path::MAIN_SEPARATOR
+            );
+            params.push((":cwd_subtree", Box::new(cwd_subtree)));
+            params.push((":cwd_subtree_dir", Box::new(cwd_subtree_dir)));
+        }
+        if !query.filter.metadata.is_empty() {
+            // None of the wanted pairs is missing from the metadata of the entry
+            wheres.push(
+                "not exists (select 1 from json_each(:metadata) as wanted
+                    where not exists (select 1 from history_metadata
+                        where history_id = history.id and key = wanted.key and value = wanted.value))",
+            );
+            let metadata = serde_json::to_string(&query.filter.metadata).unwrap();
+            params.push((":metadata", Box::new(metadata)));
+        }
         if let Some(exit_successful) = query.filter.exit_successful {
             if exit_successful {
                 wheres.push("exit_status = 0");
@@ -346,6 +766,15 @@ impl SqliteBackedHistory {
         {wheres}
         order by id {asc} {limit}"
         );
-        (query, params)
+        Ok((query, params))
+    }
+}
+
+impl Drop for SqliteBackedHistory {
+    /// On drop the oldest entries are pruned if a [`SqliteBackedHistory::with_retention_policy()`] is set.
+    fn drop(&mut self) {
+        if let Some(policy) = self.retention_policy {
+            let _res = self.prune(&policy);
+        }
     }
 }
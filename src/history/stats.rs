@@ -0,0 +1,105 @@
+use super::HistoryItem;
+use chrono::{Local, Timelike};
+use std::collections::HashMap;
+
+/// How many command lines [`HistoryStats::most_frequent`] lists
+pub(crate) const MOST_FREQUENT_LEN: usize = 10;
+
+/// Summary of the entries of a history, see [`crate::History::stats`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HistoryStats {
+    /// Number of entries
+    pub total: i64,
+    /// Number of different command lines
+    pub distinct: i64,
+    /// The ten command lines run the most often with their number of runs,
+    /// most first
+    pub most_frequent: Vec<(String, i64)>,
+    /// The hours of the day in local time with the number of entries started
+    /// in them, busiest first
+    pub busiest_hours: Vec<(u32, i64)>,
+    /// The working directories with the number of entries run in them, most
+    /// first
+    pub per_directory: Vec<(String, i64)>,
+}
+
+impl HistoryStats {
+    /// Summarizes the items, the command lines of equal counts are ordered
+    /// alphabetically and the hours chronologically
+    pub(crate) fn from_items(items: &[HistoryItem]) -> Self {
+        let mut commands = HashMap::new();
+        let mut hours = HashMap::new();
+        let mut directories = HashMap::new();
+        for item in items {
+            *commands.entry(item.command_line.clone()).or_insert(0) += 1;
+            if let Some(start) = item.start_timestamp {
+                *hours.entry(start.with_timezone(&Local).hour()).or_insert(0) += 1;
+            }
+            if let Some(cwd) = &item.cwd {
+                *directories.entry(cwd.clone()).or_insert(0) += 1;
+            }
+        }
+        let distinct = commands.len() as i64;
+        let mut most_frequent = by_count(commands);
+        most_frequent.truncate(MOST_FREQUENT_LEN);
+        Self {
+            total: items.len() as i64,
+            distinct,
+            most_frequent,
+            busiest_hours: by_count(hours),
+            per_directory: by_count(directories),
+        }
+    }
+}
+
+fn by_count<K: Ord>(counts: HashMap<K, i64>) -> Vec<(K, i64)> {
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+
+    fn item(command_line: &str, cwd: Option<&str>, hour: Option<u32>) -> HistoryItem {
+        HistoryItem {
+            start_timestamp: hour.map(|hour| {
+                Local
+                    .ymd(2022, 3, 14)
+                    .and_hms(hour, 30, 0)
+                    .with_timezone(&Utc)
+            }),
+            cwd: cwd.map(str::to_string),
+            ..HistoryItem::from_command_line(command_line)
+        }
+    }
+
+    #[test]
+    fn summarizes_the_items() {
+        let items = [
+            item("ls", Some("/home"), Some(9)),
+            item("cargo test", Some("/src"), Some(14)),
+            item("cargo test", Some("/src"), Some(14)),
+            item("ls", None, Some(9)),
+            item("git status", Some("/src"), None),
+        ];
+
+        assert_eq!(
+            HistoryStats::from_items(&items),
+            HistoryStats {
+                total: 5,
+                distinct: 3,
+                most_frequent: vec![
+                    ("cargo test".to_string(), 2),
+                    ("ls".to_string(), 2),
+                    ("git status".to_string(), 1)
+                ],
+                busiest_hours: vec![(9, 2), (14, 2)],
+                per_directory: vec![("/src".to_string(), 3), ("/home".to_string(), 1)],
+            }
+        );
+    }
+}
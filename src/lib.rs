@@ -205,59 +205,83 @@
 #![warn(missing_docs)]
 // #![deny(warnings)]
 mod core_editor;
+pub use core_editor::BracketPairs;
 pub use core_editor::Editor;
 pub use core_editor::LineBuffer;
+#[cfg(feature = "system_clipboard")]
+pub use core_editor::SystemClipboard;
+pub use core_editor::UndoLimit;
+pub use core_editor::WordBoundary;
+pub use core_editor::{Clipboard, ClipboardMode, LocalClipboard};
+pub use core_editor::{TextObject, TextObjectScope, TextObjectType};
 
 mod enums;
-pub use enums::{EditCommand, ReedlineEvent, Signal, UndoBehavior};
+pub use enums::{EditCommand, ReedlineEvent, Signal, UndoBehavior, UndoCoalescing};
 
 mod painting;
-pub use painting::{Painter, StyledText};
+pub use painting::{LineNumberMode, Painter, StyledText, Theme};
 
 mod engine;
 pub use engine::Reedline;
 
 mod result;
-pub(crate) use result::Result;
+pub use result::{ReedlineError, Result};
 
 mod history;
 #[cfg(feature = "sqlite")]
 pub use history::SqliteBackedHistory;
 pub use history::{
-    CommandLineSearch, FileBackedHistory, History, HistoryItem, HistoryItemId,
-    HistoryNavigationQuery, HistorySessionId, SearchDirection, SearchFilter, SearchQuery,
-    HISTORY_SIZE,
+    check_history_conformance, fuzzy_match, CommandLineSearch, FileBackedHistory, FuzzyMatch,
+    History, HistoryDedupPolicy, HistoryExclusionFilter, HistoryExportFormat, HistoryFileFormat,
+    HistoryImporter, HistoryItem, HistoryItemId, HistoryNavigationMode, HistoryNavigationQuery,
+    HistoryRanking, HistoryRetentionPolicy, HistoryScope, HistorySessionId, HistoryStats,
+    HistorySyncPolicy, IsolatedHistory, SearchDirection, SearchFilter, SearchQuery, HISTORY_SIZE,
 };
 
 mod prompt;
 pub use prompt::{
-    DefaultPrompt, Prompt, PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus,
-    PromptViMode,
+    AsyncSegment, DefaultPrompt, Prompt, PromptEditMode, PromptHistorySearch,
+    PromptHistorySearchStatus, PromptViMode,
 };
 
 mod edit_mode;
 pub use edit_mode::{
     default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings,
-    EditMode, Emacs, Keybindings, Vi,
+    EditMode, Emacs, KeyCombination, KeybindingConflict, Keybindings, Vi,
 };
 
 mod highlighter;
+#[cfg(feature = "tree-sitter")]
+pub use highlighter::TreeSitterHighlighter;
 pub use highlighter::{ExampleHighlighter, Highlighter, SimpleMatchHighlighter};
 
 mod completion;
-pub use completion::{Completer, DefaultCompleter, Span, Suggestion};
+pub use completion::{
+    AsyncCompleter, CachedCompleter, CombineStrategy, CombinedCompleter, Completer,
+    CompletionRequest, DefaultCompleter, Span, Suggestion, SuggestionKind,
+};
 
 mod hinter;
-pub use hinter::{DefaultHinter, Hinter};
+pub use hinter::{
+    AsyncHinter, CompleterHinter, DefaultHinter, HintPolicy, HintRequest, HintStrategy, Hinter,
+};
+
+mod abbreviation;
+pub use abbreviation::{Abbreviation, AbbreviationHook, DefaultAbbreviations};
 
 mod validator;
 pub use validator::{DefaultValidator, ValidationResult, Validator};
 
 mod menu;
 pub use menu::{
-    menu_functions, ColumnarMenu, ListMenu, Menu, MenuEvent, MenuTextStyle, ReedlineMenu,
+    menu_functions, ColumnarMenu, DescriptionOverflow, HistoryColumn, ListMenu, Menu, MenuEvent,
+    MenuPlacement, MenuTextStyle, ReedlineMenu,
 };
 
+mod terminal_extensions;
+#[cfg(feature = "osc52_clipboard")]
+pub use terminal_extensions::Osc52Clipboard;
+
 mod utils;
 pub use utils::{
     get_reedline_default_keybindings, get_reedline_edit_commands,
@@ -173,6 +173,13 @@ fn main() -> Result<()> {
             Ok(Signal::CtrlC) => {
                 // Prompt has been cleared and should start on the next line
             }
+            Ok(Signal::Custom(name)) => {
+                println!("Custom event: {}", name);
+            }
+            Ok(Signal::Picked { menu, value }) => {
+                println!("Picked in {}: {}", menu, value.value);
+            }
+            Ok(_) => {}
             Err(err) => {
                 println!("Error: {:?}", err);
             }
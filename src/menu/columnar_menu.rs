@@ -1,9 +1,19 @@
-use super::{menu_functions::find_common_string, Menu, MenuEvent, MenuTextStyle};
+use super::{
+    menu_functions::find_common_string, quick_select_label, quick_select_modifier,
+    quick_select_position, DescriptionOverflow, Menu, MenuEvent, MenuPlacement, MenuSections,
+    MenuTextStyle,
+};
 use crate::{
-    core_editor::Editor, menu_functions::string_difference, painting::Painter, Completer,
-    Suggestion, UndoBehavior,
+    completion::sort_by_priority,
+    core_editor::Editor,
+    fuzzy_match,
+    menu_functions::string_difference,
+    painting::{truncate_end, Painter},
+    Completer, StyledText, Suggestion, SuggestionKind, Theme, UndoBehavior,
 };
+use crossterm::event::KeyModifiers;
 use nu_ansi_term::{ansi::RESET, Style};
+use std::cmp::Reverse;
 
 /// Default values used as reference for the menu. These values are set during
 /// the initial declaration of the menu and are always kept as reference for the
@@ -71,6 +81,57 @@ pub struct ColumnarMenu {
     /// Calls the completer using only the line buffer difference difference
     /// after the menu was activated
     only_buffer_difference: bool,
+    /// The completer was still computing suggestions when queried
+    loading: bool,
+    /// Filters and ranks the values by how well they fuzzily match the typed text
+    fuzzy: bool,
+    /// Style of the characters matching the typed text
+    match_style: Style,
+    /// Byte offsets of the characters of each value matching the typed text
+    match_positions: Vec<Vec<usize>>,
+    /// Width of the column of markers in front of the values, 0 if no value
+    /// has a marker
+    marker_width: usize,
+    /// Lines of the pane showing the description of the selected value, 0
+    /// without a pane
+    documentation_lines: u16,
+    /// First line of the description shown in the pane
+    documentation_offset: usize,
+    /// Number of values loaded from the completer at once, all of them if None
+    chunk_size: Option<usize>,
+    /// The line and position the values were completed for, to load the next
+    /// chunks of values
+    query: Option<(String, usize)>,
+    /// Number of values loaded from the completer
+    loaded: usize,
+    /// The completer has no values left to load
+    all_loaded: bool,
+    /// Several values can be selected to be inserted together
+    multi_select: bool,
+    /// Inserted between the values selected together
+    multi_select_delimiter: String,
+    /// Indexes of the values selected together, in the order they were selected
+    multi_selected: Vec<usize>,
+    /// Section headers grouping the values by their kind
+    sections: MenuSections,
+    /// Insert the common prefix of the values before opening the menu
+    common_prefix_first: bool,
+    /// Where the menu is painted relative to the prompt
+    placement: MenuPlacement,
+    /// Show the position of the selected value while not all rows fit
+    position_counter: bool,
+    /// Style of the position counter, the description style if not set
+    position_style: Option<Style>,
+    /// Widest a value is shown, longer values end in an ellipsis
+    max_value_width: Option<usize>,
+    /// Widest a description is shown, the rest of the screen if not set
+    max_description_width: Option<usize>,
+    /// How a description longer than its column is shown
+    description_overflow: DescriptionOverflow,
+    /// Modifier of the keys accepting the values by their labels
+    quick_select: Option<KeyModifiers>,
+    /// All values with their matched characters while a filter hides some
+    unfiltered: Option<(Vec<Suggestion>, Vec<Vec<usize>>)>,
 }
 
 impl Default for ColumnarMenu {
@@ -90,6 +151,30 @@ impl Default for ColumnarMenu {
             longest_suggestion: 0,
             input: None,
             only_buffer_difference: false,
+            loading: false,
+            fuzzy: false,
+            match_style: Theme::default().search_match,
+            match_positions: Vec::new(),
+            marker_width: 0,
+            documentation_lines: 0,
+            documentation_offset: 0,
+            chunk_size: None,
+            query: None,
+            loaded: 0,
+            all_loaded: true,
+            multi_select: false,
+            multi_select_delimiter: " ".to_string(),
+            multi_selected: Vec::new(),
+            sections: MenuSections::default(),
+            unfiltered: None,
+            common_prefix_first: false,
+            placement: MenuPlacement::default(),
+            position_counter: false,
+            position_style: None,
+            max_value_width: None,
+            max_description_width: None,
+            description_overflow: DescriptionOverflow::default(),
+            quick_select: None,
         }
     }
 }
@@ -158,6 +243,165 @@ impl ColumnarMenu {
         self.only_buffer_difference = only_buffer_difference;
         self
     }
+
+    /// Menu builder inserting the common prefix of the values when opening the
+    /// menu, like bash does on the first `Tab`. The menu stays closed while
+    /// there is a prefix to insert and opens on the next `Tab`.
+    #[must_use]
+    pub fn with_common_prefix_first(mut self, common_prefix_first: bool) -> Self {
+        self.common_prefix_first = common_prefix_first;
+        self
+    }
+
+    /// Menu builder with the placement of the menu above or below the prompt
+    #[must_use]
+    pub fn with_placement(mut self, placement: MenuPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Menu builder with the widest a value is shown, longer values are cut
+    /// to end in an ellipsis
+    #[must_use]
+    pub fn with_max_value_width(mut self, max_value_width: Option<usize>) -> Self {
+        self.max_value_width = max_value_width.map(|width| width.max(1));
+        self
+    }
+
+    /// Menu builder with the widest a description is shown, the rest of the
+    /// screen next to the values by default
+    #[must_use]
+    pub fn with_max_description_width(mut self, max_description_width: Option<usize>) -> Self {
+        self.max_description_width = max_description_width.map(|width| width.max(1));
+        self
+    }
+
+    /// Menu builder with how a description longer than its column is shown,
+    /// cut at the end of the column by default
+    ///
+    /// Wrapped descriptions take as many rows as they need, the value in the
+    /// first of them.
+    #[must_use]
+    pub fn with_description_overflow(mut self, description_overflow: DescriptionOverflow) -> Self {
+        self.description_overflow = description_overflow;
+        self
+    }
+
+    /// Menu builder labeling the painted values `1` to `9` and `a` to `z`,
+    /// accepting a value right away when its label is pressed with `modifier`,
+    /// like `Alt`, instead of moving to it
+    ///
+    /// No modifier and `Shift` alone are ignored, as they type text.
+    #[must_use]
+    pub fn with_quick_select(mut self, modifier: Option<KeyModifiers>) -> Self {
+        self.quick_select = quick_select_modifier(modifier);
+        self
+    }
+
+    /// Menu builder with fuzzy matching of the suggestions
+    ///
+    /// The suggestions not matching the text they replace, like `gcm` for
+    /// `git commit`, are dropped and the others are ranked by how well they
+    /// match, highlighting the matched characters. The completer should
+    /// suggest every candidate of the word and leave the filtering to the menu.
+    #[must_use]
+    pub fn with_fuzzy_matching(mut self, fuzzy: bool) -> Self {
+        self.fuzzy = fuzzy;
+        self
+    }
+
+    /// Menu builder with a pane below the values showing up to `lines` lines of
+    /// the full description of the selected value, like the help text of a
+    /// flag or the preview of a file
+    ///
+    /// The pane keeps the styles of the description and is scrolled with
+    /// [`crate::ReedlineEvent::MenuDocumentationDown`] and
+    /// [`crate::ReedlineEvent::MenuDocumentationUp`].
+    #[must_use]
+    pub fn with_documentation_pane(mut self, lines: u16) -> Self {
+        self.documentation_lines = lines;
+        self
+    }
+
+    /// Menu builder loading the values lazily in chunks of `chunk_size`, for
+    /// completers suggesting too many values to load at once, like the entries
+    /// of a huge directory
+    ///
+    /// The next chunk is loaded with [`Completer::partial_complete`] once the
+    /// selection reaches the last row of the loaded values. The values are
+    /// sorted and fuzzily matched chunk by chunk.
+    #[must_use]
+    pub fn with_chunk_size(mut self, chunk_size: Option<usize>) -> Self {
+        self.chunk_size = chunk_size.map(|size| size.max(1));
+        self
+    }
+
+    /// Menu builder with multi-select, selecting several values with
+    /// [`crate::ReedlineEvent::MenuToggleMultiSelect`] to insert them together,
+    /// like several files passed to a command
+    ///
+    /// The values selected together are underlined, or marked with a `*`
+    /// without coloring. Without any of them selected, accepting inserts the
+    /// value under the cursor of the menu.
+    #[must_use]
+    pub fn with_multi_select(mut self, multi_select: bool) -> Self {
+        self.multi_select = multi_select;
+        self
+    }
+
+    /// Menu builder with new delimiter inserted between the values selected
+    /// together, a space by default
+    #[must_use]
+    pub fn with_multi_select_delimiter(mut self, delimiter: &str) -> Self {
+        self.multi_select_delimiter = delimiter.to_string();
+        self
+    }
+
+    /// Menu builder with section headers, grouping the values by their
+    /// [`SuggestionKind`] under headers like "Files" or "Flags" that can't be
+    /// selected
+    ///
+    /// The sections are shown in the order their kinds are first suggested,
+    /// in one column like the values with descriptions.
+    #[must_use]
+    pub fn with_sections(mut self, sections: bool) -> Self {
+        self.sections.set_enabled(sections);
+        self
+    }
+
+    /// Menu builder with a style for the values of a section and its header
+    #[must_use]
+    pub fn with_section_style(mut self, kind: SuggestionKind, style: Style) -> Self {
+        self.sections.set_style(kind, style);
+        self
+    }
+
+    /// Menu builder with a counter like `12/345` below the values, showing the
+    /// position of the selected value while the values take more rows than
+    /// fit the screen
+    ///
+    /// A `+` follows the number of values while more are still to be loaded,
+    /// see [`ColumnarMenu::with_chunk_size`].
+    #[must_use]
+    pub fn with_position_counter(mut self, position_counter: bool) -> Self {
+        self.position_counter = position_counter;
+        self
+    }
+
+    /// Menu builder with new value for the style of the position counter,
+    /// the description style by default
+    #[must_use]
+    pub fn with_position_counter_style(mut self, position_style: Style) -> Self {
+        self.position_style = Some(position_style);
+        self
+    }
+
+    /// Menu builder with new value for the style of the fuzzily matched characters
+    #[must_use]
+    pub fn with_match_text_style(mut self, match_text_style: Style) -> Self {
+        self.match_style = match_text_style;
+        self
+    }
 }
 
 // Menu functionality
@@ -262,6 +506,35 @@ impl ColumnarMenu {
         }
     }
 
+    /// Move menu cursor to the element painted at the clicked cell
+    fn move_to_click(&mut self, column: u16, row: u16, available_lines: u16) {
+        if let Some(rows) = self.one_column_rows() {
+            let skip_rows = self.skipped_rows(&rows, available_lines);
+            if let Some(&(index, part)) = rows.get(skip_rows + row as usize) {
+                if part != RowPart::Header {
+                    self.col_pos = 0;
+                    self.row_pos = index as u16;
+                }
+            }
+            return;
+        }
+
+        // The rows skipped while printing the menu, see `menu_string`
+        let skip_rows = if self.row_pos >= available_lines {
+            self.row_pos.saturating_sub(available_lines) + 1
+        } else {
+            0
+        };
+
+        let new_row = skip_rows + row;
+        let new_col = (column as usize / self.get_width().max(1)) as u16;
+        let index = new_row * self.get_cols() + new_col;
+        if new_col < self.get_cols() && index < self.get_values().len() as u16 {
+            self.col_pos = new_col;
+            self.row_pos = new_row;
+        }
+    }
+
     /// Menu index based on column and row position
     fn index(&self) -> usize {
         let index = self.row_pos * self.get_cols() + self.col_pos;
@@ -278,7 +551,8 @@ impl ColumnarMenu {
         let values = self.get_values().len() as u16;
 
         if values == 0 {
-            // When the values are empty the no_records_msg is shown, taking 1 line
+            // When the values are empty the no_records_msg is shown, taking 1 line,
+            // also while they are loading
             return 1;
         }
 
@@ -301,8 +575,377 @@ impl ColumnarMenu {
         self.row_pos = 0;
     }
 
+    /// Sets the values suggested for `line`, see [`ColumnarMenu::add_values`]
+    fn set_values(&mut self, values: Vec<Suggestion>, line: &str) {
+        self.values.clear();
+        self.match_positions.clear();
+        self.multi_selected.clear();
+        self.unfiltered = None;
+        self.marker_width = 0;
+        self.add_values(values, line);
+    }
+
+    /// Adds values suggested for `line` after the ones added before, sorted by
+    /// their priority, keeping only the fuzzy matches of the text they replace,
+    /// the best matches first, when matching fuzzily
+    fn add_values(&mut self, mut values: Vec<Suggestion>, line: &str) {
+        let marker_width = values
+            .iter()
+            .filter_map(|suggestion| suggestion.marker())
+            .map(|marker| marker.chars().count() + 1)
+            .max()
+            .unwrap_or(0);
+        self.marker_width = self.marker_width.max(marker_width);
+        if !self.fuzzy {
+            sort_by_priority(&mut values);
+            if self.sections.is_enabled() {
+                group_by_kind(&mut values, |suggestion| suggestion.kind);
+            }
+            self.values.extend(values);
+            return;
+        }
+
+        let mut matches = values
+            .into_iter()
+            .filter_map(|suggestion| {
+                let typed = line
+                    .get(suggestion.span.start..suggestion.span.end)
+                    .unwrap_or_default();
+                fuzzy_match(typed, &suggestion.value).map(|found| (found, suggestion))
+            })
+            .collect::<Vec<_>>();
+        // Stable, so that equal matches keep the order of the completer
+        matches.sort_by_key(|(found, suggestion)| {
+            (Reverse(suggestion.priority), Reverse(found.score))
+        });
+        if self.sections.is_enabled() {
+            group_by_kind(&mut matches, |(_, suggestion)| suggestion.kind);
+        }
+        for (found, suggestion) in matches {
+            self.values.push(suggestion);
+            self.match_positions.push(found.positions);
+        }
+    }
+
+    /// Completes the values of `line`, only the first chunk when loading them
+    /// lazily
+    fn load_values(&mut self, completer: &mut dyn Completer, line: String, pos: usize) {
+        let values = match self.chunk_size {
+            Some(size) => {
+                let values = completer.partial_complete(&line, pos, 0, size);
+                self.all_loaded = values.len() < size;
+                values
+            }
+            None => {
+                self.all_loaded = true;
+                completer.complete(&line, pos)
+            }
+        };
+        self.loaded = values.len();
+        self.set_values(values, &line);
+        self.query = Some((line, pos));
+    }
+
+    /// Loads the next chunk of values once the selection reaches the last row
+    /// of the loaded values
+    fn load_next_chunk(&mut self, completer: &mut dyn Completer) {
+        let size = match self.chunk_size {
+            Some(size) if !self.all_loaded && self.unfiltered.is_none() => size,
+            _ => return,
+        };
+        if self.index() + (self.get_cols() as usize) < self.values.len() {
+            return;
+        }
+        let (line, pos) = match &self.query {
+            Some((line, pos)) => (line.clone(), *pos),
+            None => return,
+        };
+
+        let values = completer.partial_complete(&line, pos, self.loaded, size);
+        self.loaded += values.len();
+        self.all_loaded = values.len() < size;
+        self.add_values(values, &line);
+    }
+
+    /// The value of a suggestion behind its marker
+    fn marked_value(&self, suggestion: &Suggestion) -> String {
+        self.truncate_value(&self.full_marked_value(suggestion))
+    }
+
+    /// The value of a suggestion behind its marker, however wide it is
+    fn full_marked_value(&self, suggestion: &Suggestion) -> String {
+        format!(
+            "{:width$}{}",
+            suggestion.marker().unwrap_or_default(),
+            suggestion.value,
+            width = self.marker_width
+        )
+    }
+
+    /// A value cut to the widest a value is shown
+    fn truncate_value(&self, value: &str) -> String {
+        match self.max_value_width {
+            Some(width) => truncate_end(value, width).into_owned(),
+            None => value.to_string(),
+        }
+    }
+
+    /// The number of characters of a value behind its marker and its label
+    fn marked_width(&self, suggestion: &Suggestion) -> usize {
+        let width = suggestion.value.chars().count() + self.marker_width;
+        self.max_value_width.map_or(width, |max| width.min(max)) + self.label_width()
+    }
+
+    /// Width of the labels in front of the values, 0 without quick select
+    fn label_width(&self) -> usize {
+        if self.quick_select.is_some() {
+            2
+        } else {
+            0
+        }
+    }
+
+    /// The label in front of the `position`-th painted value
+    fn label_string(&self, position: usize, use_ansi_coloring: bool) -> String {
+        if self.quick_select.is_none() {
+            return String::new();
+        }
+        let label = format!("{} ", quick_select_label(position));
+        if use_ansi_coloring {
+            self.color.description_style.paint(label).to_string()
+        } else {
+            label
+        }
+    }
+
+    /// Columns left for the descriptions next to the values
+    fn description_width(&self) -> usize {
+        let left_text_size = self.longest_suggestion + self.default_details.col_padding;
+        let width = self.get_width().saturating_sub(left_text_size);
+        self.max_description_width
+            .map_or(width, |max| width.min(max))
+    }
+
+    /// The lines of a description fit in its column, more than one only while
+    /// the descriptions wrap
+    fn description_lines(&self, description: &str) -> Vec<String> {
+        let description = description.replace('\n', " ");
+        let width = self.description_width();
+        match self.description_overflow {
+            DescriptionOverflow::Cut => vec![description.chars().take(width).collect()],
+            DescriptionOverflow::Ellipsis => vec![truncate_end(&description, width).into_owned()],
+            DescriptionOverflow::Wrap => wrap_words(&description, width),
+        }
+    }
+
+    /// The first line of a description fit in its column
+    fn description_line(&self, description: &str) -> String {
+        self.description_lines(description)
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+    }
+
+    /// The value of a suggestion behind its marker in `style`, its fuzzily
+    /// matched characters in `match_style`
+    fn styled_value(
+        &self,
+        suggestion: &Suggestion,
+        index: usize,
+        style: Style,
+        match_style: Style,
+    ) -> String {
+        let (style, match_style) = if self.multi_selected.contains(&index) {
+            (style.underline(), match_style.underline())
+        } else {
+            (style, match_style)
+        };
+        let marked = self.full_marked_value(suggestion);
+        let offset = marked.len() - suggestion.value.len();
+        let mut styled = StyledText::new();
+        styled.push((style, marked));
+        for &position in self.match_positions.get(index).into_iter().flatten() {
+            if let Some(c) = suggestion.value[position..].chars().next() {
+                let start = offset + position;
+                styled.restyle_range(start..start + c.len_utf8(), |_| match_style);
+            }
+        }
+        self.truncate_value(&styled.render_simple())
+    }
+
+    /// The description of the selected value shown in the documentation pane
+    fn documentation(&self) -> Option<&str> {
+        if self.documentation_lines == 0 {
+            return None;
+        }
+        self.get_values()
+            .get(self.index())
+            .and_then(|suggestion| suggestion.description.as_deref())
+    }
+
+    /// Lines taken by the documentation pane, including the line showing the
+    /// scroll position of a description longer than the pane
+    fn documentation_height(&self) -> u16 {
+        self.documentation().map_or(0, |documentation| {
+            let lines = documentation.lines().count();
+            let shown = lines.min(self.documentation_lines as usize);
+            (shown + usize::from(lines > shown)) as u16
+        })
+    }
+
+    fn scroll_documentation(&mut self, down: bool) {
+        let lines = self
+            .documentation()
+            .map_or(0, |documentation| documentation.lines().count());
+        let last_offset = lines.saturating_sub(self.documentation_lines as usize);
+        self.documentation_offset = if down {
+            (self.documentation_offset + 1).min(last_offset)
+        } else {
+            self.documentation_offset.saturating_sub(1)
+        };
+    }
+
+    fn documentation_string(&self, use_ansi_coloring: bool) -> String {
+        let documentation = match self.documentation() {
+            Some(documentation) => documentation,
+            None => return String::new(),
+        };
+        let lines = documentation.lines().count();
+        let mut rows = documentation
+            .lines()
+            .skip(self.documentation_offset)
+            .take(self.documentation_lines as usize)
+            .map(|line| truncate_end(line, self.get_width()).into_owned())
+            .collect::<Vec<_>>();
+        if lines > rows.len() {
+            rows.push(format!(
+                "[{}-{} of {}]",
+                self.documentation_offset + 1,
+                self.documentation_offset + rows.len(),
+                lines
+            ));
+        }
+
+        rows.into_iter()
+            .map(|row| {
+                if use_ansi_coloring {
+                    format!("{}{}{}", self.color.description_style.prefix(), row, RESET)
+                } else {
+                    row
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\r\n")
+    }
+
+    fn toggle_multi_select(&mut self) {
+        let index = self.index();
+        if !self.multi_select || index >= self.values.len() {
+            return;
+        }
+        match self
+            .multi_selected
+            .iter()
+            .position(|&selected| selected == index)
+        {
+            Some(position) => {
+                self.multi_selected.remove(position);
+            }
+            None => self.multi_selected.push(index),
+        }
+    }
+
+    /// Shows only the values fuzzily matching `filter`, keeping their order,
+    /// or all of them again without a filter
+    fn filter_values(&mut self, filter: Option<String>) {
+        if let Some((values, match_positions)) = self.unfiltered.take() {
+            self.values = values;
+            self.match_positions = match_positions;
+        }
+        self.multi_selected.clear();
+        self.reset_position();
+        let filter = match filter {
+            Some(filter) if !filter.is_empty() => filter,
+            _ => return,
+        };
+
+        let (values, match_positions) = self
+            .values
+            .iter()
+            .filter_map(|suggestion| {
+                fuzzy_match(&filter, &suggestion.value)
+                    .map(|found| (suggestion.clone(), found.positions))
+            })
+            .unzip();
+        self.unfiltered = Some((
+            std::mem::replace(&mut self.values, values),
+            std::mem::replace(&mut self.match_positions, match_positions),
+        ));
+    }
+
+    /// The value inserted when accepting: the values selected together joined
+    /// by the delimiter, or else the value under the cursor
+    fn accepted_value(&self) -> Option<Suggestion> {
+        let mut selected = self
+            .multi_selected
+            .iter()
+            .filter_map(|&index| self.values.get(index));
+        let first = match selected.next() {
+            Some(first) => first,
+            None => return self.get_value(),
+        };
+
+        let mut accepted = first.clone();
+        for suggestion in selected {
+            accepted.value.push_str(&self.multi_select_delimiter);
+            accepted.value.push_str(&suggestion.value);
+            accepted.append_whitespace = suggestion.append_whitespace;
+        }
+        Some(accepted)
+    }
+
+    /// Lines left for the values by the documentation pane
+    fn value_lines(&self, available_lines: u16) -> u16 {
+        let lines = available_lines
+            .saturating_sub(self.documentation_height())
+            .max(1);
+        if self.shows_position(lines) {
+            lines - 1
+        } else {
+            lines
+        }
+    }
+
+    /// Rows taken by the values, the section headers and the wrapped
+    /// descriptions
+    fn value_rows(&self) -> u16 {
+        self.one_column_rows()
+            .map_or(self.get_rows(), |rows| rows.len() as u16)
+    }
+
+    /// Whether the position counter takes one of the `lines` left for the
+    /// values, which it does when they don't all fit
+    fn shows_position(&self, lines: u16) -> bool {
+        self.position_counter && lines > 1 && self.value_rows() > lines
+    }
+
+    fn position_string(&self, use_ansi_coloring: bool) -> String {
+        let more = if self.all_loaded { "" } else { "+" };
+        let position = format!("{}/{}{}", self.index() + 1, self.values.len(), more);
+        if use_ansi_coloring {
+            let style = self.position_style.unwrap_or(self.color.description_style);
+            style.paint(position).to_string()
+        } else {
+            position
+        }
+    }
+
     fn no_records_msg(&self, use_ansi_coloring: bool) -> String {
-        let msg = "NO RECORDS FOUND";
+        let msg = if self.loading {
+            "LOADING..."
+        } else {
+            "NO RECORDS FOUND"
+        };
         if use_ansi_coloring {
             format!(
                 "{}{}{}",
@@ -339,29 +982,25 @@ impl ColumnarMenu {
         use_ansi_coloring: bool,
     ) -> String {
         if use_ansi_coloring {
+            let selected = self.color.selected_text_style;
+            let text_style = self.sections.style(suggestion.kind, self.color.text_style);
             if index == self.index() {
                 if let Some(description) = &suggestion.description {
                     let left_text_size = self.longest_suggestion + self.default_details.col_padding;
-                    let right_text_size = self.get_width().saturating_sub(left_text_size);
                     format!(
-                        "{}{:max$}{}{}{}",
-                        self.color.selected_text_style.prefix(),
-                        &suggestion.value,
-                        description
-                            .chars()
-                            .take(right_text_size)
-                            .collect::<String>()
-                            .replace('\n', " "),
+                        "{}{}{:>padding$}{}{}{}",
+                        self.styled_value(suggestion, index, selected, selected.bold()),
+                        selected.prefix(),
+                        "",
+                        self.description_line(description),
                         RESET,
                         self.end_of_line(column),
-                        max = left_text_size,
+                        padding = left_text_size.saturating_sub(self.marked_width(suggestion)),
                     )
                 } else {
                     format!(
-                        "{}{}{}{:>empty$}{}",
-                        self.color.selected_text_style.prefix(),
-                        &suggestion.value,
-                        RESET,
+                        "{}{:>empty$}{}",
+                        self.styled_value(suggestion, index, selected, selected.bold()),
                         "",
                         self.end_of_line(column),
                         empty = empty_space,
@@ -369,28 +1008,22 @@ impl ColumnarMenu {
                 }
             } else if let Some(description) = &suggestion.description {
                 let left_text_size = self.longest_suggestion + self.default_details.col_padding;
-                let right_text_size = self.get_width().saturating_sub(left_text_size);
                 format!(
-                    "{}{:max$}{}{}{}{}{}",
-                    self.color.text_style.prefix(),
-                    &suggestion.value,
+                    "{}{}{:>padding$}{}{}{}{}{}",
+                    self.styled_value(suggestion, index, text_style, self.match_style),
+                    text_style.prefix(),
+                    "",
                     RESET,
                     self.color.description_style.prefix(),
-                    description
-                        .chars()
-                        .take(right_text_size)
-                        .collect::<String>()
-                        .replace('\n', " "),
+                    self.description_line(description),
                     RESET,
                     self.end_of_line(column),
-                    max = left_text_size,
+                    padding = left_text_size.saturating_sub(self.marked_width(suggestion)),
                 )
             } else {
                 format!(
-                    "{}{}{}{}{:>empty$}{}{}",
-                    self.color.text_style.prefix(),
-                    &suggestion.value,
-                    RESET,
+                    "{}{}{:>empty$}{}{}",
+                    self.styled_value(suggestion, index, text_style, self.match_style),
                     self.color.description_style.prefix(),
                     "",
                     RESET,
@@ -400,30 +1033,32 @@ impl ColumnarMenu {
             }
         } else {
             // If no ansi coloring is found, then the selection word is the line in uppercase
-            let marker = if index == self.index() { ">" } else { "" };
+            let marker = if index == self.index() {
+                ">"
+            } else if self.multi_selected.contains(&index) {
+                "*"
+            } else {
+                ""
+            };
 
             let line = if let Some(description) = &suggestion.description {
                 format!(
                     "{}{:max$}{}{}",
                     marker,
-                    &suggestion.value,
-                    description
-                        .chars()
-                        .take(empty_space)
-                        .collect::<String>()
-                        .replace('\n', " "),
+                    self.marked_value(suggestion),
+                    self.description_line(description),
                     self.end_of_line(column),
                     max = self.longest_suggestion
                         + self
                             .default_details
                             .col_padding
-                            .saturating_sub(marker.len()),
+                            .saturating_sub(marker.len() + self.label_width()),
                 )
             } else {
                 format!(
                     "{}{}{:>empty$}{}",
                     marker,
-                    &suggestion.value,
+                    self.marked_value(suggestion),
                     "",
                     self.end_of_line(column),
                     empty = empty_space.saturating_sub(marker.len()),
@@ -460,6 +1095,14 @@ impl Menu for ColumnarMenu {
         true
     }
 
+    fn inserts_common_prefix_first(&self) -> bool {
+        self.common_prefix_first
+    }
+
+    fn placement(&self) -> MenuPlacement {
+        self.placement
+    }
+
     /// The columnar menu can try to find the common string and replace it
     /// in the given line buffer
     fn can_partially_complete(
@@ -473,6 +1116,10 @@ impl Menu for ColumnarMenu {
         if !values_updated {
             self.update_values(editor, completer);
         }
+        // The values not loaded yet might not share the string
+        if !self.all_loaded {
+            return false;
+        }
 
         let values = self.get_values();
         if let (Some(Suggestion { value, span, .. }), Some(index)) = find_common_string(values) {
@@ -530,7 +1177,7 @@ impl Menu for ColumnarMenu {
             if let Some(old_string) = &self.input {
                 let (start, input) = string_difference(editor.get_buffer(), old_string);
                 if !input.is_empty() {
-                    self.values = completer.complete(input, start);
+                    self.load_values(completer, input.to_string(), start);
                     self.reset_position();
                 }
             }
@@ -541,9 +1188,10 @@ impl Menu for ColumnarMenu {
             // Also, by replacing the new line character with a space, the insert
             // position is maintain in the line buffer.
             let trimmed_buffer = editor.get_buffer().replace('\n', " ");
-            self.values = completer.complete(trimmed_buffer.as_str(), editor.insertion_point());
+            self.load_values(completer, trimmed_buffer, editor.insertion_point());
             self.reset_position();
         }
+        self.loading = completer.has_pending_completions();
     }
 
     /// The working details for the menu changes based on the size of the lines
@@ -564,21 +1212,22 @@ impl Menu for ColumnarMenu {
                 .get_values()
                 .iter()
                 .any(|suggestion| suggestion.description.is_some());
+            let exist_sections = self.sections.headers(self.get_values()).contains(&true);
 
-            if exist_description {
+            if exist_description || exist_sections {
                 self.working_details.columns = 1;
                 self.working_details.col_width = painter.screen_width() as usize;
 
                 self.longest_suggestion = self.get_values().iter().fold(0, |prev, suggestion| {
-                    if prev >= suggestion.value.len() {
+                    if prev >= self.marked_width(suggestion) {
                         prev
                     } else {
-                        suggestion.value.len()
+                        self.marked_width(suggestion)
                     }
                 });
             } else {
                 let max_width = self.get_values().iter().fold(0, |acc, suggestion| {
-                    let str_len = suggestion.value.len() + self.default_details.col_padding;
+                    let str_len = self.marked_width(suggestion) + self.default_details.col_padding;
                     if str_len > acc {
                         str_len
                     } else {
@@ -613,6 +1262,13 @@ impl Menu for ColumnarMenu {
                 }
             }
 
+            if !matches!(
+                event,
+                MenuEvent::DocumentationDown | MenuEvent::DocumentationUp | MenuEvent::Refresh
+            ) {
+                self.documentation_offset = 0;
+            }
+
             match event {
                 MenuEvent::Activate(updated) => {
                     self.active = true;
@@ -636,16 +1292,33 @@ impl Menu for ColumnarMenu {
                         self.update_values(editor, completer);
                     }
                 }
+                MenuEvent::Refresh => {
+                    let index = self.index();
+                    self.update_values(editor, completer);
+                    let index = index.min(self.get_values().len().saturating_sub(1)) as u16;
+                    self.row_pos = index / self.get_cols();
+                    self.col_pos = index % self.get_cols();
+                }
                 MenuEvent::NextElement => self.move_next(),
                 MenuEvent::PreviousElement => self.move_previous(),
                 MenuEvent::MoveUp => self.move_up(),
                 MenuEvent::MoveDown => self.move_down(),
                 MenuEvent::MoveLeft => self.move_left(),
                 MenuEvent::MoveRight => self.move_right(),
+                MenuEvent::Click { column, row } => {
+                    let available_lines = self.value_lines(painter.menu_available_lines());
+                    self.move_to_click(column, row, available_lines);
+                }
                 MenuEvent::PreviousPage | MenuEvent::NextPage => {
                     // The columnar menu doest have the concept of pages, yet
                 }
+                MenuEvent::DocumentationDown => self.scroll_documentation(true),
+                MenuEvent::DocumentationUp => self.scroll_documentation(false),
+                MenuEvent::ToggleMultiSelect => self.toggle_multi_select(),
+                MenuEvent::Filter(filter) => self.filter_values(filter),
             }
+
+            self.load_next_chunk(completer);
         }
     }
 
@@ -656,7 +1329,7 @@ impl Menu for ColumnarMenu {
             span,
             append_whitespace,
             ..
-        }) = self.get_value()
+        }) = self.accepted_value()
         {
             let start = span.start.min(editor.line_buffer().len());
             let end = span.end.min(editor.line_buffer().len());
@@ -683,48 +1356,307 @@ impl Menu for ColumnarMenu {
         &self.values
     }
 
+    fn get_selected_value(&self) -> Option<Suggestion> {
+        self.get_value()
+    }
+
     fn menu_required_lines(&self, _terminal_columns: u16) -> u16 {
-        self.get_rows()
+        self.value_rows() + self.documentation_height()
     }
 
     fn menu_string(&self, available_lines: u16, use_ansi_coloring: bool) -> String {
         if self.get_values().is_empty() {
             self.no_records_msg(use_ansi_coloring)
         } else {
-            // The skip values represent the number of lines that should be skipped
-            // while printing the menu
-            let skip_values = if self.row_pos >= available_lines {
-                let skip_lines = self.row_pos.saturating_sub(available_lines) + 1;
-                (skip_lines * self.get_cols()) as usize
-            } else {
-                0
+            let value_lines = self.value_lines(available_lines);
+            let mut values = match self.one_column_rows() {
+                Some(rows) => self.rows_string(&rows, value_lines, use_ansi_coloring),
+                None => self.values_string(value_lines, use_ansi_coloring),
             };
+            if value_lines < available_lines.saturating_sub(self.documentation_height()) {
+                if !values.ends_with('\n') {
+                    values.push_str("\r\n");
+                }
+                values.push_str(&self.position_string(use_ansi_coloring));
+            }
+
+            let documentation = self.documentation_string(use_ansi_coloring);
+            if documentation.is_empty() {
+                values
+            } else if values.ends_with('\n') {
+                values + &documentation
+            } else {
+                values + "\r\n" + &documentation
+            }
+        }
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) {
+        self.color = MenuTextStyle::from(theme);
+        self.match_style = theme.search_match;
+    }
 
-            // It seems that crossterm prefers to have a complete string ready to be printed
-            // rather than looping through the values and printing multiple things
-            // This reduces the flickering when printing the menu
-            let available_values = (available_lines * self.get_cols()) as usize;
-            self.get_values()
+    fn quick_select_modifier(&self) -> Option<KeyModifiers> {
+        self.quick_select
+    }
+
+    fn shows_quick_select_label(&self, label: char, available_lines: u16) -> bool {
+        self.quick_select_index(label, available_lines).is_some()
+    }
+
+    fn quick_select(&mut self, label: char, available_lines: u16) -> bool {
+        match self.quick_select_index(label, available_lines) {
+            Some(index) => {
+                self.row_pos = index as u16 / self.get_cols();
+                self.col_pos = index as u16 % self.get_cols();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl ColumnarMenu {
+    /// The index of the value painted with `label` in the `available_lines`
+    fn quick_select_index(&self, label: char, available_lines: u16) -> Option<usize> {
+        let position = match (self.quick_select, quick_select_position(label)) {
+            (Some(_), Some(position)) => position,
+            _ => return None,
+        };
+        let lines = self.value_lines(available_lines);
+        match self.one_column_rows() {
+            Some(rows) => rows
                 .iter()
-                .skip(skip_values)
-                .take(available_values)
-                .enumerate()
-                .map(|(index, suggestion)| {
-                    // Correcting the enumerate index based on the number of skipped values
-                    let index = index + skip_values;
-                    let column = index as u16 % self.get_cols();
-                    let empty_space = self.get_width().saturating_sub(suggestion.value.len());
-
-                    self.create_string(suggestion, index, column, empty_space, use_ansi_coloring)
-                })
-                .collect()
+                .skip(self.skipped_rows(&rows, lines))
+                .take(lines as usize)
+                .filter(|(_, part)| *part == RowPart::Value)
+                .nth(position)
+                .map(|&(index, _)| index),
+            None => {
+                let index = self.skipped_values(lines) + position;
+                let painted = (lines * self.get_cols()) as usize;
+                (position < painted && index < self.get_values().len()).then_some(index)
+            }
         }
     }
+
+    /// The values painted in the rows of the menu that fit the available lines
+    /// The values skipped while printing the menu to show the selected row
+    fn skipped_values(&self, available_lines: u16) -> usize {
+        if self.row_pos >= available_lines {
+            let skip_lines = self.row_pos.saturating_sub(available_lines) + 1;
+            (skip_lines * self.get_cols()) as usize
+        } else {
+            0
+        }
+    }
+
+    fn values_string(&self, available_lines: u16, use_ansi_coloring: bool) -> String {
+        let skip_values = self.skipped_values(available_lines);
+
+        // It seems that crossterm prefers to have a complete string ready to be printed
+        // rather than looping through the values and printing multiple things
+        // This reduces the flickering when printing the menu
+        let available_values = (available_lines * self.get_cols()) as usize;
+        self.get_values()
+            .iter()
+            .skip(skip_values)
+            .take(available_values)
+            .enumerate()
+            .map(|(position, suggestion)| {
+                // Correcting the enumerate index based on the number of skipped values
+                let index = position + skip_values;
+                let column = index as u16 % self.get_cols();
+                let empty_space = self
+                    .get_width()
+                    .saturating_sub(self.marked_width(suggestion));
+
+                self.label_string(position, use_ansi_coloring)
+                    + &self.create_string(suggestion, index, column, empty_space, use_ansi_coloring)
+            })
+            .collect()
+    }
+
+    /// The rows of a menu in one column taller than its values, by section
+    /// headers or wrapped descriptions. None while each value takes a row.
+    fn one_column_rows(&self) -> Option<Vec<(usize, RowPart)>> {
+        let headers = self.sections.headers(self.get_values());
+        let wraps = self.description_overflow == DescriptionOverflow::Wrap
+            && self
+                .get_values()
+                .iter()
+                .any(|suggestion| suggestion.description.is_some());
+        if !wraps && !headers.contains(&true) {
+            return None;
+        }
+
+        let rows = self
+            .get_values()
+            .iter()
+            .zip(headers)
+            .enumerate()
+            .flat_map(|(index, (suggestion, header))| {
+                let description_rows = match &suggestion.description {
+                    Some(description) if wraps => self.description_lines(description).len(),
+                    _ => 1,
+                };
+                header
+                    .then_some((index, RowPart::Header))
+                    .into_iter()
+                    .chain(Some((index, RowPart::Value)))
+                    .chain(
+                        (1..description_rows).map(move |line| (index, RowPart::Description(line))),
+                    )
+            })
+            .collect();
+        Some(rows)
+    }
+
+    /// The rows of a menu in one column that fit the available lines
+    fn rows_string(
+        &self,
+        rows: &[(usize, RowPart)],
+        available_lines: u16,
+        use_ansi_coloring: bool,
+    ) -> String {
+        let mut position = 0;
+        rows.iter()
+            .skip(self.skipped_rows(rows, available_lines))
+            .take(available_lines as usize)
+            .map(|&(index, part)| {
+                let suggestion = &self.get_values()[index];
+                match part {
+                    RowPart::Header => {
+                        let header = self.sections.header(
+                            suggestion.kind,
+                            self.color.text_style,
+                            use_ansi_coloring,
+                        );
+                        format!("{}\r\n", header)
+                    }
+                    RowPart::Value => {
+                        let empty_space = self
+                            .get_width()
+                            .saturating_sub(self.marked_width(suggestion));
+                        let label = self.label_string(position, use_ansi_coloring);
+                        position += 1;
+                        label
+                            + &self.create_string(
+                                suggestion,
+                                index,
+                                0,
+                                empty_space,
+                                use_ansi_coloring,
+                            )
+                    }
+                    RowPart::Description(line) => {
+                        self.description_row(suggestion, index, line, use_ansi_coloring)
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// A line after the first of a wrapped description, below the value
+    fn description_row(
+        &self,
+        suggestion: &Suggestion,
+        index: usize,
+        line: usize,
+        use_ansi_coloring: bool,
+    ) -> String {
+        let description = suggestion.description.as_deref().unwrap_or_default();
+        let text = self
+            .description_lines(description)
+            .into_iter()
+            .nth(line)
+            .unwrap_or_default();
+        let padding = self.longest_suggestion + self.default_details.col_padding;
+        if use_ansi_coloring {
+            let style = if index == self.index() {
+                self.color.selected_text_style
+            } else {
+                self.color.description_style
+            };
+            format!("{}{:padding$}{}{}\r\n", style.prefix(), "", text, RESET)
+        } else if index == self.index() {
+            format!("{:padding$}{}\r\n", "", text.to_uppercase())
+        } else {
+            format!("{:padding$}{}\r\n", "", text)
+        }
+    }
+
+    /// The rows of a menu in one column skipped while painting to show the
+    /// selected value, with as much of its wrapped description as fits
+    fn skipped_rows(&self, rows: &[(usize, RowPart)], available_lines: u16) -> usize {
+        let value_row = rows
+            .iter()
+            .position(|&row| row == (self.index(), RowPart::Value))
+            .unwrap_or(0);
+        let last_row = rows
+            .iter()
+            .rposition(|&(index, part)| index == self.index() && part != RowPart::Header)
+            .unwrap_or(0);
+        (last_row + 1)
+            .saturating_sub(available_lines as usize)
+            .min(value_row)
+    }
+}
+
+/// What of a value a row of a menu in one column shows
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RowPart {
+    /// The header of the section the value starts
+    Header,
+    /// The value with the first line of its description
+    Value,
+    /// The line of the wrapped description of the value
+    Description(usize),
+}
+
+/// Splits `text` into lines of at most `width` characters at the spaces
+/// between words, cutting the words wider than a line
+fn wrap_words(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let line_width = line.chars().count();
+        if line_width > 0 && line_width + 1 + word.chars().count() <= width {
+            line.push(' ');
+            line.push_str(word);
+            continue;
+        }
+        if line_width > 0 {
+            lines.push(std::mem::take(&mut line));
+        }
+
+        let chars = word.chars().collect::<Vec<_>>();
+        let mut chunks = chars.chunks(width).map(|chunk| chunk.iter().collect());
+        line = chunks.next().unwrap_or_default();
+        for chunk in chunks {
+            lines.push(std::mem::replace(&mut line, chunk));
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+/// Stable sorts items into runs of the same kind, in the order the kinds are
+/// first found
+fn group_by_kind<T>(items: &mut [T], kind: impl Fn(&T) -> Option<SuggestionKind>) {
+    let mut kinds = Vec::new();
+    for item in items.iter() {
+        if !kinds.contains(&kind(item)) {
+            kinds.push(kind(item));
+        }
+    }
+    items.sort_by_key(|item| kinds.iter().position(|found| *found == kind(item)));
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Span;
+    use crate::{Span, SuggestionKind};
 
     use super::*;
 
@@ -780,6 +1712,412 @@ mod tests {
             completes_no_shared_prefix: ("all", "all"),
     }
 
+    #[test]
+    fn click_selects_the_painted_value() {
+        let mut menu = ColumnarMenu {
+            values: ["a", "b", "c", "d", "e"]
+                .iter()
+                .map(|value| fake_suggestion(value, 0))
+                .collect(),
+            working_details: ColumnDetails {
+                columns: 2,
+                col_width: 10,
+            },
+            ..ColumnarMenu::default()
+        };
+
+        menu.move_to_click(12, 1, 5);
+        assert_eq!(menu.index(), 3);
+
+        // Clicking past the last value keeps the selection
+        menu.move_to_click(12, 2, 5);
+        assert_eq!(menu.index(), 3);
+    }
+
+    #[test]
+    fn refresh_keeps_the_selected_value() {
+        let mut menu = ColumnarMenu::default();
+        let mut editor = Editor::default();
+        let mut painter = Painter::new(std::io::BufWriter::new(std::io::stderr()));
+        painter.handle_resize(80, 24);
+        let mut completer = FakeCompleter::new(&["a", "b", "c"]);
+        for event in [MenuEvent::Activate(false), MenuEvent::NextElement] {
+            menu.menu_event(event);
+            menu.update_working_details(&mut editor, &mut completer, &painter);
+        }
+
+        let mut completer = FakeCompleter::new(&["a", "b", "c", "d"]);
+        menu.menu_event(MenuEvent::Refresh);
+        menu.update_working_details(&mut editor, &mut completer, &painter);
+        assert_eq!(menu.get_values().len(), 4);
+        assert_eq!(menu.index(), 1);
+
+        menu.menu_event(MenuEvent::Edit(false));
+        menu.update_working_details(&mut editor, &mut completer, &painter);
+        assert_eq!(menu.index(), 0);
+    }
+
+    #[test]
+    fn shows_the_suggestions_are_loading() {
+        /// Still computing its suggestions in the background
+        struct LoadingCompleter;
+
+        impl Completer for LoadingCompleter {
+            fn complete(&mut self, _line: &str, _pos: usize) -> Vec<Suggestion> {
+                vec![]
+            }
+
+            fn has_pending_completions(&self) -> bool {
+                true
+            }
+        }
+
+        let mut menu = ColumnarMenu::default();
+        let mut editor = Editor::default();
+        menu.update_values(&mut editor, &mut FakeCompleter::new(&[]));
+        assert_eq!(menu.menu_string(5, false), "NO RECORDS FOUND");
+
+        menu.update_values(&mut editor, &mut LoadingCompleter);
+        assert_eq!(menu.menu_string(5, false), "LOADING...");
+    }
+
+    #[test]
+    fn fuzzy_matching_ranks_and_highlights_the_matches() {
+        let mut menu = ColumnarMenu::default().with_fuzzy_matching(true);
+        let mut editor = Editor::default();
+        editor.set_buffer("gcm".to_string(), UndoBehavior::CreateUndoPoint);
+        let mut completer = FakeCompleter::new(&["git commit", "git status", "git checkout main"]);
+
+        menu.update_values(&mut editor, &mut completer);
+        assert_eq!(
+            menu.get_values()
+                .iter()
+                .map(|suggestion| suggestion.value.as_str())
+                .collect::<Vec<_>>(),
+            vec!["git checkout main", "git commit"]
+        );
+        assert_eq!(menu.match_positions[0], vec![0, 4, 13]);
+
+        let style = Style::new();
+        let match_style = Style::new().bold();
+        assert_eq!(
+            menu.styled_value(&menu.values[1], 1, style, match_style),
+            format!(
+                "{}it {}o{}mit",
+                match_style.paint("g"),
+                match_style.paint("c"),
+                match_style.paint("m")
+            )
+        );
+    }
+
+    #[test]
+    fn without_fuzzy_matching_the_values_are_kept() {
+        let mut menu = ColumnarMenu::default();
+        let mut editor = Editor::default();
+        editor.set_buffer("gcm".to_string(), UndoBehavior::CreateUndoPoint);
+        let mut completer = FakeCompleter::new(&["git status", "git commit"]);
+
+        menu.update_values(&mut editor, &mut completer);
+        assert_eq!(menu.get_values().len(), 2);
+        assert!(menu.match_positions.is_empty());
+    }
+
+    #[test]
+    fn sorts_by_priority_and_marks_the_values() {
+        let mut menu = ColumnarMenu::default();
+        menu.set_values(
+            vec![
+                Suggestion {
+                    kind: Some(SuggestionKind::File),
+                    ..fake_suggestion("Cargo.toml", 0)
+                },
+                Suggestion {
+                    kind: Some(SuggestionKind::Directory),
+                    priority: 1,
+                    ..fake_suggestion("src", 0)
+                },
+                Suggestion {
+                    icon: Some("**".to_string()),
+                    ..fake_suggestion("README.md", 0)
+                },
+                fake_suggestion("target", 0),
+            ],
+            "",
+        );
+
+        assert_eq!(
+            menu.get_values()
+                .iter()
+                .map(|suggestion| menu.marked_value(suggestion))
+                .collect::<Vec<_>>(),
+            vec!["d  src", "f  Cargo.toml", "** README.md", "   target"]
+        );
+    }
+
+    #[test]
+    fn documentation_pane_scrolls_the_selected_description() {
+        let mut menu = ColumnarMenu::default().with_documentation_pane(2);
+        menu.values = vec![Suggestion {
+            description: Some("Print more\nof the\nlog".to_string()),
+            ..fake_suggestion("-v", 0)
+        }];
+        menu.longest_suggestion = 2;
+        menu.working_details = ColumnDetails {
+            columns: 1,
+            col_width: 20,
+        };
+
+        assert_eq!(menu.menu_required_lines(20), 4);
+        assert_eq!(
+            menu.menu_string(5, false),
+            ">-V PRINT MORE OF TH\r\nPrint more\r\nof the\r\n[1-2 of 3]"
+        );
+
+        menu.scroll_documentation(true);
+        menu.scroll_documentation(true);
+        assert_eq!(
+            menu.documentation_string(false),
+            "of the\r\nlog\r\n[2-3 of 3]"
+        );
+    }
+
+    #[test]
+    fn chunks_are_loaded_once_the_last_row_is_selected() {
+        let mut menu = ColumnarMenu::default().with_chunk_size(Some(2));
+        let mut editor = Editor::default();
+        let mut completer = FakeCompleter::new(&["a", "b", "c", "d", "e"]);
+        menu.working_details = ColumnDetails {
+            columns: 1,
+            col_width: 10,
+        };
+
+        menu.update_values(&mut editor, &mut completer);
+        assert_eq!(menu.get_values().len(), 2);
+        assert!(!menu.can_partially_complete(true, &mut editor, &mut completer));
+
+        menu.load_next_chunk(&mut completer);
+        assert_eq!(menu.get_values().len(), 2);
+
+        menu.move_next();
+        menu.load_next_chunk(&mut completer);
+        assert_eq!(menu.get_values().len(), 4);
+
+        menu.move_next();
+        menu.move_next();
+        menu.load_next_chunk(&mut completer);
+        assert_eq!(
+            menu.get_values()
+                .iter()
+                .map(|suggestion| suggestion.value.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a", "b", "c", "d", "e"]
+        );
+        assert!(menu.all_loaded);
+    }
+
+    #[test]
+    fn multi_selected_values_are_inserted_together() {
+        let mut menu = ColumnarMenu::default()
+            .with_multi_select(true)
+            .with_multi_select_delimiter(",");
+        let mut editor = Editor::default();
+        menu.update_values(&mut editor, &mut FakeCompleter::new(&["a", "b", "c"]));
+        menu.working_details = ColumnDetails {
+            columns: 3,
+            col_width: 10,
+        };
+
+        menu.move_next();
+        menu.move_next();
+        menu.toggle_multi_select();
+        menu.reset_position();
+        menu.toggle_multi_select();
+        menu.move_next();
+        menu.toggle_multi_select();
+        menu.toggle_multi_select();
+        assert_eq!(
+            menu.menu_string(5, false),
+            "*a        >B        *c        \r\n"
+        );
+
+        menu.replace_in_buffer(&mut editor);
+        assert_eq!(editor.get_buffer(), "c,a");
+    }
+
+    #[test]
+    fn filter_narrows_the_values_until_cleared() {
+        let mut menu = ColumnarMenu::default();
+        let mut editor = Editor::default();
+        menu.update_values(
+            &mut editor,
+            &mut FakeCompleter::new(&["status", "stash", "switch"]),
+        );
+        let get_values = |menu: &ColumnarMenu| {
+            menu.get_values()
+                .iter()
+                .map(|suggestion| suggestion.value.clone())
+                .collect::<Vec<_>>()
+        };
+
+        menu.filter_values(Some("sah".to_string()));
+        assert_eq!(get_values(&menu), vec!["stash"]);
+        assert_eq!(menu.match_positions, vec![vec![0, 2, 4]]);
+
+        menu.filter_values(Some("sw".to_string()));
+        assert_eq!(get_values(&menu), vec!["switch"]);
+
+        menu.filter_values(None);
+        assert_eq!(get_values(&menu), vec!["status", "stash", "switch"]);
+        assert!(menu.match_positions.is_empty());
+    }
+
+    #[test]
+    fn sections_group_the_values_under_headers() {
+        let mut menu = ColumnarMenu::default().with_sections(true);
+        let kinds = [
+            ("-v", SuggestionKind::Flag),
+            ("a.rs", SuggestionKind::File),
+            ("-q", SuggestionKind::Flag),
+            ("b.rs", SuggestionKind::File),
+        ];
+        menu.set_values(
+            kinds
+                .iter()
+                .map(|&(value, kind)| Suggestion {
+                    kind: Some(kind),
+                    ..fake_suggestion(value, 0)
+                })
+                .collect(),
+            "",
+        );
+        menu.working_details = ColumnDetails {
+            columns: 1,
+            col_width: 8,
+        };
+
+        assert_eq!(menu.menu_required_lines(8), 6);
+        assert_eq!(
+            menu.menu_string(10, false),
+            "-- Flags --\r\n>- -V   \r\n- -q    \r\n-- Files --\r\nf a.rs  \r\nf b.rs  \r\n"
+        );
+
+        // The headers can't be selected
+        menu.move_to_click(0, 3, 10);
+        assert_eq!(menu.index(), 0);
+        menu.move_to_click(0, 4, 10);
+        assert_eq!(menu.index(), 2);
+    }
+
+    #[test]
+    fn position_counter_shows_while_values_overflow() {
+        let mut menu = ColumnarMenu::default().with_position_counter(true);
+        let mut editor = Editor::default();
+        menu.update_values(
+            &mut editor,
+            &mut FakeCompleter::new(&["a", "b", "c", "d", "e"]),
+        );
+        menu.working_details = ColumnDetails {
+            columns: 1,
+            col_width: 4,
+        };
+
+        menu.move_next();
+        menu.move_next();
+        assert_eq!(menu.menu_string(3, false), "b   \r\n>C  \r\n3/5");
+
+        // All values fit, the counter is left out
+        assert_eq!(menu.menu_string(5, false).lines().count(), 5);
+    }
+
+    #[test]
+    fn long_values_and_descriptions_fit_their_columns() {
+        let values = vec![
+            Suggestion {
+                description: Some("Print more of the log".to_string()),
+                ..fake_suggestion("--verbose", 0)
+            },
+            Suggestion {
+                description: Some("Quiet".to_string()),
+                ..fake_suggestion("-q", 0)
+            },
+        ];
+        let layout = |menu: ColumnarMenu| {
+            let mut menu = menu.with_max_value_width(Some(5));
+            menu.values = values.clone();
+            menu.longest_suggestion = 5;
+            menu.working_details = ColumnDetails {
+                columns: 1,
+                col_width: 20,
+            };
+            menu
+        };
+
+        let menu = layout(ColumnarMenu::default().with_max_description_width(Some(10)));
+        assert_eq!(
+            menu.menu_string(5, false),
+            ">--VE… PRINT MORE\r\n-q     Quiet\r\n"
+        );
+
+        let menu = layout(
+            ColumnarMenu::default().with_description_overflow(DescriptionOverflow::Ellipsis),
+        );
+        assert_eq!(
+            menu.menu_string(5, false),
+            ">--VE… PRINT MORE O…\r\n-q     Quiet\r\n"
+        );
+
+        let menu =
+            layout(ColumnarMenu::default().with_description_overflow(DescriptionOverflow::Wrap));
+        assert_eq!(menu.menu_required_lines(20), 3);
+        assert_eq!(
+            menu.menu_string(5, false),
+            ">--VE… PRINT MORE OF\r\n       THE LOG\r\n-q     Quiet\r\n"
+        );
+    }
+
+    #[test]
+    fn wrap_words_cuts_words_wider_than_a_line() {
+        assert_eq!(
+            wrap_words("a wrapped description", 7),
+            vec!["a", "wrapped", "descrip", "tion"]
+        );
+        assert_eq!(wrap_words("", 7), vec![""]);
+    }
+
+    #[test]
+    fn quick_select_picks_the_painted_value_with_the_label() {
+        let mut menu = ColumnarMenu::default().with_quick_select(Some(KeyModifiers::ALT));
+        let mut editor = Editor::default();
+        menu.update_values(
+            &mut editor,
+            &mut FakeCompleter::new(&["a", "b", "c", "d", "e"]),
+        );
+        menu.working_details = ColumnDetails {
+            columns: 2,
+            col_width: 5,
+        };
+
+        menu.move_down();
+        menu.move_down();
+        assert_eq!(menu.menu_string(2, false), "1 c  2 d  \r\n3 >E ");
+
+        assert!(!menu.shows_quick_select_label('4', 2));
+        assert!(!menu.quick_select('4', 2));
+        assert!(!menu.quick_select('x', 2));
+        assert!(menu.shows_quick_select_label('2', 2));
+        assert!(menu.quick_select('2', 2));
+        assert_eq!(menu.index(), 3);
+    }
+
+    #[test]
+    fn quick_select_ignores_modifiers_typing_text() {
+        for modifier in [KeyModifiers::NONE, KeyModifiers::SHIFT] {
+            let menu = ColumnarMenu::default().with_quick_select(Some(modifier));
+            assert_eq!(menu.quick_select_modifier(), None);
+        }
+    }
+
     struct FakeCompleter {
         completions: Vec<String>,
     }
@@ -808,6 +2146,9 @@ mod tests {
             extra: None,
             span: Span { start: 0, end: pos },
             append_whitespace: false,
+            kind: None,
+            icon: None,
+            priority: 0,
         }
     }
 }
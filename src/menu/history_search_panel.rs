@@ -0,0 +1,188 @@
+use {
+    super::{Menu, MenuEvent, MenuTextStyle},
+    crate::{
+        core_editor::Editor,
+        painting::{estimate_single_line_wraps, Painter},
+        Completer, StyledText, Suggestion, Theme,
+    },
+    nu_ansi_term::Style,
+    std::ops::Range,
+};
+
+/// Rows of matches painted below the prompt of the reverse history search,
+/// see [`crate::Reedline::with_history_search_panel`]
+///
+/// The panel is not an interactive menu, the search moves the selection and
+/// the panel is recreated for every repaint.
+pub(crate) struct HistorySearchPanel {
+    /// Command lines with the byte ranges matching the search, most recent first
+    rows: Vec<(String, Vec<Range<usize>>)>,
+    selected: Option<usize>,
+    /// Replaces the prompt indicator, like the indicator of a menu
+    indicator: String,
+    color: MenuTextStyle,
+    match_style: Style,
+}
+
+impl HistorySearchPanel {
+    pub(crate) fn new(
+        rows: Vec<(String, Vec<Range<usize>>)>,
+        selected: Option<usize>,
+        indicator: String,
+        theme: &Theme,
+    ) -> Self {
+        let rows = rows
+            .into_iter()
+            // Every command line takes one row, a newline is one byte like the space
+            .map(|(line, ranges)| (line.replace('\n', " "), ranges))
+            .collect();
+        Self {
+            rows,
+            selected,
+            indicator,
+            color: MenuTextStyle::from(theme),
+            match_style: theme.search_match,
+        }
+    }
+
+    fn row_string(&self, index: usize, use_ansi_coloring: bool) -> String {
+        let (line, ranges) = &self.rows[index];
+        let is_selected = self.selected == Some(index);
+        let marker = if is_selected { "> " } else { "  " };
+        if !use_ansi_coloring {
+            return format!("{}{}\r\n", marker, line);
+        }
+
+        let (style, match_style) = if is_selected {
+            let selected = self.color.selected_text_style;
+            (selected, selected.bold())
+        } else {
+            (self.color.text_style, self.match_style)
+        };
+        let mut styled = StyledText::new();
+        styled.push((style, line.clone()));
+        for range in ranges {
+            styled.restyle_range(range.clone(), |_| match_style);
+        }
+        format!("{}{}\r\n", marker, styled.render_simple())
+    }
+}
+
+impl Menu for HistorySearchPanel {
+    fn name(&self) -> &str {
+        "history_search_panel"
+    }
+
+    fn indicator(&self) -> &str {
+        &self.indicator
+    }
+
+    fn is_active(&self) -> bool {
+        true
+    }
+
+    fn menu_event(&mut self, _event: MenuEvent) {}
+
+    fn can_quick_complete(&self) -> bool {
+        false
+    }
+
+    fn can_partially_complete(
+        &mut self,
+        _values_updated: bool,
+        _editor: &mut Editor,
+        _completer: &mut dyn Completer,
+    ) -> bool {
+        false
+    }
+
+    fn update_values(&mut self, _editor: &mut Editor, _completer: &mut dyn Completer) {}
+
+    fn update_working_details(
+        &mut self,
+        _editor: &mut Editor,
+        _completer: &mut dyn Completer,
+        _painter: &Painter,
+    ) {
+    }
+
+    fn replace_in_buffer(&self, _editor: &mut Editor) {}
+
+    fn menu_required_lines(&self, terminal_columns: u16) -> u16 {
+        self.rows
+            .iter()
+            .map(|(line, _)| {
+                1 + estimate_single_line_wraps(&format!("  {}", line), terminal_columns)
+            })
+            .sum::<usize>() as u16
+    }
+
+    fn menu_string(&self, available_lines: u16, use_ansi_coloring: bool) -> String {
+        (0..self.rows.len())
+            .take(available_lines as usize)
+            .map(|index| self.row_string(index, use_ansi_coloring))
+            .collect()
+    }
+
+    fn min_rows(&self) -> u16 {
+        self.rows.len() as u16
+    }
+
+    fn get_values(&self) -> &[Suggestion] {
+        &[]
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) {
+        self.color = MenuTextStyle::from(theme);
+        self.match_style = theme.search_match;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn marks_the_selected_row() {
+        let panel = HistorySearchPanel::new(
+            vec![
+                ("git push".to_string(), std::iter::once(4..8).collect()),
+                (
+                    "git pull\n--rebase".to_string(),
+                    std::iter::once(4..6).collect(),
+                ),
+            ],
+            Some(1),
+            "(search)".to_string(),
+            &Theme::default(),
+        );
+
+        assert_eq!(
+            panel.menu_string(10, false),
+            "  git push\r\n> git pull --rebase\r\n"
+        );
+        assert_eq!(panel.menu_string(1, false), "  git push\r\n");
+    }
+
+    #[test]
+    fn styles_the_matches() {
+        let theme = Theme::default();
+        let panel = HistorySearchPanel::new(
+            vec![("git push".to_string(), std::iter::once(4..6).collect())],
+            None,
+            "(search)".to_string(),
+            &theme,
+        );
+
+        assert_eq!(
+            panel.menu_string(10, true),
+            format!(
+                "  {}{}{}\r\n",
+                theme.menu_text.paint("git "),
+                theme.search_match.paint("pu"),
+                theme.menu_text.paint("sh")
+            )
+        );
+    }
+}
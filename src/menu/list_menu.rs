@@ -3,12 +3,14 @@ use crate::{core_editor::Editor, UndoBehavior};
 use {
     super::{
         menu_functions::{parse_selection_char, string_difference},
-        Menu, MenuEvent, MenuTextStyle,
+        quick_select_label, quick_select_modifier, quick_select_position, HistoryColumn, Menu,
+        MenuEvent, MenuPlacement, MenuSections, MenuTextStyle,
     },
     crate::{
         painting::{estimate_single_line_wraps, Painter},
-        Completer, Suggestion,
+        Completer, Suggestion, SuggestionKind, Theme,
     },
+    crossterm::event::KeyModifiers,
     nu_ansi_term::{ansi::RESET, Style},
     std::iter::Sum,
     unicode_width::UnicodeWidthStr,
@@ -77,6 +79,16 @@ pub struct ListMenu {
     /// Calls the completer using only the line buffer difference difference
     /// after the menu was activated
     only_buffer_difference: bool,
+    /// The completer was still computing suggestions when queried
+    loading: bool,
+    /// Section headers in front of the runs of values of the same kind
+    sections: MenuSections,
+    /// Where the menu is painted relative to the prompt
+    placement: MenuPlacement,
+    /// Columns of the history entries shown in front of them
+    history_columns: Vec<HistoryColumn>,
+    /// Modifier of the keys accepting the entries by their labels
+    quick_select: Option<KeyModifiers>,
 }
 
 impl Default for ListMenu {
@@ -97,6 +109,11 @@ impl Default for ListMenu {
             event: None,
             input: None,
             only_buffer_difference: true,
+            loading: false,
+            sections: MenuSections::default(),
+            placement: MenuPlacement::default(),
+            history_columns: Vec::new(),
+            quick_select: None,
         }
     }
 }
@@ -144,6 +161,28 @@ impl ListMenu {
         self.only_buffer_difference = only_buffer_difference;
         self
     }
+
+    /// Menu builder with the placement of the menu above or below the prompt
+    #[must_use]
+    pub fn with_placement(mut self, placement: MenuPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Menu builder with section headers like "Files" or "Flags" in front of
+    /// the runs of values of the same [`SuggestionKind`]
+    #[must_use]
+    pub fn with_sections(mut self, sections: bool) -> Self {
+        self.sections.set_enabled(sections);
+        self
+    }
+
+    /// Menu builder with a style for the values of a section and its header
+    #[must_use]
+    pub fn with_section_style(mut self, kind: SuggestionKind, style: Style) -> Self {
+        self.sections.set_style(kind, style);
+        self
+    }
 }
 
 // Menu functionality
@@ -162,6 +201,95 @@ impl ListMenu {
         self
     }
 
+    /// Menu builder with columns in front of the entries of a history menu,
+    /// showing when, where and how they ran in the order of `columns`
+    ///
+    /// The columns are read from the [`Suggestion::extra`] of the values as
+    /// filled by the history, a menu with another completer shows them empty.
+    #[must_use]
+    pub fn with_history_columns(mut self, columns: &[HistoryColumn]) -> Self {
+        self.history_columns = columns.to_vec();
+        self
+    }
+
+    /// Menu builder labeling the entries of the page `1` to `9` and `a` to `z`
+    /// in place of their numbers, accepting an entry right away when its label
+    /// is pressed with `modifier`, like `Alt`
+    ///
+    /// No modifier and `Shift` alone are ignored, as they type text.
+    #[must_use]
+    pub fn with_quick_select(mut self, modifier: Option<KeyModifiers>) -> Self {
+        self.quick_select = quick_select_modifier(modifier);
+        self
+    }
+
+    /// The row of the entry of the page labeled `label`
+    fn quick_select_row(&self, label: char) -> Option<usize> {
+        let page_size = self.pages.get(self.page).map_or(0, |page| page.size);
+        quick_select_position(label).filter(|&position| position < page_size)
+    }
+
+    /// The text of a history column of a value, empty if it has none
+    fn history_column(suggestion: &Suggestion, column: HistoryColumn) -> &str {
+        suggestion
+            .extra
+            .as_ref()
+            .and_then(|extra| extra.get(column.position()))
+            .map_or("", String::as_str)
+    }
+
+    /// Width of each history column, fitting its widest text among the values
+    fn history_column_widths(&self) -> Vec<usize> {
+        self.history_columns
+            .iter()
+            .map(|&column| {
+                self.get_values()
+                    .iter()
+                    .map(|suggestion| Self::history_column(suggestion, column).width())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    /// The history columns of a value, each followed by a space
+    fn history_columns_string(&self, suggestion: &Suggestion, widths: &[usize]) -> String {
+        self.history_columns
+            .iter()
+            .zip(widths)
+            .map(|(&column, &width)| {
+                let text = Self::history_column(suggestion, column);
+                format!(
+                    "{}{:pad$} ",
+                    text,
+                    "",
+                    pad = width.saturating_sub(text.width())
+                )
+            })
+            .collect()
+    }
+
+    /// Columns left for the entries by the history columns in front of them
+    fn entries_columns(&self, terminal_columns: u16) -> u16 {
+        let history_columns: usize = self
+            .history_column_widths()
+            .iter()
+            .map(|width| width + 1)
+            .sum();
+        terminal_columns.saturating_sub(history_columns as u16)
+    }
+
+    /// Columns left for the entry at `index` by the row number and the
+    /// indicator in front of it, e.g. 0: XXXX
+    fn entry_columns(&self, index: usize, entries_columns: u16) -> u16 {
+        let number_width = if self.quick_select.is_some() {
+            1
+        } else {
+            count_digits(index)
+        };
+        entries_columns.saturating_sub(self.indicator().width() as u16 + number_width)
+    }
+
     fn update_row_pos(&mut self, new_pos: Option<usize>) {
         if let (Some(row), Some(page)) = (new_pos, self.pages.get(self.page)) {
             let values_before_page = self.pages.iter().take(self.page).sum::<Page>().size;
@@ -177,6 +305,31 @@ impl ListMenu {
         number_of_lines(entry, self.max_lines as usize, terminal_columns)
     }
 
+    /// Position in the page of the entry painted at the given row of the menu
+    fn entry_at_row(&self, row: u16, terminal_columns: u16) -> Option<u16> {
+        let page_size = self.pages.get(self.page)?.size;
+        let headers = self.sections.headers(self.get_values());
+        let entries_columns = self.entries_columns(terminal_columns);
+        let mut next_row = 0;
+        for (index, suggestion) in self.get_values().iter().take(page_size).enumerate() {
+            if headers[index] {
+                next_row += 1;
+                if row < next_row {
+                    return None;
+                }
+            }
+            next_row += self.number_of_lines(
+                &suggestion.value,
+                self.entry_columns(index, entries_columns),
+            );
+            if row < next_row {
+                return Some(index as u16);
+            }
+        }
+
+        None
+    }
+
     fn total_values(&self) -> usize {
         self.query_size.unwrap_or(self.values.len())
     }
@@ -213,6 +366,8 @@ impl ListMenu {
         // The number 2 comes from the prompt line and the banner printed at the bottom
         // of the menu
         let available_lines = painter.screen_height().saturating_sub(2);
+        let headers = self.sections.headers(self.get_values());
+        let entries_columns = self.entries_columns(painter.screen_width());
         let (printable_entries, _) =
             self.get_values()
                 .iter()
@@ -222,12 +377,10 @@ impl ListMenu {
                         None => (lines, None),
                         Some(total_lines) => {
                             let new_total_lines = total_lines
+                                + u16::from(headers[lines])
                                 + self.number_of_lines(
                                     &suggestion.value,
-                                    //  to account for the index and the indicator e.g. 0: XXXX
-                                    painter.screen_width().saturating_sub(
-                                        self.indicator().width() as u16 + count_digits(lines),
-                                    ),
+                                    self.entry_columns(lines, entries_columns),
                                 );
 
                             if new_total_lines < available_lines {
@@ -266,13 +419,15 @@ impl ListMenu {
         };
 
         let full_page = if page.full { "[FULL]" } else { "" };
+        let loading = if self.loading { "[LOADING]" } else { "" };
         let status_bar = format!(
-            "Page {}: records {} - {}  total: {}  {}",
+            "Page {}: records {} - {}  total: {}  {}{}",
             self.page + 1,
             value_before,
             values_until,
             self.total_values(),
             full_page,
+            loading,
         );
 
         if use_ansi_coloring {
@@ -297,7 +452,14 @@ impl ListMenu {
         if index == self.index() {
             self.color.selected_text_style.prefix().to_string()
         } else {
-            self.color.text_style.prefix().to_string()
+            let kind = self
+                .get_values()
+                .get(index)
+                .and_then(|suggestion| suggestion.kind);
+            self.sections
+                .style(kind, self.color.text_style)
+                .prefix()
+                .to_string()
         }
     }
 
@@ -305,11 +467,22 @@ impl ListMenu {
     fn create_string(
         &self,
         line: &str,
+        columns: &str,
         description: Option<&str>,
         index: usize,
         row_number: &str,
         use_ansi_coloring: bool,
     ) -> String {
+        let columns = if use_ansi_coloring && !columns.is_empty() {
+            format!(
+                "{}{}{}",
+                self.color.description_style.prefix(),
+                columns,
+                RESET
+            )
+        } else {
+            columns.to_string()
+        };
         let description = description.map_or("".to_string(), |desc| {
             if use_ansi_coloring {
                 format!(
@@ -325,8 +498,9 @@ impl ListMenu {
 
         if use_ansi_coloring {
             format!(
-                "{}{}{}{}{}{}",
+                "{}{}{}{}{}{}{}",
                 row_number,
+                columns,
                 description,
                 self.text_style(index),
                 &line,
@@ -337,9 +511,15 @@ impl ListMenu {
             // If no ansi coloring is found, then the selection word is
             // the line in uppercase
             let line_str = if index == self.index() {
-                format!("{}{}>{}", row_number, description, line.to_uppercase())
+                format!(
+                    "{}{}{}>{}",
+                    row_number,
+                    columns,
+                    description,
+                    line.to_uppercase()
+                )
             } else {
-                format!("{}{}{}", row_number, description, line)
+                format!("{}{}{}{}", row_number, columns, description, line)
             };
 
             // Final string with formatting
@@ -368,6 +548,10 @@ impl Menu for ListMenu {
         false
     }
 
+    fn placement(&self) -> MenuPlacement {
+        self.placement
+    }
+
     /// The menu should not try to auto complete to avoid comparing
     /// all registered values
     fn can_partially_complete(
@@ -435,7 +619,8 @@ impl Menu for ListMenu {
         } else {
             self.query_size = None;
             completer.complete(input, start)
-        }
+        };
+        self.loading = completer.has_pending_completions();
     }
 
     /// Gets values from cached values that will be displayed in the menu
@@ -465,6 +650,14 @@ impl Menu for ListMenu {
         }
     }
 
+    fn get_selected_value(&self) -> Option<Suggestion> {
+        self.get_value()
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) {
+        self.color = MenuTextStyle::from(theme);
+    }
+
     /// The buffer gets cleared with the actual value
     fn replace_in_buffer(&self, editor: &mut Editor) {
         if let Some(Suggestion {
@@ -525,6 +718,15 @@ impl Menu for ListMenu {
                         full: false,
                     });
                 }
+                MenuEvent::Refresh => {
+                    self.update_values(editor, completer);
+                    let size = self.printable_entries(painter);
+                    match self.pages.get_mut(self.page) {
+                        Some(page) => page.size = size,
+                        None => self.pages.push(Page { size, full: false }),
+                    }
+                    self.row_position = self.row_position.min(size.saturating_sub(1) as u16);
+                }
                 MenuEvent::NextElement | MenuEvent::MoveDown | MenuEvent::MoveRight => {
                     let new_pos = self.row_position + 1;
 
@@ -580,6 +782,11 @@ impl Menu for ListMenu {
                         self.update_values(editor, completer);
                     }
                 }
+                MenuEvent::Click { row, .. } => {
+                    if let Some(new_pos) = self.entry_at_row(row, painter.screen_width()) {
+                        self.row_position = new_pos;
+                    }
+                }
                 MenuEvent::PreviousPage => {
                     match self.page.checked_sub(1) {
                         Some(page_num) => self.page = page_num,
@@ -587,6 +794,15 @@ impl Menu for ListMenu {
                     }
                     self.update_values(editor, completer);
                 }
+                MenuEvent::DocumentationDown | MenuEvent::DocumentationUp => {
+                    // The list menu shows the descriptions in the entries
+                }
+                MenuEvent::ToggleMultiSelect => {
+                    // The list menu inserts one entry at a time
+                }
+                MenuEvent::Filter(_) => {
+                    // The list menu is filtered by the search in the buffer
+                }
             }
 
             self.event = None;
@@ -596,15 +812,15 @@ impl Menu for ListMenu {
     /// Calculates the real required lines for the menu considering how many lines
     /// wrap the terminal and if an entry is larger than the remaining lines
     fn menu_required_lines(&self, terminal_columns: u16) -> u16 {
+        let headers = self.sections.headers(self.get_values());
+        let entries_columns = self.entries_columns(terminal_columns);
         let mut entry_index = 0;
         self.get_values().iter().fold(0, |total_lines, suggestion| {
-            //  to account for the the index and the indicator e.g. 0: XXXX
             let ret = total_lines
+                + u16::from(headers[entry_index])
                 + self.number_of_lines(
                     &suggestion.value,
-                    terminal_columns.saturating_sub(
-                        self.indicator().width() as u16 + count_digits(entry_index),
-                    ),
+                    self.entry_columns(entry_index, entries_columns),
                 );
             entry_index += 1;
             ret
@@ -616,6 +832,8 @@ impl Menu for ListMenu {
         let values_before_page = self.pages.iter().take(self.page).sum::<Page>().size;
         match self.pages.get(self.page) {
             Some(page) => {
+                let headers = self.sections.headers(self.get_values());
+                let column_widths = self.history_column_widths();
                 let lines_string = self
                     .get_values()
                     .iter()
@@ -623,7 +841,10 @@ impl Menu for ListMenu {
                     .enumerate()
                     .map(|(index, suggestion)| {
                         // Final string with colors
-                        let line = &suggestion.value;
+                        let line = &match suggestion.marker() {
+                            Some(marker) => format!("{} {}", marker, suggestion.value),
+                            None => suggestion.value.clone(),
+                        };
                         let line = if line.lines().count() > self.max_lines as usize {
                             let lines = line
                                 .lines()
@@ -636,15 +857,31 @@ impl Menu for ListMenu {
                             line.replace('\n', &format!("\r\n{}", self.multiline_marker))
                         };
 
-                        let row_number = format!("{}: ", index + values_before_page);
+                        let row_number = if self.quick_select.is_some() {
+                            format!("{}: ", quick_select_label(index))
+                        } else {
+                            format!("{}: ", index + values_before_page)
+                        };
+                        let columns = self.history_columns_string(suggestion, &column_widths);
 
-                        self.create_string(
+                        let entry = self.create_string(
                             &line,
+                            &columns,
                             suggestion.description.as_deref(),
                             index,
                             &row_number,
                             use_ansi_coloring,
-                        )
+                        );
+                        if headers[index] {
+                            let header = self.sections.header(
+                                suggestion.kind,
+                                self.color.text_style,
+                                use_ansi_coloring,
+                            );
+                            format!("{}{}{}", header, Self::end_of_line(), entry)
+                        } else {
+                            entry
+                        }
                     })
                     .collect::<String>();
 
@@ -662,6 +899,24 @@ impl Menu for ListMenu {
     fn min_rows(&self) -> u16 {
         self.max_lines + 1
     }
+
+    fn quick_select_modifier(&self) -> Option<KeyModifiers> {
+        self.quick_select
+    }
+
+    fn shows_quick_select_label(&self, label: char, _available_lines: u16) -> bool {
+        self.quick_select_row(label).is_some()
+    }
+
+    fn quick_select(&mut self, label: char, _available_lines: u16) -> bool {
+        match self.quick_select_row(label) {
+            Some(position) => {
+                self.row_position = position as u16;
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 fn number_of_lines(entry: &str, max_lines: usize, terminal_columns: u16) -> u16 {
@@ -703,6 +958,7 @@ fn count_digits(mut n: usize) -> u16 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Span;
 
     #[test]
     fn number_of_lines_test() {
@@ -729,6 +985,91 @@ mod tests {
         assert_eq!(res, 6);
     }
 
+    #[test]
+    fn entry_at_row_test() {
+        let menu = ListMenu {
+            values: ["a", "b\nc", "d"]
+                .iter()
+                .map(|value| Suggestion {
+                    value: value.to_string(),
+                    description: None,
+                    extra: None,
+                    span: Span { start: 0, end: 0 },
+                    append_whitespace: false,
+                    kind: None,
+                    icon: None,
+                    priority: 0,
+                })
+                .collect(),
+            pages: vec![Page {
+                size: 3,
+                full: false,
+            }],
+            ..ListMenu::default()
+        };
+
+        assert_eq!(menu.entry_at_row(0, 30), Some(0));
+        assert_eq!(menu.entry_at_row(2, 30), Some(1));
+        assert_eq!(menu.entry_at_row(3, 30), Some(2));
+        // The banner follows the entries
+        assert_eq!(menu.entry_at_row(4, 30), None);
+    }
+
+    #[test]
+    fn headers_are_not_entries() {
+        let mut menu = ListMenu::default().with_sections(true);
+        menu.values = [
+            ("a", SuggestionKind::File),
+            ("b", SuggestionKind::File),
+            ("-c", SuggestionKind::Flag),
+        ]
+        .iter()
+        .map(|&(value, kind)| Suggestion {
+            value: value.to_string(),
+            kind: Some(kind),
+            ..Suggestion::default()
+        })
+        .collect();
+        menu.pages = vec![Page {
+            size: 3,
+            full: false,
+        }];
+
+        assert_eq!(menu.menu_required_lines(30), 6);
+        assert_eq!(menu.entry_at_row(0, 30), None);
+        assert_eq!(menu.entry_at_row(2, 30), Some(1));
+        assert_eq!(menu.entry_at_row(3, 30), None);
+        assert_eq!(menu.entry_at_row(4, 30), Some(2));
+    }
+
+    #[test]
+    fn history_columns_are_aligned_in_front_of_the_entries() {
+        let mut menu = ListMenu::default()
+            .with_history_columns(&[HistoryColumn::ExitStatus, HistoryColumn::Cwd]);
+        menu.values = [("ls", "/tmp", "0"), ("cargo test", "/home/user", "101")]
+            .iter()
+            .map(|&(value, cwd, exit_status)| Suggestion {
+                value: value.to_string(),
+                extra: Some(vec![
+                    String::new(),
+                    cwd.to_string(),
+                    String::new(),
+                    exit_status.to_string(),
+                ]),
+                ..Suggestion::default()
+            })
+            .collect();
+        menu.pages = vec![Page {
+            size: 2,
+            full: false,
+        }];
+
+        assert_eq!(
+            menu.menu_string(10, false),
+            "0: 0   /tmp       >LS\r\n1: 101 /home/user cargo test\r\nPage 1: records 0 - 1  total: 2  "
+        );
+    }
+
     #[test]
     fn number_of_max_lines_test() {
         let input = "let a\n: ano\nther:\nsomething\nanother\nmore\nanother\nasdf\nasdfa\n3123";
@@ -459,6 +459,9 @@ mod tests {
                 extra: None,
                 span: Span::new(0, s.len()),
                 append_whitespace: false,
+                kind: None,
+                icon: None,
+                priority: 0,
             })
             .collect();
         let res = find_common_string(&input);
@@ -478,6 +481,9 @@ mod tests {
                 extra: None,
                 span: Span::new(0, s.len()),
                 append_whitespace: false,
+                kind: None,
+                icon: None,
+                priority: 0,
             })
             .collect();
         let res = find_common_string(&input);
@@ -1,13 +1,18 @@
 mod columnar_menu;
+mod history_search_panel;
 mod list_menu;
 pub mod menu_functions;
 
 use crate::core_editor::Editor;
-use crate::History;
-use crate::{completion::history::HistoryCompleter, painting::Painter, Completer, Suggestion};
+use crate::{
+    completion::history::HistoryCompleter, painting::Painter, Completer, Suggestion,
+    SuggestionKind, Theme,
+};
 pub use columnar_menu::ColumnarMenu;
+use crossterm::event::KeyModifiers;
+pub(crate) use history_search_panel::HistorySearchPanel;
 pub use list_menu::ListMenu;
-use nu_ansi_term::{Color, Style};
+use nu_ansi_term::Style;
 
 /// Struct to store the menu style
 pub struct MenuTextStyle {
@@ -21,10 +26,130 @@ pub struct MenuTextStyle {
 
 impl Default for MenuTextStyle {
     fn default() -> Self {
+        Self::from(&Theme::default())
+    }
+}
+
+impl From<&Theme> for MenuTextStyle {
+    fn from(theme: &Theme) -> Self {
         Self {
-            selected_text_style: Color::Green.bold().reverse(),
-            text_style: Color::DarkGray.normal(),
-            description_style: Color::Yellow.normal(),
+            selected_text_style: theme.menu_selected_text,
+            text_style: theme.menu_text,
+            description_style: theme.menu_description,
+        }
+    }
+}
+
+/// Where a menu is painted relative to the prompt
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MenuPlacement {
+    /// In the rows below the prompt, scrolling the screen up if they are too
+    /// few (default)
+    #[default]
+    Below,
+    /// In rows opened above the prompt by moving it down, scrolling the screen
+    /// up if needed. The prompt moves back up once the menu closes.
+    Above,
+    /// Above the prompt when more of the menu fits there than below it
+    Auto,
+}
+
+/// A column of a history menu showing when, where or how an entry ran, read
+/// from the [`crate::HistoryItem`] behind it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryColumn {
+    /// When the command started, in local time
+    Timestamp,
+    /// The directory the command ran in
+    Cwd,
+    /// How long the command took to complete
+    Duration,
+    /// The exit status of the command
+    ExitStatus,
+}
+
+impl HistoryColumn {
+    /// Position of the column in the [`Suggestion::extra`] of the suggestions
+    /// of the history completer
+    pub(crate) fn position(self) -> usize {
+        self as usize
+    }
+}
+
+/// How a menu shows a description longer than its column
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DescriptionOverflow {
+    /// Cut at the end of the column (default)
+    #[default]
+    Cut,
+    /// Cut at the end of the column, ending in an ellipsis
+    Ellipsis,
+    /// Wrapped at word boundaries over as many rows as it takes
+    Wrap,
+}
+
+/// Section headers grouping the values of a menu by their
+/// [`SuggestionKind`], each section with a style of its own
+#[derive(Default)]
+pub(crate) struct MenuSections {
+    enabled: bool,
+    styles: Vec<(SuggestionKind, Style)>,
+}
+
+impl MenuSections {
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn set_style(&mut self, kind: SuggestionKind, style: Style) {
+        self.styles.retain(|(styled, _)| *styled != kind);
+        self.styles.push((kind, style));
+    }
+
+    /// Whether a header is painted in front of each of the values: before the
+    /// first value of each run of values of the same kind, unless no value has
+    /// a kind
+    pub(crate) fn headers(&self, values: &[Suggestion]) -> Vec<bool> {
+        if !self.enabled || values.iter().all(|suggestion| suggestion.kind.is_none()) {
+            return vec![false; values.len()];
+        }
+        let mut previous = None;
+        values
+            .iter()
+            .enumerate()
+            .map(|(index, suggestion)| {
+                let starts_section = index == 0 || previous != Some(suggestion.kind);
+                previous = Some(suggestion.kind);
+                starts_section
+            })
+            .collect()
+    }
+
+    /// The style of the values of a section, `text_style` without a style of
+    /// their own
+    pub(crate) fn style(&self, kind: Option<SuggestionKind>, text_style: Style) -> Style {
+        self.styles
+            .iter()
+            .find(|(styled, _)| Some(*styled) == kind)
+            .map_or(text_style, |(_, style)| *style)
+    }
+
+    /// The header of the section of `kind`, in the bold style of the section
+    pub(crate) fn header(
+        &self,
+        kind: Option<SuggestionKind>,
+        text_style: Style,
+        use_ansi_coloring: bool,
+    ) -> String {
+        let title = kind.map_or("Other", SuggestionKind::title);
+        if use_ansi_coloring {
+            self.style(kind, text_style).bold().paint(title).to_string()
+        } else {
+            format!("-- {} --", title)
         }
     }
 }
@@ -40,6 +165,9 @@ pub enum MenuEvent {
     /// Line buffer edit event. When the bool is true it means that the values
     /// have already being updated. This is true when the option `quick_completions` is true
     Edit(bool),
+    /// Updating the values with the suggestions that arrived since, keeping the
+    /// selected element
+    Refresh,
     /// Selecting next element in the menu
     NextElement,
     /// Selecting previous element in the menu
@@ -56,6 +184,23 @@ pub enum MenuEvent {
     NextPage,
     /// Move to previous page
     PreviousPage,
+    /// Scrolling down the documentation of the selected element
+    DocumentationDown,
+    /// Scrolling up the documentation of the selected element
+    DocumentationUp,
+    /// Adding the selected element to the elements inserted together, or
+    /// removing it
+    ToggleMultiSelect,
+    /// Showing only the elements matching the typed filter, or all of them
+    /// again when there is none
+    Filter(Option<String>),
+    /// Selecting the element painted at the clicked cell of the menu
+    Click {
+        /// Column of the cell
+        column: u16,
+        /// Row of the cell, counted from the first row of the menu
+        row: u16,
+    },
 }
 
 /// Trait that defines how a menu will be printed by the painter
@@ -76,6 +221,18 @@ pub trait Menu: Send {
     /// active even with one element
     fn can_quick_complete(&self) -> bool;
 
+    /// Whether opening the menu inserts the common prefix of the values
+    /// instead while there is one to insert, bash-style, so that the menu
+    /// only opens once the prefix is typed out
+    fn inserts_common_prefix_first(&self) -> bool {
+        false
+    }
+
+    /// Where the menu is painted relative to the prompt
+    fn placement(&self) -> MenuPlacement {
+        MenuPlacement::Below
+    }
+
     /// The completion menu can try to find the common string and replace it
     /// in the given line buffer
     fn can_partially_complete(
@@ -119,9 +276,59 @@ pub trait Menu: Send {
 
     /// Gets cached values from menu that will be displayed
     fn get_values(&self) -> &[Suggestion];
+
+    /// The value the selection of the menu is on, used by
+    /// [`crate::ReedlineEvent::DeleteHistoryEntry`] in the history menu
+    fn get_selected_value(&self) -> Option<Suggestion> {
+        None
+    }
+
+    /// Style the menu as set by [`crate::Reedline::with_theme`]
+    fn apply_theme(&mut self, _theme: &Theme) {}
+
+    /// The modifier of the keys accepting the entries painted with their
+    /// labels, None while the menu paints no labels
+    fn quick_select_modifier(&self) -> Option<KeyModifiers> {
+        None
+    }
+
+    /// Whether an entry is painted with `label` in the `available_lines` of
+    /// the menu
+    fn shows_quick_select_label(&self, _label: char, _available_lines: u16) -> bool {
+        false
+    }
+
+    /// Selects the entry painted with `label` in the `available_lines` of the
+    /// menu, returning false if no entry has the label
+    fn quick_select(&mut self, _label: char, _available_lines: u16) -> bool {
+        false
+    }
+}
+
+/// Labels of the entries of a menu accepted with a key, in the order the
+/// entries are painted
+pub(crate) const QUICK_SELECT_LABELS: &str = "123456789abcdefghijklmnopqrstuvwxyz";
+
+/// The label of the `position`-th painted entry of a menu, a space for the
+/// entries past the last label
+pub(crate) fn quick_select_label(position: usize) -> char {
+    QUICK_SELECT_LABELS.chars().nth(position).unwrap_or(' ')
+}
+
+/// The position among the painted entries of a menu of the entry with `label`
+pub(crate) fn quick_select_position(label: char) -> Option<usize> {
+    QUICK_SELECT_LABELS.chars().position(|found| found == label)
+}
+
+/// The modifier of the quick select keys, without the ones typing text
+pub(crate) fn quick_select_modifier(modifier: Option<KeyModifiers>) -> Option<KeyModifiers> {
+    modifier.filter(|modifier| !matches!(*modifier, KeyModifiers::NONE | KeyModifiers::SHIFT))
 }
 
 /// Allowed menus in Reedline
+///
+/// New kinds of menus may be added, match them with a wildcard arm.
+#[non_exhaustive]
 pub enum ReedlineMenu {
     /// Menu that uses Reedline's completer to update its values
     EngineCompleter(Box<dyn Menu>),
@@ -134,6 +341,25 @@ pub enum ReedlineMenu {
         /// External completer defined outside Reedline
         completer: Box<dyn Completer>,
     },
+    /// Menu picking one of the items of the host, like a saved session or an
+    /// SSH host, opened with [`crate::Reedline::open_picker`]. The picked item
+    /// is returned in [`crate::Signal::Picked`] instead of being inserted.
+    Picker {
+        /// Base menu
+        menu: Box<dyn Menu>,
+        /// Items to pick from
+        items: Vec<Suggestion>,
+    },
+}
+
+/// Completer of a [`ReedlineMenu::Picker`], suggesting all of its items
+/// whatever is typed
+struct PickerCompleter<'menu>(&'menu [Suggestion]);
+
+impl Completer for PickerCompleter<'_> {
+    fn complete(&mut self, _line: &str, _pos: usize) -> Vec<Suggestion> {
+        self.0.to_vec()
+    }
 }
 
 impl ReedlineMenu {
@@ -141,7 +367,8 @@ impl ReedlineMenu {
         match self {
             Self::EngineCompleter(menu)
             | Self::HistoryMenu(menu)
-            | Self::WithCompleter { menu, .. } => menu.as_ref(),
+            | Self::WithCompleter { menu, .. }
+            | Self::Picker { menu, .. } => menu.as_ref(),
         }
     }
 
@@ -149,29 +376,36 @@ impl ReedlineMenu {
         match self {
             Self::EngineCompleter(menu)
             | Self::HistoryMenu(menu)
-            | Self::WithCompleter { menu, .. } => menu.as_mut(),
+            | Self::WithCompleter { menu, .. }
+            | Self::Picker { menu, .. } => menu.as_mut(),
         }
     }
 
+    pub(crate) fn apply_theme(&mut self, theme: &Theme) {
+        self.as_mut().apply_theme(theme);
+    }
+
     pub(crate) fn can_partially_complete(
         &mut self,
         values_updated: bool,
         editor: &mut Editor,
         completer: &mut dyn Completer,
-        history: &dyn History,
+        history_completer: &mut HistoryCompleter,
     ) -> bool {
         match self {
             Self::EngineCompleter(menu) => {
                 menu.can_partially_complete(values_updated, editor, completer)
             }
             Self::HistoryMenu(menu) => {
-                let mut history_completer = HistoryCompleter::new(history);
-                menu.can_partially_complete(values_updated, editor, &mut history_completer)
+                menu.can_partially_complete(values_updated, editor, history_completer)
             }
             Self::WithCompleter {
                 menu,
                 completer: own_completer,
             } => menu.can_partially_complete(values_updated, editor, own_completer.as_mut()),
+            Self::Picker { menu, items } => {
+                menu.can_partially_complete(values_updated, editor, &mut PickerCompleter(items))
+            }
         }
     }
 
@@ -179,13 +413,12 @@ impl ReedlineMenu {
         &mut self,
         editor: &mut Editor,
         completer: &mut dyn Completer,
-        history: &dyn History,
+        history_completer: &mut HistoryCompleter,
     ) {
         match self {
             Self::EngineCompleter(menu) => menu.update_values(editor, completer),
             Self::HistoryMenu(menu) => {
-                let mut history_completer = HistoryCompleter::new(history);
-                menu.update_values(editor, &mut history_completer);
+                menu.update_values(editor, history_completer);
             }
             Self::WithCompleter {
                 menu,
@@ -193,6 +426,21 @@ impl ReedlineMenu {
             } => {
                 menu.update_values(editor, own_completer.as_mut());
             }
+            Self::Picker { menu, items } => {
+                menu.update_values(editor, &mut PickerCompleter(items));
+            }
+        }
+    }
+
+    /// Whether the completer of the menu is still computing its suggestions
+    pub(crate) fn has_pending_completions(&self, completer: &dyn Completer) -> bool {
+        match self {
+            Self::EngineCompleter(_) => completer.has_pending_completions(),
+            Self::HistoryMenu(_) | Self::Picker { .. } => false,
+            Self::WithCompleter {
+                completer: own_completer,
+                ..
+            } => own_completer.has_pending_completions(),
         }
     }
 
@@ -200,7 +448,7 @@ impl ReedlineMenu {
         &mut self,
         editor: &mut Editor,
         completer: &mut dyn Completer,
-        history: &dyn History,
+        history_completer: &mut HistoryCompleter,
         painter: &Painter,
     ) {
         match self {
@@ -208,8 +456,7 @@ impl ReedlineMenu {
                 menu.update_working_details(editor, completer, painter);
             }
             Self::HistoryMenu(menu) => {
-                let mut history_completer = HistoryCompleter::new(history);
-                menu.update_working_details(editor, &mut history_completer, painter);
+                menu.update_working_details(editor, history_completer, painter);
             }
             Self::WithCompleter {
                 menu,
@@ -217,6 +464,9 @@ impl ReedlineMenu {
             } => {
                 menu.update_working_details(editor, own_completer.as_mut(), painter);
             }
+            Self::Picker { menu, items } => {
+                menu.update_working_details(editor, &mut PickerCompleter(items), painter);
+            }
         }
     }
 }
@@ -242,6 +492,14 @@ impl Menu for ReedlineMenu {
         self.as_ref().can_quick_complete()
     }
 
+    fn inserts_common_prefix_first(&self) -> bool {
+        self.as_ref().inserts_common_prefix_first()
+    }
+
+    fn placement(&self) -> MenuPlacement {
+        self.as_ref().placement()
+    }
+
     fn can_partially_complete(
         &mut self,
         values_updated: bool,
@@ -256,6 +514,9 @@ impl Menu for ReedlineMenu {
                 menu,
                 completer: own_completer,
             } => menu.can_partially_complete(values_updated, editor, own_completer.as_mut()),
+            Self::Picker { menu, items } => {
+                menu.can_partially_complete(values_updated, editor, &mut PickerCompleter(items))
+            }
         }
     }
 
@@ -270,6 +531,9 @@ impl Menu for ReedlineMenu {
             } => {
                 menu.update_values(editor, own_completer.as_mut());
             }
+            Self::Picker { menu, items } => {
+                menu.update_values(editor, &mut PickerCompleter(items));
+            }
         }
     }
 
@@ -289,6 +553,9 @@ impl Menu for ReedlineMenu {
             } => {
                 menu.update_working_details(editor, own_completer.as_mut(), painter);
             }
+            Self::Picker { menu, items } => {
+                menu.update_working_details(editor, &mut PickerCompleter(items), painter);
+            }
         }
     }
 
@@ -312,4 +579,21 @@ impl Menu for ReedlineMenu {
     fn get_values(&self) -> &[Suggestion] {
         self.as_ref().get_values()
     }
+
+    fn get_selected_value(&self) -> Option<Suggestion> {
+        self.as_ref().get_selected_value()
+    }
+
+    fn quick_select_modifier(&self) -> Option<KeyModifiers> {
+        self.as_ref().quick_select_modifier()
+    }
+
+    fn shows_quick_select_label(&self, label: char, available_lines: u16) -> bool {
+        self.as_ref()
+            .shows_quick_select_label(label, available_lines)
+    }
+
+    fn quick_select(&mut self, label: char, available_lines: u16) -> bool {
+        self.as_mut().quick_select(label, available_lines)
+    }
 }
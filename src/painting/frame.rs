@@ -0,0 +1,252 @@
+use super::utils::{estimate_required_lines, strip_ansi};
+use crossterm::{
+    cursor::MoveTo,
+    style::Print,
+    terminal::{Clear, ClearType},
+    QueueableCommand, Result,
+};
+use std::io::Write;
+
+/// Escape sequence of `crossterm::cursor::SavePosition`, the line holding the
+/// cursor is always repainted to save its position again
+const SAVE_POSITION: &str = "\x1b7";
+
+/// Prompt, buffer and menu as painted during one repaint
+///
+/// Each segment of the frame is painted from the start of a row, like the menu
+/// below the buffer, and split into the lines the painter moves between with a
+/// carriage return and newline. Comparing a frame with the previous one allows
+/// to only repaint the rows that changed.
+pub(crate) struct Frame {
+    screen_size: (u16, u16),
+    segments: Vec<Segment>,
+}
+
+struct Segment {
+    start_row: u16,
+    lines: Vec<String>,
+}
+
+impl Frame {
+    pub(crate) fn new(screen_size: (u16, u16)) -> Self {
+        Self {
+            screen_size,
+            segments: vec![],
+        }
+    }
+
+    /// Adds the text painted from the start of `start_row`, clearing the rows below
+    pub(crate) fn push_segment(&mut self, start_row: u16, text: &str) {
+        self.segments.push(Segment {
+            start_row,
+            lines: text.split("\r\n").map(str::to_string).collect(),
+        });
+    }
+
+    /// Queues the commands repainting the rows that changed since `previous`
+    /// was painted, or the whole frame if the screen moved since then
+    pub(crate) fn queue_changes(
+        &self,
+        previous: Option<&Frame>,
+        out: &mut impl Write,
+    ) -> Result<()> {
+        let previous = match previous {
+            Some(previous) if self.comparable_to(previous) => previous,
+            _ => return self.queue_from(0, 0, "", out),
+        };
+
+        // The attributes set by the lines above, active when a line starts
+        let mut attributes = String::new();
+        for (segment_index, (segment, old_segment)) in
+            self.segments.iter().zip(&previous.segments).enumerate()
+        {
+            let mut row = segment.start_row;
+            for (index, line) in segment.lines.iter().enumerate() {
+                let rows = self.rows(line);
+                match old_segment.lines.get(index) {
+                    Some(old) if old == line && !line.contains(SAVE_POSITION) => {}
+                    Some(old) if self.rows(old) == rows => {
+                        for line_row in row..row + rows {
+                            out.queue(MoveTo(0, line_row))?
+                                .queue(Clear(ClearType::CurrentLine))?;
+                        }
+                        out.queue(MoveTo(0, row))?
+                            .queue(Print(&attributes))?
+                            .queue(Print(line))?;
+                    }
+                    // The rows below move, so everything from here is repainted
+                    _ => return self.queue_from(segment_index, index, &attributes, out),
+                }
+                attributes = carried_attributes(&(attributes + line));
+                row += rows;
+            }
+
+            let old_end_row = old_segment.start_row + self.segment_rows(old_segment);
+            for line_row in row..old_end_row.min(self.screen_size.1) {
+                out.queue(MoveTo(0, line_row))?
+                    .queue(Clear(ClearType::CurrentLine))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Queues the commands painting the frame from the given line on, clearing
+    /// whatever was painted below
+    fn queue_from(
+        &self,
+        segment_index: usize,
+        line_index: usize,
+        attributes: &str,
+        out: &mut impl Write,
+    ) -> Result<()> {
+        for (index, segment) in self.segments.iter().enumerate().skip(segment_index) {
+            let (line_index, attributes) = if index == segment_index {
+                (line_index, attributes)
+            } else {
+                (0, "")
+            };
+            let row = segment.start_row
+                + segment.lines[..line_index]
+                    .iter()
+                    .map(|line| self.rows(line))
+                    .sum::<u16>();
+            out.queue(MoveTo(0, row))?
+                .queue(Clear(ClearType::FromCursorDown))?
+                .queue(Print(attributes))?
+                .queue(Print(segment.lines[line_index..].join("\r\n")))?;
+        }
+
+        Ok(())
+    }
+
+    /// Rows can be compared one by one if the frames were painted at the same
+    /// places and no segment paints over the rows of another one
+    fn comparable_to(&self, previous: &Frame) -> bool {
+        self.screen_size == previous.screen_size
+            && self.segments.len() == previous.segments.len()
+            && self
+                .segments
+                .iter()
+                .zip(&previous.segments)
+                .all(|(segment, old_segment)| segment.start_row == old_segment.start_row)
+            && !self.overlapping()
+            && !previous.overlapping()
+    }
+
+    fn overlapping(&self) -> bool {
+        self.segments
+            .windows(2)
+            .any(|pair| pair[0].start_row + self.segment_rows(&pair[0]) > pair[1].start_row)
+    }
+
+    fn segment_rows(&self, segment: &Segment) -> u16 {
+        segment.lines.iter().map(|line| self.rows(line)).sum()
+    }
+
+    /// Rows a line takes on the screen, considering that it may wrap
+    fn rows(&self, line: &str) -> u16 {
+        estimate_required_lines(&strip_ansi(line), self.screen_size.0).max(1) as u16
+    }
+}
+
+/// The SGR sequences of `text` still in effect at its end, those before the
+/// last reset don't matter anymore
+pub(crate) fn carried_attributes(text: &str) -> String {
+    let mut attributes = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("\x1b[") {
+        let sequence = &rest[start..];
+        let end = match sequence[2..].find(|c: char| ('\x40'..='\x7e').contains(&c)) {
+            Some(end) => end + 3,
+            None => break,
+        };
+        let (sequence, remainder) = sequence.split_at(end);
+        if sequence.ends_with('m') {
+            if sequence == "\x1b[m" || sequence == "\x1b[0m" {
+                attributes.clear();
+            } else {
+                attributes.push_str(sequence);
+            }
+        }
+        rest = remainder;
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn frame(start_row: u16, screen_size: (u16, u16), segments: &[&str]) -> Frame {
+        let mut frame = Frame::new(screen_size);
+        let mut row = start_row;
+        for text in segments {
+            frame.push_segment(row, text);
+            row += text.split("\r\n").count() as u16;
+        }
+        frame
+    }
+
+    fn changes(previous: Option<&Frame>, frame: &Frame) -> String {
+        let mut out = Vec::new();
+        frame.queue_changes(previous, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn unchanged_lines_are_skipped() {
+        let previous = frame(2, (20, 10), &["> ls", "first\r\nsecond"]);
+        let frame = frame(2, (20, 10), &["> ls", "first\r\nSECOND"]);
+
+        assert_eq!(
+            changes(Some(&previous), &frame),
+            "\x1b[5;1H\x1b[2K\x1b[5;1HSECOND"
+        );
+    }
+
+    #[test]
+    fn moved_frame_is_repainted() {
+        let previous = frame(2, (20, 10), &["> ls"]);
+        let frame = frame(1, (20, 10), &["> ls"]);
+
+        assert_eq!(changes(Some(&previous), &frame), "\x1b[2;1H\x1b[J> ls");
+    }
+
+    #[test]
+    fn changed_wrapping_repaints_below() {
+        let previous = frame(0, (10, 10), &["> ls\r\nshort\r\nlast"]);
+        let frame = frame(0, (10, 10), &["> ls\r\nmuch longer\r\nlast"]);
+
+        assert_eq!(
+            changes(Some(&previous), &frame),
+            "\x1b[2;1H\x1b[Jmuch longer\r\nlast"
+        );
+    }
+
+    #[test]
+    fn removed_lines_are_cleared() {
+        let previous = frame(0, (10, 10), &["> ls\r\nhint"]);
+        let frame = frame(0, (10, 10), &["> ls"]);
+
+        assert_eq!(changes(Some(&previous), &frame), "\x1b[2;1H\x1b[2K");
+    }
+
+    #[test]
+    fn cursor_line_is_always_repainted() {
+        let previous = frame(0, (10, 10), &["> ls\x1b7"]);
+
+        assert_eq!(
+            changes(Some(&previous), &previous),
+            "\x1b[1;1H\x1b[2K\x1b[1;1H> ls\x1b7"
+        );
+    }
+
+    #[test]
+    fn attributes_are_carried_until_reset() {
+        assert_eq!(carried_attributes("\x1b[1ma\x1b[0mb\x1b[32mc"), "\x1b[32m");
+        assert_eq!(carried_attributes("\x1b[1m\x1b[5;1Ha"), "\x1b[1m");
+        assert_eq!(carried_attributes("\x1b[31ma\x1b[m"), "");
+    }
+}
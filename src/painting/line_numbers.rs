@@ -0,0 +1,102 @@
+use super::StyledText;
+use nu_ansi_term::Style;
+
+/// Numbers painted in a gutter in front of each line of a multiline buffer
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineNumberMode {
+    /// No gutter is painted (default)
+    #[default]
+    Off,
+    /// Every line shows its line number, starting at 1
+    Absolute,
+    /// Every line shows its distance to the line of the cursor, as used as the
+    /// count of vertical motions. The line of the cursor shows its line number
+    Relative,
+}
+
+/// Width of the gutter painted in front of the lines of `buffer`, zero while
+/// the buffer fits on a single line
+pub(crate) fn gutter_width(mode: LineNumberMode, buffer: &str) -> usize {
+    let line_count = buffer.split('\n').count();
+    if mode == LineNumberMode::Off || line_count < 2 {
+        0
+    } else {
+        line_count.to_string().len() + 1
+    }
+}
+
+/// Inserts the gutter painted in `style` in front of each line of the `styled`
+/// buffer, returning the insertion point moved past the inserted numbers
+pub(crate) fn insert_line_numbers(
+    styled: &mut StyledText,
+    mode: LineNumberMode,
+    style: Style,
+    insertion_point: usize,
+) -> usize {
+    let raw = styled.raw_string();
+    let width = gutter_width(mode, &raw);
+    if width == 0 {
+        return insertion_point;
+    }
+
+    let cursor_line = raw[..insertion_point].matches('\n').count();
+    let line_starts = std::iter::once(0)
+        .chain(raw.match_indices('\n').map(|(index, _)| index + 1))
+        .collect::<Vec<_>>();
+
+    // Inserting from the last line on keeps the offsets of the lines above valid
+    let mut insertion_point = insertion_point;
+    for (line, start) in line_starts.into_iter().enumerate().rev() {
+        let number = match mode {
+            LineNumberMode::Relative if line != cursor_line => line.abs_diff(cursor_line),
+            _ => line + 1,
+        };
+        let gutter = format!("{:>1$} ", number, width - 1);
+        if start <= insertion_point {
+            insertion_point += gutter.len();
+        }
+        styled.insert_at(start, (style, gutter));
+    }
+
+    insertion_point
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn numbered(mode: LineNumberMode, buffer: &str, insertion_point: usize) -> (String, usize) {
+        let mut styled = StyledText::new();
+        styled.push((Style::new(), buffer.to_string()));
+        let insertion_point = insert_line_numbers(&mut styled, mode, Style::new(), insertion_point);
+        (styled.raw_string(), insertion_point)
+    }
+
+    #[rstest]
+    #[case(LineNumberMode::Off, "a\nb\nc", 2, "a\nb\nc", 2)]
+    #[case(LineNumberMode::Absolute, "single", 3, "single", 3)]
+    #[case(LineNumberMode::Absolute, "a\nb\nc", 2, "1 a\n2 b\n3 c", 6)]
+    #[case(LineNumberMode::Relative, "a\nb\nc", 2, "1 a\n2 b\n1 c", 6)]
+    #[case(LineNumberMode::Relative, "a\nb\nc", 5, "2 a\n1 b\n3 c", 11)]
+    #[case(
+        LineNumberMode::Absolute,
+        "a\n\n\n\n\n\n\n\n\nj",
+        0,
+        " 1 a\n 2 \n 3 \n 4 \n 5 \n 6 \n 7 \n 8 \n 9 \n10 j",
+        3
+    )]
+    fn test_insert_line_numbers(
+        #[case] mode: LineNumberMode,
+        #[case] buffer: &str,
+        #[case] insertion_point: usize,
+        #[case] expected: &str,
+        #[case] expected_insertion_point: usize,
+    ) {
+        assert_eq!(
+            numbered(mode, buffer, insertion_point),
+            (expected.to_string(), expected_insertion_point)
+        );
+    }
+}
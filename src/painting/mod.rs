@@ -1,9 +1,18 @@
+mod frame;
+mod line_numbers;
 mod painter;
 mod prompt_lines;
 mod styled_text;
+mod theme;
 mod utils;
 
+pub use line_numbers::LineNumberMode;
+pub(crate) use line_numbers::{gutter_width, insert_line_numbers};
 pub use painter::Painter;
 pub(crate) use prompt_lines::PromptLines;
 pub use styled_text::StyledText;
-pub(crate) use utils::estimate_single_line_wraps;
+pub(crate) use theme::overlay;
+pub use theme::Theme;
+pub(crate) use utils::{
+    estimate_single_line_wraps, ghost_lines, keybinding_hints_string, truncate_end,
+};
@@ -1,45 +1,40 @@
 use {
-    super::utils::{coerce_crlf, line_width},
+    super::utils::{
+        buffer_cell_at, buffer_offset_at, coerce_crlf, estimate_required_lines, line_width,
+        wrap_rows,
+    },
     crate::{
-        menu::{Menu, ReedlineMenu},
-        painting::PromptLines,
+        menu::{Menu, MenuPlacement, ReedlineMenu},
+        painting::{
+            frame::{carried_attributes, Frame},
+            PromptLines,
+        },
+        terminal_extensions::{
+            osc133,
+            synchronized_output::{BeginSynchronizedUpdate, EndSynchronizedUpdate},
+        },
         Prompt,
     },
     crossterm::{
         cursor::{self, MoveTo, RestorePosition, SavePosition},
+        event::{DisableMouseCapture, EnableMouseCapture},
         style::{Attribute, Print, ResetColor, SetAttribute, SetForegroundColor},
-        terminal::{self, Clear, ClearType, ScrollUp},
+        terminal::{self, ClearType, ScrollUp},
         QueueableCommand, Result,
     },
     std::io::Write,
 };
 
-// Returns a string that skips N number of lines with the next offset of lines
-// An offset of 0 would return only one line after skipping the required lines
-fn skip_buffer_lines(string: &str, skip: usize, offset: Option<usize>) -> &str {
-    let mut matches = string.match_indices('\n');
-    let index = if skip == 0 {
-        0
-    } else {
-        matches
-            .clone()
-            .nth(skip - 1)
-            .map(|(index, _)| index + 1)
-            .unwrap_or(string.len())
-    };
-
-    let limit = match offset {
-        Some(offset) => {
-            let offset = skip + offset;
-            matches
-                .nth(offset)
-                .map(|(index, _)| index)
-                .unwrap_or(string.len())
-        }
-        None => string.len(),
-    };
-
-    string[index..limit].trim_end_matches('\n')
+// Rows kept visible between the cursor and the edges of the viewport
+const SCROLL_MARGIN: usize = 2;
+
+// Returns the first row of a viewport `height` rows tall over `total_rows` rows,
+// scrolled from `top` as little as needed to keep the margins around the cursor
+fn scroll_viewport(top: usize, cursor_row: usize, total_rows: usize, height: usize) -> usize {
+    let margin = SCROLL_MARGIN.min(height.saturating_sub(1) / 2);
+    top.min(cursor_row.saturating_sub(margin))
+        .max((cursor_row + margin + 1).saturating_sub(height))
+        .min(total_rows.saturating_sub(height))
 }
 
 /// the type used by crossterm operations
@@ -50,9 +45,30 @@ pub struct Painter {
     // Stdout
     stdout: W,
     prompt_start_row: u16,
+    // Rows of the menu painted above the prompt, which moved the prompt down
+    menu_above_lines: u16,
     terminal_size: (u16, u16),
     last_required_lines: u16,
     large_buffer: bool,
+    // Cell of the first buffer character, known while the whole buffer fits on the screen
+    input_origin: Option<(u16, u16)>,
+    // Row of the first menu line and the lines the menu could fill from there
+    menu_rows: Option<(u16, u16)>,
+    // Emit the OSC 133 semantic prompt markers
+    shell_integration: bool,
+    // Output of the repaint, starting a new segment at every row the painter moves to
+    frame: Vec<u8>,
+    frame_segments: Vec<(u16, usize)>,
+    // What was painted during the last repaint, while it is still on the screen
+    last_frame: Option<Frame>,
+    // Screens narrower than this don't show the right prompt
+    right_prompt_min_width: u16,
+    // Transient message painted below the input
+    status: Option<String>,
+    // First row of a large buffer shown on the screen
+    viewport_top: usize,
+    // Let the terminal draw each repaint at once
+    synchronized_output: bool,
 }
 
 impl Painter {
@@ -60,12 +76,35 @@ impl Painter {
         Painter {
             stdout,
             prompt_start_row: 0,
+            menu_above_lines: 0,
             terminal_size: (0, 0),
             last_required_lines: 0,
             large_buffer: false,
+            input_origin: None,
+            menu_rows: None,
+            shell_integration: false,
+            frame: Vec::new(),
+            frame_segments: Vec::new(),
+            last_frame: None,
+            right_prompt_min_width: 0,
+            status: None,
+            viewport_top: 0,
+            synchronized_output: true,
         }
     }
 
+    pub(crate) fn set_shell_integration(&mut self, shell_integration: bool) {
+        self.shell_integration = shell_integration;
+    }
+
+    pub(crate) fn set_synchronized_output(&mut self, synchronized_output: bool) {
+        self.synchronized_output = synchronized_output;
+    }
+
+    pub(crate) fn set_right_prompt_min_width(&mut self, right_prompt_min_width: u16) {
+        self.right_prompt_min_width = right_prompt_min_width;
+    }
+
     /// Height of the current terminal window
     pub fn screen_height(&self) -> u16 {
         self.terminal_size.1
@@ -76,6 +115,99 @@ impl Painter {
         self.terminal_size.0
     }
 
+    /// Lines the active menu could fill during the last repaint
+    pub fn menu_available_lines(&self) -> u16 {
+        self.menu_rows
+            .map_or(0, |(_, available_lines)| available_lines)
+    }
+
+    /// Row of the painted menu that was clicked, counted from its first row
+    pub(crate) fn menu_row_at(&self, row: u16) -> Option<u16> {
+        self.menu_rows
+            .and_then(|(start_row, _)| row.checked_sub(start_row))
+    }
+
+    /// Offset in `buffer` of the character painted at the clicked cell
+    ///
+    /// Clicks are only mapped while the whole buffer fits on the screen. Every
+    /// line is painted behind a gutter of `gutter_width` columns
+    pub(crate) fn buffer_offset_at(
+        &self,
+        buffer: &str,
+        multiline_indicator: &str,
+        gutter_width: u16,
+        column: u16,
+        row: u16,
+    ) -> Option<usize> {
+        let (origin_column, origin_row) = self.input_origin?;
+        let screen_width = self.screen_width().max(1);
+        let origin_column = origin_column + gutter_width;
+        let origin = (
+            origin_column % screen_width,
+            origin_row + origin_column / screen_width,
+        );
+        buffer_offset_at(
+            buffer,
+            origin,
+            line_width(multiline_indicator) as u16 + gutter_width,
+            self.screen_width(),
+            (column, row),
+        )
+    }
+
+    /// Offset in `buffer` of the character painted one screen row above or
+    /// below the insertion point, in its column, `None` if that row doesn't
+    /// belong to the buffer. Every line is painted behind a gutter of
+    /// `gutter_width` columns
+    pub(crate) fn display_line_offset(
+        &self,
+        buffer: &str,
+        insertion_point: usize,
+        lines: &PromptLines,
+        multiline_indicator: &str,
+        gutter_width: u16,
+        up: bool,
+    ) -> Option<usize> {
+        let screen_width = self.screen_width().max(1);
+        let lines = lines.fit_to_width(screen_width, self.right_prompt_min_width);
+        let origin_column = (lines.input_origin(screen_width).0 + gutter_width) % screen_width;
+        let indicator_width = line_width(multiline_indicator) as u16 + gutter_width;
+        let (column, row) = buffer_cell_at(
+            buffer,
+            origin_column,
+            indicator_width,
+            screen_width,
+            insertion_point,
+        );
+        let row = if up { row.checked_sub(1)? } else { row + 1 };
+        buffer_offset_at(
+            buffer,
+            (origin_column, 0),
+            indicator_width,
+            screen_width,
+            (column, row),
+        )
+    }
+
+    /// Shows `status` in a line below the input, until it is replaced or
+    /// cleared with `None`
+    pub(crate) fn set_status(&mut self, status: Option<String>) {
+        self.status = status;
+    }
+
+    /// Whether a status line is shown below the input
+    pub(crate) fn has_status(&self) -> bool {
+        self.status.is_some()
+    }
+
+    // Lines taken by the status line, which gives way to an open menu
+    fn status_lines(&self, menu: Option<&ReedlineMenu>) -> u16 {
+        match (&self.status, menu) {
+            (Some(status), None) => estimate_required_lines(status, self.screen_width()) as u16,
+            _ => 0,
+        }
+    }
+
     /// Returns the available lines from the prompt down
     pub fn remaining_lines(&self) -> u16 {
         self.screen_height() - self.prompt_start_row
@@ -114,6 +246,9 @@ impl Painter {
             new_row
         };
         self.prompt_start_row = new_row;
+        self.menu_above_lines = 0;
+        self.viewport_top = 0;
+        self.last_frame = None;
         Ok(())
     }
 
@@ -133,32 +268,53 @@ impl Painter {
         menu: Option<&ReedlineMenu>,
         use_ansi_coloring: bool,
     ) -> Result<()> {
+        if self.synchronized_output {
+            self.stdout.queue(BeginSynchronizedUpdate)?;
+        }
         self.stdout.queue(cursor::Hide)?;
 
         let screen_width = self.screen_width();
+        let lines = &lines.fit_to_width(screen_width, self.right_prompt_min_width);
         let screen_height = self.screen_height();
 
         // Lines and distance parameters
-        let remaining_lines = self.remaining_lines();
-        let required_lines = lines.required_lines(screen_width, menu);
+        // A menu above the prompt takes the rows the prompt started at, moving
+        // the prompt down, and gives them back once it closes
+        let top_row = self.prompt_start_row.saturating_sub(self.menu_above_lines);
+        let remaining_lines = screen_height.saturating_sub(top_row);
+        let menu_above_lines = match menu {
+            Some(menu) => self.menu_above_lines(top_row, lines, menu),
+            None => 0,
+        };
+        let below_menu = menu.filter(|_| menu_above_lines == 0);
+        let required_lines = menu_above_lines
+            + lines.required_lines(screen_width, below_menu)
+            + self.status_lines(menu);
 
         // Marking the painter state as larger buffer to avoid animations
         self.large_buffer = required_lines >= screen_height;
+        self.input_origin = None;
+        self.menu_rows = None;
 
         // Moving the start position of the cursor based on the size of the required lines
         if self.large_buffer {
             self.prompt_start_row = 0;
-        } else if required_lines >= remaining_lines {
-            let extra = required_lines.saturating_sub(remaining_lines);
-            self.stdout.queue(ScrollUp(extra))?;
-            self.prompt_start_row = self.prompt_start_row.saturating_sub(extra);
+            self.menu_above_lines = 0;
+        } else {
+            let mut top_row = top_row;
+            if required_lines >= remaining_lines {
+                let extra = required_lines.saturating_sub(remaining_lines);
+                self.stdout.queue(ScrollUp(extra))?;
+                self.last_frame = None;
+                top_row = top_row.saturating_sub(extra);
+            }
+            self.prompt_start_row = top_row + menu_above_lines;
+            self.menu_above_lines = menu_above_lines;
         }
 
-        // Moving the cursor to the start of the prompt
-        // from this position everything will be printed
-        self.stdout
-            .queue(cursor::MoveTo(0, self.prompt_start_row))?
-            .queue(Clear(ClearType::FromCursorDown))?;
+        // Everything is printed into the frame from the start of the prompt
+        self.frame.clear();
+        self.frame_segments = vec![(self.prompt_start_row, 0)];
 
         if self.large_buffer {
             self.print_large_buffer(prompt, lines, menu, use_ansi_coloring)?;
@@ -166,11 +322,27 @@ impl Painter {
             self.print_small_buffer(prompt, lines, menu, use_ansi_coloring)?;
         }
 
+        // Only the rows that changed since the last repaint are written
+        let output = String::from_utf8_lossy(&self.frame);
+        let mut frame = Frame::new(self.terminal_size);
+        for (index, &(start_row, start)) in self.frame_segments.iter().enumerate() {
+            let end = self
+                .frame_segments
+                .get(index + 1)
+                .map_or(output.len(), |&(_, end)| end);
+            frame.push_segment(start_row, &output[start..end]);
+        }
+        frame.queue_changes(self.last_frame.as_ref(), &mut self.stdout)?;
+        self.last_frame = Some(frame);
+
         // The last_required_lines is used to move the cursor at the end where stdout
         // can print without overwriting the things written during the painting
-        self.last_required_lines = required_lines;
+        self.last_required_lines = required_lines - self.menu_above_lines;
 
         self.stdout.queue(RestorePosition)?.queue(cursor::Show)?;
+        if self.synchronized_output {
+            self.stdout.queue(EndSynchronizedUpdate)?;
+        }
 
         self.stdout.flush()
     }
@@ -181,11 +353,13 @@ impl Painter {
             .screen_width()
             .saturating_sub(prompt_length_right as u16);
         let input_width = lines.estimate_first_input_line_width();
+        // The right prompt is painted next to the first line of the prompt, below the banner
+        let row = self.prompt_start_row + lines.banner_rows(self.screen_width());
 
         if input_width <= start_position {
-            self.stdout
+            self.frame
                 .queue(SavePosition)?
-                .queue(cursor::MoveTo(start_position, self.prompt_start_row))?
+                .queue(cursor::MoveTo(start_position, row))?
                 .queue(Print(&coerce_crlf(&lines.prompt_str_right)))?
                 .queue(RestorePosition)?;
         }
@@ -193,29 +367,84 @@ impl Painter {
         Ok(())
     }
 
-    fn print_menu(
+    // Paints the right prompt at the end of the line holding the cursor, unless
+    // that line reaches into it. Printed as part of that line, so the line is
+    // cleared and repainted without it once the cursor leaves or the input
+    // grows into it
+    fn print_right_prompt_on_cursor_line(
         &mut self,
-        menu: &dyn Menu,
+        prompt: &dyn Prompt,
         lines: &PromptLines,
         use_ansi_coloring: bool,
     ) -> Result<()> {
+        let prompt_length_right = line_width(&lines.prompt_str_right);
+        let start_position = self
+            .screen_width()
+            .saturating_sub(prompt_length_right as u16);
+        let input_width = lines.estimate_cursor_line_width();
+        let row = self.prompt_start_row + lines.distance_from_prompt(self.screen_width());
+
+        if input_width <= start_position {
+            // Restoring the position also restores the attributes of the buffer
+            self.frame
+                .queue(SavePosition)?
+                .queue(cursor::MoveTo(start_position, row))?;
+            if use_ansi_coloring {
+                self.frame
+                    .queue(SetForegroundColor(prompt.get_prompt_right_color()))?
+                    .queue(SetAttribute(Attribute::Bold))?;
+            }
+            self.frame
+                .queue(Print(&coerce_crlf(&lines.prompt_str_right)))?
+                .queue(SetAttribute(Attribute::Reset))?
+                .queue(RestorePosition)?;
+        }
+
+        Ok(())
+    }
+
+    /// The lines of the menu painted above the prompt following its placement,
+    /// `0` if it is painted below. `top_row` is the first row of the prompt
+    /// without a menu above it
+    fn menu_above_lines(&self, top_row: u16, lines: &PromptLines, menu: &ReedlineMenu) -> u16 {
         let screen_width = self.screen_width();
-        let screen_height = self.screen_height();
-        let cursor_distance = lines.distance_from_prompt(screen_width);
+        let prompt_lines = lines.required_lines(screen_width, None);
+        let menu_lines = menu
+            .menu_required_lines(screen_width)
+            .min(self.screen_height().saturating_sub(prompt_lines));
+        if menu_lines < menu.min_rows() {
+            return 0;
+        }
 
-        // If there is not enough space to print the menu, then the starting
-        // drawing point for the menu will overwrite the last rows in the buffer
-        let starting_row = if cursor_distance >= screen_height.saturating_sub(1) {
-            screen_height.saturating_sub(menu.min_rows())
-        } else {
-            self.prompt_start_row + cursor_distance + 1
+        let above = match menu.placement() {
+            MenuPlacement::Below => false,
+            MenuPlacement::Above => true,
+            MenuPlacement::Auto => {
+                let rows_below = self
+                    .screen_height()
+                    .saturating_sub(top_row)
+                    .saturating_sub(prompt_lines);
+                menu_lines > rows_below && top_row > rows_below
+            }
         };
+        if above {
+            menu_lines
+        } else {
+            0
+        }
+    }
 
-        let remaining_lines = screen_height.saturating_sub(starting_row);
-        let menu_string = menu.menu_string(remaining_lines, use_ansi_coloring);
-        self.stdout
-            .queue(cursor::MoveTo(0, starting_row))?
-            .queue(Clear(ClearType::FromCursorDown))?
+    fn print_menu(
+        &mut self,
+        menu: &dyn Menu,
+        starting_row: u16,
+        available_lines: u16,
+        use_ansi_coloring: bool,
+    ) -> Result<()> {
+        self.menu_rows = Some((starting_row, available_lines));
+        let menu_string = menu.menu_string(available_lines, use_ansi_coloring);
+        self.frame_segments.push((starting_row, self.frame.len()));
+        self.frame
             .queue(Print(menu_string.trim_end_matches('\n')))?;
 
         Ok(())
@@ -228,14 +457,37 @@ impl Painter {
         menu: Option<&ReedlineMenu>,
         use_ansi_coloring: bool,
     ) -> Result<()> {
+        // The menu above ends right before the prompt, which starts a segment
+        // of its own
+        let menu_above = self.menu_above_lines > 0;
+        if let Some(menu) = menu.filter(|_| menu_above) {
+            self.frame_segments.clear();
+            self.print_menu(
+                menu,
+                self.prompt_start_row - self.menu_above_lines,
+                self.menu_above_lines,
+                use_ansi_coloring,
+            )?;
+            self.frame_segments
+                .push((self.prompt_start_row, self.frame.len()));
+        }
+
+        self.mark_prompt_start()?;
+
+        if !lines.banner.is_empty() {
+            self.frame
+                .queue(Print(&coerce_crlf(&lines.banner)))?
+                .queue(Print("\r\n"))?;
+        }
+
         // print our prompt with color
         if use_ansi_coloring {
-            self.stdout
+            self.frame
                 .queue(SetForegroundColor(prompt.get_prompt_color()))?
                 .queue(SetAttribute(Attribute::Bold))?;
         }
 
-        self.stdout
+        self.frame
             .queue(Print(&coerce_crlf(&lines.prompt_str_left)))?;
 
         let prompt_indicator = match menu {
@@ -244,34 +496,61 @@ impl Painter {
         };
 
         if use_ansi_coloring {
-            self.stdout
+            self.frame
                 .queue(SetForegroundColor(prompt.get_indicator_color()))?
                 .queue(SetAttribute(Attribute::Bold))?;
         }
 
-        self.stdout.queue(Print(&coerce_crlf(prompt_indicator)))?;
+        self.frame.queue(Print(&coerce_crlf(prompt_indicator)))?;
 
-        if use_ansi_coloring {
-            self.stdout
-                .queue(SetForegroundColor(prompt.get_prompt_right_color()))?
-                .queue(SetAttribute(Attribute::Bold))?;
-        }
+        if !prompt.right_prompt_on_cursor_line() {
+            if use_ansi_coloring {
+                self.frame
+                    .queue(SetForegroundColor(prompt.get_prompt_right_color()))?
+                    .queue(SetAttribute(Attribute::Bold))?;
+            }
 
-        self.print_right_prompt(lines)?;
+            self.print_right_prompt(lines)?;
+        }
 
         if use_ansi_coloring {
-            self.stdout.queue(ResetColor)?;
+            self.frame.queue(ResetColor)?;
         }
 
-        self.stdout
-            .queue(Print(&lines.before_cursor))?
+        let (origin_column, origin_rows) = lines.input_origin(self.screen_width());
+        self.input_origin = Some((origin_column, self.prompt_start_row + origin_rows));
+        self.mark_command_start()?;
+
+        self.frame.queue(Print(&lines.before_cursor))?;
+        if prompt.right_prompt_on_cursor_line() {
+            self.print_right_prompt_on_cursor_line(prompt, lines, use_ansi_coloring)?;
+        }
+        self.frame
             .queue(SavePosition)?
             .queue(Print(&lines.after_cursor))?;
 
-        if let Some(menu) = menu {
-            self.print_menu(menu, lines, use_ansi_coloring)?;
+        if menu_above {
+            self.frame.queue(Print(&lines.hint))?;
+        } else if let Some(menu) = menu {
+            let screen_height = self.screen_height();
+            let cursor_distance = lines.distance_from_prompt(self.screen_width());
+
+            // If there is not enough space to print the menu, then the starting
+            // drawing point for the menu will overwrite the last rows in the buffer
+            let starting_row = if cursor_distance >= screen_height.saturating_sub(1) {
+                screen_height.saturating_sub(menu.min_rows())
+            } else {
+                self.prompt_start_row + cursor_distance + 1
+            };
+            let available_lines = screen_height.saturating_sub(starting_row);
+            self.print_menu(menu, starting_row, available_lines, use_ansi_coloring)?;
         } else {
-            self.stdout.queue(Print(&lines.hint))?;
+            self.frame.queue(Print(&lines.hint))?;
+            if let Some(status) = &self.status {
+                self.frame
+                    .queue(Print("\r\n"))?
+                    .queue(Print(&coerce_crlf(status)))?;
+            }
         }
 
         Ok(())
@@ -286,100 +565,161 @@ impl Painter {
     ) -> Result<()> {
         let screen_width = self.screen_width();
         let screen_height = self.screen_height();
-        let cursor_distance = lines.distance_from_prompt(screen_width);
-        let remaining_lines = screen_height.saturating_sub(cursor_distance);
-
-        // Calculating the total lines before the cursor
-        // The -1 in the total_lines_before is there because the at least one line of the prompt
-        // indicator is printed in the same line as the first line of the buffer
-        let prompt_lines = lines.prompt_lines_with_wrap(screen_width) as usize;
 
         let prompt_indicator = match menu {
             Some(menu) => menu.indicator(),
             None => &lines.prompt_indicator,
         };
 
-        let prompt_indicator_lines = prompt_indicator.lines().count();
-        let before_cursor_lines = lines.before_cursor.lines().count();
-        let total_lines_before = prompt_lines + prompt_indicator_lines + before_cursor_lines - 1;
-
-        // Extra rows represent how many rows are "above" the visible area in the terminal
-        let extra_rows = (total_lines_before).saturating_sub(screen_height as usize);
-
-        // print our prompt with color
+        // The whole prompt and buffer are laid out in rows, and only the rows of
+        // the viewport around the cursor are printed
+        let mut content = Vec::new();
+        if self.shell_integration {
+            content.queue(Print(osc133::PROMPT_START))?;
+        }
+        if !lines.banner.is_empty() {
+            content.queue(Print(&lines.banner))?.queue(Print("\n"))?;
+        }
         if use_ansi_coloring {
-            self.stdout
-                .queue(SetForegroundColor(prompt.get_prompt_color()))?;
+            content.queue(SetForegroundColor(prompt.get_prompt_color()))?;
+        }
+        content
+            .queue(Print(&lines.prompt_str_left))?
+            .queue(Print(prompt_indicator))?;
+        if use_ansi_coloring {
+            content.queue(ResetColor)?;
+        }
+        if self.shell_integration {
+            content.queue(Print(osc133::COMMAND_START))?;
+        }
+        content
+            .queue(Print(&lines.before_cursor))?
+            .queue(SavePosition)?;
+        // TODO: Also solve the difficult problem of displaying (parts of)
+        // the content after the cursor with the completion menu
+        if menu.is_none() {
+            content
+                .queue(Print(&lines.after_cursor))?
+                .queue(Print(&lines.hint))?;
+            if let Some(status) = &self.status {
+                content.queue(Print("\r\n"))?.queue(Print(status))?;
+            }
         }
 
-        // In case the prompt is made out of multiple lines, the prompt is split by
-        // lines and only the required ones are printed
-        let prompt_skipped = skip_buffer_lines(&lines.prompt_str_left, extra_rows, None);
-        self.stdout.queue(Print(&coerce_crlf(prompt_skipped)))?;
-
-        if extra_rows == 0 {
+        let rows = wrap_rows(&String::from_utf8_lossy(&content), screen_width);
+        let cursor_row = rows
+            .iter()
+            .position(|row| row.contains("\x1b7"))
+            .unwrap_or_default();
+
+        // The menu takes the rows below the viewport
+        let menu_rows = menu.map_or(0, |menu| menu.min_rows().min(screen_height / 2));
+        let height = screen_height.saturating_sub(menu_rows).max(1) as usize;
+        let top = scroll_viewport(self.viewport_top, cursor_row, rows.len(), height);
+        self.viewport_top = top;
+        let bottom = (top + height).min(rows.len());
+
+        // Rows hidden above or below are announced in place of the first or last
+        // row of the viewport, which the scroll margins keep clear of the cursor
+        let hidden_above = (top > 0 && height > 2).then_some(top + 1);
+        let hidden_below = (bottom < rows.len() && height > 2).then_some(rows.len() - bottom + 1);
+        let first_row = top + usize::from(hidden_above.is_some());
+        let last_row = bottom - usize::from(hidden_below.is_some());
+
+        if let Some(hidden) = hidden_above {
+            self.print_scroll_indicator(&format!("↑ {} more lines", hidden), use_ansi_coloring)?;
+            self.frame.queue(Print("\r\n"))?;
+        }
+        if top == 0 {
+            if use_ansi_coloring {
+                self.frame
+                    .queue(SetForegroundColor(prompt.get_prompt_right_color()))?;
+            }
             self.print_right_prompt(lines)?;
+            if use_ansi_coloring {
+                self.frame.queue(ResetColor)?;
+            }
+        }
+        // The rows start with the attributes left active by the rows hidden above
+        self.frame
+            .queue(Print(carried_attributes(&rows[..first_row].concat())))?
+            .queue(Print(rows[first_row..last_row].join("\r\n")))?;
+        if let Some(hidden) = hidden_below {
+            self.frame.queue(Print("\r\n"))?;
+            self.print_scroll_indicator(&format!("↓ {} more lines", hidden), use_ansi_coloring)?;
         }
 
-        // Adjusting extra_rows base on the calculated prompt line size
-        let extra_rows = extra_rows.saturating_sub(prompt_lines);
+        if let Some(menu) = menu {
+            let starting_row = (bottom - top) as u16;
+            let available_lines = screen_height.saturating_sub(starting_row);
+            self.print_menu(menu, starting_row, available_lines, use_ansi_coloring)?;
+        }
 
-        let indicator_skipped = skip_buffer_lines(prompt_indicator, extra_rows, None);
-        self.stdout.queue(Print(&coerce_crlf(indicator_skipped)))?;
+        Ok(())
+    }
 
+    fn print_scroll_indicator(&mut self, indicator: &str, use_ansi_coloring: bool) -> Result<()> {
+        self.frame.queue(Print("\x1b[0m"))?;
         if use_ansi_coloring {
-            self.stdout.queue(ResetColor)?;
-        }
-
-        // The minimum number of lines from the menu are removed from the buffer if there is no more
-        // space to print the menu. This will only happen if the cursor is at the last line and
-        // it is a large buffer
-        let offset = menu.and_then(|menu| {
-            if cursor_distance >= screen_height.saturating_sub(1) {
-                let rows = lines
-                    .before_cursor
-                    .lines()
-                    .count()
-                    .saturating_sub(extra_rows)
-                    .saturating_sub(menu.min_rows() as usize);
-                Some(rows)
-            } else {
-                None
-            }
-        });
+            self.frame
+                .queue(SetAttribute(Attribute::Dim))?
+                .queue(Print(indicator))?
+                .queue(SetAttribute(Attribute::Reset))?;
+        } else {
+            self.frame.queue(Print(indicator))?;
+        }
 
-        // Selecting the lines before the cursor that will be printed
-        let before_cursor_skipped = skip_buffer_lines(&lines.before_cursor, extra_rows, offset);
-        self.stdout.queue(Print(before_cursor_skipped))?;
-        self.stdout.queue(SavePosition)?;
+        Ok(())
+    }
 
-        if let Some(menu) = menu {
-            // TODO: Also solve the difficult problem of displaying (parts of)
-            // the content after the cursor with the completion menu
-            self.print_menu(menu, lines, use_ansi_coloring)?;
-        } else {
-            // Selecting lines for the hint
-            // The -1 subtraction is done because the remaining lines consider the line where the
-            // cursor is located as a remaining line. That has to be removed to get the correct offset
-            // for the after-cursor and hint lines
-            let offset = remaining_lines.saturating_sub(1) as usize;
-            // Selecting lines after the cursor
-            let after_cursor_skipped = skip_buffer_lines(&lines.after_cursor, 0, Some(offset));
-            self.stdout.queue(Print(after_cursor_skipped))?;
-            // Hint lines
-            let hint_skipped = skip_buffer_lines(&lines.hint, 0, Some(offset));
-            self.stdout.queue(Print(hint_skipped))?;
+    fn mark_prompt_start(&mut self) -> Result<()> {
+        if self.shell_integration {
+            self.frame.queue(Print(osc133::PROMPT_START))?;
         }
+        Ok(())
+    }
 
+    fn mark_command_start(&mut self) -> Result<()> {
+        if self.shell_integration {
+            self.frame.queue(Print(osc133::COMMAND_START))?;
+        }
         Ok(())
     }
 
+    /// Writes an escape sequence for the terminal that isn't painted, like the
+    /// content of the clipboard
+    pub(crate) fn write_sequence(&mut self, sequence: &str) -> Result<()> {
+        self.stdout.queue(Print(sequence))?;
+
+        self.stdout.flush()
+    }
+
+    /// Marks the start of the output of the submitted command for shell integration
+    pub(crate) fn mark_output_start(&mut self) -> Result<()> {
+        if self.shell_integration {
+            self.stdout.queue(Print(osc133::OUTPUT_START))?;
+        }
+
+        self.stdout.flush()
+    }
+
+    /// Marks the end of the output of the last command for shell integration
+    pub(crate) fn mark_command_finished(&mut self, exit_code: Option<i32>) -> Result<()> {
+        if self.shell_integration {
+            self.stdout
+                .queue(Print(osc133::command_finished(exit_code)))?;
+        }
+
+        self.stdout.flush()
+    }
+
     /// Updates prompt origin and offset to handle a screen resize event
     pub(crate) fn handle_resize(&mut self, width: u16, height: u16) {
         let prev_terminal_size = self.terminal_size;
         let prev_prompt_row = self.prompt_start_row;
 
         self.terminal_size = (width, height);
+        self.last_frame = None;
         // TODO properly adjusting prompt_origin on resizing while lines > 1
 
         if prev_prompt_row >= (height - 1) {
@@ -400,15 +740,31 @@ impl Painter {
 
     /// Writes `line` to the terminal with a following carriage return and newline
     pub(crate) fn paint_line(&mut self, line: &str) -> Result<()> {
+        self.last_frame = None;
         self.stdout.queue(Print(line))?.queue(Print("\r\n"))?;
 
         self.stdout.flush()
     }
 
+    /// Lets the terminal report mouse clicks and wheel scrolls as events
+    pub(crate) fn enable_mouse_capture(&mut self) -> Result<()> {
+        self.stdout.queue(EnableMouseCapture)?;
+
+        self.stdout.flush()
+    }
+
+    /// Returns mouse handling, like selecting text, to the terminal
+    pub(crate) fn disable_mouse_capture(&mut self) -> Result<()> {
+        self.stdout.queue(DisableMouseCapture)?;
+
+        self.stdout.flush()
+    }
+
     /// Goes to the beginning of the next line
     ///
     /// Also works in raw mode
     pub(crate) fn print_crlf(&mut self) -> Result<()> {
+        self.last_frame = None;
         self.stdout.queue(Print("\r\n"))?;
 
         self.stdout.flush()
@@ -442,6 +798,7 @@ impl Painter {
     // If the prompt is in the middle of a multiline buffer, then the output to stdout
     // could overwrite the buffer writing
     pub(crate) fn move_cursor_to_end(&mut self) -> Result<()> {
+        self.last_frame = None;
         let final_row = self.prompt_start_row + self.last_required_lines;
         let scroll = final_row.saturating_sub(self.screen_height() - 1);
         if scroll != 0 {
@@ -460,53 +817,16 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     #[test]
-    fn test_skip_lines() {
-        let string = "sentence1\nsentence2\nsentence3\n";
-
-        assert_eq!(skip_buffer_lines(string, 1, None), "sentence2\nsentence3");
-        assert_eq!(skip_buffer_lines(string, 2, None), "sentence3");
-        assert_eq!(skip_buffer_lines(string, 3, None), "");
-        assert_eq!(skip_buffer_lines(string, 4, None), "");
-    }
-
-    #[test]
-    fn test_skip_lines_no_newline() {
-        let string = "sentence1";
-
-        assert_eq!(skip_buffer_lines(string, 0, None), "sentence1");
-        assert_eq!(skip_buffer_lines(string, 1, None), "");
-    }
-
-    #[test]
-    fn test_skip_lines_with_limit() {
-        let string = "sentence1\nsentence2\nsentence3\nsentence4\nsentence5";
-
-        assert_eq!(
-            skip_buffer_lines(string, 1, Some(1)),
-            "sentence2\nsentence3",
-        );
-
-        assert_eq!(
-            skip_buffer_lines(string, 1, Some(2)),
-            "sentence2\nsentence3\nsentence4",
-        );
-
-        assert_eq!(
-            skip_buffer_lines(string, 2, Some(1)),
-            "sentence3\nsentence4",
-        );
-
-        assert_eq!(
-            skip_buffer_lines(string, 1, Some(10)),
-            "sentence2\nsentence3\nsentence4\nsentence5",
-        );
-
-        assert_eq!(
-            skip_buffer_lines(string, 0, Some(1)),
-            "sentence1\nsentence2",
-        );
-
-        assert_eq!(skip_buffer_lines(string, 0, Some(0)), "sentence1",);
-        assert_eq!(skip_buffer_lines(string, 1, Some(0)), "sentence2",);
+    fn viewport_follows_the_cursor_within_the_margins() {
+        // The cursor can move freely between the margins
+        assert_eq!(scroll_viewport(10, 14, 40, 10), 10);
+        assert_eq!(scroll_viewport(10, 17, 40, 10), 10);
+        // Moving into a margin scrolls
+        assert_eq!(scroll_viewport(10, 11, 40, 10), 9);
+        assert_eq!(scroll_viewport(10, 18, 40, 10), 11);
+        // The viewport stays within the rows
+        assert_eq!(scroll_viewport(10, 0, 40, 10), 0);
+        assert_eq!(scroll_viewport(10, 39, 40, 10), 30);
+        assert_eq!(scroll_viewport(10, 5, 8, 10), 0);
     }
 }
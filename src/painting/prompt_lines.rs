@@ -1,4 +1,4 @@
-use super::utils::{coerce_crlf, estimate_required_lines, line_width};
+use super::utils::{coerce_crlf, estimate_required_lines, line_width, truncate_start};
 use crate::{
     menu::{Menu, ReedlineMenu},
     prompt::PromptEditMode,
@@ -6,8 +6,12 @@ use crate::{
 };
 use std::borrow::Cow;
 
+/// Columns kept free for the input after a prompt shortened to fit the screen
+const MIN_INPUT_WIDTH: usize = 10;
+
 /// Aggregate of prompt and input string used by `Painter`
 pub(crate) struct PromptLines<'prompt> {
+    pub(crate) banner: Cow<'prompt, str>,
     pub(crate) prompt_str_left: Cow<'prompt, str>,
     pub(crate) prompt_str_right: Cow<'prompt, str>,
     pub(crate) prompt_indicator: Cow<'prompt, str>,
@@ -28,6 +32,7 @@ impl<'prompt> PromptLines<'prompt> {
         after_cursor: &'prompt str,
         hint: &'prompt str,
     ) -> Self {
+        let banner = prompt.render_prompt_banner().unwrap_or_default();
         let prompt_str_left = prompt.render_prompt_left();
         let prompt_str_right = prompt.render_prompt_right();
 
@@ -41,6 +46,7 @@ impl<'prompt> PromptLines<'prompt> {
         let hint = coerce_crlf(hint);
 
         Self {
+            banner,
             prompt_str_left,
             prompt_str_right,
             prompt_indicator,
@@ -50,18 +56,90 @@ impl<'prompt> PromptLines<'prompt> {
         }
     }
 
+    /// The prompt laid out for a screen `screen_width` columns wide. Lines of
+    /// the left prompt too wide for it are shortened from the start, keeping
+    /// room for the input after the last one, and the right prompt is dropped
+    /// on screens narrower than `right_prompt_min_width` or than itself
+    pub(crate) fn fit_to_width(
+        &self,
+        screen_width: u16,
+        right_prompt_min_width: u16,
+    ) -> PromptLines<'_> {
+        let screen_width = screen_width as usize;
+        let indicator_width = line_width(self.prompt_indicator.lines().last().unwrap_or_default());
+        let left_lines = self.prompt_str_left.split('\n').collect::<Vec<_>>();
+        let available_width = |index: usize| {
+            if index + 1 == left_lines.len() {
+                screen_width.saturating_sub(indicator_width + MIN_INPUT_WIDTH)
+            } else {
+                screen_width
+            }
+        };
+
+        let left_fits = left_lines
+            .iter()
+            .enumerate()
+            .all(|(index, line)| line_width(line) <= available_width(index));
+        let prompt_str_left = if left_fits {
+            Cow::Borrowed(self.prompt_str_left.as_ref())
+        } else {
+            let fitted = left_lines
+                .iter()
+                .enumerate()
+                .map(|(index, line)| truncate_start(line, available_width(index)))
+                .collect::<Vec<_>>();
+            Cow::Owned(fitted.join("\n"))
+        };
+
+        let right_width = line_width(&self.prompt_str_right);
+        let prompt_str_right =
+            if screen_width < right_prompt_min_width as usize || right_width >= screen_width {
+                Cow::Borrowed("")
+            } else {
+                Cow::Borrowed(self.prompt_str_right.as_ref())
+            };
+
+        PromptLines {
+            banner: Cow::Borrowed(self.banner.as_ref()),
+            prompt_str_left,
+            prompt_str_right,
+            prompt_indicator: Cow::Borrowed(self.prompt_indicator.as_ref()),
+            before_cursor: Cow::Borrowed(self.before_cursor.as_ref()),
+            after_cursor: Cow::Borrowed(self.after_cursor.as_ref()),
+            hint: Cow::Borrowed(self.hint.as_ref()),
+        }
+    }
+
+    /// The left prompt below the banner, if there is one
+    pub(crate) fn banner_and_prompt_left(&self) -> String {
+        if self.banner.is_empty() {
+            self.prompt_str_left.to_string()
+        } else {
+            format!("{}\n{}", self.banner, self.prompt_str_left)
+        }
+    }
+
+    /// Rows the banner takes above the prompt, considering that it may wrap the screen
+    pub(crate) fn banner_rows(&self, screen_width: u16) -> u16 {
+        if self.banner.is_empty() {
+            0
+        } else {
+            estimate_required_lines(&self.banner, screen_width) as u16
+        }
+    }
+
     /// The required lines to paint the buffer are calculated by counting the
     /// number of newlines in all the strings that form the prompt and buffer.
     /// The plus 1 is to indicate that there should be at least one line.
     pub(crate) fn required_lines(&self, terminal_columns: u16, menu: Option<&ReedlineMenu>) -> u16 {
         let input = if menu.is_none() {
-            self.prompt_str_left.to_string()
+            self.banner_and_prompt_left()
                 + &self.prompt_indicator
                 + &self.before_cursor
                 + &self.after_cursor
                 + &self.hint
         } else {
-            self.prompt_str_left.to_string()
+            self.banner_and_prompt_left()
                 + &self.prompt_indicator
                 + &self.before_cursor
                 + &self.after_cursor
@@ -79,16 +157,38 @@ impl<'prompt> PromptLines<'prompt> {
     /// Estimated distance of the cursor to the prompt.
     /// This considers line wrapping
     pub(crate) fn distance_from_prompt(&self, terminal_columns: u16) -> u16 {
-        let input = self.prompt_str_left.to_string() + &self.prompt_indicator + &self.before_cursor;
+        let input = self.banner_and_prompt_left() + &self.prompt_indicator + &self.before_cursor;
         let lines = estimate_required_lines(&input, terminal_columns);
         lines.saturating_sub(1) as u16
     }
 
-    /// Total lines that the prompt uses considering that it may wrap the screen
-    pub(crate) fn prompt_lines_with_wrap(&self, screen_width: u16) -> u16 {
-        let complete_prompt = self.prompt_str_left.to_string() + &self.prompt_indicator;
-        let lines = estimate_required_lines(&complete_prompt, screen_width);
-        lines.saturating_sub(1) as u16
+    /// Cell where the input starts, as its column and the rows below the first
+    /// prompt line, considering that the prompt may wrap the screen
+    pub(crate) fn input_origin(&self, screen_width: u16) -> (u16, u16) {
+        let complete_prompt = self.banner_and_prompt_left() + &self.prompt_indicator;
+        let screen_width = screen_width.max(1) as usize;
+        let (column, rows) = complete_prompt.split('\n').fold((0, 0), |(_, rows), line| {
+            let width = line_width(line);
+            (width % screen_width, rows + 1 + width / screen_width)
+        });
+
+        (column as u16, rows.saturating_sub(1) as u16)
+    }
+
+    /// Estimated width of the line holding the cursor, including the prompt in
+    /// front of the first line
+    pub(crate) fn estimate_cursor_line_width(&self) -> u16 {
+        let cursor_line_start = self.before_cursor.rfind('\n').map_or(0, |index| index + 1);
+        if cursor_line_start == 0 {
+            return self.estimate_first_input_line_width();
+        }
+
+        let input = self.before_cursor.to_string() + &self.after_cursor + &self.hint;
+        let cursor_line = input[cursor_line_start..]
+            .lines()
+            .next()
+            .unwrap_or_default();
+        line_width(cursor_line).min(u16::MAX as usize) as u16
     }
 
     /// Estimated width of the actual input
@@ -1,10 +1,18 @@
 use super::utils::strip_ansi;
+use crate::terminal_extensions::osc8;
 use nu_ansi_term::{Color, Style};
+use std::ops::Range;
 
 /// A representation of a buffer with styling, used for doing syntax highlighting
 pub struct StyledText {
     /// The component, styled parts of the text
     pub buffer: Vec<(Style, String)>,
+    /// Targets the byte ranges of the text link to, painted as clickable
+    /// hyperlinks on terminals supporting them. Added with [`StyledText::push_hyperlink`].
+    ///
+    /// Only the buffer returned by the [`crate::Highlighter`] is painted with its
+    /// links, the menus paint the suggestions without any.
+    pub hyperlinks: Vec<(Range<usize>, String)>,
 }
 
 impl Default for StyledText {
@@ -16,7 +24,10 @@ impl Default for StyledText {
 impl StyledText {
     /// Construct a new `StyledText`
     pub fn new() -> Self {
-        Self { buffer: vec![] }
+        Self {
+            buffer: vec![],
+            hyperlinks: vec![],
+        }
     }
 
     /// Add a new styled string to the buffer
@@ -24,6 +35,77 @@ impl StyledText {
         self.buffer.push(styled_string);
     }
 
+    /// Add a new styled string to the buffer, linking to `target`
+    pub fn push_hyperlink(&mut self, styled_string: (Style, String), target: &str) {
+        let start = self
+            .buffer
+            .iter()
+            .map(|(_, text)| text.len())
+            .sum::<usize>();
+        let end = start + styled_string.1.len();
+        self.hyperlinks.push((start..end, target.to_string()));
+        self.push(styled_string);
+    }
+
+    /// Change the style of the text in the given byte range, e.g. to highlight a selection.
+    /// The parts overlapping the range are split to keep the style of the text around it.
+    pub fn restyle_range(&mut self, range: Range<usize>, restyle: impl Fn(Style) -> Style) {
+        let mut offset = 0;
+        let mut buffer = Vec::with_capacity(self.buffer.len() + 2);
+        for (style, text) in self.buffer.drain(..) {
+            let start = offset;
+            let end = offset + text.len();
+            offset = end;
+
+            let restyle_start = range.start.clamp(start, end) - start;
+            let restyle_end = range.end.clamp(start, end) - start;
+            if restyle_start >= restyle_end {
+                buffer.push((style, text));
+                continue;
+            }
+            let parts = [
+                (style, &text[..restyle_start]),
+                (restyle(style), &text[restyle_start..restyle_end]),
+                (style, &text[restyle_end..]),
+            ];
+            for (style, part) in parts {
+                if !part.is_empty() {
+                    buffer.push((style, part.to_string()));
+                }
+            }
+        }
+        self.buffer = buffer;
+    }
+
+    /// Insert a styled string at the given byte offset, splitting the part at the offset
+    pub fn insert_at(&mut self, offset: usize, styled_string: (Style, String)) {
+        let inserted_len = styled_string.1.len();
+        for (range, _) in self.hyperlinks.iter_mut() {
+            if range.start >= offset {
+                range.start += inserted_len;
+            }
+            if range.end > offset {
+                range.end += inserted_len;
+            }
+        }
+
+        let mut current_idx = 0;
+        for (index, (style, text)) in self.buffer.iter_mut().enumerate() {
+            if offset <= current_idx + text.len() {
+                let split = offset.saturating_sub(current_idx);
+                let right_side = text.split_off(split);
+                let style = *style;
+                self.buffer.insert(index + 1, styled_string);
+                if !right_side.is_empty() {
+                    self.buffer.insert(index + 2, (style, right_side));
+                }
+                return;
+            }
+            current_idx += text.len();
+        }
+        self.buffer.push(styled_string);
+    }
+
     /// Render the styled string. We use the insertion point to render around so that
     /// we can properly write out the styled string to the screen and find the correct
     /// place to put the cursor. This assumes a logic that prints the first part of the
@@ -41,29 +123,41 @@ impl StyledText {
         let mut left_string = String::new();
         let mut right_string = String::new();
         let prompt_style = Style::new().fg(Color::LightBlue);
-        for pair in &self.buffer {
+        for (style, text, target) in self.linked_parts() {
             if current_idx >= insertion_point {
-                right_string.push_str(&render_as_string(pair, &prompt_style, multiline_prompt));
-            } else if pair.1.len() + current_idx <= insertion_point {
-                left_string.push_str(&render_as_string(pair, &prompt_style, multiline_prompt));
-            } else if pair.1.len() + current_idx > insertion_point {
+                right_string.push_str(&render_as_string(
+                    &(style, text.to_string()),
+                    target,
+                    &prompt_style,
+                    multiline_prompt,
+                ));
+            } else if text.len() + current_idx <= insertion_point {
+                left_string.push_str(&render_as_string(
+                    &(style, text.to_string()),
+                    target,
+                    &prompt_style,
+                    multiline_prompt,
+                ));
+            } else if text.len() + current_idx > insertion_point {
                 let offset = insertion_point - current_idx;
 
-                let left_side = pair.1[..offset].to_string();
-                let right_side = pair.1[offset..].to_string();
+                let left_side = text[..offset].to_string();
+                let right_side = text[offset..].to_string();
 
                 left_string.push_str(&render_as_string(
-                    &(pair.0, left_side),
+                    &(style, left_side),
+                    target,
                     &prompt_style,
                     multiline_prompt,
                 ));
                 right_string.push_str(&render_as_string(
-                    &(pair.0, right_side),
+                    &(style, right_side),
+                    target,
                     &prompt_style,
                     multiline_prompt,
                 ));
             }
-            current_idx += pair.1.len();
+            current_idx += text.len();
         }
 
         if use_ansi_coloring {
@@ -75,9 +169,15 @@ impl StyledText {
 
     /// Apply the ANSI style formatting to the full string.
     pub fn render_simple(&self) -> String {
-        self.buffer
-            .iter()
-            .map(|(style, text)| style.paint(text).to_string())
+        self.linked_parts()
+            .into_iter()
+            .map(|(style, text, target)| {
+                let painted = style.paint(text).to_string();
+                match target {
+                    Some(target) => osc8::hyperlink(target, &painted),
+                    None => painted,
+                }
+            })
             .collect()
     }
 
@@ -85,10 +185,46 @@ impl StyledText {
     pub fn raw_string(&self) -> String {
         self.buffer.iter().map(|(_, str)| str.as_str()).collect()
     }
+
+    /// The styled parts split where hyperlinks start or end, with the target
+    /// they link to
+    fn linked_parts(&self) -> Vec<(Style, &str, Option<&str>)> {
+        let mut parts = Vec::with_capacity(self.buffer.len());
+        let mut offset = 0;
+        for (style, text) in &self.buffer {
+            let start = offset;
+            offset += text.len();
+
+            let mut splits = vec![0, text.len()];
+            for (range, _) in &self.hyperlinks {
+                for boundary in [range.start, range.end] {
+                    if boundary > start
+                        && boundary < offset
+                        && text.is_char_boundary(boundary - start)
+                    {
+                        splits.push(boundary - start);
+                    }
+                }
+            }
+            splits.sort_unstable();
+            splits.dedup();
+
+            for split in splits.windows(2) {
+                let target = self
+                    .hyperlinks
+                    .iter()
+                    .find(|(range, _)| range.contains(&(start + split[0])))
+                    .map(|(_, target)| target.as_str());
+                parts.push((*style, &text[split[0]..split[1]], target));
+            }
+        }
+        parts
+    }
 }
 
 fn render_as_string(
     renderable: &(Style, String),
+    target: Option<&str>,
     prompt_style: &Style,
     multiline_prompt: &str,
 ) -> String {
@@ -98,7 +234,79 @@ fn render_as_string(
         if line_number != 0 {
             rendered.push_str(&prompt_style.paint(&formatted_multiline_prompt).to_string());
         }
-        rendered.push_str(&renderable.0.paint(line).to_string());
+        let painted = renderable.0.paint(line).to_string();
+        match target {
+            // Linking each line on its own, the multiline prompt isn't part of the link
+            Some(target) if !line.is_empty() => {
+                rendered.push_str(&osc8::hyperlink(target, &painted));
+            }
+            _ => rendered.push_str(&painted),
+        }
     }
     rendered
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn restyle_range_splits_parts() {
+        let red = Style::new().fg(Color::Red);
+        let mut styled = StyledText::new();
+        styled.push((red, "abc".to_string()));
+        styled.push((Style::new(), "def".to_string()));
+
+        styled.restyle_range(1..4, |style| style.bold());
+
+        assert_eq!(
+            styled.buffer,
+            vec![
+                (red, "a".to_string()),
+                (red.bold(), "bc".to_string()),
+                (Style::new().bold(), "d".to_string()),
+                (Style::new(), "ef".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn hyperlinks_are_rendered_around_their_range() {
+        let mut styled = StyledText::new();
+        styled.push((Style::new(), "open ".to_string()));
+        styled.push_hyperlink((Style::new(), "a.rs".to_string()), "file:///a.rs");
+
+        let (before_cursor, after_cursor) = styled.render_around_insertion_point(7, "", true);
+
+        assert_eq!(
+            before_cursor,
+            "open \x1b]8;;file:///a.rs\x1b\\a.\x1b]8;;\x1b\\"
+        );
+        assert_eq!(after_cursor, "\x1b]8;;file:///a.rs\x1b\\rs\x1b]8;;\x1b\\");
+    }
+
+    #[test]
+    fn insert_at_moves_hyperlinks() {
+        let mut styled = StyledText::new();
+        styled.push((Style::new(), "ab".to_string()));
+        styled.push_hyperlink((Style::new(), "cd".to_string()), "https://example.com");
+        styled.insert_at(1, (Style::new(), " ".to_string()));
+
+        assert_eq!(
+            styled.hyperlinks,
+            vec![(3..5, "https://example.com".to_string())]
+        );
+    }
+
+    #[test]
+    fn insert_at_splits_part() {
+        let mut styled = StyledText::new();
+        styled.push((Style::new(), "abcd".to_string()));
+        styled.insert_at(2, (Style::new().bold(), " ".to_string()));
+        styled.insert_at(5, (Style::new().bold(), "!".to_string()));
+
+        assert_eq!(styled.raw_string(), "ab cd!");
+        assert_eq!(styled.buffer.len(), 4);
+    }
+}
@@ -0,0 +1,122 @@
+use nu_ansi_term::{Color, Style};
+use serde::{Deserialize, Serialize};
+
+/// Colors of the parts of the editor not styled by the highlighter or the prompt
+///
+/// Set with [`crate::Reedline::with_theme`], which also passes it on to the
+/// hinter and the menus through [`crate::Hinter::apply_theme`] and
+/// [`crate::Menu::apply_theme`]. Entries missing from a configuration keep
+/// their default style.
+///
+/// # Example
+/// ```rust
+/// use {
+///     nu_ansi_term::{Color, Style},
+///     reedline::{Reedline, Theme},
+/// };
+///
+/// let theme = Theme {
+///     hint: Style::new().italic().fg(Color::DarkGray),
+///     search_match: Style::new().bold().fg(Color::Yellow),
+///     ..Theme::default()
+/// };
+/// let mut line_editor = Reedline::create().with_theme(theme);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Style of the hint painted after the cursor
+    pub hint: Style,
+    /// Style laid over the selected text and the additional cursors, keeping
+    /// the colors of the highlighter it doesn't set
+    pub selection: Style,
+    /// Style of the bracket matching the one at the cursor, `None` to not
+    /// highlight it, which is the default
+    pub matching_bracket: Option<Style>,
+    /// Style of the buffer holding a multi-line paste
+    pub pasted_text: Style,
+    /// Style of the matches in the result of the history search
+    pub search_match: Style,
+    /// Style of the gutter showing the line numbers
+    pub line_numbers: Style,
+    /// Style of the entries of a menu
+    pub menu_text: Style,
+    /// Style of the selected entry of a menu
+    pub menu_selected_text: Style,
+    /// Style of the descriptions of the menu entries
+    pub menu_description: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            hint: Style::new().fg(Color::LightGray),
+            selection: Style::new().reverse(),
+            matching_bracket: None,
+            pasted_text: Style::new().underline(),
+            search_match: Style::new().fg(Color::Green),
+            line_numbers: Style::new().fg(Color::DarkGray),
+            menu_text: Color::DarkGray.normal(),
+            menu_selected_text: Color::Green.bold().reverse(),
+            menu_description: Color::Yellow.normal(),
+        }
+    }
+}
+
+/// Lays `over` on top of `style`, keeping the colors `over` doesn't set
+pub(crate) fn overlay(style: Style, over: Style) -> Style {
+    Style {
+        foreground: over.foreground.or(style.foreground),
+        background: over.background.or(style.background),
+        is_bold: style.is_bold || over.is_bold,
+        is_dimmed: style.is_dimmed || over.is_dimmed,
+        is_italic: style.is_italic || over.is_italic,
+        is_underline: style.is_underline || over.is_underline,
+        is_blink: style.is_blink || over.is_blink,
+        is_reverse: style.is_reverse || over.is_reverse,
+        is_hidden: style.is_hidden || over.is_hidden,
+        is_strikethrough: style.is_strikethrough || over.is_strikethrough,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn theme_round_trips_through_toml() {
+        let theme = Theme {
+            hint: Style::new().italic().fg(Color::Fixed(245)),
+            matching_bracket: Some(Style::new().on(Color::Fixed(238))),
+            ..Theme::default()
+        };
+
+        let config = crate::utils::to_toml_string(&theme).unwrap();
+
+        assert_eq!(toml::from_str::<Theme>(&config).unwrap(), theme);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn missing_entries_keep_their_default() {
+        let theme: Theme = toml::from_str("").unwrap();
+
+        assert_eq!(theme, Theme::default());
+    }
+
+    #[test]
+    fn overlay_keeps_unset_colors() {
+        let highlighted = Style::new().fg(Color::Green).bold();
+
+        assert_eq!(
+            overlay(highlighted, Style::new().reverse()),
+            highlighted.reverse()
+        );
+        assert_eq!(
+            overlay(highlighted, Style::new().on(Color::Blue)),
+            highlighted.on(Color::Blue)
+        );
+    }
+}
@@ -1,4 +1,7 @@
+use super::frame::carried_attributes;
+use nu_ansi_term::{Color, Style};
 use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
 /// Ensures input uses CRLF line endings.
@@ -71,6 +74,300 @@ pub(crate) fn line_width(line: &str) -> usize {
     strip_ansi(line).width()
 }
 
+/// Byte offset in `buffer` of the character painted at the screen cell
+/// `(column, row)`, for a buffer whose first character is painted at `origin`
+/// and whose continuation lines start after the multiline indicator.
+///
+/// A cell past the end of a painted line maps to the end of that line, cells
+/// above or below the buffer don't map to any offset.
+pub(crate) fn buffer_offset_at(
+    buffer: &str,
+    origin: (u16, u16),
+    indicator_width: u16,
+    screen_width: u16,
+    (column, row): (u16, u16),
+) -> Option<usize> {
+    let (column, row) = (column as usize, row as usize);
+    let (mut current_column, mut current_row) = (origin.0 as usize, origin.1 as usize);
+    let screen_width = screen_width.max(1) as usize;
+    if row < current_row {
+        return None;
+    }
+
+    for (index, grapheme) in buffer.grapheme_indices(true) {
+        if grapheme == "\n" || grapheme == "\r\n" {
+            if current_row == row {
+                return Some(index);
+            }
+            current_row += 1;
+            current_column = indicator_width as usize;
+            continue;
+        }
+
+        let width = grapheme.width();
+        if current_column + width > screen_width {
+            if current_row == row {
+                return Some(index);
+            }
+            current_row += 1;
+            current_column = 0;
+        }
+        if current_row == row && column < current_column + width {
+            return Some(index);
+        }
+        current_column += width;
+    }
+
+    (current_row == row).then_some(buffer.len())
+}
+
+/// Screen cell `(column, row)` of the cursor at `offset` in `buffer`, laid out
+/// like for [`buffer_offset_at`], counting the rows from the first one of the
+/// buffer
+pub(crate) fn buffer_cell_at(
+    buffer: &str,
+    origin_column: u16,
+    indicator_width: u16,
+    screen_width: u16,
+    offset: usize,
+) -> (u16, u16) {
+    let screen_width = screen_width.max(1) as usize;
+    let (mut column, mut row) = (origin_column as usize, 0);
+    for grapheme in buffer[..offset].graphemes(true) {
+        if grapheme == "\n" || grapheme == "\r\n" {
+            row += 1;
+            column = indicator_width as usize;
+            continue;
+        }
+
+        let width = grapheme.width();
+        if column + width > screen_width {
+            row += 1;
+            column = 0;
+        }
+        column += width;
+    }
+
+    // The cursor after a full row is painted at the start of the next one
+    if column >= screen_width {
+        (0, row as u16 + 1)
+    } else {
+        (column as u16, row as u16)
+    }
+}
+
+/// Splits `text` into the rows it takes on a screen `screen_width` columns
+/// wide, breaking at its newlines and wherever a line wraps.
+///
+/// Escape sequences take no space and stay in the row they were found in,
+/// except for the one saving the cursor position, which moves to the next row
+/// if the current one is full, as that is where the cursor is painted.
+pub(crate) fn wrap_rows(text: &str, screen_width: u16) -> Vec<String> {
+    let screen_width = screen_width.max(1) as usize;
+    let mut rows = vec![];
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let mut row = String::new();
+        let mut width = 0;
+        let mut rest = line;
+        while !rest.is_empty() {
+            let length = escape_sequence_length(rest);
+            let (part, part_width) = if length > 0 {
+                (&rest[..length], 0)
+            } else {
+                let grapheme = rest.graphemes(true).next().unwrap_or(rest);
+                (grapheme, grapheme.width())
+            };
+            let full = if part == "\x1b7" {
+                width >= screen_width
+            } else {
+                part_width > 0 && width + part_width > screen_width
+            };
+            if full {
+                rows.push(std::mem::take(&mut row));
+                width = 0;
+            }
+            row.push_str(part);
+            width += part_width;
+            rest = &rest[part.len()..];
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+/// Shortens `text` to `width` columns by replacing its start with an ellipsis,
+/// keeping the escape sequences of the removed part for the styles they set
+pub(crate) fn truncate_start(text: &str, width: usize) -> Cow<'_, str> {
+    let text_width = line_width(text);
+    if text_width <= width {
+        return Cow::Borrowed(text);
+    }
+
+    let mut columns_to_drop = text_width - width.saturating_sub(1);
+    let mut ellipsis = width > 0;
+    let mut truncated = String::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let length = escape_sequence_length(rest);
+        let part = if length > 0 {
+            &rest[..length]
+        } else {
+            rest.graphemes(true).next().unwrap_or(rest)
+        };
+        rest = &rest[part.len()..];
+
+        if length > 0 {
+            truncated.push_str(part);
+        } else if columns_to_drop > 0 {
+            columns_to_drop = columns_to_drop.saturating_sub(part.width());
+        } else {
+            if ellipsis {
+                truncated.push('…');
+                ellipsis = false;
+            }
+            truncated.push_str(part);
+        }
+    }
+    if ellipsis {
+        truncated.push('…');
+    }
+
+    Cow::Owned(truncated)
+}
+
+/// Shortens `text` to `width` columns by replacing its end with an ellipsis,
+/// keeping the escape sequences of the removed part for the styles they reset
+pub(crate) fn truncate_end(text: &str, width: usize) -> Cow<'_, str> {
+    if line_width(text) <= width {
+        return Cow::Borrowed(text);
+    }
+
+    let mut columns_left = width.saturating_sub(1);
+    let mut ellipsis = width > 0;
+    let mut truncated = String::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let length = escape_sequence_length(rest);
+        let part = if length > 0 {
+            &rest[..length]
+        } else {
+            rest.graphemes(true).next().unwrap_or(rest)
+        };
+        rest = &rest[part.len()..];
+
+        if length > 0 {
+            truncated.push_str(part);
+        } else if part.width() <= columns_left {
+            columns_left -= part.width();
+            truncated.push_str(part);
+        } else {
+            if ellipsis {
+                truncated.push('…');
+                ellipsis = false;
+            }
+            columns_left = 0;
+        }
+    }
+
+    Cow::Owned(truncated)
+}
+
+/// Length of the escape sequence `text` starts with, zero if it doesn't start
+/// with one
+fn escape_sequence_length(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    match bytes {
+        // Control sequence, ended by a byte in the range `@` to `~`
+        [b'\x1b', b'[', rest @ ..] => rest
+            .iter()
+            .position(|byte| (0x40..=0x7e).contains(byte))
+            .map_or(bytes.len(), |end| end + 3),
+        // Operating system command, ended by a bell or string terminator
+        [b'\x1b', b']', rest @ ..] => {
+            let end = rest
+                .iter()
+                .enumerate()
+                .find_map(|(index, byte)| match byte {
+                    b'\x07' => Some(index + 3),
+                    b'\x1b' if rest.get(index + 1) == Some(&b'\\') => Some(index + 4),
+                    _ => None,
+                });
+            end.unwrap_or(bytes.len())
+        }
+        [b'\x1b', byte, ..] if byte.is_ascii() => 2,
+        [b'\x1b', ..] => 1,
+        _ => 0,
+    }
+}
+
+/// Lay out the lines of a multi-line hint below the line holding the cursor,
+/// each after the multiline indicator like the lines of the buffer and dimmed
+/// on top of the style of the hint
+pub(crate) fn ghost_lines(
+    hint: &str,
+    multiline_indicator: &str,
+    use_ansi_coloring: bool,
+) -> String {
+    let mut lines = hint.split('\n');
+    let mut painted = lines.next().unwrap_or_default().to_string();
+    if !use_ansi_coloring {
+        for line in lines {
+            painted = painted + "\n" + multiline_indicator + line;
+        }
+        return painted;
+    }
+
+    let indicator = Style::new()
+        .fg(Color::LightBlue)
+        .dimmed()
+        .paint(multiline_indicator)
+        .to_string();
+    let dimmed = Style::new().dimmed().prefix().to_string();
+    // The style of the hint carried over to the next line
+    let mut attributes = carried_attributes(&painted);
+    for line in lines {
+        painted = painted + "\x1b[0m\n" + &indicator + &attributes + &dimmed + line;
+        attributes = carried_attributes(&(attributes + line));
+    }
+    painted
+}
+
+/// Lay out the keys continuing a key sequence and their descriptions in
+/// columns fitting the screen width, one line per row
+pub(crate) fn keybinding_hints_string(
+    continuations: &[(String, String)],
+    screen_width: u16,
+    use_ansi_coloring: bool,
+) -> String {
+    let cell_width = continuations
+        .iter()
+        .map(|(keys, description)| keys.width() + description.width() + 4)
+        .max()
+        .unwrap_or_default();
+    let columns = (screen_width as usize / cell_width.max(1)).max(1);
+
+    continuations
+        .chunks(columns)
+        .map(|row| {
+            let cells = row
+                .iter()
+                .map(|(keys, description)| {
+                    let padding = cell_width - keys.width() - description.width() - 2;
+                    let keys = if use_ansi_coloring {
+                        Style::new().bold().paint(keys).to_string()
+                    } else {
+                        keys.clone()
+                    };
+                    format!("{}  {}{}", keys, description, " ".repeat(padding))
+                })
+                .collect::<String>();
+            cells.trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -95,4 +392,109 @@ mod test {
             "Unnecessary allocation"
         )
     }
+
+    #[rstest]
+    #[case((0, 2), Some(0))]
+    #[case((4, 2), Some(2))]
+    #[case((20, 2), Some(5))]
+    #[case((0, 3), Some(6))]
+    #[case((6, 3), Some(8))]
+    #[case((20, 3), Some(15))]
+    #[case((1, 4), Some(16))]
+    #[case((9, 4), Some(17))]
+    #[case((0, 1), None)]
+    #[case((0, 5), None)]
+    fn test_buffer_offset_at(#[case] cell: (u16, u16), #[case] expected: Option<usize>) {
+        // "> hello" on row 2, "::: second li" on row 3 wrapping "ne" onto row 4
+        let buffer = "hello\nsecond line";
+
+        assert_eq!(buffer_offset_at(buffer, (2, 2), 4, 13, cell), expected);
+    }
+
+    #[rstest]
+    #[case(0, (2, 0))]
+    #[case(3, (5, 0))]
+    #[case(6, (4, 1))]
+    #[case(14, (12, 1))]
+    #[case(15, (0, 2))]
+    #[case(16, (1, 2))]
+    #[case(17, (2, 2))]
+    fn test_buffer_cell_at(#[case] offset: usize, #[case] expected: (u16, u16)) {
+        // "> hello" on the first row, "::: second li" on the next wrapping "ne"
+        let buffer = "hello\nsecond line";
+
+        assert_eq!(buffer_cell_at(buffer, 2, 4, 13, offset), expected);
+    }
+
+    #[rstest]
+    #[case("~/projects", 10, "~/projects")]
+    #[case("~/projects/reedline", 10, "…/reedline")]
+    #[case("\x1b[32m~/projects\x1b[0m", 6, "\x1b[32m…jects\x1b[0m")]
+    #[case("~/プロジェクト", 7, "…ェクト")]
+    #[case("~/プロジェクト", 6, "…クト")]
+    #[case("~/projects", 1, "…")]
+    #[case("\x1b[32m~/projects", 0, "\x1b[32m")]
+    fn test_truncate_start(#[case] text: &str, #[case] width: usize, #[case] expected: &str) {
+        assert_eq!(truncate_start(text, width), expected);
+    }
+
+    #[rstest]
+    #[case("--verbose", 9, "--verbose")]
+    #[case("--verbose", 6, "--ver…")]
+    #[case("\x1b[32m--verbose\x1b[0m", 4, "\x1b[32m--v…\x1b[0m")]
+    #[case("プロジェクト", 6, "プロ…")]
+    #[case("--verbose", 1, "…")]
+    #[case("--verbose\x1b[0m", 0, "\x1b[0m")]
+    fn test_truncate_end(#[case] text: &str, #[case] width: usize, #[case] expected: &str) {
+        assert_eq!(truncate_end(text, width), expected);
+    }
+
+    #[rstest]
+    #[case("", vec![""])]
+    #[case("short\r\nlines", vec!["short", "lines"])]
+    #[case("abcdefgh", vec!["abcde", "fgh"])]
+    #[case("abcde\n", vec!["abcde", ""])]
+    #[case("\x1b[31mabcde\x1b[0mfg", vec!["\x1b[31mabcde\x1b[0m", "fg"])]
+    #[case("abcde\x1b7fg", vec!["abcde", "\x1b7fg"])]
+    #[case("abcd😇", vec!["abcd", "😇"])]
+    #[case("\x1b]8;;a\x1b\\abcdef\x1b]8;;\x07", vec!["\x1b]8;;a\x1b\\abcde", "f\x1b]8;;\x07"])]
+    fn test_wrap_rows(#[case] text: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(wrap_rows(text, 5), expected);
+    }
+
+    #[test]
+    fn ghost_lines_follow_the_multiline_indicator() {
+        assert_eq!(ghost_lines("tus", "::: ", false), "tus");
+        assert_eq!(
+            ghost_lines(" -m 'fix\n\nmore'", "::: ", false),
+            " -m 'fix\n::: \n::: more'"
+        );
+
+        let style = Style::new().italic();
+        let indicator = Style::new().fg(Color::LightBlue).dimmed().paint("::: ");
+        assert_eq!(
+            ghost_lines(&style.paint("fix\nmore").to_string(), "::: ", true),
+            format!(
+                "{}fix\x1b[0m\n{}{}{}more{}",
+                style.prefix(),
+                indicator,
+                style.prefix(),
+                Style::new().dimmed().prefix(),
+                style.suffix()
+            )
+        );
+    }
+
+    #[test]
+    fn keybinding_hints_fill_the_columns() {
+        let continuations = [("w", "next word"), ("b", "back"), ("$", "line end")]
+            .iter()
+            .map(|(keys, description)| (keys.to_string(), description.to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            keybinding_hints_string(&continuations, 30, false),
+            "w  next word  b  back\n$  line end"
+        );
+    }
 }
@@ -65,6 +65,9 @@ pub enum PromptViMode {
 
     /// Insertion mode
     Insert,
+
+    /// Visual block selection mode
+    VisualBlock,
 }
 
 impl Default for PromptViMode {
@@ -113,4 +116,28 @@ pub trait Prompt: Send {
     fn get_prompt_right_color(&self) -> Color {
         DEFAULT_PROMPT_RIGHT_COLOR
     }
+    /// Block painted above the prompt as part of it, e.g. a header, a warning
+    /// or a message of the day. Its lines wrap at the width of the screen
+    /// instead of being shortened like the prompt
+    fn render_prompt_banner(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+    /// Whether the right prompt follows the cursor to the line of the buffer
+    /// it is on, instead of staying on the first line. Buffers taller than
+    /// the screen keep it on the first line
+    fn right_prompt_on_cursor_line(&self) -> bool {
+        false
+    }
+    /// Minimal prompt replacing the whole prompt, including its indicator and
+    /// right prompt, once the line was submitted. Keeps the full prompt in the
+    /// scrollback if `None`
+    fn render_transient_prompt(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+    /// Whether parts of the prompt, like an [`crate::AsyncSegment`], are still
+    /// computed in the background. While they are, the prompt is checked for
+    /// changes between keystrokes and repainted as they resolve
+    fn has_pending_segments(&self) -> bool {
+        false
+    }
 }
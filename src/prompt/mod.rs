@@ -1,8 +1,12 @@
 mod base;
 mod default;
+mod segment;
+mod transient;
 
 pub use base::{
     Prompt, PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus, PromptViMode,
 };
 
 pub use default::DefaultPrompt;
+pub use segment::AsyncSegment;
+pub(crate) use transient::TransientPrompt;
@@ -0,0 +1,88 @@
+use std::{
+    borrow::Cow,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// Part of a prompt that is slow to compute, like the git status or the
+/// kubernetes context, computed on a background thread.
+///
+/// The segment renders its placeholder until the content arrives, so the prompt
+/// shows up right away. Report pending segments from
+/// [`crate::Prompt::has_pending_segments()`] to have the prompt repainted once
+/// they resolve.
+///
+/// # Example
+/// ```rust
+/// use reedline::AsyncSegment;
+///
+/// let branch = AsyncSegment::spawn("…", || "main".to_string());
+/// // Renders "…" until the closure returned
+/// let rendered = branch.render();
+/// ```
+#[derive(Clone)]
+pub struct AsyncSegment {
+    placeholder: String,
+    content: Arc<Mutex<Option<String>>>,
+}
+
+impl AsyncSegment {
+    /// Computes the content of the segment with `compute` on a new thread
+    pub fn spawn<F>(placeholder: impl Into<String>, compute: F) -> Self
+    where
+        F: FnOnce() -> String + Send + 'static,
+    {
+        let content = Arc::new(Mutex::new(None));
+        let resolved = Arc::clone(&content);
+        thread::spawn(move || {
+            let value = compute();
+            if let Ok(mut resolved) = resolved.lock() {
+                *resolved = Some(value);
+            }
+        });
+
+        Self {
+            placeholder: placeholder.into(),
+            content,
+        }
+    }
+
+    /// The content of the segment, or its placeholder while it is computed
+    pub fn render(&self) -> Cow<'_, str> {
+        match self.content.lock() {
+            Ok(content) => match content.as_ref() {
+                Some(content) => Cow::Owned(content.clone()),
+                None => Cow::Borrowed(&self.placeholder),
+            },
+            Err(_) => Cow::Borrowed(&self.placeholder),
+        }
+    }
+
+    /// Whether the content is still computed
+    pub fn is_pending(&self) -> bool {
+        matches!(self.content.lock(), Ok(content) if content.is_none())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::mpsc;
+
+    #[test]
+    fn placeholder_is_rendered_until_resolved() {
+        let (sender, receiver) = mpsc::channel::<String>();
+        let segment = AsyncSegment::spawn("…", move || receiver.recv().unwrap_or_default());
+
+        assert!(segment.is_pending());
+        assert_eq!(segment.render(), "…");
+
+        sender.send("main".to_string()).unwrap();
+        while segment.is_pending() {
+            thread::yield_now();
+        }
+
+        assert_eq!(segment.render(), "main");
+    }
+}
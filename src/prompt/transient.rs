@@ -0,0 +1,53 @@
+use {
+    super::{Prompt, PromptEditMode, PromptHistorySearch},
+    crossterm::style::Color,
+    std::borrow::Cow,
+};
+
+/// The transient prompt of a [`Prompt`], painting the submitted line
+pub(crate) struct TransientPrompt {
+    prompt: String,
+    multiline_indicator: String,
+    prompt_color: Color,
+}
+
+impl TransientPrompt {
+    /// The transient version of `prompt`, if it has one
+    pub(crate) fn of(prompt: &dyn Prompt) -> Option<Self> {
+        let transient = prompt.render_transient_prompt()?;
+        Some(Self {
+            prompt: transient.into_owned(),
+            multiline_indicator: prompt.render_prompt_multiline_indicator().into_owned(),
+            prompt_color: prompt.get_prompt_color(),
+        })
+    }
+}
+
+impl Prompt for TransientPrompt {
+    fn render_prompt_left(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.prompt)
+    }
+
+    fn render_prompt_right(&self) -> Cow<'_, str> {
+        Cow::Borrowed("")
+    }
+
+    fn render_prompt_indicator(&self, _prompt_mode: PromptEditMode) -> Cow<'_, str> {
+        Cow::Borrowed("")
+    }
+
+    fn render_prompt_multiline_indicator(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.multiline_indicator)
+    }
+
+    fn render_prompt_history_search_indicator(
+        &self,
+        _history_search: PromptHistorySearch,
+    ) -> Cow<'_, str> {
+        Cow::Borrowed("")
+    }
+
+    fn get_prompt_color(&self) -> Color {
+        self.prompt_color
+    }
+}
@@ -10,17 +10,53 @@ pub(crate) enum ReedlineErrorVariants {
     HistoryDatabaseError(String),
     #[error("error within history: {0}")]
     OtherHistoryError(&'static str),
+    #[error("error within history backend: {0}")]
+    HistoryBackendError(String),
+    #[error("error importing history: {0}")]
+    HistoryImportError(String),
+    #[error("error exporting history: {0}")]
+    HistoryExportError(String),
     #[error("the history {history} does not support feature {feature}")]
     HistoryFeatureUnsupported {
         history: &'static str,
         feature: &'static str,
     },
+    #[cfg(feature = "toml")]
+    #[error("error within keybinding configuration: {0}")]
+    KeybindingsConfigError(String),
+    #[cfg(feature = "tree-sitter")]
+    #[error("error within highlighter configuration: {0}")]
+    HighlighterConfigError(String),
 }
 
 /// separate struct to not expose anything to the public (for now)
 #[derive(Debug)]
 pub struct ReedlineError(pub(crate) ReedlineErrorVariants);
 
+impl ReedlineError {
+    /// Error of a custom [`crate::History`] backend, like a lost connection to
+    /// the service storing the entries
+    pub fn history_backend(message: impl Display) -> Self {
+        Self(ReedlineErrorVariants::HistoryBackendError(
+            message.to_string(),
+        ))
+    }
+
+    /// Error of a [`crate::History`] that can't honor a part of a request, like
+    /// filtering by time or deleting entries
+    pub fn history_feature_unsupported(history: &'static str, feature: &'static str) -> Self {
+        Self(ReedlineErrorVariants::HistoryFeatureUnsupported { history, feature })
+    }
+
+    /// Whether the error only reports an unsupported feature of the history
+    pub fn is_unsupported_feature(&self) -> bool {
+        matches!(
+            self.0,
+            ReedlineErrorVariants::HistoryFeatureUnsupported { .. }
+        )
+    }
+}
+
 impl Display for ReedlineError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.0.fmt(f)
@@ -28,5 +64,5 @@ impl Display for ReedlineError {
 }
 impl std::error::Error for ReedlineError {}
 
-// for now don't expose the above error type to the public
+/// Result of the operations of a [`crate::History`]
 pub type Result<T> = std::result::Result<T, ReedlineError>;
@@ -0,0 +1,8 @@
+pub(crate) mod osc133;
+#[cfg(feature = "osc52_clipboard")]
+mod osc52;
+pub(crate) mod osc8;
+pub(crate) mod synchronized_output;
+
+#[cfg(feature = "osc52_clipboard")]
+pub use osc52::Osc52Clipboard;
@@ -0,0 +1,32 @@
+//! Semantic prompt markers (OSC 133), letting terminals tell the prompt, the
+//! typed command and its output apart to jump between prompts or show the
+//! exit status of each command
+
+/// Start of the prompt
+pub(crate) const PROMPT_START: &str = "\x1b]133;A\x07";
+
+/// End of the prompt, the typed command follows
+pub(crate) const COMMAND_START: &str = "\x1b]133;B\x07";
+
+/// The command was submitted, its output follows
+pub(crate) const OUTPUT_START: &str = "\x1b]133;C\x07";
+
+/// End of the output of the last command, with its exit code if known
+pub(crate) fn command_finished(exit_code: Option<i32>) -> String {
+    match exit_code {
+        Some(exit_code) => format!("\x1b]133;D;{}\x07", exit_code),
+        None => "\x1b]133;D\x07".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn command_finished_reports_exit_code() {
+        assert_eq!(command_finished(Some(127)), "\x1b]133;D;127\x07");
+        assert_eq!(command_finished(None), "\x1b]133;D\x07");
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{Clipboard, ClipboardMode};
+
+/// Clipboard that sends everything cut or copied to the clipboard of the
+/// terminal using the OSC 52 escape sequence
+///
+/// This reaches the local clipboard of the user even when running over SSH,
+/// as long as the terminal supports OSC 52. Terminals usually don't allow
+/// reading their clipboard, so pasting inside the line editor uses the
+/// content last set through this clipboard.
+///
+/// The escape sequence is written by the line editor together with its next
+/// repaint, through the same output.
+///
+/// # Example
+/// ```rust
+/// use reedline::{Osc52Clipboard, Reedline};
+///
+/// let mut line_editor = Reedline::create().with_clipboard(Box::new(Osc52Clipboard::new()));
+/// ```
+pub struct Osc52Clipboard {
+    content: String,
+    mode: ClipboardMode,
+    sequence: Option<String>,
+}
+
+impl Default for Osc52Clipboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Osc52Clipboard {
+    /// Create an empty clipboard
+    pub fn new() -> Self {
+        Osc52Clipboard {
+            content: String::new(),
+            mode: ClipboardMode::Normal,
+            sequence: None,
+        }
+    }
+}
+
+impl Clipboard for Osc52Clipboard {
+    fn set(&mut self, content: &str, mode: ClipboardMode) {
+        // The terminal may ignore the sequence, the content stays available locally
+        self.sequence = Some(format!("\x1b]52;c;{}\x07", base64::encode(content)));
+        self.content = content.to_owned();
+        self.mode = mode;
+    }
+
+    fn get(&mut self) -> (String, ClipboardMode) {
+        (self.content.clone(), self.mode)
+    }
+
+    fn take_terminal_sequence(&mut self) -> Option<String> {
+        self.sequence.take()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn set_emits_escape_sequence() {
+        let mut clipboard = Osc52Clipboard::new();
+        clipboard.set("äöü", ClipboardMode::Normal);
+
+        assert_eq!(
+            clipboard.take_terminal_sequence().as_deref(),
+            Some("\x1b]52;c;w6TDtsO8\x07")
+        );
+        assert_eq!(clipboard.take_terminal_sequence(), None);
+        assert_eq!(clipboard.get().0, "äöü");
+    }
+}
@@ -0,0 +1,73 @@
+//! Clickable hyperlinks (OSC 8) in the painted text
+
+/// Wraps `text` in the escape sequences linking it to `target`
+pub(crate) fn hyperlink(target: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", target, text)
+}
+
+/// Guesses from the environment if the terminal renders hyperlinks, as
+/// terminals not knowing OSC 8 may print the escape sequences as text
+///
+/// `FORCE_HYPERLINK` overrides the guess, `0` turning the links off
+pub(crate) fn supports_hyperlinks() -> bool {
+    supports_hyperlinks_in(|name| std::env::var(name).ok())
+}
+
+fn supports_hyperlinks_in(var: impl Fn(&str) -> Option<String>) -> bool {
+    if let Some(force) = var("FORCE_HYPERLINK") {
+        return force != "0";
+    }
+
+    if var("WT_SESSION").is_some() || var("DOMTERM").is_some() || var("KONSOLE_VERSION").is_some() {
+        return true;
+    }
+
+    // VTE based terminals like GNOME Terminal support hyperlinks since 0.50
+    if let Some(vte_version) = var("VTE_VERSION") {
+        if matches!(vte_version.parse::<u32>(), Ok(version) if version >= 5000) {
+            return true;
+        }
+    }
+
+    matches!(
+        var("TERM_PROGRAM").as_deref(),
+        Some("iTerm.app" | "WezTerm" | "vscode" | "Hyper" | "ghostty")
+    ) || matches!(
+        var("TERM").as_deref(),
+        Some("xterm-kitty" | "alacritty" | "foot" | "xterm-ghostty")
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[test]
+    fn hyperlink_wraps_text() {
+        assert_eq!(
+            hyperlink("file:///tmp", "tmp"),
+            "\x1b]8;;file:///tmp\x1b\\tmp\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[rstest]
+    #[case(&[], false)]
+    #[case(&[("TERM", "xterm-256color")], false)]
+    #[case(&[("TERM", "xterm-kitty")], true)]
+    #[case(&[("TERM_PROGRAM", "WezTerm")], true)]
+    #[case(&[("VTE_VERSION", "4803")], false)]
+    #[case(&[("VTE_VERSION", "6800")], true)]
+    #[case(&[("TERM_PROGRAM", "WezTerm"), ("FORCE_HYPERLINK", "0")], false)]
+    #[case(&[("TERM", "dumb"), ("FORCE_HYPERLINK", "1")], true)]
+    fn detects_support_from_environment(#[case] env: &[(&str, &str)], #[case] expected: bool) {
+        let var = |name: &str| {
+            env.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        };
+
+        assert_eq!(supports_hyperlinks_in(var), expected);
+    }
+}
@@ -0,0 +1,52 @@
+//! Synchronized output (DEC private mode 2026), letting the terminal show a
+//! repaint at once instead of drawing its parts as they arrive
+//!
+//! Terminals not knowing the mode ignore it, like any unknown private mode.
+
+use crossterm::Command;
+use std::fmt;
+
+/// Holds back the drawing of the following output until
+/// [`EndSynchronizedUpdate`]
+pub(crate) struct BeginSynchronizedUpdate;
+
+/// Draws the output held back since [`BeginSynchronizedUpdate`]
+pub(crate) struct EndSynchronizedUpdate;
+
+impl Command for BeginSynchronizedUpdate {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str("\x1b[?2026h")
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> crossterm::Result<()> {
+        // Legacy consoles draw every call right away
+        Ok(())
+    }
+}
+
+impl Command for EndSynchronizedUpdate {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str("\x1b[?2026l")
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> crossterm::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn updates_set_and_reset_mode_2026() {
+        let mut output = String::new();
+        BeginSynchronizedUpdate.write_ansi(&mut output).unwrap();
+        EndSynchronizedUpdate.write_ansi(&mut output).unwrap();
+
+        assert_eq!(output, "\x1b[?2026h\x1b[?2026l");
+    }
+}
@@ -6,3 +6,107 @@ pub use query::{
     get_reedline_keybinding_modifiers, get_reedline_keycodes, get_reedline_prompt_edit_modes,
     get_reedline_reedline_events,
 };
+
+/// Write a value as TOML with the nested values as inline tables
+///
+/// Goes through JSON as the `toml` serializer rejects enum variants with data,
+/// which JSON writes as a table with the variant name as the only key. Unset
+/// optional values are left out as TOML has no null.
+#[cfg(feature = "toml")]
+pub(crate) fn to_toml_string<T: serde::Serialize>(value: &T) -> Result<String, String> {
+    use toml::Value;
+
+    fn from_json(value: serde_json::Value) -> Option<Value> {
+        match value {
+            serde_json::Value::Null => None,
+            serde_json::Value::Bool(b) => Some(Value::Boolean(b)),
+            serde_json::Value::Number(n) => n
+                .as_i64()
+                .map(Value::Integer)
+                .or_else(|| n.as_f64().map(Value::Float)),
+            serde_json::Value::String(s) => Some(Value::String(s)),
+            serde_json::Value::Array(items) => Some(Value::Array(
+                items.into_iter().filter_map(from_json).collect(),
+            )),
+            serde_json::Value::Object(entries) => Some(Value::Table(
+                entries
+                    .into_iter()
+                    .filter_map(|(key, value)| from_json(value).map(|value| (key, value)))
+                    .collect(),
+            )),
+        }
+    }
+
+    fn format_key(key: &str) -> String {
+        if !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            key.to_string()
+        } else {
+            Value::String(key.to_string()).to_string()
+        }
+    }
+
+    fn format_inline(value: &Value) -> String {
+        match value {
+            Value::Array(items) => format!(
+                "[{}]",
+                items
+                    .iter()
+                    .map(format_inline)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Value::Table(entries) if entries.is_empty() => "{}".to_string(),
+            Value::Table(entries) => format!(
+                "{{ {} }}",
+                entries
+                    .iter()
+                    .map(|(key, value)| format!("{} = {}", format_key(key), format_inline(value)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            value => value.to_string(),
+        }
+    }
+
+    fn write_entries(document: &mut String, entries: &toml::value::Table) {
+        for (key, value) in entries {
+            document.push_str(&format!("{} = {}\n", format_key(key), format_inline(value)));
+        }
+    }
+
+    let json = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    let entries = match from_json(json) {
+        Some(Value::Table(entries)) => entries,
+        _ => return Err("only tables can be written as a TOML document".to_string()),
+    };
+
+    // Plain values come first as they would otherwise belong to the last section
+    let (sections, values): (toml::value::Table, toml::value::Table) =
+        entries.into_iter().partition(|(_, value)| match value {
+            Value::Table(_) => true,
+            Value::Array(items) => !items.is_empty() && items.iter().all(Value::is_table),
+            _ => false,
+        });
+    let mut document = String::new();
+    write_entries(&mut document, &values);
+    for (key, value) in &sections {
+        match value {
+            Value::Array(items) => {
+                for item in items.iter().filter_map(Value::as_table) {
+                    document.push_str(&format!("\n[[{}]]\n", format_key(key)));
+                    write_entries(&mut document, item);
+                }
+            }
+            Value::Table(entries) => {
+                document.push_str(&format!("\n[{}]\n", format_key(key)));
+                write_entries(&mut document, entries);
+            }
+            _ => {}
+        }
+    }
+    Ok(document)
+}
@@ -6,6 +6,13 @@ pub use default::DefaultValidator;
 pub trait Validator: Send {
     /// The action that will handle the current buffer as a line and return the corresponding validation
     fn validate(&self, line: &str) -> ValidationResult;
+
+    /// A message shown in the status line below the input when `line` was found
+    /// incomplete, e.g. to point out an unclosed quote. It stays until the next
+    /// keystroke
+    fn status(&self, _line: &str) -> Option<String> {
+        None
+    }
 }
 
 #[derive(Clone, Copy)]